@@ -2,7 +2,7 @@
 # Exceptions. See /LICENSE for license information.
 # SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
-"""`cc_bindings_from_rust` rule.
+"""`cc_bindings_from_rust` rule and `cc_bindings_from_rust_aspect` aspect.
 
 Disclaimer: This project is experimental, under heavy development, and should
 not be used yet.
@@ -35,35 +35,62 @@ load(
     "//rs_bindings_from_cc/bazel_support:compile_rust.bzl",
     "compile_rust",
 )
+load(
+    "//cc_bindings_from_rs/bazel_support:providers.bzl",
+    "CcBindingsFromRustInfo",
+)
+load(
+    "//rs_bindings_from_cc/bazel_support:crubit_feature_hint.bzl",
+    "find_crubit_features",
+)
 load("@bazel_tools//tools/cpp:toolchain_utils.bzl", "find_cpp_toolchain", "use_cpp_toolchain")
 
-def _generate_bindings(ctx, basename, inputs, rustc_args):
+def _generate_bindings(ctx, basename, inputs, rustc_args, deps_bindings_infos, crubit_features):
     """Invokes the `cc_bindings_from_rs` tool to generate C++ bindings for a Rust crate.
 
     Args:
-      ctx: The rule context.
+      ctx: The aspect context.
       basename: The basename for the generated files
       rustc_args: `rustc` flags to pass to `cc_bindings_from_rs`
       inputs: `cc_bindings_from_rs` inputs specific to the target `crate`
+      deps_bindings_infos: CcBindingsFromRustInfo providers of the direct
+          dependencies of the target `crate`
+      crubit_features: Crubit features enabled (via `aspect_hints`) for the
+          target `crate`.  If empty, then the tool's defaults are used.
 
     Returns:
-      A pair of files:
+      A tuple of files:
       - h_out_file (named "<basename>_cc_api.h")
       - rs_out_file (named "<basename>_cc_api_impl.rs")
+      - cc_out_file (named "<basename>_cc_api.cc")
     """
     h_out_file = ctx.actions.declare_file(basename + "_cc_api.h")
     rs_out_file = ctx.actions.declare_file(basename + "_cc_api_impl.rs")
+    cc_out_file = ctx.actions.declare_file(basename + "_cc_api.cc")
 
     crubit_args = ctx.actions.args()
     crubit_args.add("--h-out", h_out_file)
     crubit_args.add("--rs-out", rs_out_file)
+    crubit_args.add("--cc-out", cc_out_file)
     crubit_args.add("--crubit-support-path", "support")
     crubit_args.add("--clang-format-exe-path", ctx.file._clang_format)
     crubit_args.add("--rustfmt-exe-path", ctx.file._rustfmt)
     crubit_args.add("--rustfmt-config-path", ctx.file._rustfmt_cfg)
 
+    # The generated `rs_out_file` is always linked together with the generated header.
+    crubit_args.add("--abi-stamp")
+    for dep_bindings_info in deps_bindings_infos:
+        crubit_args.add(
+            "--crate-header={}={}".format(
+                dep_bindings_info.crate_name,
+                dep_bindings_info.h_out_file.short_path,
+            ),
+        )
+    if crubit_features:
+        crubit_args.add("--crubit-features", ",".join(crubit_features))
+
     ctx.actions.run(
-        outputs = [h_out_file, rs_out_file],
+        outputs = [h_out_file, rs_out_file, cc_out_file],
         inputs = depset(
             [ctx.file._clang_format, ctx.file._rustfmt, ctx.file._rustfmt_cfg],
             transitive = [inputs],
@@ -75,28 +102,31 @@ def _generate_bindings(ctx, basename, inputs, rustc_args):
         arguments = [crubit_args, "--", rustc_args, "-Cpanic=abort"],
     )
 
-    return (h_out_file, rs_out_file)
+    return (h_out_file, rs_out_file, cc_out_file)
 
-def _make_cc_info_for_h_out_file(ctx, h_out_file, linking_contexts):
+def _make_cc_info_for_h_out_file(ctx, h_out_file, cc_out_file, linking_contexts, deps_cc_infos):
     """Creates and returns CcInfo for the generated ..._cc_api.h header file.
 
     Args:
-      ctx: The rule context.
+      ctx: The aspect context.
       h_out_file: The generated "..._cc_api.h" header file
+      cc_out_file: The generated "..._cc_api.cc" source file
       linking_contexts: Linking contexts - should include both:
           1) the target `crate` and
           2) the compiled Rust glue crate (`..._cc_api_impl.rs` file).
+      deps_cc_infos: CcInfo providers of the C++ bindings of the direct
+          dependencies of the target `crate`.
 
     Returns:
       A CcInfo provider.
     """
-    cc_deps = ctx.attr._cc_deps_for_bindings
+    cc_deps = [cc_dep[CcInfo] for cc_dep in ctx.attr._cc_deps_for_bindings] + deps_cc_infos
     cc_deps_compilation_contexts = [
-        cc_dep[CcInfo].compilation_context
+        cc_dep.compilation_context
         for cc_dep in cc_deps
     ]
     cc_deps_linking_contexts = [
-        cc_dep[CcInfo].linking_context
+        cc_dep.linking_context
         for cc_dep in cc_deps
     ]
     cc_toolchain = find_cpp_toolchain(ctx)
@@ -105,15 +135,16 @@ def _make_cc_info_for_h_out_file(ctx, h_out_file, linking_contexts):
         cc_toolchain = cc_toolchain,
     )
     (compilation_context, compilation_outputs) = cc_common.compile(
-        name = ctx.label.name,
+        name = ctx.label.name + "_cc_api",
         actions = ctx.actions,
         feature_configuration = feature_configuration,
         cc_toolchain = cc_toolchain,
+        srcs = [cc_out_file],
         public_hdrs = [h_out_file],
         compilation_contexts = cc_deps_compilation_contexts,
     )
     (linking_context, _) = cc_common.create_linking_context_from_compilation_outputs(
-        name = ctx.label.name,
+        name = ctx.label.name + "_cc_api",
         actions = ctx.actions,
         feature_configuration = feature_configuration,
         cc_toolchain = cc_toolchain,
@@ -125,13 +156,13 @@ def _make_cc_info_for_h_out_file(ctx, h_out_file, linking_contexts):
         linking_context = linking_context,
     )
 
-def _compile_rs_out_file(ctx, rs_out_file, target_crate):
+def _compile_rs_out_file(ctx, rs_out_file, target):
     """Compiles the generated "..._cc_api_impl.rs" file.
 
     Args:
-      ctx: The rule context.
+      ctx: The aspect context.
       rs_out_file: The generated "..._cc_api_impl.rs" file
-      target_crate: The value of `ctx.attr.crate`
+      target: The target `crate` (the target that the aspect is applied to)
 
     Returns:
       LinkingContext for linking in the generated "..._cc_api_impl.rs".
@@ -143,17 +174,44 @@ def _compile_rs_out_file(ctx, rs_out_file, target_crate):
             cc_info = dep[CcInfo],
             build_info = None,
         )
-        for dep in ctx.attr._rs_deps_for_bindings + [target_crate]
+        for dep in ctx.attr._rs_deps_for_bindings + [target]
     ]
-    dep_variant_info = compile_rust(ctx, ctx.attr, rs_out_file, [], deps)
+    dep_variant_info = compile_rust(
+        ctx,
+        ctx.rule.attr,
+        rs_out_file,
+        [],
+        deps,
+        # The glue crate can't use the name of the target `crate` (which is the
+        # default), because the glue crate depends on the target `crate`.
+        crate_name = target[CrateInfo].name + "_cc_api_impl",
+    )
     return dep_variant_info.cc_info.linking_context
 
-def _cc_bindings_from_rust_rule_impl(ctx):
-    basename = ctx.attr.crate.label.name
+def _cc_bindings_from_rust_aspect_impl(target, ctx):
+    # This is not a Rust rule.
+    if CrateInfo not in target:
+        return []
+
+    crate_info = target[CrateInfo]
+
+    # Procedural macros don't have a C++ API.
+    if crate_info.type == "proc-macro":
+        return []
+
+    basename = ctx.label.name
+
+    # Bindings of the dependencies are generated first (the aspect propagates
+    # along `deps`), so that the generated header of the target `crate` can
+    # include the headers of the dependencies.
+    deps_bindings_infos = [
+        dep[CcBindingsFromRustInfo]
+        for dep in getattr(ctx.rule.attr, "deps", [])
+        if CcBindingsFromRustInfo in dep
+    ]
 
     toolchain = find_toolchain(ctx)
-    crate_info = ctx.attr.crate[CrateInfo]
-    dep_info = ctx.attr.crate[DepInfo]
+    dep_info = target[DepInfo]
     cc_toolchain = find_cpp_toolchain(ctx)
     feature_configuration = cc_common.configure_features(
         ctx = ctx,
@@ -162,8 +220,8 @@ def _cc_bindings_from_rust_rule_impl(ctx):
 
     compile_inputs, out_dir, build_env_files, build_flags_files, linkstamp_outs, ambiguous_libs = collect_inputs(
         ctx = ctx,
-        file = ctx.file,
-        files = ctx.files,
+        file = ctx.rule.file,
+        files = ctx.rule.files,
         linkstamps = depset([]),
         toolchain = toolchain,
         cc_toolchain = cc_toolchain,
@@ -177,7 +235,7 @@ def _cc_bindings_from_rust_rule_impl(ctx):
 
     args, _ = construct_arguments(
         ctx = ctx,
-        attr = ctx.attr,
+        attr = ctx.rule.attr,
         file = ctx.file,
         toolchain = toolchain,
         tool_path = toolchain.rustc.path,
@@ -201,36 +259,42 @@ def _cc_bindings_from_rust_rule_impl(ctx):
         use_json_output = False,
     )
 
-    (h_out_file, rs_out_file) = _generate_bindings(
+    (h_out_file, rs_out_file, cc_out_file) = _generate_bindings(
         ctx,
         basename,
-        compile_inputs,
+        depset(
+            [info.h_out_file for info in deps_bindings_infos],
+            transitive = [compile_inputs],
+        ),
         args.rustc_flags,
+        deps_bindings_infos,
+        find_crubit_features(target, ctx),
     )
 
-    impl_linking_context = _compile_rs_out_file(ctx, rs_out_file, ctx.attr.crate)
+    impl_linking_context = _compile_rs_out_file(ctx, rs_out_file, target)
 
-    target_cc_info = ctx.attr.crate[CcInfo]
-    target_crate_linking_context = target_cc_info.linking_context
+    target_crate_linking_context = target[CcInfo].linking_context
     cc_info = _make_cc_info_for_h_out_file(
         ctx,
         h_out_file,
+        cc_out_file,
         [target_crate_linking_context, impl_linking_context],
+        [info.cc_info for info in deps_bindings_infos],
     )
-    return [cc_info]
+    return [
+        CcBindingsFromRustInfo(
+            cc_info = cc_info,
+            crate_name = crate_info.name,
+            h_out_file = h_out_file,
+        ),
+    ]
 
-# TODO(b/257283134): Register actions via an `aspect`, rather than directly
-# from the `rule` implementation?
-cc_bindings_from_rust = rule(
-    implementation = _cc_bindings_from_rust_rule_impl,
-    doc = "Rule for generating C++ bindings for a Rust library.",
+cc_bindings_from_rust_aspect = aspect(
+    implementation = _cc_bindings_from_rust_aspect_impl,
+    doc = """Aspect for generating C++ bindings for a Rust library and (transitively) for all
+          the Rust libraries it depends on.""",
+    attr_aspects = ["deps"],
     attrs = {
-        "crate": attr.label(
-            doc = "Rust library to generate C++ bindings for",
-            allow_files = False,
-            mandatory = True,
-            providers = [CrateInfo],
-        ),
         "_cc_bindings_from_rs_tool": attr.label(
             default = Label("//cc_bindings_from_rs:cc_bindings_from_rs_legacy_toolchain_runner.sar"),
             executable = True,
@@ -250,7 +314,17 @@ cc_bindings_from_rust = rule(
             doc = "Dependencies needed to build the C++ sources generated by cc_bindings_from_rs.",
             default = [
                 "//support/internal:bindings_support",
+                "//support/rs_std:rs_box",
                 "//support/rs_std:rs_char",
+                "//support/rs_std:rs_cow_str",
+                "//support/rs_std:rs_lock_guard",
+                "//support/rs_std:rs_nonnull",
+                "//support/rs_std:rs_nonzero",
+                "//support/rs_std:rs_slice",
+                "//support/rs_std:rs_str",
+                "//support/rs_std:rs_string",
+                "//support/rs_std:rs_vec",
+                "//support/rs_std:unsafe_call",
             ],
         ),
         "_process_wrapper": attr.label(
@@ -262,6 +336,9 @@ cc_bindings_from_rust = rule(
         "_rs_deps_for_bindings": attr.label_list(
             doc = "Dependencies needed to build the Rust sources generated by cc_bindings_from_rs.",
             default = [
+                "//support/rs_std:alloc_bridge",
+                "//support/rs_std:cow_bridge",
+                "//support/rs_std:lock_bridge",
                 "@crate_index//:memoffset",
             ],
         ),
@@ -281,3 +358,26 @@ cc_bindings_from_rust = rule(
     ] + use_cpp_toolchain(),
     fragments = ["cpp"],
 )
+
+def _cc_bindings_from_rust_rule_impl(ctx):
+    crate = ctx.attr.crate
+    return [crate[CcBindingsFromRustInfo].cc_info]
+
+cc_bindings_from_rust = rule(
+    implementation = _cc_bindings_from_rust_rule_impl,
+    doc = """Rule for generating C++ bindings for a Rust library.
+
+    The rule can be used in `deps` of `cc_library` and other C++ rules.  C++
+    bindings of the (transitive) Rust dependencies of `crate` are generated as
+    well (by `cc_bindings_from_rust_aspect`) and are available via the
+    generated header of `crate`.""",
+    attrs = {
+        "crate": attr.label(
+            doc = "Rust library to generate C++ bindings for",
+            allow_files = False,
+            mandatory = True,
+            providers = [CrateInfo],
+            aspects = [cc_bindings_from_rust_aspect],
+        ),
+    },
+)
@@ -0,0 +1,20 @@
+# Part of the Crubit project, under the Apache License v2.0 with LLVM
+# Exceptions. See /LICENSE for license information.
+# SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+"""Providers of the `cc_bindings_from_rust` rule and aspect.
+
+Disclaimer: This project is experimental, under heavy development, and should
+not be used yet.
+"""
+
+CcBindingsFromRustInfo = provider(
+    doc = "A provider that contains the C++ bindings generated for a Rust crate.",
+    fields = {
+        "cc_info": ("A CcInfo provider for the generated `..._cc_api.h` header (also " +
+                    "covering the compiled `..._cc_api.cc` and `..._cc_api_impl.rs` files " +
+                    "and the C++ bindings of all the dependencies of the crate)."),
+        "crate_name": "The name of the Rust crate that the bindings have been generated for.",
+        "h_out_file": "The generated `..._cc_api.h` header file.",
+    },
+)
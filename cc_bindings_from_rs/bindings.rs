@@ -2,28 +2,68 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+use crate::rustc_compat;
 use anyhow::{anyhow, bail, ensure, Context, Result};
 use code_gen_utils::{
-    format_cc_ident, format_cc_includes, format_namespace_bound_cc_tokens, make_rs_ident,
-    CcInclude, NamespaceQualifier,
+    escape_cc_ident, format_cc_ident, format_cc_includes, format_cc_tokens,
+    format_namespace_bound_cc_tokens, format_unescaped_cc_ident, is_cc_reserved_identifier,
+    is_cc_reserved_keyword, make_rs_ident, CcInclude, NamespaceQualifier, CRUBIT_ANNOTATION_TOOL,
 };
+use crubit_feature::CrubitFeature;
 use itertools::Itertools;
-use proc_macro2::{Ident, Literal, TokenStream};
+use proc_macro2::{Ident, Literal, TokenStream, TokenTree};
 use quote::{format_ident, quote, ToTokens};
+use rustc_data_structures::sync::Lock;
+use rustc_hir::def::DefKind;
 use rustc_hir::{AssocItemKind, ImplItemKind, ImplicitSelfKind, Item, ItemKind, Node, Unsafety};
+use rustc_infer::infer::TyCtxtInferExt;
 use rustc_middle::dep_graph::DepContext;
 use rustc_middle::mir::Mutability;
+use rustc_middle::ty::util::IntTypeExt;
+use rustc_middle::ty::TypeVisitableExt;
 use rustc_middle::ty::{self, Ty, TyCtxt}; // See <internal link>/ty.html#import-conventions
-use rustc_span::def_id::{DefId, LocalDefId, LOCAL_CRATE};
+use rustc_span::def_id::{DefId, LocalDefId, CRATE_DEF_ID, LOCAL_CRATE};
 use rustc_span::symbol::Symbol;
 use rustc_target::abi::{Abi, FieldsShape, Integer, Layout, Primitive, Scalar};
 use rustc_target::spec::PanicStrategy;
+use rustc_trait_selection::infer::InferCtxtExt;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::iter::once;
 use std::ops::AddAssign;
+use std::path::Path;
 use std::rc::Rc;
 
+/// Like `anyhow!`, but the created error is classified as `$code` by
+/// `ErrorCode::from_error` (see `CodedError`).
+macro_rules! coded_anyhow {
+    ($code:expr, $($t:tt)*) => {
+        anyhow::Error::new(CodedError { code: $code, message: format!($($t)*) })
+    }
+}
+
+/// Like `bail!`, but the returned error is classified as `$code` (see
+/// `coded_anyhow!`).
+macro_rules! coded_bail {
+    ($code:expr, $($t:tt)*) => {
+        return Err(coded_anyhow!($code, $($t)*))
+    }
+}
+
+/// Like `ensure!`, but the returned error is classified as `$code` (see
+/// `coded_anyhow!`).
+macro_rules! coded_ensure {
+    ($code:expr, $cond:expr, $($t:tt)*) => {
+        if !$cond {
+            coded_bail!($code, $($t)*);
+        }
+    }
+}
+
 pub struct Input<'tcx> {
     /// Compilation context for the crate that the bindings should be generated
     /// for.
@@ -34,27 +74,361 @@ pub struct Input<'tcx> {
     /// Example: "crubit/support".
     pub crubit_support_path: Rc<str>,
 
-    // TODO(b/262878759): Provide a set of enabled/disabled Crubit features.
-    pub _features: (),
+    /// Crubit features enabled for the crate.  No bindings are generated
+    /// without `CrubitFeature::Supported`, and the capabilities that are not
+    /// stable yet (e.g. using the original C++ types behind the bindings
+    /// generated by `rs_bindings_from_cc`) require
+    /// `CrubitFeature::Experimental`.
+    pub crubit_features: flagset::FlagSet<CrubitFeature>,
+
+    /// A map from a crate name to the include path with the corresponding C++
+    /// bindings.  This is used when formatting a type exported from another
+    /// crate.  The C++ bindings of such a type are expected to live in the
+    /// top-level namespace given by `crate_name_to_namespace` (i.e. the same
+    /// namespace that `cc_bindings_from_rs` used when generating bindings for
+    /// that crate).
+    pub crate_name_to_include_path: HashMap<Rc<str>, CcInclude>,
+
+    /// A map from a crate name to the top-level C++ namespace of its bindings
+    /// (e.g. `foo::bar` for nested namespaces).  The map may cover both the
+    /// current crate and other crates.  Crates missing from the map use their
+    /// crate name as the top-level namespace.
+    pub crate_name_to_namespace: HashMap<Rc<str>, Rc<str>>,
+
+    /// If present, then the C++ bindings of the given top-level modules of
+    /// the crate are moved into separate headers (see `SplitHeaders`).
+    pub split_headers: Option<SplitHeaders>,
+
+    /// A map from a Cargo feature name to the name of a C++ preprocessor
+    /// macro.  Bindings of items gated by `#[cfg(feature = "...")]` (either
+    /// directly or through one of their parent modules) are wrapped in
+    /// `#ifdef <macro>` when the feature is present in this map.
+    pub feature_guard_macros: HashMap<Rc<str>, Rc<str>>,
+
+    /// Specifies how the generated C++ headers prevent double inclusion.
+    pub include_guard: IncludeGuard,
+
+    /// If present, then the C++ bindings are wrapped in an inline namespace
+    /// (nested inside the top-level namespace of the crate).  The inline
+    /// namespace doesn't affect how C++ code refers to the bindings, but it is
+    /// part of the mangled names of the C++ symbols - this way the bindings of
+    /// different versions of the crate can coexist in one C++ binary.  Not
+    /// used by `c_mode`.
+    pub inline_namespace: Option<InlineNamespace>,
+
+    /// If not empty, then bindings are only generated for the items with a
+    /// Rust path (relative to the crate root) matching one of these path
+    /// filters (see `is_covered_by_path_filter`).
+    pub bind_only: Vec<Rc<str>>,
+
+    /// Bindings are not generated for the items with a Rust path (relative to
+    /// the crate root) matching one of these path filters (see
+    /// `is_covered_by_path_filter`).
+    pub skip: Vec<Rc<str>>,
+
+    /// A map from the Rust path of an item to the C++ name of its bindings.
+    /// Paths of the items from the current crate are relative to the crate
+    /// root (e.g. `some_mod::SomeStruct`), and paths of the items from other
+    /// crates start with the crate name.  This is an alternative to
+    /// `#[crubit::rename(...)]` that doesn't require modifying the Rust
+    /// sources, and it takes precedence over such annotations.  Renaming a
+    /// module renames the corresponding C++ namespace.
+    pub rename_map: HashMap<Rc<str>, Rc<str>>,
+
+    /// A map from the Rust path of a type (with the same format as the keys
+    /// of `rename_map`) to a pre-existing C++ type that should be used in the
+    /// generated bindings instead of the bindings of the Rust type.
+    pub type_map: HashMap<Rc<str>, TypeMapping>,
+
+    /// If true, then the generated header only uses C-compatible declarations,
+    /// so that it can be consumed by C code (and not only by C++ code).  In
+    /// this mode there are no namespaces (instead the names of the generated
+    /// structs and functions are prefixed with the top-level namespace and the
+    /// module path - e.g. `foo_bar_baz` for `bar::baz` from the `foo` crate),
+    /// structs are `typedef`ed and opaque, and structs can't be passed by
+    /// value.
+    pub c_mode: bool,
+
+    /// Version of the C++ standard targeted by the generated C++ bindings.
+    /// Constructs that require C++20 are only generated when targeting C++20.
+    pub cc_std: CcStandard,
+
+    /// Naming convention of the C++ bindings of functions and methods.  Not
+    /// used by `c_mode`.
+    pub fn_naming: FnNaming,
+
+    /// How the C++ bindings of `unsafe` Rust functions and methods require
+    /// the C++ callers to opt into calling them.  Not used by `c_mode`.
+    pub unsafe_fn_marker: UnsafeFnMarker,
+
+    /// If true, then the declarations in the generated C++ header(s) are
+    /// preceded by `#line` directives that point at the Rust definitions of the
+    /// corresponding items (so that C++ compiler errors and IDE "go to
+    /// definition" lead to the Rust source code).  `#line` directives that
+    /// point back at the generated header follow the declarations - they have
+    /// to be finalized by `finalize_line_directives` after formatting the
+    /// header.
+    pub emit_line_directives: bool,
+
+    /// If true, then the Rust side of the bindings defines a symbol whose name
+    /// contains a hash of the bound API (the ABI stamp), and the generated
+    /// C++ header(s) refer to this symbol - this way C++ code that uses a
+    /// stale header fails to link (see `format_abi_stamp_check`).  This
+    /// requires linking the Rust side of the bindings even if the C++ code
+    /// only calls `extern "C"` functions of the crate.
+    pub emit_abi_stamp: bool,
+
+    /// Prefixes of the file paths that should be replaced when the paths are
+    /// embedded into the generated bindings (e.g. into the comments with the
+    /// source locations of the Rust items), so that the bindings don't depend
+    /// on the machine-specific location of the sources.  The replacements work
+    /// the same way as `rustc --remap-path-prefix` (which is also respected) -
+    /// e.g. if multiple prefixes match a path, then the last one is used.
+    pub remap_path_prefixes: Vec<(Rc<str>, Rc<str>)>,
+
+    /// The library of the Rust standard library family (`std`, `alloc`, or
+    /// `core`) that the Rust side of the bindings may depend on (see
+    /// `detect_rust_std_lib`).  Without `std`, the generated Rust code is
+    /// `#![no_std]`, and the types that require a missing library (e.g.
+    /// `Cow<str>` without `alloc`) are not supported.
+    pub rust_std_lib: RustStdLib,
+
+    /// How C++ code links against the crate (see `detect_crate_linkage`).
+    /// When the crate is a shared library, the C++ declarations of the symbols
+    /// that the library exports (e.g. `extern "C"` functions and thunks) are
+    /// annotated with `CRUBIT_DYLIB_IMPORT` (i.e. `__declspec(dllimport)` on
+    /// Windows, and default visibility elsewhere).
+    pub crate_linkage: CrateLinkage,
+
+    /// If present, then the `static_assert`s that verify the layouts of the
+    /// generated C++ structs are emitted into `Output::cc_body` (a companion
+    /// C++ source file that `#include`s the given header), rather than into
+    /// the generated C++ header(s).  This way the assertions are compiled only
+    /// once, rather than in every translation unit that includes the header.
+    /// Not supported together with `c_mode`.
+    pub cc_out_h_include: Option<CcInclude>,
+
+    /// If present, then the bindings of the items that didn't change since the
+    /// run of the tool that populated the cache are reused (rather than
+    /// regenerated), and the cache is updated with the bindings of all the
+    /// other items.
+    pub snippet_cache: Option<RefCell<SnippetCache>>,
+
+    /// Snippets that repeat throughout the generated bindings (e.g. the C++
+    /// spelling of `i32`) are generated once and then shared.
+    pub interner: SnippetInterner<'tcx>,
+}
+
+/// A pre-existing C++ type that represents a Rust type (see `Input::type_map`).
+#[derive(Clone, Debug)]
+pub struct TypeMapping {
+    /// Fully qualified name of the C++ type (e.g. `::base::Uuid`).
+    pub cc_type: Rc<str>,
+
+    /// Headers that need to be `#include`d to use `cc_type` (and the functions
+    /// from `conversion`).
+    pub cc_headers: Vec<Rc<str>>,
+
+    /// When `None`, then `cc_type` is expected to have the same layout as the
+    /// Rust type and to be trivially relocatable - `cc_type` is used instead
+    /// of the C++ bindings of the Rust type everywhere (and the bindings of the
+    /// Rust type are not generated).
+    ///
+    /// Otherwise, the C++ bindings of the Rust type are generated as usual,
+    /// and `cc_type` is only used in the signatures of the generated
+    /// functions, which convert the parameters and return values at runtime.
+    pub conversion: Option<TypeConversion>,
+}
+
+/// Functions that convert between a C++ type from a `TypeMapping` and the C++
+/// bindings of the corresponding Rust type.
+#[derive(Clone, Debug)]
+pub struct TypeConversion {
+    /// Fully qualified name of a C++ function that takes the C++ bindings of
+    /// the Rust type by value and returns the `TypeMapping::cc_type`.
+    pub to_cc: Rc<str>,
+
+    /// Fully qualified name of a C++ function that takes the
+    /// `TypeMapping::cc_type` by value and returns the C++ bindings of the
+    /// Rust type.
+    pub from_cc: Rc<str>,
+}
+
+/// Memoized snippets of the generated bindings (see `Input::interner`).
+/// Cloning a memoized `TokenStream` or `CcInclude` only bumps a reference
+/// count, which avoids a lot of allocations when generating the bindings of
+/// big crates.
+#[derive(Default)]
+pub struct SnippetInterner<'tcx> {
+    /// Successful results of `format_ty_for_cc`.
+    cc_tys: RefCell<HashMap<Ty<'tcx>, CcSnippet>>,
+
+    /// Results of `Input::support_header`.
+    support_headers: RefCell<HashMap<String, CcInclude>>,
 
-    // TODO(b/258261328): Provide a map from crate name into C++ header path with crate bindings.
-    pub _crate_to_include_map: (),
+    /// Results of `Input::crubit_annotations`.
+    annotations: RefCell<HashMap<DefId, Rc<CrubitAnnotations>>>,
+
+    /// Results of `format_item`.
+    items: RefCell<HashMap<LocalDefId, Result<Vec<(SnippetKey, MixedSnippet)>, Rc<anyhow::Error>>>>,
+
+    /// Results of `generate_item_ir`, recorded by the same pass that formats
+    /// the bindings of the items and `impl` items (see `record_item_ir`).
+    item_irs: RefCell<HashMap<LocalDefId, Result<Option<ApiItem>, Rc<anyhow::Error>>>>,
 }
 
 impl<'tcx> Input<'tcx> {
-    // TODO(b/259724276): This function's results should be memoized.  It may be
-    // easier if separate functions are provided for each support header - e.g.
-    // `rs_char()`, `return_value_slot()`, etc.
+    /// Returns the annotations of `def_id` (see `CrubitAnnotations::new`).
+    fn crubit_annotations(&self, def_id: DefId) -> Rc<CrubitAnnotations> {
+        let mut annotations = self.interner.annotations.borrow_mut();
+        let entry = annotations.entry(def_id);
+        entry.or_insert_with(|| Rc::new(CrubitAnnotations::new(self.tcx, def_id))).clone()
+    }
+
     fn support_header(&self, suffix: &str) -> CcInclude {
+        let mut support_headers = self.interner.support_headers.borrow_mut();
+        if let Some(include) = support_headers.get(suffix) {
+            return include.clone();
+        }
         let support_path = &*self.crubit_support_path;
-        let full_path = format!("{support_path}/{suffix}");
-        CcInclude::user_header(full_path.into())
+        let include = CcInclude::user_header(format!("{support_path}/{suffix}").into());
+        support_headers.insert(suffix.to_string(), include.clone());
+        include
+    }
+
+    /// Formats the annotation of the C++ declarations of the symbols exported
+    /// by the Rust side of the bindings (see `Input::crate_linkage`).  The
+    /// annotation goes between `extern "C"` and the declaration.
+    fn format_cc_import_attr(&self) -> TokenStream {
+        match self.crate_linkage {
+            CrateLinkage::Rlib | CrateLinkage::StaticLib => quote! {},
+            CrateLinkage::DynamicLib => quote! { CRUBIT_DYLIB_IMPORT },
+        }
+    }
+
+    /// Formats the top-level C++ namespace of the bindings of `krate` (e.g.
+    /// `foo::bar`, without a leading or trailing `::`).
+    fn format_top_level_ns_for_cc(&self, krate: Symbol) -> Result<TokenStream> {
+        let top_level_ns = self.get_top_level_ns(&krate);
+        let ns_parts = top_level_ns
+            .split("::")
+            .map(format_cc_ident)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid top-level namespace: `{top_level_ns}`"))?;
+        Ok(quote! { #(#ns_parts)::* })
+    }
+
+    /// Returns the top-level C++ namespace of the bindings of `krate` (e.g.
+    /// `foo::bar`).
+    fn get_top_level_ns<'a>(&'a self, krate: &'a Symbol) -> &'a str {
+        self.crate_name_to_namespace
+            .get(krate.as_str())
+            .map(|ns| ns.as_ref())
+            .unwrap_or_else(|| krate.as_str())
+    }
+
+    /// Returns whether bindings of `def_id` should be generated, based on
+    /// `bind_only`, `skip`, and `#[crubit::skip]` annotations (of `def_id` or
+    /// of its parent modules).
+    fn is_selected(&self, def_id: LocalDefId) -> bool {
+        let tcx = self.tcx;
+        let path = tcx.def_path_str(def_id.to_def_id());
+        let matches_any = |filters: &[Rc<str>]| {
+            filters.iter().any(|filter| is_covered_by_path_filter(filter, &path))
+        };
+        let is_annotated_with_skip =
+            std::iter::successors(Some(def_id.to_def_id()), |&def_id| tcx.opt_parent(def_id))
+                .any(|def_id| self.crubit_annotations(def_id).skip);
+        (self.bind_only.is_empty() || matches_any(&self.bind_only))
+            && !matches_any(&self.skip)
+            && !is_annotated_with_skip
+    }
+
+    /// Formats the path of the Rust crate that the generated Rust code uses to
+    /// refer to the items from `core`: `::std` (which re-exports them) or,
+    /// when the bindings can't depend on `std`, `::core`.
+    fn format_rs_core_crate(&self) -> TokenStream {
+        match self.rust_std_lib {
+            RustStdLib::Std => quote! { ::std },
+            RustStdLib::Alloc | RustStdLib::Core => quote! { ::core },
+        }
+    }
+
+    /// Returns the C++ name of `def_id` from `rename_map`, or `None` if
+    /// `def_id` is not covered by the map.
+    fn get_renamed_cc_name(&self, def_id: DefId) -> Option<Symbol> {
+        if self.rename_map.is_empty() {
+            return None;
+        }
+        let path = self.tcx.def_path_str(def_id);
+        self.rename_map.get(path.as_str()).map(|cc_name| Symbol::intern(cc_name))
+    }
+
+    /// Returns the entry of `type_map` that covers the type `def_id`.
+    fn get_type_mapping(&self, def_id: DefId) -> Option<&TypeMapping> {
+        if self.type_map.is_empty() {
+            return None;
+        }
+        let path = self.tcx.def_path_str(def_id);
+        self.type_map.get(path.as_str())
+    }
+
+    /// Returns the entry of `type_map` that covers `ty`, but only if the entry
+    /// requires a `TypeConversion`.
+    fn get_converted_type_mapping(&self, ty: Ty) -> Option<&TypeMapping> {
+        match ty.kind() {
+            ty::TyKind::Adt(adt, _) => {
+                self.get_type_mapping(adt.did()).filter(|mapping| mapping.conversion.is_some())
+            }
+            _ => None,
+        }
     }
 }
 
+/// Returns whether `path` is covered by `filter`.  A `filter` containing a `*`
+/// is a pattern (see `matches_path_pattern`).  Other filters cover the item
+/// with the given path and all the items nested inside it - e.g. `foo` covers
+/// `foo` and `foo::bar`, but not `foobar`.
+fn is_covered_by_path_filter(filter: &str, path: &str) -> bool {
+    if filter.contains('*') {
+        matches_path_pattern(filter, path)
+    } else {
+        path.strip_prefix(filter).map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+    }
+}
+
+/// Explicit mapping from top-level modules of the crate to the C++ headers
+/// that contain their bindings (see `Input::split_headers`).  The bindings of
+/// the items at the crate root and of the modules that are not listed in
+/// `module_headers` stay in the main header (`Output::h_body`).  The main
+/// header also `#include`s all the module headers, so that `#include`-ing it
+/// provides the whole API of the crate.
+#[derive(Clone, Debug, Default)]
+pub struct SplitHeaders {
+    /// Path of the main header.  Used by the module headers to `#include` the
+    /// main header (e.g. when a function from a module takes a struct from
+    /// the crate root).
+    pub h_out: Rc<str>,
+
+    /// A map from the name of a top-level module of the crate to the path of
+    /// the header with the bindings of the module.  The paths are used both
+    /// as output paths and in the `#include`s of the generated headers.
+    pub module_headers: BTreeMap<Rc<str>, Rc<str>>,
+}
+
 pub struct Output {
     pub h_body: TokenStream,
+
+    /// Paths and bodies of additional C++ headers.  These are only generated
+    /// when `Input::split_headers` is present (one header for each entry of
+    /// `SplitHeaders::module_headers`).
+    pub h_parts: Vec<(String, TokenStream)>,
+
     pub rs_body: TokenStream,
+
+    /// Body of the companion C++ source file.  Empty unless
+    /// `Input::cc_out_h_include` is present.
+    pub cc_body: TokenStream,
 }
 
 pub fn generate_bindings(input: &Input) -> Result<Output> {
@@ -65,29 +439,57 @@ pub fn generate_bindings(input: &Input) -> Result<Output> {
 
     let top_comment = {
         let crate_name = input.tcx.crate_name(LOCAL_CRATE);
+        let lang = if input.c_mode { "C" } else { "C++" };
         let txt = format!(
-            "Automatically @generated C++ bindings for the following Rust crate:\n\
+            "Automatically @generated {lang} bindings for the following Rust crate:\n\
              {crate_name}"
         );
         quote! { __COMMENT__ #txt __NEWLINE__ }
     };
 
-    let Output { h_body, rs_body } = format_crate(input).unwrap_or_else(|err| {
+    let Output { h_body, h_parts, rs_body, cc_body } = format_crate(input).unwrap_or_else(|err| {
         let txt = format!("Failed to generate bindings for the crate: {err}");
         let src = quote! { __COMMENT__ #txt };
-        Output { h_body: src.clone(), rs_body: src }
+        Output { h_body: src.clone(), h_parts: vec![], rs_body: src.clone(), cc_body: src }
     });
+    // Checked after `format_crate`, which records the results that the check
+    // needs (see `generate_item_ir`).
+    check_must_bind_items(input)?;
 
-    let h_body = quote! {
-        #top_comment
-
-        // TODO(b/251445877): Replace `#pragma once` with include guards.
-        __HASH_TOKEN__ pragma once __NEWLINE__
-        __NEWLINE__
-
-        #h_body
+    let link_line_comment = match format_link_line_comment(input) {
+        None => quote! {},
+        Some(txt) => quote! { __NEWLINE__ __COMMENT__ #txt __NEWLINE__ },
+    };
+    let format_h_body = |h_path: Option<&str>, h_body: TokenStream| {
+        let h_body = input.include_guard.format(h_path, h_body);
+        quote! {
+            #top_comment
+            #link_line_comment
+            #h_body
+        }
     };
+    let h_body = format_h_body(None, h_body);
+    let h_parts = h_parts
+        .into_iter()
+        .map(|(h_path, h_body)| {
+            let remapped_h_path = remap_path_prefix(&input.remap_path_prefixes, &h_path);
+            let h_body = format_h_body(Some(&remapped_h_path), h_body);
+            (h_path, h_body)
+        })
+        .collect_vec();
 
+    // The thunks of `extern "C-unwind"` functions are also `extern "C-unwind"`
+    // (see `format_thunk_abi`), and therefore the generated code needs the same
+    // feature as the crate that declares such functions.
+    let unwind_feature = if input.tcx.features().c_unwind {
+        quote! { #![feature(c_unwind)] __NEWLINE__ }
+    } else {
+        quote! {}
+    };
+    let no_std = match input.rust_std_lib {
+        RustStdLib::Std => quote! {},
+        RustStdLib::Alloc | RustStdLib::Core => quote! { #![no_std] __NEWLINE__ },
+    };
     let rs_body = quote! {
         #top_comment
 
@@ -95,12 +497,271 @@ pub fn generate_bindings(input: &Input) -> Result<Output> {
         // bindings need to relax the `improper_ctypes_definitions` warning
         // for `char` (and possibly for other built-in types in the future).
         #![allow(improper_ctypes_definitions)] __NEWLINE__
+        #unwind_feature
+        #no_std
         __NEWLINE__
 
         #rs_body
     };
 
-    Ok(Output { h_body, rs_body })
+    let cc_body = if input.cc_out_h_include.is_some() {
+        quote! {
+            #top_comment
+            #cc_body
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(Output { h_body, h_parts, rs_body, cc_body })
+}
+
+/// Version of the C++ standard targeted by the generated C++ bindings.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum CcStandard {
+    Cxx17,
+    Cxx20,
+}
+
+impl CcStandard {
+    /// Formats an assertion that the generated C++ bindings are compiled with
+    /// (at least) the targeted version of the C++ standard.  No assertion is
+    /// needed for C++17, because it is the minimum version supported by
+    /// Crubit.
+    fn format_assertion(&self) -> TokenStream {
+        match self {
+            Self::Cxx17 => quote! {},
+            Self::Cxx20 => quote! {
+                static_assert(
+                    __cplusplus >= 202002,
+                    "The bindings have been generated with `--cc-std=c++20`");
+                __NEWLINE__ __NEWLINE__
+            },
+        }
+    }
+}
+
+/// The library of the Rust standard library family that the Rust side of the
+/// bindings may depend on.  Each library is a superset of the libraries that
+/// come before it (e.g. `Alloc` implies `Core`).
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum RustStdLib {
+    Core,
+    Alloc,
+    Std,
+}
+
+/// Detects which library of the Rust standard library family is available to
+/// the crate being compiled: `std` unless the crate is `#![no_std]`, and
+/// otherwise `alloc` if the crate depends on it (e.g. via `extern crate
+/// alloc`), or only `core`.
+pub fn detect_rust_std_lib(tcx: TyCtxt) -> RustStdLib {
+    let is_no_std =
+        tcx.get_attrs(CRATE_DEF_ID.to_def_id(), rustc_span::symbol::sym::no_std).next().is_some();
+    let depends_on_alloc =
+        tcx.crates(()).iter().any(|&cnum| tcx.crate_name(cnum) == rustc_span::symbol::sym::alloc);
+    if !is_no_std {
+        RustStdLib::Std
+    } else if depends_on_alloc {
+        RustStdLib::Alloc
+    } else {
+        RustStdLib::Core
+    }
+}
+
+/// The kind of library that C++ code links against to use the generated
+/// bindings (the Rust side of the bindings is expected to be compiled into the
+/// same library as the crate).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CrateLinkage {
+    /// The crate is an `rlib` that is linked into the final binary by the build
+    /// system (e.g. Bazel's `rust_library`).
+    Rlib,
+
+    /// The crate is a `staticlib` (e.g. `libfoo.a`).
+    StaticLib,
+
+    /// The crate is a `cdylib` or a `dylib` (e.g. `libfoo.so` or `foo.dll`).
+    /// The C++ declarations of the symbols exported by the library are
+    /// annotated with `CRUBIT_DYLIB_IMPORT`.
+    DynamicLib,
+}
+
+/// Detects how the crate being compiled is linked based on its crate types
+/// (i.e. `--crate-type` or `#![crate_type = "..."]`).  If there are multiple
+/// crate types, then a shared library takes precedence over a static library.
+pub fn detect_crate_linkage(tcx: TyCtxt) -> CrateLinkage {
+    use rustc_session::config::CrateType;
+    let crate_types = tcx.sess.crate_types();
+    if crate_types.iter().any(|t| matches!(t, CrateType::Cdylib | CrateType::Dylib)) {
+        CrateLinkage::DynamicLib
+    } else if crate_types.contains(&CrateType::Staticlib) {
+        CrateLinkage::StaticLib
+    } else {
+        CrateLinkage::Rlib
+    }
+}
+
+/// Formats a comment with the expected link line of the C++ code that uses
+/// the bindings of a `staticlib` or a `cdylib` crate (using the library file
+/// names of the target platform).  Returns `None` for `CrateLinkage::Rlib`,
+/// because such crates are linked by the build system.
+fn format_link_line_comment(input: &Input) -> Option<String> {
+    let tcx = input.tcx;
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let target = &tcx.sess.target;
+    match input.crate_linkage {
+        CrateLinkage::Rlib => None,
+        CrateLinkage::StaticLib => {
+            let lib = format!("{}{crate_name}{}", target.staticlib_prefix, target.staticlib_suffix);
+            Some(format!(
+                "Expected link line: `<dir>/{lib}` followed by the native libraries that it \
+                 depends on (see `rustc --print native-static-libs`).  The Rust side of the \
+                 bindings has to be compiled into the same static library."
+            ))
+        }
+        CrateLinkage::DynamicLib => {
+            let link_line = if target.is_like_msvc {
+                format!("`<dir>/{crate_name}{}.lib`", target.dll_suffix)
+            } else {
+                format!("`-L<dir> -l{crate_name}`")
+            };
+            let lib = format!("{}{crate_name}{}", target.dll_prefix, target.dll_suffix);
+            Some(format!(
+                "Expected link line: {link_line} (with `{lib}` available at runtime).  The Rust \
+                 side of the bindings has to be compiled into the same shared library."
+            ))
+        }
+    }
+}
+
+/// Specifies how the generated C++ headers prevent double inclusion.
+pub enum IncludeGuard {
+    /// `#pragma once`.
+    PragmaOnce,
+
+    /// `#ifndef` / `#define` / `#endif` using a macro name derived from the
+    /// path of the header - e.g. `FOO_BAR_CC_API_H_` for `foo/bar_cc_api.h`.
+    /// `h_path` is the path of `Output::h_body` (the paths of `Output::h_parts`
+    /// come from `SplitHeaders::module_headers`).
+    PathBased { h_path: Rc<str> },
+
+    /// Like `PathBased`, but using a macro name derived from a hash of the
+    /// path of the header - e.g. `CRUBIT_GENERATED_0123456789ABCDEF_H_`.
+    HashBased { h_path: Rc<str> },
+}
+
+impl IncludeGuard {
+    /// Wraps `h_body` in an include guard.  `part_h_path` is the (remapped)
+    /// path of one of `Output::h_parts` (or `None` for `Output::h_body`).
+    fn format(&self, part_h_path: Option<&str>, h_body: TokenStream) -> TokenStream {
+        let (h_path, is_hash_based) = match self {
+            Self::PragmaOnce => {
+                return quote! {
+                    __HASH_TOKEN__ pragma once __NEWLINE__
+                    __NEWLINE__
+                    #h_body
+                };
+            }
+            Self::PathBased { h_path } => (h_path, false),
+            Self::HashBased { h_path } => (h_path, true),
+        };
+        let h_path = part_h_path.unwrap_or(h_path);
+        let guard_name = if is_hash_based {
+            format_hash_based_guard_name(h_path)
+        } else {
+            format_path_based_guard_name(h_path)
+        };
+        let guard = format_ident!("{}", guard_name);
+        quote! {
+            __HASH_TOKEN__ ifndef #guard __NEWLINE__
+            __HASH_TOKEN__ define #guard __NEWLINE__
+            __NEWLINE__
+            #h_body
+            __NEWLINE__
+            __HASH_TOKEN__ endif __COMMENT__ #guard_name
+        }
+    }
+}
+
+/// Specifies how the name of the inline namespace of the C++ bindings is
+/// derived (see `Input::inline_namespace`).
+pub enum InlineNamespace {
+    /// The name is derived from the version of the crate - e.g. `1.2.3`
+    /// results in `v1_2_3`.
+    CrateVersion(Rc<str>),
+
+    /// The name is derived from a hash of the bound API (see `get_abi_hash`) -
+    /// e.g. `abi_0123456789abcdef`.
+    AbiHash,
+}
+
+/// Formats the name of the inline namespace of the C++ bindings (see
+/// `Input::inline_namespace`).
+fn format_inline_namespace(
+    inline_namespace: &InlineNamespace,
+    parts: &BTreeMap<HeaderPart, HeaderPartBuilder>,
+    rs_body: &TokenStream,
+) -> Result<TokenStream> {
+    let name = match inline_namespace {
+        InlineNamespace::CrateVersion(version) => {
+            let version = version
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>();
+            format!("v{version}")
+        }
+        InlineNamespace::AbiHash => format!("abi_{:016x}", get_abi_hash(parts, rs_body)),
+    };
+    format_cc_ident(&name).with_context(|| format!("Invalid inline namespace: `{name}`"))
+}
+
+/// Returns the part of `h_path` that doesn't depend on the build
+/// configuration - e.g. `foo/bar_cc_api.h` for
+/// `bazel-out/k8-fastbuild/bin/foo/bar_cc_api.h`.  This way the include guard
+/// of a header is the same in all build configurations (and doesn't depend on
+/// whether `--remap-path-prefix` has already stripped the output directory).
+fn get_config_independent_h_path(h_path: &str) -> &str {
+    let Some((_, path_in_bazel_out)) = h_path.split_once("bazel-out/") else { return h_path };
+    // Skipping the `<config>/bin/` (or `<config>/genfiles/`) components.
+    match path_in_bazel_out.splitn(3, '/').collect_vec()[..] {
+        [_config, "bin" | "genfiles", path] => path,
+        _ => h_path,
+    }
+}
+
+/// Derives the name of an include guard macro from `h_path` - e.g.
+/// `foo/bar_cc_api.h` becomes `FOO_BAR_CC_API_H_`.
+fn format_path_based_guard_name(h_path: &str) -> String {
+    let mut name = String::with_capacity(h_path.len());
+    for c in get_config_independent_h_path(h_path).chars() {
+        let c = if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' };
+        // Collapsing runs of underscores, because names that contain `__` are reserved in C++.
+        if c != '_' || !name.ends_with('_') {
+            name.push(c);
+        }
+    }
+
+    // Names starting with an underscore followed by an uppercase letter are reserved
+    // in C++ (e.g. this may happen for absolute paths).  Trailing underscores are trimmed,
+    // because another underscore is appended below.
+    let name = name.trim_matches('_');
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        format!("{name}_")
+    } else {
+        format!("CRUBIT_{name}_")
+    }
+}
+
+/// Derives the name of an include guard macro from a hash of `h_path`.
+fn format_hash_based_guard_name(h_path: &str) -> String {
+    // FNV-1a is used (rather than `std::hash::Hash`), because its results are
+    // guaranteed to be stable across Rust versions and platforms.
+    let h_path = get_config_independent_h_path(h_path);
+    let hash = h_path.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("CRUBIT_GENERATED_{hash:016X}_H_")
 }
 
 #[derive(Clone, Debug, Default)]
@@ -162,7 +823,7 @@ impl AddAssign for CcPrerequisites {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 struct CcSnippet {
     tokens: TokenStream,
     prereqs: CcPrerequisites,
@@ -190,6 +851,336 @@ impl CcSnippet {
     }
 }
 
+/// `#[crubit::...]` annotations of a Rust item (see
+/// `support/crubit_attributes.rs`), and the annotations that
+/// `rs_bindings_from_cc` writes into the Rust bindings of C++ types.
+#[derive(Debug, Default)]
+struct CrubitAnnotations {
+    /// `#[crubit::rename("CcName")]`.
+    rename: Option<Symbol>,
+
+    /// `#[crubit::skip]`.
+    skip: bool,
+
+    /// `#[crubit::must_bind]`.
+    must_bind: bool,
+
+    /// `#[crubit::cc_namespace("x::y")]`.
+    cc_namespace: Option<Rc<str>>,
+
+    /// `#[crubit::cc_case("CamelCase")]` or `#[crubit::cc_case("snake_case")]`.
+    cc_case: Option<CcNameCase>,
+
+    /// `#[crubit::cc_builder]`.
+    cc_builder: bool,
+
+    /// `cpp_type=::ns::SomeStruct` - the fully qualified name of the C++ type
+    /// that the Rust type is a binding of (annotations of Rust types generated
+    /// by `rs_bindings_from_cc`).
+    cpp_type: Option<Rc<str>>,
+
+    /// `cpp_header=path/to/header.h` - the headers that declare the C++ types
+    /// with a `cpp_type` annotation (annotations of the root of a crate
+    /// generated by `rs_bindings_from_cc`).
+    cpp_headers: Vec<Rc<str>>,
+}
+
+impl CrubitAnnotations {
+    /// Reads the annotations of `def_id` (i.e. its `#[crubit_annotate::...]`
+    /// tool attributes - see `CRUBIT_ANNOTATION_TOOL`).  Unrecognized
+    /// annotations (e.g. ones introduced by a newer version of the `crubit`
+    /// crate) are ignored.  Use `Input::crubit_annotations` rather than calling
+    /// this function directly (the former memoizes the results).
+    fn new(tcx: TyCtxt, def_id: DefId) -> Self {
+        let mut result = Self::default();
+        let annotations = tcx
+            .get_attrs_unchecked(def_id)
+            .iter()
+            .filter(|attr| !attr.is_doc_comment())
+            .filter_map(|attr| match &attr.get_normal_item().path.segments[..] {
+                [tool, name] if tool.ident.as_str() == CRUBIT_ANNOTATION_TOOL => {
+                    Some((name.ident.name, attr.value_str()))
+                }
+                _ => None,
+            });
+        for (name, value) in annotations {
+            match (name.as_str(), value.as_ref().map(Symbol::as_str)) {
+                ("rename", Some(_)) => result.rename = value,
+                ("skip", None) => result.skip = true,
+                ("must_bind", None) => result.must_bind = true,
+                ("cc_namespace", Some(value)) => result.cc_namespace = Some(value.into()),
+                ("cc_case", Some("CamelCase")) => result.cc_case = Some(CcNameCase::CamelCase),
+                ("cc_case", Some("snake_case")) => result.cc_case = Some(CcNameCase::SnakeCase),
+                ("cc_builder", None) => result.cc_builder = true,
+                ("cpp_type", Some(value)) => result.cpp_type = Some(value.into()),
+                ("cpp_header", Some(value)) => result.cpp_headers.push(value.into()),
+                _ => (),
+            }
+        }
+        result
+    }
+}
+
+/// Case of the C++ names of functions and methods (see `FnNaming`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CcNameCase {
+    /// The Rust name is used as-is (i.e. typically it is `snake_case`).
+    #[default]
+    SnakeCase,
+
+    /// `snake_case` Rust names are converted into `CamelCase` (the style of
+    /// function names recommended by the Google C++ Style Guide).
+    CamelCase,
+}
+
+/// Naming convention of the C++ bindings of functions and methods.  The
+/// convention doesn't apply to functions with a `#[crubit::rename(...)]`
+/// annotation.
+///
+/// Note that the C++ names that result from the convention are not
+/// disambiguated (e.g. when `CamelCase` turns `fn point` into a name that
+/// collides with `struct Point`).
+#[derive(Clone, Debug, Default)]
+pub struct FnNaming {
+    /// Case of the C++ names.  Can be overridden for a function (or, if the
+    /// annotation is on a module or an `impl` block, for all the functions
+    /// inside it) by a `#[crubit::cc_case(...)]` annotation.
+    pub case: CcNameCase,
+
+    /// Prefix of the C++ names (e.g. `Rs` results in `RsFoo`).
+    pub prefix: Rc<str>,
+
+    /// Suffix of the C++ names (e.g. `_rs` results in `foo_rs`).
+    pub suffix: Rc<str>,
+}
+
+/// How the C++ bindings of `unsafe` Rust functions and methods make the C++
+/// callers consciously opt into upholding the safety requirements of the
+/// Rust function.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UnsafeFnMarker {
+    /// The C++ function takes an extra, leading `rs_std::UnsafeCall`
+    /// parameter - e.g. `foo(rs_std::UnsafeCall(), x)`.
+    #[default]
+    TokenParam,
+
+    /// The C++ name of the function gets an `_unsafe` suffix - e.g.
+    /// `foo_unsafe(x)`.
+    NameSuffix,
+}
+
+/// Returns the C++ name of the function or method `def_id` - i.e.
+/// `FullyQualifiedName::cc_name` adjusted according to `Input::fn_naming` (and
+/// `Input::unsafe_fn_marker`).
+fn get_fn_cc_name(input: &Input, def_id: DefId) -> Symbol {
+    let tcx = input.tcx;
+
+    // Methods from the `impl`s of operator traits are bound as C++ operators (e.g. `operator+=`).
+    if let Some(cc_operator) = get_cc_operator(tcx, def_id) {
+        return Symbol::intern(&format!("operator{cc_operator}"));
+    }
+
+    // Methods from the `impl`s of `AsRef` and `Borrow` are bound as accessors named after the
+    // returned view (e.g. `as_str`).
+    if let Some(accessor_name) = get_cc_view_accessor_name(tcx, def_id) {
+        return Symbol::intern(&match input.fn_naming.case {
+            CcNameCase::SnakeCase => accessor_name,
+            CcNameCase::CamelCase => convert_snake_case_to_camel_case(&accessor_name),
+        });
+    }
+
+    // Methods from trait `impl`s are named after the methods of the trait, so that the C++
+    // bindings of the ADT match the C++20 concept of the trait (see `format_trait`).
+    if let Some(trait_item_def_id) =
+        tcx.opt_associated_item(def_id).and_then(|item| item.trait_item_def_id)
+    {
+        return get_fn_cc_name(input, trait_item_def_id);
+    }
+
+    let cc_name = FullyQualifiedName::new(input, def_id)
+        .cc_name
+        .expect("Functions are assumed to always have a name");
+    let name = if input.crubit_annotations(def_id).rename.is_some()
+        || input.get_renamed_cc_name(def_id).is_some()
+    {
+        cc_name.to_string()
+    } else {
+        // The innermost `#[crubit::cc_case(...)]` annotation takes precedence.
+        let case = std::iter::successors(Some(def_id), |&def_id| tcx.opt_parent(def_id))
+            .find_map(|def_id| input.crubit_annotations(def_id).cc_case)
+            .unwrap_or(input.fn_naming.case);
+        let name = match case {
+            CcNameCase::SnakeCase => cc_name.to_string(),
+            CcNameCase::CamelCase => convert_snake_case_to_camel_case(cc_name.as_str()),
+        };
+        let FnNaming { prefix, suffix, .. } = &input.fn_naming;
+        format!("{prefix}{name}{suffix}")
+    };
+
+    // Unlike `Input::fn_naming`, the `_unsafe` suffix also applies to renamed
+    // functions - the suffix is what makes the C++ callers opt into the call.
+    let is_unsafe = get_fn_sig(tcx, def_id).unsafety == Unsafety::Unsafe;
+    if is_unsafe && !input.c_mode && input.unsafe_fn_marker == UnsafeFnMarker::NameSuffix {
+        Symbol::intern(&format!("{name}_unsafe"))
+    } else {
+        Symbol::intern(&name)
+    }
+}
+
+/// Formats the C++ name of a function returned by `get_fn_cc_name` - either
+/// an identifier, or the name of an operator function (e.g. `operator+=`).
+fn format_fn_cc_name(cc_name: Symbol) -> Result<TokenStream> {
+    match cc_name.as_str().strip_prefix("operator") {
+        Some(op) if op.starts_with(|c: char| c.is_ascii_punctuation() && c != '_') => {
+            let op: TokenStream =
+                op.parse().map_err(|_| anyhow!("Can't format `{cc_name}` as a C++ operator"))?;
+            Ok(quote! { operator #op })
+        }
+        _ => format_cc_ident(cc_name.as_str()),
+    }
+}
+
+/// The `core::ops` traits whose `impl`s are bound as C++ operators, and the
+/// corresponding C++ operators (see `get_cc_operator`):
+/// - the compound assignment traits (e.g. `AddAssign`) take `&mut self`, and
+///   are bound as non-`const` member functions that return `void` (e.g.
+///   `void operator+=(std::int32_t rhs)`),
+/// - the unary operator traits (`Neg` and `Not`) take `self` by value, and
+///   therefore (like other methods that consume `self`) they are bound as
+///   `&&`-qualified member functions (e.g. `-std::move(x)`).
+const CC_OPERATOR_TRAITS: &[(&str, &str)] = &[
+    ("AddAssign", "+="),
+    ("SubAssign", "-="),
+    ("MulAssign", "*="),
+    ("DivAssign", "/="),
+    ("RemAssign", "%="),
+    ("BitAndAssign", "&="),
+    ("BitOrAssign", "|="),
+    ("BitXorAssign", "^="),
+    ("ShlAssign", "<<="),
+    ("ShrAssign", ">>="),
+    ("Neg", "-"),
+    ("Not", "!"),
+];
+
+/// Returns the name of the trait (e.g. `AddAssign`) and the corresponding C++
+/// operator (e.g. `+=`) if `trait_def_id` identifies one of the
+/// `CC_OPERATOR_TRAITS`.
+fn get_cc_operator_trait(tcx: TyCtxt, trait_def_id: DefId) -> Option<(&'static str, &'static str)> {
+    CC_OPERATOR_TRAITS
+        .iter()
+        .copied()
+        .find(|(name, _)| is_std_item(tcx, trait_def_id, &format!("std::ops::{name}")))
+}
+
+/// Returns the C++ operator (e.g. `+=`) if `def_id` identifies a method from
+/// an `impl` of one of the `CC_OPERATOR_TRAITS` (e.g. `add_assign` from
+/// `impl AddAssign<i32> for Foo`).
+///
+/// Rust uses the `Not` trait both for the logical and the bitwise negation, and
+/// therefore `Not::not` is bound as `operator!` if it returns `bool`, and as
+/// `operator~` otherwise (e.g. for bit flags).
+fn get_cc_operator(tcx: TyCtxt, def_id: DefId) -> Option<&'static str> {
+    let trait_item_def_id = tcx.opt_associated_item(def_id)?.trait_item_def_id?;
+    let (trait_name, cc_operator) =
+        get_cc_operator_trait(tcx, tcx.trait_of_item(trait_item_def_id)?)?;
+    if trait_name == "Not" && !get_fn_sig(tcx, def_id).output().is_bool() {
+        Some("~")
+    } else {
+        Some(cc_operator)
+    }
+}
+
+/// The traits whose `impl`s are bound as C++ accessors that return a cheap
+/// view of the object (see `get_cc_view_accessor_name`).  For example, `impl
+/// AsRef<str> for Foo` is bound as `rs_std::Str as_str() const`.
+const CC_VIEW_TRAITS: &[&str] = &["std::convert::AsRef", "std::borrow::Borrow"];
+
+/// Returns whether `def_id` identifies a method from an `impl` of one of the
+/// `CC_VIEW_TRAITS` (e.g. `as_ref` from `impl AsRef<str> for Foo`).
+fn is_cc_view_trait_method(tcx: TyCtxt, def_id: DefId) -> bool {
+    tcx.opt_associated_item(def_id)
+        .and_then(|item| item.trait_item_def_id)
+        .and_then(|trait_item_def_id| tcx.trait_of_item(trait_item_def_id))
+        .map_or(false, |trait_def_id| is_cc_view_trait(tcx, trait_def_id))
+}
+
+fn is_cc_view_trait(tcx: TyCtxt, trait_def_id: DefId) -> bool {
+    CC_VIEW_TRAITS.iter().any(|std_path| is_std_item(tcx, trait_def_id, std_path))
+}
+
+/// Returns the C++ name of the accessor (e.g. `as_str`) if `def_id` identifies
+/// a method from an `impl` of one of the `CC_VIEW_TRAITS` that returns a view
+/// that C++ can represent:
+/// - `AsRef<str>` is bound as `as_str` (returning `rs_std::Str`),
+/// - `AsRef<[u8]>` is bound as `as_bytes` (returning
+///   `rs_std::Slice<std::uint8_t>`),
+/// - `AsRef<[T]>` is bound as `as_t_slice` (e.g. `as_i32_slice` or
+///   `as_some_struct_slice`).
+///
+/// Other views (e.g. `AsRef<SomeStruct>`) are not bound, because C++
+/// bindings can't return references yet.
+fn get_cc_view_accessor_name(tcx: TyCtxt, def_id: DefId) -> Option<String> {
+    if !is_cc_view_trait_method(tcx, def_id) {
+        return None;
+    }
+    let ty::TyKind::Ref(_, referent, Mutability::Not) = get_fn_sig(tcx, def_id).output().kind()
+    else {
+        return None;
+    };
+    let view_name = match referent.kind() {
+        ty::TyKind::Str => "str".to_string(),
+        ty::TyKind::Slice(elem_ty) => match elem_ty.kind() {
+            ty::TyKind::Uint(ty::UintTy::U8) => "bytes".to_string(),
+            ty::TyKind::Bool | ty::TyKind::Int(_) | ty::TyKind::Uint(_) | ty::TyKind::Float(_) => {
+                format!("{elem_ty}_slice")
+            }
+            ty::TyKind::Adt(adt, _) => {
+                let elem_name = convert_camel_case_to_snake_case(tcx.item_name(adt.did()).as_str());
+                format!("{elem_name}_slice")
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(format!("as_{view_name}"))
+}
+
+/// Converts `snake_case` into `CamelCase` - e.g. `get_len` into `GetLen` (and
+/// `get_2nd` into `Get2nd`).  Names that consist only of underscores are
+/// returned unchanged.
+fn convert_snake_case_to_camel_case(name: &str) -> String {
+    let camel_case: String = name
+        .split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_ascii_uppercase()).into_iter().chain(chars)
+        })
+        .collect();
+    if camel_case.is_empty() {
+        name.to_string()
+    } else {
+        camel_case
+    }
+}
+
+/// Converts `CamelCase` into `snake_case` - e.g. `SomeStruct` into
+/// `some_struct` (and `HTTPServer` into `h_t_t_p_server`).
+fn convert_camel_case_to_snake_case(name: &str) -> String {
+    let mut snake_case = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i != 0 {
+                snake_case.push('_');
+            }
+            snake_case.push(c.to_ascii_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+    snake_case
+}
+
 /// Represents the fully qualified name of a Rust item (e.g. of a `struct` or a
 /// function).
 struct FullyQualifiedName {
@@ -207,6 +1198,17 @@ struct FullyQualifiedName {
     /// * `Some("Ordering")` for `std::cmp::Ordering`.
     /// * `None` for `ItemKind::Use` - e.g.: `use submodule::*`
     name: Option<Symbol>,
+
+    /// Path to the C++ namespace (nested inside the top-level namespace of
+    /// the crate) of the item.  This is the same as `mod_path`, unless the item
+    /// or one of its parent modules has a `#[crubit::cc_namespace(...)]`
+    /// annotation.
+    cc_mod_path: NamespaceQualifier,
+
+    /// C++ name of the item.  This is the same as `name`, unless the item has a
+    /// `#[crubit::rename(...)]` annotation or unless the name had to be
+    /// disambiguated by `disambiguate_cc_name`.
+    cc_name: Option<Symbol>,
 }
 
 impl FullyQualifiedName {
@@ -214,7 +1216,8 @@ impl FullyQualifiedName {
     ///
     /// May panic if `def_id` is an invalid id.
     // TODO(b/259724276): This function's results should be memoized.
-    fn new(tcx: TyCtxt, def_id: DefId) -> Self {
+    fn new(input: &Input, def_id: DefId) -> Self {
+        let tcx = input.tcx;
         let krate = tcx.crate_name(def_id.krate);
 
         let mut full_path = tcx.def_path(def_id).data; // mod_path + name
@@ -228,19 +1231,76 @@ impl FullyQualifiedName {
                 .map(|s| Rc::<str>::from(s.as_str())),
         );
 
-        Self { krate, mod_path, name }
+        let cc_name = get_undisambiguated_cc_name(input, def_id)
+            .map(|cc_name| disambiguate_cc_name(input, def_id, cc_name));
+        let cc_mod_path = {
+            // Walking the parents from the outermost one, so that a `cc_namespace` of a module
+            // replaces the namespaces of the modules that contain it.
+            let mut parents = std::iter::successors(tcx.opt_parent(def_id), |&parent_id| {
+                tcx.opt_parent(parent_id)
+            })
+            .collect_vec();
+            parents.reverse();
+            let mut cc_mod_path: Vec<Rc<str>> = vec![];
+            for parent_id in parents.into_iter().chain(once(def_id)) {
+                if let Some(cc_namespace) = input.crubit_annotations(parent_id).cc_namespace.clone()
+                {
+                    cc_mod_path = cc_namespace.split("::").map(Rc::<str>::from).collect();
+                } else if parent_id != def_id {
+                    let parent_name = input
+                        .get_renamed_cc_name(parent_id)
+                        .or_else(|| tcx.def_key(parent_id).disambiguated_data.data.get_opt_name());
+                    cc_mod_path.extend(parent_name.map(|s| Rc::<str>::from(s.as_str())));
+                }
+            }
+            NamespaceQualifier::new(cc_mod_path)
+        };
+
+        Self { krate, mod_path, name, cc_mod_path, cc_name }
     }
 
-    fn format_for_cc(&self) -> Result<TokenStream> {
+    fn format_for_cc(&self, input: &Input) -> Result<TokenStream> {
         let name =
-            self.name.as_ref().expect("`format_for_cc` can't be called on name-less item kinds");
+            self.cc_name.as_ref().expect("`format_for_cc` can't be called on name-less item kinds");
 
-        let top_level_ns = format_cc_ident(self.krate.as_str())?;
-        let ns_path = self.mod_path.format_for_cc()?;
+        let top_level_ns = input.format_top_level_ns_for_cc(self.krate)?;
+        let ns_path = self.cc_mod_path.format_for_cc()?;
         let name = format_cc_ident(name.as_str())?;
         Ok(quote! { :: #top_level_ns :: #ns_path #name })
     }
 
+    /// Returns the name that `Input::c_mode` uses for the item - e.g.
+    /// `foo_bar_Baz` for `bar::Baz` from a crate with the `foo` top-level
+    /// namespace.  C doesn't support namespaces and therefore they are folded
+    /// into the name (avoiding collisions between items from different modules
+    /// or crates).
+    fn format_for_c(&self, input: &Input) -> String {
+        let name =
+            self.cc_name.as_ref().expect("`format_for_c` can't be called on name-less item kinds");
+
+        input
+            .get_top_level_ns(&self.krate)
+            .split("::")
+            .chain(self.cc_mod_path.parts().map(|part| part.as_ref()))
+            .chain(once(name.as_str()))
+            .join("_")
+    }
+
+    /// Returns the fully qualified Rust path of the item (e.g.
+    /// `::some_crate::some_module::SomeStruct`) as a string.
+    fn format_for_rs_as_string(&self) -> String {
+        let name = self
+            .name
+            .as_ref()
+            .expect("`format_for_rs_as_string` can't be called on name-less item kinds");
+
+        once(self.krate.as_str())
+            .chain(self.mod_path.parts().map(|part| part.as_ref()))
+            .chain(once(name.as_str()))
+            .map(|part| format!("::{}", make_rs_ident(part)))
+            .join("")
+    }
+
     fn format_for_rs(&self) -> TokenStream {
         let name =
             self.name.as_ref().expect("`format_for_cc` can't be called on name-less item kinds");
@@ -252,20 +1312,210 @@ impl FullyQualifiedName {
     }
 }
 
-fn format_ret_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
-    let void = Ok(CcSnippet::new(quote! { void }));
-    match ty.kind() {
-        ty::TyKind::Never => void,  // `!`
-        ty::TyKind::Tuple(types) if types.len() == 0 => void,  // `()`
-        _ => format_ty_for_cc(input, ty),
-    }
+/// Returns the C++ name of `def_id` before `disambiguate_cc_name` - i.e. the
+/// name from `Input::rename_map`, the name from the `#[crubit::rename(...)]`
+/// annotation, or the Rust name (escaped by `escape_cc_ident`, so that
+/// `disambiguate_cc_name` can detect collisions introduced by escaping).
+fn get_undisambiguated_cc_name(input: &Input, def_id: DefId) -> Option<Symbol> {
+    let tcx = input.tcx;
+    let annotations = input.crubit_annotations(def_id);
+    let cc_name = input
+        .get_renamed_cc_name(def_id)
+        .or(annotations.rename)
+        .or_else(|| tcx.def_key(def_id).disambiguated_data.data.get_opt_name())?;
+    Some(Symbol::intern(&escape_cc_ident(cc_name.as_str())))
 }
 
-/// Whether functions using `extern "C"` ABI can safely handle values of type
-/// `ty` (e.g. when passing by value arguments or return values of such type).
-fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
-    match ty.kind() {
-        // `improper_ctypes_definitions` warning doesn't complain about the following types:
+/// Returns a C++ name for `def_id` that doesn't collide with the C++ names of
+/// other public items from the same module and C++ namespace.
+///
+/// Rust allows a type and a function to have the same name (and
+/// `#[crubit::rename(...)]` can make any two items have the same C++ name),
+/// but in C++ such items would collide (e.g. a function hides a `struct` with
+/// the same name).  Colliding items are disambiguated deterministically:
+/// modules take precedence over types, types take precedence over functions,
+/// and otherwise items that come earlier in the source code take precedence.  All but the first
+/// of the colliding items get a `_<N>` suffix (or just `<N>` if the name
+/// already ends with `_`, so that the suffix doesn't form a reserved `__`
+/// identifier), with the smallest `N` that doesn't collide with another C++
+/// name.
+///
+/// TODO(b/259724276): This function's results should be memoized.
+/// TODO(b/262052635): Detect collisions between items from different modules
+/// (e.g. re-exports, or `#[crubit::cc_namespace(...)]` annotations).
+fn disambiguate_cc_name(input: &Input, def_id: DefId, cc_name: Symbol) -> Symbol {
+    let tcx = input.tcx;
+    let Some(local_def_id) = def_id.as_local() else { return cc_name };
+    let Some(parent_id) = tcx.opt_local_parent(local_def_id) else { return cc_name };
+    let item_ids = match tcx.hir().get_by_def_id(parent_id) {
+        Node::Crate(module) | Node::Item(Item { kind: ItemKind::Mod(module), .. }) => {
+            module.item_ids
+        }
+        _ => return cc_name, // E.g. methods inside an `impl`.
+    };
+
+    // Modules always keep their names, because a disambiguated namespace name would also
+    // need to be used by all the items inside the module.
+    let get_rank = |def_id: LocalDefId| match tcx.hir().expect_item(def_id).kind {
+        ItemKind::Mod(_) => Some(0),
+        ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..) => Some(1),
+        ItemKind::Fn(..) => Some(2),
+        _ => None,
+    };
+    let cc_namespace = input.crubit_annotations(def_id).cc_namespace.clone();
+    let siblings = item_ids
+        .iter()
+        .map(|item_id| item_id.owner_id.def_id)
+        .filter(|&sibling| tcx.effective_visibilities(()).is_directly_public(sibling))
+        .filter(|&sibling| {
+            input.crubit_annotations(sibling.to_def_id()).cc_namespace == cc_namespace
+        })
+        .filter_map(|sibling| {
+            let rank = get_rank(sibling)?;
+            let cc_name = get_undisambiguated_cc_name(input, sibling.to_def_id())?;
+            Some((sibling, rank, cc_name))
+        })
+        .collect_vec();
+
+    let mut taken_names: HashSet<Symbol> = siblings.iter().map(|(_, _, name)| *name).collect();
+    let colliding_ids = siblings
+        .iter()
+        .filter(|(_, _, name)| *name == cc_name)
+        .sorted_by_key(|(sibling, rank, _)| (*rank, tcx.def_span(*sibling)))
+        .map(|(sibling, _, _)| *sibling);
+    for (index, sibling) in colliding_ids.enumerate() {
+        let disambiguated_name = if index == 0 {
+            cc_name
+        } else {
+            (1..)
+                .map(|n| match cc_name.as_str().ends_with('_') {
+                    true => Symbol::intern(&format!("{cc_name}{n}")),
+                    false => Symbol::intern(&format!("{cc_name}_{n}")),
+                })
+                .find(|name| !taken_names.contains(name))
+                .unwrap()
+        };
+        if sibling == local_def_id {
+            return disambiguated_name;
+        }
+        taken_names.insert(disambiguated_name);
+    }
+
+    // `def_id` itself is typically one of the `siblings`, but it may also be an item that
+    // doesn't get C++ bindings (e.g. a private item).
+    cc_name
+}
+
+fn format_ret_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
+    let void = Ok(CcSnippet::new(quote! { void }));
+    match ty.kind() {
+        ty::TyKind::Never => void,  // `!`
+        ty::TyKind::Tuple(types) if types.len() == 0 => void,  // `()`
+        _ => match get_lock_guard_value_ty(input.tcx, ty) {
+            Some((value_ty, mutability)) => {
+                format_lock_guard_ty_for_cc(input, ty, value_ty, mutability)
+            }
+            None => format_param_ty_for_cc(input, ty),
+        },
+    }
+}
+
+/// Formats the return type `ty` of a lock guard that protects a `value_ty`
+/// (see `get_lock_guard_value_ty`).
+fn format_lock_guard_ty_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    ty: Ty<'tcx>,
+    value_ty: Ty<'tcx>,
+    mutability: Mutability,
+) -> Result<CcSnippet> {
+    if input.c_mode {
+        coded_bail!(
+            ErrorCode::CMode,
+            "`{ty}` is not supported by `--c-mode` (`rs_std::LockGuard` is a C++ class)"
+        );
+    }
+    coded_ensure!(
+        ErrorCode::MissingRustStdLib,
+        input.rust_std_lib >= RustStdLib::Std,
+        "`{ty}` is not supported without the `std` crate (see `--rust-std-lib`)"
+    );
+    let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, value_ty)
+        .with_context(|| format!("Failed to format the value protected by `{ty}`"))?;
+    prereqs.move_defs_to_fwd_decls();
+    prereqs.includes.insert(input.support_header("rs_std/rs_lock_guard.h"));
+    let tokens = match mutability {
+        Mutability::Mut => quote! { rs_std::LockGuard< #tokens > },
+        Mutability::Not => quote! { rs_std::LockGuard<const #tokens > },
+    };
+    Ok(CcSnippet { prereqs, tokens })
+}
+
+/// Formats the type of a function parameter.  In addition to the types
+/// supported by `format_ty_for_cc`, this covers the types that the generated
+/// thunks convert at the FFI boundary (e.g. `Cow<'_, str>`, `String`,
+/// `Vec<T>`, or `Box<T>` - see `RawPartsConversion`), and the types that the
+/// generated C++ functions convert into pre-existing C++ types (see
+/// `TypeMapping::conversion`).
+fn format_param_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
+    if let Some(mapping) = input.get_converted_type_mapping(ty) {
+        return format_mapped_type_for_cc(input, ty, mapping);
+    }
+    let tcx = input.tcx;
+    let (cc_class, header, type_arg) = if is_cow_str(tcx, ty) {
+        ("rs_std::CowStr", "rs_std/rs_cow_str.h", None)
+    } else if is_std_string(tcx, ty) {
+        ("rs_std::String", "rs_std/rs_string.h", None)
+    } else if let Some(elem_ty) = get_vec_elem_ty(tcx, ty) {
+        ("rs_std::Vec", "rs_std/rs_vec.h", Some(elem_ty))
+    } else if let Some(pointee_ty) = get_box_pointee_ty(tcx, ty) {
+        ("rs_std::Box", "rs_std/rs_box.h", Some(pointee_ty))
+    } else {
+        return format_ty_for_cc(input, ty);
+    };
+    if input.c_mode {
+        coded_bail!(
+            ErrorCode::CMode,
+            "`{ty}` is not supported by `--c-mode` (`{cc_class}` is a C++ class)"
+        );
+    }
+    coded_ensure!(
+        ErrorCode::MissingRustStdLib,
+        input.rust_std_lib >= RustStdLib::Alloc,
+        "`{ty}` is not supported without the `alloc` crate (see `--rust-std-lib`)"
+    );
+    let mut prereqs = CcPrerequisites::default();
+    prereqs.includes.insert(input.support_header(header));
+    let cc_class = cc_class.parse::<TokenStream>().expect("`cc_class` should be a valid C++ name");
+    let tokens = match type_arg {
+        None => cc_class,
+        Some(type_arg) => {
+            let type_arg = format_ty_for_cc(input, type_arg)
+                .with_context(|| format!("Failed to format the type argument of `{ty}`"))?
+                .into_tokens(&mut prereqs);
+            quote! { #cc_class< #type_arg > }
+        }
+    };
+    Ok(CcSnippet { prereqs, tokens })
+}
+
+/// Formats the type of a struct field.  In addition to the types supported by
+/// `format_ty_for_cc`, this covers `Box<T>` and `Option<Box<T>>` (see
+/// `get_boxed_field_pointee_ty`).
+fn format_field_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
+    let Some(pointee_ty) = get_boxed_field_pointee_ty(input.tcx, ty) else {
+        return format_ty_for_cc(input, ty);
+    };
+    let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, pointee_ty)
+        .with_context(|| format!("Failed to format the pointee of the `Box` type `{ty}`"))?;
+    prereqs.move_defs_to_fwd_decls();
+    Ok(CcSnippet { prereqs, tokens: quote! { #tokens* } })
+}
+
+/// Whether functions using `extern "C"` ABI can safely handle values of type
+/// `ty` (e.g. when passing by value arguments or return values of such type).
+fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
+    match ty.kind() {
+        // `improper_ctypes_definitions` warning doesn't complain about the following types:
         ty::TyKind::Bool |
         ty::TyKind::Float{..} |
         ty::TyKind::Int{..} |
@@ -297,6 +1547,23 @@ fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
         ty::TyKind::Tuple{..} |  // An empty tuple (`()` - the unit type) is handled above.
         ty::TyKind::Adt{..} => false,
 
+        // Slice references (and `&str`) are fat pointers which are not FFI-safe.  The thunks pass
+        // them by pointer instead (relying on the ABI assumptions spelled out in
+        // `rust_builtin_type_abi_assumptions.md`).
+        ty::TyKind::Ref(_, referent, _)
+            if matches!(referent.kind(), ty::TyKind::Slice(_) | ty::TyKind::Str) =>
+        {
+            false
+        }
+
+        // `&mut MaybeUninit<T>` is the only supported reference to a sized type (see
+        // `get_maybe_uninit_pointee_ty`) - it has the same ABI as a `*mut T` pointer.
+        ty::TyKind::Ref(_, referent, Mutability::Mut)
+            if matches!(referent.kind(), ty::TyKind::Adt(..)) =>
+        {
+            true
+        }
+
         // These kinds of reference-related types are not implemented yet - `is_c_abi_compatible_by_value`
         // should never need to handle them, because `format_ty_for_cc` fails for such types.
         //
@@ -319,29 +1586,251 @@ fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
     }
 }
 
+/// Returns the integer type wrapped by `ty` if `ty` is one of the `NonZero*`
+/// integer types from the standard library (e.g. `u32` for `NonZeroU32`).
+fn get_nonzero_int_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let ty::TyKind::Adt(adt, substs) = ty.kind() else { return None };
+    if tcx.crate_name(adt.did().krate) != rustc_span::symbol::sym::core
+        || !tcx.item_name(adt.did()).as_str().starts_with("NonZero")
+    {
+        return None;
+    }
+    let int_ty = adt.all_fields().exactly_one().ok()?.ty(tcx, substs);
+    matches!(int_ty.kind(), ty::TyKind::Int(_) | ty::TyKind::Uint(_)).then_some(int_ty)
+}
+
+/// Returns the `NonZero*` integer type wrapped by `ty` if `ty` is an `Option`
+/// of such type (e.g. `NonZeroU32` for `Option<NonZeroU32>`).
+fn get_optional_nonzero_int_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let ty::TyKind::Adt(adt, substs) = ty.kind() else { return None };
+    if !tcx.is_diagnostic_item(rustc_span::symbol::sym::Option, adt.did()) {
+        return None;
+    }
+    let nonzero_ty = substs.type_at(0);
+    get_nonzero_int_ty(tcx, nonzero_ty).map(|_| nonzero_ty)
+}
+
+/// Returns whether `def_id` identifies the item with the given `std_path`
+/// (e.g. `std::ptr::NonNull`) from the Rust standard library.  In `#![no_std]`
+/// crates `def_path_str` returns the path of the item in `core` or `alloc`
+/// (e.g. `core::ptr::NonNull`) - such paths also match, because `std`
+/// re-exports these items under the same path.
+fn is_std_item(tcx: TyCtxt, def_id: DefId, std_path: &str) -> bool {
+    let is_std_crate = |krate: &str| matches!(krate, "std" | "alloc" | "core");
+    if !is_std_crate(tcx.crate_name(def_id.krate).as_str()) {
+        return false;
+    }
+    let path = tcx.def_path_str(def_id);
+    match (path.split_once("::"), std_path.split_once("::")) {
+        (Some((krate, rest)), Some((_, std_rest))) => is_std_crate(krate) && rest == std_rest,
+        _ => false,
+    }
+}
+
+/// Returns the pointee type if `ty` is `std::ptr::NonNull<T>` (e.g. `i32` for
+/// `NonNull<i32>`).
+fn get_nonnull_pointee_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    match ty.kind() {
+        ty::TyKind::Adt(adt, substs) if is_std_item(tcx, adt.did(), "std::ptr::NonNull") => {
+            Some(substs.type_at(0))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `T` if `ty` is `&mut MaybeUninit<T>`, `*mut MaybeUninit<T>`, or
+/// `*const MaybeUninit<T>` (e.g. `i32` for `&mut MaybeUninit<i32>`).
+///
+/// `MaybeUninit<T>` has the same layout as `T`, and therefore such pointers
+/// are represented in C++ as `T*` (or `const T*`) pointers to storage for a
+/// `T` that may be uninitialized.  This supports out-parameters that the Rust
+/// function initializes (e.g. via `MaybeUninit::write`) - see also
+/// `format_maybe_uninit_note`.
+fn get_maybe_uninit_pointee_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let pointee_ty = match ty.kind() {
+        ty::TyKind::Ref(_, referent, Mutability::Mut) => *referent,
+        ty::TyKind::RawPtr(ty::TypeAndMut { ty, .. }) => *ty,
+        _ => return None,
+    };
+    match pointee_ty.kind() {
+        ty::TyKind::Adt(adt, substs) if is_std_item(tcx, adt.did(), "std::mem::MaybeUninit") => {
+            Some(substs.type_at(0))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the pointee type if `ty` is `Box<T>` or `Option<Box<T>>` (e.g.
+/// `Node` for `Option<Box<Node>>`), where `T` is `Sized` and the `Box` uses the
+/// global allocator.
+///
+/// Rust guarantees that such `Box<T>` has the same layout as a pointer, and
+/// that `Option<Box<T>>` represents `None` as a null pointer.  Therefore fields
+/// of these types can be represented in C++ as a plain `T*` pointer, which
+/// only needs a forward declaration of `T` - this is what makes it possible to
+/// bind recursive types like `struct Node { next: Option<Box<Node>> }`.  The
+/// fields are private in C++, and the pointee is owned (and dropped) by Rust -
+/// the generated C++ field is a plain pointer without ownership semantics, and
+/// therefore its comment warns that C++ code must not free or reassign it.
+fn get_boxed_field_pointee_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let box_ty = match ty.kind() {
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Option, adt.did()) =>
+        {
+            substs.type_at(0)
+        }
+        _ => ty,
+    };
+    get_box_pointee_ty(tcx, box_ty)
+}
+
+/// Returns the pointee type if `ty` is `Box<T>` (e.g. `i32` for `Box<i32>`),
+/// where `T` is `Sized` and the `Box` uses the global allocator.
+///
+/// Function parameters and return values of this type are represented in C++
+/// as `rs_std::Box<T>`, which owns the pointee.  The generated thunks pass the
+/// raw pointer (see `alloc_bridge::box_into_raw`).
+fn get_box_pointee_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let ty::TyKind::Adt(adt, substs) = ty.kind() else { return None };
+    if !adt.is_box() {
+        return None;
+    }
+    let is_global_alloc = match substs.type_at(1).kind() {
+        ty::TyKind::Adt(alloc, _) => is_std_item(tcx, alloc.did(), "std::alloc::Global"),
+        _ => false,
+    };
+    let pointee_ty = substs.type_at(0);
+    (is_global_alloc && pointee_ty.is_sized(tcx, ty::ParamEnv::empty())).then_some(pointee_ty)
+}
+
+/// Returns whether `ty` has interior mutability - i.e. whether it (directly or
+/// transitively) contains an `UnsafeCell` (e.g. via a `Cell`, `RefCell`, or
+/// `Mutex`), which means that Rust code may modify a value of `ty` through a
+/// shared reference.
+///
+/// C++ assumes that the objects are not modified through `const` references
+/// and pointers (and modifying a `const` object is Undefined Behavior), and
+/// therefore the bindings of such types:
+/// - represent all fields as an opaque blob of bytes (see `format_adt`),
+/// - bind `&self` methods as non-`const` member functions (see `format_fn`),
+/// - don't support `&[T]` slices of such types (see `format_ty_for_cc`).
+fn has_interior_mutability<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    !ty.is_freeze(tcx, ty::ParamEnv::empty())
+}
+
+/// Returns whether `ty` is `std::borrow::Cow<'_, str>`.
+///
+/// The layout of `Cow<'_, str>` is not guaranteed, and therefore the generated
+/// thunks convert it into `cow_bridge::CowStrRawParts` (see
+/// `crubit/support/rs_std/cow_bridge.rs`).
+fn is_cow_str(tcx: TyCtxt, ty: Ty) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt, substs) if is_std_item(tcx, adt.did(), "std::borrow::Cow") => {
+            substs.type_at(1).is_str()
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether `ty` is `std::string::String`.
+///
+/// The layout of `String` is not guaranteed, and therefore the generated
+/// thunks convert it into `alloc_bridge::StringRawParts` (see
+/// `crubit/support/rs_std/alloc_bridge.rs`).
+fn is_std_string(tcx: TyCtxt, ty: Ty) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt, _) => is_std_item(tcx, adt.did(), "std::string::String"),
+        _ => false,
+    }
+}
+
+/// Returns the element type if `ty` is `std::vec::Vec<T>` that uses the
+/// global allocator (e.g. `i32` for `Vec<i32>`).
+///
+/// The layout of `Vec<T>` is not guaranteed, and therefore the generated
+/// thunks convert it into `alloc_bridge::VecRawParts<T>` (see
+/// `crubit/support/rs_std/alloc_bridge.rs`).
+fn get_vec_elem_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let ty::TyKind::Adt(adt, substs) = ty.kind() else { return None };
+    if !is_std_item(tcx, adt.did(), "std::vec::Vec") {
+        return None;
+    }
+    match substs.type_at(1).kind() {
+        ty::TyKind::Adt(alloc, _) if is_std_item(tcx, alloc.did(), "std::alloc::Global") => {
+            Some(substs.type_at(0))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the type of the value protected by the lock, and the kind of
+/// access to the value, if `ty` is a lock guard from the standard library:
+/// - `MutexGuard<'_, T>` and `RwLockWriteGuard<'_, T>` provide exclusive
+///   access (`Mutability::Mut`),
+/// - `RwLockReadGuard<'_, T>` provides shared access (`Mutability::Not`).
+///
+/// Such guards are only supported as return types - the generated thunks
+/// convert them into `lock_bridge::LockGuardRawParts` (see
+/// `crubit/support/rs_std/lock_bridge.rs`), and the C++ side wraps the raw
+/// parts into an RAII `rs_std::LockGuard<T>`.
+fn get_lock_guard_value_ty<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+) -> Option<(Ty<'tcx>, Mutability)> {
+    let ty::TyKind::Adt(adt, substs) = ty.kind() else { return None };
+    let is_guard = |name| is_std_item(tcx, adt.did(), name);
+    let mutability = if is_guard("std::sync::MutexGuard") || is_guard("std::sync::RwLockWriteGuard")
+    {
+        Mutability::Mut
+    } else if is_guard("std::sync::RwLockReadGuard") {
+        Mutability::Not
+    } else {
+        return None;
+    };
+    Some((substs.type_at(0), mutability))
+}
+
 /// Formats `ty` into a `CcSnippet` that represents how the type should be
-/// spelled in a C++ declaration of a function parameter or field.
-//
-// TODO(b/259724276): This function's results should be memoized.
+/// spelled in a C++ declaration of a function parameter or field.  The
+/// results are memoized in `Input::interner`.
 fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
-    fn cstdint(tokens: TokenStream) -> CcSnippet {
-        CcSnippet::with_include(tokens, CcInclude::cstdint())
+    if let Some(snippet) = input.interner.cc_tys.borrow().get(&ty) {
+        return Ok(snippet.clone());
     }
+    let snippet = format_ty_for_cc_uncached(input, ty)?;
+    input.interner.cc_tys.borrow_mut().insert(ty, snippet.clone());
+    Ok(snippet)
+}
+
+fn format_ty_for_cc_uncached<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
+    let cstdint = |name: TokenStream| {
+        if input.c_mode {
+            CcSnippet::with_include(name, CcInclude::stdint_h())
+        } else {
+            CcSnippet::with_include(quote! { std::#name }, CcInclude::cstdint())
+        }
+    };
     fn keyword(tokens: TokenStream) -> CcSnippet {
         CcSnippet::new(tokens)
     }
     Ok(match ty.kind() {
         ty::TyKind::Never => {
             // TODO(b/254507801): Maybe translate into `crubit::Never`?
-            bail!("The never type `!` is only supported as a return type (b/254507801)");
+            coded_bail!(
+                ErrorCode::RestrictedPositionType,
+                "The never type `!` is only supported as a return type (b/254507801)"
+            );
         },
         ty::TyKind::Tuple(types) => {
             if types.len() == 0 {
                 // TODO(b/254507801): Maybe translate into `crubit::Unit`?
-                bail!("`()` / `void` is only supported as a return type (b/254507801)");
+                coded_bail!(
+                    ErrorCode::RestrictedPositionType,
+                    "`()` / `void` is only supported as a return type (b/254507801)"
+                );
             } else {
                 // TODO(b/254099023): Add support for tuples.
-                bail!("Tuples are not supported yet: {} (b/254099023)", ty);
+                coded_bail!(ErrorCode::Tuple, "Tuples are not supported yet: {ty} (b/254099023)");
             }
         }
 
@@ -349,6 +1838,9 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
         // that "Rust's bool has the same layout as C17's _Bool".  The details (e.g. size, valid
         // bit patterns) are implementation-defined, but this is okay, because `bool` in the
         // `extern "C"` functions in the generated `..._cc_api.h` will also be the C17's _Bool.
+        ty::TyKind::Bool if input.c_mode => {
+            CcSnippet::with_include(quote! { bool }, CcInclude::stdbool_h())
+        },
         ty::TyKind::Bool => keyword(quote! { bool }),
 
         // https://rust-lang.github.io/unsafe-code-guidelines/layout/scalars.html#fixed-width-floating-point-types
@@ -363,6 +1855,12 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
         // ABI compatibility and other details are described in the doc comments in
         // `crubit/support/rs_std/rs_char.h` and `crubit/support/rs_std/char_test.cc` (search for
         // "Layout tests").
+        ty::TyKind::Char if input.c_mode => {
+            coded_bail!(
+                ErrorCode::CMode,
+                "`char` is not supported by `--c-mode` (`rs_std::rs_char` is a C++ class)"
+            );
+        },
         ty::TyKind::Char => {
             // Asserting that the target architecture meets the assumption from Crubit's
             // `rust_builtin_type_abi_assumptions.md` - we assume that Rust's `char` has the same
@@ -395,20 +1893,20 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
         // documents that "Rust does not support C platforms on which the C native integer type are
         // not compatible with any of Rust's fixed-width integer type (e.g. because of
         // padding-bits, lack of 2's complement, etc.)."
-        ty::TyKind::Int(ty::IntTy::I8) => cstdint(quote!{ std::int8_t }),
-        ty::TyKind::Int(ty::IntTy::I16) => cstdint(quote!{ std::int16_t }),
-        ty::TyKind::Int(ty::IntTy::I32) => cstdint(quote!{ std::int32_t }),
-        ty::TyKind::Int(ty::IntTy::I64) => cstdint(quote!{ std::int64_t }),
-        ty::TyKind::Uint(ty::UintTy::U8) => cstdint(quote!{ std::uint8_t }),
-        ty::TyKind::Uint(ty::UintTy::U16) => cstdint(quote!{ std::uint16_t }),
-        ty::TyKind::Uint(ty::UintTy::U32) => cstdint(quote!{ std::uint32_t }),
-        ty::TyKind::Uint(ty::UintTy::U64) => cstdint(quote!{ std::uint64_t }),
+        ty::TyKind::Int(ty::IntTy::I8) => cstdint(quote!{ int8_t }),
+        ty::TyKind::Int(ty::IntTy::I16) => cstdint(quote!{ int16_t }),
+        ty::TyKind::Int(ty::IntTy::I32) => cstdint(quote!{ int32_t }),
+        ty::TyKind::Int(ty::IntTy::I64) => cstdint(quote!{ int64_t }),
+        ty::TyKind::Uint(ty::UintTy::U8) => cstdint(quote!{ uint8_t }),
+        ty::TyKind::Uint(ty::UintTy::U16) => cstdint(quote!{ uint16_t }),
+        ty::TyKind::Uint(ty::UintTy::U32) => cstdint(quote!{ uint32_t }),
+        ty::TyKind::Uint(ty::UintTy::U64) => cstdint(quote!{ uint64_t }),
 
         // https://rust-lang.github.io/unsafe-code-guidelines/layout/scalars.html#isize-and-usize
         // documents that "The isize and usize types are [...] layout compatible with C's uintptr_t
         // and intptr_t types.".
-        ty::TyKind::Int(ty::IntTy::Isize) => cstdint(quote!{ std::intptr_t }),
-        ty::TyKind::Uint(ty::UintTy::Usize) => cstdint(quote!{ std::uintptr_t }),
+        ty::TyKind::Int(ty::IntTy::Isize) => cstdint(quote!{ intptr_t }),
+        ty::TyKind::Uint(ty::UintTy::Usize) => cstdint(quote!{ uintptr_t }),
 
         ty::TyKind::Int(ty::IntTy::I128) | ty::TyKind::Uint(ty::UintTy::U128) => {
             // Note that "the alignment of Rust's {i,u}128 is unspecified and allowed to
@@ -417,41 +1915,145 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
             //
             // TODO(b/254094650): Consider mapping this to Clang's (and GCC's) `__int128`
             // or to `absl::in128`.
-            bail!("C++ doesn't have a standard equivalent of `{ty}` (b/254094650)");
+            coded_bail!(
+                ErrorCode::UnsupportedType,
+                "C++ doesn't have a standard equivalent of `{ty}` (b/254094650)"
+            );
+        }
+
+        // ABI compatibility and other details are described in `rust_builtin_type_abi_assumptions.md`
+        // and in the doc comments in `crubit/support/rs_std/rs_nonzero.h`.
+        ty::TyKind::Adt(..) if get_nonzero_int_ty(input.tcx, ty).is_some() => {
+            if input.c_mode {
+                coded_bail!(
+                    ErrorCode::CMode,
+                    "`{ty}` is not supported by `--c-mode` (`rs_std::NonZero` is a C++ class)"
+                );
+            }
+            let int_ty = get_nonzero_int_ty(input.tcx, ty).unwrap();
+            let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, int_ty)?;
+            prereqs.includes.insert(input.support_header("rs_std/rs_nonzero.h"));
+            CcSnippet { prereqs, tokens: quote! { rs_std::NonZero< #tokens > } }
+        }
+
+        // Rust guarantees that `Option<NonZeroU32>` has the same layout as `u32` (with 0
+        // representing `None`).
+        ty::TyKind::Adt(..) if get_optional_nonzero_int_ty(input.tcx, ty).is_some() => {
+            let nonzero_ty = get_optional_nonzero_int_ty(input.tcx, ty).unwrap();
+            let int_ty = get_nonzero_int_ty(input.tcx, nonzero_ty).unwrap();
+            format_ty_for_cc(input, int_ty)?
+        }
+
+        // ABI compatibility and other details are described in `rust_builtin_type_abi_assumptions.md`
+        // and in the doc comments in `crubit/support/rs_std/rs_nonnull.h`.
+        ty::TyKind::Adt(..) if get_nonnull_pointee_ty(input.tcx, ty).is_some() => {
+            if input.c_mode {
+                coded_bail!(
+                    ErrorCode::CMode,
+                    "`{ty}` is not supported by `--c-mode` (`rs_std::NonNull` is a C++ template)"
+                );
+            }
+            let pointee_ty = get_nonnull_pointee_ty(input.tcx, ty).unwrap();
+            let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, pointee_ty)
+                .with_context(|| {
+                    format!("Failed to format the pointee of the `NonNull` type `{ty}`")
+                })?;
+            prereqs.move_defs_to_fwd_decls();
+            prereqs.includes.insert(input.support_header("rs_std/rs_nonnull.h"));
+            CcSnippet { prereqs, tokens: quote! { rs_std::NonNull< #tokens > } }
+        }
+
+        // `format_fn` handles `Cow<str>`, `String`, `Vec<T>`, and `Box<T>` parameters and return
+        // values via `format_param_ty_for_cc`.
+        ty::TyKind::Adt(..)
+            if is_cow_str(input.tcx, ty)
+                || is_std_string(input.tcx, ty)
+                || get_vec_elem_ty(input.tcx, ty).is_some()
+                || get_box_pointee_ty(input.tcx, ty).is_some() =>
+        {
+            coded_bail!(
+                ErrorCode::RestrictedPositionType,
+                "`{ty}` is only supported as a type of function parameters and return values"
+            )
+        }
+
+        // `format_fn` handles lock guards returned by functions via `format_ret_ty_for_cc`.
+        ty::TyKind::Adt(..) if get_lock_guard_value_ty(input.tcx, ty).is_some() => {
+            coded_bail!(
+                ErrorCode::RestrictedPositionType,
+                "`{ty}` is only supported as a return type"
+            )
         }
 
         ty::TyKind::Adt(adt, substs) => {
-            ensure!(substs.len() == 0, "Generic types are not supported yet (b/259749095)");
-            ensure!(
+            coded_ensure!(
+                ErrorCode::GenericType,
+                substs.len() == 0,
+                "Generic types are not supported yet (b/259749095)"
+            );
+            coded_ensure!(
+                ErrorCode::ReExportedType,
                 is_directly_public(input.tcx, adt.did()),
-                "Not directly public type (re-exports are not supported yet - b/262052635)");
+                "Not directly public type (re-exports are not supported yet - b/262052635)"
+            );
 
             let def_id = adt.did();
             let mut prereqs = CcPrerequisites::default();
+            if let Some(cpp_type) = input.crubit_annotations(def_id).cpp_type.clone() {
+                return format_cpp_type_for_cc(input, ty, def_id, &cpp_type);
+            }
+            if let Some(mapping) = input.get_type_mapping(def_id) {
+                if mapping.conversion.is_none() {
+                    return format_mapped_type_for_cc(input, ty, mapping);
+                }
+            }
             if def_id.krate == LOCAL_CRATE {
+                coded_ensure!(
+                    ErrorCode::ExcludedType,
+                    input.is_selected(def_id.expect_local()),
+                    "Type `{ty}` is excluded from the bindings by `--bind-only` or `--skip`"
+                );
                 prereqs.defs.insert(def_id.expect_local());
             } else {
-                // TODO(b/258261328): Add `#include` of other crate's `..._cc_api.h`.
-                bail!("Cross-crate dependencies are not supported yet (b/258261328)");
+                let other_crate_name = input.tcx.crate_name(def_id.krate);
+                let include = input
+                    .crate_name_to_include_path
+                    .get(other_crate_name.as_str())
+                    .ok_or_else(|| {
+                        coded_anyhow!(
+                            ErrorCode::MissingCrateHeader,
+                            "Type `{ty}` comes from the `{other_crate_name}` crate, \
+                             but no `--crate-header` was specified for this crate"
+                        )
+                    })?;
+                prereqs.includes.insert(include.clone());
             };
 
             // Verify if definition of `ty` can be succesfully imported and bail otherwise.
-            format_adt_core(input.tcx, def_id)
+            let core = format_adt_core(input, def_id)
                 .with_context(|| format!(
                         "Failed to generate bindings for the definition of `{ty}`"))?;
-
-            CcSnippet {
-                tokens: FullyQualifiedName::new(input.tcx, def_id).format_for_cc()?,
-                prereqs
+            if core.enum_underlying_type.is_some() {
+                // Forward declarations of a C++ `enum class` spell out its underlying type.
+                prereqs.includes.insert(CcInclude::cstdint());
             }
+
+            let fully_qualified_name = FullyQualifiedName::new(input, def_id);
+            let tokens = if input.c_mode {
+                format_cc_ident(&fully_qualified_name.format_for_c(input))?
+            } else {
+                fully_qualified_name.format_for_cc(input)?
+            };
+            CcSnippet { tokens, prereqs }
         },
 
-        ty::TyKind::RawPtr(ty::TypeAndMut{ty, mutbl}) => {
+        ty::TyKind::RawPtr(ty::TypeAndMut{ty: pointee_ty, mutbl}) => {
             let const_qualifier = match mutbl {
                 Mutability::Mut => quote!{},
                 Mutability::Not => quote!{ const },
             };
-            let CcSnippet{ tokens, mut prereqs } = format_ty_for_cc(input, *ty)
+            let pointee_ty = get_maybe_uninit_pointee_ty(input.tcx, ty).unwrap_or(*pointee_ty);
+            let CcSnippet{ tokens, mut prereqs } = format_ty_for_cc(input, pointee_ty)
                 .with_context(|| format!(
                         "Failed to format the pointee of the pointer type `{ty}`"))?;
             prereqs.move_defs_to_fwd_decls();
@@ -461,15 +2063,265 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
             }
         },
 
+        // `&mut MaybeUninit<T>` is a (non-null) pointer to storage for a `T` that may be
+        // uninitialized (see `get_maybe_uninit_pointee_ty`).
+        ty::TyKind::Ref(..) if get_maybe_uninit_pointee_ty(input.tcx, ty).is_some() => {
+            let pointee_ty = get_maybe_uninit_pointee_ty(input.tcx, ty).unwrap();
+            let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, pointee_ty)
+                .with_context(|| {
+                    format!("Failed to format the pointee of the reference type `{ty}`")
+                })?;
+            prereqs.move_defs_to_fwd_decls();
+            CcSnippet { prereqs, tokens: quote! { #tokens * } }
+        }
+
+        // ABI compatibility and other details are described in `rust_builtin_type_abi_assumptions.md`
+        // and in the doc comments in `crubit/support/rs_std/rs_slice.h`.
+        ty::TyKind::Ref(_, referent, mutbl) if matches!(referent.kind(), ty::TyKind::Slice(_)) => {
+            if input.c_mode {
+                coded_bail!(
+                    ErrorCode::CMode,
+                    "`{ty}` is not supported by `--c-mode` (`rs_std::Slice` is a C++ class)"
+                );
+            }
+            let ty::TyKind::Slice(elem_ty) = referent.kind() else { unreachable!() };
+            coded_ensure!(
+                ErrorCode::InteriorMutability,
+                *mutbl == Mutability::Mut || !has_interior_mutability(input.tcx, *elem_ty),
+                "`{ty}` is not supported, because `{elem_ty}` has interior mutability \
+                 (`rs_std::Slice` only provides `const` access to the elements)"
+            );
+            let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, *elem_ty)
+                .with_context(|| {
+                    format!("Failed to format the element type of the slice type `{ty}`")
+                })?;
+            prereqs.move_defs_to_fwd_decls();
+            prereqs.includes.insert(input.support_header("rs_std/rs_slice.h"));
+            let slice = match mutbl {
+                Mutability::Mut => quote! { rs_std::MutSlice },
+                Mutability::Not => quote! { rs_std::Slice },
+            };
+            CcSnippet { prereqs, tokens: quote! { #slice < #tokens > } }
+        }
+
+        // ABI compatibility and other details are described in `rust_builtin_type_abi_assumptions.md`
+        // and in the doc comments in `crubit/support/rs_std/rs_str.h`.
+        ty::TyKind::Ref(_, referent, Mutability::Not) if referent.is_str() => {
+            if input.c_mode {
+                coded_bail!(
+                    ErrorCode::CMode,
+                    "`{ty}` is not supported by `--c-mode` (`rs_std::Str` is a C++ class)"
+                );
+            }
+            let mut prereqs = CcPrerequisites::default();
+            prereqs.includes.insert(input.support_header("rs_std/rs_str.h"));
+            CcSnippet { prereqs, tokens: quote! { rs_std::Str } }
+        }
+
         // TODO(b/260268230, b/260729464): When recursively processing nested types (e.g. an
         // element type of an Array, a referent of a Ref, a parameter type of an FnPtr, etc), one
         // should also 1) propagate `CcPrerequisites::defs`, 2) cover `CcPrerequisites::defs` in
         // `test_format_ty_for_cc...`.  For ptr/ref it might be possible to use
         // `CcPrerequisites::move_defs_to_fwd_decls`.
-        _ => bail!("The following Rust type is not supported yet: {ty}"),
+        _ => coded_bail!(
+            ErrorCode::UnsupportedType,
+            "The following Rust type is not supported yet: {ty}"
+        ),
     })
 }
 
+/// Formats `ty` (the Rust binding of the C++ type `cpp_type`, generated by
+/// `rs_bindings_from_cc`) for C++.  The original C++ type is used, so that the
+/// type keeps its identity when it makes a round trip through both tools.
+fn format_cpp_type_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    ty: Ty<'tcx>,
+    def_id: DefId,
+    cpp_type: &str,
+) -> Result<CcSnippet> {
+    coded_ensure!(
+        ErrorCode::CMode,
+        !input.c_mode,
+        "`{ty}` is a C++ type and can't be used in `--c-mode`"
+    );
+    ensure_crubit_features(input, CrubitFeature::Experimental.into())?;
+
+    // The values are memcpy-ed by the thunks (see `format_fn`), which is only
+    // sound if the C++ type is trivially relocatable.
+    let param_env = ty::ParamEnv::empty();
+    ensure!(
+        ty.is_unpin(input.tcx, param_env),
+        "Non-`Unpin` C++ types (e.g. `{cpp_type}`) are not supported yet"
+    );
+
+    let tokens = cpp_type
+        .parse::<TokenStream>()
+        .map_err(|err| anyhow!("Invalid `cpp_type` annotation of `{ty}`: {err}"))?;
+    let mut prereqs = CcPrerequisites::default();
+    let crate_root_annotations = input.crubit_annotations(def_id.krate.as_def_id());
+    ensure!(
+        !crate_root_annotations.cpp_headers.is_empty(),
+        "The C++ type `{cpp_type}` comes from the `{}` crate, \
+         which doesn't have any `cpp_header` annotations",
+        input.tcx.crate_name(def_id.krate)
+    );
+    for header in crate_root_annotations.cpp_headers.iter() {
+        prereqs.includes.insert(CcInclude::user_header(header.clone()));
+    }
+    Ok(CcSnippet { tokens, prereqs })
+}
+
+/// Formats `ty` as the pre-existing C++ type from its `mapping` (see
+/// `Input::type_map`).
+fn format_mapped_type_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    ty: Ty<'tcx>,
+    mapping: &TypeMapping,
+) -> Result<CcSnippet> {
+    coded_ensure!(
+        ErrorCode::CMode,
+        !input.c_mode,
+        "`{ty}` is mapped to a C++ type and can't be used in `--c-mode`"
+    );
+    if mapping.conversion.is_none() {
+        // The values are memcpy-ed by the thunks (see `format_fn`), which is only
+        // sound if the C++ type is trivially relocatable.
+        ensure!(
+            ty.is_unpin(input.tcx, ty::ParamEnv::empty()),
+            "Non-`Unpin` types can't be mapped to C++ types without a conversion: `{ty}`"
+        );
+    }
+    let cc_type = &*mapping.cc_type;
+    let tokens = cc_type.parse::<TokenStream>().map_err(|err| {
+        anyhow!("Invalid C++ type `{cc_type}` in the type map entry of `{ty}`: {err}")
+    })?;
+    let mut prereqs = CcPrerequisites::default();
+    for header in mapping.cc_headers.iter() {
+        prereqs.includes.insert(CcInclude::user_header(header.clone()));
+    }
+    Ok(CcSnippet { tokens, prereqs })
+}
+
+/// A parameter or a return type that is converted at the boundary between C++
+/// and Rust, rather than passed to (or returned from) the thunks as-is.
+enum ConvertedType {
+    /// The generated C++ function converts between a pre-existing C++ type and
+    /// the C++ bindings of the Rust type (see `TypeMapping::conversion`).  The
+    /// thunks pass the C++ bindings of the Rust type as usual.
+    Mapped { cc_binding_type: TokenStream, to_cc: TokenStream, from_cc: TokenStream },
+
+    /// The thunks pass the raw parts of the value (see `RawPartsConversion`).
+    RawParts(RawPartsConversion),
+}
+
+/// Conversion of a type whose layout is not guaranteed by Rust (e.g. of
+/// `String`).  The generated Rust thunks convert the Rust value into
+/// C-ABI-compatible raw parts (and back), and the generated C++ function
+/// converts the raw parts into a C++ class from `rs_std` (e.g. into
+/// `rs_std::String`) with `from_raw_parts`, or converts the C++ class back into
+/// the raw parts with `std::move(value).release()`.
+struct RawPartsConversion {
+    /// C++ type of the raw parts (e.g. `rs_std::String::RawParts`).
+    cc_raw_parts_type: TokenStream,
+
+    /// C++ function that takes the raw parts and returns the C++ value (e.g.
+    /// `rs_std::String::from_raw_parts`).
+    cc_from_raw_parts: TokenStream,
+
+    /// Rust type of the raw parts (e.g. `::alloc_bridge::StringRawParts`).
+    rs_raw_parts_type: TokenStream,
+
+    /// Rust function that takes the Rust value and returns its raw parts (e.g.
+    /// `::alloc_bridge::StringRawParts::from_string`).
+    rs_into_raw_parts: TokenStream,
+
+    /// `unsafe` Rust function that takes the raw parts and returns the Rust
+    /// value (e.g. `::alloc_bridge::StringRawParts::into_string`).  `None` for
+    /// types that are only supported as return types (e.g. lock guards).
+    rs_from_raw_parts: Option<TokenStream>,
+}
+
+impl ConvertedType {
+    /// Returns how the generated bindings convert the parameter or the return
+    /// type `ty`, or `None` if `ty` doesn't need a conversion.  Callers should
+    /// first format the C++ type of the parameter or of the return value (see
+    /// `format_param_ty_for_cc` and `format_ret_ty_for_cc`), which rejects the
+    /// conversions that are not supported in the given position.
+    fn new<'tcx>(
+        input: &Input<'tcx>,
+        ty: Ty<'tcx>,
+        prereqs: &mut CcPrerequisites,
+    ) -> Result<Option<Self>> {
+        let tcx = input.tcx;
+        if let Some(TypeMapping { conversion: Some(conversion), .. }) =
+            input.get_converted_type_mapping(ty)
+        {
+            let parse_fn_name = |name: &str| {
+                name.parse::<TokenStream>()
+                    .map_err(|err| anyhow!("Invalid C++ function name `{name}` for `{ty}`: {err}"))
+            };
+            let to_cc = parse_fn_name(&conversion.to_cc)?;
+            let from_cc = parse_fn_name(&conversion.from_cc)?;
+            let cc_binding_type = format_ty_for_cc(input, ty)?.into_tokens(prereqs);
+            return Ok(Some(Self::Mapped { cc_binding_type, to_cc, from_cc }));
+        }
+        let raw_parts = if is_cow_str(tcx, ty) {
+            RawPartsConversion {
+                cc_raw_parts_type: quote! { rs_std::internal::CowStrRawParts },
+                cc_from_raw_parts: quote! { rs_std::CowStr::from_raw_parts },
+                rs_raw_parts_type: quote! { ::cow_bridge::CowStrRawParts },
+                rs_into_raw_parts: quote! { ::cow_bridge::CowStrRawParts::from_cow },
+                rs_from_raw_parts: Some(quote! { ::cow_bridge::CowStrRawParts::into_cow }),
+            }
+        } else if is_std_string(tcx, ty) {
+            RawPartsConversion {
+                cc_raw_parts_type: quote! { rs_std::String::RawParts },
+                cc_from_raw_parts: quote! { rs_std::String::from_raw_parts },
+                rs_raw_parts_type: quote! { ::alloc_bridge::StringRawParts },
+                rs_into_raw_parts: quote! { ::alloc_bridge::StringRawParts::from_string },
+                rs_from_raw_parts: Some(quote! { ::alloc_bridge::StringRawParts::into_string }),
+            }
+        } else if let Some(elem_ty) = get_vec_elem_ty(tcx, ty) {
+            let cc_elem_ty = format_ty_for_cc(input, elem_ty)?.into_tokens(prereqs);
+            let rs_elem_ty = format_ty_for_rs(input, elem_ty)?;
+            RawPartsConversion {
+                cc_raw_parts_type: quote! { rs_std::Vec< #cc_elem_ty >::RawParts },
+                cc_from_raw_parts: quote! { rs_std::Vec< #cc_elem_ty >::from_raw_parts },
+                rs_raw_parts_type: quote! { ::alloc_bridge::VecRawParts<#rs_elem_ty> },
+                rs_into_raw_parts: quote! { ::alloc_bridge::VecRawParts::from_vec },
+                rs_from_raw_parts: Some(quote! { ::alloc_bridge::VecRawParts::into_vec }),
+            }
+        } else if let Some(pointee_ty) = get_box_pointee_ty(tcx, ty) {
+            let cc_pointee_ty = format_ty_for_cc(input, pointee_ty)?.into_tokens(prereqs);
+            let rs_pointee_ty = format_ty_for_rs(input, pointee_ty)?;
+            RawPartsConversion {
+                cc_raw_parts_type: quote! { #cc_pointee_ty* },
+                cc_from_raw_parts: quote! { rs_std::Box< #cc_pointee_ty >::from_raw },
+                rs_raw_parts_type: quote! { *mut #rs_pointee_ty },
+                rs_into_raw_parts: quote! { ::alloc_bridge::box_into_raw },
+                rs_from_raw_parts: Some(quote! { ::alloc_bridge::box_from_raw }),
+            }
+        } else if let Some((value_ty, mutability)) = get_lock_guard_value_ty(tcx, ty) {
+            let cc_type =
+                format_lock_guard_ty_for_cc(input, ty, value_ty, mutability)?.into_tokens(prereqs);
+            let rs_into_raw_parts = match mutability {
+                Mutability::Mut => quote! { ::lock_bridge::LockGuardRawParts::new_mut },
+                Mutability::Not => quote! { ::lock_bridge::LockGuardRawParts::new },
+            };
+            RawPartsConversion {
+                cc_raw_parts_type: quote! { rs_std::internal::LockGuardRawParts },
+                cc_from_raw_parts: quote! { #cc_type::from_raw_parts },
+                rs_raw_parts_type: quote! { ::lock_bridge::LockGuardRawParts },
+                rs_into_raw_parts,
+                rs_from_raw_parts: None,
+            }
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Self::RawParts(raw_parts)))
+    }
+}
+
 /// Formats `ty` for Rust - to be used in `..._cc_api_impl.rs` (e.g. as a type
 /// of a parameter in a Rust thunk).  Because `..._cc_api_impl.rs` is a
 /// distinct, separate crate, the returned `TokenStream` uses crate-qualified
@@ -477,7 +2329,7 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
 /// than just `SomeStruct`.
 //
 // TODO(b/259724276): This function's results should be memoized.
-fn format_ty_for_rs(tcx: TyCtxt, ty: Ty) -> Result<TokenStream> {
+fn format_ty_for_rs(input: &Input, ty: Ty) -> Result<TokenStream> {
     Ok(match ty.kind() {
         ty::TyKind::Bool
         | ty::TyKind::Float(_)
@@ -493,24 +2345,76 @@ fn format_ty_for_rs(tcx: TyCtxt, ty: Ty) -> Result<TokenStream> {
                 quote! { () }
             } else {
                 // TODO(b/254099023): Add support for tuples.
-                bail!("Tuples are not supported yet: {} (b/254099023)", ty);
+                coded_bail!(ErrorCode::Tuple, "Tuples are not supported yet: {ty} (b/254099023)");
             }
         }
+        ty::TyKind::Adt(adt, _) if get_nonzero_int_ty(input.tcx, ty).is_some() => {
+            // `NonZero*` types are defined in a private module of `core`.
+            let name = make_rs_ident(input.tcx.item_name(adt.did()).as_str());
+            quote! { ::core::num::#name }
+        }
+        ty::TyKind::Adt(..) if get_optional_nonzero_int_ty(input.tcx, ty).is_some() => {
+            let nonzero_ty = get_optional_nonzero_int_ty(input.tcx, ty).unwrap();
+            let nonzero_ty = format_ty_for_rs(input, nonzero_ty)?;
+            quote! { ::core::option::Option<#nonzero_ty> }
+        }
+        ty::TyKind::Adt(..) if get_nonnull_pointee_ty(input.tcx, ty).is_some() => {
+            let pointee_ty = get_nonnull_pointee_ty(input.tcx, ty).unwrap();
+            let pointee_ty = format_ty_for_rs(input, pointee_ty)?;
+            quote! { ::core::ptr::NonNull<#pointee_ty> }
+        }
+        ty::TyKind::Adt(adt, substs)
+            if is_std_item(input.tcx, adt.did(), "std::mem::MaybeUninit") =>
+        {
+            let value_ty = format_ty_for_rs(input, substs.type_at(0))?;
+            quote! { ::core::mem::MaybeUninit<#value_ty> }
+        }
         ty::TyKind::Adt(adt, substs) => {
-            ensure!(substs.len() == 0, "Generic types are not supported yet (b/259749095)");
-            FullyQualifiedName::new(tcx, adt.did()).format_for_rs()
+            coded_ensure!(
+                ErrorCode::GenericType,
+                substs.len() == 0,
+                "Generic types are not supported yet (b/259749095)"
+            );
+            FullyQualifiedName::new(input, adt.did()).format_for_rs()
         },
         ty::TyKind::RawPtr(ty::TypeAndMut{ty, mutbl}) => {
             let qualifier = match mutbl {
                 Mutability::Mut => quote!{ mut },
                 Mutability::Not => quote!{ const },
             };
-            let ty = format_ty_for_rs(tcx, *ty)
+            let ty = format_ty_for_rs(input, *ty)
                 .with_context(|| format!(
                         "Failed to format the pointee of the pointer type `{ty}`"))?;
             quote!{ * #qualifier #ty }
         },
-        _ => bail!("The following Rust type is not supported yet: {ty}"),
+        ty::TyKind::Ref(_, referent, mutbl) if matches!(referent.kind(), ty::TyKind::Slice(_)) => {
+            let ty::TyKind::Slice(elem_ty) = referent.kind() else { unreachable!() };
+            let qualifier = match mutbl {
+                Mutability::Mut => quote! { mut },
+                Mutability::Not => quote! {},
+            };
+            let elem_ty = format_ty_for_rs(input, *elem_ty).with_context(|| {
+                format!("Failed to format the element type of the slice type `{ty}`")
+            })?;
+            // The thunks are only called from C++ (which doesn't track lifetimes), and therefore
+            // the (erased - see `get_fn_sig`) lifetimes of the references are spelled as `'static`.
+            quote! { &'static #qualifier [#elem_ty] }
+        }
+        ty::TyKind::Ref(_, referent, Mutability::Not) if referent.is_str() => {
+            // See the comment about `'static` lifetimes of slice references above.
+            quote! { &'static str }
+        }
+        ty::TyKind::Ref(_, referent, Mutability::Mut)
+            if get_maybe_uninit_pointee_ty(input.tcx, ty).is_some() =>
+        {
+            // See the comment about `'static` lifetimes of slice references above.
+            let referent = format_ty_for_rs(input, *referent)?;
+            quote! { &'static mut #referent }
+        }
+        _ => coded_bail!(
+            ErrorCode::UnsupportedType,
+            "The following Rust type is not supported yet: {ty}"
+        ),
     })
 }
 
@@ -545,18 +2449,68 @@ fn preferred_snippet_order(tcx: TyCtxt) -> impl Fn(&SnippetKey, &SnippetKey) ->
 
 /// A C++ snippet (e.g. function declaration for `..._cc_api.h`) and a Rust
 /// snippet (e.g. a thunk definition for `..._cc_api_impl.rs`).
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 struct MixedSnippet {
     cc: CcSnippet,
     rs: TokenStream,
+
+    /// A C++ snippet for the companion `.cc` file (e.g. layout assertions).
+    /// Only used when `Input::cc_out_h_include` is present.
+    cc_out: TokenStream,
 }
 
 impl From<CcSnippet> for MixedSnippet {
     fn from(cc: CcSnippet) -> Self {
-        Self { cc, rs: quote! {} }
+        Self { cc, ..Default::default() }
+    }
+}
+
+/// Returns the signature of the function with the given `def_id`.
+///
+/// Late-bound lifetimes (e.g. the elided lifetimes of references - `fn foo(x:
+/// &[i32])` is really `fn foo<'a>(x: &'a [i32])`) are erased, because the C++
+/// bindings don't depend on them.  Generic functions (including functions with
+/// early-bound lifetime parameters) are expected to be rejected by the caller.
+///
+/// The associated types in the signatures of non-generic functions are
+/// normalized - e.g. `Self::Output` in `fn neg(self) -> Self::Output` from
+/// `impl Neg for Foo` becomes `Foo` (if `type Output = Foo;`).
+fn get_fn_sig(tcx: TyCtxt, def_id: DefId) -> ty::FnSig {
+    let sig = rustc_compat::erase_late_bound_regions(tcx, rustc_compat::fn_sig(tcx, def_id));
+    if sig.has_projections() && tcx.generics_of(def_id).count() == 0 {
+        tcx.normalize_erasing_regions(ty::ParamEnv::empty(), sig)
+    } else {
+        sig
     }
 }
 
+/// Returns the indices of the parameters of the function `def_id` (including
+/// the `self` parameter) that are borrowed by the return value - i.e. the
+/// parameters that share a lifetime with the return type (after lifetime
+/// elision), like `s` in `fn first_mut(s: &mut [i32]) -> &mut i32`.
+fn get_lifetime_bound_params(tcx: TyCtxt, def_id: DefId) -> HashSet<usize> {
+    // `get_fn_sig` erases the late-bound regions, which are needed below.
+    let sig = rustc_compat::fn_sig(tcx, def_id).skip_binder();
+    let get_regions = |ty: Ty| {
+        ty.walk()
+            .filter_map(|arg| match arg.unpack() {
+                ty::GenericArgKind::Lifetime(region) if !region.is_static() => Some(region),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+    };
+    let output_regions = get_regions(sig.output());
+    if output_regions.is_empty() {
+        return HashSet::new();
+    }
+    sig.inputs()
+        .iter()
+        .enumerate()
+        .filter(|(_, &ty)| !get_regions(ty).is_disjoint(&output_regions))
+        .map(|(i, _)| i)
+        .collect()
+}
+
 /// Formats a function with the given `local_def_id`.
 ///
 /// Returns multiple snippets, so that a function declaration can be emitted
@@ -580,14 +2534,12 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
     let tcx = input.tcx;
     let def_id: DefId = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
 
-    ensure!(
+    coded_ensure!(
+        ErrorCode::GenericFunction,
         tcx.generics_of(def_id).count() == 0,
         "Generic functions are not supported yet (b/259749023)"
     );
-    let sig = match tcx.fn_sig(def_id).subst_identity().no_bound_vars() {
-        None => bail!("Generic functions are not supported yet (b/259749023)"),
-        Some(sig) => sig,
-    };
+    let sig = get_fn_sig(tcx, def_id);
 
     let mut symbol_name = {
         // Call to `mono` is ok - `generics_of` have been checked above.
@@ -597,15 +2549,20 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
 
     if sig.c_variadic {
         // TODO(b/254097223): Add support for variadic functions.
-        bail!("C variadic functions are not supported (b/254097223)");
+        coded_bail!(
+            ErrorCode::CVariadicFunction,
+            "C variadic functions are not supported (b/254097223)"
+        );
     }
 
-    match sig.unsafety {
-        Unsafety::Normal => (),
-        Unsafety::Unsafe => {
-            // TODO(b/254095482): Figure out how to handle `unsafe` functions.
-            bail!("Bindings for `unsafe` functions are not fully designed yet (b/254095482)");
-        }
+    let is_unsafe = sig.unsafety == Unsafety::Unsafe;
+    if input.c_mode {
+        coded_ensure!(
+            ErrorCode::UnsafeFunction,
+            !is_unsafe,
+            "`unsafe` functions are not supported by `--c-mode`"
+        );
+        return format_fn_for_c(input, local_def_id, sig, symbol_name);
     }
 
     let needs_thunk: bool;
@@ -617,16 +2574,29 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         //
         // After https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html a Rust panic that
         // tries to "escape" a "C" ABI function will terminate the program.  This is okay.
-        rustc_target::spec::abi::Abi::C { unwind: false } => {
+        //
+        // A C++ declaration can't use an exported name that is a C++ reserved keyword or a
+        // reserved identifier (the C++ name of the function gets escaped by `format_cc_ident`, but
+        // the exported name can't be escaped) - such functions are handled by the
+        // thunk-generating branch below.
+        rustc_target::spec::abi::Abi::C { unwind: false }
+            if !is_cc_reserved_keyword(symbol_name.name)
+                && !is_cc_reserved_identifier(symbol_name.name) =>
+        {
             needs_thunk = false;
-        },
+        }
 
         // "C-unwind" ABI is okay: After https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html a
         // new "C-unwind" ABI may be used by Rust functions that want to safely propagate Rust
-        // panics through frames that may belong to another language.
-        rustc_target::spec::abi::Abi::C { unwind: true } => {
+        // panics through frames that may belong to another language.  If a thunk is needed
+        // (e.g. because the exported name is a C++ reserved keyword), then the thunk is also
+        // `extern "C-unwind"` (see `format_thunk_abi`).
+        rustc_target::spec::abi::Abi::C { unwind: true }
+            if !is_cc_reserved_keyword(symbol_name.name)
+                && !is_cc_reserved_identifier(symbol_name.name) =>
+        {
             needs_thunk = false;
-        },
+        }
 
         // All other ABIs trigger thunk generation.  This covers Rust ABI functions, but
         // also ABIs that theoretically are understood both by C++ and Rust (e.g. see
@@ -638,79 +2608,237 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         }
     };
 
-    let FullyQualifiedName { krate, mod_path, name, .. } = FullyQualifiedName::new(tcx, def_id);
+    let FullyQualifiedName { krate, mod_path, name, .. } = FullyQualifiedName::new(input, def_id);
     let fn_name = name.expect("Functions are assumed to always have a name");
+    let cc_fn_name = get_fn_cc_name(input, def_id);
     let main_api_fn_name =
-        format_cc_ident(fn_name.as_str()).context("Error formatting function name")?;
+        format_fn_cc_name(cc_fn_name).context("Error formatting function name")?;
 
     let mut main_api_prereqs = CcPrerequisites::default();
     let main_api_ret_type = format_ret_ty_for_cc(input, sig.output())
         .context("Error formatting function return type")?
         .into_tokens(&mut main_api_prereqs);
-    let is_static_method = match tcx.hir().get_by_def_id(local_def_id) {
-        Node::ImplItem(impl_item) => match &impl_item.kind {
-            ImplItemKind::Fn(fn_sig, _) => match fn_sig.decl.implicit_self {
-                ImplicitSelfKind::None => true,
-                _ => bail!("`self` parameter is not supported yet"),
-            },
-            _ => panic!("`format_fn` can only work with functions"),
-        },
-        Node::Item(_) => false, // Free function
-        other => panic!("Unexpected HIR node kind: {other:?}"),
-    };
-
-    struct Param<'tcx> {
+    let implicit_self = get_implicit_self_kind(tcx, local_def_id);
+    let is_static_method = matches!(implicit_self, Some(ImplicitSelfKind::None));
+
+    /// The `self` parameter of a method.  C++ passes `this` to the thunk, and
+    /// the receiver type determines the qualifiers of the C++ member function:
+    /// - `&self` => `const` member function,
+    /// - `&mut self` => non-`const` member function,
+    /// - `self` => `&&`-qualified member function (i.e. one that consumes the
+    ///   object - `std::move(x).into_foo()`).
+    struct SelfParam {
+        cc_qualifiers: TokenStream,
+        cc_thunk_param: TokenStream,
+        rs_thunk_param: TokenStream,
+        rs_thunk_arg: TokenStream,
+    }
+    let self_param = match implicit_self {
+        None | Some(ImplicitSelfKind::None) => None,
+        Some(_) => {
+            let self_ty = sig.inputs()[0];
+            let (struct_ty, mutability) = match self_ty.kind() {
+                ty::TyKind::Ref(_, referent, mutbl) => (*referent, Some(*mutbl)),
+                _ => (self_ty, None),
+            };
+            let cc_type = format_ty_for_cc(input, struct_ty)
+                .context("Error formatting the type of the `self` parameter")?
+                .into_tokens(&mut main_api_prereqs);
+            let rs_type = format_ty_for_rs(input, struct_ty)?;
+            Some(match mutability {
+                // Rust code may modify objects with interior mutability through `&self`, and
+                // therefore the C++ member function is not `const` (see
+                // `has_interior_mutability`).
+                Some(Mutability::Not) if has_interior_mutability(tcx, struct_ty) => SelfParam {
+                    cc_qualifiers: quote! {},
+                    cc_thunk_param: quote! { #cc_type* __self },
+                    rs_thunk_param: quote! { __self: &'static #rs_type },
+                    rs_thunk_arg: quote! { __self },
+                },
+                // See the comment about `'static` lifetimes of slice references in
+                // `format_ty_for_rs`.
+                Some(Mutability::Not) => SelfParam {
+                    cc_qualifiers: quote! { const },
+                    cc_thunk_param: quote! { const #cc_type* __self },
+                    rs_thunk_param: quote! { __self: &'static #rs_type },
+                    rs_thunk_arg: quote! { __self },
+                },
+                Some(Mutability::Mut) => SelfParam {
+                    cc_qualifiers: quote! {},
+                    cc_thunk_param: quote! { #cc_type* __self },
+                    rs_thunk_param: quote! { __self: &'static mut #rs_type },
+                    rs_thunk_arg: quote! { __self },
+                },
+                None if is_moved_by_take(tcx, self_ty)? => SelfParam {
+                    cc_qualifiers: quote! { && },
+                    cc_thunk_param: quote! { #cc_type* __self },
+                    rs_thunk_param: quote! { __self: &'static mut #rs_type },
+                    rs_thunk_arg: quote! { ::core::mem::take(__self) },
+                },
+                None => SelfParam {
+                    cc_qualifiers: quote! { && },
+                    cc_thunk_param: quote! { #cc_type* __self },
+                    rs_thunk_param: quote! { __self: &mut ::core::mem::MaybeUninit<#rs_type> },
+                    rs_thunk_arg: quote! { unsafe { __self.assume_init_read() } },
+                },
+            })
+        }
+    };
+
+    struct Param<'tcx> {
         cc_name: TokenStream,
         cc_type: TokenStream,
         rs_name: Ident,
         rs_type: TokenStream,
         ty: Ty<'tcx>,
+        conversion: Option<ConvertedType>,
+        is_lifetime_bound: bool,
     }
+    let lifetime_bound_params = get_lifetime_bound_params(tcx, def_id);
     let params = {
         let names = tcx.fn_arg_names(def_id).iter();
         let types = sig.inputs().iter();
         names
             .zip(types)
+            .skip(usize::from(self_param.is_some()))
             .enumerate()
             .map(|(i, (name, &ty))| -> Result<Param> {
                 let cc_name = format_cc_ident(name.as_str())
-                    .unwrap_or_else(|_err| format_cc_ident(&format!("__param_{i}")).unwrap());
-                let cc_type = format_ty_for_cc(input, ty)?.into_tokens(&mut main_api_prereqs);
+                    .unwrap_or_else(|_err| format_ident!("__param_{i}").into_token_stream());
+                let cc_type = format_param_ty_for_cc(input, ty)?.into_tokens(&mut main_api_prereqs);
+                let conversion = ConvertedType::new(input, ty, &mut main_api_prereqs)?;
                 let rs_name = if name.as_str().is_empty() {
                     format_ident!("__param_{i}")
                 } else {
                     make_rs_ident(name.as_str())
                 };
-                let rs_type = format_ty_for_rs(tcx, ty)?;
-                Ok(Param { cc_name, cc_type, rs_name, rs_type, ty })
+                let rs_type = match &conversion {
+                    Some(ConvertedType::RawParts(raw_parts)) => raw_parts.rs_raw_parts_type.clone(),
+                    _ => format_ty_for_rs(input, ty)?,
+                };
+                let is_lifetime_bound =
+                    lifetime_bound_params.contains(&(i + usize::from(self_param.is_some())));
+                Ok(Param { cc_name, cc_type, rs_name, rs_type, ty, conversion, is_lifetime_bound })
             })
             .enumerate()
             .map(|(i, result)| result.with_context(|| format!("Error handling parameter #{i}")))
             .collect::<Result<Vec<_>>>()?
     };
-    let main_api_params = params
+    // The parameters borrowed by the return value are annotated with `[[clang::lifetimebound]]`,
+    // so that the C++ compiler can diagnose some of the dangling references (e.g. when a
+    // `rs_std::MutSlice` returned by the function outlives the C++ buffer passed to it).
+    if !lifetime_bound_params.is_empty() {
+        main_api_prereqs.includes.insert(input.support_header("internal/lifetime_bound.h"));
+    }
+    let mut main_api_params = params
         .iter()
-        .map(|Param { cc_name, cc_type, .. }| quote! { #cc_type #cc_name })
+        .map(|Param { cc_name, cc_type, is_lifetime_bound, .. }| {
+            if *is_lifetime_bound {
+                quote! { #cc_type #cc_name CRUBIT_LIFETIME_BOUND }
+            } else {
+                quote! { #cc_type #cc_name }
+            }
+        })
         .collect_vec();
 
-    let struct_name = match tcx.impl_of_method(def_id) {
-        Some(impl_id) => match tcx.impl_subject(impl_id) {
-            ty::ImplSubject::Inherent(ty) => match ty.kind() {
+    // The converted types are converted by the thunks and by the definition of the C++ function
+    // (see `ConvertedType`), and `extern "C"` functions are called directly from C++ (without a
+    // thunk and without a definition of the C++ function).
+    let ret_conversion = ConvertedType::new(input, sig.output(), &mut main_api_prereqs)?;
+    let has_converted_types =
+        params.iter().any(|param| param.conversion.is_some()) || ret_conversion.is_some();
+    ensure!(
+        needs_thunk || !has_converted_types,
+        "Types converted at the FFI boundary (e.g. `Cow<str>`, lock guards, or types mapped to \
+         C++ types with a conversion - see `--type-map`) are not supported in signatures of \
+         `extern \"C\"` functions"
+    );
+
+    // The C++ callers of `unsafe` functions have to explicitly opt into upholding the safety
+    // requirements of the Rust function - either by passing an `rs_std::UnsafeCall` token, or by
+    // calling a function with an `_unsafe` suffix (see `get_fn_cc_name`).
+    let needs_unsafe_call_param = is_unsafe && input.unsafe_fn_marker == UnsafeFnMarker::TokenParam;
+    if needs_unsafe_call_param {
+        main_api_prereqs.includes.insert(input.support_header("rs_std/unsafe_call.h"));
+        main_api_params.insert(0, quote! { rs_std::UnsafeCall });
+    }
+
+    let (struct_def_id, trait_ref) = match tcx.impl_of_method(def_id) {
+        Some(impl_id) => {
+            let (self_ty, trait_ref) = match tcx.impl_subject(impl_id) {
+                ty::ImplSubject::Inherent(ty) => (ty, None),
+                ty::ImplSubject::Trait(trait_ref) => (trait_ref.self_ty(), Some(trait_ref)),
+            };
+            match self_ty.kind() {
                 ty::TyKind::Adt(adt, substs) => {
                     assert_eq!(0, substs.len(), "Callers should filter out generics");
-                    Some(tcx.item_name(adt.did()))
+                    (Some(adt.did()), trait_ref)
                 }
                 _ => panic!("Non-ADT `impl`s should be filtered by caller"),
-            },
-            ty::ImplSubject::Trait(_) => panic!("Trait methods should be filtered by caller"),
-        },
-        None => None,
+            }
+        }
+        None => (None, None),
     };
-    let needs_definition = fn_name.as_str() != symbol_name.name;
+    // Methods with a `self` parameter are C++ member functions, which can't be
+    // declared as `extern "C"` functions.  Similarly, the `rs_std::UnsafeCall`
+    // parameter is not passed to the Rust function, and the converted types have to be converted
+    // by the C++ function.
+    let needs_definition = cc_fn_name.as_str() != symbol_name.name
+        || self_param.is_some()
+        || needs_unsafe_call_param
+        || has_converted_types;
+    let mut cc_qualifiers =
+        self_param.as_ref().map(|p| p.cc_qualifiers.clone()).unwrap_or_default();
+    if self_param.is_some() && lifetime_bound_params.contains(&0) {
+        cc_qualifiers.extend(quote! { CRUBIT_LIFETIME_BOUND });
+    }
     let main_api = {
         let doc_comment = {
-            let doc_comment = format_doc_comment(tcx, local_def_id);
-            quote! { __NEWLINE__ #doc_comment }
+            let doc_comment = format_doc_comment(input, local_def_id);
+            let unsafe_warning = if is_unsafe {
+                let msg = "\\warning This is an `unsafe` Rust function - the caller has to \
+                           uphold its safety requirements (typically documented in the \
+                           `# Safety` section of its Rust doc comment).";
+                quote! { __NEWLINE__ __COMMENT__ #msg }
+            } else {
+                quote! {}
+            };
+            // `MaybeUninit` out-parameters (see `get_maybe_uninit_pointee_ty`) document the
+            // initialize-before-read contract.
+            let maybe_uninit_names = params
+                .iter()
+                .filter(|param| get_maybe_uninit_pointee_ty(tcx, param.ty).is_some())
+                .map(|param| format!("`{}`", param.cc_name))
+                .join(", ");
+            let interior_mutability_note = match sig.inputs().first().map(|ty| ty.kind()) {
+                Some(ty::TyKind::Ref(_, struct_ty, Mutability::Not))
+                    if self_param.is_some() && has_interior_mutability(tcx, *struct_ty) =>
+                {
+                    let msg = format!(
+                        "\\note `{struct_ty}` has interior mutability (e.g. a `Cell` or a \
+                         `RefCell` field) - this method takes `&self` in Rust, but it may modify \
+                         the object, and therefore it is not a `const` member function.  Borrows \
+                         of `RefCell`s are checked at runtime: the method panics if it conflicts \
+                         with an outstanding borrow (e.g. when called reentrantly)."
+                    );
+                    quote! { __NEWLINE__ __COMMENT__ #msg }
+                }
+                _ => quote! {},
+            };
+            let maybe_uninit_note = if maybe_uninit_names.is_empty() {
+                quote! {}
+            } else {
+                let msg = format!(
+                    "\\note {maybe_uninit_names} may point at uninitialized storage - the Rust \
+                     function won't read it before initializing it, and the caller may only read \
+                     it after the call if the function documents that it initializes it."
+                );
+                quote! { __NEWLINE__ __COMMENT__ #msg }
+            };
+            quote! {
+                __NEWLINE__ #doc_comment #unsafe_warning #interior_mutability_note
+                #maybe_uninit_note
+            }
         };
 
         let mut prereqs = main_api_prereqs.clone();
@@ -722,7 +2850,8 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
             quote! {}
         };
         let extern_c_or_inline = if !needs_definition {
-            quote! { extern "C" }
+            let import_attr = input.format_cc_import_attr();
+            quote! { extern "C" #import_attr }
         } else {
             quote! { inline }
         };
@@ -732,7 +2861,8 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
                 __NEWLINE__
                 #doc_comment
                 #static_ #extern_c_or_inline
-                    #main_api_ret_type #main_api_fn_name ( #( #main_api_params ),* );
+                    #main_api_ret_type #main_api_fn_name ( #( #main_api_params ),* )
+                    #cc_qualifiers;
                 __NEWLINE__
             },
         }
@@ -741,47 +2871,104 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         None
     } else {
         let cc = {
-            let thunk_name =
-                format_cc_ident(symbol_name.name).context("Error formatting exported name")?;
-            let struct_name = match struct_name.as_ref() {
+            let thunk_name = format_unescaped_cc_ident(symbol_name.name)
+                .context("Error formatting exported name")?;
+            let struct_name = match struct_def_id {
                 None => quote! {},
-                Some(symbol) => {
-                    let name = format_cc_ident(symbol.as_str())
+                Some(struct_def_id) => {
+                    let AdtCoreBindings { cc_name, .. } = format_adt_core(input, struct_def_id)
                         .expect("Caller of format_fn should verify struct via format_adt_core");
-                    quote! { #name :: }
+                    quote! { #cc_name :: }
                 }
             };
 
             let mut prereqs = main_api_prereqs;
             let mut thunk_params = params
                 .iter()
-                .map(|Param { cc_name, cc_type, ty, .. }| -> Result<TokenStream> {
-                     if is_c_abi_compatible_by_value(*ty) {
-                         Ok(quote! { #cc_type #cc_name })
-                     } else {
-                         // Rust thunk will move a value via memcpy - we need to `ensure` that
-                         // invoking the C++ destructor (on the moved-away value) is safe.
-                         // TODO(b/259749095): Support generic structs (with non-empty ParamEnv).
-                         ensure!(!ty.needs_drop(tcx, ty::ParamEnv::empty()),
-                                 "Only trivially-movable and trivially-destructible types \
-                                  may be passed by value over the FFI boundary");
-                         Ok(quote! { #cc_type* #cc_name })
-                     }})
+                .map(|Param { cc_name, cc_type, ty, conversion, .. }| -> Result<TokenStream> {
+                    let cc_type = match conversion {
+                        Some(ConvertedType::Mapped { cc_binding_type, .. }) => cc_binding_type,
+                        Some(ConvertedType::RawParts(raw_parts)) => {
+                            let cc_raw_parts_type = &raw_parts.cc_raw_parts_type;
+                            return Ok(quote! { #cc_raw_parts_type #cc_name });
+                        }
+                        None => cc_type,
+                    };
+                    if is_c_abi_compatible_by_value(*ty) {
+                        Ok(quote! { #cc_type #cc_name })
+                    } else {
+                        // The Rust thunk moves the value out of the C++ object (see
+                        // `is_moved_by_take`), and the C++ destructor still runs for the
+                        // moved-from object.
+                        is_moved_by_take(tcx, *ty)?;
+                        Ok(quote! { #cc_type* #cc_name })
+                    }
+                })
                 .collect::<Result<Vec<_>>>()?;
             let mut thunk_args = params
                 .iter()
-                .map(|Param{ cc_name, ty, ..}|
-                     if is_c_abi_compatible_by_value(*ty) {
-                         quote!{ #cc_name }
-                     } else {
-                         quote!{ & #cc_name }
-                     })
+                .enumerate()
+                .map(|(i, Param { cc_name, ty, conversion, .. })| match conversion {
+                    Some(ConvertedType::Mapped { .. }) => {
+                        let converted_name = format_ident!("__converted_{i}");
+                        quote! { & #converted_name }
+                    }
+                    Some(ConvertedType::RawParts(_)) => quote! { std::move(#cc_name).release() },
+                    None if is_c_abi_compatible_by_value(*ty) => quote! { #cc_name },
+                    None => quote! { & #cc_name },
+                })
+                .collect_vec();
+            if let Some(SelfParam { cc_thunk_param, .. }) = &self_param {
+                thunk_params.insert(0, cc_thunk_param.clone());
+                thunk_args.insert(0, quote! { this });
+            }
+            if has_converted_types {
+                prereqs.includes.insert(CcInclude::utility());
+            }
+            // The parameters of mapped C++ types are converted into the C++ bindings of the Rust
+            // types before calling the thunk.
+            let param_conversions = params
+                .iter()
+                .enumerate()
+                .filter_map(|(i, Param { cc_name, conversion, .. })| {
+                    let Some(ConvertedType::Mapped { cc_binding_type, from_cc, .. }) = conversion
+                    else {
+                        return None;
+                    };
+                    let converted_name = format_ident!("__converted_{i}");
+                    Some(quote! {
+                        #cc_binding_type #converted_name = #from_cc(std::move(#cc_name));
+                    })
+                })
                 .collect_vec();
             let thunk_ret_type: TokenStream;
             let impl_body: TokenStream;
-            if is_c_abi_compatible_by_value(sig.output()) {
+            if let Some(ConvertedType::Mapped { cc_binding_type, to_cc, .. }) = &ret_conversion {
+                // The return value is converted into the mapped C++ type after calling the
+                // thunk.  The Rust type is never C-ABI-compatible by value (see
+                // `Input::get_converted_type_mapping`), so it is returned via `__ret_slot`.
+                thunk_ret_type = quote!{ void };
+                thunk_params.push(quote!{ #cc_binding_type* __ret_ptr });
+                thunk_args.push(quote!{ __ret_slot.Get() });
+                impl_body = quote!{
+                    #( #param_conversions )*
+                    crubit::ReturnValueSlot<#cc_binding_type> __ret_slot;
+                    __crubit_internal :: #thunk_name( #( #thunk_args ),* );
+                    return #to_cc(std::move(__ret_slot).AssumeInitAndTakeValue());
+                };
+                prereqs.includes.insert(input.support_header("internal/return_value_slot.h"));
+            } else if let Some(ConvertedType::RawParts(raw_parts)) = &ret_conversion {
+                let RawPartsConversion { cc_raw_parts_type, cc_from_raw_parts, .. } = raw_parts;
+                thunk_ret_type = cc_raw_parts_type.clone();
+                impl_body = quote! {
+                    #( #param_conversions )*
+                    return #cc_from_raw_parts(
+                        __crubit_internal :: #thunk_name( #( #thunk_args ),* ));
+                };
+            } else if is_c_abi_compatible_by_value(sig.output()) {
                 thunk_ret_type = main_api_ret_type.clone();
                 impl_body = quote!{
+                    #( #param_conversions )*
                     return __crubit_internal :: #thunk_name( #( #thunk_args ),* );
                 };
             } else {
@@ -789,6 +2976,7 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
                 thunk_params.push(quote!{ #main_api_ret_type* __ret_ptr });
                 thunk_args.push(quote!{ __ret_slot.Get() });
                 impl_body = quote!{
+                    #( #param_conversions )*
                     crubit::ReturnValueSlot<#main_api_ret_type> __ret_slot;
                     __crubit_internal :: #thunk_name( #( #thunk_args ),* );
                     return std::move(__ret_slot).AssumeInitAndTakeValue();
@@ -796,15 +2984,17 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
                 prereqs.includes.insert(CcInclude::utility());
                 prereqs.includes.insert(input.support_header("internal/return_value_slot.h"));
             };
+            let import_attr = input.format_cc_import_attr();
             CcSnippet {
                 prereqs,
                 tokens: quote! {
                     __NEWLINE__
                     namespace __crubit_internal {
-                        extern "C" #thunk_ret_type #thunk_name ( #( #thunk_params ),* );
+                        extern "C" #import_attr #thunk_ret_type #thunk_name (
+                            #( #thunk_params ),* );
                     }
                     inline #main_api_ret_type #struct_name #main_api_fn_name (
-                            #( #main_api_params ),* ) {
+                            #( #main_api_params ),* ) #cc_qualifiers {
                         #impl_body
                     }
                     __NEWLINE__
@@ -818,51 +3008,126 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
             let thunk_name = make_rs_ident(symbol_name.name);
             let mut thunk_params = params
                 .iter()
-                .map(|Param{ rs_name, rs_type, ty, ..}|
-                    if is_c_abi_compatible_by_value(*ty) {
-                        quote!{ #rs_name: #rs_type }
+                .map(|Param { rs_name, rs_type, ty, conversion, .. }| -> Result<TokenStream> {
+                    let is_raw_parts = matches!(conversion, Some(ConvertedType::RawParts(_)));
+                    Ok(if is_c_abi_compatible_by_value(*ty) || is_raw_parts {
+                        quote! { #rs_name: #rs_type }
+                    } else if is_moved_by_take(tcx, *ty)? {
+                        quote! { #rs_name: &mut #rs_type }
                     } else {
-                        quote!{ #rs_name: &mut ::core::mem::MaybeUninit<#rs_type> }
+                        quote! { #rs_name: &mut ::core::mem::MaybeUninit<#rs_type> }
                     })
-                .collect_vec();
-            let mut thunk_ret_type = format_ty_for_rs(tcx, sig.output())?;
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if let Some(SelfParam { rs_thunk_param, .. }) = &self_param {
+                thunk_params.insert(0, rs_thunk_param.clone());
+            }
+            let mut thunk_ret_type = match &ret_conversion {
+                Some(ConvertedType::RawParts(raw_parts)) => raw_parts.rs_raw_parts_type.clone(),
+                _ => format_ty_for_rs(input, sig.output())?,
+            };
             let mut thunk_body = {
                 let crate_name = make_rs_ident(krate.as_str());
                 let mod_path = mod_path.format_for_rs();
                 let fn_name = make_rs_ident(fn_name.as_str());
-                let struct_name = match struct_name.as_ref() {
+                let struct_name = match struct_def_id {
                     None => quote! {},
-                    Some(symbol) => {
-                        let name = make_rs_ident(symbol.as_str());
+                    Some(struct_def_id) => {
+                        let name = make_rs_ident(tcx.item_name(struct_def_id).as_str());
                         quote! { #name :: }
                     }
                 };
-                let fn_args = params.iter().map(|Param{ rs_name, ty, .. }|
-                    if is_c_abi_compatible_by_value(*ty) {
-                        quote!{ #rs_name }
-                    } else {
-                        quote!{ unsafe { #rs_name.assume_init_read() } }
-                    });
-                quote!{
-                    :: #crate_name :: #mod_path #struct_name #fn_name( #( #fn_args ),* )
+                let fn_path = match (struct_def_id, trait_ref) {
+                    (Some(struct_def_id), Some(trait_ref)) => {
+                        let struct_name =
+                            FullyQualifiedName::new(input, struct_def_id).format_for_rs();
+                        let trait_path = match get_cc_operator_trait(tcx, trait_ref.def_id) {
+                            // The operator traits are defined in private modules of `core` (e.g.
+                            // `core::ops::arith`).
+                            Some((name, _)) => {
+                                let name = make_rs_ident(name);
+                                quote! { ::core::ops::#name }
+                            }
+                            None => {
+                                FullyQualifiedName::new(input, trait_ref.def_id).format_for_rs()
+                            }
+                        };
+                        // The generic arguments of the trait (e.g. the `Rhs` of `AddAssign<Rhs>`
+                        // or the `T` of `AsRef<T>`) have to be spelled out when the type
+                        // implements the trait for multiple types.
+                        let generic_args = trait_ref
+                            .substs
+                            .types()
+                            .skip(1) // `Self`
+                            .map(|ty| match ty.kind() {
+                                // Unsized types (e.g. `str` in `AsRef<str>`) can only be used
+                                // behind a pointer, and therefore `format_ty_for_rs` rejects them.
+                                ty::TyKind::Str => Ok(quote! { str }),
+                                ty::TyKind::Slice(elem_ty) => {
+                                    let elem_ty = format_ty_for_rs(input, *elem_ty)?;
+                                    Ok(quote! { [#elem_ty] })
+                                }
+                                _ => format_ty_for_rs(input, ty),
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        let trait_name = if generic_args.is_empty() {
+                            trait_path
+                        } else {
+                            quote! { #trait_path < #( #generic_args ),* > }
+                        };
+                        quote! { <#struct_name as #trait_name> :: #fn_name }
+                    }
+                    _ => quote! { :: #crate_name :: #mod_path #struct_name #fn_name },
+                };
+                let self_arg = self_param.as_ref().map(|p| p.rs_thunk_arg.clone());
+                let fn_args = params
+                    .iter()
+                    .map(|Param { rs_name, ty, conversion, .. }| -> Result<TokenStream> {
+                        Ok(if let Some(ConvertedType::RawParts(raw_parts)) = conversion {
+                            let rs_from_raw_parts = raw_parts
+                                .rs_from_raw_parts
+                                .as_ref()
+                                .expect("`format_param_ty_for_cc` should reject return-only types");
+                            quote! { unsafe { #rs_from_raw_parts(#rs_name) } }
+                        } else if is_c_abi_compatible_by_value(*ty) {
+                            quote! { #rs_name }
+                        } else if is_moved_by_take(tcx, *ty)? {
+                            quote! { ::core::mem::take(#rs_name) }
+                        } else {
+                            quote! { unsafe { #rs_name.assume_init_read() } }
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let fn_args = self_arg.into_iter().chain(fn_args);
+                let call = quote! { #fn_path( #( #fn_args ),* ) };
+                if is_unsafe {
+                    // The C++ caller has opted into upholding the safety requirements (see
+                    // `needs_unsafe_call_param`).
+                    quote! { unsafe { #call } }
+                } else {
+                    call
                 }
             };
-            if !is_c_abi_compatible_by_value(sig.output()) {
+            if let Some(ConvertedType::RawParts(raw_parts)) = &ret_conversion {
+                let rs_into_raw_parts = &raw_parts.rs_into_raw_parts;
+                thunk_body = quote! { #rs_into_raw_parts(#thunk_body) };
+            } else if !is_c_abi_compatible_by_value(sig.output()) {
                 thunk_params.push(quote!{
                     __ret_slot: &mut ::core::mem::MaybeUninit<#thunk_ret_type>
                 });
                 thunk_ret_type = quote!{ () };
                 thunk_body = quote!{ __ret_slot.write(#thunk_body); };
             };
+            let thunk_abi = format_thunk_abi(&sig);
             quote! {
                 #[no_mangle]
-                extern "C" fn #thunk_name( #( #thunk_params ),* ) -> #thunk_ret_type {
+                extern #thunk_abi fn #thunk_name( #( #thunk_params ),* ) -> #thunk_ret_type {
                     #thunk_body
                 }
             }
         };
 
-        Some(MixedSnippet { cc, rs })
+        Some(MixedSnippet { cc, rs, ..Default::default() })
     };
 
     let mut result =
@@ -876,6 +3141,185 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
     Ok(result)
 }
 
+/// Returns whether a value of `ty` passed by value from C++ to Rust is moved
+/// out of the C++ object with `mem::take` (rather than with a bitwise copy).
+///
+/// After a bitwise copy the C++ object still holds the moved-from value, and
+/// the C++ destructor would drop the value a second time.  Therefore types that
+/// need to be dropped are moved with `mem::take`, which leaves
+/// `Default::default()` in the C++ object (just like the C++ move constructor
+/// from `format_adt_drop_and_move`).  Returns an error if `ty` needs to be
+/// dropped, but doesn't implement `Default`.
+fn is_moved_by_take<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<bool> {
+    // TODO(b/259749095): Support generic structs (with non-empty ParamEnv).
+    if !ty.needs_drop(tcx, ty::ParamEnv::empty()) {
+        return Ok(false);
+    }
+    coded_ensure!(
+        ErrorCode::NonTriviallyMovableByValue,
+        implements_default(tcx, ty),
+        "Types that need to be dropped (e.g. `{ty}`) may only be passed by value over the \
+         FFI boundary if they implement `Default`"
+    );
+    Ok(true)
+}
+
+/// Returns the ABI string of the Rust thunk that wraps a function with the
+/// given `sig`.
+///
+/// Thunks of `extern "C-unwind"` functions are also `extern "C-unwind"` - the
+/// wrapped function has opted into propagating panics to its (C++) caller, and
+/// an `extern "C"` thunk would abort the process instead.  Note that this only
+/// makes a difference with `-Cpanic=unwind`: with `-Cpanic=abort` (the only
+/// configuration currently supported by Crubit - see
+/// `panics_and_exceptions.md`) a panic aborts the process regardless of the
+/// ABI of the thunk.
+fn format_thunk_abi(sig: &ty::FnSig) -> TokenStream {
+    match sig.abi {
+        rustc_target::spec::abi::Abi::C { unwind: true } => quote! { "C-unwind" },
+        _ => quote! { "C" },
+    }
+}
+
+/// Returns the kind of the `self` parameter of the method with the given
+/// `local_def_id` (`ImplicitSelfKind::None` for static methods), or `None` if
+/// `local_def_id` identifies a free function.
+fn get_implicit_self_kind(tcx: TyCtxt, local_def_id: LocalDefId) -> Option<ImplicitSelfKind> {
+    match tcx.hir().get_by_def_id(local_def_id) {
+        Node::ImplItem(impl_item) => match &impl_item.kind {
+            ImplItemKind::Fn(fn_sig, _) => Some(fn_sig.decl.implicit_self),
+            _ => panic!("`get_implicit_self_kind` can only work with functions"),
+        },
+        Node::Item(_) => None, // Free function
+        other => panic!("Unexpected HIR node kind: {other:?}"),
+    }
+}
+
+/// Formats a function with the given `local_def_id` for `Input::c_mode`.
+///
+/// The C declaration uses the name from `FullyQualifiedName::format_for_c`
+/// (prefixed with the name of the struct for static methods - e.g.
+/// `foo_SomeStruct_create`).  Unless the Rust function already exports this
+/// name using the "C" ABI, the name is exported by a Rust thunk.  There are no
+/// C++ thunks, and therefore the parameter and return types need to be passed
+/// by value in a C-ABI-compatible way.
+///
+/// Will panic if `local_def_id` doesn't identify a function.
+fn format_fn_for_c<'tcx>(
+    input: &Input<'tcx>,
+    local_def_id: LocalDefId,
+    sig: ty::FnSig<'tcx>,
+    symbol_name: ty::SymbolName,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id: DefId = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+
+    let fully_qualified_name = FullyQualifiedName::new(input, def_id);
+    let (c_name, rs_path) = match tcx.impl_of_method(def_id) {
+        None => (fully_qualified_name.format_for_c(input), fully_qualified_name.format_for_rs()),
+        Some(impl_id) => {
+            coded_ensure!(
+                ErrorCode::SelfParameter,
+                !tcx.associated_item(def_id).fn_has_self_parameter,
+                "`self` parameter is not supported yet"
+            );
+            let struct_def_id = match rustc_compat::type_of(tcx, impl_id).kind() {
+                ty::TyKind::Adt(adt, _) => adt.did(),
+                _ => panic!("Non-ADT `impl`s should be filtered by caller"),
+            };
+            let struct_name = FullyQualifiedName::new(input, struct_def_id);
+            let fn_cc_name = fully_qualified_name.cc_name.expect("Functions always have a name");
+            let fn_rs_name = fully_qualified_name.name.expect("Functions always have a name");
+            let fn_rs_name = make_rs_ident(fn_rs_name.as_str());
+            let struct_rs_path = struct_name.format_for_rs();
+            (
+                format!("{}_{fn_cc_name}", struct_name.format_for_c(input)),
+                quote! { #struct_rs_path :: #fn_rs_name },
+            )
+        }
+    };
+    // The C name is escaped upfront, because it is also the name exported by the Rust thunk.
+    let c_name = escape_cc_ident(&c_name).into_owned();
+    let c_fn_name = format_cc_ident(&c_name).context("Error formatting function name")?;
+
+    let mut prereqs = CcPrerequisites::default();
+    let c_ret_type = format_ret_ty_for_cc(input, sig.output())
+        .context("Error formatting function return type")?
+        .into_tokens(&mut prereqs);
+    coded_ensure!(
+        ErrorCode::CMode,
+        is_c_abi_compatible_by_value(sig.output()),
+        "Returning `{}` by value is not supported by `--c-mode`",
+        sig.output()
+    );
+
+    let names = tcx.fn_arg_names(def_id).iter();
+    let types = sig.inputs().iter();
+    let (c_params, rs_params): (Vec<_>, Vec<_>) = names
+        .zip(types)
+        .enumerate()
+        .map(|(i, (name, &ty))| -> Result<(TokenStream, (Ident, TokenStream))> {
+            let c_name = format_cc_ident(name.as_str())
+                .unwrap_or_else(|_err| format_ident!("__param_{i}").into_token_stream());
+            let c_type = format_ty_for_cc(input, ty)?.into_tokens(&mut prereqs);
+            coded_ensure!(
+                ErrorCode::CMode,
+                is_c_abi_compatible_by_value(ty),
+                "Passing `{ty}` by value is not supported by `--c-mode`"
+            );
+            let rs_name = if name.as_str().is_empty() {
+                format_ident!("__param_{i}")
+            } else {
+                make_rs_ident(name.as_str())
+            };
+            let rs_type = format_ty_for_rs(input, ty)?;
+            Ok((quote! { #c_type #c_name }, (rs_name, rs_type)))
+        })
+        .enumerate()
+        .map(|(i, result)| result.with_context(|| format!("Error handling parameter #{i}")))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+
+    let main_api = {
+        let doc_comment = format_doc_comment(input, local_def_id);
+        // In C an empty parameter list means that the parameters are unspecified.
+        let c_params = if c_params.is_empty() { vec![quote! { void }] } else { c_params };
+        let import_attr = input.format_cc_import_attr();
+        CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__
+                #doc_comment
+                #import_attr #c_ret_type #c_fn_name ( #( #c_params ),* );
+                __NEWLINE__
+            },
+        }
+    };
+    let mut result =
+        vec![(SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into())];
+
+    let is_exported_with_c_abi = matches!(sig.abi, rustc_target::spec::abi::Abi::C { .. });
+    if !is_exported_with_c_abi || symbol_name.name != c_name {
+        let thunk_name = make_rs_ident(&c_name);
+        let thunk_ret_type = format_ty_for_rs(input, sig.output())?;
+        let thunk_params = rs_params.iter().map(|(name, ty)| quote! { #name: #ty });
+        let thunk_args = rs_params.iter().map(|(name, _ty)| name);
+        let thunk_abi = format_thunk_abi(&sig);
+        let rs = quote! {
+            #[no_mangle]
+            extern #thunk_abi fn #thunk_name( #( #thunk_params ),* ) -> #thunk_ret_type {
+                #rs_path( #( #thunk_args ),* )
+            }
+        };
+        result.push((
+            SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails },
+            MixedSnippet { rs, ..Default::default() },
+        ));
+    }
+    Ok(result)
+}
+
 /// Represents bindings for the "core" part of an algebraic data type (an ADT -
 /// a struct, an enum, or a union) in a way that supports later injecting the
 /// other parts like so:
@@ -895,11 +3339,18 @@ struct AdtCoreBindings {
     /// DefId of the ADT.
     def_id: DefId,
 
-    /// C++ tag - e.g. `struct`, `class`, `enum`, or `union`.  This isn't always
-    /// a direct mapping from Rust (e.g. a Rust `enum` might end up being
+    /// C++ tag - e.g. `struct`, `class`, `enum class`, or `union`.  This isn't
+    /// always a direct mapping from Rust (e.g. a Rust `enum` might end up being
     /// represented as an opaque C++ `struct`).
     keyword: TokenStream,
 
+    /// The C++ underlying type (one of the `<cstdint>` types - e.g.
+    /// `std::int32_t`) of a fieldless Rust enum with a primitive representation
+    /// (e.g. `#[repr(i32)]`).  Such enums are represented as a C++ `enum class`
+    /// or (if they are `#[non_exhaustive]`) as a C++ `class` that wraps the
+    /// underlying type (see `format_enum_class`), and `core` is empty.
+    enum_underlying_type: Option<TokenStream>,
+
     /// C++ translation of the ADT identifier - e.g. `SomeStruct`.
     cc_name: TokenStream,
 
@@ -916,6 +3367,17 @@ struct AdtCoreBindings {
     /// - the destructor
     core: TokenStream,
 
+    /// Whether the ADT provides a custom `Drop` impl or requires drop glue (in
+    /// which case `core` only declares the destructor and the move operations -
+    /// their definitions come from `format_adt_drop_and_move`).
+    needs_drop: bool,
+
+    /// Whether `core` declares a `private` default constructor that
+    /// initializes the ADT with `Default::default()` (its definition comes from
+    /// `format_adt_default_ctor`).  This is only the case for
+    /// `#[non_exhaustive]` structs that implement `Default`.
+    has_private_default_ctor: bool,
+
     alignment_in_bytes: u64,
     size_in_bytes: u64,
 }
@@ -935,6 +3397,41 @@ fn is_directly_public(tcx: TyCtxt, def_id: DefId) -> bool {
     }
 }
 
+/// Returns whether `ty` implements the `Default` trait.
+///
+/// TODO(b/259749095): Support generic types (this only finds `impl`s for the
+/// exact, non-generic `ty`).
+fn implements_default<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    let Some(default_trait_id) = tcx.get_diagnostic_item(rustc_span::symbol::sym::Default) else {
+        return false;
+    };
+    tcx.all_impls(default_trait_id).any(|impl_id| {
+        rustc_compat::impl_trait_ref(tcx, impl_id).map(|trait_ref| trait_ref.self_ty()) == Some(ty)
+    })
+}
+
+/// Returns whether `ty` implements the trait identified by `trait_def_id`.
+/// Unlike `implements_default`, this also works for auto traits like `Send`
+/// and `Sync` (which are usually implemented without an explicit `impl`).
+///
+/// TODO(b/259749095): Support generic types.
+fn implements_trait<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>, trait_def_id: DefId) -> bool {
+    let infcx = tcx.infer_ctxt().build();
+    infcx
+        .type_implements_trait(trait_def_id, [ty], ty::ParamEnv::empty())
+        .must_apply_modulo_regions()
+}
+
+/// Returns whether `ty` implements the `Send` and the `Sync` auto traits.
+fn get_send_and_sync<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> (bool, bool) {
+    let implements = |trait_def_id: Option<DefId>| {
+        trait_def_id.map_or(false, |trait_def_id| implements_trait(tcx, ty, trait_def_id))
+    };
+    let is_send = implements(tcx.get_diagnostic_item(rustc_span::symbol::sym::Send));
+    let is_sync = implements(tcx.lang_items().sync_trait());
+    (is_send, is_sync)
+}
+
 fn get_layout<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<Layout<'tcx>> {
     // TODO(b/259749095): Support non-empty set of generic parameters.
     let param_env = ty::ParamEnv::empty();
@@ -967,28 +3464,88 @@ fn get_layout<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<Layout<'tcx>> {
 /// is why the `def_id` parameter is a DefId rather than LocalDefId.
 //
 // TODO(b/259724276): This function's results should be memoized.
-fn format_adt_core(tcx: TyCtxt, def_id: DefId) -> Result<AdtCoreBindings> {
-    let ty = tcx.type_of(def_id).subst_identity();
+fn format_adt_core(input: &Input, def_id: DefId) -> Result<AdtCoreBindings> {
+    let tcx = input.tcx;
+    let ty = rustc_compat::type_of(tcx, def_id);
     assert!(ty.is_adt());
     assert!(is_directly_public(tcx, def_id), "Caller should verify");
 
     // TODO(b/259749095): Support non-empty set of generic parameters.
     let param_env = ty::ParamEnv::empty();
 
-    if ty.needs_drop(tcx, param_env) {
-        // TODO(b/258251148): Support custom `Drop` impls.
-        bail!("`Drop` trait and \"drop glue\" are not supported yet (b/258251148)");
+    let needs_drop = ty.needs_drop(tcx, param_env);
+    if needs_drop {
+        if input.c_mode {
+            coded_bail!(
+                ErrorCode::CMode,
+                "`Drop` trait and \"drop glue\" are not supported by `--c-mode`"
+            );
+        }
+        // The C++ move constructor needs to leave a valid Rust value in the moved-from object
+        // (because the C++ destructor will run for it) - see the comments in `core` below.
+        //
+        // TODO(b/258251148): Support `Drop` types that don't implement `Default` (e.g. by
+        // `delete`ing their C++ move constructor, and constructing return values in-place).
+        coded_ensure!(
+            ErrorCode::DropWithoutDefault,
+            implements_default(tcx, ty),
+            "`Drop` trait and \"drop glue\" are only supported for types that implement \
+             `Default` (b/258251148)"
+        );
     }
 
     let adt_def = ty.ty_adt_def().expect("`def_id` needs to identify an ADT");
+    let item_name = tcx.item_name(def_id);
+
+    // An explicit primitive representation makes the discriminants a part of the ABI of
+    // a fieldless enum - such an enum can be represented as a C++ `enum class` (with the
+    // same underlying type and with the same discriminant values).  `Drop` can't be
+    // supported this way, because C++ `enum class`es can't have destructors.
+    let enum_underlying_type = if adt_def.is_enum()
+        && adt_def.repr().int.is_some()
+        && adt_def.is_payloadfree()
+        && !needs_drop
+        && !input.c_mode
+    {
+        for variant in adt_def.variants() {
+            format_cc_ident(variant.name.as_str()).with_context(|| {
+                format!("Error formatting the name of the `{}` variant", variant.name)
+            })?;
+            // The named constants of a `#[non_exhaustive]` enum are static members of a C++
+            // class (see `format_enum_class`).
+            ensure!(
+                !adt_def.is_variant_list_non_exhaustive()
+                    || ![item_name.as_str(), "FromInt", "ToInt", "IsKnown", "value_"]
+                        .contains(&variant.name.as_str()),
+                "The name of the `{}` variant conflicts with a C++ member of `{item_name}`",
+                variant.name
+            );
+        }
+        let discr_ty = adt_def.repr().discr_type().to_ty(tcx);
+        let underlying_type = format_ty_for_cc(input, discr_ty).with_context(|| {
+            format!("Error formatting the underlying type of the `{item_name}` enum")
+        })?;
+        Some(underlying_type.tokens)
+    } else {
+        None
+    };
     let keyword = match adt_def.adt_kind() {
+        ty::AdtKind::Enum if enum_underlying_type.is_some() => {
+            if adt_def.is_variant_list_non_exhaustive() {
+                quote! { class }
+            } else {
+                quote! { enum class }
+            }
+        }
         ty::AdtKind::Struct | ty::AdtKind::Enum => quote! { struct },
         ty::AdtKind::Union => quote! { union },
     };
 
-    let item_name = tcx.item_name(def_id);
-    let rs_name = format_ty_for_rs(tcx, ty)?;
-    let cc_name = format_cc_ident(item_name.as_str()).context("Error formatting item name")?;
+    let rs_name = format_ty_for_rs(input, ty)?;
+    let cc_name = {
+        let cc_name = FullyQualifiedName::new(input, def_id).cc_name.unwrap_or(item_name);
+        format_cc_ident(cc_name.as_str()).context("Error formatting item name")?
+    };
 
     let layout = get_layout(tcx, ty)
         .with_context(|| format!("Error computing the layout of #{item_name}"))?;
@@ -1000,14 +3557,52 @@ fn format_adt_core(tcx: TyCtxt, def_id: DefId) -> Result<AdtCoreBindings> {
         layout.align().abi.bytes()
     };
     let size_in_bytes = layout.size().bytes();
-    ensure!(size_in_bytes != 0, "Zero-sized types (ZSTs) are not supported (b/258259459)");
+    coded_ensure!(
+        ErrorCode::ZeroSizedType,
+        size_in_bytes != 0,
+        "Zero-sized types (ZSTs) are not supported (b/258259459)"
+    );
 
+    let (move_ctor, move_assignment, dtor) = if needs_drop {
+        (
+            quote! { #cc_name(#cc_name&&); },
+            quote! { #cc_name& operator=(#cc_name&&); },
+            quote! { ~#cc_name(); },
+        )
+    } else {
+        (
+            quote! { #cc_name(#cc_name&&) = default; },
+            quote! { #cc_name& operator=(#cc_name&&) = delete; },
+            quote! { ~#cc_name() = default; },
+        )
+    };
+    // Other crates can't construct a `#[non_exhaustive]` struct from its fields (e.g. with a
+    // struct expression), because more fields may be added in the future.  C++ can't either -
+    // values of such structs come from Rust (e.g. from a factory function), or from
+    // `Default::default()`.  The latter is exposed as a `private` default C++ constructor,
+    // which is only accessible to the `#[crubit::cc_builder]` builder (a `friend` of the
+    // struct).
+    let is_non_exhaustive_struct =
+        adt_def.is_struct() && adt_def.non_enum_variant().is_field_list_non_exhaustive();
+    let has_private_default_ctor =
+        is_non_exhaustive_struct && !input.c_mode && implements_default(tcx, ty);
+    let default_ctor = if has_private_default_ctor {
+        quote! {
+            private:
+                #cc_name();
+            public:
+        }
+    } else {
+        quote! {
+            public:
+                // TODO(b/258249980): If the wrapped type implements the `Default` trait, then
+                // we should call its `impl` from the default C++ constructor (instead of
+                // `delete`ing the default C++ constructor).
+                #cc_name() = delete;
+        }
+    };
     let core = quote! {
-        public:
-            // TODO(b/258249980): If the wrapped type implements the `Default` trait, then we
-            // should call its `impl` from the default C++ constructor (instead of `delete`ing
-            // the default C++ constructor).
-            #cc_name() = delete;
+        #default_ctor
 
             // TODO(b/258249993): Provide `default` copy constructor and assignment operator if
             // the wrapped type is `Copy` on Rust side.
@@ -1030,8 +3625,7 @@ fn format_adt_core(tcx: TyCtxt, def_id: DefId) -> Result<AdtCoreBindings> {
             //   constructor, that is, makes a copy of the object representation as if by
             //   std::memmove."
             // * Generate trivial C++ destructor. (Types that implement `Drop` trait or require
-            //   "drop glue" are not *yet* supported - this might eventually change as part of the
-            //   work tracked under b/258251148). Per
+            //   "drop glue" are handled differently - see below). Per
             //   <internal link>/cpp/language/destructor#Trivial_destructor: "A trivial destructor is a
             //   destructor that performs no action."
             //
@@ -1043,32 +3637,40 @@ fn format_adt_core(tcx: TyCtxt, def_id: DefId) -> Result<AdtCoreBindings> {
             // * Rust, assumming non-`Copy`, no custom `Drop` or drop glue:
             //   `let x = y;`
             //
-            // TODO(b/258251148): If the ADT provides a custom `Drop` impls or requires drop glue,
-            // then extra care should be taken to ensure the C++ destructor can handle the
-            // moved-from object in a way that meets Rust move semantics.  For example, the
-            // generated C++ move constructor might need to assign `Default::default()` to the
-            // moved-from object.
-            #cc_name(#cc_name&&) = default;
+            // If the ADT provides a custom `Drop` impl or requires drop glue, then the C++
+            // destructor will also run for the moved-from object.  To meet Rust move semantics,
+            // the move constructor (and the move assignment operator) of such ADTs leave
+            // `Default::default()` in the moved-from object (see `format_adt_drop_and_move`).
+            #move_ctor
 
             // TODO(b/258235219): Providing assignment operators enables mutation which
             // may negatively interact with support for references.  Therefore until we
             // have more confidence in our reference-handling-plans, we are deleting the
-            // assignment operators.
+            // copy assignment operator (and the move assignment operator of ADTs that don't
+            // need to be dropped).
             //
             // (Move assignment operator has another set of concerns and constraints - see the
             // comment for the move constructor above).
             #cc_name& operator=(const #cc_name&) = delete;
-            #cc_name& operator=(#cc_name&&) = delete;
+            #move_assignment
 
-            // TODO(b/258251148): Support custom `Drop` impls and drop glue.
-            ~#cc_name() = default;
+            #dtor
+    };
+    // C++ `enum class`es can't have special member functions.
+    let core = if enum_underlying_type.is_some() {
+        quote! {}
+    } else {
+        core
     };
     Ok(AdtCoreBindings {
         def_id,
         keyword,
+        enum_underlying_type,
         cc_name,
         rs_name,
         core,
+        needs_drop,
+        has_private_default_ctor,
         alignment_in_bytes,
         size_in_bytes,
     })
@@ -1098,6 +3700,10 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
     struct FieldTypeInfo {
         size: u64,
         cc_type: CcSnippet,
+
+        /// Whether the field is a `Box<T>` or an `Option<Box<T>>` (see
+        /// `get_boxed_field_pointee_ty`).
+        is_boxed: bool,
     }
     struct Field {
         type_info: Result<FieldTypeInfo>,
@@ -1108,19 +3714,31 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
         offset: u64,
         offset_of_next_field: u64,
     }
-    let ty = tcx.type_of(core.def_id).subst_identity();
+    let ty = rustc_compat::type_of(tcx, core.def_id);
     let layout = tcx
         .layout_of(param_env.and(ty))
         .expect("Layout should be already verified by `format_adt_core`")
         .layout;
-    let fields: Vec<Field> = if ty.is_enum() || ty.is_union() {
+    let opaque_fields_reason = if ty.is_enum() || ty.is_union() {
         // Note that `#[repr(Rust)]` unions don't guarantee that all their fields
         // have offset 0.
+        Some(anyhow!(
+            "No support for bindings of individual fields of \
+                                `union` (b/272801632) or `enum`"
+        ))
+    } else if has_interior_mutability(tcx, ty) {
+        // Rust code may modify the fields through shared references (e.g. while C++
+        // code reads them).
+        Some(coded_anyhow!(
+            ErrorCode::InteriorMutability,
+            "`{ty}` has interior mutability (e.g. a `Cell` or a `RefCell` field)"
+        ))
+    } else {
+        None
+    };
+    let fields: Vec<Field> = if let Some(reason) = opaque_fields_reason {
         vec![Field {
-            type_info: Err(anyhow!(
-                "No support for bindings of individual fields of \
-                                    `union` (b/272801632) or `enum`"
-            )),
+            type_info: Err(reason),
             cc_name: quote! { __opaque_blob_of_bytes },
             rs_name: quote! { __opaque_blob_of_bytes },
             is_public: false,
@@ -1139,12 +3757,15 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
                 let field_ty = field_def.ty(tcx, substs_ref);
                 let size = get_layout(tcx, field_ty).map(|layout| layout.size().bytes());
                 let type_info = size.and_then(|size| {
-                    Ok(FieldTypeInfo { size, cc_type: format_ty_for_cc(input, field_ty)? })
+                    Ok(FieldTypeInfo {
+                        size,
+                        cc_type: format_field_ty_for_cc(input, field_ty)?,
+                        is_boxed: get_boxed_field_pointee_ty(tcx, field_ty).is_some(),
+                    })
                 });
                 let name = field_def.ident(tcx);
-                let cc_name = format_cc_ident(name.as_str()).unwrap_or_else(|_err|
-                    format_ident!("__field{index}").into_token_stream()
-                );
+                let cc_name = format_cc_ident(name.as_str())
+                    .unwrap_or_else(|_err| format_ident!("__field{index}").into_token_stream());
                 let rs_name = {
                     let name_starts_with_digit = name
                         .as_str()
@@ -1225,29 +3846,33 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
         })
         .collect();
 
-    let (impl_item_main_apis, impl_item_other_snippets) = tcx
-        .inherent_impls(core.def_id)
-        .iter()
-        .map(|impl_id| tcx.hir().expect_item(impl_id.expect_local()))
-        .flat_map(|item| match &item.kind {
-            ItemKind::Impl(impl_) => impl_.items,
-            other => panic!("Unexpected `ItemKind` from `inherent_impls`: {other:?}"),
-        })
-        .flat_map(|impl_item_ref| {
-            let def_id = impl_item_ref.id.owner_id.def_id;
-            if !tcx.effective_visibilities(()).is_directly_public(def_id) {
-                return vec![];
-            }
-            let result = match impl_item_ref.kind {
-                AssocItemKind::Fn { .. } => format_fn(input, def_id),
-                other => Err(anyhow!("Unsupported `impl` item kind: {other:?}")),
-            };
-            result.unwrap_or_else(|err| vec![format_unsupported_def(tcx, def_id, err)])
-        })
+    let mut builder = if input.crubit_annotations(core.def_id).cc_builder {
+        Some(format_adt_builder(input, core).with_context(|| {
+            format!("Error generating the `#[crubit::cc_builder]` builder of `{adt_cc_name}`")
+        }))
+    } else {
+        None
+    };
+
+    let (impl_item_main_apis, impl_item_other_snippets) = format_impl_items(input, core)
+        .into_iter()
         .partition::<Vec<_>, _>(|(SnippetKey { kind, .. }, _)| *kind == SnippetKind::MainApi);
 
+    // `kIsSend` and `kIsSync` let C++ code that shares objects across threads
+    // check at compile time whether the Rust type implements the `Send` and
+    // `Sync` auto traits (see also `support/rs_std/thread_safety.h`).
+    let thread_safety_decls = {
+        let (is_send, is_sync) = get_send_and_sync(tcx, ty);
+        quote! {
+            static constexpr bool kIsSend = #is_send;
+            static constexpr bool kIsSync = #is_sync;
+        }
+    };
+
     let alignment = Literal::u64_unsuffixed(core.alignment_in_bytes);
     let size = Literal::u64_unsuffixed(core.size_in_bytes);
+    let has_cc_out = input.cc_out_h_include.is_some();
+    let assertions_inline = if has_cc_out { quote! {} } else { quote! { inline } };
     let main_api = {
         let cc_packed_attribute = {
             let has_packed_attribute = tcx
@@ -1261,7 +3886,7 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
             }
         };
 
-        let doc_comment = format_doc_comment(tcx, core.def_id.expect_local());
+        let doc_comment = format_doc_comment(input, core.def_id.expect_local());
         let keyword = &core.keyword;
         let core = &core.core;
 
@@ -1281,7 +3906,7 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
                             unsigned char #cc_name[#size];
                         }
                     }
-                    Ok(FieldTypeInfo { cc_type, size }) => {
+                    Ok(FieldTypeInfo { cc_type, size, is_boxed }) => {
                         let padding = field.offset_of_next_field - field.offset - size;
                         let padding = if padding == 0 {
                             quote! {}
@@ -1291,7 +3916,15 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
                             quote! { unsigned char #ident[#padding]; }
                         };
                         let cc_type = cc_type.into_tokens(&mut prereqs);
-                        quote! { #cc_type #cc_name; #padding }
+                        let comment = if is_boxed {
+                            let msg = "Owned by Rust (a `Box`): C++ code must not free or \
+                                       reassign the pointer - the pointee is dropped together \
+                                       with the struct.";
+                            quote! { __COMMENT__ #msg }
+                        } else {
+                            quote! {}
+                        };
+                        quote! { #comment #cc_type #cc_name; #padding }
                     }
                 }
             })
@@ -1316,38 +3949,61 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
         } else {
             // We put the assertions in a method so that they can read private member
             // variables.
-            quote! { inline static void __crubit_field_offset_assertions(); }
+            quote! { #assertions_inline static void __crubit_field_offset_assertions(); }
+        };
+
+        // The annotation lets `rs_bindings_from_cc` map the C++ type back to
+        // the original Rust type (rather than generating new Rust bindings for
+        // the C++ bindings).
+        let rust_type = FullyQualifiedName::new(input, core.def_id).format_for_rs_as_string();
+        let rust_type_annotation = quote! { [[clang::annotate("crubit_rust_type", #rust_type)]] };
+
+        // The builder assigns the (private - see the TODO below) fields directly.
+        let (friend_decl, builder_def) = match &mut builder {
+            None => (quote! {}, quote! {}),
+            Some(Ok(AdtBuilderBindings { cc_name, main_api, .. })) => {
+                let main_api = std::mem::take(main_api).into_tokens(&mut prereqs);
+                (quote! { friend class #cc_name; }, main_api)
+            }
+            Some(Err(err)) => {
+                let msg = format!("{err:#}");
+                (quote! {}, quote! { __NEWLINE__ __COMMENT__ #msg })
+            }
         };
 
         CcSnippet {
             prereqs,
             tokens: quote! {
                 __NEWLINE__ #doc_comment
-                #keyword alignas(#alignment) #cc_packed_attribute #adt_cc_name final {
+                #keyword #rust_type_annotation alignas(#alignment) #cc_packed_attribute
+                #adt_cc_name final {
                     #core
+                    #thread_safety_decls
                     #impl_item_decls
 
                     // TODO(b/271002281): Preserve actual field visibility.
                     private: __NEWLINE__
+                        #friend_decl
                         #fields
                         #assertions_method_decl
                 };
                 __NEWLINE__
+                #builder_def
             },
         }
     };
     let impl_details = {
-        let mut cc = {
+        let assertions = {
             let assertions_method_def = if cc_field_assertions.is_empty() {
                 quote! {}
             } else {
                 quote! {
-                    inline void #adt_cc_name::__crubit_field_offset_assertions() {
+                    #assertions_inline void #adt_cc_name::__crubit_field_offset_assertions() {
                         #cc_field_assertions
                     }
                 }
             };
-            CcSnippet::new(quote! {
+            quote! {
                 __NEWLINE__
                 static_assert(
                     sizeof(#adt_cc_name) == #size,
@@ -1357,17 +4013,41 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
                     "Verify that struct layout didn't change since this header got generated");
                 __NEWLINE__
                 #assertions_method_def
-            })
+            }
+        };
+        // The assertions are moved into the companion `.cc` file (if any), so that they are
+        // compiled once, rather than in every translation unit that includes the header.
+        let (mut cc, cc_out) = if has_cc_out {
+            (CcSnippet::default(), assertions)
+        } else {
+            (CcSnippet::new(assertions), quote! {})
         };
         cc.prereqs.defs.insert(local_def_id);
-        let rs = {
+        let core_crate = input.format_rs_core_crate();
+        let mut rs = {
             quote! {
-                const _: () = assert!(::std::mem::size_of::<#adt_rs_name>() == #size);
-                const _: () = assert!(::std::mem::align_of::<#adt_rs_name>() == #alignment);
+                const _: () = assert!(#core_crate::mem::size_of::<#adt_rs_name>() == #size);
+                const _: () = assert!(#core_crate::mem::align_of::<#adt_rs_name>() == #alignment);
                 #rs_field_assertions
             }
         };
-        MixedSnippet { cc, rs }
+        if core.needs_drop {
+            // Unlike the assertions, the inline definitions have to stay in the header.
+            let (drop_and_move_cc, drop_and_move_rs) = format_adt_drop_and_move(input, core);
+            cc.tokens.extend(drop_and_move_cc);
+            rs.extend(drop_and_move_rs);
+        }
+        if core.has_private_default_ctor {
+            let (default_ctor_cc, default_ctor_rs) = format_adt_default_ctor(input, core);
+            cc.tokens.extend(default_ctor_cc);
+            rs.extend(default_ctor_rs);
+        }
+        if let Some(Ok(AdtBuilderBindings { impl_details_cc, impl_details_rs, .. })) = builder {
+            cc.prereqs += impl_details_cc.prereqs;
+            cc.tokens.extend(impl_details_cc.tokens);
+            rs.extend(impl_details_rs);
+        }
+        MixedSnippet { cc, rs, cc_out }
     };
 
     let mut result = vec![
@@ -1378,728 +4058,7419 @@ fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSn
     result
 }
 
-/// Formats the forward declaration of an algebraic data type (an ADT - a
-/// struct, an enum, or a union), returning something like
-/// `quote!{ struct SomeStruct; }`.
+/// Formats the definitions of the C++ destructor and the C++ move operations
+/// of an ADT that provides a custom `Drop` impl or requires drop glue (see
+/// `AdtCoreBindings::needs_drop`).  Returns the C++ and the Rust parts of the
+/// bindings.
 ///
-/// Will panic if `def_id` doesn't identify an ADT that can be successfully
-/// handled by `format_adt_core`.
-fn format_fwd_decl(tcx: TyCtxt, def_id: LocalDefId) -> TokenStream {
-    let def_id = def_id.to_def_id(); // LocalDefId -> DefId conversion.
+/// The C++ definitions call Rust thunks that 1) drop the Rust value (from the
+/// destructor), and 2) move the Rust value out of the moved-from object,
+/// leaving `Default::default()` behind (from the move constructor and the move
+/// assignment operator).  This way the C++ destructor can run for a moved-from
+/// object - e.g. after returning the ADT by value through
+/// `crubit::ReturnValueSlot`.
+fn format_adt_drop_and_move(input: &Input, core: &AdtCoreBindings) -> (TokenStream, TokenStream) {
+    let def_path_hash = format_def_path_hash(input.tcx, core.def_id.expect_local());
+    let drop_thunk = format_ident!("__crubit_thunk_drop_{def_path_hash}");
+    let move_thunk = format_ident!("__crubit_thunk_move_{def_path_hash}");
+    let move_assign_thunk = format_ident!("__crubit_thunk_move_assign_{def_path_hash}");
+    let cc_name = &core.cc_name;
+    let rs_name = &core.rs_name;
+    let import_attr = input.format_cc_import_attr();
+    let cc = quote! {
+        __NEWLINE__
+        namespace __crubit_internal {
+            extern "C" #import_attr void #drop_thunk(#cc_name* __self);
+            extern "C" #import_attr void #move_thunk(#cc_name* __self, #cc_name* __other);
+            extern "C" #import_attr void #move_assign_thunk(
+                #cc_name* __self, #cc_name* __other);
+        }
+        inline #cc_name::~#cc_name() {
+            __crubit_internal::#drop_thunk(this);
+        }
+        inline #cc_name::#cc_name(#cc_name&& other) {
+            __crubit_internal::#move_thunk(this, &other);
+        }
+        inline #cc_name& #cc_name::operator=(#cc_name&& other) {
+            // Self-assignment would alias the `&mut` references of the Rust thunk.
+            if (this != &other) {
+                __crubit_internal::#move_assign_thunk(this, &other);
+            }
+            return *this;
+        }
+        __NEWLINE__
+    };
+    let rs = quote! {
+        #[no_mangle]
+        extern "C" fn #drop_thunk(__self: &mut ::core::mem::MaybeUninit<#rs_name>) {
+            unsafe { __self.assume_init_drop() };
+        }
+        #[no_mangle]
+        extern "C" fn #move_thunk(
+            __self: &mut ::core::mem::MaybeUninit<#rs_name>,
+            __other: &mut #rs_name,
+        ) {
+            __self.write(::core::mem::take(__other));
+        }
+        #[no_mangle]
+        extern "C" fn #move_assign_thunk(__self: &mut #rs_name, __other: &mut #rs_name) {
+            *__self = ::core::mem::take(__other);
+        }
+    };
+    (cc, rs)
+}
 
-    // `format_fwd_decl` should only be called for items from
-    // `CcPrerequisites::fwd_decls` and `fwd_decls` should only contain ADTs
-    // that `format_adt_core` succeeds for.
-    let AdtCoreBindings { keyword, cc_name, .. } = format_adt_core(tcx, def_id)
-        .expect("`format_fwd_decl` should only be called if `format_adt_core` succeeded");
+/// Formats the definition of the `private` default C++ constructor of an ADT
+/// with `AdtCoreBindings::has_private_default_ctor`.  Returns the C++ and the
+/// Rust parts of the bindings.
+///
+/// The C++ constructor calls a Rust thunk that initializes the C++ object with
+/// `Default::default()`.
+fn format_adt_default_ctor(input: &Input, core: &AdtCoreBindings) -> (TokenStream, TokenStream) {
+    let def_path_hash = format_def_path_hash(input.tcx, core.def_id.expect_local());
+    let default_thunk = format_ident!("__crubit_thunk_default_{def_path_hash}");
+    let cc_name = &core.cc_name;
+    let rs_name = &core.rs_name;
+    let import_attr = input.format_cc_import_attr();
+    let cc = quote! {
+        __NEWLINE__
+        namespace __crubit_internal {
+            extern "C" #import_attr void #default_thunk(#cc_name* __self);
+        }
+        inline #cc_name::#cc_name() {
+            __crubit_internal::#default_thunk(this);
+        }
+        __NEWLINE__
+    };
+    let rs = quote! {
+        #[no_mangle]
+        extern "C" fn #default_thunk(__self: &mut ::core::mem::MaybeUninit<#rs_name>) {
+            __self.write(<#rs_name as ::core::default::Default>::default());
+        }
+    };
+    (cc, rs)
+}
 
-    quote! { #keyword #cc_name; }
+/// The C++ builder class of an ADT with the `#[crubit::cc_builder]` annotation
+/// (see `format_adt_builder`).
+struct AdtBuilderBindings {
+    /// The name of the builder class - e.g. `SomeStructBuilder`.
+    cc_name: TokenStream,
+
+    /// The definition of the builder class (emitted right after the
+    /// definition of the ADT).
+    main_api: CcSnippet,
+
+    impl_details_cc: CcSnippet,
+    impl_details_rs: TokenStream,
 }
 
-fn format_source_location(tcx: TyCtxt, local_def_id: LocalDefId) -> String {
-    let def_span = tcx.def_span(local_def_id);
-    let rustc_span::FileLines { file, lines } =
-        match tcx.sess().source_map().span_to_lines(def_span) {
-            Ok(filelines) => filelines,
-            Err(_) => return "unknown location".to_string(),
-        };
-    let file_name = file.name.prefer_local().to_string();
-    // Note: line_index starts at 0, while CodeSearch starts indexing at 1.
-    let line_number = lines[0].line_index + 1;
-    let google3_prefix = {
-        // If rustc_span::FileName isn't a 'real' file, then it's surrounded by by angle
-        // brackets, thus don't prepend "google3/" prefix.
-        if file.name.is_real() { "google3/" } else { "" }
-    };
-    format!("{google3_prefix}{file_name};l={line_number}")
-}
+/// Formats a fluent C++ builder of the ADT represented by `core` - e.g.
+/// `SomeStructBuilder().set_x(1).set_y(2).Build()`.
+///
+/// The builder starts from `Default::default()` (obtained from a Rust thunk, or
+/// from the `private` default constructor of the ADT - see
+/// `AdtCoreBindings::has_private_default_ctor`), and then its setters assign the fields of the C++ struct directly (the
+/// builder is a `friend` of the struct), so that setting the fields doesn't
+/// require any calls into Rust.
+fn format_adt_builder(input: &Input, core: &AdtCoreBindings) -> Result<AdtBuilderBindings> {
+    let tcx = input.tcx;
+    let ty = rustc_compat::type_of(tcx, core.def_id);
+    let adt_def = ty.ty_adt_def().expect("`core.def_id` needs to identify an ADT");
+    ensure!(adt_def.is_struct(), "Builders are only supported for structs");
+    ensure!(
+        implements_default(tcx, ty),
+        "Builders are only supported for structs that implement `Default`"
+    );
 
-/// Formats the doc comment (if any) associated with the item identified by
-/// `local_def_id`, and appends the source location at which the item is
-/// defined.
-fn format_doc_comment(tcx: TyCtxt, local_def_id: LocalDefId) -> TokenStream {
-    let hir_id = tcx.local_def_id_to_hir_id(local_def_id);
-    let doc_comment = tcx
-        .hir()
-        .attrs(hir_id)
-        .iter()
-        .filter_map(|attr| attr.doc_str())
-        .map(|symbol| symbol.to_string())
-        .chain(once(format!("Generated from: {}", format_source_location(tcx, local_def_id))))
-        .join("\n\n");
-    quote! { __COMMENT__ #doc_comment}
-}
+    let adt_cc_name = &core.cc_name;
+    let adt_rs_name = &core.rs_name;
+    let cc_name = format_cc_ident(&format!("{adt_cc_name}Builder"))?;
+    let mut main_api_prereqs = CcPrerequisites::default();
+    main_api_prereqs.includes.insert(CcInclude::utility());
 
-/// Formats a HIR item idenfied by `def_id`.  Returns `None` if the item
-/// can be ignored. Returns an `Err` if the definition couldn't be formatted.
-///
-/// Will panic if `def_id` is invalid (i.e. doesn't identify a HIR item).
-fn format_item(input: &Input, def_id: LocalDefId) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
-    // TODO(b/262052635): When adding support for re-exports we may need to change
-    // `is_directly_public` below into `is_exported`.  (OTOH such change *alone* is undesirable,
-    // because it would mean exposing items from a private module.  Exposing a private module is
-    // undesirable, because it would mean that changes of private implementation details of the
-    // crate could become breaking changes for users of the generated C++ bindings.)
-    if !input.tcx.effective_visibilities(()).is_directly_public(def_id) {
-        return Ok(vec![]);
-    }
+    // TODO(b/259749095): Support non-empty set of generic parameters.
+    let substs_ref = ty::List::empty().as_substs();
+    let setters = adt_def
+        .all_fields()
+        .sorted_by_key(|f| tcx.def_span(f.did))
+        .map(|field_def| -> Result<TokenStream> {
+            let name = field_def.ident(tcx);
+            ensure!(
+                !name.as_str().starts_with(|c: char| c.is_ascii_digit()),
+                "Builders are not supported for tuple structs"
+            );
+            ensure!(field_def.vis == ty::Visibility::Public, "Field `{name}` is not public");
+            // Fields of other types may require running C++ constructors or destructors (or
+            // Rust drop glue) when assigned.
+            let field_ty = field_def.ty(tcx, substs_ref);
+            ensure!(
+                is_c_abi_compatible_by_value(field_ty),
+                "The type of field `{name}` is not supported by builders yet: {field_ty}"
+            );
+            let cc_type = format_ty_for_cc(input, field_ty)
+                .with_context(|| format!("Error formatting the type of field `{name}`"))?
+                .into_tokens(&mut main_api_prereqs);
+            let field_name = format_cc_ident(name.as_str())?;
+            let setter_name = format_cc_ident(&format!("set_{name}"))?;
+            Ok(quote! {
+                #cc_name& #setter_name(#cc_type #field_name) & {
+                    value_.#field_name = #field_name;
+                    return *this;
+                }
+                #cc_name&& #setter_name(#cc_type #field_name) && {
+                    value_.#field_name = #field_name;
+                    return std::move(*this);
+                }
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    match input.tcx.hir().expect_item(def_id) {
-        Item { kind: ItemKind::Struct(_, generics) |
-                     ItemKind::Enum(_, generics) |
-                     ItemKind::Union(_, generics),
-               .. } if !generics.params.is_empty() => {
-            bail!("Generic types are not supported yet (b/259749095)");
-        },
-        Item { kind: ItemKind::Fn(..), .. } => format_fn(input, def_id),
-        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. } =>
-            format_adt_core(input.tcx, def_id.to_def_id())
-                .map(|core| format_adt(input, &core)),
-        Item { kind: ItemKind::Impl(_), .. } |  // Handled by `format_adt`
-        Item { kind: ItemKind::Mod(_), .. } =>  // Handled by `format_crate`
-            Ok(vec![]),
-        Item { kind, .. } => bail!("Unsupported rustc_hir::hir::ItemKind: {}", kind.descr()),
-    }
-}
+    let doc_comment = format!(
+        "Builder of `{adt_cc_name}` values.  The fields that are not set by the \
+         builder keep their values from `{adt_cc_name}::default()` in Rust."
+    );
+    let main_api = CcSnippet {
+        prereqs: main_api_prereqs,
+        tokens: quote! {
+            __NEWLINE__ __COMMENT__ #doc_comment
+            class #cc_name final {
+                public:
+                    #cc_name();
+                    #( #setters )*
+                    #adt_cc_name Build() && { return std::move(value_); }
 
-/// Formats a C++ comment explaining why no bindings have been generated for
-/// `local_def_id`.
-fn format_unsupported_def(
-    tcx: TyCtxt,
-    local_def_id: LocalDefId,
-    err: anyhow::Error,
-) -> (SnippetKey, MixedSnippet) {
-    let source_loc = format_source_location(tcx, local_def_id);
-    let name = tcx.def_path_str(local_def_id.to_def_id());
+                private:
+                    #adt_cc_name value_;
+            };
+            __NEWLINE__
+        },
+    };
 
-    // https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
-    // says: To print causes as well [...], use the alternate selector “{:#}”.
-    let msg = format!("Error generating bindings for `{name}` defined at {source_loc}: {err:#}");
-    let cc = CcSnippet::new(quote! { __NEWLINE__ __NEWLINE__ __COMMENT__ #msg __NEWLINE__ });
+    // The `private` default constructor of the ADT is accessible to the builder (a `friend` of
+    // the ADT), and already comes with its own thunk.
+    if core.has_private_default_ctor {
+        let impl_details_cc = CcSnippet::new(quote! {
+            __NEWLINE__
+            inline #cc_name::#cc_name() : value_() {}
+            __NEWLINE__
+        });
+        return Ok(AdtBuilderBindings {
+            cc_name,
+            main_api,
+            impl_details_cc,
+            impl_details_rs: quote! {},
+        });
+    }
+    let default_thunk = format_ident!(
+        "__crubit_thunk_default_{}",
+        format_def_path_hash(tcx, core.def_id.expect_local())
+    );
+    let mut impl_details_prereqs = CcPrerequisites::default();
+    impl_details_prereqs.includes.insert(CcInclude::utility());
+    impl_details_prereqs.includes.insert(input.support_header("internal/return_value_slot.h"));
+    let import_attr = input.format_cc_import_attr();
+    let impl_details_cc = CcSnippet {
+        prereqs: impl_details_prereqs,
+        tokens: quote! {
+            __NEWLINE__
+            namespace __crubit_internal {
+                extern "C" #import_attr void #default_thunk(#adt_cc_name* __ret_ptr);
+            }
+            inline #cc_name::#cc_name() : value_([] {
+                crubit::ReturnValueSlot<#adt_cc_name> __ret_slot;
+                __crubit_internal::#default_thunk(__ret_slot.Get());
+                return std::move(__ret_slot).AssumeInitAndTakeValue();
+            }()) {}
+            __NEWLINE__
+        },
+    };
+    let impl_details_rs = quote! {
+        #[no_mangle]
+        extern "C" fn #default_thunk(__ret_slot: &mut ::core::mem::MaybeUninit<#adt_rs_name>) {
+            __ret_slot.write(<#adt_rs_name as ::core::default::Default>::default());
+        }
+    };
 
-    (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, cc.into())
+    Ok(AdtBuilderBindings { cc_name, main_api, impl_details_cc, impl_details_rs })
 }
 
-/// Formats all public items from the Rust crate being compiled.
-fn format_crate(input: &Input) -> Result<Output> {
+/// Formats the public items from the inherent `impl`s of the ADT represented by
+/// `core`.  Items that can't be formatted are represented by a comment from
+/// `format_unsupported_def`.
+fn format_impl_items(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSnippet)> {
     let tcx = input.tcx;
-    let mut bindings: HashMap<SnippetKey, MixedSnippet> = tcx
-        .hir()
-        .items()
-        .flat_map(|item_id| {
-            let def_id: LocalDefId = item_id.owner_id.def_id;
-            format_item(input, def_id)
-                .unwrap_or_else(|err| vec![format_unsupported_def(tcx, def_id, err)])
-                .into_iter()
+    let mut cc_method_names = HashSet::new();
+    get_bound_impl_ids(input, core.def_id)
+        .into_iter()
+        .map(|impl_id| tcx.hir().expect_item(impl_id))
+        .flat_map(|item| match &item.kind {
+            ItemKind::Impl(impl_) => {
+                let is_trait_impl = impl_.of_trait.is_some();
+                impl_.items.iter().map(move |impl_item_ref| (is_trait_impl, impl_item_ref))
+            }
+            other => panic!("Unexpected `ItemKind` from `get_bound_impl_ids`: {other:?}"),
         })
-        .fold(HashMap::new(), |mut map, (key, value)| {
-            let old_item = map.insert(key, value);
-            assert!(old_item.is_none(), "Duplicated key: {key:?}");
-            map
-        });
-
-    // Find the order of `bindings` that 1) meets the requirements of
-    // `CcPrerequisites::defs` and 2) makes a best effort attempt to keep the
-    // `bindings` in the same order as the source order of the Rust APIs.
-    let ordered_ids = {
-        let toposort::TopoSortResult { ordered: ordered_ids, failed: failed_ids } = {
-            let nodes = bindings.keys().copied();
-            let deps = bindings.iter().flat_map(|(&successor, snippet)| {
-                let predecessors = snippet.cc.prereqs.defs.iter().map(|&def_id|
-                    SnippetKey { def_id, kind: SnippetKind::MainApi }
-                );
-                predecessors.map(move |predecessor| toposort::Dependency { predecessor, successor })
-            });
-            toposort::toposort(nodes, deps, preferred_snippet_order(tcx))
-        };
-        assert_eq!(
-            0,
-            failed_ids.len(),
-            "There are no known scenarios where CcPrerequisites::defs can form \
-                    a dependency cycle. These `LocalDefId`s form an unexpected cycle: {}",
-            failed_ids.into_iter().map(|id| format!("{:?}", id)).join(",")
-        );
-        ordered_ids
-    };
-
-    // Destructure/rebuild `bindings` (in the same order as `ordered_ids`) into
-    // `includes`, and into separate C++ snippets and Rust snippets.
-    let (includes, ordered_cc, rs_body) = {
-        let mut already_declared = HashSet::new();
-        let mut fwd_decls = HashSet::new();
-        let mut includes = BTreeSet::new();
-        let mut ordered_cc = Vec::new();
-        let mut rs_body = quote! {};
-        for key in ordered_ids.into_iter() {
-            let mod_path = FullyQualifiedName::new(tcx, key.def_id.to_def_id()).mod_path;
-            let MixedSnippet {
-                rs: inner_rs,
-                cc: CcSnippet {
-                    tokens: cc_tokens,
-                    prereqs: CcPrerequisites {
-                        includes: mut inner_includes,
-                        fwd_decls: inner_fwd_decls,
-                        .. // `defs` have already been utilized by `toposort` above
+        .flat_map(|(is_trait_impl, impl_item_ref)| {
+            let def_id = impl_item_ref.id.owner_id.def_id;
+            // The items of trait `impl`s are as visible as the trait (which
+            // `get_bound_impl_ids` has already checked).
+            let is_public =
+                is_trait_impl || tcx.effective_visibilities(()).is_directly_public(def_id);
+            if !is_public || !input.is_selected(def_id) {
+                return vec![];
+            }
+            let result = match impl_item_ref.kind {
+                // The accessors from the `impl`s of `AsRef` and `Borrow` are only bound if C++ can
+                // represent the returned view, and if they don't clash with other methods (e.g.
+                // with an inherent `as_str` method, or with `AsRef<str>` for `Borrow<str>`).
+                AssocItemKind::Fn { .. } if is_cc_view_trait_method(tcx, def_id.to_def_id()) => {
+                    let cc_name = get_fn_cc_name(input, def_id.to_def_id());
+                    let is_accessor = get_cc_view_accessor_name(tcx, def_id.to_def_id()).is_some();
+                    if !is_accessor || cc_method_names.contains(&cc_name) {
+                        return vec![];
+                    }
+                    let Ok(snippets) = format_fn(input, def_id) else { return vec![] };
+                    cc_method_names.insert(cc_name);
+                    Ok(snippets)
+                }
+                AssocItemKind::Fn { .. } => {
+                    // Methods from different `impl`s (e.g. an inherent method and a trait method)
+                    // may have the same name, but C++ member functions can't be disambiguated by
+                    // the trait (and overloading on the qualifiers of `this` is not enough).
+                    // C++ operators are the exception: `impl`s of an operator trait with
+                    // different `Rhs` types (e.g. `AddAssign<i32>` and `AddAssign<Foo>`) become
+                    // overloads of the operator.
+                    let cc_name = get_fn_cc_name(input, def_id.to_def_id());
+                    let is_operator = get_cc_operator(tcx, def_id.to_def_id()).is_some();
+                    if is_operator || cc_method_names.insert(cc_name) {
+                        format_fn(input, def_id)
+                    } else {
+                        Err(coded_anyhow!(
+                            ErrorCode::DuplicateCcName,
+                            "The C++ name `{cc_name}` is already used by another method"
+                        ))
                     }
                 }
-            } = bindings.remove(&key).unwrap();
+                other => Err(coded_anyhow!(
+                    ErrorCode::UnsupportedItemKind,
+                    "Unsupported `impl` item kind: {other:?}"
+                )),
+            };
+            let result = result.map_err(Rc::new);
+            record_item_ir(input, def_id, &result);
+            result.unwrap_or_else(|err| vec![format_unsupported_def(input, def_id, &err)])
+        })
+        .collect()
+}
 
-            fwd_decls.extend(inner_fwd_decls.difference(&already_declared).copied());
-            already_declared.insert(key.def_id);
-            already_declared.extend(inner_fwd_decls.into_iter());
+/// Returns the `impl`s whose methods are bound as member functions of the C++
+/// bindings of the ADT identified by `adt_def_id`: the inherent `impl`s, and
+/// (except in `Input::c_mode`) the `impl`s of the traits that `format_trait`
+/// formats as C++20 concepts (public, non-generic traits from the current
+/// crate), of the operator traits that are bound as C++ operators (see
+/// `CC_OPERATOR_TRAITS`), and of the traits that are bound as view accessors
+/// (see `CC_VIEW_TRAITS`).
+fn get_bound_impl_ids(input: &Input, adt_def_id: DefId) -> Vec<LocalDefId> {
+    let tcx = input.tcx;
+    let inherent_impl_ids = tcx.inherent_impls(adt_def_id).iter().filter_map(|id| id.as_local());
+    let trait_impl_ids = if input.c_mode {
+        vec![]
+    } else {
+        let self_ty = rustc_compat::type_of(tcx, adt_def_id);
+        tcx.all_local_trait_impls(())
+            .iter()
+            .filter(|(&trait_id, _)| {
+                let is_concept = trait_id.is_local()
+                    && is_directly_public(tcx, trait_id)
+                    && tcx.generics_of(trait_id).count() == 1; // Just `Self`.
+                is_concept
+                    || get_cc_operator_trait(tcx, trait_id).is_some()
+                    || is_cc_view_trait(tcx, trait_id)
+            })
+            .flat_map(|(_, impl_ids)| impl_ids.iter().copied())
+            .filter(|&impl_id| rustc_compat::type_of(tcx, impl_id.to_def_id()) == self_ty)
+            .collect_vec()
+    };
+    inherent_impl_ids.chain(trait_impl_ids).collect()
+}
 
-            includes.append(&mut inner_includes);
-            ordered_cc.push((mod_path, cc_tokens));
-            rs_body.extend(inner_rs);
-        }
+/// Formats a trait as a C++20 concept that mirrors the method surface of the
+/// trait.  For example, the following trait:
+///
+/// ```ignore
+/// pub trait Shape {
+///     fn area(&self) -> f64;
+///     fn scale(&mut self, factor: f64);
+/// }
+/// ```
+///
+/// is formatted as:
+///
+/// ```cpp
+/// template <typename T>
+/// concept Shape = requires {
+///     { std::declval<const T&>().area() } -> std::same_as<double>;
+///     { std::declval<T&>().scale(std::declval<double>()) } -> std::same_as<void>;
+/// };
+/// ```
+///
+/// The C++ bindings of the ADTs that implement the trait satisfy the concept,
+/// because the methods from the trait `impl`s are bound as member functions
+/// (see `get_bound_impl_ids`).  This way C++ templates can check their
+/// template parameters against the contract of the Rust trait.
+fn format_trait(
+    input: &Input,
+    local_def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    coded_ensure!(
+        ErrorCode::TraitWithoutCpp20,
+        !input.c_mode && input.cc_std >= CcStandard::Cxx20,
+        "Traits are only supported (as C++20 concepts) with `--cc-std=c++20`"
+    );
+    coded_ensure!(
+        ErrorCode::UnsupportedTrait,
+        tcx.generics_of(def_id).count() == 1,
+        "Generic traits are not supported yet"
+    );
+    coded_ensure!(
+        ErrorCode::UnsupportedTrait,
+        tcx.super_predicates_of(def_id).predicates.is_empty(),
+        "Traits with supertraits are not supported yet"
+    );
+    coded_ensure!(
+        ErrorCode::UnsupportedTrait,
+        tcx.trait_def(def_id).unsafety == Unsafety::Normal,
+        "`unsafe` traits are not supported"
+    );
 
-        // Prepend `fwd_decls` (in the original source order) to `ordered_cc`.
-        let fwd_decls = fwd_decls
-            .into_iter()
-            .sorted_by_key(|def_id| tcx.def_span(*def_id))
-            .map(|local_def_id| {
-                let mod_path = FullyQualifiedName::new(tcx, local_def_id.to_def_id()).mod_path;
-                (mod_path, format_fwd_decl(tcx, local_def_id))
+    let mut prereqs = CcPrerequisites::default();
+    prereqs.includes.insert(CcInclude::concepts());
+    prereqs.includes.insert(CcInclude::utility());
+    let self_param = tcx.types.self_param;
+    let requirements = tcx
+        .associated_items(def_id)
+        .in_definition_order()
+        .map(|item| -> Result<TokenStream> {
+            let name = item.name;
+            coded_ensure!(
+                ErrorCode::UnsupportedTrait,
+                item.kind == ty::AssocKind::Fn,
+                "Only methods are supported in traits (`{name}` is not a method)"
+            );
+            coded_ensure!(
+                ErrorCode::GenericFunction,
+                tcx.generics_of(item.def_id).count() == 1,
+                "Generic methods are not supported yet (`{name}`)"
+            );
+            let sig = get_fn_sig(tcx, item.def_id);
+            ensure!(
+                sig.unsafety == Unsafety::Normal,
+                "`unsafe` methods are not supported (`{name}`)"
+            );
+            let mut format_ty = |ty: Ty, is_ret_ty: bool| -> Result<TokenStream> {
+                if ty == self_param {
+                    return Ok(quote! { T });
+                }
+                let snippet = if is_ret_ty {
+                    format_ret_ty_for_cc(input, ty)?
+                } else {
+                    format_ty_for_cc(input, ty)?
+                };
+                Ok(snippet.into_tokens(&mut prereqs))
+            };
+            let (receiver, param_tys) = if item.fn_has_self_parameter {
+                let self_ty = sig.inputs()[0];
+                let receiver = match self_ty.kind() {
+                    _ if self_ty == self_param => quote! { std::declval<T>(). },
+                    ty::TyKind::Ref(_, referent, Mutability::Not) if *referent == self_param => {
+                        quote! { std::declval<const T&>(). }
+                    }
+                    ty::TyKind::Ref(_, referent, Mutability::Mut) if *referent == self_param => {
+                        quote! { std::declval<T&>(). }
+                    }
+                    _ => coded_bail!(
+                        ErrorCode::SelfParameter,
+                        "`self: {self_ty}` parameter is not supported yet (`{name}`)"
+                    ),
+                };
+                (receiver, &sig.inputs()[1..])
+            } else {
+                (quote! { T:: }, sig.inputs())
+            };
+            let args = param_tys
+                .iter()
+                .map(|&ty| {
+                    let ty = format_ty(ty, false)?;
+                    Ok(quote! { std::declval<#ty>() })
+                })
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Error formatting the parameters of `{name}`"))?;
+            let ret_ty = format_ty(sig.output(), true)
+                .with_context(|| format!("Error formatting the return type of `{name}`"))?;
+            let method_name = format_cc_ident(get_fn_cc_name(input, item.def_id).as_str())?;
+            Ok(quote! {
+                { #receiver #method_name( #( #args ),* ) } -> std::same_as<#ret_ty>;
             })
-            .collect_vec();
-        let ordered_cc = fwd_decls.into_iter().chain(ordered_cc.into_iter()).collect_vec();
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // An empty `requires` expression is ill-formed (and the contract of marker traits can't be
+    // expressed as a concept).
+    coded_ensure!(
+        ErrorCode::UnsupportedTrait,
+        !requirements.is_empty(),
+        "Traits without methods are not supported"
+    );
 
-        (includes, ordered_cc, rs_body)
+    let cc_name = {
+        let cc_name =
+            FullyQualifiedName::new(input, def_id).cc_name.unwrap_or_else(|| tcx.item_name(def_id));
+        format_cc_ident(cc_name.as_str()).context("Error formatting item name")?
+    };
+    let doc_comment = format_doc_comment(input, local_def_id);
+    prereqs.move_defs_to_fwd_decls();
+    let main_api = CcSnippet {
+        prereqs,
+        tokens: quote! {
+            __NEWLINE__ #doc_comment
+            template <typename T>
+            concept #cc_name = requires {
+                #( #requirements )*
+            };
+            __NEWLINE__
+        },
     };
+    Ok(vec![(SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into())])
+}
 
-    // Generate top-level elements of the C++ header file.
-    let h_body = {
-        // TODO(b/254690602): Decide whether using `#crate_name` as the name of the
-        // top-level namespace is okay (e.g. investigate if this name is globally
-        // unique + ergonomic).
-        let crate_name = format_cc_ident(tcx.crate_name(LOCAL_CRATE).as_str())?;
+/// Formats an algebraic data type (an ADT - a struct, an enum, or a union)
+/// represented by `core` for `Input::c_mode`.  C has no access control, so
+/// all the fields are replaced with an opaque blob of bytes (and the ADT can
+/// only be used behind a pointer, because `format_fn_for_c` doesn't support
+/// passing ADTs by value).  Static methods become free functions.
+fn format_adt_for_c(
+    input: &Input,
+    core: &AdtCoreBindings,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
 
-        let includes = format_cc_includes(&includes);
-        let ordered_cc = format_namespace_bound_cc_tokens(ordered_cc);
-        quote! {
-            #includes
-            __NEWLINE__ __NEWLINE__
-            namespace #crate_name {
+    // `format_adt_for_c` should only be called for local ADTs.
+    let local_def_id = core.def_id.expect_local();
+
+    let c_name = FullyQualifiedName::new(input, core.def_id).format_for_c(input);
+    let c_name = format_cc_ident(&c_name).context("Error formatting item name")?;
+    let adt_rs_name = &core.rs_name;
+    let keyword = &core.keyword;
+    let alignment = Literal::u64_unsuffixed(core.alignment_in_bytes);
+    let size = Literal::u64_unsuffixed(core.size_in_bytes);
+
+    let main_api = {
+        let doc_comment = format_doc_comment(input, local_def_id);
+        CcSnippet::with_include(
+            quote! {
+                __NEWLINE__ #doc_comment
+                typedef #keyword #c_name {
+                    alignas(#alignment) unsigned char __opaque_blob_of_bytes[#size];
+                } #c_name;
                 __NEWLINE__
-                #ordered_cc
+            },
+            CcInclude::stdalign_h(),
+        )
+    };
+    let impl_details = {
+        let mut cc = CcSnippet::with_include(
+            quote! {
                 __NEWLINE__
-            }
-            __NEWLINE__
-        }
+                static_assert(
+                    sizeof(#c_name) == #size,
+                    "Verify that struct layout didn't change since this header got generated");
+                static_assert(
+                    alignof(#c_name) == #alignment,
+                    "Verify that struct layout didn't change since this header got generated");
+                __NEWLINE__
+            },
+            CcInclude::assert_h(),
+        );
+        cc.prereqs.includes.insert(CcInclude::stdalign_h());
+        cc.prereqs.defs.insert(local_def_id);
+        let core_crate = input.format_rs_core_crate();
+        let rs = quote! {
+            const _: () = assert!(#core_crate::mem::size_of::<#adt_rs_name>() == #size);
+            const _: () = assert!(#core_crate::mem::align_of::<#adt_rs_name>() == #alignment);
+        };
+        MixedSnippet { cc, rs, ..Default::default() }
     };
 
-    Ok(Output { h_body, rs_body })
+    let mut result = vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ];
+    result.extend(format_impl_items(input, core));
+    Ok(result)
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
+/// Formats a fieldless enum with a primitive representation (e.g.
+/// `#[repr(i32)]`) as a C++ `enum class` with the same underlying type and with
+/// the same discriminant values.  For example, the following enum:
+///
+/// ```ignore
+/// #[repr(u8)]
+/// pub enum Color {
+///     Red = 1,
+///     Green = 2,
+/// }
+/// ```
+///
+/// is formatted as `enum class Color : std::uint8_t { Red = 1, Green = 2 };`,
+/// plus `ColorFromInt` and `ColorToInt` helpers that convert between the enum
+/// and its underlying type (`ColorFromInt` returns `std::nullopt` for values
+/// that are not valid discriminants - C++ can't tell them apart from
+/// enumerators, but in Rust such values are Undefined Behavior).
+///
+/// A `#[non_exhaustive]` enum may get new variants in the future, which the
+/// C++ code compiled against the current bindings wouldn't know about.  Such an
+/// enum is therefore formatted as a C++ `class` that wraps the underlying type,
+/// and that has 1) a named constant for each of the current variants (e.g.
+/// `Color::Red`) and 2) `FromInt`, `ToInt`, and `IsKnown` member functions.
+/// Values received from Rust are passed through unchanged (even if they are
+/// unknown to the C++ code), but C++ can't create values other than the named
+/// constants.
+///
+/// Like `format_adt`, this function is infallible - `format_adt_core` has
+/// already verified the underlying type and the names of the variants.
+fn format_enum_class(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSnippet)> {
+    let tcx = input.tcx;
 
-    use anyhow::Result;
-    use itertools::Itertools;
-    use proc_macro2::TokenStream;
-    use quote::quote;
-    use rustc_middle::ty::{Ty, TyCtxt};
-    use rustc_span::def_id::LocalDefId;
+    // `format_enum_class` should only be called for local ADTs.
+    let local_def_id = core.def_id.expect_local();
 
-    use crate::run_compiler::tests::run_compiler_for_testing;
-    use code_gen_utils::format_cc_includes;
-    use token_stream_matchers::{
-        assert_cc_matches, assert_cc_not_matches, assert_rs_matches, assert_rs_not_matches,
-    };
+    let adt_def = tcx.adt_def(core.def_id);
+    let adt_cc_name = &core.cc_name;
+    let adt_rs_name = &core.rs_name;
+    let underlying_type =
+        core.enum_underlying_type.as_ref().expect("Caller should verify that this is an enum");
+    let (variant_names, discriminants): (Vec<_>, Vec<_>) = adt_def
+        .discriminants(tcx)
+        .map(|(variant_index, discr)| {
+            let variant_name = format_cc_ident(adt_def.variant(variant_index).name.as_str())
+                .expect("`format_adt_core` should have verified the variant names");
+            // The `Display` impl of `Discr` takes the signedness of the discriminant into account.
+            let discr = discr.to_string().parse::<i128>().expect("Discriminants are integers");
+            (variant_name, format_cc_discriminant(discr))
+        })
+        .unzip();
 
-    #[test]
+    let main_api = {
+        let doc_comment = format_doc_comment(input, local_def_id);
+        let rust_type = FullyQualifiedName::new(input, core.def_id).format_for_rs_as_string();
+        let rust_type_annotation = quote! { [[clang::annotate("crubit_rust_type", #rust_type)]] };
+        let tokens = if adt_def.is_variant_list_non_exhaustive() {
+            let from_int_doc = format!(
+                "Returns the `{adt_cc_name}` with the given discriminant, or `std::nullopt` if \
+                 `value` is not a discriminant of any of the named constants above."
+            );
+            let is_known_doc = "Returns `false` if the value has been received from Rust, but \
+                                it doesn't correspond to any of the named constants above \
+                                (e.g. because it represents a variant that has been added \
+                                after the bindings got generated).";
+            quote! {
+                __NEWLINE__ #doc_comment
+                class #rust_type_annotation #adt_cc_name final {
+                    public:
+                        #( static const #adt_cc_name #variant_names; )*
+                        __NEWLINE__
+                        __COMMENT__ #from_int_doc
+                        static constexpr std::optional<#adt_cc_name> FromInt(
+                            #underlying_type value) {
+                            switch (value) {
+                                #( case #discriminants: )*
+                                    return #adt_cc_name(value);
+                                default:
+                                    return std::nullopt;
+                            }
+                        }
+                        __NEWLINE__
+                        __COMMENT__ "Returns the discriminant of the value."
+                        constexpr #underlying_type ToInt() const { return value_; }
+                        __NEWLINE__
+                        __COMMENT__ #is_known_doc
+                        constexpr bool IsKnown() const { return FromInt(value_).has_value(); }
+                        __NEWLINE__
+                        constexpr bool operator==(#adt_cc_name other) const {
+                            return value_ == other.value_;
+                        }
+                        constexpr bool operator!=(#adt_cc_name other) const {
+                            return value_ != other.value_;
+                        }
+                    private:
+                        explicit constexpr #adt_cc_name(#underlying_type value) : value_(value) {}
+                        #underlying_type value_;
+                };
+                __NEWLINE__
+                #(
+                    inline constexpr #adt_cc_name #adt_cc_name::#variant_names =
+                        #adt_cc_name(#discriminants);
+                )*
+                __NEWLINE__
+            }
+        } else {
+            let from_int = format_cc_ident(&format!("{adt_cc_name}FromInt")).expect(
+                "Appending a suffix to a valid identifier should result in a valid identifier",
+            );
+            let to_int = format_cc_ident(&format!("{adt_cc_name}ToInt")).expect(
+                "Appending a suffix to a valid identifier should result in a valid identifier",
+            );
+            let from_int_doc = format!(
+                "Returns the `{adt_cc_name}` with the given discriminant, or `std::nullopt` if \
+                 `value` is not a discriminant of any of the `{adt_cc_name}` variants."
+            );
+            let to_int_doc = "Returns the discriminant of `value`.";
+            quote! {
+                __NEWLINE__ #doc_comment
+                enum class #rust_type_annotation #adt_cc_name : #underlying_type {
+                    #( #variant_names = #discriminants, )*
+                };
+                __NEWLINE__
+                __COMMENT__ #from_int_doc
+                constexpr std::optional<#adt_cc_name> #from_int(#underlying_type value) {
+                    switch (value) {
+                        #( case #discriminants: return #adt_cc_name::#variant_names; )*
+                        default: return std::nullopt;
+                    }
+                }
+                __NEWLINE__
+                __COMMENT__ #to_int_doc
+                constexpr #underlying_type #to_int(#adt_cc_name value) {
+                    return static_cast<#underlying_type>(value);
+                }
+                __NEWLINE__
+            }
+        };
+        let mut prereqs = CcPrerequisites::default();
+        prereqs.includes.insert(CcInclude::cstdint());
+        prereqs.includes.insert(CcInclude::optional());
+        CcSnippet { prereqs, tokens }
+    };
+    let impl_details = {
+        let size = Literal::u64_unsuffixed(core.size_in_bytes);
+        let alignment = Literal::u64_unsuffixed(core.alignment_in_bytes);
+        let assertions = quote! {
+            __NEWLINE__
+            static_assert(
+                sizeof(#adt_cc_name) == #size,
+                "Verify that enum layout didn't change since this header got generated");
+            static_assert(
+                alignof(#adt_cc_name) == #alignment,
+                "Verify that enum layout didn't change since this header got generated");
+            __NEWLINE__
+        };
+        // Like in `format_adt`, the assertions are moved into the companion `.cc` file (if any).
+        let (mut cc, cc_out) = if input.cc_out_h_include.is_some() {
+            (CcSnippet::default(), assertions)
+        } else {
+            (CcSnippet::new(assertions), quote! {})
+        };
+        cc.prereqs.defs.insert(local_def_id);
+        let core_crate = input.format_rs_core_crate();
+        let rs = quote! {
+            const _: () = assert!(#core_crate::mem::size_of::<#adt_rs_name>() == #size);
+            const _: () = assert!(#core_crate::mem::align_of::<#adt_rs_name>() == #alignment);
+        };
+        MixedSnippet { cc, rs, cc_out }
+    };
+
+    let mut result = vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ];
+
+    // C++ `enum class`es can't have member functions, and therefore the methods are reported as
+    // unsupported (rather than silently dropped from the bindings).
+    let impl_items = get_bound_impl_ids(input, core.def_id)
+        .into_iter()
+        .flat_map(|impl_id| tcx.associated_item_def_ids(impl_id.to_def_id()))
+        .filter_map(|def_id| def_id.as_local())
+        .filter(|&def_id| {
+            tcx.effective_visibilities(()).is_directly_public(def_id) && input.is_selected(def_id)
+        })
+        .map(|def_id| {
+            let err = Rc::new(coded_anyhow!(
+                ErrorCode::EnumClassMethod,
+                "C++ `enum class`es can't have member functions"
+            ));
+            record_item_ir(input, def_id, &Err(err.clone()));
+            format_unsupported_def(input, def_id, &err)
+        });
+    result.extend(impl_items);
+    result
+}
+
+/// Formats the value of an enum discriminant as a C++ integer literal.
+fn format_cc_discriminant(value: i128) -> TokenStream {
+    if value < 0 {
+        let abs = value.unsigned_abs();
+        if abs > i64::MAX as u128 {
+            // `9223372036854775808` doesn't fit into any signed C++ integer type.
+            let i64_max = Literal::i64_unsuffixed(i64::MAX);
+            quote! { (-#i64_max - 1) }
+        } else {
+            let abs = Literal::u128_unsuffixed(abs);
+            quote! { -#abs }
+        }
+    } else if value > i64::MAX as i128 {
+        // Unlike decimal literals, hexadecimal literals may have an unsigned type.
+        format!("{value:#x}").parse().expect("Hexadecimal literals should parse")
+    } else {
+        Literal::u128_unsuffixed(value as u128).into_token_stream()
+    }
+}
+
+/// Returns whether `def_id` identifies a `const` or a (non-`mut`) `static`
+/// item of type `&'static str`.
+fn is_str_const(tcx: TyCtxt, def_id: DefId) -> bool {
+    let is_immutable = match tcx.def_kind(def_id) {
+        DefKind::Const => true,
+        DefKind::Static(..) => tcx.static_mutability(def_id) == Some(Mutability::Not),
+        _ => false,
+    };
+    let ty = rustc_compat::type_of(tcx, def_id);
+    is_immutable
+        && matches!(ty.kind(), ty::TyKind::Ref(_, referent, Mutability::Not) if referent.is_str())
+}
+
+/// Formats a `&'static str` constant (see `is_str_const`) as an
+/// `inline constexpr std::string_view`, so that the value can be used in C++
+/// constant expressions.  For example, `pub const VERSION: &str = "1.2.3";` is
+/// formatted as `inline constexpr std::string_view VERSION = "1.2.3";`.
+///
+/// Only constants initialized with a string literal (possibly coming from a
+/// macro like `concat!` or `env!`) are supported.
+fn format_str_const(input: &Input, def_id: LocalDefId) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let body = tcx.hir().body(tcx.hir().body_owned_by(def_id));
+    let value = match body.value.kind {
+        rustc_hir::ExprKind::Lit(lit) => match lit.node {
+            rustc_ast::LitKind::Str(value, _) => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| anyhow!("Only string literals are supported as values of `&str` constants"))?;
+    let value = format_cc_string_literal(value.as_str())?;
+
+    let cc_name = {
+        let item_name = tcx.item_name(def_id.to_def_id());
+        let cc_name =
+            FullyQualifiedName::new(input, def_id.to_def_id()).cc_name.unwrap_or(item_name);
+        format_cc_ident(cc_name.as_str()).context("Error formatting item name")?
+    };
+    let doc_comment = format_doc_comment(input, def_id);
+    let main_api = CcSnippet::with_include(
+        quote! {
+            __NEWLINE__ #doc_comment
+            inline constexpr std::string_view #cc_name = #value;
+            __NEWLINE__
+        },
+        CcInclude::string_view(),
+    );
+    Ok(vec![(SnippetKey { def_id, kind: SnippetKind::MainApi }, main_api.into())])
+}
+
+/// Returns the type of the per-thread values of a thread-local static: either
+/// `T` of a `std::thread::LocalKey<T>` (which is what the `thread_local!` macro
+/// expands into), or the type of a `#[thread_local]` static.  Returns `None` if
+/// `def_id` doesn't identify a thread-local static.
+fn get_thread_local_value_ty<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Option<Ty<'tcx>> {
+    let ty = rustc_compat::type_of(tcx, def_id);
+    match ty.kind() {
+        _ if tcx.is_thread_local_static(def_id) => Some(ty),
+        ty::TyKind::Adt(adt, substs) if is_std_item(tcx, adt.did(), "std::thread::LocalKey") => {
+            Some(substs.type_at(0))
+        }
+        _ => None,
+    }
+}
+
+/// Formats an accessor function for a thread-local static (see
+/// `get_thread_local_value_ty`).  The accessor returns a pointer to the
+/// instance of the static that belongs to the calling thread - e.g. for
+/// `thread_local! { pub static COUNTER: Cell<u32> = Cell::new(0); }` the
+/// generated C++ function is `std::uint32_t* COUNTER()`.
+///
+/// Values wrapped in a `Cell` can be mutated through the returned pointer (see
+/// `Cell::as_ptr`) - otherwise a `const` pointer is returned.
+fn format_thread_local(
+    input: &Input,
+    def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    coded_ensure!(
+        ErrorCode::CMode,
+        !input.c_mode,
+        "Thread-local statics are not supported by `--c-mode`"
+    );
+    ensure!(
+        tcx.static_mutability(def_id.to_def_id()) != Some(Mutability::Mut),
+        "`#[thread_local] static mut` is not supported"
+    );
+    let value_ty = get_thread_local_value_ty(tcx, def_id.to_def_id())
+        .expect("Caller should verify that this is a thread-local static");
+    let cell_value_ty = match value_ty.kind() {
+        ty::TyKind::Adt(adt, substs) if is_std_item(tcx, adt.did(), "std::cell::Cell") => {
+            Some(substs.type_at(0))
+        }
+        _ => None,
+    };
+    let (pointee_ty, const_qualifier, rs_ptr_kind, get_ptr) = match cell_value_ty {
+        Some(cell_value_ty) => {
+            (cell_value_ty, quote! {}, quote! { mut }, quote! { __value.as_ptr() })
+        }
+        None => (value_ty, quote! { const }, quote! { const }, quote! { __value as *const _ }),
+    };
+    let CcSnippet { tokens: cc_pointee, mut prereqs } = format_ty_for_cc(input, pointee_ty)
+        .context("Error formatting the type of the thread-local value")?;
+    prereqs.move_defs_to_fwd_decls();
+    let cc_ptr_ty = quote! { #const_qualifier #cc_pointee * };
+    let rs_ptr_ty = {
+        let rs_pointee = format_ty_for_rs(input, pointee_ty)?;
+        quote! { * #rs_ptr_kind #rs_pointee }
+    };
+
+    let cc_name = {
+        let item_name = tcx.item_name(def_id.to_def_id());
+        let cc_name =
+            FullyQualifiedName::new(input, def_id.to_def_id()).cc_name.unwrap_or(item_name);
+        format_cc_ident(cc_name.as_str()).context("Error formatting item name")?
+    };
+    let rs_path = FullyQualifiedName::new(input, def_id.to_def_id()).format_for_rs();
+    let thunk_name =
+        format_ident!("__crubit_thunk_thread_local_{}", format_def_path_hash(tcx, def_id));
+
+    let main_api = {
+        let doc_comment = format_doc_comment(input, def_id);
+        let per_thread_comment = format!(
+            "Returns a pointer to the calling thread's instance of the thread-local `{cc_name}` \
+             static.  The pointer should not be shared with other threads, and it is only valid \
+             until the calling thread exits."
+        );
+        CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__
+                #doc_comment
+                __COMMENT__ #per_thread_comment
+                inline #cc_ptr_ty #cc_name();
+                __NEWLINE__
+            },
+        }
+    };
+    let impl_details = {
+        let import_attr = input.format_cc_import_attr();
+        let mut cc = CcSnippet::new(quote! {
+            __NEWLINE__
+            namespace __crubit_internal {
+                extern "C" #import_attr #cc_ptr_ty #thunk_name();
+            }
+            inline #cc_ptr_ty #cc_name() {
+                return __crubit_internal::#thunk_name();
+            }
+            __NEWLINE__
+        });
+        cc.prereqs.defs.insert(def_id);
+        let value = if tcx.is_thread_local_static(def_id.to_def_id()) {
+            quote! { { let __value = &#rs_path; #get_ptr } }
+        } else {
+            quote! { #rs_path.with(|__value| #get_ptr) }
+        };
+        let rs = quote! {
+            #[no_mangle]
+            extern "C" fn #thunk_name() -> #rs_ptr_ty {
+                #value
+            }
+        };
+        MixedSnippet { cc, rs, ..Default::default() }
+    };
+    Ok(vec![
+        (SnippetKey { def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ])
+}
+
+/// Formats `value` as a C++ string literal.
+fn format_cc_string_literal(value: &str) -> Result<TokenStream> {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // Other escapes are spelled differently in Rust and C++ (and C++'s `\x` and
+            // octal escapes may swallow the characters that follow them).
+            c if c.is_control() => bail!("Unsupported control character: {c:?}"),
+            // Non-ASCII characters are emitted as UTF-8 (C++ compilers use UTF-8 as the
+            // execution character set by default).
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    // The escapes above are also valid in Rust string literals.
+    Ok(literal.parse().expect("Escaped string literal should parse as a Rust literal"))
+}
+
+/// Formats the forward declaration of an algebraic data type (an ADT - a
+/// struct, an enum, or a union), returning something like
+/// `quote!{ struct SomeStruct; }`.
+///
+/// Will panic if `def_id` doesn't identify an ADT that can be successfully
+/// handled by `format_adt_core`.
+fn format_fwd_decl(input: &Input, def_id: LocalDefId) -> TokenStream {
+    let tcx = input.tcx;
+    let def_id = def_id.to_def_id(); // LocalDefId -> DefId conversion.
+
+    // `format_fwd_decl` should only be called for items from
+    // `CcPrerequisites::fwd_decls` and `fwd_decls` should only contain ADTs
+    // that `format_adt_core` succeeds for.
+    let AdtCoreBindings { keyword, cc_name, enum_underlying_type, .. } =
+        format_adt_core(input, def_id)
+            .expect("`format_fwd_decl` should only be called if `format_adt_core` succeeded");
+
+    if input.c_mode {
+        // C11 allows repeating a `typedef` (e.g. in the definition from `format_adt_for_c`).
+        let c_name = format_cc_ident(&FullyQualifiedName::new(input, def_id).format_for_c(input))
+            .expect("`format_ty_for_cc` should have verified the C name of the ADT");
+        quote! { typedef #keyword #c_name #c_name; }
+    } else if let Some(underlying_type) =
+        enum_underlying_type.filter(|_| !tcx.adt_def(def_id).is_variant_list_non_exhaustive())
+    {
+        // Only an `enum class` (not a `class` that represents a `#[non_exhaustive]` enum)
+        // has an underlying type.
+        quote! { #keyword #cc_name : #underlying_type; }
+    } else {
+        quote! { #keyword #cc_name; }
+    }
+}
+
+/// Returns the source file and the (1-based) number of the line at which the
+/// item identified by `local_def_id` is defined.
+fn get_source_file_and_line(
+    tcx: TyCtxt,
+    local_def_id: LocalDefId,
+) -> Option<(rustc_span::FileName, usize)> {
+    let def_span = tcx.def_span(local_def_id);
+    let rustc_span::FileLines { file, lines } =
+        tcx.sess().source_map().span_to_lines(def_span).ok()?;
+    // Note: line_index starts at 0, while CodeSearch starts indexing at 1.
+    Some((file.name.clone(), lines[0].line_index + 1))
+}
+
+/// Replaces the first matching prefix from `remap_path_prefixes` (searching
+/// from the end - see `Input::remap_path_prefixes`) in `path`.
+pub fn remap_path_prefix(remap_path_prefixes: &[(Rc<str>, Rc<str>)], path: &str) -> String {
+    remap_path_prefixes
+        .iter()
+        .rev()
+        .find_map(|(from, to)| {
+            let suffix = Path::new(path).strip_prefix(&**from).ok()?;
+            Some(Path::new(&**to).join(suffix).to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Formats the name of a source file of the crate, in a way that doesn't
+/// depend on the location of the sources (see `Input::remap_path_prefixes`).
+fn format_file_name(input: &Input, file_name: &rustc_span::FileName) -> String {
+    remap_path_prefix(&input.remap_path_prefixes, &file_name.prefer_remapped().to_string())
+}
+
+fn format_source_location(input: &Input, local_def_id: LocalDefId) -> String {
+    let Some((file_name, line_number)) = get_source_file_and_line(input.tcx, local_def_id) else {
+        return "unknown location".to_string();
+    };
+    let google3_prefix = {
+        // If rustc_span::FileName isn't a 'real' file, then it's surrounded by by angle
+        // brackets, thus don't prepend "google3/" prefix.
+        if file_name.is_real() { "google3/" } else { "" }
+    };
+    let file_name = format_file_name(input, &file_name);
+    format!("{google3_prefix}{file_name};l={line_number}")
+}
+
+/// The placeholder for the line number in the `#line` directives that point
+/// back at the generated header (see `Input::emit_line_directives`).
+const HEADER_LINE_PLACEHOLDER: &str = "__crubit_header_line__";
+
+/// Replaces the placeholders in the `#line` directives that point back at the
+/// generated header (see `Input::emit_line_directives`) with the actual line
+/// numbers - this is only possible after the header has been formatted.
+/// `h_path` should be the path of the header (as it should be reported in C++
+/// compiler errors).
+pub fn finalize_line_directives(h_content: &str, h_path: &str) -> String {
+    let placeholder = format!("#line {HEADER_LINE_PLACEHOLDER}");
+    h_content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(line_index, line)| {
+            if line.trim() == placeholder {
+                // `#line N` sets the number of the *next* line to `N`, and `line_index` starts
+                // at 0.
+                format!("#line {} \"{h_path}\"\n", line_index + 2)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Formats a `#line` directive that points back at the generated header (see
+/// `finalize_line_directives`), or nothing if `Input::emit_line_directives`
+/// is false.
+fn format_header_line_directive(input: &Input) -> TokenStream {
+    if !input.emit_line_directives {
+        return quote! {};
+    }
+    let placeholder = format_ident!("{HEADER_LINE_PLACEHOLDER}");
+    quote! { __NEWLINE__ __HASH_TOKEN__ line #placeholder __NEWLINE__ }
+}
+
+/// Formats the doc comment (if any) associated with the item identified by
+/// `local_def_id`, and appends the source location at which the item is
+/// defined.  If `Input::emit_line_directives` is true, then the doc comment is
+/// followed by a `#line` directive that points at the same source location.
+fn format_doc_comment(input: &Input, local_def_id: LocalDefId) -> TokenStream {
+    let tcx = input.tcx;
+    let hir_id = tcx.local_def_id_to_hir_id(local_def_id);
+    let doc_comment = tcx
+        .hir()
+        .attrs(hir_id)
+        .iter()
+        .filter_map(|attr| attr.doc_str())
+        .map(|symbol| symbol.to_string())
+        .chain(once(format!("Generated from: {}", format_source_location(input, local_def_id))))
+        .join("\n\n");
+    let line_directive = match get_source_file_and_line(tcx, local_def_id) {
+        Some((file_name, line_number)) if input.emit_line_directives => {
+            let line_number = Literal::usize_unsuffixed(line_number);
+            let file_name = Literal::string(&format_file_name(input, &file_name));
+            quote! { __HASH_TOKEN__ line #line_number #file_name __NEWLINE__ }
+        }
+        _ => quote! {},
+    };
+    quote! { __COMMENT__ #doc_comment #line_directive }
+}
+
+/// Formats a HIR item idenfied by `def_id`.  Returns `None` if the item
+/// can be ignored. Returns an `Err` if the definition couldn't be formatted.
+/// The results are memoized (see `SnippetInterner::items`).
+///
+/// Will panic if `def_id` is invalid (i.e. doesn't identify a HIR item).
+fn format_item(
+    input: &Input,
+    def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>, Rc<anyhow::Error>> {
+    // TODO(b/262052635): When adding support for re-exports we may need to change
+    // `is_directly_public` below into `is_exported`.  (OTOH such change *alone* is undesirable,
+    // because it would mean exposing items from a private module.  Exposing a private module is
+    // undesirable, because it would mean that changes of private implementation details of the
+    // crate could become breaking changes for users of the generated C++ bindings.)
+    if !input.tcx.effective_visibilities(()).is_directly_public(def_id)
+        || !input.is_selected(def_id)
+    {
+        return Ok(vec![]);
+    }
+
+    if let Some(result) = input.interner.items.borrow().get(&def_id) {
+        return result.clone();
+    }
+    let cached_snippets =
+        input.snippet_cache.as_ref().and_then(|cache| cache.borrow_mut().get(input, def_id));
+    let result = match cached_snippets {
+        // The `ApiItem` of the item is only built if `generate_item_ir` needs
+        // it (and then the item is formatted again, bypassing the cache).
+        Some(snippets) => Ok(snippets),
+        None => {
+            let result = format_public_item(input, def_id).map_err(Rc::new);
+            record_item_ir(input, def_id, &result);
+            if let (Ok(snippets), Some(cache)) = (&result, input.snippet_cache.as_ref()) {
+                cache.borrow_mut().insert(input, def_id, snippets);
+            }
+            result
+        }
+    };
+    input.interner.items.borrow_mut().insert(def_id, result.clone());
+    result
+}
+
+/// Records the `ApiItem` describing the bindings of the item (or `impl` item)
+/// identified by `def_id`, given the `result` of formatting the item.  This
+/// way `generate_item_ir` reports the same errors (and covers the same items)
+/// as `generate_bindings`, without formatting the items again.
+fn record_item_ir(
+    input: &Input,
+    def_id: LocalDefId,
+    result: &Result<Vec<(SnippetKey, MixedSnippet)>, Rc<anyhow::Error>>,
+) {
+    let ir = match result {
+        Err(err) => Err(err.clone()),
+        Ok(snippets) if snippets.is_empty() => Ok(None),
+        Ok(_) => match input.tcx.def_kind(def_id) {
+            DefKind::Fn | DefKind::AssocFn => {
+                generate_fn_ir(input, def_id).map(|func| Some(ApiItem::Func(func)))
+            }
+            DefKind::Struct | DefKind::Enum | DefKind::Union => {
+                generate_adt_ir(input, def_id).map(|adt| Some(ApiItem::Adt(adt)))
+            }
+            _ => Ok(None),
+        }
+        .map_err(Rc::new),
+    };
+    input.interner.item_irs.borrow_mut().insert(def_id, ir);
+}
+
+/// Returns an error if some of the `required` features are not enabled in
+/// `Input::crubit_features`.
+fn ensure_crubit_features(input: &Input, required: flagset::FlagSet<CrubitFeature>) -> Result<()> {
+    let missing_features = required - input.crubit_features;
+    coded_ensure!(
+        ErrorCode::MissingCrubitFeatures,
+        missing_features.is_empty(),
+        "Missing required Crubit features: {} (add {} to the `aspect_hints` of the crate)",
+        missing_features.into_iter().map(|f| format!("`{}`", f.short_name())).join(", "),
+        missing_features.into_iter().map(|f| format!("`{}`", f.aspect_hint())).join(", "),
+    );
+    Ok(())
+}
+
+/// Implementation of `format_item` for an item that is public and selected by
+/// `Input::is_selected`.
+fn format_public_item(
+    input: &Input,
+    def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    ensure_crubit_features(input, CrubitFeature::Supported.into())?;
+    let snippets = match input.tcx.hir().expect_item(def_id) {
+        Item { kind: ItemKind::Struct(_, generics) |
+                     ItemKind::Enum(_, generics) |
+                     ItemKind::Union(_, generics),
+               .. } if !generics.params.is_empty() => {
+            coded_bail!(ErrorCode::GenericType, "Generic types are not supported yet (b/259749095)");
+        },
+        Item { kind: ItemKind::Fn(..), .. } => format_fn(input, def_id),
+        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. }
+            if input.crubit_annotations(def_id.to_def_id()).cpp_type.is_some() =>
+            // The original C++ type is used instead (see `format_cpp_type_for_cc`).
+            Ok(vec![]),
+        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. }
+            if matches!(input.get_type_mapping(def_id.to_def_id()),
+                        Some(TypeMapping { conversion: None, .. })) =>
+            // The pre-existing C++ type is used instead (see `format_mapped_type_for_cc`).
+            Ok(vec![]),
+        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. } =>
+            format_adt_core(input, def_id.to_def_id())
+                .and_then(|core| if input.c_mode {
+                    format_adt_for_c(input, &core)
+                } else if core.enum_underlying_type.is_some() {
+                    Ok(format_enum_class(input, &core))
+                } else {
+                    Ok(format_adt(input, &core))
+                }),
+        Item { kind: ItemKind::Trait(..), .. } => format_trait(input, def_id),
+        Item { kind: ItemKind::Const(..) | ItemKind::Static(..), .. }
+            if get_thread_local_value_ty(input.tcx, def_id.to_def_id()).is_some() =>
+            format_thread_local(input, def_id),
+        Item { kind: ItemKind::Const(..) | ItemKind::Static(..), .. }
+            if is_str_const(input.tcx, def_id.to_def_id()) =>
+            format_str_const(input, def_id),
+        Item { kind: ItemKind::Impl(_), .. } |  // Handled by `format_adt`
+        Item { kind: ItemKind::Mod(_), .. } =>  // Handled by `format_crate`
+            Ok(vec![]),
+        Item { kind, .. } => coded_bail!(
+            ErrorCode::UnsupportedItemKind,
+            "Unsupported rustc_hir::hir::ItemKind: {}",
+            kind.descr()
+        ),
+    }?;
+    let snippets = add_cc_name_collision_comment(input, def_id, snippets);
+
+    let guard_macros = get_feature_guard_macros(input, def_id);
+    if guard_macros.is_empty() {
+        return Ok(snippets);
+    }
+    snippets
+        .into_iter()
+        .map(|(key, mut snippet)| {
+            snippet.cc.tokens = format_feature_guards(&guard_macros, snippet.cc.tokens)?;
+            Ok((key, snippet))
+        })
+        .collect()
+}
+
+/// Prepends a comment to the `SnippetKind::MainApi` snippet of `def_id` if
+/// `disambiguate_cc_name` had to rename the item.
+fn add_cc_name_collision_comment(
+    input: &Input,
+    def_id: LocalDefId,
+    mut snippets: Vec<(SnippetKey, MixedSnippet)>,
+) -> Vec<(SnippetKey, MixedSnippet)> {
+    let cc_name = FullyQualifiedName::new(input, def_id.to_def_id()).cc_name;
+    let undisambiguated_cc_name = get_undisambiguated_cc_name(input, def_id.to_def_id());
+    let (Some(cc_name), Some(undisambiguated_cc_name)) = (cc_name, undisambiguated_cc_name) else {
+        return snippets;
+    };
+    if cc_name == undisambiguated_cc_name {
+        return snippets;
+    }
+
+    let msg = format!(
+        "`{undisambiguated_cc_name}` has been renamed to `{cc_name}` to avoid a collision \
+         with another C++ name from the same namespace"
+    );
+    for (key, snippet) in snippets.iter_mut() {
+        if key.def_id == def_id && key.kind == SnippetKind::MainApi {
+            let tokens = &snippet.cc.tokens;
+            snippet.cc.tokens = quote! { __NEWLINE__ __COMMENT__ #msg #tokens };
+        }
+    }
+    snippets
+}
+
+/// Returns the C++ macros (from `Input::feature_guard_macros`) that should
+/// guard the bindings of `def_id`, based on the `#[cfg(feature = "...")]`
+/// attributes of `def_id` and of its parent modules.
+///
+/// Note that more complex `cfg` predicates (e.g. `all(...)` or `not(...)`) are
+/// ignored.
+fn get_feature_guard_macros(input: &Input, def_id: LocalDefId) -> BTreeSet<Rc<str>> {
+    let tcx = input.tcx;
+    std::iter::successors(Some(def_id.to_def_id()), |&def_id| tcx.opt_parent(def_id))
+        .flat_map(|def_id| tcx.get_attrs(def_id, rustc_span::symbol::sym::cfg))
+        .filter_map(|attr| attr.meta_item_list())
+        .flatten()
+        .filter(|predicate| predicate.has_name(rustc_span::symbol::sym::feature))
+        .filter_map(|predicate| predicate.value_str())
+        .filter_map(|feature| input.feature_guard_macros.get(feature.as_str()).cloned())
+        .collect()
+}
+
+/// Wraps `tokens` in `#ifdef` / `#endif` for each of the `guard_macros`.
+fn format_feature_guards(
+    guard_macros: &BTreeSet<Rc<str>>,
+    tokens: TokenStream,
+) -> Result<TokenStream> {
+    guard_macros.iter().rev().try_fold(tokens, |tokens, guard_macro| {
+        let guard_macro = format_unescaped_cc_ident(guard_macro)
+            .with_context(|| format!("Invalid feature guard macro: `{guard_macro}`"))?;
+        Ok(quote! {
+            __NEWLINE__ __HASH_TOKEN__ ifdef #guard_macro __NEWLINE__
+            #tokens
+            __NEWLINE__ __HASH_TOKEN__ endif __NEWLINE__
+        })
+    })
+}
+
+/// Formats a C++ comment explaining why no bindings have been generated for
+/// `local_def_id` (including the `ErrorCode` of `err` and its suggested
+/// workaround).
+fn format_unsupported_def(
+    input: &Input,
+    local_def_id: LocalDefId,
+    err: &anyhow::Error,
+) -> (SnippetKey, MixedSnippet) {
+    let tcx = input.tcx;
+    let source_loc = format_source_location(input, local_def_id);
+    let name = tcx.def_path_str(local_def_id.to_def_id());
+
+    // https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
+    // says: To print causes as well [...], use the alternate selector “{:#}”.
+    let msg = format!("Error generating bindings for `{name}` defined at {source_loc}: {err:#}");
+    let msg = format_error_code_comment(ErrorCode::from_error(err), &msg);
+    let cc = CcSnippet::new(quote! { __NEWLINE__ __NEWLINE__ __COMMENT__ #msg __NEWLINE__ });
+
+    (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, cc.into())
+}
+
+/// Formats C++ comments explaining why no bindings have been generated for
+/// `unsupported_defs`.  Items that failed because of the same root cause (e.g.
+/// because all of them use the same unsupported type) share a single comment
+/// that lists the affected items.  Such comment is placed where the bindings
+/// of the first affected item would go.
+fn format_unsupported_defs(
+    input: &Input,
+    unsupported_defs: Vec<(LocalDefId, Rc<anyhow::Error>)>,
+) -> Vec<(SnippetKey, MixedSnippet)> {
+    let tcx = input.tcx;
+    unsupported_defs
+        .into_iter()
+        .into_group_map_by(|(_, err)| err.root_cause().to_string())
+        .into_iter()
+        .map(|(root_cause, mut group)| {
+            if group.len() == 1 {
+                let (local_def_id, err) = group.pop().unwrap();
+                return format_unsupported_def(input, local_def_id, &err);
+            }
+            group.sort_by_key(|(local_def_id, _)| tcx.def_span(*local_def_id));
+            let affected_items = group
+                .iter()
+                .map(|(local_def_id, err)| {
+                    let source_loc = format_source_location(input, *local_def_id);
+                    let name = tcx.def_path_str(local_def_id.to_def_id());
+                    match format_error_context(err) {
+                        None => format!("\n- `{name}` defined at {source_loc}"),
+                        Some(context) => format!("\n- `{name}` defined at {source_loc}: {context}"),
+                    }
+                })
+                .join("");
+            let msg = format!(
+                "Error generating bindings for {} items: {root_cause}{affected_items}",
+                group.len()
+            );
+            let code = ErrorCode::from_error(&group[0].1);
+            let msg = format_error_code_comment(code, &msg);
+            let cc =
+                CcSnippet::new(quote! { __NEWLINE__ __NEWLINE__ __COMMENT__ #msg __NEWLINE__ });
+            let local_def_id = group[0].0;
+            (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, cc.into())
+        })
+        .collect()
+}
+
+/// Formats the context in which the root cause of `err` has been encountered
+/// (e.g. `Error handling parameter #0`), or returns `None` if `err` has no
+/// context.
+fn format_error_context(err: &anyhow::Error) -> Option<String> {
+    let chain = err.chain().map(|cause| cause.to_string()).collect_vec();
+    let (_root_cause, context) = chain.split_last()?;
+    if context.is_empty() {
+        None
+    } else {
+        Some(context.join(": "))
+    }
+}
+
+/// Cache of the bindings generated for the items of the crate (see
+/// `Input::snippet_cache`).  The cache can be persisted across runs of the tool
+/// (see `SnippetCache::new` and `SnippetCache::to_json`), so that the bindings
+/// of the items that didn't change don't need to be generated again.
+///
+/// The cached bindings of an item are only reused when the fingerprint of the
+/// item (see `get_item_fingerprint`) didn't change, and when the fingerprint
+/// of everything else that may affect the bindings (the configuration of the
+/// tool and the dependency crates - see `get_crate_fingerprint`) didn't change.
+pub struct SnippetCache {
+    /// Identifies the binary and the cmdline of the tool.
+    invocation_fingerprint: String,
+
+    /// Cache contents loaded from an earlier run of the tool.
+    old: CachedCrate,
+
+    /// Cache contents gathered during the current run of the tool.  Items that
+    /// are not visited during the current run are dropped from the cache.
+    new: CachedCrate,
+
+    /// Memoized results of `get_item_fingerprint`.
+    item_fingerprints: HashMap<LocalDefId, u64>,
+
+    /// Maps formatted `DefPathHash`es to the local definitions (see
+    /// `format_def_path_hash`).  Populated on first use.
+    local_def_ids: HashMap<String, LocalDefId>,
+
+    /// Whether the crate fingerprint has already been verified.
+    is_prepared: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct CachedCrate {
+    /// The result of `get_crate_fingerprint`.
+    fingerprint: u64,
+
+    /// Cached bindings keyed by the formatted `DefPathHash` of the item.
+    items: HashMap<String, CachedItem>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct CachedItem {
+    /// The result of `get_item_fingerprint`.
+    fingerprint: u64,
+
+    snippets: Vec<CachedSnippet>,
+}
+
+/// Serializable equivalent of `(SnippetKey, MixedSnippet)`.  `LocalDefId`s are
+/// represented as formatted `DefPathHash`es (which, unlike `LocalDefId`s, are
+/// stable across compilations) and tokens are represented as strings.
+#[derive(Clone, Deserialize, Serialize)]
+struct CachedSnippet {
+    def_id: String,
+    is_main_api: bool,
+    cc_tokens: String,
+    includes: Vec<CachedInclude>,
+    defs: Vec<String>,
+    fwd_decls: Vec<String>,
+    rs_tokens: String,
+    cc_out_tokens: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+enum CachedInclude {
+    System(String),
+    User(String),
+}
+
+impl SnippetCache {
+    /// Creates a `SnippetCache` from the output of an earlier
+    /// `SnippetCache::to_json` (or an empty cache if `json` is `None`).
+    /// `invocation_fingerprint` should identify the binary and the cmdline of
+    /// the tool - the cache is discarded if it has been populated by a run
+    /// with a different `invocation_fingerprint`.
+    ///
+    /// A malformed `json` results in an empty cache, because the cache is only
+    /// an optimization (e.g. the file may be corrupted if an earlier run of
+    /// the tool has been interrupted).
+    pub fn new(json: Option<&str>, invocation_fingerprint: String) -> Self {
+        let old = json.and_then(|json| serde_json::from_str(json).ok()).unwrap_or_default();
+        Self {
+            invocation_fingerprint,
+            old,
+            new: CachedCrate::default(),
+            item_fingerprints: HashMap::new(),
+            local_def_ids: HashMap::new(),
+            is_prepared: false,
+        }
+    }
+
+    /// Serializes the bindings cached during the current run of the tool.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.new)?)
+    }
+
+    /// Discards `self.old` if `get_crate_fingerprint` has changed.
+    fn prepare(&mut self, tcx: TyCtxt) {
+        if self.is_prepared {
+            return;
+        }
+        self.is_prepared = true;
+        self.new.fingerprint = get_crate_fingerprint(tcx, &self.invocation_fingerprint);
+        if self.old.fingerprint != self.new.fingerprint {
+            self.old.items.clear();
+        }
+        self.local_def_ids = tcx
+            .hir_crate_items(())
+            .definitions()
+            .map(|def_id| (format_def_path_hash(tcx, def_id), def_id))
+            .collect();
+    }
+
+    /// Returns the cached bindings of `def_id` if they can be reused.
+    fn get(
+        &mut self,
+        input: &Input,
+        def_id: LocalDefId,
+    ) -> Option<Vec<(SnippetKey, MixedSnippet)>> {
+        let tcx = input.tcx;
+        self.prepare(tcx);
+        let key = format_def_path_hash(tcx, def_id);
+        let cached_item = self.old.items.get(&key)?;
+        let fingerprint = get_item_fingerprint(input, def_id, &mut self.item_fingerprints);
+        if cached_item.fingerprint != fingerprint {
+            return None;
+        }
+        let snippets = cached_item
+            .snippets
+            .iter()
+            .map(|snippet| self.decode_snippet(snippet))
+            .collect::<Option<Vec<_>>>()?;
+        let cached_item = cached_item.clone();
+        self.new.items.insert(key, cached_item);
+        Some(snippets)
+    }
+
+    /// Caches the `snippets` generated for `def_id`.
+    fn insert(
+        &mut self,
+        input: &Input,
+        def_id: LocalDefId,
+        snippets: &[(SnippetKey, MixedSnippet)],
+    ) {
+        let tcx = input.tcx;
+        self.prepare(tcx);
+        let format_def_ids = |def_ids: &HashSet<LocalDefId>| {
+            def_ids.iter().map(|&def_id| format_def_path_hash(tcx, def_id)).sorted().collect()
+        };
+        let snippets = snippets
+            .iter()
+            .map(|(key, snippet)| CachedSnippet {
+                def_id: format_def_path_hash(tcx, key.def_id),
+                is_main_api: key.kind == SnippetKind::MainApi,
+                cc_tokens: snippet.cc.tokens.to_string(),
+                includes: snippet
+                    .cc
+                    .prereqs
+                    .includes
+                    .iter()
+                    .map(|include| match include {
+                        CcInclude::SystemHeader(name) => CachedInclude::System(name.to_string()),
+                        CcInclude::UserHeader(path) => CachedInclude::User(path.to_string()),
+                    })
+                    .collect(),
+                defs: format_def_ids(&snippet.cc.prereqs.defs),
+                fwd_decls: format_def_ids(&snippet.cc.prereqs.fwd_decls),
+                rs_tokens: snippet.rs.to_string(),
+                cc_out_tokens: snippet.cc_out.to_string(),
+            })
+            .collect();
+        let fingerprint = get_item_fingerprint(input, def_id, &mut self.item_fingerprints);
+        self.new
+            .items
+            .insert(format_def_path_hash(tcx, def_id), CachedItem { fingerprint, snippets });
+    }
+
+    /// Rebuilds a `(SnippetKey, MixedSnippet)` from `snippet`.  Returns `None`
+    /// if the cached data is invalid.
+    fn decode_snippet(&self, snippet: &CachedSnippet) -> Option<(SnippetKey, MixedSnippet)> {
+        let def_id = |def_path_hash: &String| self.local_def_ids.get(def_path_hash).copied();
+        let tokens = |tokens: &str| tokens.parse::<TokenStream>().ok();
+        let includes = snippet
+            .includes
+            .iter()
+            .map(|include| match include {
+                CachedInclude::System(name) => {
+                    // Leaking is okay, because there are only a few distinct system headers.
+                    CcInclude::SystemHeader(Box::leak(name.clone().into_boxed_str()))
+                }
+                CachedInclude::User(path) => CcInclude::user_header(path.as_str().into()),
+            })
+            .collect();
+        let prereqs = CcPrerequisites {
+            includes,
+            defs: snippet.defs.iter().map(def_id).collect::<Option<_>>()?,
+            fwd_decls: snippet.fwd_decls.iter().map(def_id).collect::<Option<_>>()?,
+        };
+        let kind =
+            if snippet.is_main_api { SnippetKind::MainApi } else { SnippetKind::ImplDetails };
+        let key = SnippetKey { def_id: def_id(&snippet.def_id)?, kind };
+        let snippet = MixedSnippet {
+            cc: CcSnippet { tokens: tokens(&snippet.cc_tokens)?, prereqs },
+            rs: tokens(&snippet.rs_tokens)?,
+            cc_out: tokens(&snippet.cc_out_tokens)?,
+        };
+        Some((key, snippet))
+    }
+}
+
+/// Formats the `DefPathHash` of `def_id` (which, unlike `LocalDefId`, is stable
+/// across compilations).
+fn format_def_path_hash(tcx: TyCtxt, def_id: LocalDefId) -> String {
+    let (hi, lo) = tcx.def_path_hash(def_id.to_def_id()).0.as_value();
+    format!("{hi:016x}{lo:016x}")
+}
+
+/// Returns a fingerprint of everything other than the items of the current
+/// crate that may affect the generated bindings: the configuration of the tool
+/// (`invocation_fingerprint`), and the dependency crates (e.g. the layouts of
+/// their types).
+fn get_crate_fingerprint(tcx: TyCtxt, invocation_fingerprint: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    invocation_fingerprint.hash(&mut hasher);
+    tcx.crate_name(LOCAL_CRATE).as_str().hash(&mut hasher);
+    for &cnum in tcx.crates(()).iter() {
+        tcx.crate_name(cnum).as_str().hash(&mut hasher);
+        tcx.crate_hash(cnum).to_string().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Returns a fingerprint of everything in the current crate that may affect
+/// the bindings that `format_item` generates for `def_id`:
+/// - the HIR of the item (excluding function bodies, which don't affect the
+///   bindings, but including the spans, which are used in the source location
+///   comments), and for ADTs also the HIR of all their `impl`s (including the
+///   trait impls that don't get bindings, like `impl Drop`) and whether they
+///   implement `Send` and `Sync`,
+/// - the C++ name and namespace of the item (which may depend on the other
+///   items from the same module - see `disambiguate_cc_name`) and its feature
+///   guards,
+/// - recursively, the fingerprints of the local ADTs that are used by the
+///   signatures of the item (e.g. by the parameter types of a function, or by
+///   the field types of a struct).
+///
+/// Fingerprints of ADTs that use each other (e.g. through a pointer) only
+/// cover each other's HIR, which is sufficient, because a change in one of the
+/// ADTs changes the fingerprint of the other.
+fn get_item_fingerprint(
+    input: &Input,
+    def_id: LocalDefId,
+    memo: &mut HashMap<LocalDefId, u64>,
+) -> u64 {
+    if let Some(&fingerprint) = memo.get(&def_id) {
+        return fingerprint;
+    }
+    let tcx = input.tcx;
+
+    let mut owners = vec![def_id];
+    let mut tys = vec![];
+    let mut send_and_sync = None;
+    match tcx.def_kind(def_id) {
+        DefKind::Fn | DefKind::AssocFn => {
+            let sig = rustc_compat::fn_sig(tcx, def_id.to_def_id());
+            tys.extend(sig.skip_binder().inputs_and_output);
+        }
+        DefKind::Struct | DefKind::Enum | DefKind::Union => {
+            let adt_def = tcx.adt_def(def_id);
+            tys.extend(adt_def.all_fields().map(|field| rustc_compat::type_of(tcx, field.did)));
+            let bound_impl_ids = get_bound_impl_ids(input, def_id.to_def_id());
+            for &impl_id in bound_impl_ids.iter() {
+                owners.push(impl_id);
+                for assoc_id in tcx.associated_item_def_ids(impl_id).iter() {
+                    let Some(assoc_id) = assoc_id.as_local() else { continue };
+                    owners.push(assoc_id);
+                    if tcx.def_kind(assoc_id) == DefKind::AssocFn {
+                        let sig = rustc_compat::fn_sig(tcx, assoc_id.to_def_id());
+                        tys.extend(sig.skip_binder().inputs_and_output);
+                    }
+                }
+            }
+            // The other trait impls don't get bindings, but they still affect the bindings of
+            // the ADT (e.g. `impl Drop` requires the drop and move thunks, and `impl Default` is
+            // required by the move constructor).
+            let self_ty = rustc_compat::type_of(tcx, def_id.to_def_id());
+            owners.extend(
+                tcx.all_local_trait_impls(())
+                    .values()
+                    .flatten()
+                    .copied()
+                    .filter(|impl_id| !bound_impl_ids.contains(impl_id))
+                    .filter(|&impl_id| rustc_compat::type_of(tcx, impl_id.to_def_id()) == self_ty),
+            );
+            // `Send` and `Sync` may also depend on the types from other crates.
+            send_and_sync = Some(get_send_and_sync(tcx, self_ty));
+        }
+        DefKind::Trait => {
+            for assoc_id in tcx.associated_item_def_ids(def_id).iter() {
+                let Some(assoc_id) = assoc_id.as_local() else { continue };
+                owners.push(assoc_id);
+                if tcx.def_kind(assoc_id) == DefKind::AssocFn {
+                    let sig = rustc_compat::fn_sig(tcx, assoc_id.to_def_id());
+                    tys.extend(sig.skip_binder().inputs_and_output);
+                }
+            }
+        }
+        _ => (),
+    }
+
+    let mut hasher = DefaultHasher::new();
+    let hir_crate = tcx.hir_crate(());
+    for owner in owners {
+        let owner_info = hir_crate.owners[owner].as_owner().expect("Items should be HIR owners");
+        owner_info.nodes.hash_without_bodies.hash(&mut hasher);
+        owner_info.attrs.hash.hash(&mut hasher);
+        let fully_qualified_name = FullyQualifiedName::new(input, owner.to_def_id());
+        fully_qualified_name.cc_mod_path.hash(&mut hasher);
+        fully_qualified_name.cc_name.map(|name| name.to_string()).hash(&mut hasher);
+    }
+    send_and_sync.hash(&mut hasher);
+    get_feature_guard_macros(input, def_id).hash(&mut hasher);
+
+    // Memoizing the fingerprint that doesn't yet cover the used ADTs protects
+    // against infinite recursion.
+    memo.insert(def_id, hasher.finish());
+    let used_adts = tys
+        .into_iter()
+        .flat_map(|ty| ty.walk())
+        .filter_map(|arg| match arg.unpack() {
+            ty::GenericArgKind::Type(ty) => ty.ty_adt_def().and_then(|adt| adt.did().as_local()),
+            _ => None,
+        })
+        .filter(|&adt_id| adt_id != def_id)
+        .unique()
+        .collect_vec();
+    for adt_id in used_adts {
+        get_item_fingerprint(input, adt_id, memo).hash(&mut hasher);
+    }
+
+    let fingerprint = hasher.finish();
+    memo.insert(def_id, fingerprint);
+    fingerprint
+}
+
+/// The result of `format_item` for each of the items of the crate.
+type FormattedItems = Vec<(LocalDefId, Result<Vec<(SnippetKey, MixedSnippet)>, Rc<anyhow::Error>>)>;
+
+/// Formats all items from the Rust crate being compiled (see `format_item`).
+///
+/// The items are formatted via rustc's parallel queries (i.e. on multiple
+/// threads if rustc has been built with the parallel compiler).  The results
+/// are returned in the source order of the items (rather than in the order in
+/// which the items have been formatted), so that the generated bindings are
+/// deterministic.
+fn format_items(input: &Input) -> FormattedItems {
+    let tcx = input.tcx;
+    let results = Lock::new(HashMap::new());
+    tcx.hir().par_for_each_item(|item_id| {
+        let def_id: LocalDefId = item_id.owner_id.def_id;
+        let result = format_item(input, def_id);
+        results.lock().insert(def_id, result);
+    });
+    let mut results = results.into_inner();
+    tcx.hir()
+        .items()
+        .map(|item_id| {
+            let def_id: LocalDefId = item_id.owner_id.def_id;
+            let result = results.remove(&def_id).expect("Every item should have been formatted");
+            (def_id, result)
+        })
+        .collect()
+}
+
+/// Formats all public items from the Rust crate being compiled.
+fn format_crate(input: &Input) -> Result<Output> {
+    let tcx = input.tcx;
+    let mut unsupported_defs = vec![];
+    let bindings = format_items(input)
+        .into_iter()
+        .flat_map(|(def_id, result)| {
+            result.unwrap_or_else(|err| {
+                unsupported_defs.push((def_id, err));
+                vec![]
+            })
+        })
+        .collect_vec();
+    let mut bindings: HashMap<SnippetKey, MixedSnippet> = bindings
+        .into_iter()
+        .chain(format_unsupported_defs(input, unsupported_defs))
+        .fold(HashMap::new(), |mut map, (key, value)| {
+            let old_item = map.insert(key, value);
+            assert!(old_item.is_none(), "Duplicated key: {key:?}");
+            map
+        });
+
+    // Find the order of `bindings` that 1) meets the requirements of
+    // `CcPrerequisites::defs` and 2) makes a best effort attempt to keep the
+    // `bindings` in the same order as the source order of the Rust APIs.
+    let ordered_ids = {
+        let toposort::TopoSortResult { ordered: ordered_ids, failed: failed_ids } = {
+            let nodes = bindings.keys().copied();
+            let deps = bindings.iter().flat_map(|(&successor, snippet)| {
+                let predecessors = snippet.cc.prereqs.defs.iter().map(|&def_id|
+                    SnippetKey { def_id, kind: SnippetKind::MainApi }
+                );
+                predecessors.map(move |predecessor| toposort::Dependency { predecessor, successor })
+            });
+            toposort::toposort(nodes, deps, preferred_snippet_order(tcx))
+        };
+        assert_eq!(
+            0,
+            failed_ids.len(),
+            "There are no known scenarios where CcPrerequisites::defs can form \
+                    a dependency cycle. These `LocalDefId`s form an unexpected cycle: {}",
+            failed_ids.into_iter().map(|id| format!("{:?}", id)).join(",")
+        );
+        ordered_ids
+    };
+
+    // Destructure/rebuild `bindings` (in the same order as `ordered_ids`) into
+    // separate C++ snippets (grouped by `HeaderPart`) and Rust snippets.
+    let part_of = |def_id: LocalDefId| match input.split_headers.as_ref() {
+        Some(split_headers) => HeaderPart::new(input, split_headers, def_id),
+        None => HeaderPart::CrateRoot,
+    };
+    let (parts, rs_body, cc_out) = {
+        let mut parts: BTreeMap<HeaderPart, HeaderPartBuilder> = BTreeMap::new();
+        let mut rs_body = quote! {};
+        let mut cc_out = vec![];
+        for key in ordered_ids.into_iter() {
+            let cc_mod_path = get_cc_mod_path(input, key.def_id);
+            let MixedSnippet {
+                rs: inner_rs,
+                cc_out: inner_cc_out,
+                cc: CcSnippet {
+                    tokens: cc_tokens,
+                    prereqs: CcPrerequisites {
+                        includes: mut inner_includes,
+                        defs: inner_defs,
+                        fwd_decls: inner_fwd_decls,
+                    }
+                }
+            } = bindings.remove(&key).unwrap();
+
+            let part_key = part_of(key.def_id);
+            let part_deps = inner_defs
+                .into_iter()
+                .map(part_of)
+                .filter(|dep| *dep != part_key)
+                .collect::<BTreeSet<_>>();
+            let part = parts.entry(part_key).or_default();
+            part.deps.extend(part_deps);
+
+            part.fwd_decls.extend(inner_fwd_decls.difference(&part.already_declared).copied());
+            part.already_declared.insert(key.def_id);
+            part.already_declared.extend(inner_fwd_decls.into_iter());
+
+            part.includes.append(&mut inner_includes);
+            let cc_tokens = if key.kind == SnippetKind::MainApi {
+                let header_line_directive = format_header_line_directive(input);
+                quote! { #cc_tokens #header_line_directive }
+            } else {
+                cc_tokens
+            };
+            part.ordered_cc.push((cc_mod_path.clone(), cc_tokens));
+            rs_body.extend(inner_rs);
+            if !inner_cc_out.is_empty() {
+                cc_out.push((cc_mod_path, inner_cc_out));
+            }
+        }
+        (parts, rs_body, cc_out)
+    };
+
+    // The inline namespace is derived from the API before it is extended with the ABI stamp.
+    let inline_ns = match input.inline_namespace.as_ref() {
+        Some(inline_namespace) if !input.c_mode => {
+            Some(format_inline_namespace(inline_namespace, &parts, &rs_body)?)
+        }
+        _ => None,
+    };
+
+    // The generated header(s) and `rs_body` are only compatible with each other if they
+    // have been generated together - see `format_abi_stamp_check`.
+    let (abi_stamp, rs_body) = if input.emit_abi_stamp {
+        let abi_stamp = format_abi_stamp(input, &parts, &rs_body);
+        let rs_body = quote! {
+            #rs_body
+            #[no_mangle]
+            #[used]
+            static #abi_stamp: u8 = 0;
+        };
+        (Some(abi_stamp), rs_body)
+    } else {
+        (None, rs_body)
+    };
+
+    // Generate top-level elements of the C++ header file(s).
+    let top_level_ns = input.format_top_level_ns_for_cc(tcx.crate_name(LOCAL_CRATE))?;
+    let cc_body = match input.cc_out_h_include.as_ref() {
+        None => quote! {},
+        Some(h_include) => {
+            let includes = format_cc_includes(
+                &[h_include.clone(), CcInclude::cstddef()].into_iter().collect(),
+            );
+            let cc_out = format_namespace_bound_cc_tokens(cc_out);
+            let cc_out = format_top_level_ns_block(&top_level_ns, inline_ns.as_ref(), cc_out);
+            quote! {
+                #includes
+                __NEWLINE__
+                #cc_out
+                __NEWLINE__
+            }
+        }
+    };
+    let Some(split_headers) = input.split_headers.as_ref() else {
+        let part = parts.into_values().exactly_one().unwrap_or_default();
+        let abi_stamp_check =
+            format_abi_stamp_check(input, abi_stamp.as_ref(), &HeaderPart::CrateRoot);
+        let h_body = part.format(
+            input,
+            &top_level_ns,
+            inline_ns.as_ref(),
+            BTreeSet::new(),
+            BTreeSet::new(),
+            abi_stamp_check,
+        );
+        return Ok(Output { h_body, h_parts: vec![], rs_body, cc_body });
+    };
+
+    // Every header from `SplitHeaders::module_headers` is generated (even if the module doesn't
+    // have any items with bindings), so that build systems can rely on all of them being written.
+    let mut parts = parts;
+    let top_level_modules = get_top_level_module_names(tcx);
+    for module_name in split_headers.module_headers.keys() {
+        ensure!(
+            top_level_modules.contains(&Symbol::intern(module_name)),
+            "Can't generate a separate header for `{module_name}`, because it is not a \
+             top-level module of the crate",
+        );
+        parts.entry(HeaderPart::TopLevelModule(module_name.clone())).or_default();
+    }
+    let root_part = parts.remove(&HeaderPart::CrateRoot).unwrap_or_default();
+
+    // Headers of different modules may `#include` each other (e.g. when a struct from one
+    // module has a field whose type is a struct from another module).  Such `#include`s can't
+    // form a cycle.
+    let toposort::TopoSortResult { failed, .. } = {
+        let nodes = parts.keys().cloned().chain(once(HeaderPart::CrateRoot));
+        let deps = parts.iter().chain(once((&HeaderPart::CrateRoot, &root_part))).flat_map(
+            |(successor, part)| {
+                part.deps.iter().map(move |predecessor| toposort::Dependency {
+                    predecessor: predecessor.clone(),
+                    successor: successor.clone(),
+                })
+            },
+        );
+        toposort::toposort(nodes, deps, Ord::cmp)
+    };
+    ensure!(
+        failed.is_empty(),
+        "Can't split the C++ bindings into one header per module, because the following \
+         modules depend on each other: {}",
+        failed.iter().sorted().map(|part| part.name()).join(", ")
+    );
+
+    let h_parts = parts
+        .iter()
+        .map(|(part_key, part)| {
+            let dep_includes =
+                part.deps.iter().map(|dep| dep.format_include(split_headers)).collect();
+            let abi_stamp_check = format_abi_stamp_check(input, abi_stamp.as_ref(), part_key);
+            let body = part.format(
+                input,
+                &top_level_ns,
+                inline_ns.as_ref(),
+                dep_includes,
+                BTreeSet::new(),
+                abi_stamp_check,
+            );
+            (part_key.h_path(split_headers).to_string(), body)
+        })
+        .collect_vec();
+
+    // The main header `#include`s the headers that it depends on upfront, and the other module
+    // headers at the end (those may depend on the main header, which they `#include` in turn -
+    // the include guards break the cycle).
+    let dep_includes = root_part.deps.iter().map(|dep| dep.format_include(split_headers)).collect();
+    let trailing_includes = parts
+        .keys()
+        .filter(|part_key| !root_part.deps.contains(part_key))
+        .map(|part_key| part_key.format_include(split_headers))
+        .collect();
+    let abi_stamp_check = format_abi_stamp_check(input, abi_stamp.as_ref(), &HeaderPart::CrateRoot);
+    let h_body = root_part.format(
+        input,
+        &top_level_ns,
+        inline_ns.as_ref(),
+        dep_includes,
+        trailing_includes,
+        abi_stamp_check,
+    );
+    Ok(Output { h_body, h_parts, rs_body, cc_body })
+}
+
+/// Returns the names of the top-level modules of the local crate.
+fn get_top_level_module_names(tcx: TyCtxt) -> HashSet<Symbol> {
+    let Node::Crate(root_module) = tcx.hir().get_by_def_id(CRATE_DEF_ID) else {
+        panic!("`CRATE_DEF_ID` should identify the crate root");
+    };
+    root_module
+        .item_ids
+        .iter()
+        .filter_map(|item_id| match tcx.hir().item(*item_id) {
+            Item { kind: ItemKind::Mod(_), ident, .. } => Some(ident.name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Identifies which C++ header contains the bindings of a given item when
+/// `Input::split_headers` is used: either the main header (for the items at
+/// the crate root and in the modules without a separate header), or the
+/// header of one of the top-level modules from
+/// `SplitHeaders::module_headers`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum HeaderPart {
+    CrateRoot,
+    TopLevelModule(Rc<str>),
+}
+
+impl HeaderPart {
+    fn new(input: &Input, split_headers: &SplitHeaders, def_id: LocalDefId) -> Self {
+        let mod_path = FullyQualifiedName::new(input, def_id.to_def_id()).mod_path;
+        match mod_path.parts().next() {
+            Some(module) if split_headers.module_headers.contains_key(module) => {
+                Self::TopLevelModule(module.clone())
+            }
+            _ => Self::CrateRoot,
+        }
+    }
+
+    /// Name used in diagnostics and in the names of the ABI stamp checks.
+    /// `crate` can be safely used for the crate root, because it is a reserved
+    /// Rust keyword and therefore can't be used as a name of a Rust module.
+    fn name(&self) -> &str {
+        match self {
+            Self::CrateRoot => "crate",
+            Self::TopLevelModule(name) => name,
+        }
+    }
+
+    fn h_path<'a>(&self, split_headers: &'a SplitHeaders) -> &'a str {
+        match self {
+            Self::CrateRoot => &split_headers.h_out,
+            Self::TopLevelModule(name) => &split_headers.module_headers[name],
+        }
+    }
+
+    fn format_include(&self, split_headers: &SplitHeaders) -> CcInclude {
+        CcInclude::user_header(self.h_path(split_headers).into())
+    }
+}
+
+/// Returns the C++ namespace (nested inside the top-level namespace of the
+/// crate) of the bindings of `def_id`.  There are no namespaces in
+/// `Input::c_mode`.
+fn get_cc_mod_path(input: &Input, def_id: LocalDefId) -> NamespaceQualifier {
+    if input.c_mode {
+        NamespaceQualifier::new::<Rc<str>>([])
+    } else {
+        FullyQualifiedName::new(input, def_id.to_def_id()).cc_mod_path
+    }
+}
+
+/// Formats an assertion that the generated C++ bindings are compiled for a
+/// target with the same pointer size as the Rust target of the bindings (i.e.
+/// the `--target` passed to `rustc`, or the host if no `--target` was given).
+/// Layouts of structs are verified separately by the `static_assert`s from
+/// `format_adt`, but this assertion also covers functions that only use
+/// target-dependent built-in types like `usize`.
+fn format_target_assertion(tcx: TyCtxt) -> TokenStream {
+    let pointer_size = Literal::u64_unsuffixed(tcx.data_layout.pointer_size.bytes());
+    quote! {
+        static_assert(sizeof(void*) == #pointer_size, "Bindings are for a different target");
+        __NEWLINE__ __NEWLINE__
+    }
+}
+
+/// Accumulates the C++ snippets that belong to a single `HeaderPart`.
+#[derive(Default)]
+struct HeaderPartBuilder {
+    includes: BTreeSet<CcInclude>,
+    fwd_decls: HashSet<LocalDefId>,
+    already_declared: HashSet<LocalDefId>,
+    ordered_cc: Vec<(NamespaceQualifier, TokenStream)>,
+
+    /// Other `HeaderPart`s that provide definitions that this part depends on.
+    deps: BTreeSet<HeaderPart>,
+}
+
+impl HeaderPartBuilder {
+    fn format(
+        &self,
+        input: &Input,
+        top_level_ns: &TokenStream,
+        inline_ns: Option<&TokenStream>,
+        mut dep_includes: BTreeSet<CcInclude>,
+        trailing_includes: BTreeSet<CcInclude>,
+        abi_stamp_check: TokenStream,
+    ) -> TokenStream {
+        let tcx = input.tcx;
+
+        // Prepend `fwd_decls` (in the original source order) to `ordered_cc`.
+        let fwd_decls = self.fwd_decls.iter().sorted_by_key(|def_id| tcx.def_span(**def_id)).map(
+            |&local_def_id| {
+                let cc_mod_path = get_cc_mod_path(input, local_def_id);
+                (cc_mod_path, format_fwd_decl(input, local_def_id))
+            },
+        );
+        let ordered_cc = fwd_decls.chain(self.ordered_cc.iter().cloned()).collect_vec();
+
+        let mut includes = self.includes.clone();
+        includes.append(&mut dep_includes);
+        if input.c_mode {
+            includes.insert(CcInclude::assert_h());
+        }
+        if input.crate_linkage == CrateLinkage::DynamicLib {
+            includes.insert(input.support_header("internal/dylib_import.h"));
+        }
+        let includes = format_cc_includes(&includes);
+        let trailing_includes = if trailing_includes.is_empty() {
+            quote! {}
+        } else {
+            let trailing_includes = format_cc_includes(&trailing_includes);
+            quote! { __NEWLINE__ #trailing_includes }
+        };
+        let target_assertion = format_target_assertion(tcx);
+        let ordered_cc = format_namespace_bound_cc_tokens(ordered_cc);
+        if input.c_mode {
+            // The `extern "C"` block makes the header usable from C++ as well.
+            return quote! {
+                #includes
+                __NEWLINE__ __NEWLINE__
+                #target_assertion
+                __HASH_TOKEN__ ifdef __cplusplus __NEWLINE__
+                extern "C" { __NEWLINE__
+                __HASH_TOKEN__ endif __NEWLINE__
+                    #ordered_cc
+                    #abi_stamp_check
+                __NEWLINE__
+                __HASH_TOKEN__ ifdef __cplusplus __NEWLINE__
+                } __NEWLINE__
+                __HASH_TOKEN__ endif __NEWLINE__
+                #trailing_includes
+            };
+        }
+        let cc_std_assertion = input.cc_std.format_assertion();
+        let ordered_cc = format_top_level_ns_block(top_level_ns, inline_ns, ordered_cc);
+        quote! {
+            #includes
+            __NEWLINE__ __NEWLINE__
+            #target_assertion
+            #cc_std_assertion
+            #ordered_cc
+            __NEWLINE__
+            #abi_stamp_check
+            #trailing_includes
+        }
+    }
+}
+
+/// Wraps `body` in the top-level namespace of the crate (and in the inline
+/// namespace from `Input::inline_namespace` if present).  Inline namespaces
+/// can't be spelled using a nested namespace definition (e.g. `namespace
+/// foo::inline v1`) before C++20, and therefore two `namespace` blocks are
+/// used.
+fn format_top_level_ns_block(
+    top_level_ns: &TokenStream,
+    inline_ns: Option<&TokenStream>,
+    body: TokenStream,
+) -> TokenStream {
+    let body = match inline_ns {
+        None => body,
+        Some(inline_ns) => quote! {
+            inline namespace #inline_ns {
+                __NEWLINE__
+                #body
+                __NEWLINE__
+            }
+        },
+    };
+    quote! {
+        namespace #top_level_ns {
+            __NEWLINE__
+            #body
+            __NEWLINE__
+        }
+    }
+}
+
+/// Returns the hash of `tokens`, ignoring comments and whitespace (which don't
+/// affect the API).
+fn hash_api_tokens(tokens: TokenStream, hasher: &mut DefaultHasher) {
+    let mut it = tokens.into_iter();
+    while let Some(tt) = it.next() {
+        match tt {
+            TokenTree::Ident(ident) if ident == "__COMMENT__" => {
+                it.next();
+            }
+            TokenTree::Ident(ident) if ident == "__NEWLINE__" || ident == "__SPACE__" => (),
+            TokenTree::Group(group) => {
+                format!("{:?}", group.delimiter()).hash(hasher);
+                hash_api_tokens(group.stream(), hasher);
+            }
+            tt => tt.to_string().hash(hasher),
+        }
+    }
+}
+
+/// Returns a hash of the bound API: the C++ declarations (which include the
+/// layouts of the structs) and the Rust thunks.
+fn get_abi_hash(parts: &BTreeMap<HeaderPart, HeaderPartBuilder>, rs_body: &TokenStream) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (_, cc_tokens) in parts.values().flat_map(|part| part.ordered_cc.iter()) {
+        hash_api_tokens(cc_tokens.clone(), &mut hasher);
+    }
+    hash_api_tokens(rs_body.clone(), &mut hasher);
+    hasher.finish()
+}
+
+/// Formats the name of the ABI stamp: a symbol that is defined by the Rust side
+/// of the bindings, and that is referenced by the generated C++ header(s) (see
+/// `format_abi_stamp_check`).  The name contains a hash of the bound API (see
+/// `get_abi_hash`).
+fn format_abi_stamp(
+    input: &Input,
+    parts: &BTreeMap<HeaderPart, HeaderPartBuilder>,
+    rs_body: &TokenStream,
+) -> Ident {
+    let crate_name = input.tcx.crate_name(LOCAL_CRATE);
+    format_ident!("__crubit_abi_stamp_{crate_name}_{:016x}", get_abi_hash(parts, rs_body))
+}
+
+/// Formats a reference to the ABI stamp (see `format_abi_stamp`) from the
+/// header of the given `part`, or nothing if `Input::emit_abi_stamp` is false.
+/// A stale header (e.g. one that hasn't been regenerated together with the
+/// Rust side of the bindings after the Rust crate has changed) refers to a
+/// symbol that doesn't exist, and therefore the C++ code that includes it fails
+/// to link.
+fn format_abi_stamp_check(
+    input: &Input,
+    abi_stamp: Option<&Ident>,
+    part: &HeaderPart,
+) -> TokenStream {
+    let Some(abi_stamp) = abi_stamp else {
+        return quote! {};
+    };
+    // The `used` attribute makes sure that the reference is emitted into every translation
+    // unit that includes the header (even if the variable is not used).
+    let check_name = format_ident!("{abi_stamp}_check_{}", part.name());
+    let msg = "Linking fails if this header is out of sync with the Rust side of the bindings \
+               (e.g. if it hasn't been regenerated after the Rust crate has changed).";
+    // In C the address of a `dllimport`ed variable is not a constant expression, so the
+    // `CRUBIT_DYLIB_IMPORT` annotation is only used in C++.
+    let extern_c = if input.c_mode {
+        quote! { extern }
+    } else {
+        let import_attr = input.format_cc_import_attr();
+        quote! { extern "C" #import_attr }
+    };
+    quote! {
+        __NEWLINE__ __COMMENT__ #msg
+        #extern_c const unsigned char #abi_stamp;
+        __attribute__((used)) static const void* const #check_name = &#abi_stamp;
+        __NEWLINE__
+    }
+}
+
+/// Serializable description of the C++ API that `generate_bindings` exposes
+/// for a Rust crate.  This is an alternative to parsing the generated C++
+/// headers for downstream tools (e.g. documentation generators or API diffing
+/// tools).
+#[derive(Debug, Serialize)]
+pub struct ApiIr {
+    /// Name of the Rust crate that the bindings are generated for.
+    pub crate_name: String,
+
+    /// Public items of the crate (including items that are not supported by
+    /// `cc_bindings_from_rs` - see `ApiItem::Unsupported`).
+    pub items: Vec<ApiItem>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ApiItem {
+    Func(ApiFunc),
+    Adt(ApiAdt),
+    Unsupported(ApiUnsupportedItem),
+}
+
+/// A free function or a method.
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiFunc {
+    /// Rust path of the function - e.g. `some_module::some_function`.
+    pub rs_path: String,
+
+    /// Fully qualified name of the C++ function - e.g.
+    /// `::some_crate::some_module::some_function`.
+    pub cc_name: String,
+
+    pub source_loc: String,
+
+    /// The `self` parameter of a method - e.g. `&self` (`None` for free
+    /// functions and static methods).  Not included in `params`.
+    pub receiver: Option<String>,
+
+    pub params: Vec<ApiParam>,
+    pub ret_type: ApiType,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiParam {
+    pub name: String,
+    pub ty: ApiType,
+}
+
+/// A Rust type and the C++ type it is mapped to - e.g. `i32` and
+/// `std::int32_t`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiType {
+    pub rs: String,
+    pub cc: String,
+}
+
+/// A struct, an enum, or a union.
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiAdt {
+    /// Rust path of the ADT - e.g. `some_module::SomeStruct`.
+    pub rs_path: String,
+
+    /// Fully qualified name of the C++ type - e.g.
+    /// `::some_crate::some_module::SomeStruct`.
+    pub cc_name: String,
+
+    /// C++ tag of the type - e.g. `struct`.
+    pub cc_keyword: String,
+
+    pub source_loc: String,
+    pub size_in_bytes: u64,
+    pub alignment_in_bytes: u64,
+
+    /// Public fields that are exposed to C++ (other fields are represented as
+    /// opaque blobs of bytes).
+    pub fields: Vec<ApiField>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiField {
+    pub name: String,
+    pub ty: ApiType,
+}
+
+/// An item that `Output::h_body` only documents with an error comment.
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiUnsupportedItem {
+    pub rs_path: String,
+    pub source_loc: String,
+    pub error: String,
+    pub error_code: ErrorCode,
+}
+
+/// Builds an `ApiIr` for the Rust crate being compiled.  The IR covers the
+/// same items as `generate_bindings`.
+pub fn generate_ir(input: &Input) -> ApiIr {
+    let tcx = input.tcx;
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let items = get_public_item_ids(input)
+        .into_iter()
+        .filter_map(|def_id| {
+            generate_item_ir(input, def_id).unwrap_or_else(|err| {
+                Some(ApiItem::Unsupported(ApiUnsupportedItem {
+                    rs_path: tcx.def_path_str(def_id.to_def_id()),
+                    source_loc: format_source_location(input, def_id),
+                    error: format!("{err:#}"),
+                    error_code: ErrorCode::from_error(&err),
+                }))
+            })
+        })
+        .collect();
+    ApiIr { crate_name, items }
+}
+
+/// Returns an error if bindings can't be generated for one of the items
+/// annotated with `#[crubit::must_bind]`.
+fn check_must_bind_items(input: &Input) -> Result<()> {
+    let tcx = input.tcx;
+    for def_id in get_item_ids(tcx) {
+        if !input.crubit_annotations(def_id.to_def_id()).must_bind {
+            continue;
+        }
+        let name = tcx.def_path_str(def_id.to_def_id());
+        ensure!(
+            tcx.effective_visibilities(()).is_directly_public(def_id),
+            "`{name}` is annotated with `#[crubit::must_bind]`, but it is not public"
+        );
+        ensure!(
+            input.is_selected(def_id),
+            "`{name}` is annotated with `#[crubit::must_bind]`, \
+             but it is excluded from the bindings"
+        );
+        if let Err(err) = generate_item_ir(input, def_id) {
+            bail!(
+                "Error generating bindings for `{name}` (annotated with `#[crubit::must_bind]`): \
+                 {err:#}"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the public items (and public `impl` items) of the crate being
+/// compiled, in the source order.
+fn get_public_item_ids(input: &Input) -> Vec<LocalDefId> {
+    let tcx = input.tcx;
+    get_item_ids(tcx)
+        .into_iter()
+        .filter(|&def_id| tcx.effective_visibilities(()).is_directly_public(def_id))
+        .filter(|&def_id| input.is_selected(def_id))
+        .collect()
+}
+
+/// Returns the items (and `impl` items) of the crate being compiled, in the
+/// source order.
+fn get_item_ids(tcx: TyCtxt) -> Vec<LocalDefId> {
+    tcx.hir()
+        .items()
+        .flat_map(|item_id| {
+            let def_id = item_id.owner_id.def_id;
+            match &tcx.hir().expect_item(def_id).kind {
+                // Methods are covered alongside free functions.
+                ItemKind::Impl(impl_) => impl_
+                    .items
+                    .iter()
+                    .map(|impl_item_ref| impl_item_ref.id.owner_id.def_id)
+                    .collect(),
+                _ => vec![def_id],
+            }
+        })
+        .collect()
+}
+
+/// Returns the `ApiItem` for the item (or `impl` item) identified by `def_id`.
+/// Returns `Ok(None)` if the item doesn't have any bindings (e.g. if it is a
+/// module, or a method of a type that doesn't have bindings). Returns an `Err`
+/// if the bindings couldn't be formatted.
+///
+/// The `ApiItem` is recorded when the bindings of the item are formatted (see
+/// `record_item_ir`).  The item (or, for `impl` items, the ADT whose bindings
+/// include the `impl` item - see `format_impl_items`) is only formatted here
+/// if that hasn't happened yet, or if its bindings have been reused from
+/// `Input::snippet_cache`.
+fn generate_item_ir(
+    input: &Input,
+    def_id: LocalDefId,
+) -> Result<Option<ApiItem>, Rc<anyhow::Error>> {
+    if let Some(ir) = input.interner.item_irs.borrow().get(&def_id) {
+        return ir.clone();
+    }
+    let tcx = input.tcx;
+    let owner_id = match tcx.hir().get_by_def_id(def_id) {
+        Node::Item(_) => def_id,
+        Node::ImplItem(_) => {
+            let self_ty = rustc_compat::type_of(tcx, tcx.local_parent(def_id).to_def_id());
+            match self_ty.ty_adt_def().and_then(|adt_def| adt_def.did().as_local()) {
+                Some(adt_def_id) => adt_def_id,
+                None => return Ok(None),
+            }
+        }
+        other => return Err(Rc::new(anyhow!("Unsupported HIR node kind: {other:?}"))),
+    };
+    if !input.interner.item_irs.borrow().contains_key(&owner_id)
+        && tcx.effective_visibilities(()).is_directly_public(owner_id)
+        && input.is_selected(owner_id)
+    {
+        let result = format_public_item(input, owner_id).map_err(Rc::new);
+        record_item_ir(input, owner_id, &result);
+    }
+    input.interner.item_irs.borrow().get(&def_id).cloned().unwrap_or(Ok(None))
+}
+
+/// Formats a C++ type or name as a single-line string (e.g. `std::int32_t`).
+fn format_cc_tokens_for_ir(tokens: TokenStream) -> Result<String> {
+    Ok(format_cc_tokens(tokens)?.trim().to_string())
+}
+
+fn generate_ty_ir<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<ApiType> {
+    let cc = format_cc_tokens_for_ir(format_field_ty_for_cc(input, ty)?.tokens)?;
+    Ok(ApiType { rs: ty.to_string(), cc })
+}
+
+/// Builds an `ApiFunc` for a function that `format_fn` succeeded for.
+fn generate_fn_ir(input: &Input, local_def_id: LocalDefId) -> Result<ApiFunc> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let sig = get_fn_sig(tcx, def_id);
+
+    let cc_name = match tcx.impl_of_method(def_id) {
+        None => FullyQualifiedName {
+            cc_name: Some(get_fn_cc_name(input, def_id)),
+            ..FullyQualifiedName::new(input, def_id)
+        }
+        .format_for_cc(input)?,
+        Some(impl_id) => {
+            let self_ty = rustc_compat::type_of(tcx, impl_id);
+            let adt_def = self_ty.ty_adt_def().context("Methods are only supported for ADTs")?;
+            let adt_name = FullyQualifiedName::new(input, adt_def.did()).format_for_cc(input)?;
+            let method_name = format_fn_cc_name(get_fn_cc_name(input, def_id))?;
+            quote! { #adt_name :: #method_name }
+        }
+    };
+    let receiver = match get_implicit_self_kind(tcx, local_def_id) {
+        None | Some(ImplicitSelfKind::None) => None,
+        Some(ImplicitSelfKind::ImmRef) => Some("&self"),
+        Some(ImplicitSelfKind::MutRef) => Some("&mut self"),
+        Some(ImplicitSelfKind::Imm) => Some("self"),
+        Some(ImplicitSelfKind::Mut) => Some("mut self"),
+    };
+    let params = tcx
+        .fn_arg_names(def_id)
+        .iter()
+        .zip(sig.inputs().iter())
+        .skip(usize::from(receiver.is_some()))
+        .map(|(name, &ty)| {
+            let cc = format_cc_tokens_for_ir(format_param_ty_for_cc(input, ty)?.tokens)?;
+            Ok(ApiParam { name: name.to_string(), ty: ApiType { rs: ty.to_string(), cc } })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let ret_type = {
+        let cc = format_ret_ty_for_cc(input, sig.output())?.tokens;
+        ApiType { rs: sig.output().to_string(), cc: format_cc_tokens_for_ir(cc)? }
+    };
+
+    Ok(ApiFunc {
+        rs_path: tcx.def_path_str(def_id),
+        cc_name: format_cc_tokens_for_ir(cc_name)?,
+        source_loc: format_source_location(input, local_def_id),
+        receiver: receiver.map(str::to_string),
+        params,
+        ret_type,
+    })
+}
+
+/// Builds an `ApiAdt` for an ADT that `format_adt_core` succeeded for.
+fn generate_adt_ir(input: &Input, local_def_id: LocalDefId) -> Result<ApiAdt> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let AdtCoreBindings { keyword, size_in_bytes, alignment_in_bytes, .. } =
+        format_adt_core(input, def_id)?;
+
+    let ty = rustc_compat::type_of(tcx, def_id);
+    let fields = if ty.is_enum() || ty.is_union() {
+        // See the corresponding comment in `format_adt`.
+        vec![]
+    } else {
+        let substs_ref = ty::List::empty().as_substs();
+        ty.ty_adt_def()
+            .expect("`def_id` needs to identify an ADT")
+            .all_fields()
+            .filter(|field_def| field_def.vis == ty::Visibility::Public)
+            .sorted_by_key(|field_def| tcx.def_span(field_def.did))
+            .filter_map(|field_def| {
+                let ty = generate_ty_ir(input, field_def.ty(tcx, substs_ref)).ok()?;
+                Some(ApiField { name: field_def.ident(tcx).to_string(), ty })
+            })
+            .collect()
+    };
+
+    let cc_name = FullyQualifiedName::new(input, def_id).format_for_cc(input)?;
+    Ok(ApiAdt {
+        rs_path: tcx.def_path_str(def_id),
+        cc_name: format_cc_tokens_for_ir(cc_name)?,
+        cc_keyword: keyword.to_string(),
+        source_loc: format_source_location(input, local_def_id),
+        size_in_bytes,
+        alignment_in_bytes,
+        fields,
+    })
+}
+
+/// Formats a self-test C++ source file for the bindings generated by
+/// `generate_bindings` (see `--cc-self-test-out`).  The file includes the
+/// generated header (`h_include`), and compiling the file validates the
+/// bindings:
+/// - `static_assert`s verify the layouts of the C++ bindings of the ADTs, and
+///   that their traits match the Rust types (e.g. that the C++ type is
+///   trivially destructible if and only if the Rust type doesn't need to be
+///   dropped),
+/// - every bound function is called (with `std::declval` arguments) in an
+///   unevaluated `decltype` operand, and the type of the call is compared with
+///   the expected return type.
+///
+/// The calls are never evaluated, and therefore the file only has to be
+/// compiled - it doesn't have to be linked with the Rust side of the bindings.
+pub fn generate_self_test(input: &Input, h_include: CcInclude) -> Result<TokenStream> {
+    ensure!(!input.c_mode, "Self-tests are not supported by `--c-mode`");
+    let top_comment = {
+        let crate_name = input.tcx.crate_name(LOCAL_CRATE);
+        let txt = format!(
+            "Automatically @generated self-test of the C++ bindings for the following Rust \
+             crate:\n{crate_name}"
+        );
+        quote! { __COMMENT__ #txt __NEWLINE__ }
+    };
+    let includes = format_cc_includes(
+        &[h_include, CcInclude::type_traits(), CcInclude::utility()].into_iter().collect(),
+    );
+    let assertions = get_public_item_ids(input)
+        .into_iter()
+        .map(|def_id| {
+            // Only the items that have bindings are tested (see `generate_item_ir`).
+            match generate_item_ir(input, def_id) {
+                Ok(Some(ApiItem::Adt(_))) => format_adt_self_test(input, def_id),
+                Ok(Some(ApiItem::Func(func))) => format_fn_self_test(input, def_id, &func),
+                _ => Ok(quote! {}),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(quote! {
+        #top_comment
+        __NEWLINE__
+        #includes
+        __NEWLINE__
+        #( #assertions __NEWLINE__ )*
+    })
+}
+
+/// Formats the `static_assert`s that verify the layout and the traits of the
+/// C++ bindings of the ADT identified by `local_def_id` (see
+/// `generate_self_test`).
+fn format_adt_self_test(input: &Input, local_def_id: LocalDefId) -> Result<TokenStream> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let AdtCoreBindings {
+        enum_underlying_type, needs_drop, size_in_bytes, alignment_in_bytes, ..
+    } = format_adt_core(input, def_id)?;
+    let cc_name = FullyQualifiedName::new(input, def_id).format_for_cc(input)?;
+    let size = Literal::u64_unsuffixed(size_in_bytes);
+    let alignment = Literal::u64_unsuffixed(alignment_in_bytes);
+    let not_trivially_destructible = if needs_drop {
+        quote! { ! }
+    } else {
+        quote! {}
+    };
+    // C++ `enum class`es don't have the `kIsSend` and `kIsSync` members (see `format_adt`).
+    let thread_safety_assertions = if enum_underlying_type.is_some() {
+        quote! {}
+    } else {
+        let (is_send, is_sync) = get_send_and_sync(tcx, rustc_compat::type_of(tcx, def_id));
+        quote! {
+            static_assert(#cc_name::kIsSend == #is_send);
+            static_assert(#cc_name::kIsSync == #is_sync);
+        }
+    };
+    Ok(quote! {
+        static_assert(sizeof(#cc_name) == #size);
+        static_assert(alignof(#cc_name) == #alignment);
+        static_assert(#not_trivially_destructible std::is_trivially_destructible_v<#cc_name>);
+        #thread_safety_assertions
+    })
+}
+
+/// Formats a `static_assert` that calls the C++ bindings of the function
+/// identified by `local_def_id` in an unevaluated `decltype` operand, and
+/// verifies the type of the call (see `generate_self_test`).  `func` provides
+/// the C++ types of the parameters and of the return value.
+fn format_fn_self_test(
+    input: &Input,
+    local_def_id: LocalDefId,
+    func: &ApiFunc,
+) -> Result<TokenStream> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let parse_cc_tokens = |cc: &str| -> Result<TokenStream> {
+        cc.parse().map_err(|_| anyhow!("Can't parse `{cc}` as C++ tokens"))
+    };
+    let mut args = func
+        .params
+        .iter()
+        .map(|ApiParam { ty, .. }| {
+            let cc_type = parse_cc_tokens(&ty.cc)?;
+            Ok(quote! { std::declval<#cc_type>() })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // See `needs_unsafe_call_param` in `format_fn`.
+    let is_unsafe = get_fn_sig(tcx, def_id).unsafety == Unsafety::Unsafe;
+    if is_unsafe && input.unsafe_fn_marker == UnsafeFnMarker::TokenParam {
+        args.insert(0, quote! { std::declval<rs_std::UnsafeCall>() });
+    }
+    let ret_type = parse_cc_tokens(&func.ret_type.cc)?;
+    let call = match (tcx.impl_of_method(def_id), get_implicit_self_kind(tcx, local_def_id)) {
+        (Some(impl_id), Some(implicit_self))
+            if !matches!(implicit_self, ImplicitSelfKind::None) =>
+        {
+            let self_ty = rustc_compat::type_of(tcx, impl_id);
+            let adt_def = self_ty.ty_adt_def().context("Methods are only supported for ADTs")?;
+            let adt_name = FullyQualifiedName::new(input, adt_def.did()).format_for_cc(input)?;
+            let method_name = format_fn_cc_name(get_fn_cc_name(input, def_id))?;
+            // The qualifiers of the C++ member function are verified by calling it on an object
+            // of the most restrictive type that the receiver allows.
+            let object_type = match implicit_self {
+                ImplicitSelfKind::ImmRef if !has_interior_mutability(tcx, self_ty) => {
+                    quote! { const #adt_name& }
+                }
+                ImplicitSelfKind::ImmRef | ImplicitSelfKind::MutRef => quote! { #adt_name& },
+                _ => quote! { #adt_name },
+            };
+            quote! { std::declval<#object_type>().#method_name( #( #args ),* ) }
+        }
+        _ => {
+            let fn_name = parse_cc_tokens(&func.cc_name)?;
+            quote! { #fn_name( #( #args ),* ) }
+        }
+    };
+    Ok(quote! {
+        static_assert(std::is_same_v<decltype(#call), #ret_type>);
+    })
+}
+
+/// Machine-readable report of the public items that `generate_bindings`
+/// couldn't generate bindings for.  This is a structured alternative to the
+/// error comments that `format_unsupported_def` puts into `Output::h_body`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    /// Name of the Rust crate that the bindings are generated for.
+    pub crate_name: String,
+
+    pub errors: Vec<ErrorRecord>,
+
+    /// `errors` grouped by their root cause (the most common root causes
+    /// first).  This mirrors how `format_unsupported_defs` groups the error
+    /// comments in `Output::h_body`.
+    pub groups: Vec<ErrorGroup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorRecord {
+    /// Rust path of the unsupported item - e.g. `some_module::some_function`.
+    pub def_path: String,
+
+    /// Kind of the unsupported item - e.g. `function` or `struct`.
+    pub item_kind: String,
+
+    pub span: ErrorSpan,
+    pub error_kind: ErrorKind,
+
+    /// Stable code of the error (e.g. `CRUBIT-E0003`).
+    pub code: ErrorCode,
+
+    /// Full error message (including the context of the root cause).
+    pub message: String,
+
+    /// The innermost error from the chain of errors in `message` - e.g.
+    /// `Generic types are not supported yet (b/259749095)`.
+    pub root_cause: String,
+
+    /// Bug tracking the missing support (e.g. `b/254099023`) if any.
+    pub tracking_bug: Option<String>,
+
+    /// Suggested workaround (e.g. another way to express the same API) if any
+    /// (see `ErrorCode::suggestion`).
+    pub suggestion: Option<&'static str>,
+}
+
+/// Unsupported items that failed because of the same root cause.
+#[derive(Debug, Serialize)]
+pub struct ErrorGroup {
+    pub root_cause: String,
+    pub code: ErrorCode,
+
+    /// Rust paths of the affected items (see `ErrorRecord::def_path`).
+    pub def_paths: Vec<String>,
+}
+
+/// Start of the definition of an item (e.g. of an unsupported item).
+#[derive(Debug, Serialize)]
+pub struct ErrorSpan {
+    pub file: String,
+
+    /// 1-based line number.
+    pub line: usize,
+
+    /// 1-based column number.
+    pub column: usize,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The item uses a Rust feature that `cc_bindings_from_rs` may support in
+    /// the future (see `ErrorRecord::tracking_bug`).
+    NotYetImplemented,
+
+    /// The item can't be supported (or there are no plans to support it).
+    Unsupported,
+}
+
+/// Stable identifiers of the common reasons why bindings couldn't be
+/// generated for an item (e.g. `CRUBIT-E0003` for tuples).  The codes are
+/// printed in the error comments of the generated header and in the reports
+/// (e.g. `--error-report`), so that they can be searched for and filtered on.
+/// Therefore the code of a given reason never changes, and codes of reasons
+/// that are no longer reported are not reused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorCode {
+    /// An error that doesn't have a more specific code.
+    Other,
+    GenericFunction,
+    GenericType,
+    Tuple,
+    UnsafeFunction,
+    SelfParameter,
+    MissingCrateHeader,
+    ZeroSizedType,
+    DropWithoutDefault,
+    ReExportedType,
+    NonTriviallyMovableByValue,
+    RestrictedPositionType,
+    CVariadicFunction,
+    UnsupportedType,
+    CMode,
+    MissingCrubitFeatures,
+    InteriorMutability,
+    UnsupportedItemKind,
+    ExcludedType,
+    TraitWithoutCpp20,
+    UnsupportedTrait,
+    MissingRustStdLib,
+    DuplicateCcName,
+    EnumClassMethod,
+}
+
+impl ErrorCode {
+    /// Returns the stable code of the error - e.g. `CRUBIT-E0003`.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorCode::Other => "CRUBIT-E0000",
+            ErrorCode::GenericFunction => "CRUBIT-E0001",
+            ErrorCode::GenericType => "CRUBIT-E0002",
+            ErrorCode::Tuple => "CRUBIT-E0003",
+            ErrorCode::UnsafeFunction => "CRUBIT-E0004",
+            ErrorCode::SelfParameter => "CRUBIT-E0005",
+            ErrorCode::MissingCrateHeader => "CRUBIT-E0006",
+            ErrorCode::ZeroSizedType => "CRUBIT-E0007",
+            ErrorCode::DropWithoutDefault => "CRUBIT-E0008",
+            ErrorCode::ReExportedType => "CRUBIT-E0009",
+            ErrorCode::NonTriviallyMovableByValue => "CRUBIT-E0010",
+            ErrorCode::RestrictedPositionType => "CRUBIT-E0011",
+            ErrorCode::CVariadicFunction => "CRUBIT-E0012",
+            ErrorCode::UnsupportedType => "CRUBIT-E0013",
+            ErrorCode::CMode => "CRUBIT-E0014",
+            ErrorCode::MissingCrubitFeatures => "CRUBIT-E0015",
+            ErrorCode::InteriorMutability => "CRUBIT-E0016",
+            ErrorCode::UnsupportedItemKind => "CRUBIT-E0017",
+            ErrorCode::ExcludedType => "CRUBIT-E0018",
+            ErrorCode::TraitWithoutCpp20 => "CRUBIT-E0019",
+            ErrorCode::UnsupportedTrait => "CRUBIT-E0020",
+            ErrorCode::MissingRustStdLib => "CRUBIT-E0021",
+            ErrorCode::DuplicateCcName => "CRUBIT-E0022",
+            ErrorCode::EnumClassMethod => "CRUBIT-E0023",
+        }
+    }
+
+    /// Returns a suggested workaround (e.g. another way to express the same
+    /// API) if there is one.
+    pub fn suggestion(self) -> Option<&'static str> {
+        match self {
+            ErrorCode::GenericFunction => {
+                Some("Consider exposing a non-generic wrapper that calls the generic function")
+            }
+            ErrorCode::GenericType => Some(
+                "Consider exposing a non-generic wrapper struct with a field of the generic type",
+            ),
+            ErrorCode::Tuple => Some("Consider using a struct instead of a tuple"),
+            ErrorCode::UnsafeFunction => Some("Consider exposing a safe wrapper function"),
+            ErrorCode::SelfParameter => {
+                Some("Consider exposing a static method or a free function instead")
+            }
+            ErrorCode::MissingCrateHeader => Some(
+                "Generate bindings for the other crate and pass its header via `--crate-header`",
+            ),
+            ErrorCode::ZeroSizedType => Some("Consider adding a field to the type"),
+            ErrorCode::DropWithoutDefault => {
+                Some("Consider implementing (or deriving) `Default` for the type")
+            }
+            ErrorCode::ReExportedType => {
+                Some("Consider using the type through the module where it is defined")
+            }
+            ErrorCode::NonTriviallyMovableByValue => {
+                Some("Consider implementing `Default` for the type")
+            }
+            ErrorCode::CMode => {
+                Some("Consider a wrapper that only uses C-compatible types, or drop `--c-mode`")
+            }
+            ErrorCode::MissingCrubitFeatures => {
+                Some("Add the missing features to the `aspect_hints` of the crate")
+            }
+            ErrorCode::ExcludedType => {
+                Some("Consider removing the type from `--skip` (or adding it to `--bind-only`)")
+            }
+            ErrorCode::TraitWithoutCpp20 => Some("Generate the bindings with `--cc-std=c++20`"),
+            ErrorCode::MissingRustStdLib => Some("Consider a different `--rust-std-lib`"),
+            ErrorCode::DuplicateCcName => {
+                Some("Consider renaming one of the methods with `#[crubit::rename(...)]`")
+            }
+            ErrorCode::EnumClassMethod => Some("Consider exposing a free function instead"),
+            ErrorCode::Other
+            | ErrorCode::RestrictedPositionType
+            | ErrorCode::CVariadicFunction
+            | ErrorCode::UnsupportedType
+            | ErrorCode::InteriorMutability
+            | ErrorCode::UnsupportedItemKind
+            | ErrorCode::UnsupportedTrait => None,
+        }
+    }
+
+    /// Classifies `err` by the code that it has been created with (see
+    /// `coded_anyhow!`).  The innermost code in the chain of causes of `err`
+    /// wins.  Errors without a code are classified as `ErrorCode::Other`.
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        err.chain()
+            .filter_map(|cause| cause.downcast_ref::<CodedError>())
+            .last()
+            .map_or(ErrorCode::Other, |coded_err| coded_err.code)
+    }
+}
+
+/// An error created by `coded_anyhow!`.  Displayed as just the `message` (the
+/// `code` is only used by `ErrorCode::from_error`).
+#[derive(Debug)]
+struct CodedError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl std::fmt::Display for CodedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+/// Prefixes `msg` with `code` (and appends the suggested workaround, if any)
+/// for the error comments in `Output::h_body` - e.g. `[CRUBIT-E0003] ...`.
+fn format_error_code_comment(code: ErrorCode, msg: &str) -> String {
+    match code.suggestion() {
+        None => format!("[{code}] {msg}"),
+        Some(suggestion) => format!("[{code}] {msg}\nSuggestion: {suggestion}"),
+    }
+}
+
+/// Builds an `ErrorReport` for the Rust crate being compiled.  The report
+/// covers the same items as `generate_bindings`.
+pub fn generate_error_report(input: &Input) -> ErrorReport {
+    let tcx = input.tcx;
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let errors = get_public_item_ids(input)
+        .into_iter()
+        .filter_map(|def_id| {
+            let err = generate_item_ir(input, def_id).err()?;
+            Some(format_error_record(input, def_id, &err))
+        })
+        .collect_vec();
+    let mut groups: Vec<ErrorGroup> = vec![];
+    for record in errors.iter() {
+        match groups.iter_mut().find(|group| group.root_cause == record.root_cause) {
+            Some(group) => group.def_paths.push(record.def_path.clone()),
+            None => groups.push(ErrorGroup {
+                root_cause: record.root_cause.clone(),
+                code: record.code,
+                def_paths: vec![record.def_path.clone()],
+            }),
+        }
+    }
+    // `sort_by_key` is stable, so groups of the same size stay in source order.
+    groups.sort_by_key(|group| std::cmp::Reverse(group.def_paths.len()));
+    ErrorReport { crate_name, errors, groups }
+}
+
+fn format_error_record(
+    input: &Input,
+    local_def_id: LocalDefId,
+    err: &anyhow::Error,
+) -> ErrorRecord {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let span = get_item_span(input, local_def_id);
+
+    // https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
+    // says: To print causes as well [...], use the alternate selector “{:#}”.
+    let message = format!("{err:#}");
+    let tracking_bug = get_tracking_bug(&message);
+    let error_kind =
+        if tracking_bug.is_some() { ErrorKind::NotYetImplemented } else { ErrorKind::Unsupported };
+    let code = ErrorCode::from_error(err);
+
+    ErrorRecord {
+        def_path: tcx.def_path_str(def_id),
+        item_kind: tcx.def_kind(def_id).descr(def_id).to_string(),
+        span,
+        error_kind,
+        code,
+        message,
+        root_cause: err.root_cause().to_string(),
+        tracking_bug,
+        suggestion: code.suggestion(),
+    }
+}
+
+fn get_item_span(input: &Input, local_def_id: LocalDefId) -> ErrorSpan {
+    let tcx = input.tcx;
+    let loc = tcx.sess().source_map().lookup_char_pos(tcx.def_span(local_def_id).lo());
+    ErrorSpan {
+        file: format_file_name(input, &loc.file.name),
+        line: loc.line,
+        column: loc.col.0 + 1,
+    }
+}
+
+/// Binding status of every public item of the crate (e.g. for presenting the
+/// interop coverage of the crate in a human-readable report).
+#[derive(Debug)]
+pub struct CoverageReport {
+    /// Name of the Rust crate that the bindings are generated for.
+    pub crate_name: String,
+
+    pub items: Vec<CoverageItem>,
+}
+
+#[derive(Debug)]
+pub struct CoverageItem {
+    /// Rust path of the item - e.g. `some_module::some_function`.
+    pub rs_path: String,
+
+    /// Kind of the item - e.g. `function` or `struct`.
+    pub item_kind: String,
+
+    pub span: ErrorSpan,
+
+    /// C++ signature of the bindings of the item (e.g. `void
+    /// ::some_crate::some_function(std::int32_t x)`), or the error that
+    /// prevented generating the bindings.
+    pub cc_signature: std::result::Result<String, CoverageError>,
+}
+
+#[derive(Debug)]
+pub struct CoverageError {
+    pub code: ErrorCode,
+
+    /// Full error message (including the context of the root cause).
+    pub message: String,
+}
+
+/// Builds a `CoverageReport` for the Rust crate being compiled.  The report
+/// covers the same items as `generate_bindings`.
+pub fn generate_coverage_report(input: &Input) -> CoverageReport {
+    let tcx = input.tcx;
+    let items = get_public_item_ids(input)
+        .into_iter()
+        .filter_map(|local_def_id| {
+            let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+            let cc_signature = match generate_item_ir(input, local_def_id) {
+                Ok(None) => return None,
+                Ok(Some(item)) => Ok(format_cc_signature(&item)),
+                Err(err) => Err(CoverageError {
+                    code: ErrorCode::from_error(&err),
+                    message: format!("{err:#}"),
+                }),
+            };
+            Some(CoverageItem {
+                rs_path: tcx.def_path_str(def_id),
+                item_kind: tcx.def_kind(def_id).descr(def_id).to_string(),
+                span: get_item_span(input, local_def_id),
+                cc_signature,
+            })
+        })
+        .collect();
+    CoverageReport { crate_name: tcx.crate_name(LOCAL_CRATE).to_string(), items }
+}
+
+/// Formats the C++ signature of an `ApiItem` as a single-line string.
+fn format_cc_signature(item: &ApiItem) -> String {
+    match item {
+        ApiItem::Func(func) => {
+            let params = func
+                .params
+                .iter()
+                .map(|param| format!("{} {}", param.ty.cc, param.name))
+                .join(", ");
+            format!("{} {}({params})", func.ret_type.cc, func.cc_name)
+        }
+        ApiItem::Adt(adt) => format!("{} {}", adt.cc_keyword, adt.cc_name),
+        ApiItem::Unsupported(unsupported) => unsupported.error.clone(),
+    }
+}
+
+/// Summary of how many public items of the crate are covered by the bindings
+/// from `generate_bindings` (e.g. for tracking the interop coverage of a crate
+/// over time).
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    /// Name of the Rust crate that the bindings are generated for.
+    pub crate_name: String,
+
+    /// Number of public items (e.g. functions and types, but not modules) that
+    /// have C++ bindings.
+    pub bound_items: usize,
+
+    /// Number of public items that bindings couldn't be generated for.
+    pub unsupported_items: usize,
+
+    /// Reasons why bindings couldn't be generated, from the most common one.
+    pub unsupported_reasons: Vec<UnsupportedReason>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnsupportedReason {
+    /// Root cause of the errors, without the item-specific details (i.e. without
+    /// the part after the first `: `) - e.g. `Tuples are not supported yet`.
+    pub reason: String,
+
+    pub error_kind: ErrorKind,
+    pub code: ErrorCode,
+
+    /// Bug tracking the missing support (e.g. `b/254099023`) if any.
+    pub tracking_bug: Option<String>,
+
+    /// Number of public items that bindings couldn't be generated for, because
+    /// of this reason.
+    pub count: usize,
+}
+
+/// Builds `Stats` for the Rust crate being compiled.  The stats cover the same
+/// items as `generate_bindings`.
+pub fn generate_stats(input: &Input) -> Stats {
+    let tcx = input.tcx;
+    let mut bound_items = 0;
+    let mut reasons: BTreeMap<String, (Option<String>, ErrorCode, usize)> = BTreeMap::new();
+    for def_id in get_public_item_ids(input) {
+        match generate_item_ir(input, def_id) {
+            Ok(None) => (),
+            Ok(Some(_)) => bound_items += 1,
+            Err(err) => {
+                let root_cause = err.root_cause().to_string();
+                let reason = root_cause.split(": ").next().unwrap_or_default().to_string();
+                let (_, _, count) = reasons.entry(reason).or_insert_with(|| {
+                    (get_tracking_bug(&root_cause), ErrorCode::from_error(&err), 0)
+                });
+                *count += 1;
+            }
+        }
+    }
+    let unsupported_reasons = reasons
+        .into_iter()
+        // `sorted_by` is stable, so reasons with the same count stay sorted by name.
+        .sorted_by(|(_, (_, _, count1)), (_, (_, _, count2))| count2.cmp(count1))
+        .map(|(reason, (tracking_bug, code, count))| UnsupportedReason {
+            reason,
+            error_kind: if tracking_bug.is_some() {
+                ErrorKind::NotYetImplemented
+            } else {
+                ErrorKind::Unsupported
+            },
+            code,
+            tracking_bug,
+            count,
+        })
+        .collect_vec();
+    Stats {
+        crate_name: tcx.crate_name(LOCAL_CRATE).to_string(),
+        bound_items,
+        unsupported_items: unsupported_reasons.iter().map(|reason| reason.count).sum(),
+        unsupported_reasons,
+    }
+}
+
+/// Returns whether `path` matches `pattern`, where `*` in `pattern` matches any
+/// (possibly empty) sequence of characters - e.g. `some_module::*` matches
+/// `some_module::some_function`.
+pub fn matches_path_pattern(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().expect("`split` always returns at least one element");
+    let Some(mut rest) = path.strip_prefix(first) else { return false };
+    let parts = parts.collect_vec();
+    match parts.split_last() {
+        None => rest.is_empty(), // No `*` in the `pattern`.
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    None => return false,
+                    Some(index) => rest = &rest[index + part.len()..],
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
+
+/// Extracts the first parenthesized bug reference (e.g. `(b/254099023)`) from
+/// `message`.
+fn get_tracking_bug(message: &str) -> Option<String> {
+    message.match_indices("(b/").find_map(|(index, _)| {
+        let digits: String =
+            message[index + 3..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() {
+            None
+        } else {
+            Some(format!("b/{digits}"))
+        }
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use anyhow::Result;
+    use itertools::Itertools;
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use rustc_middle::ty::{Ty, TyCtxt};
+    use rustc_span::def_id::LocalDefId;
+
+    use crate::run_compiler::tests::run_compiler_for_testing;
+    use code_gen_utils::format_cc_includes;
+    use token_stream_matchers::{
+        assert_cc_matches, assert_cc_not_matches, assert_rs_matches, assert_rs_not_matches,
+    };
+
+    #[test]
     #[should_panic(expected = "No items named `missing_name`.\n\
                                Instead found:\n`bar`,\n`foo`,\n`m1`,\n`m2`,\n`std`")]
     fn test_find_def_id_by_name_panic_when_no_item_with_matching_name() {
         let test_src = r#"
-                pub extern "C" fn foo() {}
+                pub extern "C" fn foo() {}
+
+                pub mod m1 {
+                    pub fn bar() {}
+                }
+                pub mod m2 {
+                    pub fn bar() {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "missing_name"));
+    }
+
+    #[test]
+    #[should_panic(expected = "More than one item named `some_name`")]
+    fn test_find_def_id_by_name_panic_when_multiple_items_with_matching_name() {
+        let test_src = r#"
+                pub mod m1 {
+                    pub fn some_name() {}
+                }
+                pub mod m2 {
+                    pub fn some_name() {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "some_name"));
+    }
+
+    /// This test covers only a single example of a function that should get a
+    /// C++ binding. The test focuses on verification that the output from
+    /// `format_fn` gets propagated all the way to `GenerateBindings::new`.
+    /// Additional coverage of how functions are formatted is provided
+    /// by `test_format_item_..._fn_...` tests (which work at the `format_fn`
+    /// level).
+    #[test]
+    fn test_generated_bindings_fn_no_mangle_extern_c() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function() {
+                    println!("foo");
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    extern "C" void public_function();
+                }
+            );
+
+            // No Rust thunks should be generated in this test scenario.
+            assert_rs_not_matches!(bindings.rs_body, quote!{ public_function });
+        });
+    }
+
+    /// `test_generated_bindings_fn_export_name` covers a scenario where
+    /// `MixedSnippet::cc` is present but `MixedSnippet::rs` is empty
+    /// (because no Rust thunks are needed).
+    #[test]
+    fn test_generated_bindings_fn_export_name() {
+        let test_src = r#"
+                #[export_name = "export_name"]
+                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        inline double public_function(double x, double y);
+                        namespace __crubit_internal {
+                            extern "C" double export_name(double x, double y);
+                        }
+                        inline double public_function(double x, double y) {
+                            return __crubit_internal::export_name(x, y);
+                        }
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_fn_with_c_unwind_abi() {
+        let test_src = r#"
+                #![feature(c_unwind)]
+
+                #[no_mangle]
+                pub extern "C-unwind" fn reinterpret_cast() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    #![allow(improper_ctypes_definitions)]
+                    #![feature(c_unwind)]
+                    ...
+                    #[no_mangle]
+                    extern "C-unwind" fn __crubit_thunk_reinterpret_cast() -> () {
+                        ::rust_out::reinterpret_cast()
+                    }
+                }
+            );
+        });
+    }
+
+    /// The `test_generated_bindings_struct` test covers only a single example
+    /// of an ADT (struct/enum/union) that should get a C++ binding.
+    /// Additional coverage of how items are formatted is provided by
+    /// `test_format_item_..._struct_...`, `test_format_item_..._enum_...`,
+    /// and `test_format_item_..._union_...` tests.
+    ///
+    /// We don't want to duplicate coverage already provided by
+    /// `test_format_item_struct_with_fields`, but we do want to verify that
+    /// * `format_crate` will actually find and process the struct
+    ///   (`test_format_item_...` doesn't cover this aspect - it uses a
+    ///   test-only `find_def_id_by_name` instead)
+    /// * The actual shape of the bindings still looks okay at this level.
+    #[test]
+    fn test_generated_bindings_struct() {
+        let test_src = r#"
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) Point final {
+                            // No point replicating test coverage of
+                            // `test_format_item_struct_with_fields`.
+                            ...
+                        };
+                        static_assert(sizeof(Point) == 8, ...);
+                        static_assert(alignof(Point) == 4, ...);
+                        ... // Other static_asserts are covered by
+                            // `test_format_item_struct_with_fields`
+                    }  // namespace rust_out
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    // No point replicating test coverage of
+                    // `test_format_item_struct_with_fields`.
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, x) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, y) == 4);
+                }
+            );
+        });
+    }
+
+    /// The `test_generated_bindings_impl` test covers only a single example of
+    /// a non-trait `impl`. Additional coverage of how items are formatted
+    /// should be provided in the future by `test_format_item_...` tests.
+    ///
+    /// We don't want to duplicate coverage already provided by
+    /// `test_format_item_static_method`, but we do want to verify that
+    /// * `format_crate` won't process the `impl` as a standalone HIR item
+    /// * The actual shape of the bindings still looks okay at this level.
+    #[test]
+    fn test_generated_bindings_impl() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+
+                impl SomeStruct {
+                    pub fn public_static_method() -> i32 { 123 }
+
+                    #[allow(dead_code)]
+                    fn private_static_method() -> i32 { 123 }
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct ... SomeStruct ... {
+                            // No point replicating test coverage of
+                            // `test_format_item_static_method`.
+                            ...
+                            std::int32_t public_static_method();
+                            ...
+                        };
+                        ...
+                        std::int32_t SomeStruct::public_static_method() {
+                            ...
+                        }
+                    }  // namespace rust_out
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    extern "C" fn ...() -> i32 {
+                        ::rust_out::SomeStruct::public_static_method()
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_includes() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function(i: i32, d: isize, u: u64) {
+                    dbg!(i);
+                    dbg!(d);
+                    dbg!(u);
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __HASH_TOKEN__ include <cstdint> ...
+                    namespace ... {
+                        ...
+                        extern "C" void public_function(
+                            std::int32_t i,
+                            std::intptr_t d,
+                            std::uint64_t u);
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_includes_are_deduplicated` tests that the
+    /// `#include`s needed by multiple items are emitted only once (in a
+    /// single, sorted block at the top of the header).
+    #[test]
+    fn test_generated_bindings_includes_are_deduplicated() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn first_function(i: i32) -> u8 { i as u8 }
+
+                #[no_mangle]
+                pub extern "C" fn second_function(u: u64) -> i16 { u as i16 }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __HASH_TOKEN__ include <cstdint> ...
+                    namespace ... {
+                        ...
+                        extern "C" std::uint8_t first_function(std::int32_t i);
+                        ...
+                        extern "C" std::int16_t second_function(std::uint64_t u);
+                    }
+                }
+            );
+            assert_cc_not_matches!(
+                bindings.h_body,
+                quote! { __HASH_TOKEN__ include <cstdint> ... __HASH_TOKEN__ include <cstdint> }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_cross_crate_type` tests that types from other
+    /// crates are spelled using their fully-qualified C++ name and that the
+    /// bindings `#include` the header from `Input::crate_name_to_include_path`.
+    #[test]
+    fn test_generated_bindings_cross_crate_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function(x: *const std::cmp::Ordering) {
+                    dbg!(x);
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __HASH_TOKEN__ include "crubit/test/core_cc_api.h" ...
+                    namespace rust_out {
+                        ...
+                        extern "C" void public_function(const ::core::cmp::Ordering* x);
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_cpp_type_annotation` tests that Rust bindings
+    /// of C++ types (i.e. types with a `cpp_type` annotation written by
+    /// `rs_bindings_from_cc`) are spelled using the original C++ type, and that
+    /// the bindings `#include` the headers from the `cpp_header` annotations.
+    #[test]
+    fn test_generated_bindings_cpp_type_annotation() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #![crubit_annotate::cpp_header = "some/cc/header.h"]
+
+                #[crubit_annotate::cpp_type = "::ns::CcStruct"]
+                #[derive(Clone, Copy)]
+                #[repr(C)]
+                pub struct CcStruct {
+                    pub x: i32,
+                }
+
+                pub fn get_x(s: CcStruct) -> i32 { s.x }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __HASH_TOKEN__ include "some/cc/header.h" ...
+                    namespace rust_out {
+                        ...
+                        std::int32_t get_x(::ns::CcStruct s);
+                        ...
+                    }
+                }
+            );
+            assert_cc_not_matches!(bindings.h_body, quote! { struct ... CcStruct final });
+        });
+    }
+
+    /// `test_generated_bindings_rust_type_annotation` tests that the C++
+    /// bindings of Rust types are annotated with the original Rust type (so
+    /// that `rs_bindings_from_cc` can map them back to it).
+    #[test]
+    fn test_generated_bindings_rust_type_annotation() {
+        let test_src = r#"
+                pub mod some_module {
+                    pub struct SomeStruct {
+                        pub x: i32,
+                    }
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    struct [[clang::annotate("crubit_rust_type", "::rust_out::some_module::SomeStruct")]]
+                        alignas(4) SomeStruct final { ... }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_without_supported_feature` tests that no
+    /// bindings are generated for a crate that doesn't enable the `supported`
+    /// Crubit feature.
+    #[test]
+    fn test_generated_bindings_without_supported_feature() {
+        let test_src = r#"
+                pub fn public_function() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input =
+                Input { crubit_features: Default::default(), ..bindings_input_for_tests(tcx) };
+            let Output { h_body, rs_body, .. } = generate_bindings(&input).unwrap();
+            assert!(
+                h_body.to_string().contains(
+                    "Missing required Crubit features: `supported` \
+                     (add `//third_party/crubit:supported` to the `aspect_hints` of the crate)"
+                ),
+                "h_body = {h_body}"
+            );
+            assert_cc_not_matches!(h_body, quote! { void public_function(); });
+            assert!(!rs_body.to_string().contains("public_function"), "rs_body = {rs_body}");
+        });
+    }
+
+    /// `test_generated_bindings_cpp_type_annotation_without_experimental_feature`
+    /// tests that using the original C++ types behind `cpp_type` annotations
+    /// requires the `experimental` Crubit feature.
+    #[test]
+    fn test_generated_bindings_cpp_type_annotation_without_experimental_feature() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #![crubit_annotate::cpp_header = "some/cc/header.h"]
+
+                #[crubit_annotate::cpp_type = "::ns::CcStruct"]
+                #[derive(Clone, Copy)]
+                #[repr(C)]
+                pub struct CcStruct {
+                    pub x: i32,
+                }
+
+                pub fn get_x(s: CcStruct) -> i32 { s.x }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                crubit_features: CrubitFeature::Supported.into(),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert!(
+                h_body.to_string().contains("Missing required Crubit features: `experimental`"),
+                "h_body = {h_body}"
+            );
+            assert_cc_not_matches!(h_body, quote! { ... get_x(::ns::CcStruct s); });
+        });
+    }
+
+    /// `test_generated_bindings_custom_namespaces` tests that
+    /// `Input::crate_name_to_namespace` overrides the top-level namespace of
+    /// both the current crate and of other crates.
+    #[test]
+    fn test_generated_bindings_custom_namespaces() {
+        let test_src = r#"
+                pub struct S {
+                    pub x: i32,
+                }
+
+                #[no_mangle]
+                pub extern "C" fn public_function(s: *const S, o: *const std::cmp::Ordering) {
+                    dbg!(s, o);
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                crate_name_to_namespace: [
+                    ("rust_out".into(), "outer::inner".into()),
+                    ("core".into(), "rs_core".into()),
+                ]
+                .into_iter()
+                .collect(),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    namespace outer::inner {
+                        ...
+                        extern "C" void public_function(
+                            const ::outer::inner::S* s,
+                            const ::rs_core::cmp::Ordering* o);
+                    }
+                }
+            );
+            assert_cc_not_matches!(h_body, quote! { rust_out });
+        });
+    }
+
+    /// `test_generated_bindings_bind_only_and_skip` tests that `Input::bind_only`
+    /// and `Input::skip` control which items get bindings.
+    #[test]
+    fn test_generated_bindings_bind_only_and_skip() {
+        let test_src = r#"
+                pub mod api {
+                    pub fn public_api_function() {}
+                    pub fn public_api_function_impl() {}
+
+                    pub mod internal {
+                        pub fn internal_function() {}
+                    }
+                }
+
+                pub fn root_function() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                bind_only: vec!["api".into()],
+                skip: vec!["api::internal".into(), "*_impl".into()],
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(h_body, quote! { void public_api_function(); });
+            assert_cc_not_matches!(h_body, quote! { public_api_function_impl });
+            assert_cc_not_matches!(h_body, quote! { internal_function });
+            assert_cc_not_matches!(h_body, quote! { root_function });
+        });
+    }
+
+    /// `test_generated_bindings_skipped_type` tests that functions that refer
+    /// to a type excluded by `Input::skip` don't get bindings.
+    #[test]
+    fn test_generated_bindings_skipped_type() {
+        let test_src = r#"
+                pub struct SkippedStruct {
+                    pub x: i32,
+                }
+
+                pub fn create() -> SkippedStruct {
+                    SkippedStruct { x: 123 }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input =
+                Input { skip: vec!["SkippedStruct".into()], ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "[CRUBIT-E0018] Error generating bindings for `create` \
+                 defined at <crubit_unittests.rs>;l=6: \
+                 Error formatting function return type: \
+                 Type `SkippedStruct` is excluded from the bindings by `--bind-only` or `--skip`\n\
+                 Suggestion: Consider removing the type from `--skip` (or adding it to \
+                 `--bind-only`)";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+            assert_cc_not_matches!(h_body, quote! { struct SkippedStruct });
+        });
+    }
+
+    #[test]
+    fn test_is_covered_by_path_filter() {
+        assert!(is_covered_by_path_filter("foo", "foo"));
+        assert!(is_covered_by_path_filter("foo", "foo::bar"));
+        assert!(!is_covered_by_path_filter("foo", "foobar"));
+        assert!(!is_covered_by_path_filter("foo::bar", "foo"));
+        assert!(is_covered_by_path_filter("foo::*", "foo::bar"));
+        assert!(is_covered_by_path_filter("*::bar", "foo::bar"));
+        assert!(!is_covered_by_path_filter("*::bar", "foo::bar::baz"));
+    }
+
+    /// `test_generated_bindings_rename_annotation` tests that
+    /// `#[crubit::rename(...)]` changes the C++ names of functions and structs
+    /// (see `support/crubit_attributes.rs` for the `#[doc = ...]` attributes
+    /// that the `#[crubit::...]` attributes expand into).
+    #[test]
+    fn test_generated_bindings_rename_annotation() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::rename = "CcStruct"]
+                pub struct RustStruct {
+                    pub x: i32,
+                }
+
+                impl RustStruct {
+                    #[crubit_annotate::rename = "CcMethod"]
+                    pub fn rust_method() -> i32 { 123 }
+                }
+
+                /// Doc comment of `rust_function`.
+                #[crubit_annotate::rename = "CcFunction"]
+                pub fn rust_function(s: RustStruct) -> i32 { s.x }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct ... CcStruct final {
+                            ...
+                            std::int32_t CcMethod();
+                            ...
+                        };
+                        ...
+                        inline std::int32_t CcStruct::CcMethod() { ... }
+                        ...
+                        __COMMENT__ "Doc comment of `rust_function`.\n\n\
+                                     Generated from: <crubit_unittests.rs>;l=16"
+                        inline std::int32_t CcFunction(::rust_out::CcStruct s);
+                        ...
+                    }
+                }
+            );
+            assert_cc_not_matches!(bindings.h_body, quote! { RustStruct });
+            assert_cc_not_matches!(bindings.h_body, quote! { rust_function });
+            assert_cc_not_matches!(bindings.h_body, quote! { rust_method });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::rust_function(...) });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::RustStruct::rust_method() });
+        });
+    }
+
+    /// `test_generated_bindings_cc_name_collision` tests that items with
+    /// colliding C++ names get disambiguated.
+    #[test]
+    fn test_generated_bindings_cc_name_collision() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[allow(non_snake_case)]
+                pub fn Point() -> i32 { 123 }
+
+                pub struct Point {
+                    pub x: i32,
+                }
+
+                #[crubit_annotate::rename = "foo"]
+                pub fn bar() {}
+
+                pub fn foo() {}
+
+                pub fn foo_1() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let point_comment = "`Point` has been renamed to `Point_1` to avoid a collision \
+                                 with another C++ name from the same namespace";
+            let foo_comment = "`foo` has been renamed to `foo_2` to avoid a collision \
+                               with another C++ name from the same namespace";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        __COMMENT__ #point_comment
+                        ...
+                        inline std::int32_t Point_1();
+                        ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) Point final { ... };
+                        ...
+                        void foo();
+                        ...
+                        __COMMENT__ #foo_comment
+                        ...
+                        void foo_2();
+                        ...
+                        void foo_1();
+                        ...
+                    }
+                }
+            );
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::Point() });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::bar() });
+        });
+    }
+
+    /// `test_generated_bindings_cc_name_collision_after_escaping` tests that
+    /// names which only collide after escaping (see `escape_cc_ident`) get
+    /// disambiguated.
+    #[test]
+    fn test_generated_bindings_cc_name_collision_after_escaping() {
+        let test_src = r#"
+                pub fn class_() {}
+
+                pub fn class() {}
+
+                #[allow(non_snake_case)]
+                pub fn _Foo() {}
+
+                #[allow(non_snake_case)]
+                pub fn Foo() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let class_comment = "`class_` has been renamed to `class_1` to avoid a collision \
+                                 with another C++ name from the same namespace";
+            let foo_comment = "`Foo` has been renamed to `Foo_1` to avoid a collision \
+                               with another C++ name from the same namespace";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        void class_();
+                        ...
+                        __COMMENT__ #class_comment
+                        ...
+                        void class_1();
+                        ...
+                        void Foo();
+                        ...
+                        __COMMENT__ #foo_comment
+                        ...
+                        void Foo_1();
+                        ...
+                    }
+                }
+            );
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::class() });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::Foo() });
+        });
+    }
+
+    /// `test_generated_bindings_skip_annotation` tests that `#[crubit::skip]`
+    /// suppresses the bindings of an item and of the items inside a module.
+    #[test]
+    fn test_generated_bindings_skip_annotation() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::skip]
+                pub fn skipped_function() {}
+
+                #[crubit_annotate::skip]
+                pub mod skipped_module {
+                    pub fn function_in_skipped_module() {}
+                }
+
+                pub fn bound_function() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(bindings.h_body, quote! { void bound_function(); });
+            assert_cc_not_matches!(bindings.h_body, quote! { skipped_function });
+            assert_cc_not_matches!(bindings.h_body, quote! { function_in_skipped_module });
+        });
+    }
+
+    /// `test_generated_bindings_must_bind_annotation` tests that
+    /// `#[crubit::must_bind]` turns an unsupported item into an error.
+    #[test]
+    fn test_generated_bindings_must_bind_annotation() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::must_bind]
+                pub fn tuple_function(_x: (i32, i32)) {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let Err(err) = bindings else { panic!("Expected an error") };
+            assert_eq!(
+                format!("{err:#}"),
+                "Error generating bindings for `tuple_function` \
+                 (annotated with `#[crubit::must_bind]`): \
+                 Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+        });
+    }
+
+    /// `test_generated_bindings_must_bind_annotation_of_private_item` tests
+    /// that `#[crubit::must_bind]` can't be used with private items.
+    #[test]
+    fn test_generated_bindings_must_bind_annotation_of_private_item() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::must_bind]
+                fn private_function() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let Err(err) = bindings else { panic!("Expected an error") };
+            assert_eq!(
+                format!("{err:#}"),
+                "`private_function` is annotated with `#[crubit::must_bind]`, \
+                 but it is not public"
+            );
+        });
+    }
+
+    /// `test_generated_bindings_cc_namespace_annotation` tests that
+    /// `#[crubit::cc_namespace(...)]` of an item or of a module changes the
+    /// C++ namespace of the bindings.
+    #[test]
+    fn test_generated_bindings_cc_namespace_annotation() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::cc_namespace = "x::y"]
+                pub mod m1 {
+                    pub mod m2 {
+                        pub fn f1() {}
+                    }
+                }
+
+                pub mod m3 {
+                    #[crubit_annotate::cc_namespace = "z"]
+                    pub fn f2() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace x::y::m2 {
+                        ...
+                        void f1();
+                        ...
+                    }
+                }
+            );
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace z {
+                        ...
+                        void f2();
+                        ...
+                    }
+                }
+            );
+            assert_cc_not_matches!(bindings.h_body, quote! { m1 });
+            assert_cc_not_matches!(bindings.h_body, quote! { m3 });
+        });
+    }
+
+    /// `test_generated_bindings_rename_map` tests that `Input::rename_map`
+    /// changes the C++ names of modules (i.e. namespaces), structs, and
+    /// functions, and that it takes precedence over `#[crubit::rename(...)]`.
+    #[test]
+    fn test_generated_bindings_rename_map() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                pub mod rust_mod {
+                    pub struct RustStruct {
+                        pub x: i32,
+                    }
+
+                    pub fn rust_function(s: RustStruct) -> i32 { s.x }
+                }
+
+                #[crubit_annotate::rename = "AnnotatedName"]
+                pub fn annotated_function() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let rename_map = [
+                ("rust_mod", "cc_mod"),
+                ("rust_mod::RustStruct", "CcStruct"),
+                ("rust_mod::rust_function", "CcFunction"),
+                ("annotated_function", "MapName"),
+            ]
+            .into_iter()
+            .map(|(path, cc_name)| (path.into(), cc_name.into()))
+            .collect();
+            let input = Input { rename_map, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, rs_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    namespace rust_out {
+                        namespace cc_mod {
+                            ...
+                            struct ... CcStruct final {
+                                ...
+                            };
+                            ...
+                            inline std::int32_t CcFunction(::rust_out::cc_mod::CcStruct s);
+                            ...
+                        }
+                        ...
+                        inline void MapName();
+                        ...
+                    }
+                }
+            );
+            assert_cc_not_matches!(h_body, quote! { rust_mod });
+            assert_cc_not_matches!(h_body, quote! { RustStruct });
+            assert_cc_not_matches!(h_body, quote! { AnnotatedName });
+            assert_rs_matches!(rs_body, quote! { ::rust_out::rust_mod::rust_function(...) });
+        });
+    }
+
+    /// `test_generated_bindings_type_map` tests that `Input::type_map` replaces
+    /// the C++ bindings of a Rust type with a pre-existing C++ type.
+    #[test]
+    fn test_generated_bindings_type_map() {
+        let test_src = r#"
+                pub mod ids {
+                    #[repr(C)]
+                    pub struct Uuid {
+                        pub high: u64,
+                        pub low: u64,
+                    }
+                }
+
+                pub fn get_id() -> ids::Uuid { ids::Uuid { high: 0, low: 0 } }
+                pub fn is_nil(id: ids::Uuid) -> bool { id.high == 0 && id.low == 0 }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let mapping = TypeMapping {
+                cc_type: "::base::Uuid".into(),
+                cc_headers: vec!["base/uuid.h".into()],
+                conversion: None,
+            };
+            let type_map = HashMap::from([("ids::Uuid".into(), mapping)]);
+            let input = Input { type_map, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    __HASH_TOKEN__ include "base/uuid.h"
+                    ...
+                    inline ::base::Uuid get_id();
+                    ...
+                    inline bool is_nil(::base::Uuid id);
+                    ...
+                    inline bool is_nil(::base::Uuid id) {
+                        return __crubit_internal::...(&id);
+                    }
+                }
+            );
+            assert_cc_not_matches!(h_body, quote! { struct ... Uuid });
+        });
+    }
+
+    /// `test_generated_bindings_type_map_with_conversion` tests that the
+    /// generated C++ functions convert the parameters and the return values of
+    /// types mapped to C++ types with a `TypeConversion`.
+    #[test]
+    fn test_generated_bindings_type_map_with_conversion() {
+        let test_src = r#"
+                pub struct Uuid {
+                    high: u64,
+                    low: u64,
+                }
+
+                pub fn get_id() -> Uuid { Uuid { high: 0, low: 0 } }
+                pub fn is_nil(id: Uuid) -> bool { id.high == 0 && id.low == 0 }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let mapping = TypeMapping {
+                cc_type: "::base::Uuid".into(),
+                cc_headers: vec!["base/uuid_conversions.h".into()],
+                conversion: Some(TypeConversion {
+                    to_cc: "::base::UuidFromRust".into(),
+                    from_cc: "::base::UuidToRust".into(),
+                }),
+            };
+            let type_map = HashMap::from([("Uuid".into(), mapping)]);
+            let input = Input { type_map, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    __HASH_TOKEN__ include "base/uuid_conversions.h"
+                    ...
+                    inline ::base::Uuid get_id();
+                    ...
+                    inline bool is_nil(::base::Uuid id);
+                    ...
+                    struct ... Uuid final {
+                        ...
+                    };
+                    ...
+                    inline ::base::Uuid get_id() {
+                        crubit::ReturnValueSlot<::rust_out::Uuid> __ret_slot;
+                        __crubit_internal::...(__ret_slot.Get());
+                        return ::base::UuidFromRust(std::move(__ret_slot).AssumeInitAndTakeValue());
+                    }
+                    ...
+                    inline bool is_nil(::base::Uuid id) {
+                        ::rust_out::Uuid __converted_0 = ::base::UuidToRust(std::move(id));
+                        return __crubit_internal::...(&__converted_0);
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_fn_naming` tests that `Input::fn_naming`
+    /// changes the C++ names of functions and methods, unless overridden by
+    /// `#[crubit::cc_case(...)]` or `#[crubit::rename(...)]`.
+    #[test]
+    fn test_generated_bindings_fn_naming() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                pub fn get_len() -> i32 { 123 }
+
+                #[crubit_annotate::cc_case = "snake_case"]
+                pub fn keep_case() {}
+
+                #[crubit_annotate::rename = "custom_name"]
+                pub fn renamed() {}
+
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+
+                impl SomeStruct {
+                    pub fn static_method() -> i32 { 123 }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let fn_naming =
+                FnNaming { case: CcNameCase::CamelCase, prefix: "Rs".into(), suffix: "".into() };
+            let input = Input { fn_naming, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(h_body, quote! { std::int32_t RsGetLen(); });
+            assert_cc_matches!(h_body, quote! { void Rskeep_case(); });
+            assert_cc_matches!(h_body, quote! { void custom_name(); });
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    struct ... SomeStruct ... {
+                        ...
+                        std::int32_t RsStaticMethod();
+                        ...
+                    };
+                    ...
+                    std::int32_t SomeStruct::RsStaticMethod() {
+                        ...
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_unsafe_fn_marker` tests that
+    /// `UnsafeFnMarker::NameSuffix` adds an `_unsafe` suffix to the C++ names
+    /// of `unsafe` functions and methods (instead of adding an
+    /// `rs_std::UnsafeCall` parameter).
+    #[test]
+    fn test_generated_bindings_unsafe_fn_marker() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                pub unsafe fn foo(x: i32) -> i32 { x }
+
+                #[crubit_annotate::rename = "renamed"]
+                pub unsafe fn bar() {}
+
+                pub fn safe_function() {}
+
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+
+                impl SomeStruct {
+                    pub unsafe fn static_method() -> i32 { 123 }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                unsafe_fn_marker: UnsafeFnMarker::NameSuffix,
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, rs_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(h_body, quote! { inline std::int32_t foo_unsafe(std::int32_t x); });
+            assert_cc_matches!(h_body, quote! { inline void renamed_unsafe(); });
+            assert_cc_matches!(h_body, quote! { inline void safe_function(); });
+            assert_cc_matches!(
+                h_body,
+                quote! { static inline std::int32_t static_method_unsafe(); }
+            );
+            assert_cc_not_matches!(h_body, quote! { rs_std::UnsafeCall });
+            assert_rs_matches!(rs_body, quote! { unsafe { ::rust_out::foo(x) } });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_c_mode_unsafe_fn() {
+        let test_src = r#"
+                #[no_mangle]
+                pub unsafe extern "C" fn foo() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { c_mode: true, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "[CRUBIT-E0004] Error generating bindings for `foo` \
+                 defined at <crubit_unittests.rs>;l=3: \
+                 `unsafe` functions are not supported by `--c-mode`\n\
+                 Suggestion: Consider exposing a safe wrapper function";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+        });
+    }
+
+    #[test]
+    fn test_convert_snake_case_to_camel_case() {
+        assert_eq!(convert_snake_case_to_camel_case("get_len"), "GetLen");
+        assert_eq!(convert_snake_case_to_camel_case("get_2nd"), "Get2nd");
+        assert_eq!(convert_snake_case_to_camel_case("__foo__bar"), "FooBar");
+        assert_eq!(convert_snake_case_to_camel_case("AlreadyCamel"), "AlreadyCamel");
+        assert_eq!(convert_snake_case_to_camel_case("_"), "_");
+    }
+
+    #[test]
+    fn test_generated_bindings_c_mode() {
+        let test_src = r#"
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+
+                impl Point {
+                    pub fn get_x(p: *const Point) -> i32 {
+                        unsafe { (*p).x }
+                    }
+                }
+
+                pub mod some_module {
+                    pub fn add(x: i32, y: i32) -> i32 { x + y }
+                }
+
+                #[no_mangle]
+                pub extern "C" fn rust_out_is_ready() -> bool { true }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { c_mode: true, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, rs_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    __HASH_TOKEN__ include <assert.h>
+                    __HASH_TOKEN__ include <stdalign.h>
+                    __HASH_TOKEN__ include <stdbool.h>
+                    __HASH_TOKEN__ include <stdint.h>
+                    ...
+                    __HASH_TOKEN__ ifdef __cplusplus
+                    extern "C" {
+                        __HASH_TOKEN__ endif
+                        ...
+                        typedef struct rust_out_Point {
+                            alignas(4) unsigned char __opaque_blob_of_bytes[8];
+                        } rust_out_Point;
+                        ...
+                    }
+                }
+            );
+            assert_cc_matches!(
+                h_body,
+                quote! { int32_t rust_out_Point_get_x(const rust_out_Point* p); }
+            );
+            assert_cc_matches!(
+                h_body,
+                quote! { int32_t rust_out_some_module_add(int32_t x, int32_t y); }
+            );
+            assert_cc_matches!(h_body, quote! { bool rust_out_is_ready(void); });
+            assert_cc_matches!(h_body, quote! { static_assert(sizeof(rust_out_Point) == 8, ...); });
+            assert_cc_not_matches!(h_body, quote! { namespace });
+            assert_cc_not_matches!(h_body, quote! { std::int32_t });
+
+            assert_rs_matches!(
+                rs_body,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn rust_out_Point_get_x(p: *const ::rust_out::Point) -> i32 {
+                        ::rust_out::Point::get_x(p)
+                    }
+                }
+            );
+            assert_rs_matches!(
+                rs_body,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn rust_out_some_module_add(x: i32, y: i32) -> i32 {
+                        ::rust_out::some_module::add(x, y)
+                    }
+                }
+            );
+            // No Rust thunk is needed, because the function is already exported with
+            // the expected name.
+            assert_rs_not_matches!(rs_body, quote! { rust_out_is_ready });
+        });
+    }
+
+    /// `test_generated_bindings_c_mode_struct_by_value` tests that
+    /// `Input::c_mode` doesn't support passing structs by value.
+    #[test]
+    fn test_generated_bindings_c_mode_struct_by_value() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+
+                pub fn consume(_s: SomeStruct) {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { c_mode: true, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "[CRUBIT-E0014] Error generating bindings for `consume` \
+                 defined at <crubit_unittests.rs>;l=4: \
+                 Error handling parameter #0: \
+                 Passing `SomeStruct` by value is not supported by `--c-mode`\n\
+                 Suggestion: Consider a wrapper that only uses C-compatible types, or drop \
+                 `--c-mode`";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+            assert_cc_matches!(h_body, quote! { typedef struct rust_out_SomeStruct { ... } });
+        });
+    }
+
+    /// `test_generated_bindings_c_mode_struct_with_custom_drop_impl` tests that
+    /// `Input::c_mode` doesn't support ADTs that need to be dropped (because C
+    /// has no destructors).
+    #[test]
+    fn test_generated_bindings_c_mode_struct_with_custom_drop_impl() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+
+                impl Default for SomeStruct {
+                    fn default() -> Self { Self(0) }
+                }
+
+                impl Drop for SomeStruct {
+                    fn drop(&mut self) {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { c_mode: true, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "[CRUBIT-E0014] Error generating bindings for `SomeStruct` \
+                 defined at <crubit_unittests.rs>;l=2: \
+                 `Drop` trait and \"drop glue\" are not supported by `--c-mode`\n\
+                 Suggestion: Consider a wrapper that only uses C-compatible types, or drop \
+                 `--c-mode`";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+            assert_cc_not_matches!(h_body, quote! { typedef struct rust_out_SomeStruct });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_cc_std() {
+        let test_src = r#"
+                pub fn public_function() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { cc_std: CcStandard::Cxx20, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    static_assert(__cplusplus >= 202002, ...);
+                    namespace rust_out { ... }
+                }
+            );
+
+            let input = Input { cc_std: CcStandard::Cxx17, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_not_matches!(h_body, quote! { __cplusplus });
+        });
+    }
+
+    /// `test_generated_bindings_trait_as_concept` tests that traits are
+    /// formatted as C++20 concepts, and that the methods from the trait `impl`s
+    /// become member functions (which satisfy the concept).
+    #[test]
+    fn test_generated_bindings_trait_as_concept() {
+        let test_src = r#"
+                pub trait Shape {
+                    fn create(size: f64) -> Self;
+                    fn area(&self) -> f64;
+                    fn scale(&mut self, factor: f64);
+                    fn into_size(self) -> f64;
+                }
+
+                pub struct Square(f64);
+
+                impl Shape for Square {
+                    fn create(size: f64) -> Self { Self(size) }
+                    fn area(&self) -> f64 { self.0 * self.0 }
+                    fn scale(&mut self, factor: f64) { self.0 *= factor; }
+                    fn into_size(self) -> f64 { self.0 }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { cc_std: CcStandard::Cxx20, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, rs_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    __HASH_TOKEN__ include <concepts>
+                    ...
+                    template <typename T>
+                    concept Shape = requires {
+                        { T::create(std::declval<double>()) } -> std::same_as<T>;
+                        { std::declval<const T&>().area() } -> std::same_as<double>;
+                        { std::declval<T&>().scale(std::declval<double>()) }
+                            -> std::same_as<void>;
+                        { std::declval<T>().into_size() } -> std::same_as<double>;
+                    };
+                    ...
+                    struct ... Square final {
+                        ...
+                        public:
+                            ...
+                            static inline ::rust_out::Square create(double size);
+                            ...
+                            inline double area() const;
+                            ...
+                            inline void scale(double factor);
+                            ...
+                            inline double into_size() &&;
+                        ...
+                    };
+                }
+            );
+            assert_rs_matches!(
+                rs_body,
+                quote! {
+                    extern "C" fn ...(__self: &'static ::rust_out::Square) -> f64 {
+                        <::rust_out::Square as ::rust_out::Shape>::area(__self)
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_compound_assignment_operators() {
+        let test_src = r#"
+                use std::ops::{AddAssign, ShlAssign};
+
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+
+                impl AddAssign for Point {
+                    fn add_assign(&mut self, other: Self) {
+                        self.x += other.x;
+                        self.y += other.y;
+                    }
+                }
+
+                impl AddAssign<i32> for Point {
+                    fn add_assign(&mut self, delta: i32) {
+                        self.x += delta;
+                        self.y += delta;
+                    }
+                }
+
+                impl ShlAssign<u32> for Point {
+                    fn shl_assign(&mut self, bits: u32) {
+                        self.x <<= bits;
+                        self.y <<= bits;
+                    }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let Output { h_body, rs_body, .. } =
+                generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    struct ... Point final {
+                        ...
+                        public:
+                            ...
+                            inline void operator+=(::rust_out::Point other);
+                            ...
+                            inline void operator+=(std::int32_t delta);
+                            ...
+                            inline void operator<<=(std::uint32_t bits);
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    inline void Point::operator+=(std::int32_t delta) {
+                        return __crubit_internal::...(this, delta);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                rs_body,
+                quote! {
+                    extern "C" fn ...(__self: &'static mut ::rust_out::Point, delta: i32) ... {
+                        <::rust_out::Point as ::core::ops::AddAssign<i32> >::add_assign(
+                            __self, delta)
+                    }
+                }
+            );
+            assert_rs_matches!(
+                rs_body,
+                quote! {
+                    <::rust_out::Point as ::core::ops::AddAssign<::rust_out::Point> >::add_assign(
+                        __self, unsafe { other.assume_init_read() })
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_unary_operators() {
+        let test_src = r#"
+                use std::ops::{Neg, Not};
+
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+
+                impl Neg for Point {
+                    type Output = Self;
+                    fn neg(self) -> Self::Output {
+                        Point { x: -self.x, y: -self.y }
+                    }
+                }
+
+                pub struct Flags(pub u8);
+
+                impl Not for Flags {
+                    type Output = Flags;
+                    fn not(self) -> Flags {
+                        Flags(!self.0)
+                    }
+                }
+
+                pub struct Switch {
+                    pub is_on: bool,
+                }
+
+                impl Not for Switch {
+                    type Output = bool;
+                    fn not(self) -> bool {
+                        !self.is_on
+                    }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let Output { h_body, rs_body, .. } =
+                generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    struct ... Point final {
+                        ...
+                        public:
+                            ...
+                            inline ::rust_out::Point operator-() &&;
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    struct ... Flags final {
+                        ...
+                        public:
+                            ...
+                            inline ::rust_out::Flags operator~() &&;
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    struct ... Switch final {
+                        ...
+                        public:
+                            ...
+                            inline bool operator!() &&;
+                        ...
+                    };
+                }
+            );
+            assert_rs_matches!(
+                rs_body,
+                quote! {
+                    <::rust_out::Point as ::core::ops::Neg>::neg(
+                        unsafe { __self.assume_init_read() })
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generate_self_test() {
+        let test_src = r#"
+                pub struct Counter {
+                    value: i32,
+                }
+
+                impl Drop for Counter {
+                    fn drop(&mut self) {}
+                }
+
+                impl Counter {
+                    pub fn get(&self) -> i32 {
+                        self.value
+                    }
+                }
+
+                pub fn add(x: i32, y: i32) -> i32 {
+                    x + y
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = bindings_input_for_tests(tcx);
+            let self_test =
+                generate_self_test(&input, CcInclude::user_header("rust_out_cc_api.h".into()))
+                    .unwrap();
+            assert_cc_matches!(
+                self_test,
+                quote! {
+                    __HASH_TOKEN__ include <type_traits>
+                    __HASH_TOKEN__ include <utility>
+                    __HASH_TOKEN__ include "rust_out_cc_api.h"
+                }
+            );
+            assert_cc_matches!(
+                self_test,
+                quote! {
+                    static_assert(sizeof(::rust_out::Counter) == 4);
+                    static_assert(alignof(::rust_out::Counter) == 4);
+                    static_assert(!std::is_trivially_destructible_v<::rust_out::Counter>);
+                    static_assert(::rust_out::Counter::kIsSend == true);
+                    static_assert(::rust_out::Counter::kIsSync == true);
+                }
+            );
+            assert_cc_matches!(
+                self_test,
+                quote! {
+                    static_assert(std::is_same_v<
+                        decltype(std::declval<const ::rust_out::Counter&>().get()),
+                        std::int32_t>);
+                }
+            );
+            assert_cc_matches!(
+                self_test,
+                quote! {
+                    static_assert(std::is_same_v<
+                        decltype(::rust_out::add(std::declval<std::int32_t>(),
+                                                 std::declval<std::int32_t>())),
+                        std::int32_t>);
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generate_self_test_with_c_mode() {
+        let test_src = r#"
+                pub fn foo() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { c_mode: true, ..bindings_input_for_tests(tcx) };
+            let err =
+                generate_self_test(&input, CcInclude::user_header("rust_out_cc_api.h".into()))
+                    .unwrap_err();
+            assert_eq!(format!("{err:#}"), "Self-tests are not supported by `--c-mode`");
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_trait_without_cc_std_20() {
+        let test_src = r#"
+                pub trait Shape {
+                    fn area(&self) -> f64;
+                }
+
+                pub struct Square(f64);
+
+                impl Shape for Square {
+                    fn area(&self) -> f64 { self.0 * self.0 }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { cc_std: CcStandard::Cxx17, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let unsupported_msg = "[CRUBIT-E0019] Error generating bindings for `Shape` \
+                                   defined at <crubit_unittests.rs>;l=2: \
+                                   Traits are only supported (as C++20 concepts) \
+                                   with `--cc-std=c++20`\n\
+                                   Suggestion: Generate the bindings with `--cc-std=c++20`";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #unsupported_msg });
+            assert_cc_not_matches!(h_body, quote! { concept });
+            // The methods from the trait `impl` are still available as member functions.
+            assert_cc_matches!(h_body, quote! { inline double area() const; });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_unsupported_trait_with_associated_type() {
+        let test_src = r#"
+                pub trait SomeTrait {
+                    type Output;
+                    fn get(&self) -> i32;
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { cc_std: CcStandard::Cxx20, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let unsupported_msg = "[CRUBIT-E0020] Error generating bindings for `SomeTrait` \
+                                   defined at \
+                                   <crubit_unittests.rs>;l=2: \
+                                   Only methods are supported in traits \
+                                   (`Output` is not a method)";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #unsupported_msg });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_trait_method_with_same_name_as_inherent_method() {
+        let test_src = r#"
+                pub trait Shape {
+                    fn area(&self) -> f64;
+                }
+
+                pub struct Square(f64);
+
+                impl Square {
+                    pub fn area(&self) -> f64 { self.0 * self.0 }
+                }
+
+                impl Shape for Square {
+                    fn area(&self) -> f64 { self.0 * self.0 }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { cc_std: CcStandard::Cxx20, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let unsupported_msg = "[CRUBIT-E0022] Error generating bindings for `<Square as \
+                                   Shape>::area` \
+                                   defined at <crubit_unittests.rs>;l=13: \
+                                   The C++ name `area` is already used by another method\n\
+                                   Suggestion: Consider renaming one of the methods with \
+                                   `#[crubit::rename(...)]`";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #unsupported_msg });
+        });
+    }
+
+    /// `test_generated_bindings_target_assertion` tests that the generated
+    /// header verifies that it is compiled for the pointer size of the Rust
+    /// target.
+    #[test]
+    fn test_generated_bindings_target_assertion() {
+        let test_src = r#"
+                pub fn get_len() -> usize { 123 }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let pointer_size = Literal::u64_unsuffixed(tcx.data_layout.pointer_size.bytes());
+            let Output { h_body, .. } = generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    static_assert(sizeof(void*) == #pointer_size, ...);
+                    namespace rust_out {
+                        ...
+                        std::uintptr_t get_len();
+                        ...
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_cc_out` tests that `Input::cc_out_h_include`
+    /// moves the layout assertions from the header into `Output::cc_body`.
+    #[test]
+    fn test_generated_bindings_cc_out() {
+        let test_src = r#"
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                cc_out_h_include: Some(CcInclude::user_header("some/path/rust_out.h".into())),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, cc_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) Point final {
+                        ...
+                        private:
+                            ...
+                            static void __crubit_field_offset_assertions();
+                    };
+                    ...
+                }
+            );
+            assert_cc_not_matches!(h_body, quote! { static_assert(sizeof(Point) ...) });
+            assert_cc_not_matches!(h_body, quote! { inline static void });
+            assert_cc_matches!(
+                cc_body,
+                quote! {
+                    __HASH_TOKEN__ include <cstddef>
+                    __HASH_TOKEN__ include "some/path/rust_out.h"
+                    ...
+                    namespace rust_out {
+                        ...
+                        static_assert(sizeof(Point) == 8, ...);
+                        static_assert(alignof(Point) == 4, ...);
+                        void Point::__crubit_field_offset_assertions() {
+                            static_assert(0 == offsetof(Point, x));
+                            static_assert(4 == offsetof(Point, y));
+                        }
+                        ...
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_cc_out_absent` tests that `Output::cc_body` is
+    /// empty when `Input::cc_out_h_include` is absent.
+    #[test]
+    fn test_generated_bindings_cc_out_absent() {
+        let test_src = r#"
+                pub struct Point {
+                    pub x: i32,
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let Output { h_body, cc_body, .. } =
+                generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
+            assert!(cc_body.is_empty());
+            assert_cc_matches!(h_body, quote! { static_assert(sizeof(Point) == 4, ...); });
+        });
+    }
+
+    /// `test_generated_bindings_line_directives` tests that
+    /// `Input::emit_line_directives` makes the generated declarations point at
+    /// the Rust definitions (and back at the generated header afterwards).
+    #[test]
+    fn test_generated_bindings_line_directives() {
+        let test_src = r#"
+                pub fn public_function() {}
+
+                /// Doc comment of `SomeStruct`.
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { emit_line_directives: true, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    __COMMENT__ "Generated from: <crubit_unittests.rs>;l=2"
+                    __HASH_TOKEN__ line 2 "<crubit_unittests.rs>"
+                    ... void public_function();
+                    __HASH_TOKEN__ line __crubit_header_line__
+                    ...
+                    __COMMENT__ "Doc comment of `SomeStruct`.\n\n\
+                                 Generated from: <crubit_unittests.rs>;l=5"
+                    __HASH_TOKEN__ line 5 "<crubit_unittests.rs>"
+                    struct ... SomeStruct final {
+                        ...
+                    };
+                    __HASH_TOKEN__ line __crubit_header_line__
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_line_directives_absent` tests that no `#line`
+    /// directives are emitted by default.
+    #[test]
+    fn test_generated_bindings_line_directives_absent() {
+        let test_src = r#"
+                pub fn public_function() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { __HASH_TOKEN__ line });
+        });
+    }
+
+    /// `test_generated_bindings_abi_stamp` tests that the generated header
+    /// refers to the ABI stamp defined by the Rust side of the bindings, and
+    /// that the stamp only changes when the bound API changes.
+    #[test]
+    fn test_generated_bindings_abi_stamp() {
+        let get_abi_stamp = |test_src: &str| {
+            run_compiler_for_testing(test_src, |tcx| {
+                let input = Input { emit_abi_stamp: true, ..bindings_input_for_tests(tcx) };
+                let Output { h_body, rs_body, .. } = generate_bindings(&input).unwrap();
+                assert_rs_matches!(
+                    rs_body,
+                    quote! {
+                        #[no_mangle]
+                        #[used]
+                        static ...: u8 = 0;
+                    }
+                );
+                let rs_body = rs_body.to_string();
+                let prefix = "__crubit_abi_stamp_rust_out_";
+                let start = rs_body.find(prefix).expect("The ABI stamp should be defined");
+                let abi_stamp = rs_body[start..]
+                    .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .next()
+                    .unwrap()
+                    .to_string();
+
+                let h_body = h_body.to_string();
+                let expected_decl = format!("extern \"C\" const unsigned char {abi_stamp} ;");
+                assert!(h_body.contains(&expected_decl), "{h_body}");
+                let expected_check = format!(
+                    "__attribute__ ((used)) static const void * const {abi_stamp}_check_crate = & \
+                     {abi_stamp} ;"
+                );
+                assert!(h_body.contains(&expected_check), "{h_body}");
+                abi_stamp
+            })
+        };
+
+        let abi_stamp = get_abi_stamp("pub fn add_i32(x: i32, y: i32) -> i32 { x + y }");
+        let abi_stamp_after_doc_change = get_abi_stamp(
+            "/// Doc comment.\n\
+             pub fn add_i32(x: i32, y: i32) -> i32 { x + y }",
+        );
+        let abi_stamp_after_sig_change =
+            get_abi_stamp("pub fn add_i32(x: i32, y: i64) -> i32 { x + y as i32 }");
+        assert_eq!(abi_stamp, abi_stamp_after_doc_change);
+        assert_ne!(abi_stamp, abi_stamp_after_sig_change);
+    }
+
+    /// `test_generated_bindings_abi_stamp_absent` tests that no ABI stamp is
+    /// emitted by default.
+    #[test]
+    fn test_generated_bindings_abi_stamp_absent() {
+        let test_src = "pub fn add_i32(x: i32, y: i32) -> i32 { x + y }";
+        test_generated_bindings(test_src, |bindings| {
+            let Output { h_body, rs_body, .. } = bindings.unwrap();
+            assert!(!h_body.to_string().contains("__crubit_abi_stamp"), "{h_body}");
+            assert!(!rs_body.to_string().contains("__crubit_abi_stamp"), "{rs_body}");
+        });
+    }
+
+    /// `test_generated_bindings_inline_namespace_crate_version` tests that the
+    /// bindings are wrapped in an inline namespace derived from the crate
+    /// version.
+    #[test]
+    fn test_generated_bindings_inline_namespace_crate_version() {
+        let test_src = "pub fn public_function() {}";
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                inline_namespace: Some(InlineNamespace::CrateVersion("1.2.3-beta".into())),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    namespace rust_out {
+                        inline namespace v1_2_3_beta {
+                            ...
+                            void public_function();
+                            ...
+                        }
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_inline_namespace_abi_hash` tests that the name
+    /// of the inline namespace derived from the ABI hash only changes when the
+    /// bound API changes.
+    #[test]
+    fn test_generated_bindings_inline_namespace_abi_hash() {
+        let get_inline_ns = |test_src: &str| {
+            run_compiler_for_testing(test_src, |tcx| {
+                let input = Input {
+                    inline_namespace: Some(InlineNamespace::AbiHash),
+                    ..bindings_input_for_tests(tcx)
+                };
+                let Output { h_body, .. } = generate_bindings(&input).unwrap();
+                let h_body = h_body.to_string();
+                let prefix = "namespace rust_out { inline namespace ";
+                let start = h_body.find(prefix).expect("The inline namespace should be present");
+                h_body[start + prefix.len()..].split(' ').next().unwrap().to_string()
+            })
+        };
+
+        let inline_ns = get_inline_ns("pub fn add_i32(x: i32, y: i32) -> i32 { x + y }");
+        let inline_ns_after_doc_change = get_inline_ns(
+            "/// Doc comment.\n\
+             pub fn add_i32(x: i32, y: i32) -> i32 { x + y }",
+        );
+        let inline_ns_after_sig_change =
+            get_inline_ns("pub fn add_i32(x: i32, y: i64) -> i32 { x + y as i32 }");
+        assert!(inline_ns.starts_with("abi_"), "{inline_ns}");
+        assert_eq!(inline_ns, inline_ns_after_doc_change);
+        assert_ne!(inline_ns, inline_ns_after_sig_change);
+    }
+
+    /// `test_generated_bindings_inline_namespace_absent` tests that there is no
+    /// inline namespace by default.
+    #[test]
+    fn test_generated_bindings_inline_namespace_absent() {
+        let test_src = "pub fn public_function() {}";
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { inline namespace });
+        });
+    }
+
+    #[test]
+    fn test_finalize_line_directives() {
+        let h_content = "// comment\n\
+                         #line 2 \"foo.rs\"\n\
+                         void foo();\n\
+                         #line __crubit_header_line__\n\
+                         void bar();\n";
+        assert_eq!(
+            finalize_line_directives(h_content, "foo_cc_api.h"),
+            "// comment\n\
+             #line 2 \"foo.rs\"\n\
+             void foo();\n\
+             #line 5 \"foo_cc_api.h\"\n\
+             void bar();\n"
+        );
+    }
+
+    #[test]
+    fn test_remap_path_prefix() {
+        let remap_path_prefixes: Vec<(Rc<str>, Rc<str>)> = vec![
+            ("/home/user/src".into(), "".into()),
+            ("/home/user".into(), "home".into()),
+            ("/home/user/src/third_party".into(), "external".into()),
+        ];
+        let remap = |path| remap_path_prefix(&remap_path_prefixes, path);
+        assert_eq!(remap("/home/user/src/foo/lib.rs"), "foo/lib.rs");
+        assert_eq!(remap("/home/user/other/lib.rs"), "home/other/lib.rs");
+        // The last matching prefix wins.
+        assert_eq!(remap("/home/user/src/third_party/bar/lib.rs"), "external/bar/lib.rs");
+        // Prefixes only match whole path components.
+        assert_eq!(remap("/home/user_2/lib.rs"), "/home/user_2/lib.rs");
+        assert_eq!(remap("lib.rs"), "lib.rs");
+    }
+
+    /// `test_format_items_matches_serial_formatting` tests that `format_items`
+    /// (which formats the items via rustc's parallel queries) returns the same
+    /// results, in the same order, as formatting the items one by one.
+    #[test]
+    fn test_format_items_matches_serial_formatting() {
+        let test_src = r#"
+                pub struct S { pub x: i32 }
+                pub fn get_x(s: S) -> i32 { s.x }
+                pub fn unsupported_fn(_: (i32, i32)) {}
+                pub mod some_module {
+                    pub fn get_42() -> i32 { 42 }
+                }
+                pub enum E { A, B }
+            "#;
+        let format_results = |results: FormattedItems| {
+            results
+                .into_iter()
+                .map(|(def_id, result)| {
+                    let result = result
+                        .map(|snippets| {
+                            snippets
+                                .into_iter()
+                                .map(|(key, MixedSnippet { cc, rs, cc_out })| {
+                                    format!("{key:?}: {} {rs} {cc_out}", cc.tokens)
+                                })
+                                .collect_vec()
+                        })
+                        .map_err(|err| format!("{err:#}"));
+                    (format!("{def_id:?}"), result)
+                })
+                .collect_vec()
+        };
+        let parallel_results = run_compiler_for_testing(test_src, |tcx| {
+            format_results(format_items(&bindings_input_for_tests(tcx)))
+        });
+        let serial_results = run_compiler_for_testing(test_src, |tcx| {
+            let input = bindings_input_for_tests(tcx);
+            let results = tcx
+                .hir()
+                .items()
+                .map(|item_id| {
+                    let def_id = item_id.owner_id.def_id;
+                    (def_id, format_item(&input, def_id))
+                })
+                .collect();
+            format_results(results)
+        });
+        assert_eq!(parallel_results, serial_results);
+    }
+
+    /// Generates bindings for `test_src` using a `SnippetCache` created from
+    /// `cache_json`.  Returns the generated C++ header and the updated cache.
+    fn generate_bindings_with_snippet_cache(
+        test_src: &str,
+        cache_json: Option<&str>,
+    ) -> (String, String) {
+        run_compiler_for_testing(test_src, |tcx| {
+            let cache = SnippetCache::new(cache_json, "test invocation".to_string());
+            let input =
+                Input { snippet_cache: Some(RefCell::new(cache)), ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let cache_json = input.snippet_cache.unwrap().into_inner().to_json().unwrap();
+            (h_body.to_string(), cache_json)
+        })
+    }
+
+    /// `test_generated_bindings_snippet_cache_reuse` tests that cached
+    /// bindings of unchanged items are reused.
+    #[test]
+    fn test_generated_bindings_snippet_cache_reuse() {
+        let test_src = r#"
+                /// Doc of `add_i32`.
+                pub fn add_i32(x: i32, y: i32) -> i32 { x + y }
+            "#;
+        let (h_body, cache_json) = generate_bindings_with_snippet_cache(test_src, None);
+        assert!(h_body.contains("Doc of `add_i32`."), "{h_body}");
+
+        // Tampering with the cache shows whether the cached bindings have been reused.
+        let cache_json = cache_json.replace("Doc of", "Cached doc of");
+        let (h_body, _) = generate_bindings_with_snippet_cache(test_src, Some(&cache_json));
+        assert!(h_body.contains("Cached doc of `add_i32`."), "{h_body}");
+    }
+
+    /// `test_generated_bindings_snippet_cache_invalidation` tests that cached
+    /// bindings are not reused for items that changed (or that use types that
+    /// changed), nor when the invocation of the tool changed.
+    #[test]
+    fn test_generated_bindings_snippet_cache_invalidation() {
+        let test_src = r#"
+                /// Doc of `S`.
+                pub struct S { pub x: i32 }
+                /// Doc of `get_x`.
+                pub fn get_x(s: S) -> i32 { s.x }
+                /// Doc of `add_i32`.
+                pub fn add_i32(x: i32, y: i32) -> i32 { x + y }
+            "#;
+        let (_, cache_json) = generate_bindings_with_snippet_cache(test_src, None);
+        let cache_json = cache_json.replace("Doc of", "Cached doc of");
+
+        // Changing the type of the field (without changing the spans of the other items).
+        let changed_src = test_src.replace("pub x: i32", "pub x: i64").replace("s.x", "s.x as i32");
+        let (h_body, _) = generate_bindings_with_snippet_cache(&changed_src, Some(&cache_json));
+        assert!(h_body.contains("Cached doc of `add_i32`."), "{h_body}");
+        assert!(h_body.contains("Doc of `get_x`."), "{h_body}");
+        assert!(h_body.contains("Doc of `S`."), "{h_body}");
+        assert!(!h_body.contains("Cached doc of `get_x`."), "{h_body}");
+        assert!(!h_body.contains("Cached doc of `S`."), "{h_body}");
+        assert!(h_body.contains("std :: int64_t x"), "{h_body}");
+
+        // A different invocation of the tool invalidates the whole cache.
+        let other_invocation_h_body = run_compiler_for_testing(test_src, |tcx| {
+            let cache = SnippetCache::new(Some(&cache_json), "other invocation".to_string());
+            let input =
+                Input { snippet_cache: Some(RefCell::new(cache)), ..bindings_input_for_tests(tcx) };
+            generate_bindings(&input).unwrap().h_body.to_string()
+        });
+        assert!(!other_invocation_h_body.contains("Cached doc of"), "{other_invocation_h_body}");
+    }
+
+    /// `test_generated_bindings_snippet_cache_invalidation_by_drop_impl` tests
+    /// that adding or removing a trait impl that doesn't get bindings (but
+    /// affects the bindings of the ADT - here: `impl Drop`) invalidates the
+    /// cached bindings of the ADT.
+    #[test]
+    fn test_generated_bindings_snippet_cache_invalidation_by_drop_impl() {
+        let test_src = r#"
+                /// Doc of `S`.
+                #[derive(Default)]
+                pub struct S { pub x: i32 }
+            "#;
+        let drop_impl_src = format!("{test_src} impl Drop for S {{ fn drop(&mut self) {{}} }}");
+
+        let (h_body, cache_json) = generate_bindings_with_snippet_cache(test_src, None);
+        assert!(!h_body.contains("__crubit_thunk_drop_"), "{h_body}");
+        let cache_json = cache_json.replace("Doc of", "Cached doc of");
+
+        // Adding `impl Drop` (after `S`, so that the span of `S` doesn't change).
+        let (h_body, cache_json) =
+            generate_bindings_with_snippet_cache(&drop_impl_src, Some(&cache_json));
+        assert!(h_body.contains("Doc of `S`."), "{h_body}");
+        assert!(!h_body.contains("Cached doc of `S`."), "{h_body}");
+        assert!(h_body.contains("__crubit_thunk_drop_"), "{h_body}");
+        let cache_json = cache_json.replace("Doc of", "Cached doc of");
+
+        // Removing `impl Drop` again (reusing the cache from the previous run).
+        let (h_body, _) = generate_bindings_with_snippet_cache(test_src, Some(&cache_json));
+        assert!(h_body.contains("Doc of `S`."), "{h_body}");
+        assert!(!h_body.contains("Cached doc of `S`."), "{h_body}");
+        assert!(!h_body.contains("__crubit_thunk_drop_"), "{h_body}");
+    }
+
+    /// `test_generated_bindings_invalid_namespace` tests that an invalid entry
+    /// in `Input::crate_name_to_namespace` results in an error.
+    #[test]
+    fn test_generated_bindings_invalid_namespace() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                crate_name_to_namespace: [("rust_out".into(), "outer::::inner".into())]
+                    .into_iter()
+                    .collect(),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "Failed to generate bindings for the crate: \
+                 Invalid top-level namespace: `outer::::inner`";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+        });
+    }
+
+    fn split_headers_for_tests(modules: &[&str]) -> Option<SplitHeaders> {
+        Some(SplitHeaders {
+            h_out: "foo/foo_cc_api.h".into(),
+            module_headers: modules
+                .iter()
+                .map(|&module| (module.into(), format!("foo/foo_{module}_cc_api.h").into()))
+                .collect(),
+        })
+    }
+
+    /// `test_generated_bindings_split_by_module` tests that the bindings of the
+    /// modules from `SplitHeaders::module_headers` are moved into separate
+    /// headers when `Input::split_headers` is present.
+    #[test]
+    fn test_generated_bindings_split_by_module() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn root_function() {}
+
+                pub mod m1 {
+                    pub struct S1 {
+                        pub x: i32,
+                    }
+                }
+
+                pub mod m2 {
+                    pub fn get_x(s: crate::m1::S1) -> i32 { s.x }
+                }
+
+                pub mod unlisted {
+                    #[no_mangle]
+                    pub extern "C" fn unlisted_function() {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                split_headers: split_headers_for_tests(&["m1", "m2"]),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, h_parts, .. } = generate_bindings(&input).unwrap();
+
+            // The main header contains the bindings of the items at the crate root and of the
+            // modules without a separate header, and `#include`s the module headers.
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        extern "C" void root_function();
+                        ...
+                        namespace unlisted {
+                            ...
+                            extern "C" void unlisted_function();
+                            ...
+                        }
+                        ...
+                    }
+                    ...
+                    __HASH_TOKEN__ include "foo/foo_m1_cc_api.h"
+                    __HASH_TOKEN__ include "foo/foo_m2_cc_api.h"
+                }
+            );
+            assert_cc_not_matches!(h_body, quote! { S1 });
+
+            let h_paths = h_parts.iter().map(|(h_path, _)| h_path.as_str()).collect_vec();
+            assert_eq!(h_paths, ["foo/foo_m1_cc_api.h", "foo/foo_m2_cc_api.h"]);
+            let (_, m1_body) = &h_parts[0];
+            assert_cc_matches!(m1_body, quote! { namespace m1 { ... struct ... S1 ... } });
+            assert_cc_not_matches!(m1_body, quote! { get_x });
+            assert_cc_not_matches!(m1_body, quote! { root_function });
+            let (_, m2_body) = &h_parts[1];
+            assert_cc_matches!(m2_body, quote! { __HASH_TOKEN__ include "foo/foo_m1_cc_api.h" });
+            assert_cc_matches!(
+                m2_body,
+                quote! { namespace m2 { ... std::int32_t get_x(::rust_out::m1::S1 s); ... } }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_split_by_module_depending_on_crate_root` tests
+    /// that a module header `#include`s the main header when the module
+    /// depends on items from the crate root.
+    #[test]
+    fn test_generated_bindings_split_by_module_depending_on_crate_root() {
+        let test_src = r#"
+                pub struct RootStruct {
+                    pub x: i32,
+                }
+
+                pub mod m1 {
+                    pub fn get_x(s: crate::RootStruct) -> i32 { s.x }
+                }
+
+                pub mod m2 {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                split_headers: split_headers_for_tests(&["m1", "m2"]),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, h_parts, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    namespace rust_out { ... RootStruct ... }
+                    ...
+                    __HASH_TOKEN__ include "foo/foo_m1_cc_api.h"
+                    __HASH_TOKEN__ include "foo/foo_m2_cc_api.h"
+                }
+            );
+
+            // Headers are generated for all the listed modules (even for ones without any
+            // bindings).
+            let h_paths = h_parts.iter().map(|(h_path, _)| h_path.as_str()).collect_vec();
+            assert_eq!(h_paths, ["foo/foo_m1_cc_api.h", "foo/foo_m2_cc_api.h"]);
+            let (_, m1_body) = &h_parts[0];
+            assert_cc_matches!(m1_body, quote! { __HASH_TOKEN__ include "foo/foo_cc_api.h" });
+        });
+    }
+
+    /// `test_generated_bindings_split_by_module_unknown_module` tests that an
+    /// error is reported for a module that is not a top-level module of the
+    /// crate.
+    #[test]
+    fn test_generated_bindings_split_by_module_unknown_module() {
+        let test_src = r#"
+                pub mod m1 {
+                    pub mod nested {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                split_headers: split_headers_for_tests(&["nested"]),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, h_parts, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "Failed to generate bindings for the crate: \
+                 Can't generate a separate header for `nested`, because it is not a \
+                 top-level module of the crate";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+            assert!(h_parts.is_empty());
+        });
+    }
+
+    /// `test_generated_bindings_split_by_module_with_cyclic_deps` tests that an
+    /// error is reported when headers of different modules would have to
+    /// `#include` each other.
+    #[test]
+    fn test_generated_bindings_split_by_module_with_cyclic_deps() {
+        let test_src = r#"
+                pub mod m1 {
+                    pub struct S1 {
+                        pub x: i32,
+                    }
+                    pub struct T1 {
+                        pub s2: crate::m2::S2,
+                    }
+                }
+
+                pub mod m2 {
+                    pub struct S2 {
+                        pub s1: crate::m1::S1,
+                    }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                split_headers: split_headers_for_tests(&["m1", "m2"]),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, h_parts, .. } = generate_bindings(&input).unwrap();
+            let expected_comment_txt = "Failed to generate bindings for the crate: \
+                 Can't split the C++ bindings into one header per module, \
+                 because the following modules depend on each other: m1, m2";
+            assert_cc_matches!(h_body, quote! { __COMMENT__ #expected_comment_txt });
+            assert!(h_parts.is_empty());
+        });
+    }
+
+    /// Tests that `toposort` is used to reorder item bindings.
+    #[test]
+    fn test_generated_bindings_prereq_defs_field_deps_require_reordering() {
+        let test_src = r#"
+                // In the generated bindings `Outer` needs to come *after* `Inner`.
+                pub struct Outer(Inner);
+                pub struct Inner(bool);
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                    ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(1) Inner final {
+                          ...  bool __field0; ...
+                        };
+                    ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(1) Outer final {
+                          ...  ::rust_out::Inner __field0; ...
+                        };
+                    ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// Tests that a forward declaration is present when it is required to
+    /// preserve the original source order.  In this test the
+    /// `CcPrerequisites::fwd_decls` dependency comes from a pointer parameter.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_for_ptr_param() {
+        let test_src = r#"
+                // To preserve original API order we need to forward declare S.
+                pub fn f(_: *const S) {}
+                pub struct S(bool);
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        // Verifing the presence of this forward declaration
+                        // it the essence of this test.  The order of the items
+                        // below also matters.
+                        struct S;
+                        ...
+                        inline void f(const ::rust_out::S* __param_0);
+                        ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(...) S final { ... }
+                        ...
+                        inline void f(const ::rust_out::S* __param_0) { ... }
+                        ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// Tests that a forward declaration is present when it is required to
+    /// preserve the original source order.  In this test the
+    /// `CcPrerequisites::fwd_decls` dependency comes from a parameter
+    /// that takes a struct by value, but is only needed in a C++ function
+    /// declaration (not in a C++ function definition).
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_for_cpp_fn_decl() {
+        let test_src = r#"
+                // `f` will only have a C++ declaration (and no C++ definition)
+                // in the generated `..._cc_api.h` header.
+                //
+                // Therefore `f`'s `CcPrerequisites` should include `S`
+                // as a `fwd_decls` edge, rather than as a `defs` edge.
+                #[no_mangle]
+                pub extern "C" fn f(s: S) -> bool { s.0 }
+
+                #[repr(C)]
+                pub struct S(bool);
+            "#;
+
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        // Verifing the presence of this forward declaration
+                        // it the essence of this test.  The order also matters:
+                        // 1. The fwd decl of `S` should come first,
+                        // 2. Declaration of `f` and definition of `S` should come next
+                        //    (in their original order - `f` first and then `S`).
+                        struct S;
+                        ...
+                        extern "C" bool f(::rust_out::S s);
+                        ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(...) S final { ... }
+                        ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// This test verifies that a forward declaration for a given ADT is only
+    /// emitted once (and not once for every API item that requires the
+    /// forward declaration as a prerequisite).
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_no_duplication() {
+        let test_src = r#"
+                // All three functions below require a forward declaration of S.
+                pub fn f1(_: *const S) {}
+                pub fn f2(_: *const S) {}
+                pub fn f3(_: *const S) {}
+
+                pub struct S(bool);
+
+                // This function also includes S in its CcPrerequisites::fwd_decls
+                // (although here it is not required, because the definition of S
+                // is already available above).
+                pub fn f4(_: *const S) {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap().h_body.to_string();
+
+            // Only a single forward declaration is expected.
+            assert_eq!(1, bindings.matches("struct S ;").count(), "bindings = {bindings}");
+        });
+    }
+
+    /// This test verifies that forward declarations are emitted in a
+    /// deterministic order. The particular order doesn't matter _that_
+    /// much, but it definitely shouldn't change every time
+    /// `cc_bindings_from_rs` is invoked again.  The current order preserves
+    /// the original source order of the Rust API items.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_deterministic_order() {
+        let test_src = r#"
+                // To try to mix things up, the bindings for the functions below
+                // will *ask* for forward declarations in a different order:
+                // * Different from the order in which the forward declarations
+                //   are expected to be *emitted* (the original source order).
+                // * Different from alphabetical order.
+                pub fn f1(_: *const b::S3) {}
+                pub fn f2(_: *const a::S2) {}
+                pub fn f3(_: *const a::S1) {}
+
+                pub mod a {
+                    pub struct S1(bool);
+                    pub struct S2(bool);
+                }
+
+                pub mod b {
+                    pub struct S3(bool);
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        // Verifying that we get the same order in each test
+                        // run is the essence of this test.
+                        namespace a {
+                        struct S1;
+                        struct S2;
+                        }
+                        namespace b {
+                        struct S3;
+                        }
+                        ...
+                        inline void f1 ...
+                        inline void f2 ...
+                        inline void f3 ...
+
+                        namespace a { ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(...) S1 final { ... } ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(...) S2 final { ... } ...
+                        } ...
+                        namespace b { ...
+                        struct [[clang::annotate("crubit_rust_type", ...)]] alignas(...) S3 final { ... } ...
+                        } ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// This test verifies that forward declarations are not emitted if they are
+    /// not needed (e.g. if bindings the given `struct` or other ADT have
+    /// already been defined earlier).  In particular, we don't want to emit
+    /// forward declarations for *all* `structs` (regardless if they are
+    /// needed or not).
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_not_needed_because_of_initial_order() {
+        let test_src = r#"
+                pub struct S(bool);
+
+                // S is already defined above - no need for forward declaration in C++.
+                pub fn f(_s: *const S) {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
+            assert_cc_matches!(bindings.h_body, quote! { void f(const ::rust_out::S* _s); });
+        });
+    }
+
+    /// This test verifies that a method declaration doesn't ask for a forward
+    /// declaration to the struct.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_not_needed_inside_struct_definition() {
+        let test_src = r#"
+                #![allow(dead_code)]
+
+                pub struct S {
+                    // This shouldn't require a fwd decl of S.
+                    field: *const S,
+                }
+
+                impl S {
+                    // This shouldn't require a fwd decl of S.
+                    pub fn create() -> S { Self{ field: std::ptr::null() } }
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    static inline ::rust_out::S create(); ...
+                    const ::rust_out::S* field; ...
+                }
+            );
+        });
+    }
+
+    /// This test verifies that recursive types can be bound, because `Box<T>`
+    /// and `Option<Box<T>>` fields only need a forward declaration of `T`.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_for_boxed_fields() {
+        let test_src = r#"
+                #[derive(Default)]
+                pub struct Node {
+                    pub value: i32,
+                    pub next: Option<Box<Node>>,
+                }
+
+                #[derive(Default)]
+                pub struct A {
+                    pub b: Option<Box<B>>,
+                }
+
+                #[derive(Default)]
+                pub struct B {
+                    pub a: Box<A>,
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            // `Node` is defined before its own (pointer) field.
+            assert_cc_not_matches!(bindings.h_body, quote! { struct Node; });
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct B;
+                        ...
+                        struct ... Node final {
+                            ...
+                            private:
+                                ...
+                                ::rust_out::Node* next;
+                                ...
+                        };
+                        ...
+                        struct ... A final {
+                            ...
+                            private:
+                                ...
+                                ::rust_out::B* b;
+                                ...
+                        };
+                        ...
+                        struct ... B final {
+                            ...
+                            private:
+                                ...
+                                ::rust_out::A* a;
+                                ...
+                        };
+                        ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_module_basics() {
+        let test_src = r#"
+                pub mod some_module {
+                    pub fn some_func() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        namespace some_module {
+                            ...
+                            inline void some_func() { ... }
+                            ...
+                        }  // namespace some_module
+                    }  // namespace rust_out
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...() -> () {
+                        ::rust_out::some_module::some_func()
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_module_name_is_cpp_reserved_keyword() {
+        let test_src = r#"
+                pub mod working_module {
+                    pub fn working_module_f1() {}
+                }
+                pub mod reinterpret_cast {
+                    pub fn escaped_module_f1() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+
+            // The name of the module should be escaped by appending an underscore.
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        namespace working_module {
+                            ...
+                            inline void working_module_f1();
+                            ...
+                        }  // namespace working_module
+
+                        namespace reinterpret_cast_ {
+                            ...
+                            inline void escaped_module_f1();
+                            ...
+                        }  // namespace reinterpret_cast_
+                    }  // namespace rust_out
+                }
+            );
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace reinterpret_cast });
+        });
+    }
+
+    /// `test_generated_bindings_non_pub_items` verifies that non-public items
+    /// are not present/propagated into the generated bindings.
+    #[test]
+    fn test_generated_bindings_non_pub_items() {
+        let test_src = r#"
+                #![allow(dead_code)]
+
+                extern "C" fn private_function() {
+                    println!("foo");
+                }
+
+                struct PrivateStruct {
+                    x: i32,
+                    y: i32,
+                }
+
+                pub struct PublicStruct(i32);
+
+                impl PublicStruct {
+                    fn private_method() {}
+                }
+
+                pub mod public_module {
+                    fn priv_func_in_pub_module() {}
+                }
+
+                mod private_module {
+                    pub fn pub_func_in_priv_module() { priv_func_in_priv_module() }
+                    fn priv_func_in_priv_module() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { private_function });
+            assert_rs_not_matches!(bindings.rs_body, quote! { private_function });
+            assert_cc_not_matches!(bindings.h_body, quote! { PrivateStruct });
+            assert_rs_not_matches!(bindings.rs_body, quote! { PrivateStruct });
+            assert_cc_not_matches!(bindings.h_body, quote! { private_method });
+            assert_rs_not_matches!(bindings.rs_body, quote! { private_method });
+            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_priv_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_priv_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_pub_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_pub_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { private_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { private_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { pub_func_in_priv_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { pub_func_in_priv_module });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_top_level_items() {
+        let test_src = "pub fn public_function() {}";
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let expected_comment_txt =
+                "Automatically @generated C++ bindings for the following Rust crate:\n\
+                 rust_out";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                    ...
+                    __HASH_TOKEN__ pragma once
+                    ...
+                    namespace rust_out {
+                        ...
+                    }
+                }
+            );
+            assert_cc_matches!(
+                bindings.rs_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+        })
+    }
+
+    #[test]
+    fn test_generated_bindings_path_based_include_guard() {
+        let test_src = "pub fn public_function() {}";
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                include_guard: IncludeGuard::PathBased { h_path: "foo/bar_cc_api.h".into() },
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(
+                h_body,
+                quote! {
+                    __HASH_TOKEN__ ifndef FOO_BAR_CC_API_H_
+                    __HASH_TOKEN__ define FOO_BAR_CC_API_H_
+                    static_assert(sizeof(void*) == ..., ...);
+                    namespace rust_out {
+                        ...
+                    }
+                    __HASH_TOKEN__ endif __COMMENT__ "FOO_BAR_CC_API_H_"
+                }
+            );
+            assert_cc_not_matches!(h_body, quote! { __HASH_TOKEN__ pragma once });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_include_guard_of_split_headers() {
+        let test_src = r#"
+                pub mod some_module {
+                    pub fn public_function() {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                include_guard: IncludeGuard::PathBased { h_path: "foo/bar_cc_api.h".into() },
+                split_headers: Some(SplitHeaders {
+                    h_out: "foo/bar_cc_api.h".into(),
+                    module_headers: [("some_module".into(), "foo/some_module_cc_api.h".into())]
+                        .into_iter()
+                        .collect(),
+                }),
+                ..bindings_input_for_tests(tcx)
+            };
+            let Output { h_body, h_parts, .. } = generate_bindings(&input).unwrap();
+            assert_cc_matches!(h_body, quote! { __HASH_TOKEN__ ifndef FOO_BAR_CC_API_H_ });
+            let (_, module_body) = h_parts.into_iter().exactly_one().unwrap();
+            assert_cc_matches!(
+                module_body,
+                quote! { __HASH_TOKEN__ ifndef FOO_SOME_MODULE_CC_API_H_ }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_path_based_guard_name() {
+        assert_eq!("FOO_BAR_CC_API_H_", format_path_based_guard_name("foo/bar_cc_api.h"));
+        assert_eq!("FOO_BAR_CC_API_H_", format_path_based_guard_name("/foo/bar-cc-api.h"));
+        assert_eq!("CRUBIT_123_H_", format_path_based_guard_name("123.h"));
+        assert_eq!("FOO_BAR_CC_API_H_", format_path_based_guard_name("foo/bar__cc--api.h"));
+        assert_eq!("FOO_BAR_H_", format_path_based_guard_name("foo_/_bar.h"));
+    }
+
+    #[test]
+    fn test_format_path_based_guard_name_in_bazel_out() {
+        assert_eq!(
+            "FOO_BAR_CC_API_H_",
+            format_path_based_guard_name("bazel-out/k8-fastbuild/bin/foo/bar_cc_api.h")
+        );
+        assert_eq!(
+            "FOO_BAR_CC_API_H_",
+            format_path_based_guard_name("/abs/bazel-out/k8-opt/genfiles/foo/bar_cc_api.h")
+        );
+        // Unexpected layouts of `bazel-out` are kept as-is.
+        assert_eq!("BAZEL_OUT_FOO_H_", format_path_based_guard_name("bazel-out/foo.h"));
+    }
+
+    #[test]
+    fn test_format_hash_based_guard_name() {
+        let name = format_hash_based_guard_name("foo/bar_cc_api.h");
+        assert!(name.starts_with("CRUBIT_GENERATED_"), "name = {name}");
+        assert!(name.ends_with("_H_"), "name = {name}");
+        assert_eq!(name, format_hash_based_guard_name("foo/bar_cc_api.h"));
+        assert_ne!(name, format_hash_based_guard_name("foo/baz_cc_api.h"));
+        assert_eq!(
+            name,
+            format_hash_based_guard_name("bazel-out/k8-fastbuild/bin/foo/bar_cc_api.h")
+        );
+    }
+
+    /// The `test_generated_bindings_unsupported_item` test verifies how `Err`
+    /// from `format_item` is formatted as a C++ comment (in `format_crate`
+    /// and `format_unsupported_def`):
+    /// - This test covers only a single example of an unsupported item.
+    ///   Additional coverage is provided by `test_format_item_unsupported_...`
+    ///   tests.
+    /// - This test somewhat arbitrarily chooses an example of an unsupported
+    ///   item, trying to pick one that tests that the full error chain is
+    ///   included in the message.
+    #[test]
+    fn test_generated_bindings_unsupported_item() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn unsupported_function(_x: (i32, i32)) {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let expected_comment_txt = "[CRUBIT-E0003] Error generating bindings for \
+                 `unsupported_function` \
+                 defined at <crubit_unittests.rs>;l=3: \
+                 Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)\n\
+                 Suggestion: Consider using a struct instead of a tuple";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+        })
+    }
+
+    #[test]
+    fn test_generated_bindings_reimports() {
+        let test_src = r#"
+                #![allow(dead_code)]
+                #![allow(unused_imports)]
+                mod private_submodule1 {
+                    pub fn subfunction1() {}
+                    pub fn subfunction2() {}
+                    pub fn subfunction3() {}
+                }
+                mod private_submodule2 {
+                    pub fn subfunction8() {}
+                    pub fn subfunction9() {}
+                }
+
+                // Public re-import.
+                pub use private_submodule1::subfunction1;
+
+                // Private re-import.
+                use private_submodule1::subfunction2;
+
+                // Re-import that renames.
+                pub use private_submodule1::subfunction3 as public_function3;
+
+                // Re-import of multiple items via glob.
+                pub use private_submodule2::*;
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+
+            // All the failures have the same root cause, so they are reported in a
+            // single comment (see `format_unsupported_defs`).
+            let expected_comment_txt = "[CRUBIT-E0017] Error generating bindings for 3 items: \
+                 Unsupported rustc_hir::hir::ItemKind: `use` import\n\
+                 - `{use#1}` defined at <crubit_unittests.rs>;l=15\n\
+                 - `{use#3}` defined at <crubit_unittests.rs>;l=21\n\
+                 - `{use#4}` defined at <crubit_unittests.rs>;l=24";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_unsupported_items_with_same_root_cause` tests
+    /// that items that can't be bound because of the same root cause are
+    /// reported in a single comment (with the context of each failure), while
+    /// other unsupported items get a comment of their own.
+    #[test]
+    fn test_generated_bindings_unsupported_items_with_same_root_cause() {
+        let test_src = r#"
+                pub fn tuple_param(_x: (i32, i32)) {}
+
+                pub fn tuple_return() -> (i32, i32) { (1, 2) }
+
+                pub fn other_tuple_param(_x: (u8, u8)) {}
+
+                pub fn same_tuple_param(_y: i32, _x: (i32, i32)) {}
+
+                pub fn supported_fn() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let grouped_comment_txt = "[CRUBIT-E0003] Error generating bindings for 3 items: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)\n\
+                 - `tuple_param` defined at <crubit_unittests.rs>;l=2: \
+                 Error handling parameter #0\n\
+                 - `tuple_return` defined at <crubit_unittests.rs>;l=4: \
+                 Error formatting function return type\n\
+                 - `same_tuple_param` defined at <crubit_unittests.rs>;l=8: \
+                 Error handling parameter #1\n\
+                 Suggestion: Consider using a struct instead of a tuple";
+            assert_cc_matches!(bindings.h_body, quote! { __COMMENT__ #grouped_comment_txt });
+            let single_comment_txt = "[CRUBIT-E0003] Error generating bindings for \
+                 `other_tuple_param` \
+                 defined at <crubit_unittests.rs>;l=6: \
+                 Error handling parameter #0: \
+                 Tuples are not supported yet: (u8, u8) (b/254099023)\n\
+                 Suggestion: Consider using a struct instead of a tuple";
+            assert_cc_matches!(bindings.h_body, quote! { __COMMENT__ #single_comment_txt });
+            assert_cc_matches!(bindings.h_body, quote! { void supported_fn(); });
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_extern_c_no_mangle_no_params_no_return_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function() {}
+            "#;
+        test_format_item(test_src, "public_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void public_function();
+                }
+            );
+        });
+    }
+
+    /// The `test_format_item_fn_explicit_unit_return_type` test below is very
+    /// similar to the
+    /// `test_format_item_fn_extern_c_no_mangle_no_params_no_return_type` above,
+    /// except that the return type is explicitly spelled out.  There is no
+    /// difference in `ty::FnSig` so our code behaves exactly the same, but the
+    /// test has been planned based on earlier, hir-focused approach and having
+    /// this extra test coverage shouldn't hurt. (`hir::FnSig`
+    /// and `hir::FnRetTy` _would_ see a difference between the two tests, even
+    /// though there is no different in the current `bindings.rs` code).
+    #[test]
+    fn test_format_item_fn_explicit_unit_return_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn explicit_unit_return_type() -> () {}
+            "#;
+        test_format_item(test_src, "explicit_unit_return_type", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void explicit_unit_return_type();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_never_return_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn never_returning_function() -> ! {
+                    panic!("This function panics and therefore never returns");
+                }
+            "#;
+        test_format_item(test_src, "never_returning_function", |result| {
+            // TODO(b/254507801): The function should be annotated with the `[[noreturn]]`
+            // attribute.
+            // TODO(b/254507801): Expect `crubit::Never` instead (see the bug for more
+            // details).
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void never_returning_function();
+                }
+            );
+        })
+    }
+
+    /// `test_format_item_fn_mangling` checks that bindings can be generated for
+    /// `extern "C"` functions that do *not* have `#[no_mangle]` attribute.  The
+    /// test elides away the mangled name in the `assert_cc_matches` checks
+    /// below, but end-to-end test coverage should eventually be provided by
+    /// `test/functions` (see b/262904507).
+    #[test]
+    fn test_format_item_fn_mangling() {
+        let test_src = r#"
+                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+            "#;
+        test_format_item(test_src, "public_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline double public_function(double x, double y);
+                }
+            );
+            assert!(impl_details.rs.is_empty());
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double ...(double x, double y);
+                    }
+                    ...
+                    inline double public_function(double x, double y) {
+                        return __crubit_internal::...(x, y);
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_export_name() {
+        let test_src = r#"
+                #[export_name = "export_name"]
+                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+            "#;
+        test_format_item(test_src, "public_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline double public_function(double x, double y);
+                }
+            );
+            assert!(impl_details.rs.is_empty());
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double export_name(double x, double y);
+                    }
+                    ...
+                    inline double public_function(double x, double y) {
+                        return __crubit_internal::export_name(x, y);
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_unsafe() {
+        let test_src = r#"
+                /// Safety: `x` has to be positive.
+                #[no_mangle]
+                pub unsafe extern "C" fn foo(x: i32) -> i32 { x }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { __HASH_TOKEN__ include "crubit/support/for/tests/rs_std/unsafe_call.h" }
+            );
+            let comment = " Safety: `x` has to be positive.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
+            let warning = "\\warning This is an `unsafe` Rust function - the caller has to \
+                           uphold its safety requirements (typically documented in the \
+                           `# Safety` section of its Rust doc comment).";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    __COMMENT__ #warning
+                    inline std::int32_t foo(rs_std::UnsafeCall, std::int32_t x);
+                }
+            );
+            // The C++ definition calls the exported Rust function directly (no
+            // Rust thunk is needed), dropping the `rs_std::UnsafeCall` token.
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t foo(std::int32_t x);
+                    }
+                    inline std::int32_t foo(rs_std::UnsafeCall, std::int32_t x) {
+                        return __crubit_internal::foo(x);
+                    }
+                }
+            );
+            assert!(impl_details.rs.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_unsafe_with_rust_abi() {
+        let test_src = r#"
+                pub unsafe fn foo(x: i32) -> i32 { x }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t foo(rs_std::UnsafeCall, std::int32_t x);
+                }
+            );
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(x: i32) -> i32 {
+                        unsafe { ::rust_out::foo(x) }
+                    }
+                }
+            );
+        });
+    }
+
+    /// `test_format_item_fn_const` tests how bindings for an `const fn` are
+    /// generated.
+    ///
+    /// Right now the `const` qualifier is ignored, but one can imagine that in
+    /// the (very) long-term future such functions (including their bodies)
+    /// could be translated into C++ `consteval` functions.
+    #[test]
+    fn test_format_item_fn_const() {
+        let test_src = r#"
+                pub const fn foo(i: i32) -> i32 { i * 42 }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            // TODO(b/254095787): Update test expectations below once `const fn` from Rust
+            // is translated into a `consteval` C++ function.
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t foo(std::int32_t i);
+                }
+            );
+            assert!(!impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...( std::int32_t i);
+                    }
+                    ...
+                    inline std::int32_t foo(std::int32_t i) {
+                        return __crubit_internal::...(i);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(i: i32) -> i32 {
+                        ::rust_out::foo(i)
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_c_unwind_abi() {
+        // See also https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html
+        let test_src = r#"
+                #![feature(c_unwind)]
+
+                #[no_mangle]
+                pub extern "C-unwind" fn may_throw() {}
+            "#;
+        test_format_item(test_src, "may_throw", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void may_throw();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_c_unwind_abi_and_thunk() {
+        let test_src = r#"
+                #![feature(c_unwind)]
+
+                #[no_mangle]
+                pub extern "C-unwind" fn reinterpret_cast() {}
+            "#;
+        test_format_item(test_src, "reinterpret_cast", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline void reinterpret_cast_();
+                }
+            );
+            // The thunk needs to preserve the unwinding behavior of the wrapped
+            // function.
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C-unwind" fn __crubit_thunk_reinterpret_cast() -> () {
+                        ::rust_out::reinterpret_cast()
+                    }
+                }
+            );
+        });
+    }
+
+    /// This test mainly verifies that `format_item` correctly propagates
+    /// `CcPrerequisites` of parameter types and return type.
+    #[test]
+    fn test_format_item_fn_cc_prerequisites_if_cpp_definition_needed() {
+        let test_src = r#"
+                pub fn foo(_i: i32) -> S { panic!("foo") }
+                pub struct S(i32);
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+
+            // Minimal coverage, just to double-check that the test setup works.
+            //
+            // Note that this is a definition, and therefore `S` should be defined
+            // earlier (not just forward declared).
+            assert_cc_matches!(main_api.tokens, quote! { S foo(std::int32_t _i);});
+            assert_cc_matches!(impl_details.cc.tokens, quote! { S foo(std::int32_t _i) { ... }});
+
+            // Main checks: `CcPrerequisites::includes`.
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include <cstdint> }
+            );
+            assert_cc_matches!(
+                format_cc_includes(&impl_details.cc.prereqs.includes),
+                quote! { include <cstdint> }
+            );
+
+            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
+            //
+            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
+            // expose `tcx` to the verification function (and therefore calling
+            // `find_def_id_by_name` is not easily possible).
+            //
+            // Note that `main_api` and `impl_details` have different expectations.
+            assert_eq!(0, main_api.prereqs.defs.len());
+            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+            assert_eq!(1, impl_details.cc.prereqs.defs.len());
+            assert_eq!(0, impl_details.cc.prereqs.fwd_decls.len());
+        });
+    }
+
+    /// This test verifies that `format_item` uses `CcPrerequisites::fwd_decls`
+    /// rather than `CcPrerequisites::defs` for functions that only need a
+    /// C++ declaration (and don't need a C++ definition).
+    #[test]
+    fn test_format_item_fn_cc_prerequisites_if_only_cpp_declaration_needed() {
+        let test_src = r#"
+                // `foo` will only have a C++ declaration (and no C++ definition)
+                // in the generated `..._cc_api.h` header.
+                //
+                // Therefore `foo`'s `CcPrerequisites` should include `S`
+                // as a `fwd_decls` edge, rather than as a `defs` edge.
+                #[no_mangle]
+                pub extern "C" fn foo(s: S) -> bool { s.0 }
+
+                #[repr(C)]
+                pub struct S(bool);
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+
+            // Minimal coverage, just to double-check that the test setup works.
+            //
+            // Note that this is only a function *declaration* (not a function definition -
+            // there is no function body), and therefore `S` just needs to be
+            // forward-declared earlier.
+            assert_cc_matches!(main_api.tokens, quote! { extern "C" bool foo(::rust_out::S s); });
+
+            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
+            //
+            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
+            // expose `tcx` to the verification function (and therefore calling
+            // `find_def_id_by_name` is not easily possible).
+            assert_eq!(0, main_api.prereqs.defs.len());
+            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_type_aliased_return_type() {
+        // Type aliases disappear at the `rustc_middle::ty::Ty` level and therefore in
+        // the short-term the generated bindings also ignore type aliases.
+        //
+        // TODO(b/254096006): Consider preserving `type` aliases when generating
+        // bindings.
+        let test_src = r#"
+                type MyTypeAlias = f64;
+
+                #[no_mangle]
+                pub extern "C" fn type_aliased_return() -> MyTypeAlias { 42.0 }
+            "#;
+        test_format_item(test_src, "type_aliased_return", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" double type_aliased_return();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_doc_comment_with_unmangled_name() {
+        let test_src = r#"
+            /// Outer line doc.
+            /** Outer block doc that spans lines.
+             */
+            #[doc = "Doc comment via doc attribute."]
+            #[no_mangle]
+            pub extern "C" fn fn_with_doc_comment_with_unmangled_name() {}
+          "#;
+        test_format_item(test_src, "fn_with_doc_comment_with_unmangled_name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let doc_comments = [
+                " Outer line doc.",
+                "",
+                " Outer block doc that spans lines.",
+                "             ",
+                "",
+                "Doc comment via doc attribute.",
+                "",
+                "Generated from: <crubit_unittests.rs>;l=7",
+            ]
+            .join("\n");
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comments
+                    extern "C" void fn_with_doc_comment_with_unmangled_name();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_inner_doc_comment_with_unmangled_name() {
+        let test_src = r#"
+            /// Outer doc comment.
+            #[no_mangle]
+            pub extern "C" fn fn_with_inner_doc_comment_with_unmangled_name() {
+                //! Inner doc comment.
+            }
+          "#;
+        test_format_item(test_src, "fn_with_inner_doc_comment_with_unmangled_name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let doc_comments = [
+                " Outer doc comment.",
+                " Inner doc comment.",
+                "Generated from: <crubit_unittests.rs>;l=4",
+            ]
+            .join("\n\n");
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comments
+                    extern "C" void fn_with_inner_doc_comment_with_unmangled_name();
+                }
+            );
+        });
+    }
 
-                pub mod m1 {
-                    pub fn bar() {}
+    #[test]
+    fn test_format_item_fn_with_doc_comment_with_mangled_name() {
+        let test_src = r#"
+                /// Doc comment of a function with mangled name.
+                pub extern "C" fn fn_with_doc_comment_with_mangled_name() {}
+            "#;
+        test_format_item(test_src, "fn_with_doc_comment_with_mangled_name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let comment = " Doc comment of a function with mangled name.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    inline void fn_with_doc_comment_with_mangled_name();
                 }
-                pub mod m2 {
-                    pub fn bar() {}
+            );
+        });
+    }
+
+    /// `test_format_item_fn_name_is_reserved_cpp_keyword` tests that the C++
+    /// name of a function is escaped if it is a C++ reserved keyword.  The
+    /// exported name of the function can't be escaped, and therefore a thunk
+    /// is used even though the function uses the "C" ABI.
+    #[test]
+    fn test_format_item_fn_name_is_reserved_cpp_keyword() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn reinterpret_cast() -> () {}
+            "#;
+        test_format_item(test_src, "reinterpret_cast", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline void reinterpret_cast_();
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void __crubit_thunk_reinterpret_cast();
+                    }
+                    inline void reinterpret_cast_() {
+                        return __crubit_internal::__crubit_thunk_reinterpret_cast();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk_reinterpret_cast() -> () {
+                        ::rust_out::reinterpret_cast()
+                    }
                 }
+            );
+        });
+    }
+
+    /// `test_format_item_fn_name_is_reserved_cpp_identifier` tests that the
+    /// C++ name of a function is escaped if it is a reserved C++ identifier
+    /// (and that, like for reserved keywords, a thunk is used for the
+    /// unescaped exported name).
+    #[test]
+    fn test_format_item_fn_name_is_reserved_cpp_identifier() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn __reserved__name() -> () {}
             "#;
-        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "missing_name"));
+        test_format_item(test_src, "__reserved__name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline void _reserved_name();
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void __crubit_thunk___reserved__name();
+                    }
+                    inline void _reserved_name() {
+                        return __crubit_internal::__crubit_thunk___reserved__name();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk___reserved__name() -> () {
+                        ::rust_out::__reserved__name()
+                    }
+                }
+            );
+        });
     }
 
     #[test]
-    #[should_panic(expected = "More than one item named `some_name`")]
-    fn test_find_def_id_by_name_panic_when_multiple_items_with_matching_name() {
+    fn test_format_item_fn_param_name_is_reserved_cpp_keyword() {
         let test_src = r#"
-                pub mod m1 {
-                    pub fn some_name() {}
+                pub fn some_function(class: f64) {}
+            "#;
+        test_format_item(test_src, "some_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline void some_function(double class_);
                 }
-                pub mod m2 {
-                    pub fn some_name() {}
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(class: f64) -> () {
+                        ::rust_out::some_function(class)
+                    }
                 }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_ret_type() {
+        let test_src = r#"
+                pub fn foo() -> (i32, i32) { (123, 456) }
             "#;
-        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "some_name"));
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error formatting function return type: \
+                       Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_with_late_bound_lifetimes() {
+        // TODO(b/258235219): Expect success after adding support for references.
+        let test_src = r#"
+                pub fn foo(arg: &i32) -> &i32 { arg }
+
+                // Lifetime inference translates the above into:
+                //     pub fn foo<'a>(arg: &'a i32) -> &'a i32 { ... }
+                // leaving 'a lifetime late-bound (it is bound with a lifetime
+                // taken from each of the callsites).  `get_fn_sig` erases such
+                // lifetimes.
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error formatting function return type: \
+                 The following Rust type is not supported yet: &i32"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_generic_fn() {
+        let test_src = r#"
+                use std::default::Default;
+                use std::fmt::Display;
+                pub fn generic_function<T: Default + Display>() {
+                    println!("{}", T::default());
+                }
+            "#;
+        test_format_item(test_src, "generic_function", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic functions are not supported yet (b/259749023)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_generic_struct() {
+        let test_src = r#"
+                pub struct Point<T> {
+                    pub x: T,
+                    pub y: T,
+                }
+            "#;
+        test_format_item(test_src, "Point", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_generic_enum() {
+        let test_src = r#"
+                pub enum Point<T> {
+                    Cartesian{x: T, y: T},
+                    Polar{angle: T, dist: T},
+                }
+            "#;
+        test_format_item(test_src, "Point", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_generic_union() {
+        let test_src = r#"
+                pub union SomeUnion<T> {
+                    pub x: std::mem::ManuallyDrop<T>,
+                    pub y: i32,
+                }
+            "#;
+        test_format_item(test_src, "SomeUnion", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_async() {
+        let test_src = r#"
+                pub async fn async_function() {}
+            "#;
+        test_format_item(test_src, "async_function", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Error formatting function return type: \
+                             The following Rust type is not supported yet: \
+                             impl std::future::Future<Output = ()>");
+        });
     }
 
-    /// This test covers only a single example of a function that should get a
-    /// C++ binding. The test focuses on verification that the output from
-    /// `format_fn` gets propagated all the way to `GenerateBindings::new`.
-    /// Additional coverage of how functions are formatted is provided
-    /// by `test_format_item_..._fn_...` tests (which work at the `format_fn`
-    /// level).
     #[test]
-    fn test_generated_bindings_fn_no_mangle_extern_c() {
+    fn test_format_item_fn_rust_abi() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn public_function() {
-                    println!("foo");
-                }
+                pub fn add(x: f64, y: f64) -> f64 { x * y }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "add", |result| {
+            // TODO(b/261074843): Re-add thunk name verification once we are using stable name
+            // mangling (which may be coming in Q1 2023).  (This might mean reverting cl/492333432
+            // + manual review and tweaks.)
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    extern "C" void public_function();
+                    inline double add(double x, double y);
                 }
             );
-
-            // No Rust thunks should be generated in this test scenario.
-            assert_rs_not_matches!(bindings.rs_body, quote!{ public_function });
-        });
-    }
-
-    /// `test_generated_bindings_fn_export_name` covers a scenario where
-    /// `MixedSnippet::cc` is present but `MixedSnippet::rs` is empty
-    /// (because no Rust thunks are needed).
-    #[test]
-    fn test_generated_bindings_fn_export_name() {
-        let test_src = r#"
-                #[export_name = "export_name"]
-                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                impl_details.cc.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        inline double public_function(double x, double y);
-                        namespace __crubit_internal {
-                            extern "C" double export_name(double x, double y);
-                        }
-                        inline double public_function(double x, double y) {
-                            return __crubit_internal::export_name(x, y);
-                        }
+                    namespace __crubit_internal {
+                        extern "C" double ...(double x, double y);
+                    }
+                    ...
+                    inline double add(double x, double y) {
+                        return __crubit_internal::...(x, y);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(x: f64, y: f64) -> f64 {
+                        ::rust_out::add(x, y)
                     }
                 }
             );
         });
     }
 
-    /// The `test_generated_bindings_struct` test covers only a single example
-    /// of an ADT (struct/enum/union) that should get a C++ binding.
-    /// Additional coverage of how items are formatted is provided by
-    /// `test_format_item_..._struct_...`, `test_format_item_..._enum_...`,
-    /// and `test_format_item_..._union_...` tests.
-    ///
-    /// We don't want to duplicate coverage already provided by
-    /// `test_format_item_struct_with_fields`, but we do want to verify that
-    /// * `format_crate` will actually find and process the struct
-    ///   (`test_format_item_...` doesn't cover this aspect - it uses a
-    ///   test-only `find_def_id_by_name` instead)
-    /// * The actual shape of the bindings still looks okay at this level.
     #[test]
-    fn test_generated_bindings_struct() {
+    fn test_format_item_fn_rust_abi_with_param_taking_struct_by_value() {
         let test_src = r#"
-                pub struct Point {
-                    pub x: i32,
-                    pub y: i32,
-                }
+                pub struct S(i32);
+                pub fn into_i32(s: S) -> i32 { s.0 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "into_i32", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        struct alignas(4) Point final {
-                            // No point replicating test coverage of
-                            // `test_format_item_struct_with_fields`.
-                            ...
-                        };
-                        static_assert(sizeof(Point) == 8, ...);
-                        static_assert(alignof(Point) == 4, ...);
-                        ... // Other static_asserts are covered by
-                            // `test_format_item_struct_with_fields`
-                    }  // namespace rust_out
+                    inline std::int32_t into_i32(::rust_out::S s);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(::rust_out::S* s);
+                    }
+                    ...
+                    inline std::int32_t into_i32(::rust_out::S s) {
+                        return __crubit_internal::...(&s);
+                    }
                 }
             );
             assert_rs_matches!(
-                bindings.rs_body,
+                impl_details.rs,
                 quote! {
-                    // No point replicating test coverage of
-                    // `test_format_item_struct_with_fields`.
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, x) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, y) == 4);
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(s: &mut ::core::mem::MaybeUninit<::rust_out::S>) -> i32 {
+                        ::rust_out::into_i32(unsafe { s.assume_init_read() })
+                    }
                 }
             );
         });
     }
 
-    /// The `test_generated_bindings_impl` test covers only a single example of
-    /// a non-trait `impl`. Additional coverage of how items are formatted
-    /// should be provided in the future by `test_format_item_...` tests.
-    ///
-    /// We don't want to duplicate coverage already provided by
-    /// `test_format_item_static_method`, but we do want to verify that
-    /// * `format_crate` won't process the `impl` as a standalone HIR item
-    /// * The actual shape of the bindings still looks okay at this level.
     #[test]
-    fn test_generated_bindings_impl() {
+    fn test_format_item_fn_rust_abi_with_param_taking_slice() {
         let test_src = r#"
-                pub struct SomeStruct(i32);
-
-                impl SomeStruct {
-                    pub fn public_static_method() -> i32 { 123 }
-
-                    #[allow(dead_code)]
-                    fn private_static_method() -> i32 { 123 }
-                }
+                pub fn sum(s: &[i32]) -> i32 { s.iter().sum() }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "sum", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        struct ... SomeStruct ... {
-                            // No point replicating test coverage of
-                            // `test_format_item_static_method`.
-                            ...
-                            std::int32_t public_static_method();
-                            ...
-                        };
-                        ...
-                        std::int32_t SomeStruct::public_static_method() {
-                            ...
-                        }
-                    }  // namespace rust_out
+                    inline std::int32_t sum(rs_std::Slice<std::int32_t> s);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(rs_std::Slice<std::int32_t>* s);
+                    }
+                    ...
+                    inline std::int32_t sum(rs_std::Slice<std::int32_t> s) {
+                        return __crubit_internal::...(&s);
+                    }
                 }
             );
             assert_rs_matches!(
-                bindings.rs_body,
+                impl_details.rs,
                 quote! {
-                    extern "C" fn ...() -> i32 {
-                        ::rust_out::SomeStruct::public_static_method()
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(s: &mut ::core::mem::MaybeUninit<&'static [i32]>) -> i32 {
+                        ::rust_out::sum(unsafe { s.assume_init_read() })
                     }
                 }
             );
         });
     }
 
+    /// `test_format_item_fn_rust_abi_returning_mut_slice` tests that the
+    /// parameters borrowed by the returned value are annotated with
+    /// `CRUBIT_LIFETIME_BOUND`.
     #[test]
-    fn test_generated_bindings_includes() {
+    fn test_format_item_fn_rust_abi_returning_mut_slice() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn public_function(i: i32, d: isize, u: u64) {
-                    dbg!(i);
-                    dbg!(d);
-                    dbg!(u);
+                pub fn first_half<'a>(s: &'a mut [i32], _t: &[i32]) -> &'a mut [i32] {
+                    let len = s.len() / 2;
+                    &mut s[..len]
                 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "first_half", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { __HASH_TOKEN__ include "crubit/support/for/tests/internal/lifetime_bound.h" }
+            );
+            assert_cc_matches!(
+                main_api.tokens,
                 quote! {
-                    __HASH_TOKEN__ include <cstdint> ...
-                    namespace ... {
-                        ...
-                        extern "C" void public_function(
-                            std::int32_t i,
-                            std::intptr_t d,
-                            std::uint64_t u);
+                    inline rs_std::MutSlice<std::int32_t> first_half(
+                        rs_std::MutSlice<std::int32_t> s CRUBIT_LIFETIME_BOUND,
+                        rs_std::Slice<std::int32_t> _t);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            rs_std::MutSlice<std::int32_t>* s,
+                            rs_std::Slice<std::int32_t>* _t,
+                            rs_std::MutSlice<std::int32_t>* __ret_ptr);
+                    }
+                    ...
+                    inline rs_std::MutSlice<std::int32_t> first_half(
+                            rs_std::MutSlice<std::int32_t> s CRUBIT_LIFETIME_BOUND,
+                            rs_std::Slice<std::int32_t> _t) {
+                        crubit::ReturnValueSlot<rs_std::MutSlice<std::int32_t>> __ret_slot;
+                        __crubit_internal::...(&s, &_t, __ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
                     }
                 }
             );
         });
     }
 
-    /// Tests that `toposort` is used to reorder item bindings.
+    /// `test_format_item_fn_maybe_uninit_out_param` tests that `&mut
+    /// MaybeUninit<T>` parameters are passed as `T*` pointers, and that the
+    /// generated doc comment spells out the initialize-before-read contract.
     #[test]
-    fn test_generated_bindings_prereq_defs_field_deps_require_reordering() {
+    fn test_format_item_fn_maybe_uninit_out_param() {
         let test_src = r#"
-                // In the generated bindings `Outer` needs to come *after* `Inner`.
-                pub struct Outer(Inner);
-                pub struct Inner(bool);
+                use std::mem::MaybeUninit;
+
+                pub fn init(value: i32, out: &mut MaybeUninit<i32>) {
+                    out.write(value);
+                }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "init", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            let note = "\\note `out` may point at uninitialized storage - the Rust function \
+                        won't read it before initializing it, and the caller may only read it \
+                        after the call if the function documents that it initializes it.";
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                    ...
-                        struct alignas(1) Inner final {
-                          ...  bool __field0; ...
-                        };
                     ...
-                        struct alignas(1) Outer final {
-                          ...  ::rust_out::Inner __field0; ...
-                        };
-                    ...
-                    }  // namespace rust_out
+                    __COMMENT__ #note
+                    inline void init(std::int32_t value, std::int32_t* out);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(std::int32_t value, std::int32_t* out);
+                    }
+                    inline void init(std::int32_t value, std::int32_t* out) {
+                        return __crubit_internal::...(value, out);
+                    }
                 }
             );
-        });
-    }
-
-    /// Tests that a forward declaration is present when it is required to
-    /// preserve the original source order.  In this test the
-    /// `CcPrerequisites::fwd_decls` dependency comes from a pointer parameter.
-    #[test]
-    fn test_generated_bindings_prereq_fwd_decls_for_ptr_param() {
-        let test_src = r#"
-                // To preserve original API order we need to forward declare S.
-                pub fn f(_: *const S) {}
-                pub struct S(bool);
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_matches!(
-                bindings.h_body,
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        // Verifing the presence of this forward declaration
-                        // it the essence of this test.  The order of the items
-                        // below also matters.
-                        struct S;
-                        ...
-                        inline void f(const ::rust_out::S* __param_0);
-                        ...
-                        struct alignas(...) S final { ... }
-                        ...
-                        inline void f(const ::rust_out::S* __param_0) { ... }
-                        ...
-                    }  // namespace rust_out
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(value: i32, out: &'static mut ::core::mem::MaybeUninit<i32>) -> () {
+                        ::rust_out::init(value, out)
+                    }
                 }
             );
         });
     }
 
-    /// Tests that a forward declaration is present when it is required to
-    /// preserve the original source order.  In this test the
-    /// `CcPrerequisites::fwd_decls` dependency comes from a parameter
-    /// that takes a struct by value, but is only needed in a C++ function
-    /// declaration (not in a C++ function definition).
+    /// `test_format_item_method_lifetime_bound_self` tests that `this` is
+    /// annotated with `CRUBIT_LIFETIME_BOUND` when the returned value borrows
+    /// from `self`.
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_for_cpp_fn_decl() {
+    fn test_format_item_method_lifetime_bound_self() {
         let test_src = r#"
-                // `f` will only have a C++ declaration (and no C++ definition)
-                // in the generated `..._cc_api.h` header.
-                //
-                // Therefore `f`'s `CcPrerequisites` should include `S`
-                // as a `fwd_decls` edge, rather than as a `defs` edge.
-                #[no_mangle]
-                pub extern "C" fn f(s: S) -> bool { s.0 }
+                pub struct SomeStruct([i32; 4]);
 
-                #[repr(C)]
-                pub struct S(bool);
+                impl SomeStruct {
+                    pub fn as_mut_slice(&mut self) -> &mut [i32] { &mut self.0 }
+                    pub fn len(&self) -> usize { self.0.len() }
+                }
             "#;
-
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        // Verifing the presence of this forward declaration
-                        // it the essence of this test.  The order also matters:
-                        // 1. The fwd decl of `S` should come first,
-                        // 2. Declaration of `f` and definition of `S` should come next
-                        //    (in their original order - `f` first and then `S`).
-                        struct S;
-                        ...
-                        extern "C" bool f(::rust_out::S s);
+                    ...
+                    struct ... SomeStruct final {
                         ...
-                        struct alignas(...) S final { ... }
+                        public:
+                          ...
+                          inline rs_std::MutSlice<std::int32_t> as_mut_slice()
+                              CRUBIT_LIFETIME_BOUND;
+                          ...
+                          inline std::uintptr_t len() const;
                         ...
-                    }  // namespace rust_out
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline rs_std::MutSlice<std::int32_t> SomeStruct::as_mut_slice()
+                        CRUBIT_LIFETIME_BOUND {
+                      ...
+                    }
                 }
             );
         });
     }
 
-    /// This test verifies that a forward declaration for a given ADT is only
-    /// emitted once (and not once for every API item that requires the
-    /// forward declaration as a prerequisite).
+    /// `test_format_item_as_ref_accessors` tests that the `impl`s of `AsRef`
+    /// and `Borrow` are bound as accessors named after the returned view.
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_no_duplication() {
+    fn test_format_item_as_ref_accessors() {
         let test_src = r#"
-                // All three functions below require a forward declaration of S.
-                pub fn f1(_: *const S) {}
-                pub fn f2(_: *const S) {}
-                pub fn f3(_: *const S) {}
+                use std::borrow::Borrow;
 
-                pub struct S(bool);
+                pub struct SomeStruct {
+                    name: &'static str,
+                    bytes: [u8; 4],
+                    values: [i32; 4],
+                    id: i32,
+                }
 
-                // This function also includes S in its CcPrerequisites::fwd_decls
-                // (although here it is not required, because the definition of S
-                // is already available above).
-                pub fn f4(_: *const S) {}
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap().h_body.to_string();
+                impl AsRef<str> for SomeStruct {
+                    fn as_ref(&self) -> &str { self.name }
+                }
 
-            // Only a single forward declaration is expected.
-            assert_eq!(1, bindings.matches("struct S ;").count(), "bindings = {bindings}");
-        });
-    }
+                // Not bound, because `as_str` is already provided by `AsRef<str>`.
+                impl Borrow<str> for SomeStruct {
+                    fn borrow(&self) -> &str { self.name }
+                }
 
-    /// This test verifies that forward declarations are emitted in a
-    /// deterministic order. The particular order doesn't matter _that_
-    /// much, but it definitely shouldn't change every time
-    /// `cc_bindings_from_rs` is invoked again.  The current order preserves
-    /// the original source order of the Rust API items.
-    #[test]
-    fn test_generated_bindings_prereq_fwd_decls_deterministic_order() {
-        let test_src = r#"
-                // To try to mix things up, the bindings for the functions below
-                // will *ask* for forward declarations in a different order:
-                // * Different from the order in which the forward declarations
-                //   are expected to be *emitted* (the original source order).
-                // * Different from alphabetical order.
-                pub fn f1(_: *const b::S3) {}
-                pub fn f2(_: *const a::S2) {}
-                pub fn f3(_: *const a::S1) {}
+                impl AsRef<[u8]> for SomeStruct {
+                    fn as_ref(&self) -> &[u8] { &self.bytes }
+                }
 
-                pub mod a {
-                    pub struct S1(bool);
-                    pub struct S2(bool);
+                impl Borrow<[i32]> for SomeStruct {
+                    fn borrow(&self) -> &[i32] { &self.values }
                 }
 
-                pub mod b {
-                    pub struct S3(bool);
+                // Not bound, because C++ bindings can't return references yet.
+                impl AsRef<i32> for SomeStruct {
+                    fn as_ref(&self) -> &i32 { &self.id }
                 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
+                quote! { inline rs_std::Str as_str() const CRUBIT_LIFETIME_BOUND; }
+            );
+            assert_cc_matches!(
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        // Verifying that we get the same order in each test
-                        // run is the essence of this test.
-                        namespace a {
-                        struct S1;
-                        struct S2;
-                        }
-                        namespace b {
-                        struct S3;
-                        }
-                        ...
-                        inline void f1 ...
-                        inline void f2 ...
-                        inline void f3 ...
-
-                        namespace a { ...
-                        struct alignas(...) S1 final { ... } ...
-                        struct alignas(...) S2 final { ... } ...
-                        } ...
-                        namespace b { ...
-                        struct alignas(...) S3 final { ... } ...
-                        } ...
-                    }  // namespace rust_out
+                    inline rs_std::Slice<std::uint8_t> as_bytes() const CRUBIT_LIFETIME_BOUND;
+                }
+            );
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::Slice<std::int32_t> as_i32_slice() const CRUBIT_LIFETIME_BOUND;
+                }
+            );
+            assert_cc_not_matches!(main_api.tokens, quote! { as_ref });
+            assert_cc_not_matches!(main_api.tokens, quote! { borrow });
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    <::rust_out::SomeStruct as ::core::convert::AsRef<[u8]>>::as_ref(__self)
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    <::rust_out::SomeStruct as ::core::borrow::Borrow<[i32]>>::borrow(__self)
                 }
             );
         });
     }
 
-    /// This test verifies that forward declarations are not emitted if they are
-    /// not needed (e.g. if bindings the given `struct` or other ADT have
-    /// already been defined earlier).  In particular, we don't want to emit
-    /// forward declarations for *all* `structs` (regardless if they are
-    /// needed or not).
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_not_needed_because_of_initial_order() {
-        let test_src = r#"
-                pub struct S(bool);
+    fn test_convert_camel_case_to_snake_case() {
+        assert_eq!(convert_camel_case_to_snake_case("SomeStruct"), "some_struct");
+        assert_eq!(convert_camel_case_to_snake_case("Foo"), "foo");
+        assert_eq!(convert_camel_case_to_snake_case("foo"), "foo");
+        assert_eq!(convert_camel_case_to_snake_case("Foo2Bar"), "foo2_bar");
+    }
 
-                // S is already defined above - no need for forward declaration in C++.
-                pub fn f(_s: *const S) {}
+    #[test]
+    fn test_format_item_fn_rust_abi_with_nonzero_params() {
+        let test_src = r#"
+                use std::num::NonZeroU32;
+                pub fn add(x: NonZeroU32, y: Option<NonZeroU32>) -> Option<NonZeroU32> {
+                    y.and_then(|y| x.checked_add(y.get()))
+                }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
-            assert_cc_matches!(bindings.h_body, quote! { void f(const ::rust_out::S* _s); });
+        test_format_item(test_src, "add", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::uint32_t add(rs_std::NonZero<std::uint32_t> x, std::uint32_t y);
+                }
+            );
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! {
+                    include <cstdint> __NEWLINE__
+                    __HASH_TOKEN__ include "crubit/support/for/tests/rs_std/rs_nonzero.h"
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            rs_std::NonZero<std::uint32_t>* x,
+                            std::uint32_t* y,
+                            std::uint32_t* __ret_ptr);
+                    }
+                    ...
+                    inline std::uint32_t add(rs_std::NonZero<std::uint32_t> x, std::uint32_t y) {
+                        crubit::ReturnValueSlot<std::uint32_t> __ret_slot;
+                        __crubit_internal::...(&x, &y, __ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(
+                        x: &mut ::core::mem::MaybeUninit<::core::num::NonZeroU32>,
+                        y: &mut ::core::mem::MaybeUninit<
+                            ::core::option::Option<::core::num::NonZeroU32>
+                        >,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<
+                            ::core::option::Option<::core::num::NonZeroU32>
+                        >
+                    ) -> () {
+                        __ret_slot.write(::rust_out::add(
+                            unsafe { x.assume_init_read() },
+                            unsafe { y.assume_init_read() }
+                        ));
+                    }
+                }
+            );
         });
     }
 
-    /// This test verifies that a method declaration doesn't ask for a forward
-    /// declaration to the struct.
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_not_needed_inside_struct_definition() {
+    fn test_format_item_fn_rust_abi_with_param_taking_nonnull() {
         let test_src = r#"
-                #![allow(dead_code)]
-
-                pub struct S {
-                    // This shouldn't require a fwd decl of S.
-                    field: *const S,
+                use std::ptr::NonNull;
+                pub fn addr(ptr: NonNull<i32>) -> usize { ptr.as_ptr() as usize }
+            "#;
+        test_format_item(test_src, "addr", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::uintptr_t addr(rs_std::NonNull<std::int32_t> ptr);
                 }
-
-                impl S {
-                    // This shouldn't require a fwd decl of S.
-                    pub fn create() -> S { Self{ field: std::ptr::null() } }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::uintptr_t ...(rs_std::NonNull<std::int32_t>* ptr);
+                    }
+                    ...
+                    inline std::uintptr_t addr(rs_std::NonNull<std::int32_t> ptr) {
+                        return __crubit_internal::...(&ptr);
+                    }
                 }
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
-            assert_cc_matches!(
-                bindings.h_body,
+            );
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    static inline ::rust_out::S create(); ...
-                    const ::rust_out::S* field; ...
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(ptr: &mut ::core::mem::MaybeUninit<::core::ptr::NonNull<i32>>) -> usize {
+                        ::rust_out::addr(unsafe { ptr.assume_init_read() })
+                    }
                 }
             );
         });
     }
 
     #[test]
-    fn test_generated_bindings_module_basics() {
+    fn test_format_item_fn_rust_abi_with_cow_str() {
         let test_src = r#"
-                pub mod some_module {
-                    pub fn some_func() {}
+                use std::borrow::Cow;
+                pub fn to_upper(s: Cow<'_, str>) -> Cow<'static, str> {
+                    Cow::Owned(s.to_uppercase())
                 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "to_upper", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        namespace some_module {
-                            ...
-                            inline void some_func() { ... }
-                            ...
-                        }  // namespace some_module
-                    }  // namespace rust_out
+                    inline rs_std::CowStr to_upper(rs_std::CowStr s);
+                }
+            );
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include "crubit/support/for/tests/rs_std/rs_cow_str.h" }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" rs_std::internal::CowStrRawParts ...(
+                            rs_std::internal::CowStrRawParts s);
+                    }
+                    inline rs_std::CowStr to_upper(rs_std::CowStr s) {
+                        return rs_std::CowStr::from_raw_parts(
+                            __crubit_internal::...(std::move(s).release()));
+                    }
                 }
             );
             assert_rs_matches!(
-                bindings.rs_body,
+                impl_details.rs,
                 quote! {
                     #[no_mangle]
                     extern "C"
-                    fn ...() -> () {
-                        ::rust_out::some_module::some_func()
+                    fn ...(s: ::cow_bridge::CowStrRawParts) -> ::cow_bridge::CowStrRawParts {
+                        ::cow_bridge::CowStrRawParts::from_cow(::rust_out::to_upper(
+                            unsafe { ::cow_bridge::CowStrRawParts::into_cow(s) }))
                     }
                 }
             );
@@ -2107,312 +11478,299 @@ pub mod tests {
     }
 
     #[test]
-    fn test_generated_bindings_module_name_is_cpp_reserved_keyword() {
+    fn test_format_item_method_returning_mutex_guard() {
         let test_src = r#"
-                pub mod working_module {
-                    pub fn working_module_f1() {}
-                    pub fn working_module_f2() {}
-                }
-                pub mod reinterpret_cast {
-                    pub fn broken_module_f1() {}
-                    pub fn broken_module_f2() {}
-                }
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+                use std::sync::{Mutex, MutexGuard};
 
-            // Items in the broken module should be replaced with a comment explaining the
-            // problem.
-            let broken_module_msg = "Failed to format namespace name `reinterpret_cast`: \
-                                     `reinterpret_cast` is a C++ reserved keyword \
-                                     and can't be used as a C++ identifier";
-            assert_cc_not_matches!(bindings.h_body, quote! { namespace reinterpret_cast });
-            assert_cc_not_matches!(bindings.h_body, quote! { broken_module_f1 });
-            assert_cc_not_matches!(bindings.h_body, quote! { broken_module_f2 });
+                pub struct Counter(Mutex<i32>);
 
-            // Items in the other module should still go through.
+                impl Counter {
+                    pub fn lock(&self) -> MutexGuard<'_, i32> { self.0.lock().unwrap() }
+                }
+            "#;
+        test_format_item(test_src, "Counter", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        namespace working_module {
-                            ...
-                            inline void working_module_f1();
-                            ...
-                            inline void working_module_f2();
-                            ...
-                        }  // namespace some_module
-
-                        __COMMENT__ #broken_module_msg
+                    ...
+                    struct ... Counter final {
                         ...
-                    }  // namespace rust_out
+                        public:
+                          ...
+                          inline rs_std::LockGuard<std::int32_t> lock() CRUBIT_LIFETIME_BOUND;
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include "crubit/support/for/tests/rs_std/rs_lock_guard.h" }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" rs_std::internal::LockGuardRawParts ...(
+                            ::rust_out::Counter* __self);
+                    }
+                    inline rs_std::LockGuard<std::int32_t> Counter::lock()
+                        CRUBIT_LIFETIME_BOUND {
+                      return rs_std::LockGuard<std::int32_t>::from_raw_parts(
+                          __crubit_internal::...(this));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(__self: ...) -> ::lock_bridge::LockGuardRawParts {
+                        ::lock_bridge::LockGuardRawParts::new_mut(
+                            ::rust_out::Counter::lock(__self))
+                    }
                 }
             );
         });
     }
 
-    /// `test_generated_bindings_non_pub_items` verifies that non-public items
-    /// are not present/propagated into the generated bindings.
     #[test]
-    fn test_generated_bindings_non_pub_items() {
+    fn test_format_item_fn_returning_rwlock_read_guard() {
         let test_src = r#"
-                #![allow(dead_code)]
-
-                extern "C" fn private_function() {
-                    println!("foo");
-                }
-
-                struct PrivateStruct {
-                    x: i32,
-                    y: i32,
-                }
-
-                pub struct PublicStruct(i32);
+                use std::sync::{RwLock, RwLockReadGuard};
 
-                impl PublicStruct {
-                    fn private_method() {}
-                }
-
-                pub mod public_module {
-                    fn priv_func_in_pub_module() {}
-                }
-
-                mod private_module {
-                    pub fn pub_func_in_priv_module() { priv_func_in_priv_module() }
-                    fn priv_func_in_priv_module() {}
+                pub fn read(lock: &'static RwLock<f64>) -> RwLockReadGuard<'static, f64> {
+                    lock.read().unwrap()
                 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_not_matches!(bindings.h_body, quote! { private_function });
-            assert_rs_not_matches!(bindings.rs_body, quote! { private_function });
-            assert_cc_not_matches!(bindings.h_body, quote! { PrivateStruct });
-            assert_rs_not_matches!(bindings.rs_body, quote! { PrivateStruct });
-            assert_cc_not_matches!(bindings.h_body, quote! { private_method });
-            assert_rs_not_matches!(bindings.rs_body, quote! { private_method });
-            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_priv_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_priv_module });
-            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_pub_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_pub_module });
-            assert_cc_not_matches!(bindings.h_body, quote! { private_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { private_module });
-            assert_cc_not_matches!(bindings.h_body, quote! { pub_func_in_priv_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { pub_func_in_priv_module });
-        });
-    }
-
-    #[test]
-    fn test_generated_bindings_top_level_items() {
-        let test_src = "pub fn public_function() {}";
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            let expected_comment_txt =
-                "Automatically @generated C++ bindings for the following Rust crate:\n\
-                 rust_out";
+        test_format_item(test_src, "read", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                impl_details.cc.tokens,
                 quote! {
-                    __COMMENT__ #expected_comment_txt
-                    ...
-                    __HASH_TOKEN__ pragma once
                     ...
-                    namespace rust_out {
-                        ...
+                    inline rs_std::LockGuard<const double> read(...) {
+                      return rs_std::LockGuard<const double>::from_raw_parts(
+                          __crubit_internal::...(...));
                     }
                 }
             );
-            assert_cc_matches!(
-                bindings.rs_body,
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    __COMMENT__ #expected_comment_txt
+                    ::lock_bridge::LockGuardRawParts::new(::rust_out::read(lock))
                 }
             );
-        })
+        });
     }
 
-    /// The `test_generated_bindings_unsupported_item` test verifies how `Err`
-    /// from `format_item` is formatted as a C++ comment (in `format_crate`
-    /// and `format_unsupported_def`):
-    /// - This test covers only a single example of an unsupported item.
-    ///   Additional coverage is provided by `test_format_item_unsupported_...`
-    ///   tests.
-    /// - This test somewhat arbitrarily chooses an example of an unsupported
-    ///   item, trying to pick one that 1) will never be supported (b/254104998
-    ///   has some extra notes about APIs named after reserved C++ keywords) and
-    ///   2) tests that the full error chain is included in the message.
+    /// `test_generated_bindings_no_std` tests that the Rust side of the
+    /// bindings of a `#![no_std]` crate doesn't depend on `std`, and that types
+    /// from `core` are recognized despite their `core::...` paths.
     #[test]
-    fn test_generated_bindings_unsupported_item() {
+    fn test_generated_bindings_no_std() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn reinterpret_cast() {}
+                #![no_std]
+                use core::ptr::NonNull;
+
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+
+                pub fn addr(ptr: NonNull<i32>) -> usize { ptr.as_ptr() as usize }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            let expected_comment_txt = "Error generating bindings for `reinterpret_cast` \
-                 defined at <crubit_unittests.rs>;l=3: \
-                 Error formatting function name: \
-                 `reinterpret_cast` is a C++ reserved keyword \
-                 and can't be used as a C++ identifier";
+        run_compiler_for_testing(test_src, |tcx| {
+            assert_eq!(detect_rust_std_lib(tcx), RustStdLib::Core);
+            let Output { h_body, rs_body, .. } =
+                generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
             assert_cc_matches!(
-                bindings.h_body,
+                h_body,
+                quote! { inline std::uintptr_t addr(rs_std::NonNull<std::int32_t> ptr); }
+            );
+            assert_rs_matches!(rs_body, quote! { #![no_std] });
+            assert_rs_matches!(
+                rs_body,
                 quote! {
-                    __COMMENT__ #expected_comment_txt
+                    const _: () = assert!(::core::mem::size_of::<::rust_out::Point>() == 8);
+                    const _: () = assert!(::core::mem::align_of::<::rust_out::Point>() == 4);
                 }
             );
-        })
-    }
-
-    #[test]
-    fn test_generated_bindings_reimports() {
-        let test_src = r#"
-                #![allow(dead_code)]
-                #![allow(unused_imports)]
-                mod private_submodule1 {
-                    pub fn subfunction1() {}
-                    pub fn subfunction2() {}
-                    pub fn subfunction3() {}
-                }
-                mod private_submodule2 {
-                    pub fn subfunction8() {}
-                    pub fn subfunction9() {}
-                }
-
-                // Public re-import.
-                pub use private_submodule1::subfunction1;
-
-                // Private re-import.
-                use private_submodule1::subfunction2;
-
-                // Re-import that renames.
-                pub use private_submodule1::subfunction3 as public_function3;
-
-                // Re-import of multiple items via glob.
-                pub use private_submodule2::*;
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-
-            let failures = vec![(1, 15), (3, 21), (4, 24)];
-            for (use_number, line_number) in failures.into_iter() {
-                let expected_comment_txt = format!(
-                    "Error generating bindings for `{{use#{use_number}}}` defined at \
-                     <crubit_unittests.rs>;l={line_number}: \
-                     Unsupported rustc_hir::hir::ItemKind: `use` import"
-                );
-                assert_cc_matches!(
-                    bindings.h_body,
-                    quote! {
-                        __COMMENT__ #expected_comment_txt
-                    }
-                );
-            }
+            assert_rs_not_matches!(rs_body, quote! { ::std });
         });
     }
 
+    /// `test_generated_bindings_no_std_with_alloc` tests that `Cow<str>` is
+    /// supported in `#![no_std]` crates that depend on `alloc`, but not when
+    /// the bindings are restricted to `core`.
     #[test]
-    fn test_format_item_fn_extern_c_no_mangle_no_params_no_return_type() {
+    fn test_generated_bindings_no_std_with_alloc() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn public_function() {}
+                #![no_std]
+                extern crate alloc;
+                use alloc::borrow::Cow;
+
+                pub fn identity(s: Cow<'_, str>) -> Cow<'_, str> { s }
             "#;
-        test_format_item(test_src, "public_function", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+        run_compiler_for_testing(test_src, |tcx| {
+            assert_eq!(detect_rust_std_lib(tcx), RustStdLib::Alloc);
+            let Output { h_body, rs_body, .. } =
+                generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
             assert_cc_matches!(
-                main_api.tokens,
-                quote! {
-                    extern "C" void public_function();
-                }
+                h_body,
+                quote! { inline rs_std::CowStr identity(rs_std::CowStr s); }
+            );
+            assert_rs_matches!(rs_body, quote! { #![no_std] });
+
+            let input = Input { rust_std_lib: RustStdLib::Core, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
+            assert_cc_not_matches!(h_body, quote! { rs_std::CowStr });
+            assert!(
+                h_body.to_string().contains("is not supported without the `alloc` crate"),
+                "h_body = {h_body}"
             );
         });
     }
 
-    /// The `test_format_item_fn_explicit_unit_return_type` test below is very
-    /// similar to the
-    /// `test_format_item_fn_extern_c_no_mangle_no_params_no_return_type` above,
-    /// except that the return type is explicitly spelled out.  There is no
-    /// difference in `ty::FnSig` so our code behaves exactly the same, but the
-    /// test has been planned based on earlier, hir-focused approach and having
-    /// this extra test coverage shouldn't hurt. (`hir::FnSig`
-    /// and `hir::FnRetTy` _would_ see a difference between the two tests, even
-    /// though there is no different in the current `bindings.rs` code).
+    /// `test_generated_bindings_cdylib` tests that the bindings of a `cdylib`
+    /// crate annotate the imported symbols with `CRUBIT_DYLIB_IMPORT` and
+    /// document the expected link line.
     #[test]
-    fn test_format_item_fn_explicit_unit_return_type() {
+    fn test_generated_bindings_cdylib() {
         let test_src = r#"
                 #[no_mangle]
-                pub extern "C" fn explicit_unit_return_type() -> () {}
-            "#;
-        test_format_item(test_src, "explicit_unit_return_type", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+                pub extern "C" fn public_function() {}
+
+                pub fn add(x: i32, y: i32) -> i32 { x + y }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            assert_eq!(detect_crate_linkage(tcx), CrateLinkage::Rlib);
+            let Output { h_body, .. } = generate_bindings(&bindings_input_for_tests(tcx)).unwrap();
+            assert_cc_not_matches!(h_body, quote! { CRUBIT_DYLIB_IMPORT });
+            assert!(!h_body.to_string().contains("Expected link line"), "h_body = {h_body}");
+
+            let input =
+                Input { crate_linkage: CrateLinkage::DynamicLib, ..bindings_input_for_tests(tcx) };
+            let Output { h_body, .. } = generate_bindings(&input).unwrap();
             assert_cc_matches!(
-                main_api.tokens,
+                h_body,
+                quote! { __HASH_TOKEN__ include "crubit/support/for/tests/internal/dylib_import.h" }
+            );
+            assert_cc_matches!(
+                h_body,
+                quote! { extern "C" CRUBIT_DYLIB_IMPORT void public_function(); }
+            );
+            assert_cc_matches!(
+                h_body,
                 quote! {
-                    extern "C" void explicit_unit_return_type();
+                    namespace __crubit_internal {
+                        extern "C" CRUBIT_DYLIB_IMPORT std::int32_t ...(
+                            std::int32_t x, std::int32_t y);
+                    }
                 }
             );
+            assert!(
+                h_body.to_string().contains(
+                    "The Rust side of the bindings has to be compiled into the same shared library."
+                ),
+                "h_body = {h_body}"
+            );
         });
     }
 
     #[test]
-    fn test_format_item_fn_never_return_type() {
+    fn test_format_item_fn_rust_abi_with_string() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn never_returning_function() -> ! {
-                    panic!("This function panics and therefore never returns");
+                pub fn exclaim(s: String) -> String {
+                    s + "!"
                 }
             "#;
-        test_format_item(test_src, "never_returning_function", |result| {
-            // TODO(b/254507801): The function should be annotated with the `[[noreturn]]`
-            // attribute.
-            // TODO(b/254507801): Expect `crubit::Never` instead (see the bug for more
-            // details).
+        test_format_item(test_src, "exclaim", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    extern "C" void never_returning_function();
+                    inline rs_std::String exclaim(rs_std::String s);
                 }
             );
-        })
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include "crubit/support/for/tests/rs_std/rs_string.h" }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" rs_std::String::RawParts ...(rs_std::String::RawParts s);
+                    }
+                    inline rs_std::String exclaim(rs_std::String s) {
+                        return rs_std::String::from_raw_parts(
+                            __crubit_internal::...(std::move(s).release()));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(s: ::alloc_bridge::StringRawParts) -> ::alloc_bridge::StringRawParts {
+                        ::alloc_bridge::StringRawParts::from_string(::rust_out::exclaim(
+                            unsafe { ::alloc_bridge::StringRawParts::into_string(s) }))
+                    }
+                }
+            );
+        });
     }
 
-    /// `test_format_item_fn_mangling` checks that bindings can be generated for
-    /// `extern "C"` functions that do *not* have `#[no_mangle]` attribute.  The
-    /// test elides away the mangled name in the `assert_cc_matches` checks
-    /// below, but end-to-end test coverage should eventually be provided by
-    /// `test/functions` (see b/262904507).
     #[test]
-    fn test_format_item_fn_mangling() {
+    fn test_format_item_fn_rust_abi_with_vec() {
         let test_src = r#"
-                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+                pub fn doubled(v: Vec<i32>) -> Vec<i32> {
+                    v.into_iter().map(|x| x * 2).collect()
+                }
             "#;
-        test_format_item(test_src, "public_function", |result| {
+        test_format_item(test_src, "doubled", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double public_function(double x, double y);
+                    inline rs_std::Vec<std::int32_t> doubled(rs_std::Vec<std::int32_t> v);
                 }
             );
-            assert!(impl_details.rs.is_empty());
-            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include "crubit/support/for/tests/rs_std/rs_vec.h" }
+            );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" double ...(double x, double y);
+                        extern "C" rs_std::Vec<std::int32_t>::RawParts ...(
+                            rs_std::Vec<std::int32_t>::RawParts v);
                     }
-                    ...
-                    inline double public_function(double x, double y) {
-                        return __crubit_internal::...(x, y);
+                    inline rs_std::Vec<std::int32_t> doubled(rs_std::Vec<std::int32_t> v) {
+                        return rs_std::Vec<std::int32_t>::from_raw_parts(
+                            __crubit_internal::...(std::move(v).release()));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(v: ::alloc_bridge::VecRawParts<i32>) -> ::alloc_bridge::VecRawParts<i32> {
+                        ::alloc_bridge::VecRawParts::from_vec(::rust_out::doubled(
+                            unsafe { ::alloc_bridge::VecRawParts::into_vec(v) }))
                     }
                 }
             );
@@ -2420,89 +11778,100 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_fn_export_name() {
+    fn test_format_item_fn_rust_abi_with_box() {
         let test_src = r#"
-                #[export_name = "export_name"]
-                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+                pub fn incremented(b: Box<i32>) -> Box<i32> {
+                    Box::new(*b + 1)
+                }
             "#;
-        test_format_item(test_src, "public_function", |result| {
+        test_format_item(test_src, "incremented", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double public_function(double x, double y);
+                    inline rs_std::Box<std::int32_t> incremented(rs_std::Box<std::int32_t> b);
                 }
             );
-            assert!(impl_details.rs.is_empty());
-            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include "crubit/support/for/tests/rs_std/rs_box.h" }
+            );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" double export_name(double x, double y);
+                        extern "C" std::int32_t* ...(std::int32_t* b);
                     }
-                    ...
-                    inline double public_function(double x, double y) {
-                        return __crubit_internal::export_name(x, y);
+                    inline rs_std::Box<std::int32_t> incremented(rs_std::Box<std::int32_t> b) {
+                        return rs_std::Box<std::int32_t>::from_raw(
+                            __crubit_internal::...(std::move(b).release()));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(b: *mut i32) -> *mut i32 {
+                        ::alloc_bridge::box_into_raw(::rust_out::incremented(
+                            unsafe { ::alloc_bridge::box_from_raw(b) }))
                     }
                 }
             );
         });
     }
 
+    /// `Cow<str>` is converted by the thunks, and therefore it is not supported
+    /// in signatures of `extern "C"` functions (which are called without a
+    /// thunk).
     #[test]
-    fn test_format_item_unsupported_fn_unsafe() {
+    fn test_format_item_extern_c_fn_with_cow_str() {
         let test_src = r#"
+                use std::borrow::Cow;
                 #[no_mangle]
-                pub unsafe extern "C" fn foo() {}
+                #[allow(improper_ctypes_definitions)]
+                pub extern "C" fn len(s: Cow<'_, str>) -> usize { s.len() }
             "#;
-        test_format_item(test_src, "foo", |result| {
+        test_format_item(test_src, "len", |result| {
             let err = result.unwrap_err();
             assert_eq!(
                 err,
-                "Bindings for `unsafe` functions \
-                             are not fully designed yet (b/254095482)"
+                "Types converted at the FFI boundary (e.g. `Cow<str>`, lock guards, or types \
+                 mapped to C++ types with a conversion - see `--type-map`) are not supported in \
+                 signatures of `extern \"C\"` functions"
             );
         });
     }
 
-    /// `test_format_item_fn_const` tests how bindings for an `const fn` are
-    /// generated.
-    ///
-    /// Right now the `const` qualifier is ignored, but one can imagine that in
-    /// the (very) long-term future such functions (including their bodies)
-    /// could be translated into C++ `consteval` functions.
     #[test]
-    fn test_format_item_fn_const() {
+    fn test_format_item_fn_rust_abi_returning_struct_by_value() {
         let test_src = r#"
-                pub const fn foo(i: i32) -> i32 { i * 42 }
+                pub struct S(i32);
+                pub fn create(i: i32) -> S { S(i) }
             "#;
-        test_format_item(test_src, "foo", |result| {
-            // TODO(b/254095787): Update test expectations below once `const fn` from Rust
-            // is translated into a `consteval` C++ function.
+        test_format_item(test_src, "create", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline std::int32_t foo(std::int32_t i);
+                    inline ::rust_out::S create(std::int32_t i);
                 }
             );
-            assert!(!impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" std::int32_t ...( std::int32_t i);
+                        extern "C" void ...(std::int32_t i, ::rust_out::S* __ret_ptr);
                     }
                     ...
-                    inline std::int32_t foo(std::int32_t i) {
-                        return __crubit_internal::...(i);
+                    inline ::rust_out::S create(std::int32_t i) {
+                        crubit::ReturnValueSlot<::rust_out::S> __ret_slot;
+                        __crubit_internal::...(i, __ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
                     }
                 }
             );
@@ -2511,672 +11880,672 @@ pub mod tests {
                 quote! {
                     #[no_mangle]
                     extern "C"
-                    fn ...(i: i32) -> i32 {
-                        ::rust_out::foo(i)
+                    fn ...(
+                        i: i32,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<::rust_out::S>
+                    ) -> () {
+                        __ret_slot.write(::rust_out::create(i));
                     }
                 }
             );
         });
     }
 
+    /// `test_format_item_fn_rust_abi` tests a function call that is not a
+    /// C-ABI, and is not the default Rust ABI.  It can't use `"stdcall"`,
+    /// because it is not supported on the targets where Crubit's tests run.
+    /// So, it ended up using `"vectorcall"`.
+    ///
+    /// This test almost entirely replicates `test_format_item_fn_rust_abi`,
+    /// except for the `extern "vectorcall"` part in the `test_src` test
+    /// input.
+    ///
+    /// This test verifies the current behavior that gives reasonable and
+    /// functional FFI bindings.  OTOH, in the future we may decide to avoid
+    /// having the extra thunk for cases where the given non-C-ABI function
+    /// call convention is supported by both C++ and Rust
+    /// (see also `format_cc_call_conv_as_clang_attribute` in
+    /// `rs_bindings_from_cc/src_code_gen.rs`)
     #[test]
-    fn test_format_item_fn_with_c_unwind_abi() {
-        // See also https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html
+    fn test_format_item_fn_vectorcall_abi() {
         let test_src = r#"
-                #![feature(c_unwind)]
-
-                #[no_mangle]
-                pub extern "C-unwind" fn may_throw() {}
+                #![feature(abi_vectorcall)]
+                pub extern "vectorcall" fn add(x: f64, y: f64) -> f64 { x * y }
             "#;
-        test_format_item(test_src, "may_throw", |result| {
+        test_format_item(test_src, "add", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    extern "C" void may_throw();
+                    inline double add(double x, double y);
                 }
             );
-        });
-    }
-
-    /// This test mainly verifies that `format_item` correctly propagates
-    /// `CcPrerequisites` of parameter types and return type.
-    #[test]
-    fn test_format_item_fn_cc_prerequisites_if_cpp_definition_needed() {
-        let test_src = r#"
-                pub fn foo(_i: i32) -> S { panic!("foo") }
-                pub struct S(i32);
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-
-            // Minimal coverage, just to double-check that the test setup works.
-            //
-            // Note that this is a definition, and therefore `S` should be defined
-            // earlier (not just forward declared).
-            assert_cc_matches!(main_api.tokens, quote! { S foo(std::int32_t _i);});
-            assert_cc_matches!(impl_details.cc.tokens, quote! { S foo(std::int32_t _i) { ... }});
-
-            // Main checks: `CcPrerequisites::includes`.
+            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
-                format_cc_includes(&main_api.prereqs.includes),
-                quote! { include <cstdint> }
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double ...(double x, double y);
+                    }
+                    ...
+                    inline double add(double x, double y) {
+                        return __crubit_internal::...(x, y);
+                    }
+                }
             );
-            assert_cc_matches!(
-                format_cc_includes(&impl_details.cc.prereqs.includes),
-                quote! { include <cstdint> }
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(x: f64, y: f64) -> f64 {
+                        ::rust_out::add(x, y)
+                    }
+                }
             );
-
-            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
-            //
-            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
-            // expose `tcx` to the verification function (and therefore calling
-            // `find_def_id_by_name` is not easily possible).
-            //
-            // Note that `main_api` and `impl_details` have different expectations.
-            assert_eq!(0, main_api.prereqs.defs.len());
-            assert_eq!(1, main_api.prereqs.fwd_decls.len());
-            assert_eq!(1, impl_details.cc.prereqs.defs.len());
-            assert_eq!(0, impl_details.cc.prereqs.fwd_decls.len());
         });
     }
 
-    /// This test verifies that `format_item` uses `CcPrerequisites::fwd_decls`
-    /// rather than `CcPrerequisites::defs` for functions that only need a
-    /// C++ declaration (and don't need a C++ definition).
     #[test]
-    fn test_format_item_fn_cc_prerequisites_if_only_cpp_declaration_needed() {
+    fn test_format_item_unsupported_fn_variadic() {
         let test_src = r#"
-                // `foo` will only have a C++ declaration (and no C++ definition)
-                // in the generated `..._cc_api.h` header.
-                //
-                // Therefore `foo`'s `CcPrerequisites` should include `S`
-                // as a `fwd_decls` edge, rather than as a `defs` edge.
-                #[no_mangle]
-                pub extern "C" fn foo(s: S) -> bool { s.0 }
+                #![feature(c_variadic)]
 
-                #[repr(C)]
-                pub struct S(bool);
+                #[no_mangle]
+                pub unsafe extern "C" fn variadic_function(_fmt: *const u8, ...) {}
             "#;
-        test_format_item(test_src, "foo", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-
-            // Minimal coverage, just to double-check that the test setup works.
-            //
-            // Note that this is only a function *declaration* (not a function definition -
-            // there is no function body), and therefore `S` just needs to be
-            // forward-declared earlier.
-            assert_cc_matches!(main_api.tokens, quote! { extern "C" bool foo(::rust_out::S s); });
-
-            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
-            //
-            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
-            // expose `tcx` to the verification function (and therefore calling
-            // `find_def_id_by_name` is not easily possible).
-            assert_eq!(0, main_api.prereqs.defs.len());
-            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+        test_format_item(test_src, "variadic_function", |result| {
+            // TODO(b/254097223): Add support for variadic functions.
+            let err = result.unwrap_err();
+            assert_eq!(err, "C variadic functions are not supported (b/254097223)");
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_type_aliased_return_type() {
-        // Type aliases disappear at the `rustc_middle::ty::Ty` level and therefore in
-        // the short-term the generated bindings also ignore type aliases.
-        //
-        // TODO(b/254096006): Consider preserving `type` aliases when generating
-        // bindings.
+    fn test_format_item_fn_params() {
         let test_src = r#"
-                type MyTypeAlias = f64;
-
+                #[allow(unused_variables)]
                 #[no_mangle]
-                pub extern "C" fn type_aliased_return() -> MyTypeAlias { 42.0 }
+                pub extern "C" fn foo(b: bool, f: f64) {}
             "#;
-        test_format_item(test_src, "type_aliased_return", |result| {
+        test_format_item(test_src, "foo", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    extern "C" double type_aliased_return();
+                    ...
+                    extern "C" void foo(bool b, double f);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_doc_comment_with_unmangled_name() {
+    fn test_format_item_fn_param_name_reserved_keyword() {
         let test_src = r#"
-            /// Outer line doc.
-            /** Outer block doc that spans lines.
-             */
-            #[doc = "Doc comment via doc attribute."]
-            #[no_mangle]
-            pub extern "C" fn fn_with_doc_comment_with_unmangled_name() {}
-          "#;
-        test_format_item(test_src, "fn_with_doc_comment_with_unmangled_name", |result| {
+                #[allow(unused_variables)]
+                #[no_mangle]
+                pub extern "C" fn some_function(reinterpret_cast: f64) {}
+            "#;
+        test_format_item(test_src, "some_function", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             assert!(main_api.prereqs.is_empty());
-            let doc_comments = [
-                " Outer line doc.",
-                "",
-                " Outer block doc that spans lines.",
-                "             ",
-                "",
-                "Doc comment via doc attribute.",
-                "",
-                "Generated from: <crubit_unittests.rs>;l=7",
-            ]
-            .join("\n");
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #doc_comments
-                    extern "C" void fn_with_doc_comment_with_unmangled_name();
+                    ...
+                    extern "C" void some_function(double __param_0);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_inner_doc_comment_with_unmangled_name() {
+    fn test_format_item_fn_with_multiple_anonymous_parameter_names() {
         let test_src = r#"
-            /// Outer doc comment.
-            #[no_mangle]
-            pub extern "C" fn fn_with_inner_doc_comment_with_unmangled_name() {
-                //! Inner doc comment.
-            }
-          "#;
-        test_format_item(test_src, "fn_with_inner_doc_comment_with_unmangled_name", |result| {
+                pub fn foo(_: f64, _: f64) {}
+            "#;
+        test_format_item(test_src, "foo", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert!(main_api.prereqs.is_empty());
-            let doc_comments = [
-                " Outer doc comment.",
-                " Inner doc comment.",
-                "Generated from: <crubit_unittests.rs>;l=4",
-            ]
-            .join("\n\n");
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #doc_comments
-                    extern "C" void fn_with_inner_doc_comment_with_unmangled_name();
+                    inline void foo(double __param_0, double __param_1);
+                }
+            );
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            double __param_0, double __param_1);
+                    }
+                    ...
+                    inline void foo(double __param_0, double __param_1) {
+                        return __crubit_internal::...(__param_0, __param_1);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__param_0: f64, __param_1: f64) -> () {
+                        ::rust_out::foo(__param_0, __param_1)
+                    }
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_doc_comment_with_mangled_name() {
+    fn test_format_item_fn_with_destructuring_parameter_name() {
         let test_src = r#"
-                /// Doc comment of a function with mangled name.
-                pub extern "C" fn fn_with_doc_comment_with_mangled_name() {}
+                pub struct S {
+                    pub f1: i32,
+                    pub f2: i32,
+                }
+
+                // This test mostly focuses on the weird parameter "name" below.
+                // See also
+                // https://doc.rust-lang.org/reference/items/functions.html#function-parameters
+                // which points out that function parameters are just irrefutable patterns.
+                pub fn func(S{f1, f2}: S) -> i32 { f1 + f2 }
             "#;
-        test_format_item(test_src, "fn_with_doc_comment_with_mangled_name", |result| {
+        test_format_item(test_src, "func", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let comment = " Doc comment of a function with mangled name.\n\n\
-                           Generated from: <crubit_unittests.rs>;l=3";
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #comment
-                    inline void fn_with_doc_comment_with_mangled_name();
+                    inline std::int32_t func(::rust_out::S __param_0);
                 }
             );
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_name_is_reserved_cpp_keyword() {
-        let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn reinterpret_cast() -> () {}
-            "#;
-        test_format_item(test_src, "reinterpret_cast", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error formatting function name: \
-                       `reinterpret_cast` is a C++ reserved keyword \
-                       and can't be used as a C++ identifier"
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(::rust_out::S* __param_0);
+                    }
+                    ...
+                    inline std::int32_t func(::rust_out::S __param_0) {
+                        return __crubit_internal::...(&__param_0);
+                    }
+                }
             );
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_ret_type() {
-        let test_src = r#"
-                pub fn foo() -> (i32, i32) { (123, 456) }
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error formatting function return type: \
-                       Tuples are not supported yet: (i32, i32) (b/254099023)"
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __param_0: &mut ::core::mem::MaybeUninit<::rust_out::S>
+                    ) -> i32 {
+                        ::rust_out::func(unsafe {__param_0.assume_init_read() })
+                    }
+                }
             );
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_with_late_bound_lifetimes() {
-        // TODO(b/258235219): Expect success after adding support for references.
+    fn test_format_item_unsupported_fn_param_type() {
         let test_src = r#"
-                pub fn foo(arg: &i32) -> &i32 { arg }
-
-                // Lifetime inference translates the above into:
-                //     pub fn foo<'a>(arg: &'a i32) -> &'a i32 { ... }
-                // leaving 'a lifetime late-bound (it is bound with a lifetime
-                // taken from each of the callsites).  In other words, we can't
-                // just call `no_bound_vars` on this `FnSig`'s `Binder`.
+                pub fn foo(_param: (i32, i32)) {}
             "#;
         test_format_item(test_src, "foo", |result| {
             let err = result.unwrap_err();
-            assert_eq!(err, "Generic functions are not supported yet (b/259749023)");
+            assert_eq!(err, "Error handling parameter #0: \
+                             Tuples are not supported yet: (i32, i32) (b/254099023)");
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_generic_fn() {
+    fn test_format_item_unsupported_fn_param_type_unit() {
         let test_src = r#"
-                use std::default::Default;
-                use std::fmt::Display;
-                pub fn generic_function<T: Default + Display>() {
-                    println!("{}", T::default());
-                }
+                #[no_mangle]
+                pub fn fn_with_params(_param: ()) {}
             "#;
-        test_format_item(test_src, "generic_function", |result| {
+        test_format_item(test_src, "fn_with_params", |result| {
             let err = result.unwrap_err();
-            assert_eq!(err, "Generic functions are not supported yet (b/259749023)");
+            assert_eq!(err, "Error handling parameter #0: \
+                             `()` / `void` is only supported as a return type (b/254507801)");
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_generic_struct() {
+    fn test_format_item_unsupported_fn_param_type_never() {
         let test_src = r#"
-                pub struct Point<T> {
-                    pub x: T,
-                    pub y: T,
-                }
-            "#;
-        test_format_item(test_src, "Point", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
-        });
-    }
+                #![feature(never_type)]
 
-    #[test]
-    fn test_format_item_unsupported_generic_enum() {
-        let test_src = r#"
-                pub enum Point<T> {
-                    Cartesian{x: T, y: T},
-                    Polar{angle: T, dist: T},
-                }
+                #[no_mangle]
+                pub extern "C" fn fn_with_params(_param: !) {}
             "#;
-        test_format_item(test_src, "Point", |result| {
+        test_format_item(test_src, "fn_with_params", |result| {
             let err = result.unwrap_err();
-            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+            assert_eq!(
+                err,
+                "Error handling parameter #0: \
+                 The never type `!` is only supported as a return type (b/254507801)"
+            );
         });
     }
 
+    /// This is a test for a regular struct - a struct with named fields.
+    /// https://doc.rust-lang.org/reference/items/structs.html refers to this kind of struct as
+    /// `StructStruct` or "nominal struct type".
     #[test]
-    fn test_format_item_unsupported_generic_union() {
+    fn test_format_item_struct_with_fields() {
         let test_src = r#"
-                pub union SomeUnion<T> {
-                    pub x: std::mem::ManuallyDrop<T>,
+                pub struct SomeStruct {
+                    pub x: i32,
                     pub y: i32,
-                }
-            "#;
-        test_format_item(test_src, "SomeUnion", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_async() {
-        let test_src = r#"
-                pub async fn async_function() {}
-            "#;
-        test_format_item(test_src, "async_function", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Error formatting function return type: \
-                             The following Rust type is not supported yet: \
-                             impl std::future::Future<Output = ()>");
-        });
-    }
-
-    #[test]
-    fn test_format_item_fn_rust_abi() {
-        let test_src = r#"
-                pub fn add(x: f64, y: f64) -> f64 { x * y }
+                }
+
+                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
+                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
             "#;
-        test_format_item(test_src, "add", |result| {
-            // TODO(b/261074843): Re-add thunk name verification once we are using stable name
-            // mangling (which may be coming in Q1 2023).  (This might mean reverting cl/492333432
-            // + manual review and tweaks.)
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double add(double x, double y);
+                    ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) SomeStruct final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            SomeStruct() = delete;
+
+                            // In this test there is no `Copy` implementation / derive.
+                            SomeStruct(const SomeStruct&) = delete;
+
+                            // All Rust types are trivially-movable.
+                            SomeStruct(SomeStruct&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            SomeStruct& operator=(const SomeStruct&) = delete;
+                            SomeStruct& operator=(SomeStruct&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~SomeStruct() = default;
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = true;
+                        private:
+                            ...  std::int32_t x;
+                            ...  std::int32_t y;
+                            inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                        extern "C" double ...(double x, double y);
-                    }
-                    ...
-                    inline double add(double x, double y) {
-                        return __crubit_internal::...(x, y);
+                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, x));
+                      static_assert(4 == offsetof(SomeStruct, y));
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...(x: f64, y: f64) -> f64 {
-                        ::rust_out::add(x, y)
-                    }
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, x) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, y) == 4);
                 }
             );
         });
     }
 
+    /// This is a test for `TupleStruct` or "tuple struct" - for more details
+    /// please refer to https://doc.rust-lang.org/reference/items/structs.html
     #[test]
-    fn test_format_item_fn_rust_abi_with_param_taking_struct_by_value() {
+    fn test_format_item_struct_with_tuple() {
         let test_src = r#"
-                pub struct S(i32);
-                pub fn into_i32(s: S) -> i32 { s.0 }
+                pub struct TupleStruct(pub i32, pub i32);
+                const _: () = assert!(std::mem::size_of::<TupleStruct>() == 8);
+                const _: () = assert!(std::mem::align_of::<TupleStruct>() == 4);
             "#;
-        test_format_item(test_src, "into_i32", |result| {
+        test_format_item(test_src, "TupleStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline std::int32_t into_i32(::rust_out::S s);
+                    ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) TupleStruct final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            TupleStruct() = delete;
+
+                            // In this test there is no `Copy` implementation / derive.
+                            TupleStruct(const TupleStruct&) = delete;
+
+                            // All Rust types are trivially-movable.
+                            TupleStruct(TupleStruct&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            TupleStruct& operator=(const TupleStruct&) = delete;
+                            TupleStruct& operator=(TupleStruct&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~TupleStruct() = default;
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = true;
+                        private:
+                            ...  std::int32_t __field0;
+                            ...  std::int32_t __field1;
+                            inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                        extern "C" std::int32_t ...(::rust_out::S* s);
-                    }
-                    ...
-                    inline std::int32_t into_i32(::rust_out::S s) {
-                        return __crubit_internal::...(&s);
+                    static_assert(sizeof(TupleStruct) == 8, ...);
+                    static_assert(alignof(TupleStruct) == 4, ...);
+                    inline void TupleStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(TupleStruct, __field0));
+                      static_assert(4 == offsetof(TupleStruct, __field1));
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...(s: &mut ::core::mem::MaybeUninit<::rust_out::S>) -> i32 {
-                        ::rust_out::into_i32(unsafe { s.assume_init_read() })
-                    }
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::TupleStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::TupleStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 0) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 1) == 4);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_rust_abi_returning_struct_by_value() {
+    fn test_format_item_struct_that_is_not_send_nor_sync() {
         let test_src = r#"
-                pub struct S(i32);
-                pub fn create(i: i32) -> S { S(i) }
+                pub struct SomeStruct {
+                    ptr: *const i32,
+                }
             "#;
-        test_format_item(test_src, "create", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline ::rust_out::S create(std::int32_t i);
-                }
-            );
-            assert_cc_matches!(
-                impl_details.cc.tokens,
-                quote! {
-                    namespace __crubit_internal {
-                        extern "C" void ...(std::int32_t i, ::rust_out::S* __ret_ptr);
-                    }
                     ...
-                    inline ::rust_out::S create(std::int32_t i) {
-                        crubit::ReturnValueSlot<::rust_out::S> __ret_slot;
-                        __crubit_internal::...(i, __ret_slot.Get());
-                        return std::move(__ret_slot).AssumeInitAndTakeValue();
-                    }
-                }
-            );
-            assert_rs_matches!(
-                impl_details.rs,
-                quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...(
-                        i: i32,
-                        __ret_slot: &mut ::core::mem::MaybeUninit<::rust_out::S>
-                    ) -> () {
-                        __ret_slot.write(::rust_out::create(i));
-                    }
+                    struct ... SomeStruct final {
+                        public:
+                            ...
+                            ~SomeStruct() = default;
+                            static constexpr bool kIsSend = false;
+                            static constexpr bool kIsSync = false;
+                        private:
+                            ...
+                    };
                 }
             );
         });
     }
 
-    /// `test_format_item_fn_rust_abi` tests a function call that is not a
-    /// C-ABI, and is not the default Rust ABI.  It can't use `"stdcall"`,
-    /// because it is not supported on the targets where Crubit's tests run.
-    /// So, it ended up using `"vectorcall"`.
-    ///
-    /// This test almost entirely replicates `test_format_item_fn_rust_abi`,
-    /// except for the `extern "vectorcall"` part in the `test_src` test
-    /// input.
-    ///
-    /// This test verifies the current behavior that gives reasonable and
-    /// functional FFI bindings.  OTOH, in the future we may decide to avoid
-    /// having the extra thunk for cases where the given non-C-ABI function
-    /// call convention is supported by both C++ and Rust
-    /// (see also `format_cc_call_conv_as_clang_attribute` in
-    /// `rs_bindings_from_cc/src_code_gen.rs`)
     #[test]
-    fn test_format_item_fn_vectorcall_abi() {
+    fn test_format_item_struct_with_unsafe_impl_send() {
         let test_src = r#"
-                #![feature(abi_vectorcall)]
-                pub extern "vectorcall" fn add(x: f64, y: f64) -> f64 { x * y }
+                pub struct SomeStruct {
+                    ptr: *const i32,
+                }
+
+                unsafe impl Send for SomeStruct {}
             "#;
-        test_format_item(test_src, "add", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double add(double x, double y);
-                }
-            );
-            assert!(impl_details.cc.prereqs.is_empty());
-            assert_cc_matches!(
-                impl_details.cc.tokens,
-                quote! {
-                    namespace __crubit_internal {
-                        extern "C" double ...(double x, double y);
-                    }
                     ...
-                    inline double add(double x, double y) {
-                        return __crubit_internal::...(x, y);
-                    }
-                }
-            );
-            assert_rs_matches!(
-                impl_details.rs,
-                quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...(x: f64, y: f64) -> f64 {
-                        ::rust_out::add(x, y)
-                    }
+                    struct ... SomeStruct final {
+                        public:
+                            ...
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = false;
+                        private:
+                            ...
+                    };
                 }
             );
         });
     }
 
+    /// This test the scenario where Rust lays out field in a different order
+    /// than the source order.
     #[test]
-    fn test_format_item_unsupported_fn_variadic() {
+    fn test_format_item_struct_with_reordered_field_offsets() {
         let test_src = r#"
-                #![feature(c_variadic)]
-
-                #[no_mangle]
-                pub unsafe extern "C" fn variadic_function(_fmt: *const u8, ...) {}
-            "#;
-        test_format_item(test_src, "variadic_function", |result| {
-            // TODO(b/254097223): Add support for variadic functions.
-            let err = result.unwrap_err();
-            assert_eq!(err, "C variadic functions are not supported (b/254097223)");
-        });
-    }
+                pub struct SomeStruct {
+                    pub field1: i16,
+                    pub field2: i32,
+                    pub field3: i16,
+                }
 
-    #[test]
-    fn test_format_item_fn_params() {
-        let test_src = r#"
-                #[allow(unused_variables)]
-                #[no_mangle]
-                pub extern "C" fn foo(b: bool, f: f64) {}
+                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
+                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
             "#;
-        test_format_item(test_src, "foo", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) SomeStruct final {
+                        ...
+                        private:
+                            // The particular order below is not guaranteed,
+                            // so we may need to adjust this test assertion
+                            // (if Rust changes how it lays out the fields).
+                            ...  std::int32_t field2;
+                            ...  std::int16_t field1;
+                            ...  std::int16_t field3;
+                            inline static void __crubit_field_offset_assertions();
+                    };
+                }
+            );
             assert_cc_matches!(
-                main_api.tokens,
+                impl_details.cc.tokens,
                 quote! {
-                    ...
-                    extern "C" void foo(bool b, double f);
+                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, field2));
+                      static_assert(4 == offsetof(SomeStruct, field1));
+                      static_assert(6 == offsetof(SomeStruct, field3));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
+                                           == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
+                                           == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field3)
+                                           == 6);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_param_name_reserved_keyword() {
+    fn test_format_item_struct_with_packed_layout() {
         let test_src = r#"
-                #[allow(unused_variables)]
-                #[no_mangle]
-                pub extern "C" fn some_function(reinterpret_cast: f64) {}
+                #[repr(packed(1))]
+                pub struct SomeStruct {
+                    pub field1: u16,
+                    pub field2: u32,
+                }
+                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 6);
+                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 1);
             "#;
-        test_format_item(test_src, "some_function", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    extern "C" void some_function(double __param_0);
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(1) __attribute__((packed)) SomeStruct final {
+                        ...
+                        std::uint16_t field1;
+                        std::uint32_t field2;
+                        inline static void __crubit_field_offset_assertions();
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(SomeStruct) == 6, ...);
+                    static_assert(alignof(SomeStruct) == 1, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, field1));
+                      static_assert(2 == offsetof(SomeStruct, field2));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 6);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 1);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
+                                           == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
+                                           == 2);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_multiple_anonymous_parameter_names() {
+    fn test_format_item_struct_with_explicit_padding_in_generated_code() {
         let test_src = r#"
-                pub fn foo(_: f64, _: f64) {}
+                pub struct SomeStruct {
+                    pub f1: u8,
+                    pub f2: u32,
+                }
+                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 8);
+                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 4);
             "#;
-        test_format_item(test_src, "foo", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline void foo(double __param_0, double __param_1);
+                    ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) SomeStruct final {
+                        ...
+                        std::uint32_t f2;
+                        std::uint8_t f1;
+                        unsigned char __padding0[3];
+                        inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                        extern "C" void ...(
-                            double __param_0, double __param_1);
-                    }
-                    ...
-                    inline void foo(double __param_0, double __param_1) {
-                        return __crubit_internal::...(__param_0, __param_1);
+                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, f2));
+                      static_assert(4 == offsetof(SomeStruct, f1));
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    #[no_mangle]
-                    extern "C" fn ...(__param_0: f64, __param_1: f64) -> () {
-                        ::rust_out::foo(__param_0, __param_1)
-                    }
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f2) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f1) == 4);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_destructuring_parameter_name() {
+    fn test_format_item_static_method() {
         let test_src = r#"
-                pub struct S {
-                    pub f1: i32,
-                    pub f2: i32,
-                }
+                /// No-op `f32` placeholder is used, because ZSTs are not supported
+                /// (b/258259459).
+                pub struct Math(f32);
 
-                // This test mostly focuses on the weird parameter "name" below.
-                // See also
-                // https://doc.rust-lang.org/reference/items/functions.html#function-parameters
-                // which points out that function parameters are just irrefutable patterns.
-                pub fn func(S{f1, f2}: S) -> i32 { f1 + f2 }
+                impl Math {
+                    pub fn add_i32(x: f32, y: f32) -> f32 {
+                        x + y
+                    }
+                }
             "#;
-        test_format_item(test_src, "func", |result| {
+        test_format_item(test_src, "Math", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline std::int32_t func(::rust_out::S __param_0);
+                    ...
+                    struct ... Math final {
+                        ...
+                        public:
+                          ...
+                          static inline float add_i32(float x, float y);
+                        ...
+                    };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" std::int32_t ...(::rust_out::S* __param_0);
+                    extern "C" float ... (float x, float y);
                     }
-                    ...
-                    inline std::int32_t func(::rust_out::S __param_0) {
-                        return __crubit_internal::...(&__param_0);
+                    inline float Math::add_i32(float x, float y) {
+                      return __crubit_internal::...(x, y);
                     }
                 }
             );
@@ -3184,10 +12553,8 @@ pub mod tests {
                 impl_details.rs,
                 quote! {
                     #[no_mangle]
-                    extern "C" fn ...(
-                        __param_0: &mut ::core::mem::MaybeUninit<::rust_out::S>
-                    ) -> i32 {
-                        ::rust_out::func(unsafe {__param_0.assume_init_read() })
+                    extern "C" fn ...(x: f32, y: f32) -> f32 {
+                        ::rust_out::Math::add_i32(x, y)
                     }
                 }
             );
@@ -3195,708 +12562,1011 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_param_type() {
+    fn test_format_item_static_method_with_generic_type_parameters() {
         let test_src = r#"
-                pub fn foo(_param: (i32, i32)) {}
+                /// No-op `f32` placeholder is used, because ZSTs are not supported
+                /// (b/258259459).
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    // To make this testcase distinct / non-overlapping wrt
+                    // test_format_item_static_method_with_generic_lifetime_parameters
+                    // `t` is taken by value below.
+                    pub fn generic_method<T: Clone>(t: T) -> T {
+                        t.clone()
+                    }
+                }
             "#;
-        test_format_item(test_src, "foo", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Error handling parameter #0: \
-                             Tuples are not supported yet: (i32, i32) (b/254099023)");
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let unsupported_msg = "[CRUBIT-E0001] Error generating bindings for \
+                                   `SomeStruct::generic_method` \
+                                   defined at <crubit_unittests.rs>;l=10: \
+                                   Generic functions are not supported yet (b/259749023)\n\
+                                   Suggestion: Consider exposing a non-generic wrapper that calls \
+                                   the generic function";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        __COMMENT__ #unsupported_msg
+                        ...
+                    };
+                    ...
+                }
+            );
+            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::generic_method },);
+            assert_rs_not_matches!(impl_details.rs, quote! { generic_method },);
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_param_type_unit() {
+    fn test_format_item_static_method_with_generic_lifetime_parameters() {
         let test_src = r#"
-                #[no_mangle]
-                pub fn fn_with_params(_param: ()) {}
+                /// No-op `f32` placeholder is used, because ZSTs are not supported
+                /// (b/258259459).
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn fn_taking_reference<'a>(x: &'a i32) -> i32 { *x }
+                }
             "#;
-        test_format_item(test_src, "fn_with_params", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Error handling parameter #0: \
-                             `()` / `void` is only supported as a return type (b/254507801)");
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let unsupported_msg = "[CRUBIT-E0013] Error generating bindings for \
+                                   `SomeStruct::fn_taking_reference` \
+                                   defined at <crubit_unittests.rs>;l=7: \
+                                   Error handling parameter #0: \
+                                   The following Rust type is not supported yet: &i32";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        __COMMENT__ #unsupported_msg
+                        ...
+                    };
+                    ...
+                }
+            );
+            assert_cc_not_matches!(
+                impl_details.cc.tokens,
+                quote! { SomeStruct::fn_taking_reference },
+            );
+            assert_rs_not_matches!(impl_details.rs, quote! { fn_taking_reference },);
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_param_type_never() {
+    fn test_format_item_method_taking_self_by_value() {
         let test_src = r#"
-                #![feature(never_type)]
+                pub struct SomeStruct(f32);
 
-                #[no_mangle]
-                pub extern "C" fn fn_with_params(_param: !) {}
+                impl SomeStruct {
+                    pub fn into_f32(self) -> f32 {
+                        self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "fn_with_params", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error handling parameter #0: \
-                 The never type `!` is only supported as a return type (b/254507801)"
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline float into_f32() &&;
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                    extern "C" float ... (::rust_out::SomeStruct* __self);
+                    }
+                    inline float SomeStruct::into_f32() && {
+                      return __crubit_internal::...(this);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>
+                    ) -> f32 {
+                        ::rust_out::SomeStruct::into_f32(unsafe { __self.assume_init_read() })
+                    }
+                }
             );
         });
     }
 
-    /// This is a test for a regular struct - a struct with named fields.
-    /// https://doc.rust-lang.org/reference/items/structs.html refers to this kind of struct as
-    /// `StructStruct` or "nominal struct type".
     #[test]
-    fn test_format_item_struct_with_fields() {
+    fn test_format_item_method_taking_self_by_const_ref() {
         let test_src = r#"
-                pub struct SomeStruct {
-                    pub x: i32,
-                    pub y: i32,
-                }
+                pub struct SomeStruct(f32);
 
-                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
-                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
+                impl SomeStruct {
+                    pub fn get_f32(&self) -> f32 {
+                        self.0
+                    }
+                }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(4) SomeStruct final {
+                    struct ... SomeStruct final {
+                        ...
                         public:
-                            // In this test there is no `Default` implementation.
-                            SomeStruct() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            SomeStruct(const SomeStruct&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            SomeStruct(SomeStruct&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            SomeStruct& operator=(const SomeStruct&) = delete;
-                            SomeStruct& operator=(SomeStruct&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~SomeStruct() = default;
-                        private:
-                            ...  std::int32_t x;
-                            ...  std::int32_t y;
-                            inline static void __crubit_field_offset_assertions();
+                          ...
+                          inline float get_f32() const;
+                        ...
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 8, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, x));
-                      static_assert(4 == offsetof(SomeStruct, y));
+                    namespace __crubit_internal {
+                    extern "C" float ... (const ::rust_out::SomeStruct* __self);
+                    }
+                    inline float SomeStruct::get_f32() const {
+                      return __crubit_internal::...(this);
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, x) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, y) == 4);
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &'static ::rust_out::SomeStruct) -> f32 {
+                        ::rust_out::SomeStruct::get_f32(__self)
+                    }
                 }
             );
         });
     }
 
-    /// This is a test for `TupleStruct` or "tuple struct" - for more details
-    /// please refer to https://doc.rust-lang.org/reference/items/structs.html
+    /// `test_format_item_struct_with_interior_mutability` tests that the
+    /// fields of a struct with interior mutability are opaque, and that its
+    /// `&self` methods are not `const` member functions.
     #[test]
-    fn test_format_item_struct_with_tuple() {
+    fn test_format_item_struct_with_interior_mutability() {
         let test_src = r#"
-                pub struct TupleStruct(pub i32, pub i32);
-                const _: () = assert!(std::mem::size_of::<TupleStruct>() == 8);
-                const _: () = assert!(std::mem::align_of::<TupleStruct>() == 4);
+                use std::cell::Cell;
+
+                pub struct Counter {
+                    pub limit: i32,
+                    count: Cell<i32>,
+                }
+
+                impl Counter {
+                    pub fn increment(&self) -> i32 {
+                        self.count.set(self.count.get() + 1);
+                        self.count.get()
+                    }
+                }
             "#;
-        test_format_item(test_src, "TupleStruct", |result| {
+        test_format_item(test_src, "Counter", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
+            let note = "\\note `Counter` has interior mutability (e.g. a `Cell` or a `RefCell` \
+                        field) - this method takes `&self` in Rust, but it may modify the \
+                        object, and therefore it is not a `const` member function.  Borrows of \
+                        `RefCell`s are checked at runtime: the method panics if it conflicts \
+                        with an outstanding borrow (e.g. when called reentrantly).";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(4) TupleStruct final {
+                    struct ... Counter final {
+                        ...
                         public:
-                            // In this test there is no `Default` implementation.
-                            TupleStruct() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            TupleStruct(const TupleStruct&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            TupleStruct(TupleStruct&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            TupleStruct& operator=(const TupleStruct&) = delete;
-                            TupleStruct& operator=(TupleStruct&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~TupleStruct() = default;
+                          ...
+                          __COMMENT__ #note
+                          inline std::int32_t increment();
                         private:
-                            ...  std::int32_t __field0;
-                            ...  std::int32_t __field1;
-                            inline static void __crubit_field_offset_assertions();
+                          __COMMENT__ "Field type has been replaced with a blob of bytes: \
+                                       `Counter` has interior mutability \
+                                       (e.g. a `Cell` or a `RefCell` field)"
+                          unsigned char __opaque_blob_of_bytes[8];
+                        ...
                     };
                 }
             );
+            assert_cc_not_matches!(main_api.tokens, quote! { limit });
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(TupleStruct) == 8, ...);
-                    static_assert(alignof(TupleStruct) == 4, ...);
-                    inline void TupleStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(TupleStruct, __field0));
-                      static_assert(4 == offsetof(TupleStruct, __field1));
+                    namespace __crubit_internal {
+                    extern "C" std::int32_t ... (::rust_out::Counter* __self);
+                    }
+                    inline std::int32_t Counter::increment() {
+                      return __crubit_internal::...(this);
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::TupleStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::TupleStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 0) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 1) == 4);
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &'static ::rust_out::Counter) -> i32 {
+                        ::rust_out::Counter::increment(__self)
+                    }
                 }
             );
         });
     }
 
-    /// This test the scenario where Rust lays out field in a different order
-    /// than the source order.
+    /// `test_format_item_fn_slice_of_interior_mutability` tests that `&[T]`
+    /// is not supported for types with interior mutability (because
+    /// `rs_std::Slice<T>` only provides `const` access to the elements).
     #[test]
-    fn test_format_item_struct_with_reordered_field_offsets() {
+    fn test_format_item_fn_slice_of_interior_mutability() {
         let test_src = r#"
-                pub struct SomeStruct {
-                    pub field1: i16,
-                    pub field2: i32,
-                    pub field3: i16,
+                use std::cell::Cell;
+
+                pub struct Counter(Cell<i32>);
+
+                pub fn reset_all(counters: &[Counter]) {
+                    counters.iter().for_each(|counter| counter.0.set(0));
                 }
+            "#;
+        test_format_item(test_src, "reset_all", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling parameter #0: `&[Counter]` is not supported, because `Counter` \
+                 has interior mutability (`rs_std::Slice` only provides `const` access to the \
+                 elements)"
+            );
+        });
+    }
 
-                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
-                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
+    #[test]
+    fn test_format_item_method_taking_self_by_mutable_ref() {
+        let test_src = r#"
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn set_f32(&mut self, new_value: f32) {
+                        self.0 = new_value;
+                    }
+                }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(4) SomeStruct final {
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline void set_f32(float new_value);
                         ...
-                        private:
-                            // The particular order below is not guaranteed,
-                            // so we may need to adjust this test assertion
-                            // (if Rust changes how it lays out the fields).
-                            ...  std::int32_t field2;
-                            ...  std::int16_t field1;
-                            ...  std::int16_t field3;
-                            inline static void __crubit_field_offset_assertions();
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 8, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, field2));
-                      static_assert(4 == offsetof(SomeStruct, field1));
-                      static_assert(6 == offsetof(SomeStruct, field3));
+                    namespace __crubit_internal {
+                    extern "C" void ... (::rust_out::SomeStruct* __self, float new_value);
+                    }
+                    inline void SomeStruct::set_f32(float new_value) {
+                      return __crubit_internal::...(this, new_value);
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
-                                           == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
-                                           == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field3)
-                                           == 6);
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &'static mut ::rust_out::SomeStruct,
+                        new_value: f32
+                    ) -> () {
+                        ::rust_out::SomeStruct::set_f32(__self, new_value)
+                    }
                 }
             );
         });
     }
 
+    /// Types that need to be dropped are moved out of the C++ object with
+    /// `mem::take` (see `is_moved_by_take`).
     #[test]
-    fn test_format_item_struct_with_packed_layout() {
+    fn test_format_item_method_taking_self_by_value_with_custom_drop_impl() {
         let test_src = r#"
-                #[repr(packed(1))]
-                pub struct SomeStruct {
-                    pub field1: u16,
-                    pub field2: u32,
+                #[derive(Default)]
+                pub struct SomeStruct(f32);
+
+                impl Drop for SomeStruct {
+                    fn drop(&mut self) {}
+                }
+
+                impl SomeStruct {
+                    pub fn into_f32(self) -> f32 {
+                        self.0
+                    }
                 }
-                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 6);
-                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 1);
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(1) __attribute__((packed)) SomeStruct final {
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline float into_f32() &&;
                         ...
-                        std::uint16_t field1;
-                        std::uint32_t field2;
-                        inline static void __crubit_field_offset_assertions();
                     };
                 }
             );
-            assert_cc_matches!(
-                impl_details.cc.tokens,
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 6, ...);
-                    static_assert(alignof(SomeStruct) == 1, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, field1));
-                      static_assert(2 == offsetof(SomeStruct, field2));
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &'static mut ::rust_out::SomeStruct) -> f32 {
+                        ::rust_out::SomeStruct::into_f32(::core::mem::take(__self))
                     }
                 }
             );
-            assert_rs_matches!(
-                impl_details.rs,
+        });
+    }
+
+    #[test]
+    fn test_format_item_struct_with_name_that_is_reserved_keyword() {
+        let test_src = r#"
+                #[allow(non_camel_case_types)]
+                pub struct reinterpret_cast {
+                    pub x: i32,
+                    pub y: i32,
+                }
+            "#;
+        test_format_item(test_src, "reinterpret_cast", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 6);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 1);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
-                                           == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
-                                           == 2);
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) reinterpret_cast_ final { ... };
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_struct_with_explicit_padding_in_generated_code() {
+    fn test_format_item_struct_with_unsupported_field_type() {
         let test_src = r#"
                 pub struct SomeStruct {
-                    pub f1: u8,
-                    pub f2: u32,
+                    pub successful_field: i32,
+                    pub unsupported_field: Option<[i32; 3]>,
                 }
-                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 8);
-                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 4);
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
+            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
+                                    Generic types are not supported yet (b/259749095)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(4) SomeStruct final {
+                    struct ... SomeStruct final {
                         ...
-                        std::uint32_t f2;
-                        std::uint8_t f1;
-                        unsigned char __padding0[3];
-                        inline static void __crubit_field_offset_assertions();
+                        private:
+                            __COMMENT__ #broken_field_msg
+                            unsigned char unsupported_field[16];
+                            std::int32_t successful_field;
+                            inline static void __crubit_field_offset_assertions();
                     };
+                    ...
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(sizeof(SomeStruct) == 20, ...);
                     static_assert(alignof(SomeStruct) == 4, ...);
                     inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, f2));
-                      static_assert(4 == offsetof(SomeStruct, f1));
+                      static_assert(0 == offsetof(SomeStruct, unsupported_field));
+                      static_assert(16 == offsetof(SomeStruct, successful_field));
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 20);
                     const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f2) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f1) == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
+                                                                 unsupported_field) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
+                                                                 successful_field) == 16);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_static_method() {
+    fn test_format_item_struct_with_nonnull_field() {
         let test_src = r#"
-                /// No-op `f32` placeholder is used, because ZSTs are not supported
-                /// (b/258259459).
-                pub struct Math(f32);
-
-                impl Math {
-                    pub fn add_i32(x: f32, y: f32) -> f32 {
-                        x + y
-                    }
+                use std::ptr::NonNull;
+                pub struct SomeStruct {
+                    pub ptr: NonNull<i32>,
                 }
             "#;
-        test_format_item(test_src, "Math", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct ... Math final {
-                        ...
-                        public:
-                          ...
-                          static inline float add_i32(float x, float y);
+                    struct ... SomeStruct final {
                         ...
+                        private:
+                            ... rs_std::NonNull<std::int32_t> ptr;
+                            inline static void __crubit_field_offset_assertions();
                     };
                 }
             );
             assert_cc_matches!(
-                impl_details.cc.tokens,
-                quote! {
-                    namespace __crubit_internal {
-                    extern "C" float ... (float x, float y);
-                    }
-                    inline float Math::add_i32(float x, float y) {
-                      return __crubit_internal::...(x, y);
-                    }
-                }
-            );
-            assert_rs_matches!(
-                impl_details.rs,
+                format_cc_includes(&main_api.prereqs.includes),
                 quote! {
-                    #[no_mangle]
-                    extern "C" fn ...(x: f32, y: f32) -> f32 {
-                        ::rust_out::Math::add_i32(x, y)
-                    }
+                    include <cstdint> __NEWLINE__
+                    __HASH_TOKEN__ include "crubit/support/for/tests/rs_std/rs_nonnull.h"
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_static_method_with_generic_type_parameters() {
+    fn test_format_item_struct_with_boxed_fields() {
         let test_src = r#"
-                /// No-op `f32` placeholder is used, because ZSTs are not supported
-                /// (b/258259459).
-                pub struct SomeStruct(f32);
-
-                impl SomeStruct {
-                    // To make this testcase distinct / non-overlapping wrt
-                    // test_format_item_static_method_with_generic_lifetime_parameters
-                    // `t` is taken by value below.
-                    pub fn generic_method<T: Clone>(t: T) -> T {
-                        t.clone()
-                    }
+                #[derive(Default)]
+                pub struct SomeStruct {
+                    pub boxed: Box<i32>,
+                    pub optional: Option<Box<i32>>,
+                    pub unsized_box: Box<str>,
                 }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::generic_method` \
-                                   defined at <crubit_unittests.rs>;l=10: \
-                                   Generic functions are not supported yet (b/259749023)";
+            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
+                                    Generic types are not supported yet (b/259749095)";
+            // Rust may reorder the fields, and therefore each field is verified separately.
+            let boxed_field_msg = "Owned by Rust (a `Box`): C++ code must not free or \
+                                   reassign the pointer - the pointee is dropped together with \
+                                   the struct.";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! { __COMMENT__ #boxed_field_msg std::int32_t* boxed; }
+            );
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! { __COMMENT__ #boxed_field_msg std::int32_t* optional; }
+            );
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
-                    };
-                    ...
+                    __COMMENT__ #broken_field_msg
+                    unsigned char unsized_box[16];
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::generic_method },);
-            assert_rs_not_matches!(impl_details.rs, quote! { generic_method },);
         });
     }
 
     #[test]
-    fn test_format_item_static_method_with_generic_lifetime_parameters() {
+    fn test_format_item_unsupported_struct_with_custom_drop_impl() {
         let test_src = r#"
-                /// No-op `f32` placeholder is used, because ZSTs are not supported
-                /// (b/258259459).
-                pub struct SomeStruct(f32);
+                pub struct StructWithCustomDropImpl {
+                    pub x: i32,
+                    pub y: i32,
+                }
 
-                impl SomeStruct {
-                    pub fn fn_taking_reference<'a>(x: &'a i32) -> i32 { *x }
+                impl Drop for StructWithCustomDropImpl {
+                    fn drop(&mut self) {}
                 }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::fn_taking_reference` \
-                                   defined at <crubit_unittests.rs>;l=7: \
-                                   Generic functions are not supported yet (b/259749023)";
-            assert_cc_matches!(
-                main_api.tokens,
-                quote! {
-                    ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
-                    };
-                    ...
-                }
-            );
-            assert_cc_not_matches!(
-                impl_details.cc.tokens,
-                quote! { SomeStruct::fn_taking_reference },
+        test_format_item(test_src, "StructWithCustomDropImpl", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "`Drop` trait and \"drop glue\" are only supported for types that implement \
+                 `Default` (b/258251148)"
             );
-            assert_rs_not_matches!(impl_details.rs, quote! { fn_taking_reference },);
         });
     }
 
     #[test]
-    fn test_format_item_method_taking_self_by_value() {
+    fn test_format_item_unsupported_struct_with_custom_drop_glue() {
         let test_src = r#"
-                pub struct SomeStruct(f32);
+                #![allow(dead_code)]
 
-                impl SomeStruct {
-                    pub fn into_f32(self) -> f32 {
-                        self.0
+                // `i32` is present to avoid hitting the ZST checks related to (b/258259459)
+                struct StructWithCustomDropImpl(i32);
+
+                impl Drop for StructWithCustomDropImpl {
+                    fn drop(&mut self) {
+                        println!("dropping!");
                     }
                 }
+
+                pub struct StructRequiringCustomDropGlue {
+                    field: StructWithCustomDropImpl,
+                }
+            "#;
+        test_format_item(test_src, "StructRequiringCustomDropGlue", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "`Drop` trait and \"drop glue\" are only supported for types that implement \
+                 `Default` (b/258251148)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_struct_with_custom_drop_impl_and_default() {
+        let test_src = r#"
+                #[derive(Default)]
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+
+                impl Drop for SomeStruct {
+                    fn drop(&mut self) {}
+                }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::into_f32` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   `self` parameter is not supported yet";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) SomeStruct final {
+                        public:
+                            ...
+                            SomeStruct(const SomeStruct&) = delete;
+
+                            // The move operations and the destructor are defined in
+                            // `impl_details` (by calling Rust thunks).
+                            SomeStruct(SomeStruct&&);
+                            SomeStruct& operator=(const SomeStruct&) = delete;
+                            SomeStruct& operator=(SomeStruct&&);
+                            ~SomeStruct();
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = true;
+                        private:
+                            ...
                     };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    ...
+                    namespace __crubit_internal {
+                        extern "C" void ...(SomeStruct* __self);
+                        extern "C" void ...(SomeStruct* __self, SomeStruct* __other);
+                        extern "C" void ...(SomeStruct* __self, SomeStruct* __other);
+                    }
+                    inline SomeStruct::~SomeStruct() {
+                        __crubit_internal::...(this);
+                    }
+                    inline SomeStruct::SomeStruct(SomeStruct&& other) {
+                        __crubit_internal::...(this, &other);
+                    }
+                    inline SomeStruct& SomeStruct::operator=(SomeStruct&& other) {
+                        if (this != &other) {
+                            __crubit_internal::...(this, &other);
+                        }
+                        return *this;
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
                     ...
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>) {
+                        unsafe { __self.assume_init_drop() };
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>,
+                        __other: &mut ::rust_out::SomeStruct,
+                    ) {
+                        __self.write(::core::mem::take(__other));
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::rust_out::SomeStruct,
+                        __other: &mut ::rust_out::SomeStruct
+                    ) {
+                        *__self = ::core::mem::take(__other);
+                    }
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::into_f32 },);
-            assert_rs_not_matches!(impl_details.rs, quote! { into_f32 },);
         });
     }
 
     #[test]
-    fn test_format_item_method_taking_self_by_const_ref() {
+    fn test_format_item_struct_with_cc_builder() {
         let test_src = r#"
-                pub struct SomeStruct(f32);
-
-                impl SomeStruct {
-                    pub fn get_f32(&self) -> f32 {
-                        self.0
-                    }
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::cc_builder]
+                #[derive(Default)]
+                pub struct SomeStruct {
+                    pub x: i32,
+                    pub y: f64,
                 }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::get_f32` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   Generic functions are not supported yet (b/259749023)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
                     struct ... SomeStruct final {
                         ...
-                        __COMMENT__ #unsupported_msg
-                        ...
+                        private:
+                            friend class SomeStructBuilder;
+                            ...
                     };
                     ...
+                    class SomeStructBuilder final {
+                        public:
+                            SomeStructBuilder();
+                            SomeStructBuilder& set_x(std::int32_t x) & {
+                                value_.x = x;
+                                return *this;
+                            }
+                            SomeStructBuilder&& set_x(std::int32_t x) && {
+                                value_.x = x;
+                                return std::move(*this);
+                            }
+                            SomeStructBuilder& set_y(double y) & {
+                                value_.y = y;
+                                return *this;
+                            }
+                            SomeStructBuilder&& set_y(double y) && {
+                                value_.y = y;
+                                return std::move(*this);
+                            }
+                            SomeStruct Build() && { return std::move(value_); }
+                        private:
+                            SomeStruct value_;
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void __crubit_thunk_default_...(SomeStruct* __ret_ptr);
+                    }
+                    inline SomeStructBuilder::SomeStructBuilder() : value_([] {
+                        crubit::ReturnValueSlot<SomeStruct> __ret_slot;
+                        __crubit_internal::__crubit_thunk_default_...(__ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }()) {}
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk_default_...(
+                        __ret_slot: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>
+                    ) {
+                        __ret_slot.write(
+                            <::rust_out::SomeStruct as ::core::default::Default>::default()
+                        );
+                    }
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::get_f32 },);
-            assert_rs_not_matches!(impl_details.rs, quote! { get_f32 },);
         });
     }
 
-    #[test]
-    fn test_format_item_method_taking_self_by_mutable_ref() {
-        let test_src = r#"
-                pub struct SomeStruct(f32);
-
-                impl SomeStruct {
-                    pub fn set_f32(&mut self, new_value: f32) {
-                        self.0 = new_value;
-                    }
+    #[test]
+    fn test_format_item_struct_with_cc_builder_and_private_field() {
+        let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::cc_builder]
+                #[derive(Default)]
+                pub struct SomeStruct {
+                    pub x: i32,
+                    y: i32,
                 }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::set_f32` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   Generic functions are not supported yet (b/259749023)";
+            let msg = "Error generating the `#[crubit::cc_builder]` builder of `SomeStruct`: \
+                       Field `y` is not public";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
                     struct ... SomeStruct final {
                         ...
-                        __COMMENT__ #unsupported_msg
-                        ...
                     };
-                    ...
+                    __COMMENT__ #msg
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::set_f32 },);
-            assert_rs_not_matches!(impl_details.rs, quote! { set_f32 },);
+            assert_cc_not_matches!(main_api.tokens, quote! { friend });
+            assert_rs_not_matches!(impl_details.rs, quote! { __crubit_thunk_default_ });
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_struct_with_name_that_is_reserved_keyword() {
+    fn test_format_item_struct_with_cc_builder_without_default_impl() {
         let test_src = r#"
-                #[allow(non_camel_case_types)]
-                pub struct reinterpret_cast {
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::cc_builder]
+                pub struct SomeStruct {
                     pub x: i32,
-                    pub y: i32,
                 }
             "#;
-        test_format_item(test_src, "reinterpret_cast", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error formatting item name: \
-                             `reinterpret_cast` is a C++ reserved keyword \
-                             and can't be used as a C++ identifier"
-            );
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let msg = "Error generating the `#[crubit::cc_builder]` builder of `SomeStruct`: \
+                       Builders are only supported for structs that implement `Default`";
+            assert_cc_matches!(main_api.tokens, quote! { __COMMENT__ #msg });
         });
     }
 
+    /// `#[non_exhaustive]` structs can't be constructed field-by-field outside
+    /// of their crate, and therefore their only C++ constructor (other than the
+    /// move constructor) is the `private` one that initializes the struct with
+    /// `Default::default()`.  The constructor is used by the
+    /// `#[crubit::cc_builder]` builder.
     #[test]
-    fn test_format_item_struct_with_unsupported_field_type() {
+    fn test_format_item_struct_with_non_exhaustive_attribute() {
         let test_src = r#"
+                #![feature(register_tool)]
+                #![register_tool(crubit_annotate)]
+                #[crubit_annotate::cc_builder]
+                #[derive(Default)]
+                #[non_exhaustive]
                 pub struct SomeStruct {
-                    pub successful_field: i32,
-                    pub unsupported_field: Option<[i32; 3]>,
+                    pub x: i32,
                 }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
-                                    Generic types are not supported yet (b/259749095)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
                     struct ... SomeStruct final {
-                        ...
                         private:
-                            __COMMENT__ #broken_field_msg
-                            unsigned char unsupported_field[16];
-                            std::int32_t successful_field;
-                            inline static void __crubit_field_offset_assertions();
+                            SomeStruct();
+                        public:
+                            SomeStruct(const SomeStruct&) = delete;
+                            SomeStruct(SomeStruct&&) = default;
+                            ...
+                        private:
+                            friend class SomeStructBuilder;
+                            ...
                     };
                     ...
+                    class SomeStructBuilder final {
+                        public:
+                            SomeStructBuilder();
+                            ...
+                    };
                 }
             );
+            assert_cc_not_matches!(main_api.tokens, quote! { public: SomeStruct() });
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 20, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, unsupported_field));
-                      static_assert(16 == offsetof(SomeStruct, successful_field));
+                    namespace __crubit_internal {
+                        extern "C" void __crubit_thunk_default_...(SomeStruct* __self);
                     }
+                    inline SomeStruct::SomeStruct() {
+                        __crubit_internal::__crubit_thunk_default_...(this);
+                    }
+                    ...
+                    inline SomeStructBuilder::SomeStructBuilder() : value_() {}
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 20);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
-                                                                 unsupported_field) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
-                                                                 successful_field) == 16);
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk_default_...(
+                        __self: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>
+                    ) {
+                        __self.write(
+                            <::rust_out::SomeStruct as ::core::default::Default>::default()
+                        );
+                    }
                 }
             );
         });
     }
 
+    /// Without a `Default` impl, a `#[non_exhaustive]` struct can only come
+    /// from Rust (e.g. from a factory function).
     #[test]
-    fn test_format_item_unsupported_struct_with_custom_drop_impl() {
+    fn test_format_item_struct_with_non_exhaustive_attribute_without_default_impl() {
         let test_src = r#"
-                pub struct StructWithCustomDropImpl {
+                #[non_exhaustive]
+                pub struct SomeStruct {
                     pub x: i32,
-                    pub y: i32,
                 }
 
-                impl Drop for StructWithCustomDropImpl {
-                    fn drop(&mut self) {}
-                }
+                pub fn create(x: i32) -> SomeStruct { SomeStruct { x } }
             "#;
-        test_format_item(test_src, "StructWithCustomDropImpl", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "`Drop` trait and \"drop glue\" are not supported yet (b/258251148)");
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    struct ... SomeStruct final {
+                        public:
+                            SomeStruct() = delete;
+                            ...
+                    };
+                }
+            );
+            assert_rs_not_matches!(impl_details.rs, quote! { __crubit_thunk_default_ });
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_struct_with_custom_drop_glue() {
+    fn test_format_item_fn_returning_struct_with_custom_drop_impl() {
         let test_src = r#"
-                #![allow(dead_code)]
+                #[derive(Default)]
+                pub struct SomeStruct(i32);
 
-                // `i32` is present to avoid hitting the ZST checks related to (b/258259459)
-                struct StructWithCustomDropImpl(i32);
+                impl Drop for SomeStruct {
+                    fn drop(&mut self) {}
+                }
 
-                impl Drop for StructWithCustomDropImpl {
-                    fn drop(&mut self) {
-                        println!("dropping!");
+                pub fn create(x: i32) -> SomeStruct { SomeStruct(x) }
+            "#;
+        test_format_item(test_src, "create", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline ::rust_out::SomeStruct create(std::int32_t x);
+                }
+            );
+            // The Rust thunk writes the return value into the `ReturnValueSlot`, and then
+            // `AssumeInitAndTakeValue` invokes the (thunk-backed) move constructor and
+            // destructor.
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(std::int32_t x, ::rust_out::SomeStruct* __ret_ptr);
+                    }
+                    inline ::rust_out::SomeStruct create(std::int32_t x) {
+                        crubit::ReturnValueSlot<::rust_out::SomeStruct> __ret_slot;
+                        __crubit_internal::...(x, __ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        x: i32,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>
+                    ) -> () {
+                        __ret_slot.write(::rust_out::create(x));
                     }
                 }
+            );
+        });
+    }
 
-                pub struct StructRequiringCustomDropGlue {
-                    field: StructWithCustomDropImpl,
+    /// `test_format_item_fn_taking_struct_with_custom_drop_impl_by_value` tests
+    /// that the thunk moves the argument out of the C++ object with `mem::take`
+    /// (the C++ destructor still runs for the moved-from object).
+    #[test]
+    fn test_format_item_fn_taking_struct_with_custom_drop_impl_by_value() {
+        let test_src = r#"
+                #[derive(Default)]
+                pub struct SomeStruct(i32);
+
+                impl Drop for SomeStruct {
+                    fn drop(&mut self) {}
                 }
+
+                pub fn consume(_s: SomeStruct) {}
             "#;
-        test_format_item(test_src, "StructRequiringCustomDropGlue", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "`Drop` trait and \"drop glue\" are not supported yet (b/258251148)");
+        test_format_item(test_src, "consume", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline void consume(::rust_out::SomeStruct _s);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(::rust_out::SomeStruct* _s);
+                    }
+                    ...
+                    inline void consume(::rust_out::SomeStruct _s) {
+                        return __crubit_internal::...(&_s);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(_s: &mut ::rust_out::SomeStruct) -> () {
+                        ::rust_out::consume(::core::mem::take(_s))
+                    }
+                }
+            );
         });
     }
 
@@ -3960,7 +13630,7 @@ pub mod tests {
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(1) SomeEnum final {
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(1) SomeEnum final {
                         public:
                             // In this test there is no `Default` implementation.
                             SomeEnum() = delete;
@@ -3978,6 +13648,8 @@ pub mod tests {
                             // In this test there is no custom `Drop`, so C++ can also
                             // just use the `default` destructor.
                             ~SomeEnum() = default;
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = true;
                         private:
                             __COMMENT__ #no_fields_msg
                             unsigned char __opaque_blob_of_bytes[1];
@@ -3988,84 +13660,308 @@ pub mod tests {
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeEnum) == 1, ...);
-                    static_assert(alignof(SomeEnum) == 1, ...);
+                    static_assert(sizeof(SomeEnum) == 1, ...);
+                    static_assert(alignof(SomeEnum) == 1, ...);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeEnum>() == 1);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeEnum>() == 1);
+                }
+            );
+        });
+    }
+
+    /// This is a test for an enum that has `EnumItemTuple` and `EnumItemStruct`
+    /// items. See also https://doc.rust-lang.org/reference/items/enumerations.html
+    #[test]
+    fn test_format_item_enum_with_tuple_and_struct_items() {
+        let test_src = r#"
+                pub enum Point {
+                    Cartesian(f32, f32),
+                    Polar{ dist: f32, angle: f32 },
+                }
+
+                const _: () = assert!(std::mem::size_of::<Point>() == 12);
+                const _: () = assert!(std::mem::align_of::<Point>() == 4);
+            "#;
+        test_format_item(test_src, "Point", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
+                                 No support for bindings of individual fields of \
+                                 `union` (b/272801632) or `enum`";
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) Point final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            Point() = delete;
+
+                            // In this test there is no `Copy` implementation / derive.
+                            Point(const Point&) = delete;
+
+                            // All Rust types are trivially-movable.
+                            Point(Point&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            Point& operator=(const Point&) = delete;
+                            Point& operator=(Point&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~Point() = default;
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = true;
+                        private:
+                            __COMMENT__ #no_fields_msg
+                            unsigned char __opaque_blob_of_bytes[12];
+                            inline static void __crubit_field_offset_assertions();
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(Point) == 12, ...);
+                    static_assert(alignof(Point) == 4, ...);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 12);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
+                }
+            );
+        });
+    }
+
+    /// This is a test for a fieldless enum with a primitive representation
+    /// (see `format_enum_class`).
+    #[test]
+    fn test_format_item_enum_with_primitive_repr() {
+        let test_src = r#"
+                #[repr(i16)]
+                pub enum SomeEnum {
+                    Negative = -1,
+                    Zero,
+                    Large = 1000,
+                }
+            "#;
+        test_format_item(test_src, "SomeEnum", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include <cstdint> __HASH_TOKEN__ include <optional> }
+            );
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    enum class [[clang::annotate("crubit_rust_type", "::rust_out::SomeEnum")]]
+                    SomeEnum : std::int16_t {
+                        Negative = -1,
+                        Zero = 0,
+                        Large = 1000,
+                    };
+                    ...
+                    constexpr std::optional<SomeEnum> SomeEnumFromInt(std::int16_t value) {
+                        switch (value) {
+                            case -1: return SomeEnum::Negative;
+                            case 0: return SomeEnum::Zero;
+                            case 1000: return SomeEnum::Large;
+                            default: return std::nullopt;
+                        }
+                    }
+                    ...
+                    constexpr std::int16_t SomeEnumToInt(SomeEnum value) {
+                        return static_cast<std::int16_t>(value);
+                    }
+                }
+            );
+            assert_cc_not_matches!(main_api.tokens, quote! { struct });
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(SomeEnum) == 2, ...);
+                    static_assert(alignof(SomeEnum) == 2, ...);
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeEnum>() == 1);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeEnum>() == 1);
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeEnum>() == 2);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeEnum>() == 2);
                 }
             );
         });
     }
 
-    /// This is a test for an enum that has `EnumItemTuple` and `EnumItemStruct`
-    /// items. See also https://doc.rust-lang.org/reference/items/enumerations.html
     #[test]
-    fn test_format_item_enum_with_tuple_and_struct_items() {
+    fn test_format_cc_discriminant() {
+        let format = |value: i128| format_cc_discriminant(value).to_string();
+        assert_eq!(format(0), "0");
+        assert_eq!(format(123), "123");
+        assert_eq!(format(-123), "- 123");
+        assert_eq!(format(i64::MIN.into()), "(- 9223372036854775807 - 1)");
+        assert_eq!(format(u64::MAX.into()), "0xffffffffffffffff");
+    }
+
+    /// Fieldless enums with a primitive representation are formatted as a C++
+    /// `enum class` - this test verifies how they are used by other items.
+    #[test]
+    fn test_generated_bindings_enum_with_primitive_repr() {
         let test_src = r#"
-                pub enum Point {
-                    Cartesian(f32, f32),
-                    Polar{ dist: f32, angle: f32 },
+                pub fn f(_color: *const Color) {}
+
+                #[repr(u8)]
+                pub enum Color {
+                    Red = 1,
+                    Green = 2,
                 }
 
-                const _: () = assert!(std::mem::size_of::<Point>() == 12);
-                const _: () = assert!(std::mem::align_of::<Point>() == 4);
+                impl Color {
+                    pub fn is_green(&self) -> bool {
+                        matches!(self, Color::Green)
+                    }
+                }
+
+                pub fn get_green() -> Color {
+                    Color::Green
+                }
             "#;
-        test_format_item(test_src, "Point", |result| {
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __HASH_TOKEN__ include <cstdint> ...
+                    enum class Color : std::uint8_t;
+                    ...
+                    void f(const ::rust_out::Color* _color);
+                    ...
+                    enum class [[clang::annotate("crubit_rust_type", ...)]]
+                    Color : std::uint8_t { ... };
+                    ...
+                    inline ::rust_out::Color get_green();
+                }
+            );
+            let unsupported_msg = "[CRUBIT-E0023] Error generating bindings for `Color::is_green` \
+                                   defined at <crubit_unittests.rs>;l=11: \
+                                   C++ `enum class`es can't have member functions\n\
+                                   Suggestion: Consider exposing a free function instead";
+            assert_cc_matches!(bindings.h_body, quote! { __COMMENT__ #unsupported_msg });
+        });
+    }
+
+    /// A `#[non_exhaustive]` enum is formatted as a C++ `class` (rather than
+    /// as a C++ `enum class`) - see `format_enum_class`.
+    #[test]
+    fn test_format_item_enum_with_primitive_repr_non_exhaustive() {
+        let test_src = r#"
+                #[repr(u8)]
+                #[non_exhaustive]
+                pub enum SomeEnum {
+                    A = 1,
+                    B = 2,
+                }
+            "#;
+        test_format_item(test_src, "SomeEnum", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
-                                 No support for bindings of individual fields of \
-                                 `union` (b/272801632) or `enum`";
-            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include <cstdint> __HASH_TOKEN__ include <optional> }
+            );
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct alignas(4) Point final {
+                    class [[clang::annotate("crubit_rust_type", "::rust_out::SomeEnum")]]
+                    SomeEnum final {
                         public:
-                            // In this test there is no `Default` implementation.
-                            Point() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            Point(const Point&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            Point(Point&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            Point& operator=(const Point&) = delete;
-                            Point& operator=(Point&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~Point() = default;
+                            static const SomeEnum A;
+                            static const SomeEnum B;
+                            ...
+                            static constexpr std::optional<SomeEnum> FromInt(std::uint8_t value) {
+                                switch (value) {
+                                    case 1: case 2: return SomeEnum(value);
+                                    default: return std::nullopt;
+                                }
+                            }
+                            ...
+                            constexpr std::uint8_t ToInt() const { return value_; }
+                            ...
+                            constexpr bool IsKnown() const { return FromInt(value_).has_value(); }
+                            ...
                         private:
-                            __COMMENT__ #no_fields_msg
-                            unsigned char __opaque_blob_of_bytes[12];
-                            inline static void __crubit_field_offset_assertions();
+                            explicit constexpr SomeEnum(std::uint8_t value) : value_(value) {}
+                            std::uint8_t value_;
                     };
+                    ...
+                    inline constexpr SomeEnum SomeEnum::A = SomeEnum(1);
+                    inline constexpr SomeEnum SomeEnum::B = SomeEnum(2);
                 }
             );
+            assert_cc_not_matches!(main_api.tokens, quote! { enum class });
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(Point) == 12, ...);
-                    static_assert(alignof(Point) == 4, ...);
+                    static_assert(sizeof(SomeEnum) == 1, ...);
+                    static_assert(alignof(SomeEnum) == 1, ...);
                 }
             );
-            assert_rs_matches!(
-                impl_details.rs,
+        });
+    }
+
+    #[test]
+    fn test_format_item_enum_with_primitive_repr_non_exhaustive_name_conflict() {
+        let test_src = r#"
+                #[repr(u8)]
+                #[non_exhaustive]
+                pub enum SomeEnum {
+                    ToInt = 1,
+                }
+            "#;
+        test_format_item(test_src, "SomeEnum", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "The name of the `ToInt` variant conflicts with a C++ member of `SomeEnum`"
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_enum_with_primitive_repr_non_exhaustive_fwd_decl() {
+        let test_src = r#"
+                pub fn f(_e: *const SomeEnum) {}
+
+                #[repr(u8)]
+                #[non_exhaustive]
+                pub enum SomeEnum {
+                    A = 1,
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 12);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
+                    class SomeEnum;
+                    ...
+                    void f(const ::rust_out::SomeEnum* _e);
                 }
             );
+            assert_cc_not_matches!(bindings.h_body, quote! { enum class });
         });
     }
 
@@ -4107,7 +14003,7 @@ pub mod tests {
                 main_api.tokens,
                 quote! {
                     ...
-                    union alignas(8) SomeUnion final {
+                    union [[clang::annotate("crubit_rust_type", ...)]] alignas(8) SomeUnion final {
                         public:
                             // In this test there is no `Default` implementation.
                             SomeUnion() = delete;
@@ -4125,6 +14021,8 @@ pub mod tests {
                             // In this test there is no custom `Drop`, so C++ can also
                             // just use the `default` destructor.
                             ~SomeUnion() = default;
+                            static constexpr bool kIsSend = true;
+                            static constexpr bool kIsSync = true;
                         private:
                             __COMMENT__ #no_fields_msg
                             unsigned char __opaque_blob_of_bytes[8];
@@ -4232,101 +14130,288 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_doc_comments_tuple_struct() {
+    fn test_format_item_doc_comments_tuple_struct() {
+        let test_src = r#"
+            /// Doc for some tuple struct.
+            pub struct SomeTupleStructWithDocs(i32);
+        "#;
+        test_format_item(test_src, "SomeTupleStructWithDocs", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let comment = " Doc for some tuple struct.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    struct ... SomeTupleStructWithDocs final {
+                        ...
+                    }
+                    ...
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_source_loc_macro_rules() {
+        let test_src = r#"
+            macro_rules! some_tuple_struct_macro_for_testing_source_loc {
+                () => {
+                    /// Some doc on SomeTupleStructMacroForTesingSourceLoc.
+                    pub struct SomeTupleStructMacroForTesingSourceLoc(i32);
+                };
+            }
+
+            some_tuple_struct_macro_for_testing_source_loc!();
+        "#;
+        test_format_item(test_src, "SomeTupleStructMacroForTesingSourceLoc", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let source_loc_comment = " Some doc on SomeTupleStructMacroForTesingSourceLoc.\n\n\
+                                      Generated from: <crubit_unittests.rs>;l=5";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #source_loc_comment
+                    struct ... SomeTupleStructMacroForTesingSourceLoc final {
+                        ...
+                    }
+                    ...
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_source_loc_with_no_doc_comment() {
+        let test_src = r#"
+            pub struct SomeTupleStructWithNoDocComment(i32);
+        "#;
+        test_format_item(test_src, "SomeTupleStructWithNoDocComment", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let comment = "Generated from: <crubit_unittests.rs>;l=2";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    struct ... SomeTupleStructWithNoDocComment final {
+                        ...
+                    }
+                    ...
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_static_value() {
+        let test_src = r#"
+                #[no_mangle]
+                pub static STATIC_VALUE: i32 = 42;
+            "#;
+        test_format_item(test_src, "STATIC_VALUE", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: static item");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_const_value() {
+        let test_src = r#"
+                pub const CONST_VALUE: i32 = 42;
+            "#;
+        test_format_item(test_src, "CONST_VALUE", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: constant item");
+        });
+    }
+
+    #[test]
+    fn test_format_item_str_const() {
+        let test_src = r#"
+                /// Doc comment of `VERSION`.
+                pub const VERSION: &str = "1.2.3 \"quoted\"\n\\ żółw";
+            "#;
+        test_format_item(test_src, "VERSION", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include <string_view> }
+            );
+            let doc_comment = " Doc comment of `VERSION`.\n\n\
+                               Generated from: <crubit_unittests.rs>;l=3";
+            let value = format_cc_string_literal("1.2.3 \"quoted\"\n\\ żółw").unwrap();
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comment
+                    inline constexpr std::string_view VERSION = #value;
+                }
+            );
+            assert_eq!(value.to_string(), r#""1.2.3 \"quoted\"\n\\ żółw""#);
+        });
+    }
+
+    #[test]
+    fn test_format_item_str_static() {
+        let test_src = r#"
+                pub static KEY: &'static str = concat!("some", "_", "key");
+            "#;
+        test_format_item(test_src, "KEY", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! { inline constexpr std::string_view KEY = "some_key"; }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_str_static_mut() {
+        let test_src = r#"
+                pub static mut KEY: &str = "some_key";
+            "#;
+        test_format_item(test_src, "KEY", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: static item");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_str_const_not_a_literal() {
+        let test_src = r#"
+                const PRIVATE: &str = "private";
+                pub const PUBLIC: &str = PRIVATE;
+            "#;
+        test_format_item(test_src, "PUBLIC", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Only string literals are supported as values of `&str` constants");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_str_const_with_control_character() {
+        let test_src = r#"
+                pub const BELL: &str = "\x07";
+            "#;
+        test_format_item(test_src, "BELL", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Unsupported control character: '\\u{7}'");
+        });
+    }
+
+    #[test]
+    fn test_format_item_thread_local_cell() {
         let test_src = r#"
-            /// Doc for some tuple struct.
-            pub struct SomeTupleStructWithDocs(i32);
-        "#;
-        test_format_item(test_src, "SomeTupleStructWithDocs", |result| {
+                use std::cell::Cell;
+
+                thread_local! {
+                    pub static COUNTER: Cell<u32> = Cell::new(0);
+                }
+            "#;
+        test_format_item(test_src, "COUNTER", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let comment = " Doc for some tuple struct.\n\n\
-                           Generated from: <crubit_unittests.rs>;l=3";
+            let comment = "Returns a pointer to the calling thread's instance of the \
+                           thread-local `COUNTER` static.  The pointer should not be shared \
+                           with other threads, and it is only valid until the calling thread \
+                           exits.";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     __COMMENT__ #comment
-                    struct ... SomeTupleStructWithDocs final {
-                        ...
+                    inline std::uint32_t* COUNTER();
+                }
+            );
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::uint32_t* __crubit_thunk_thread_local_...();
                     }
-                    ...
-                },
+                    inline std::uint32_t* COUNTER() {
+                        return __crubit_internal::__crubit_thunk_thread_local_...();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk_thread_local_...() -> *mut u32 {
+                        ::rust_out::COUNTER.with(|__value| __value.as_ptr())
+                    }
+                }
             );
         });
     }
 
     #[test]
-    fn test_format_item_source_loc_macro_rules() {
+    fn test_format_item_thread_local_without_cell() {
         let test_src = r#"
-            macro_rules! some_tuple_struct_macro_for_testing_source_loc {
-                () => {
-                    /// Some doc on SomeTupleStructMacroForTesingSourceLoc.
-                    pub struct SomeTupleStructMacroForTesingSourceLoc(i32);
-                };
-            }
-
-            some_tuple_struct_macro_for_testing_source_loc!();
-        "#;
-        test_format_item(test_src, "SomeTupleStructMacroForTesingSourceLoc", |result| {
+                thread_local! {
+                    pub static VALUE: i64 = 123;
+                }
+            "#;
+        test_format_item(test_src, "VALUE", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let source_loc_comment = " Some doc on SomeTupleStructMacroForTesingSourceLoc.\n\n\
-                                      Generated from: <crubit_unittests.rs>;l=5";
-            assert_cc_matches!(
-                main_api.tokens,
+            assert_cc_matches!(main_api.tokens, quote! { inline const std::int64_t* VALUE(); });
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    __COMMENT__ #source_loc_comment
-                    struct ... SomeTupleStructMacroForTesingSourceLoc final {
-                        ...
+                    extern "C" fn __crubit_thunk_thread_local_...() -> *const i64 {
+                        ::rust_out::VALUE.with(|__value| __value as *const _)
                     }
-                    ...
-                },
+                }
             );
         });
     }
 
     #[test]
-    fn test_format_item_source_loc_with_no_doc_comment() {
+    fn test_format_item_thread_local_attribute() {
         let test_src = r#"
-            pub struct SomeTupleStructWithNoDocComment(i32);
-        "#;
-        test_format_item(test_src, "SomeTupleStructWithNoDocComment", |result| {
+                #![feature(thread_local)]
+
+                #[thread_local]
+                pub static VALUE: i32 = 123;
+            "#;
+        test_format_item(test_src, "VALUE", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let comment = "Generated from: <crubit_unittests.rs>;l=2";
-            assert_cc_matches!(
-                main_api.tokens,
+            assert_cc_matches!(main_api.tokens, quote! { inline const std::int32_t* VALUE(); });
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    __COMMENT__ #comment
-                    struct ... SomeTupleStructWithNoDocComment final {
-                        ...
+                    extern "C" fn __crubit_thunk_thread_local_...() -> *const i32 {
+                        {
+                            let __value = &::rust_out::VALUE;
+                            __value as *const _
+                        }
                     }
-                    ...
-                },
+                }
             );
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_static_value() {
+    fn test_format_item_unsupported_thread_local_static_mut() {
         let test_src = r#"
-                #[no_mangle]
-                pub static STATIC_VALUE: i32 = 42;
-            "#;
-        test_format_item(test_src, "STATIC_VALUE", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: static item");
-        });
-    }
+                #![feature(thread_local)]
 
-    #[test]
-    fn test_format_item_unsupported_const_value() {
-        let test_src = r#"
-                pub const CONST_VALUE: i32 = 42;
+                #[thread_local]
+                pub static mut VALUE: i32 = 123;
             "#;
-        test_format_item(test_src, "CONST_VALUE", |result| {
+        test_format_item(test_src, "VALUE", |result| {
             let err = result.unwrap_err();
-            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: constant item");
+            assert_eq!(err, "`#[thread_local] static mut` is not supported");
         });
     }
 
@@ -4355,14 +14440,15 @@ pub mod tests {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             assert!(!main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::CONST_VALUE` \
+            let unsupported_msg = "[CRUBIT-E0017] Error generating bindings for \
+                                   `SomeStruct::CONST_VALUE` \
                                    defined at <crubit_unittests.rs>;l=5: \
                                    Unsupported `impl` item kind: Const";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(4) SomeStruct final {
+                    struct [[clang::annotate("crubit_rust_type", ...)]] alignas(4) SomeStruct final {
                         ...
                         __COMMENT__ #unsupported_msg
                         ...
@@ -4433,12 +14519,96 @@ pub mod tests {
             ("SomeStruct", ("::rust_out::SomeStruct", "", "SomeStruct", "")),
             ("SomeEnum", ("::rust_out::SomeEnum", "", "SomeEnum", "")),
             ("SomeUnion", ("::rust_out::SomeUnion", "", "SomeUnion", "")),
+            // `std::cmp::Ordering` is defined in the `core` crate - see the
+            // `crate_name_to_include_path` entry in `bindings_input_for_tests`.
+            (
+                "std::cmp::Ordering",
+                ("::core::cmp::Ordering", "\"crubit/test/core_cc_api.h\"", "", ""),
+            ),
             ("*const i32", ("const std::int32_t*", "<cstdint>", "", "")),
             ("*mut i32", ("std::int32_t*", "<cstdint>", "", "")),
             // `SomeStruct` is a `fwd_decls` prerequisite (not `defs` prerequisite):
             ("*mut SomeStruct", ("::rust_out::SomeStruct*", "", "", "SomeStruct")),
             // Testing propagation of deeper/nested `fwd_decls`:
             ("*mut *mut SomeStruct", (":: rust_out :: SomeStruct * *", "", "", "SomeStruct")),
+            (
+                "&'static [i32]",
+                (
+                    "rs_std::Slice<std::int32_t>",
+                    "\"crubit/support/for/tests/rs_std/rs_slice.h\"",
+                    "",
+                    "",
+                ),
+            ),
+            (
+                "&'static mut [i32]",
+                (
+                    "rs_std::MutSlice<std::int32_t>",
+                    "\"crubit/support/for/tests/rs_std/rs_slice.h\"",
+                    "",
+                    "",
+                ),
+            ),
+            // `SomeStruct` is a `fwd_decls` prerequisite (not `defs` prerequisite):
+            (
+                "&'static [SomeStruct]",
+                (
+                    "rs_std::Slice<::rust_out::SomeStruct>",
+                    "\"crubit/support/for/tests/rs_std/rs_slice.h\"",
+                    "",
+                    "SomeStruct",
+                ),
+            ),
+            (
+                "&'static str",
+                ("rs_std::Str", "\"crubit/support/for/tests/rs_std/rs_str.h\"", "", ""),
+            ),
+            (
+                "std::num::NonZeroU32",
+                (
+                    "rs_std::NonZero<std::uint32_t>",
+                    "\"crubit/support/for/tests/rs_std/rs_nonzero.h\"",
+                    "",
+                    "",
+                ),
+            ),
+            (
+                "std::num::NonZeroIsize",
+                (
+                    "rs_std::NonZero<std::intptr_t>",
+                    "\"crubit/support/for/tests/rs_std/rs_nonzero.h\"",
+                    "",
+                    "",
+                ),
+            ),
+            // `Option<NonZero...>` has the same layout as the underlying integer type:
+            ("Option<std::num::NonZeroU32>", ("std::uint32_t", "<cstdint>", "", "")),
+            (
+                "std::ptr::NonNull<i32>",
+                (
+                    "rs_std::NonNull<std::int32_t>",
+                    "\"crubit/support/for/tests/rs_std/rs_nonnull.h\"",
+                    "",
+                    "",
+                ),
+            ),
+            // `SomeStruct` is a `fwd_decls` prerequisite (not `defs` prerequisite):
+            (
+                "std::ptr::NonNull<SomeStruct>",
+                (
+                    "rs_std::NonNull<::rust_out::SomeStruct>",
+                    "\"crubit/support/for/tests/rs_std/rs_nonnull.h\"",
+                    "",
+                    "SomeStruct",
+                ),
+            ),
+            // Pointers to `MaybeUninit<T>` are pointers to storage for `T`:
+            ("*mut std::mem::MaybeUninit<i32>", ("std::int32_t*", "<cstdint>", "", "")),
+            ("&'static mut std::mem::MaybeUninit<i32>", ("std::int32_t*", "<cstdint>", "", "")),
+            (
+                "*const std::mem::MaybeUninit<SomeStruct>",
+                ("const ::rust_out::SomeStruct*", "", "", "SomeStruct"),
+            ),
             // Extra parens/sugar are expected to be ignored:
             ("(bool)", ("bool", "", "", "")),
         ];
@@ -4551,12 +14721,8 @@ pub mod tests {
                 "The following Rust type is not supported yet: [i32; 42]",
             ),
             (
-                "&'static [i32]", // TyKind::Slice (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static [i32]",
-            ),
-            (
-                "&'static str", // TyKind::Str (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static str",
+                "&'static mut str", // TyKind::Str (nested underneath TyKind::Ref)
+                "The following Rust type is not supported yet: &'static mut str",
             ),
             (
                 "impl Eq", // TyKind::Alias
@@ -4573,7 +14739,8 @@ pub mod tests {
             (
                 "StructWithCustomDrop",
                 "Failed to generate bindings for the definition of `StructWithCustomDrop`: \
-                 `Drop` trait and \"drop glue\" are not supported yet (b/258251148)"
+                 `Drop` trait and \"drop glue\" are only supported for types that implement \
+                 `Default` (b/258251148)"
             ),
             (
                 "ConstGenericStruct<42>",
@@ -4595,8 +14762,9 @@ pub mod tests {
                 "Generic types are not supported yet (b/259749095)",
             ),
             (
-                "std::cmp::Ordering",
-                "Cross-crate dependencies are not supported yet (b/258261328)",
+                "std::alloc::System",
+                "Type `std::alloc::System` comes from the `std` crate, \
+                 but no `--crate-header` was specified for this crate",
             ),
             (
                 "Option<i8>",
@@ -4612,9 +14780,9 @@ pub mod tests {
                 // `core::alloc::LayoutError` was a public re-export of
                 // `core::alloc::layout::LayoutError`:
                 // `https://play.rust-lang.org/?version=stable&mode=debug&edition=2021&gist=d2b5528af9b33b25abe44cc4646d65e3`
-                // TODO(b/258261328): Once cross-crate bindings are supported we should try
-                // to test them via a test crate that we control (rather than testing via
-                // implementation details of the std crate).
+                // TODO(b/258261328): We should try to test cross-crate bindings via a test
+                // crate that we control (rather than testing via implementation details of
+                // the std crate).
                 "core::alloc::LayoutError",
                 "Not directly public type (re-exports are not supported yet - b/262052635)",
             ),
@@ -4623,6 +14791,26 @@ pub mod tests {
                 "Failed to format the pointee of the pointer type `std::option::Option<i8>`: \
                  Generic types are not supported yet (b/259749095)",
             ),
+            (
+                "std::borrow::Cow<'static, str>",
+                "`std::borrow::Cow<'static, str>` is only supported as a type of function \
+                 parameters and return values",
+            ),
+            (
+                "String",
+                "`std::string::String` is only supported as a type of function parameters and \
+                 return values",
+            ),
+            (
+                "Vec<i32>",
+                "`std::vec::Vec<i32>` is only supported as a type of function parameters and \
+                 return values",
+            ),
+            (
+                "Box<i32>",
+                "`std::boxed::Box<i32>` is only supported as a type of function parameters and \
+                 return values",
+            ),
         ];
         let preamble = quote! {
             #![feature(never_type)]
@@ -4662,6 +14850,49 @@ pub mod tests {
         });
     }
 
+    #[test]
+    fn test_format_ty_for_cc_memoization() {
+        let test_src = r#"
+                pub fn foo(_x: i32, _y: (i32, i32)) {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = bindings_input_for_tests(tcx);
+            let def_id = find_def_id_by_name(tcx, "foo").to_def_id();
+            let sig =
+                rustc_compat::erase_late_bound_regions(tcx, rustc_compat::fn_sig(tcx, def_id));
+            let (i32_ty, tuple_ty) = (sig.inputs()[0], sig.inputs()[1]);
+
+            let first = format_ty_for_cc(&input, i32_ty).unwrap();
+            let second = format_ty_for_cc(&input, i32_ty).unwrap();
+            assert_eq!(first.tokens.to_string(), second.tokens.to_string());
+            assert_eq!(first.prereqs.includes, second.prereqs.includes);
+            assert!(input.interner.cc_tys.borrow().contains_key(&i32_ty));
+
+            // Errors are not memoized.
+            assert!(format_ty_for_cc(&input, tuple_ty).is_err());
+            assert!(!input.interner.cc_tys.borrow().contains_key(&tuple_ty));
+        });
+    }
+
+    #[test]
+    fn test_support_header_memoization() {
+        run_compiler_for_testing("", |tcx| {
+            let input = bindings_input_for_tests(tcx);
+            let first = input.support_header("rs_std/rs_char.h");
+            let second = input.support_header("rs_std/rs_char.h");
+            assert_eq!(
+                first,
+                CcInclude::user_header("crubit/support/for/tests/rs_std/rs_char.h".into())
+            );
+            match (first, second) {
+                (CcInclude::UserHeader(first), CcInclude::UserHeader(second)) => {
+                    assert!(Rc::ptr_eq(&first, &second));
+                }
+                _ => unreachable!(),
+            }
+        });
+    }
+
     #[test]
     fn test_format_ty_for_rs_successes() {
         // Test coverage for cases where `format_ty_for_rs` returns an `Ok(...)`.
@@ -4696,6 +14927,21 @@ pub mod tests {
             ("*mut i32", "*mut i32"),
             // Pointer to an ADT:
             ("*mut SomeStruct", "* mut :: rust_out :: SomeStruct"),
+            // Slice references:
+            ("&'static [i32]", "&'static [i32]"),
+            ("&'static mut [i32]", "&'static mut [i32]"),
+            ("&'static [SomeStruct]", "&'static [::rust_out::SomeStruct]"),
+            ("&'static str", "&'static str"),
+            // `NonZero...` types are defined in a private `core::num::nonzero` module:
+            ("std::num::NonZeroU32", "::core::num::NonZeroU32"),
+            ("Option<std::num::NonZeroU32>", "::core::option::Option<::core::num::NonZeroU32>"),
+            ("std::ptr::NonNull<i32>", "::core::ptr::NonNull<i32>"),
+            ("std::ptr::NonNull<SomeStruct>", "::core::ptr::NonNull<::rust_out::SomeStruct>"),
+            ("*mut std::mem::MaybeUninit<i32>", "*mut ::core::mem::MaybeUninit<i32>"),
+            (
+                "&'static mut std::mem::MaybeUninit<SomeStruct>",
+                "&'static mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>",
+            ),
         ];
         let preamble = quote! {
             #![feature(never_type)]
@@ -4714,7 +14960,8 @@ pub mod tests {
             }
         };
         test_ty(&testcases, preamble, |desc, tcx, ty, expected_tokens| {
-            let actual_tokens = format_ty_for_rs(tcx, ty).unwrap().to_string();
+            let actual_tokens =
+                format_ty_for_rs(&bindings_input_for_tests(tcx), ty).unwrap().to_string();
             let expected_tokens = expected_tokens.parse::<TokenStream>().unwrap().to_string();
             assert_eq!(actual_tokens, expected_tokens, "{desc}");
         });
@@ -4739,12 +14986,8 @@ pub mod tests {
                 "The following Rust type is not supported yet: [i32; 42]",
             ),
             (
-                "&'static [i32]", // TyKind::Slice (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static [i32]",
-            ),
-            (
-                "&'static str", // TyKind::Str (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static str",
+                "&'static mut str", // TyKind::Str (nested underneath TyKind::Ref)
+                "The following Rust type is not supported yet: &'static mut str",
             ),
             (
                 "impl Eq", // TyKind::Alias
@@ -4761,12 +15004,336 @@ pub mod tests {
         ];
         let preamble = quote! {};
         test_ty(&testcases, preamble, |desc, tcx, ty, expected_err| {
-            let anyhow_err = format_ty_for_rs(tcx, ty).unwrap_err();
+            let anyhow_err = format_ty_for_rs(&bindings_input_for_tests(tcx), ty).unwrap_err();
             let actual_err = format!("{anyhow_err:#}");
             assert_eq!(&actual_err, *expected_err, "{desc}");
         });
     }
 
+    #[test]
+    fn test_generate_ir() {
+        let test_src = r#"
+                pub fn add(x: i32, y: i32) -> i32 { x + y }
+
+                pub struct SomeStruct {
+                    pub field: u8,
+                    private_field: u8,
+                }
+
+                impl SomeStruct {
+                    pub fn create() -> Self { Self { field: 0, private_field: 0 } }
+                }
+
+                pub fn unsupported_fn(_x: (i32, i32)) {}
+
+                fn private_fn() {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let ir = generate_ir(&bindings_input_for_tests(tcx));
+            assert_eq!(ir.crate_name, "rust_out");
+            assert_eq!(ir.items.len(), 4);
+
+            let ApiItem::Func(add) = &ir.items[0] else { panic!("Expected a function") };
+            assert_eq!(add.rs_path, "add");
+            assert_eq!(add.cc_name, "::rust_out::add");
+            assert_eq!(add.params.len(), 2);
+            assert_eq!(add.params[0].name, "x");
+            assert_eq!(add.params[0].ty.rs, "i32");
+            assert_eq!(add.params[0].ty.cc, "std::int32_t");
+            assert_eq!(add.ret_type.cc, "std::int32_t");
+
+            let ApiItem::Adt(adt) = &ir.items[1] else { panic!("Expected an ADT") };
+            assert_eq!(adt.rs_path, "SomeStruct");
+            assert_eq!(adt.cc_name, "::rust_out::SomeStruct");
+            assert_eq!(adt.cc_keyword, "struct");
+            assert_eq!(adt.size_in_bytes, 2);
+            assert_eq!(adt.alignment_in_bytes, 1);
+            assert_eq!(adt.fields.len(), 1);
+            assert_eq!(adt.fields[0].name, "field");
+            assert_eq!(adt.fields[0].ty.cc, "std::uint8_t");
+
+            let ApiItem::Func(method) = &ir.items[2] else { panic!("Expected a function") };
+            assert_eq!(method.cc_name, "::rust_out::SomeStruct::create");
+            assert!(method.params.is_empty());
+            assert_eq!(method.ret_type.cc, "::rust_out::SomeStruct");
+
+            let ApiItem::Unsupported(unsupported) = &ir.items[3] else {
+                panic!("Expected an unsupported item")
+            };
+            assert_eq!(unsupported.rs_path, "unsupported_fn");
+            assert_eq!(
+                unsupported.error,
+                "Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+
+            let json = serde_json::to_value(&ir).unwrap();
+            assert_eq!(json["items"][0]["kind"], "func");
+            assert_eq!(json["items"][1]["kind"], "adt");
+            assert_eq!(json["items"][3]["kind"], "unsupported");
+        });
+    }
+
+    #[test]
+    fn test_generate_item_ir_recorded_by_generate_bindings() {
+        let test_src = r#"
+                pub fn public_function() {}
+                pub fn unsupported_fn(_x: (i32, i32)) {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = bindings_input_for_tests(tcx);
+            let def_id = find_def_id_by_name(tcx, "public_function");
+            let unsupported_def_id = find_def_id_by_name(tcx, "unsupported_fn");
+            generate_bindings(&input).unwrap();
+            assert!(input.interner.items.borrow().contains_key(&def_id));
+            assert!(input.interner.item_irs.borrow().contains_key(&def_id));
+
+            // The IR is reused (rather than built by formatting the items again).
+            input.interner.items.borrow_mut().clear();
+            let Ok(Some(ApiItem::Func(func))) = generate_item_ir(&input, def_id) else {
+                panic!("Expected a function")
+            };
+            assert_eq!(func.cc_name, "::rust_out::public_function");
+            let err = generate_item_ir(&input, unsupported_def_id).unwrap_err();
+            assert_eq!(
+                format!("{err:#}"),
+                "Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+            assert!(input.interner.items.borrow().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_generate_item_ir_unsupported_hir_node() {
+        let test_src = r#"
+                pub struct SomeStruct {
+                    pub field: i32,
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let struct_def_id = find_def_id_by_name(tcx, "SomeStruct");
+            let field_def_id =
+                tcx.adt_def(struct_def_id).all_fields().next().unwrap().did.expect_local();
+            let err = generate_item_ir(&bindings_input_for_tests(tcx), field_def_id).unwrap_err();
+            let msg = format!("{err:#}");
+            assert!(msg.starts_with("Unsupported HIR node kind: Field("), "{msg}");
+        });
+    }
+
+    #[test]
+    fn test_generate_error_report() {
+        let test_src = r#"
+                pub fn tuple_fn(_x: (i32, i32)) {}
+
+                pub fn supported_fn() {}
+
+                pub const SOME_CONST: i32 = 123;
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let report = generate_error_report(&bindings_input_for_tests(tcx));
+            assert_eq!(report.crate_name, "rust_out");
+            assert_eq!(report.errors.len(), 2);
+
+            let tuple_fn = &report.errors[0];
+            assert_eq!(tuple_fn.def_path, "tuple_fn");
+            assert_eq!(tuple_fn.item_kind, "function");
+            assert_eq!(tuple_fn.span.file, "<crubit_unittests.rs>");
+            assert_eq!(tuple_fn.span.line, 2);
+            assert_eq!(tuple_fn.span.column, 17);
+            assert_eq!(tuple_fn.error_kind, ErrorKind::NotYetImplemented);
+            assert_eq!(tuple_fn.code, ErrorCode::Tuple);
+            assert_eq!(
+                tuple_fn.message,
+                "Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+            assert_eq!(tuple_fn.tracking_bug.as_deref(), Some("b/254099023"));
+            assert_eq!(tuple_fn.suggestion, Some("Consider using a struct instead of a tuple"));
+
+            let some_const = &report.errors[1];
+            assert_eq!(some_const.def_path, "SOME_CONST");
+            assert_eq!(some_const.item_kind, "constant");
+            assert_eq!(some_const.span.line, 6);
+            assert_eq!(some_const.error_kind, ErrorKind::Unsupported);
+            assert_eq!(some_const.code, ErrorCode::UnsupportedItemKind);
+            assert_eq!(some_const.tracking_bug, None);
+            assert_eq!(some_const.suggestion, None);
+
+            let json = serde_json::to_value(&report).unwrap();
+            assert_eq!(json["errors"][0]["error_kind"], "not_yet_implemented");
+            assert_eq!(json["errors"][0]["code"], "CRUBIT-E0003");
+            assert_eq!(json["errors"][1]["error_kind"], "unsupported");
+            assert_eq!(json["errors"][1]["code"], "CRUBIT-E0017");
+        });
+    }
+
+    #[test]
+    fn test_generate_error_report_groups() {
+        let test_src = r#"
+                pub fn tuple_fn(_x: (i32, i32)) {}
+
+                pub const SOME_CONST: i32 = 123;
+
+                pub fn other_tuple_fn() -> (i32, i32) { (1, 2) }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let report = generate_error_report(&bindings_input_for_tests(tcx));
+            assert_eq!(report.errors.len(), 3);
+            assert_eq!(
+                report.errors[0].root_cause,
+                "Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+
+            // The most common root cause comes first.
+            assert_eq!(report.groups.len(), 2);
+            assert_eq!(report.groups[0].root_cause, report.errors[0].root_cause);
+            assert_eq!(report.groups[0].def_paths, ["tuple_fn", "other_tuple_fn"]);
+            assert_eq!(report.groups[0].code, ErrorCode::Tuple);
+            assert_eq!(report.groups[1].root_cause, report.errors[1].root_cause);
+            assert_eq!(report.groups[1].def_paths, ["SOME_CONST"]);
+
+            let json = serde_json::to_value(&report).unwrap();
+            assert_eq!(json["groups"][0]["def_paths"][1], "other_tuple_fn");
+        });
+    }
+
+    #[test]
+    fn test_generate_coverage_report() {
+        let test_src = r#"
+                pub fn tuple_fn(_x: (i32, i32)) {}
+
+                pub fn add(x: i32, y: i32) -> i32 { x + y }
+
+                pub struct SomeStruct {
+                    pub field: i32,
+                }
+
+                pub mod some_module {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let report = generate_coverage_report(&bindings_input_for_tests(tcx));
+            assert_eq!(report.crate_name, "rust_out");
+            assert_eq!(report.items.len(), 3);
+
+            let tuple_fn = &report.items[0];
+            assert_eq!(tuple_fn.rs_path, "tuple_fn");
+            assert_eq!(tuple_fn.item_kind, "function");
+            assert_eq!(tuple_fn.span.line, 2);
+            let err = tuple_fn.cc_signature.as_ref().unwrap_err();
+            assert_eq!(err.code, ErrorCode::Tuple);
+            assert_eq!(
+                err.message,
+                "Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)"
+            );
+
+            let add = &report.items[1];
+            assert_eq!(add.rs_path, "add");
+            assert_eq!(add.span.line, 4);
+            assert_eq!(
+                add.cc_signature.as_deref().unwrap(),
+                "std::int32_t ::rust_out::add(std::int32_t x, std::int32_t y)"
+            );
+
+            let some_struct = &report.items[2];
+            assert_eq!(some_struct.rs_path, "SomeStruct");
+            assert_eq!(some_struct.item_kind, "struct");
+            assert_eq!(
+                some_struct.cc_signature.as_deref().unwrap(),
+                "struct ::rust_out::SomeStruct"
+            );
+        });
+    }
+
+    #[test]
+    fn test_generate_stats() {
+        let test_src = r#"
+                pub fn tuple_fn1(_x: (i32, i32)) {}
+                pub fn tuple_fn2() -> (i32, i32) { (1, 2) }
+
+                pub const SOME_CONST: i32 = 123;
+
+                pub fn supported_fn() {}
+                pub struct SupportedStruct(i32);
+                pub mod some_module {}
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let stats = generate_stats(&bindings_input_for_tests(tcx));
+            assert_eq!(stats.crate_name, "rust_out");
+            assert_eq!(stats.bound_items, 2);
+            assert_eq!(stats.unsupported_items, 3);
+            assert_eq!(stats.unsupported_reasons.len(), 2);
+
+            let tuples = &stats.unsupported_reasons[0];
+            assert_eq!(tuples.reason, "Tuples are not supported yet");
+            assert_eq!(tuples.error_kind, ErrorKind::NotYetImplemented);
+            assert_eq!(tuples.code, ErrorCode::Tuple);
+            assert_eq!(tuples.tracking_bug.as_deref(), Some("b/254099023"));
+            assert_eq!(tuples.count, 2);
+
+            let item_kind = &stats.unsupported_reasons[1];
+            assert_eq!(item_kind.reason, "Unsupported rustc_hir::hir::ItemKind");
+            assert_eq!(item_kind.error_kind, ErrorKind::Unsupported);
+            assert_eq!(item_kind.code, ErrorCode::UnsupportedItemKind);
+            assert_eq!(item_kind.tracking_bug, None);
+            assert_eq!(item_kind.count, 1);
+        });
+    }
+
+    #[test]
+    fn test_matches_path_pattern() {
+        assert!(matches_path_pattern("foo", "foo"));
+        assert!(!matches_path_pattern("foo", "foo::bar"));
+        assert!(matches_path_pattern("foo::*", "foo::bar"));
+        assert!(matches_path_pattern("foo::*", "foo::bar::baz"));
+        assert!(!matches_path_pattern("foo::*", "foobar"));
+        assert!(matches_path_pattern("*", ""));
+        assert!(matches_path_pattern("*::internal::*", "foo::internal::bar"));
+        assert!(!matches_path_pattern("*::internal::*", "internal::bar"));
+        assert!(matches_path_pattern("*_impl", "foo::bar_impl"));
+        assert!(!matches_path_pattern("a*ba", "aba"));
+    }
+
+    #[test]
+    fn test_get_tracking_bug() {
+        assert_eq!(get_tracking_bug("Foo (b/123) and (b/456)").as_deref(), Some("b/123"));
+        assert_eq!(get_tracking_bug("Error in lib/123.rs (b/456)").as_deref(), Some("b/456"));
+        assert_eq!(get_tracking_bug("Foo (b/) bar"), None);
+        assert_eq!(get_tracking_bug("No bug here"), None);
+    }
+
+    #[test]
+    fn test_error_code_from_error() {
+        let err = coded_anyhow!(ErrorCode::Tuple, "Tuples are not supported yet: (i32, i32)");
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::Tuple);
+
+        // The code survives adding context to the error.
+        let err = err.context("Error handling parameter #0");
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::Tuple);
+        assert_eq!(
+            format!("{err:#}"),
+            "Error handling parameter #0: Tuples are not supported yet: (i32, i32)"
+        );
+
+        // The innermost code wins.
+        let err = anyhow::Error::new(CodedError {
+            code: ErrorCode::GenericType,
+            message: "Generic types are not supported yet".to_string(),
+        })
+        .context(CodedError {
+            code: ErrorCode::GenericFunction,
+            message: "Generic functions are not supported yet".to_string(),
+        });
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::GenericType);
+
+        // The message of an error doesn't affect its classification.
+        let err = anyhow!("Tuples are not supported yet: (i32, i32)");
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::Other);
+        assert_eq!(ErrorCode::Tuple.to_string(), "CRUBIT-E0003");
+        assert_eq!(ErrorCode::Other.suggestion(), None);
+    }
+
     fn test_ty<TestFn, Expectation>(
         testcases: &[(&str, Expectation)],
         preamble: TokenStream,
@@ -4792,12 +15359,8 @@ pub mod tests {
             };
             run_compiler_for_testing(input, |tcx| {
                 let def_id = find_def_id_by_name(tcx, "test_function");
-                let ty = tcx
-                    .fn_sig(def_id.to_def_id())
-                    .subst_identity()
-                    .no_bound_vars()
-                    .unwrap()
-                    .output();
+                let ty =
+                    rustc_compat::fn_sig(tcx, def_id.to_def_id()).no_bound_vars().unwrap().output();
                 test_fn(&desc, tcx, ty, expected);
             });
         }
@@ -4831,6 +15394,7 @@ pub mod tests {
                 acc.cc.prereqs += snippet.cc.prereqs.clone();
                 acc.cc.tokens.extend(snippet.cc.tokens.clone());
                 acc.rs.extend(snippet.rs.clone());
+                acc.cc_out.extend(snippet.cc_out.clone());
                 acc
             })
     }
@@ -4893,8 +15457,34 @@ pub mod tests {
         Input {
             tcx,
             crubit_support_path: "crubit/support/for/tests".into(),
-            _features: (),
-            _crate_to_include_map: (),
+            crubit_features: flagset::FlagSet::<CrubitFeature>::full(),
+            crate_name_to_include_path: [(
+                "core".into(),
+                CcInclude::user_header("crubit/test/core_cc_api.h".into()),
+            )]
+            .into_iter()
+            .collect(),
+            split_headers: None,
+            crate_name_to_namespace: HashMap::new(),
+            feature_guard_macros: HashMap::new(),
+            include_guard: IncludeGuard::PragmaOnce,
+            inline_namespace: None,
+            bind_only: vec![],
+            skip: vec![],
+            rename_map: HashMap::new(),
+            type_map: HashMap::new(),
+            c_mode: false,
+            cc_std: CcStandard::Cxx17,
+            fn_naming: FnNaming::default(),
+            unsafe_fn_marker: UnsafeFnMarker::default(),
+            emit_line_directives: false,
+            emit_abi_stamp: false,
+            remap_path_prefixes: vec![],
+            rust_std_lib: detect_rust_std_lib(tcx),
+            crate_linkage: detect_crate_linkage(tcx),
+            cc_out_h_include: None,
+            snippet_cache: None,
+            interner: Default::default(),
         }
     }
 
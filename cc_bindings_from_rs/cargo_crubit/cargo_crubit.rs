@@ -0,0 +1,296 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! `cargo crubit` generates C++ bindings for the library crates of a Cargo
+//! workspace.
+//!
+//! The tool runs `cargo build` with itself registered as
+//! `RUSTC_WORKSPACE_WRAPPER`.  This way Cargo resolves the workspace and
+//! computes the `rustc` cmdline of every crate (including `--extern` flags for
+//! the dependencies).  When invoked as the wrapper, the tool first runs the
+//! real `rustc` and then runs `cc_bindings_from_rs` with the same `rustc` args
+//! for each library crate of the workspace.  The generated files end up in
+//! `target/crubit/`:
+//! - `<crate_name>_cc_api.h` - the C++ header with the bindings,
+//! - `<crate_name>_cc_api_impl.rs` - Rust thunks that need to be compiled and
+//!   linked together with the crate.
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the environment variable that tells `cargo-crubit` that it is
+/// invoked by Cargo as `RUSTC_WORKSPACE_WRAPPER`.  The value is the
+/// JSON-serialized `WrapperConfig`.
+const WRAPPER_CONFIG_ENV_VAR: &str = "CARGO_CRUBIT_WRAPPER_CONFIG";
+
+#[derive(Debug, Parser)]
+#[clap(name = "cargo-crubit")]
+#[clap(about = "Generates C++ bindings for the library crates of a Cargo workspace")]
+struct Cmdline {
+    /// Path to the `cc_bindings_from_rs` executable.
+    #[clap(long, value_parser, value_name = "FILE", default_value = "cc_bindings_from_rs")]
+    cc_bindings_from_rs_path: PathBuf,
+
+    /// Path to the `crubit/support` directory in a format that should be used
+    /// in the `#include` directives inside the generated C++ files.
+    #[clap(long, value_parser, value_name = "STRING", default_value = "crubit/support")]
+    crubit_support_path: String,
+
+    /// Path to a rustfmt executable that will be used to format the Rust
+    /// source files generated by the tool.
+    #[clap(long, value_parser, value_name = "FILE", default_value = "rustfmt")]
+    rustfmt_exe_path: PathBuf,
+
+    /// Path to a clang-format executable that will be used to format the C++
+    /// header files generated by the tool.  If not specified, then a built-in
+    /// formatter is used instead.
+    #[clap(long, value_parser, value_name = "FILE")]
+    clang_format_exe_path: Option<PathBuf>,
+
+    /// Command line arguments of `cargo build` (e.g. `--release`, or
+    /// `--package=foo`).
+    #[clap(last = true, value_parser)]
+    cargo_args: Vec<String>,
+}
+
+/// Configuration passed from the `cargo crubit` invocation to the
+/// `RUSTC_WORKSPACE_WRAPPER` invocations (via `WRAPPER_CONFIG_ENV_VAR`).
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+struct WrapperConfig {
+    cc_bindings_from_rs_path: PathBuf,
+    crubit_support_path: String,
+    rustfmt_exe_path: PathBuf,
+    clang_format_exe_path: Option<PathBuf>,
+
+    /// Output directory for the generated files (i.e. `target/crubit/`).
+    out_dir: PathBuf,
+
+    /// Sysroot of the Rust toolchain used by Cargo.  `cc_bindings_from_rs`
+    /// needs an explicit `--sysroot`, because it is not installed as part of
+    /// the toolchain.
+    sysroot: PathBuf,
+}
+
+/// Returns the values of the `flag` in `rustc_args` - e.g. `["lib"]` for
+/// `flag` = `--crate-type` and `rustc_args` = `["--crate-type", "lib"]` (or
+/// `["--crate-type=lib"]`).
+fn get_flag_values<'a>(rustc_args: &'a [String], flag: &str) -> Vec<&'a str> {
+    let mut result = vec![];
+    let mut iter = rustc_args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == flag {
+            result.extend(iter.next().map(String::as_str));
+        } else if let Some(value) = arg.strip_prefix(flag).and_then(|s| s.strip_prefix('=')) {
+            result.push(value);
+        }
+    }
+    result
+}
+
+/// Returns the name of the crate compiled by `rustc_args` if `rustc_args`
+/// compile a library crate that should get C++ bindings.  Returns `None` for
+/// other `rustc` invocations (e.g. compiling binaries, tests, build scripts,
+/// and procedural macros, or Cargo querying `rustc` for the target info).
+fn get_lib_crate_name(rustc_args: &[String]) -> Option<&str> {
+    if rustc_args.iter().any(|arg| arg == "--test" || arg.starts_with("--print")) {
+        return None;
+    }
+    let crate_types = get_flag_values(rustc_args, "--crate-type");
+    if !crate_types.iter().any(|&crate_type| crate_type == "lib" || crate_type == "rlib") {
+        return None;
+    }
+    get_flag_values(rustc_args, "--crate-name").into_iter().next()
+}
+
+/// Returns the `--crate-header` flags of `cc_bindings_from_rs` for the
+/// dependencies (from the `--extern` flags in `rustc_args`), that already got
+/// C++ bindings in `out_dir`.  Cargo builds the dependencies first, so this
+/// includes all the dependencies from the workspace.
+fn get_crate_header_args(rustc_args: &[String], out_dir: &Path) -> Vec<String> {
+    get_flag_values(rustc_args, "--extern")
+        .into_iter()
+        .map(|extern_arg| extern_arg.split_once('=').map_or(extern_arg, |(name, _path)| name))
+        .filter(|crate_name| out_dir.join(format!("{crate_name}_cc_api.h")).exists())
+        .map(|crate_name| format!("--crate-header={crate_name}={crate_name}_cc_api.h"))
+        .collect()
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let status =
+        command.status().with_context(|| format!("Failed to spawn {:?}", command.get_program()))?;
+    ensure!(status.success(), "{:?} failed: {status}", command.get_program());
+    Ok(())
+}
+
+fn run_and_read_stdout(command: &mut Command) -> Result<String> {
+    let output =
+        command.output().with_context(|| format!("Failed to spawn {:?}", command.get_program()))?;
+    ensure!(
+        output.status.success(),
+        "{:?} failed: {}",
+        command.get_program(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Implements `cargo crubit`: runs `cargo build` with `cargo-crubit` as
+/// `RUSTC_WORKSPACE_WRAPPER`.
+fn run_cargo(cmdline: Cmdline) -> Result<()> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+
+    let metadata = run_and_read_stdout(Command::new(&cargo).args([
+        "metadata",
+        "--format-version=1",
+        "--no-deps",
+    ]))?;
+    let metadata: serde_json::Value = serde_json::from_str(&metadata)?;
+    let Some(target_dir) = metadata["target_directory"].as_str() else {
+        bail!("`cargo metadata` didn't report the `target_directory`");
+    };
+    let out_dir = Path::new(target_dir).join("crubit");
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let config = WrapperConfig {
+        cc_bindings_from_rs_path: cmdline.cc_bindings_from_rs_path,
+        crubit_support_path: cmdline.crubit_support_path,
+        rustfmt_exe_path: cmdline.rustfmt_exe_path,
+        clang_format_exe_path: cmdline.clang_format_exe_path,
+        out_dir,
+        sysroot: run_and_read_stdout(Command::new(&rustc).args(["--print", "sysroot"]))?.into(),
+    };
+    run(Command::new(&cargo)
+        .arg("build")
+        .args(&cmdline.cargo_args)
+        .env("RUSTC_WORKSPACE_WRAPPER", std::env::current_exe()?)
+        .env(WRAPPER_CONFIG_ENV_VAR, serde_json::to_string(&config)?))
+}
+
+/// Implements the `RUSTC_WORKSPACE_WRAPPER` mode: runs `rustc` and then (for
+/// library crates) `cc_bindings_from_rs`.
+fn run_wrapper(config: &WrapperConfig, rustc: &OsString, rustc_args: &[String]) -> Result<()> {
+    run(Command::new(rustc).args(rustc_args))?;
+
+    let Some(crate_name) = get_lib_crate_name(rustc_args) else { return Ok(()) };
+    let out_dir = &config.out_dir;
+    let mut command = Command::new(&config.cc_bindings_from_rs_path);
+    command
+        .arg(format!("--h-out={}", out_dir.join(format!("{crate_name}_cc_api.h")).display()))
+        .arg(format!("--rs-out={}", out_dir.join(format!("{crate_name}_cc_api_impl.rs")).display()))
+        .arg(format!("--crubit-support-path={}", config.crubit_support_path))
+        .arg(format!("--rustfmt-exe-path={}", config.rustfmt_exe_path.display()))
+        .args(get_crate_header_args(rustc_args, out_dir));
+    if let Some(clang_format_exe_path) = config.clang_format_exe_path.as_ref() {
+        command.arg(format!("--clang-format-exe-path={}", clang_format_exe_path.display()));
+    }
+    command
+        .arg("--")
+        .args(rustc_args)
+        .arg(format!("--sysroot={}", config.sysroot.display()))
+        // `cc_bindings_from_rs` only supports `panic=abort` (see `panics_and_exceptions.md`).
+        // This doesn't affect the crate compiled by `rustc` above.
+        .arg("-Cpanic=abort");
+    run(&mut command)
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args_os().collect::<Vec<_>>();
+    if let Some(config) = std::env::var_os(WRAPPER_CONFIG_ENV_VAR) {
+        let config: WrapperConfig = serde_json::from_str(
+            config.to_str().context("Non-UTF-8 `cargo-crubit` wrapper config")?,
+        )?;
+        ensure!(args.len() >= 2, "Expecting `rustc` path in the `RUSTC_WORKSPACE_WRAPPER` args");
+        let rustc_args = args[2..]
+            .iter()
+            .map(|arg| arg.to_str().map(String::from).context("Non-UTF-8 `rustc` arg"))
+            .collect::<Result<Vec<_>>>()?;
+        return run_wrapper(&config, &args[1], &rustc_args);
+    }
+
+    // Cargo invokes `cargo-crubit crubit <args>` for `cargo crubit <args>`.
+    if args.get(1).map_or(false, |arg| arg == "crubit") {
+        args.remove(1);
+    }
+    run_cargo(Cmdline::parse_from(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_get_flag_values() {
+        let args = to_args(&["--crate-type", "lib", "--crate-type=rlib", "--crate-name", "foo"]);
+        assert_eq!(get_flag_values(&args, "--crate-type"), ["lib", "rlib"]);
+        assert_eq!(get_flag_values(&args, "--crate-name"), ["foo"]);
+        assert!(get_flag_values(&args, "--extern").is_empty());
+    }
+
+    #[test]
+    fn test_get_flag_values_missing_value() {
+        let args = to_args(&["--crate-name"]);
+        assert!(get_flag_values(&args, "--crate-name").is_empty());
+    }
+
+    #[test]
+    fn test_get_lib_crate_name() {
+        let args = to_args(&["--crate-name", "foo", "--edition=2021", "--crate-type", "lib"]);
+        assert_eq!(get_lib_crate_name(&args), Some("foo"));
+    }
+
+    #[test]
+    fn test_get_lib_crate_name_for_non_lib_crates() {
+        let tests = [
+            vec!["--crate-name", "foo", "--crate-type", "bin"],
+            vec!["--crate-name", "foo", "--crate-type", "proc-macro"],
+            vec!["--crate-name", "build_script_build", "--crate-type", "bin"],
+            vec!["--crate-name", "foo", "--crate-type", "lib", "--test"],
+            vec!["-", "--crate-name", "___", "--print=file-names", "--crate-type", "lib"],
+        ];
+        for test in tests {
+            assert_eq!(get_lib_crate_name(&to_args(&test)), None, "test = {test:?}");
+        }
+    }
+
+    #[test]
+    fn test_get_crate_header_args() -> Result<()> {
+        let out_dir = tempfile::tempdir()?;
+        std::fs::write(out_dir.path().join("dep_with_bindings_cc_api.h"), "")?;
+        let args = to_args(&[
+            "--extern",
+            "dep_with_bindings=/path/to/libdep_with_bindings-123.rlib",
+            "--extern",
+            "dep_without_bindings=/path/to/libdep_without_bindings-456.rlib",
+        ]);
+        assert_eq!(
+            get_crate_header_args(&args, out_dir.path()),
+            ["--crate-header=dep_with_bindings=dep_with_bindings_cc_api.h"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrapper_config_roundtrip() -> Result<()> {
+        let config = WrapperConfig {
+            cc_bindings_from_rs_path: "cc_bindings_from_rs".into(),
+            crubit_support_path: "crubit/support".into(),
+            rustfmt_exe_path: "rustfmt".into(),
+            clang_format_exe_path: None,
+            out_dir: "target/crubit".into(),
+            sysroot: "/path/to/sysroot".into(),
+        };
+        let json = serde_json::to_string(&config)?;
+        assert_eq!(config, serde_json::from_str::<WrapperConfig>(&json)?);
+        Ok(())
+    }
+}
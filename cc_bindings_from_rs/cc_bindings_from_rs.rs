@@ -6,31 +6,58 @@
 #![feature(rustc_private)]
 #![deny(rustc::internal)]
 
+extern crate rustc_ast;
 extern crate rustc_attr;
+extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_error_codes;
 extern crate rustc_errors;
 extern crate rustc_feature;
 extern crate rustc_hir;
+extern crate rustc_infer;
 extern crate rustc_interface;
 extern crate rustc_lint_defs;
 extern crate rustc_middle;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
+extern crate rustc_trait_selection;
 
 // TODO(b/254679226): `bindings`, `cmdline`, and `run_compiler` should be
 // separate crates.
 mod bindings;
 mod cmdline;
+#[cfg(test)]
+mod fuzz;
+mod multi_target;
 mod run_compiler;
+mod rustc_compat;
 
-use anyhow::Context;
+use anyhow::{bail, ensure, Context};
+use code_gen_utils::{format_cc_tokens, write_cc_tokens, CcInclude};
+use crubit_feature::CrubitFeature;
 use itertools::Itertools;
+use proc_macro2::TokenStream;
 use rustc_middle::ty::TyCtxt; // See also <internal link>/ty.html#import-conventions
-use std::path::Path;
+use rustc_span::def_id::LOCAL_CRATE;
+use rustc_span::FileName;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
 
-use cmdline::Cmdline;
+use bindings::{
+    detect_crate_linkage, matches_path_pattern, CoverageReport, CrateLinkage, ErrorRecord,
+    ErrorReport, ErrorSpan, TypeConversion, TypeMapping,
+};
+use cmdline::{
+    CcFnCase, CcStd, Cmdline, IncludeGuardStyle, InlineNamespaceSource, StdLib, UnsafeFnStyle,
+};
+use multi_target::{format_target_condition, merge_files, TargetFiles};
 use run_compiler::run_compiler;
 use token_stream_printer::{
     cc_tokens_to_formatted_string, rs_tokens_to_formatted_string, RustfmtConfig,
@@ -41,34 +68,946 @@ fn write_file(path: &Path, content: &str) -> anyhow::Result<()> {
         .with_context(|| format!("Error when writing to {}", path.display()))
 }
 
+/// Formats C++ `tokens` (see `code_gen_utils::write_cc_tokens`) directly into
+/// the file at `path`.
+fn write_cc_file(path: &Path, tokens: TokenStream) -> anyhow::Result<()> {
+    let write = || -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_cc_tokens(tokens, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    };
+    write().with_context(|| format!("Error when writing to {}", path.display()))
+}
+
+/// Returns the inputs that have been read when generating the bindings: the
+/// Rust source files of the crate, the dependency crates, and the config files
+/// (see `--dep-file` and `--watch`).
+fn get_input_paths(cmdline: &Cmdline, tcx: TyCtxt) -> Vec<PathBuf> {
+    let source_files = tcx
+        .sess
+        .source_map()
+        .files()
+        .iter()
+        .filter(|file| !file.is_imported())
+        .filter_map(|file| match &file.name {
+            FileName::Real(real_name) => real_name.local_path().map(Path::to_path_buf),
+            _ => None,
+        })
+        .collect_vec();
+    // Crates from the sysroot (e.g. `std`) are not listed, because they only change together
+    // with the Rust toolchain.
+    let dependency_crates = tcx
+        .crates(())
+        .iter()
+        .flat_map(|&cnum| tcx.used_crate_source(cnum).paths().cloned().collect_vec())
+        .filter(|path| !path.starts_with(&tcx.sess.sysroot));
+    let config_files = cmdline
+        .rustfmt_config_path
+        .iter()
+        .chain(cmdline.rename_map.iter())
+        .chain(cmdline.type_map.iter())
+        .cloned();
+    source_files.into_iter().chain(dependency_crates).chain(config_files).unique().collect()
+}
+
+/// Formats a Makefile-style dependency file (see `--dep-file`) that lists the
+/// inputs that have been read when generating the bindings.
+fn format_dep_file(cmdline: &Cmdline, tcx: TyCtxt) -> String {
+    let escape = |path: &Path| path.display().to_string().replace(' ', "\\ ");
+    let targets = [Some(cmdline.h_out()), Some(cmdline.rs_out()), cmdline.cc_out.as_deref()]
+        .into_iter()
+        .flatten()
+        .chain(cmdline.h_out_for_modules.iter().map(|(_, h_path)| Path::new(h_path)))
+        .map(|path| escape(path))
+        .join(" ");
+
+    let deps = get_input_paths(cmdline, tcx);
+    let mut result = format!("{targets}:");
+    for dep in deps.iter() {
+        result += &format!(" {}", escape(dep));
+    }
+    result += "\n";
+    // Empty rules for the inputs avoid build errors when an input gets deleted (similarly
+    // to `gcc -MP`).
+    for dep in deps.iter() {
+        result += &format!("\n{}:\n", escape(dep));
+    }
+    result
+}
+
+/// Formats a CMake script (see `--cmake-out`) that defines an imported target
+/// for the generated C++ bindings.
+fn format_cmake_file(cmdline: &Cmdline, tcx: TyCtxt) -> anyhow::Result<String> {
+    let rust_lib = cmdline.cmake_rust_lib.as_ref().expect("`Cmdline::new` verifies this");
+    let current_dir = std::env::current_dir().context("Failed to get the current directory")?;
+    // CMake requires absolute paths in the properties of imported targets.
+    let absolute = |path: &Path| {
+        let path: PathBuf = current_dir.join(path).components().collect();
+        path.display().to_string().replace('\\', "/")
+    };
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\\\""));
+
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let target = format!("{crate_name}_cc_api");
+    // The generated header is `#include`d by its file name, but the `#include`s
+    // inside the generated files use paths relative to the working directory
+    // of the tool.
+    let h_out_dir = cmdline.h_out().parent().unwrap_or_else(|| Path::new(""));
+    let include_dirs = [absolute(h_out_dir), absolute(&current_dir)].into_iter().unique().join(";");
+
+    let is_shared = detect_crate_linkage(tcx) == CrateLinkage::DynamicLib;
+    // On Windows a shared library is linked against via its import library.
+    let location_property = if is_shared && tcx.sess.target.is_like_windows {
+        "IMPORTED_IMPLIB"
+    } else {
+        "IMPORTED_LOCATION"
+    };
+    let mut properties = vec![
+        format!("{location_property} {}", quote(&absolute(rust_lib))),
+        format!("INTERFACE_INCLUDE_DIRECTORIES {}", quote(&include_dirs)),
+    ];
+    if let Some(cc_out) = cmdline.cc_out.as_ref() {
+        properties.push(format!("INTERFACE_SOURCES {}", quote(&absolute(cc_out))));
+    }
+    if !is_shared {
+        // A Rust static library depends on the following system libraries (see also
+        // `rustc --print native-static-libs`).
+        properties
+            .push("INTERFACE_LINK_LIBRARIES \"Threads::Threads;${CMAKE_DL_LIBS}\"".to_string());
+    }
+
+    let mut result = format!(
+        "# Automatically @generated CMake target for the C++ bindings of the following Rust \
+         crate:\n# {crate_name}\n\n"
+    );
+    if !is_shared {
+        result += "find_package(Threads REQUIRED)\n\n";
+    }
+    let library_type = if is_shared { "SHARED" } else { "STATIC" };
+    result += &format!("if(NOT TARGET {target})\n");
+    result += &format!("  add_library({target} {library_type} IMPORTED)\n");
+    result += &format!("  set_target_properties({target} PROPERTIES\n");
+    for property in properties.iter() {
+        result += &format!("    {property}\n");
+    }
+    result += "  )\nendif()\n";
+    Ok(result)
+}
+
+/// Formats a browsable HTML report of the interop coverage of the crate (see
+/// `--report-html`).
+fn format_html_report(report: &CoverageReport) -> anyhow::Result<String> {
+    let current_dir = std::env::current_dir().context("Failed to get the current directory")?;
+    let escape = |s: &str| {
+        s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    };
+
+    let bound_items = report.items.iter().filter(|item| item.cc_signature.is_ok()).count();
+    let crate_name = escape(&report.crate_name);
+    let mut result = format!(
+        "<!DOCTYPE html>\n\
+         <!-- Automatically @generated interop coverage report for the following Rust crate:\n\
+         {crate_name} -->\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>C++ bindings of {crate_name}</title>\n\
+         <style>\n\
+         table {{ border-collapse: collapse; }}\n\
+         td, th {{ border: 1px solid #ccc; padding: 4px; text-align: left; }}\n\
+         .bound {{ color: green; }}\n\
+         .unsupported {{ color: red; }}\n\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <h1>C++ bindings of <code>{crate_name}</code></h1>\n\
+         <p>Bindings have been generated for {bound_items} out of {} public items.</p>\n\
+         <table>\n\
+         <tr><th>Rust item</th><th>Kind</th><th>Status</th>\
+         <th>C++ signature or error</th><th>Source</th></tr>\n",
+        report.items.len()
+    );
+    for item in report.items.iter() {
+        let (status, details) = match item.cc_signature.as_ref() {
+            Ok(cc_signature) => ("bound", format!("<code>{}</code>", escape(cc_signature))),
+            Err(err) => {
+                let suggestion = match err.code.suggestion() {
+                    None => String::new(),
+                    Some(suggestion) => format!("<br>Suggestion: {}", escape(suggestion)),
+                };
+                let details =
+                    format!("<code>{}</code> {}{suggestion}", err.code, escape(&err.message));
+                ("unsupported", details)
+            }
+        };
+        // Real source files are linked to; other names (e.g. `<anon>`) are only printed.
+        let source = {
+            let loc = format!("{}:{}", escape(&item.span.file), item.span.line);
+            let path = Path::new(&item.span.file);
+            if path.exists() {
+                let url = format!("file://{}", current_dir.join(path).display());
+                format!("<a href=\"{}\">{loc}</a>", escape(&url))
+            } else {
+                loc
+            }
+        };
+        result += &format!(
+            "<tr><td><code>{}</code></td><td>{}</td><td class=\"{status}\">{status}</td>\
+             <td>{details}</td><td>{source}</td></tr>\n",
+            escape(&item.rs_path),
+            escape(&item.item_kind),
+        );
+    }
+    result += "</table>\n</body>\n</html>\n";
+    Ok(result)
+}
+
+/// Returns a string that identifies the binary and the cmdline of the tool, so
+/// that the `--snippet-cache` populated by one run of the tool is only reused by
+/// a later run with the same configuration.
+fn get_invocation_fingerprint(cmdline: &Cmdline) -> String {
+    let exe_metadata = std::env::current_exe()
+        .and_then(std::fs::metadata)
+        .map(|metadata| (metadata.len(), metadata.modified().ok()))
+        .ok();
+    // The `--rename-map` and `--type-map` files may change without changing the command line.
+    let rename_map =
+        cmdline.rename_map.as_ref().and_then(|path| std::fs::read_to_string(path).ok());
+    let type_map = cmdline.type_map.as_ref().and_then(|path| std::fs::read_to_string(path).ok());
+    format!("{cmdline:?} {exe_metadata:?} {rename_map:?} {type_map:?}")
+}
+
+/// Contents of the `--rename-map` file.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenameMap {
+    /// A map from a crate name to the top-level C++ namespace of its bindings
+    /// (see also `--crate-namespace`).
+    #[serde(default)]
+    crates: HashMap<String, String>,
+
+    /// A map from the Rust path of an item to the C++ name of its bindings
+    /// (see `Input::rename_map`).
+    #[serde(default)]
+    items: HashMap<String, String>,
+}
+
+fn read_rename_map(path: &Path) -> anyhow::Result<RenameMap> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Error when reading {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid rename map in {}", path.display()))
+}
+
+/// Contents of the `--type-map` file.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TypeMap {
+    /// A map from the Rust path of a type to the C++ type that should be used
+    /// instead of the C++ bindings of the Rust type (see `Input::type_map`).
+    #[serde(default)]
+    types: HashMap<String, TypeMapEntry>,
+}
+
+/// An entry of the `--type-map` file (see `bindings::TypeMapping`).
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TypeMapEntry {
+    cc_type: String,
+
+    #[serde(default)]
+    cc_headers: Vec<String>,
+
+    to_cc: Option<String>,
+
+    from_cc: Option<String>,
+}
+
+fn read_type_map(path: &Path) -> anyhow::Result<HashMap<Rc<str>, TypeMapping>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Error when reading {}", path.display()))?;
+    let type_map: TypeMap = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid type map in {}", path.display()))?;
+    type_map
+        .types
+        .into_iter()
+        .map(|(rs_path, entry)| {
+            let conversion = match (entry.to_cc, entry.from_cc) {
+                (None, None) => None,
+                (Some(to_cc), Some(from_cc)) => {
+                    Some(TypeConversion { to_cc: to_cc.into(), from_cc: from_cc.into() })
+                }
+                _ => bail!(
+                    "Invalid type map in {}: `{rs_path}` needs both `to_cc` and `from_cc` \
+                     (or neither of them)",
+                    path.display()
+                ),
+            };
+            let mapping = TypeMapping {
+                cc_type: entry.cc_type.into(),
+                cc_headers: entry.cc_headers.into_iter().map(Into::into).collect(),
+                conversion,
+            };
+            Ok((rs_path.into(), mapping))
+        })
+        .collect()
+}
+
+/// Contents of the `--batch` manifest.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchManifest {
+    crates: Vec<BatchCrate>,
+}
+
+/// A crate from the `--batch` manifest.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchCrate {
+    /// The name of the crate (the same as the one used by the Rust compiler -
+    /// e.g. the `--crate-name` from `rustc_args`).
+    crate_name: String,
+
+    /// Output path for the C++ header with the bindings of the crate (see
+    /// `--h-out`).
+    h_out: PathBuf,
+
+    /// Include path of the `h_out` header, used by the bindings of the other
+    /// crates from the manifest (see `--crate-header`).  Defaults to `h_out`.
+    #[serde(default)]
+    h_include: Option<String>,
+
+    /// Output path for the Rust implementation of the bindings of the crate
+    /// (see `--rs-out`).
+    rs_out: PathBuf,
+
+    /// Command line arguments of the Rust compiler for the crate.  The crates
+    /// that the crate depends on have to be already compiled (e.g. the crates
+    /// from the manifest are *not* compiled into `rlib`s by the tool).
+    rustc_args: Vec<String>,
+}
+
+fn read_batch_manifest(path: &Path) -> anyhow::Result<BatchManifest> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Error when reading {}", path.display()))?;
+    serde_json::from_str(&json)
+        .with_context(|| format!("Invalid batch manifest in {}", path.display()))
+}
+
+/// Returns the names of the crates from the `--batch` manifest, together with
+/// the `Cmdline` that should be used to generate the bindings of each crate.
+///
+/// The `--crate-header` of each crate from the manifest is added to the
+/// `Cmdline`s of all the other crates (explicit `--crate-header` flags take
+/// precedence).
+fn get_batch_cmdlines(
+    cmdline: &Cmdline,
+    manifest: BatchManifest,
+) -> anyhow::Result<Vec<(String, Cmdline)>> {
+    let duplicates = manifest.crates.iter().map(|krate| &krate.crate_name).duplicates().join(", ");
+    ensure!(duplicates.is_empty(), "Crate(s) listed more than once: {duplicates}");
+    let batch_crate_headers = manifest
+        .crates
+        .iter()
+        .map(|krate| {
+            let include_path = match krate.h_include.as_ref() {
+                None => krate.h_out.to_string_lossy().into_owned(),
+                Some(h_include) => h_include.clone(),
+            };
+            (krate.crate_name.clone(), include_path)
+        })
+        .collect_vec();
+    let batch_cmdlines = manifest
+        .crates
+        .into_iter()
+        .map(|BatchCrate { crate_name, h_out, rs_out, rustc_args, .. }| {
+            let crate_headers = batch_crate_headers
+                .iter()
+                .filter(|(other_crate_name, _)| *other_crate_name != crate_name)
+                .chain(cmdline.crate_headers.iter())
+                .cloned()
+                .collect();
+            let rustc_args = cmdline.rustc_args.iter().cloned().chain(rustc_args).collect();
+            let crate_cmdline = Cmdline {
+                h_out: Some(h_out),
+                rs_out: Some(rs_out),
+                batch: None,
+                crate_headers,
+                rustc_args,
+                ..cmdline.clone()
+            };
+            (crate_name, crate_cmdline)
+        })
+        .collect();
+    Ok(batch_cmdlines)
+}
+
+/// Implements `--batch`: generates the bindings of all the crates from the
+/// manifest (in the order in which they are listed), stopping at the first
+/// error.
+fn run_batch(cmdline: &Cmdline, manifest_path: &Path) -> anyhow::Result<()> {
+    let manifest = read_batch_manifest(manifest_path)?;
+    let batch_cmdlines = get_batch_cmdlines(cmdline, manifest)
+        .with_context(|| format!("Invalid batch manifest in {}", manifest_path.display()))?;
+    for (crate_name, crate_cmdline) in batch_cmdlines.iter() {
+        run_compiler(&crate_cmdline.rustc_args, |tcx| {
+            // A mismatch would result in `--crate-header`s that point at a header of a
+            // different crate.
+            let actual_crate_name = tcx.crate_name(LOCAL_CRATE);
+            ensure!(
+                actual_crate_name.as_str() == crate_name,
+                "The Rust compiler uses a different crate name: `{actual_crate_name}`"
+            );
+            run_with_tcx(crate_cmdline, tcx)
+        })
+        .with_context(|| {
+            format!(
+                "Error when generating the bindings of the `{crate_name}` crate from {}",
+                manifest_path.display()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Fails if `report` contains items that match the `--fail-on-unsupported-kind`
+/// and `--fail-on-unsupported-path` filters.
+fn check_unsupported_items(cmdline: &Cmdline, report: &ErrorReport) -> anyhow::Result<()> {
+    let kinds = &cmdline.fail_on_unsupported_kinds;
+    let paths = &cmdline.fail_on_unsupported_paths;
+    let errors = report
+        .errors
+        .iter()
+        .filter(|record| kinds.is_empty() || kinds.contains(&record.item_kind))
+        .filter(|record| {
+            paths.is_empty()
+                || paths.iter().any(|pattern| matches_path_pattern(pattern, &record.def_path))
+        })
+        .map(|ErrorRecord { def_path, item_kind, span, message, .. }| {
+            let ErrorSpan { file, line, .. } = span;
+            format!("`{def_path}` ({item_kind}) defined at {file}:{line}: {message}")
+        })
+        .collect_vec();
+    ensure!(
+        errors.is_empty(),
+        "Bindings couldn't be generated for {} public item(s) (see `--fail-on-unsupported`):\n{}",
+        errors.len(),
+        errors.join("\n")
+    );
+    Ok(())
+}
+
+/// Paths and contents of the generated bindings.  They are written at the end
+/// (or compared with the existing files in the `--check` mode).
+struct BindingsFiles {
+    /// The `--h-out` header, the module headers (see `--h-out-for-module`),
+    /// the `--cc-out` source file, and the `--cc-self-test-out` source file.
+    cc: Vec<(PathBuf, String)>,
+
+    /// The `--rs-out` file.
+    rs: (PathBuf, String),
+}
+
 fn run_with_tcx(cmdline: &Cmdline, tcx: TyCtxt) -> anyhow::Result<()> {
+    let BindingsFiles { cc, rs } = generate_bindings_files(cmdline, tcx)?;
+    let bindings_files = cc.into_iter().chain(std::iter::once(rs)).collect_vec();
+    write_files(cmdline, &bindings_files, &format_build_files(cmdline, tcx)?)
+}
+
+/// Generates the bindings of the crate (and, unless in the `--check` mode,
+/// writes the other outputs that describe the bindings, like `--emit-ir` or
+/// `--error-report`).
+fn generate_bindings_files(cmdline: &Cmdline, tcx: TyCtxt) -> anyhow::Result<BindingsFiles> {
     use bindings::*;
-    let Output { h_body, rs_body } = {
+    let remap_path_prefixes: Vec<(Rc<str>, Rc<str>)> = cmdline
+        .remap_path_prefixes
+        .iter()
+        .map(|(from, to)| (from.as_str().into(), to.as_str().into()))
+        .collect();
+    let (Output { h_body, h_parts, rs_body, cc_body }, self_test_body) = {
         let crubit_support_path = cmdline.crubit_support_path.as_str().into();
-        let input = Input { tcx, crubit_support_path, _features: (), _crate_to_include_map: () };
-        generate_bindings(&input)?
+        let crate_name_to_include_path = cmdline
+            .crate_headers
+            .iter()
+            .map(|(crate_name, include_path)| {
+                (crate_name.as_str().into(), CcInclude::user_header(include_path.as_str().into()))
+            })
+            .collect();
+        let rename_map = match cmdline.rename_map.as_ref() {
+            None => RenameMap::default(),
+            Some(path) => read_rename_map(path)?,
+        };
+        let type_map = match cmdline.type_map.as_ref() {
+            None => HashMap::new(),
+            Some(path) => read_type_map(path)?,
+        };
+        let crate_name_to_namespace = {
+            // `--crate-namespace` and `--top-level-namespace` take precedence over the
+            // `--rename-map` file.
+            let mut map: HashMap<Rc<str>, Rc<str>> = rename_map
+                .crates
+                .iter()
+                .chain(cmdline.crate_namespaces.iter().map(|(crate_name, ns)| (crate_name, ns)))
+                .map(|(crate_name, ns)| (crate_name.as_str().into(), ns.as_str().into()))
+                .collect();
+            if let Some(top_level_namespace) = cmdline.top_level_namespace.as_ref() {
+                let crate_name = tcx.crate_name(LOCAL_CRATE);
+                map.insert(crate_name.as_str().into(), top_level_namespace.as_str().into());
+            }
+            map
+        };
+        let feature_guard_macros = cmdline
+            .feature_guard_macros
+            .iter()
+            .map(|(feature, guard_macro)| (feature.as_str().into(), guard_macro.as_str().into()))
+            .collect();
+        let split_headers = if cmdline.h_out_for_modules.is_empty() {
+            None
+        } else {
+            Some(SplitHeaders {
+                h_out: cmdline.h_out().to_string_lossy().into(),
+                module_headers: cmdline
+                    .h_out_for_modules
+                    .iter()
+                    .map(|(module, h_path)| (module.as_str().into(), h_path.as_str().into()))
+                    .collect(),
+            })
+        };
+        let include_guard = {
+            let h_path =
+                remap_path_prefix(&remap_path_prefixes, &cmdline.h_out().to_string_lossy()).into();
+            match cmdline.include_guard.unwrap_or(IncludeGuardStyle::PragmaOnce) {
+                IncludeGuardStyle::PragmaOnce => IncludeGuard::PragmaOnce,
+                IncludeGuardStyle::PathBased => IncludeGuard::PathBased { h_path },
+                IncludeGuardStyle::HashBased => IncludeGuard::HashBased { h_path },
+            }
+        };
+        let inline_namespace = cmdline.inline_namespace.as_ref().map(|source| match source {
+            InlineNamespaceSource::CrateVersion(version) => {
+                InlineNamespace::CrateVersion(version.as_str().into())
+            }
+            InlineNamespaceSource::AbiHash => InlineNamespace::AbiHash,
+        });
+        let cc_std = match cmdline.cc_std.unwrap_or(CcStd::Cxx17) {
+            CcStd::Cxx17 => CcStandard::Cxx17,
+            CcStd::Cxx20 => CcStandard::Cxx20,
+        };
+        let fn_naming = FnNaming {
+            case: match cmdline.cc_fn_case.unwrap_or(CcFnCase::SnakeCase) {
+                CcFnCase::SnakeCase => CcNameCase::SnakeCase,
+                CcFnCase::CamelCase => CcNameCase::CamelCase,
+            },
+            prefix: cmdline.cc_fn_prefix.as_deref().unwrap_or_default().into(),
+            suffix: cmdline.cc_fn_suffix.as_deref().unwrap_or_default().into(),
+        };
+        let unsafe_fn_marker = match cmdline.unsafe_fn_style.unwrap_or(UnsafeFnStyle::Token) {
+            UnsafeFnStyle::Token => UnsafeFnMarker::TokenParam,
+            UnsafeFnStyle::Suffix => UnsafeFnMarker::NameSuffix,
+        };
+        let rust_std_lib = match cmdline.rust_std_lib {
+            None => detect_rust_std_lib(tcx),
+            Some(StdLib::Std) => RustStdLib::Std,
+            Some(StdLib::Alloc) => RustStdLib::Alloc,
+            Some(StdLib::Core) => RustStdLib::Core,
+        };
+        let input = Input {
+            tcx,
+            crubit_support_path,
+            crubit_features: cmdline.crubit_features.unwrap_or(CrubitFeature::Supported.into()),
+            crate_name_to_include_path,
+            crate_name_to_namespace,
+            split_headers,
+            feature_guard_macros,
+            include_guard,
+            inline_namespace,
+            bind_only: cmdline.bind_only.iter().map(|s| s.as_str().into()).collect(),
+            skip: cmdline.skip.iter().map(|s| s.as_str().into()).collect(),
+            rename_map: rename_map
+                .items
+                .iter()
+                .map(|(path, cc_name)| (path.as_str().into(), cc_name.as_str().into()))
+                .collect(),
+            type_map,
+            c_mode: cmdline.c_mode,
+            cc_std,
+            fn_naming,
+            unsafe_fn_marker,
+            emit_line_directives: cmdline.emit_line_directives,
+            emit_abi_stamp: cmdline.abi_stamp,
+            remap_path_prefixes: remap_path_prefixes.clone(),
+            rust_std_lib,
+            crate_linkage: detect_crate_linkage(tcx),
+            cc_out_h_include: cmdline
+                .cc_out
+                .as_ref()
+                .map(|_| CcInclude::user_header(cmdline.h_out().to_string_lossy().into())),
+            snippet_cache: cmdline.snippet_cache.as_ref().map(|snippet_cache| {
+                // A missing cache file is expected in the first run of the tool.
+                let json = std::fs::read_to_string(snippet_cache).ok();
+                let invocation_fingerprint = get_invocation_fingerprint(cmdline);
+                RefCell::new(SnippetCache::new(json.as_deref(), invocation_fingerprint))
+            }),
+            interner: Default::default(),
+        };
+        // The `--check` mode only verifies the bindings (see `write_files`) and doesn't write
+        // any of the other outputs.
+        let skip_in_check_mode = |path: Option<&PathBuf>| path.filter(|_| !cmdline.check);
+        if let Some(emit_ir) = skip_in_check_mode(cmdline.emit_ir.as_ref()) {
+            let ir = serde_json::to_string_pretty(&generate_ir(&input))?;
+            write_file(emit_ir, &ir)?;
+        }
+        if let Some(stats) = skip_in_check_mode(cmdline.stats.as_ref()) {
+            write_file(stats, &serde_json::to_string_pretty(&generate_stats(&input))?)?;
+        }
+        if let Some(report_html) = skip_in_check_mode(cmdline.report_html.as_ref()) {
+            std::fs::create_dir_all(report_html)
+                .with_context(|| format!("Error when creating {}", report_html.display()))?;
+            let report = format_html_report(&generate_coverage_report(&input))?;
+            write_file(&report_html.join("index.html"), &report)?;
+        }
+        let error_report_path = skip_in_check_mode(cmdline.error_report.as_ref());
+        if error_report_path.is_some() || cmdline.fail_on_unsupported {
+            let report = generate_error_report(&input);
+            if let Some(error_report) = error_report_path {
+                write_file(error_report, &serde_json::to_string_pretty(&report)?)?;
+            }
+            if cmdline.fail_on_unsupported {
+                check_unsupported_items(cmdline, &report)?;
+            }
+        }
+        let output = generate_bindings(&input)?;
+        if let (Some(path), Some(snippet_cache)) =
+            (skip_in_check_mode(cmdline.snippet_cache.as_ref()), input.snippet_cache.as_ref())
+        {
+            write_file(path, &snippet_cache.borrow().to_json()?)?;
+        }
+        let self_test_body = match cmdline.cc_self_test_out.as_ref() {
+            None => None,
+            Some(_) => {
+                let h_include = CcInclude::user_header(cmdline.h_out().to_string_lossy().into());
+                Some(generate_self_test(&input, h_include)?)
+            }
+        };
+        (output, self_test_body)
     };
 
+    let mut cc_files: Vec<(PathBuf, String)> = vec![];
     {
-        let h_body = cc_tokens_to_formatted_string(h_body, &cmdline.clang_format_exe_path)?;
-        write_file(&cmdline.h_out, &h_body)?;
+        // Without `clang-format` (and when the formatted files don't need to be
+        // post-processed or compared with the existing files) the C++ files are
+        // written while they are being formatted.  This avoids holding the
+        // formatted text of big headers in memory.
+        let can_stream_cc_files = cmdline.clang_format_exe_path.is_none()
+            && !cmdline.emit_line_directives
+            && !cmdline.check
+            && cmdline.extra_targets.is_empty();
+        let mut add_cc_file = |path: PathBuf, tokens, is_header: bool| -> anyhow::Result<()> {
+            if can_stream_cc_files {
+                return write_cc_file(&path, tokens);
+            }
+            let content = match cmdline.clang_format_exe_path.as_ref() {
+                Some(clang_format_exe_path) => {
+                    cc_tokens_to_formatted_string(tokens, clang_format_exe_path)?
+                }
+                None => format_cc_tokens(tokens)?,
+            };
+            let content = if is_header && cmdline.emit_line_directives {
+                let h_path = remap_path_prefix(&remap_path_prefixes, &path.to_string_lossy());
+                finalize_line_directives(&content, &h_path)
+            } else {
+                content
+            };
+            cc_files.push((path, content));
+            Ok(())
+        };
+        add_cc_file(cmdline.h_out().to_path_buf(), h_body, true)?;
+
+        for (h_path, h_part) in h_parts {
+            add_cc_file(PathBuf::from(h_path), h_part, true)?;
+        }
+
+        if let Some(cc_out) = cmdline.cc_out.as_ref() {
+            add_cc_file(cc_out.clone(), cc_body, false)?;
+        }
+
+        if let (Some(cc_self_test_out), Some(self_test_body)) =
+            (cmdline.cc_self_test_out.as_ref(), self_test_body)
+        {
+            add_cc_file(cc_self_test_out.clone(), self_test_body, false)?;
+        }
     }
 
-    {
+    let rs_file = {
         let rustfmt_config =
             RustfmtConfig::new(&cmdline.rustfmt_exe_path, cmdline.rustfmt_config_path.as_deref());
         let rs_body = rs_tokens_to_formatted_string(rs_body, &rustfmt_config)?;
-        write_file(&cmdline.rs_out, &rs_body)?;
+        (cmdline.rs_out().to_path_buf(), rs_body)
+    };
+
+    Ok(BindingsFiles { cc: cc_files, rs: rs_file })
+}
+
+/// Formats the files that integrate the bindings with the build system (i.e.
+/// the `--dep-file` and the `--cmake-out` file, if requested).
+fn format_build_files(cmdline: &Cmdline, tcx: TyCtxt) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    let mut result = vec![];
+    if let Some(dep_file) = cmdline.dep_file.as_ref() {
+        result.push((dep_file.clone(), format_dep_file(cmdline, tcx)));
+    }
+    if let Some(cmake_out) = cmdline.cmake_out.as_ref() {
+        result.push((cmake_out.clone(), format_cmake_file(cmdline, tcx)?));
     }
+    Ok(result)
+}
 
+/// Writes the `bindings_files` and then the `build_files` (see
+/// `format_build_files`).  In the `--check` mode only verifies that the
+/// `bindings_files` are up to date.
+fn write_files(
+    cmdline: &Cmdline,
+    bindings_files: &[(PathBuf, String)],
+    build_files: &[(PathBuf, String)],
+) -> anyhow::Result<()> {
+    if cmdline.check {
+        return check_files_are_up_to_date(bindings_files);
+    }
+    for (path, content) in bindings_files.iter().chain(build_files.iter()) {
+        write_file(path, content)?;
+    }
     Ok(())
 }
 
+/// Returns the path of the Rust side of the bindings for an `--extra-target`
+/// (e.g. `foo_impl.aarch64-apple-darwin.rs` for the `foo_impl.rs` from
+/// `--rs-out`).
+fn get_rs_out_for_extra_target(rs_out: &Path, triple: &str) -> PathBuf {
+    let rs_out_stem = rs_out.file_stem().unwrap_or_default().to_string_lossy();
+    // The target can also be given as a path to a JSON target specification.
+    let triple = Path::new(triple).file_stem().unwrap_or_default().to_string_lossy();
+    rs_out.with_file_name(format!("{rs_out_stem}.{triple}.rs"))
+}
+
+/// Returns `rustc_args` with the `--target` (if any) replaced by `triple`.
+fn get_rustc_args_for_target(rustc_args: &[String], triple: &str) -> Vec<String> {
+    let mut result = vec![];
+    let mut args = rustc_args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--target" {
+            args.next(); // Skipping the value of the flag.
+        } else if !arg.starts_with("--target=") {
+            result.push(arg.clone());
+        }
+    }
+    result.push(format!("--target={triple}"));
+    result
+}
+
+/// Implements `--extra-target`: generates the bindings for the primary target
+/// (from the Rust compiler arguments) and for each extra target, and then
+/// merges the generated C++ files (see `multi_target::merge_files`).  The
+/// other outputs (e.g. `--emit-ir` or `--dep-file`) only cover the primary
+/// target.
+fn run_with_extra_targets(cmdline: &Cmdline) -> anyhow::Result<()> {
+    let (primary_target, primary_rs_file, build_files) =
+        run_compiler(&cmdline.rustc_args, |tcx| {
+            let BindingsFiles { cc, rs } = generate_bindings_files(cmdline, tcx)?;
+            let target = TargetFiles {
+                triple: tcx.sess.opts.target_triple.triple().to_string(),
+                condition: format_target_condition(tcx)?,
+                files: cc,
+            };
+            Ok((target, rs, format_build_files(cmdline, tcx)?))
+        })?;
+
+    let mut targets = vec![primary_target];
+    let mut rs_files = vec![primary_rs_file];
+    for triple in cmdline.extra_targets.iter() {
+        // The other outputs are only written for the primary target.
+        let target_cmdline = Cmdline {
+            rs_out: Some(get_rs_out_for_extra_target(cmdline.rs_out(), triple)),
+            emit_ir: None,
+            error_report: None,
+            stats: None,
+            report_html: None,
+            snippet_cache: None,
+            ..cmdline.clone()
+        };
+        let rustc_args = get_rustc_args_for_target(&cmdline.rustc_args, triple);
+        let (target, rs_file) = run_compiler(&rustc_args, |tcx| {
+            let BindingsFiles { cc, rs } = generate_bindings_files(&target_cmdline, tcx)?;
+            let condition = format_target_condition(tcx)?;
+            Ok((TargetFiles { triple: triple.clone(), condition, files: cc }, rs))
+        })
+        .with_context(|| {
+            format!("Error when generating the bindings for `--extra-target={triple}`")
+        })?;
+        targets.push(target);
+        rs_files.push(rs_file);
+    }
+
+    let bindings_files = merge_files(&targets)?.into_iter().chain(rs_files).collect_vec();
+    write_files(cmdline, &bindings_files, &build_files)
+}
+
+/// Implements `--check`: fails if any of the `files` (paths and the expected
+/// contents) is missing or has a different content.  The error message
+/// includes a diff of each out-of-date file.
+fn check_files_are_up_to_date(files: &[(PathBuf, String)]) -> anyhow::Result<()> {
+    let problems = files
+        .iter()
+        .filter_map(|(path, expected)| match std::fs::read_to_string(path) {
+            Err(err) => Some(format!("{} can't be read: {err}", path.display())),
+            Ok(actual) if actual == *expected => None,
+            Ok(actual) => Some(format!(
+                "--- {path}\n+++ {path} (regenerated)\n{}",
+                format_line_diff(&actual, expected),
+                path = path.display(),
+            )),
+        })
+        .collect_vec();
+    ensure!(
+        problems.is_empty(),
+        "The following generated file(s) are out of date (see `--check`) - run the tool \
+         without `--check` to regenerate them:\n{}",
+        problems.join("\n")
+    );
+    Ok(())
+}
+
+/// How many unchanged lines `format_line_diff` prints around the changes.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Formats a unified-diff-like summary of the changes between `old` and
+/// `new`: the lines between the first and the last changed line are printed
+/// as a single hunk (surrounded by `DIFF_CONTEXT_LINES` unchanged lines).  This
+/// is less precise than a real diff (e.g. unchanged lines between two changes
+/// are printed as removed and re-added), but it is good enough for pointing at
+/// the out-of-date part of a generated file.
+fn format_line_diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect_vec();
+    let new = new.lines().collect_vec();
+    let common_prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let common_suffix = old[common_prefix..]
+        .iter()
+        .rev()
+        .zip(new[common_prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let context_start = common_prefix.saturating_sub(DIFF_CONTEXT_LINES);
+    let old_end = old.len() - common_suffix;
+    let new_end = new.len() - common_suffix;
+    let context_end = std::cmp::min(common_suffix, DIFF_CONTEXT_LINES);
+
+    let mut result = format!(
+        "@@ -{},{} +{},{} @@\n",
+        context_start + 1,
+        old_end + context_end - context_start,
+        context_start + 1,
+        new_end + context_end - context_start,
+    );
+    let lines = old[context_start..common_prefix]
+        .iter()
+        .map(|line| format!(" {line}"))
+        .chain(old[common_prefix..old_end].iter().map(|line| format!("-{line}")))
+        .chain(new[common_prefix..new_end].iter().map(|line| format!("+{line}")))
+        .chain(old[old_end..old_end + context_end].iter().map(|line| format!(" {line}")));
+    for line in lines {
+        result += &line;
+        result += "\n";
+    }
+    result
+}
+
+/// How often `--watch` checks whether the inputs have been modified.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The modification times of the inputs of the tool (see `get_input_paths`),
+/// used by `--watch` to detect that the bindings need to be regenerated.
+struct InputSnapshot {
+    paths: Vec<PathBuf>,
+
+    /// `None` if the file couldn't be accessed (e.g. because it didn't exist).
+    mtimes: Vec<Option<SystemTime>>,
+}
+
+impl InputSnapshot {
+    fn new(paths: Vec<PathBuf>) -> Self {
+        let mtimes = Self::get_mtimes(&paths);
+        Self { paths, mtimes }
+    }
+
+    fn get_mtimes(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+            .collect()
+    }
+
+    /// Returns whether any of the inputs has been modified, deleted, or
+    /// (re-)created since the snapshot has been taken.
+    fn has_changed(&self) -> bool {
+        Self::get_mtimes(&self.paths) != self.mtimes
+    }
+
+    /// Blocks until `has_changed` returns `true`.
+    fn wait_for_changes(&self) {
+        while !self.has_changed() {
+            std::thread::sleep(WATCH_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Implements `--watch`: regenerates the bindings whenever one of the inputs
+/// changes (reusing the `--snippet-cache`, if any).  Only the 1st run of the
+/// tool can fail - the errors from the later runs (e.g. compilation errors
+/// introduced by an edit of a Rust source file) are only printed to `stderr`.
+fn watch(cmdline: &Cmdline) -> anyhow::Result<!> {
+    let run = || {
+        run_compiler(&cmdline.rustc_args, |tcx| {
+            // The snapshot is taken before generating the bindings, so that edits made while
+            // the bindings are being generated trigger another run.
+            let snapshot = InputSnapshot::new(get_input_paths(cmdline, tcx));
+            Ok((snapshot, run_with_tcx(cmdline, tcx)))
+        })
+    };
+
+    let (mut snapshot, result) = run()?;
+    result?;
+    loop {
+        eprintln!("Watching {} input file(s) for changes (see `--watch`)...", snapshot.paths.len());
+        snapshot.wait_for_changes();
+        match run() {
+            Ok((new_snapshot, result)) => {
+                snapshot = new_snapshot;
+                match result {
+                    Ok(()) => eprintln!("Regenerated the bindings"),
+                    Err(err) => eprintln!("Error when regenerating the bindings: {err:#}"),
+                }
+            }
+            Err(err) => {
+                // The Rust compiler didn't get far enough to list the inputs - they are assumed
+                // to be the same as in the previous run.
+                eprintln!("Error when regenerating the bindings: {err:#}");
+                snapshot = InputSnapshot::new(snapshot.paths);
+            }
+        }
+    }
+}
+
 /// Main entrypoint that (unlike `main`) doesn't do any intitializations that
 /// should only happen once for the binary (e.g. it doesn't call
 /// `init_env_logger`) and therefore can be used from the tests module below.
 fn run_with_cmdline_args(args: &[String]) -> anyhow::Result<()> {
     let cmdline = Cmdline::new(args)?;
+    if let Some(manifest_path) = cmdline.batch.as_ref() {
+        return run_batch(&cmdline, manifest_path);
+    }
+    if cmdline.watch {
+        let _: ! = watch(&cmdline)?;
+    }
+    if !cmdline.extra_targets.is_empty() {
+        return run_with_extra_targets(&cmdline);
+    }
     run_compiler(&cmdline.rustc_args, |tcx| {
         run_with_tcx(&cmdline, tcx)
     })
@@ -102,12 +1041,13 @@ fn main() -> anyhow::Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::run_with_cmdline_args;
+    use super::{format_line_diff, get_batch_cmdlines, run_with_cmdline_args, InputSnapshot};
 
-    use crate::run_compiler::tests::get_sysroot_for_testing;
+    use crate::cmdline::Cmdline;
+    use crate::run_compiler::tests::{get_sysroot_for_testing, run_compiler_for_testing};
     use itertools::Itertools;
     use regex::{Regex, RegexBuilder};
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use tempfile::{tempdir, TempDir};
     use token_stream_printer::{CLANG_FORMAT_EXE_PATH_FOR_TESTING, RUSTFMT_EXE_PATH_FOR_TESTING};
 
@@ -195,6 +1135,12 @@ mod tests {
                         }
 
                         fn private_function() {}
+
+                        #[cfg(feature = "test_feature")]
+                        pub fn feature_gated_function() {}
+
+                        #[cfg(feature = "unsupported_feature")]
+                        pub fn unsupported_function(_x: (i32, i32)) {}
                     }
                 "#,
             )?;
@@ -268,6 +1214,12 @@ mod tests {
         }
     }
 
+    /// Returns the pointer size (in bytes) of the target that the tests
+    /// compile for (i.e. of the host).
+    fn get_target_pointer_size() -> u64 {
+        run_compiler_for_testing("", |tcx| tcx.data_layout.pointer_size.bytes())
+    }
+
     #[test]
     fn test_happy_path() -> anyhow::Result<()> {
         let test_args = TestArgs::default_args()?;
@@ -276,16 +1228,19 @@ mod tests {
         assert!(test_result.h_path.exists());
         let temp_dir_str = test_args.tempdir.path().to_str().unwrap();
         let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        let pointer_size = get_target_pointer_size();
         #[rustfmt::skip]
         assert_body_matches(
             &h_body,
             &format!(
-                "{}\n{}\n{}",
+                "{}\n{}\n{}\n{}\n{}",
 r#"// Automatically @generated C++ bindings for the following Rust crate:
 // test_crate
 
 #pragma once
-
+"#,
+format!(r#"static_assert(sizeof(void*) == {pointer_size}, "Bindings are for a different target");"#),
+r#"
 namespace test_crate {
 
 namespace public_module {
@@ -329,6 +1284,920 @@ extern "C" fn __crubit_thunk__ANY_IDENTIFIER_CHARACTERS()
         Ok(())
     }
 
+    /// `test_h_out_for_module` tests that `--h-out-for-module` results in
+    /// writing the bindings of the module into the given header (which is
+    /// `#include`d by the `--h-out` header).
+    #[test]
+    fn test_h_out_for_module() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let module_h_path = test_args.tempdir.path().join("public_module_cc_api.h");
+        let module_h_arg = format!("--h-out-for-module=public_module={}", module_h_path.display());
+        let test_args = test_args.with_extra_crubit_args(&[&module_h_arg]);
+        let test_result = test_args.run().expect("--h-out-for-module should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        let expected_include = format!("#include \"{}\"", module_h_path.display());
+        assert!(h_body.contains(&expected_include), "{h_body}");
+        assert!(!h_body.contains("public_function"), "{h_body}");
+
+        let module_h_body = std::fs::read_to_string(&module_h_path)?;
+        assert!(module_h_body.contains("#pragma once"), "{module_h_body}");
+        assert!(module_h_body.contains("inline void public_function();"), "{module_h_body}");
+        Ok(())
+    }
+
+    /// `test_feature_gated_item_without_cfg` tests that bindings are not
+    /// generated for items that are disabled by `#[cfg(feature = "...")]`.
+    #[test]
+    fn test_feature_gated_item_without_cfg() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--feature-guard-macro=test_feature=HAS_TEST_FEATURE"]);
+        let test_result = test_args.run().expect("Default args should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(!h_body.contains("feature_gated_function"), "{h_body}");
+        assert!(!h_body.contains("HAS_TEST_FEATURE"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_feature_gated_item_with_cfg` tests that `--cfg` is forwarded to the
+    /// Rust compiler and that bindings of the enabled feature-gated items are
+    /// guarded by the macro specified via `--feature-guard-macro`.
+    #[test]
+    fn test_feature_gated_item_with_cfg() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--feature-guard-macro=test_feature=HAS_TEST_FEATURE"])
+            .with_extra_rustc_args(&["--cfg=feature=\"test_feature\""]);
+        let test_result = test_args.run().expect("--cfg should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(
+            h_body.contains("#ifdef HAS_TEST_FEATURE\ninline void feature_gated_function();"),
+            "{h_body}"
+        );
+        assert_eq!(2, h_body.matches("#ifdef HAS_TEST_FEATURE").count(), "{h_body}");
+        assert!(!h_body.contains("#ifdef HAS_TEST_FEATURE\ninline void public_function"));
+        Ok(())
+    }
+
+    /// `test_top_level_namespace` tests that `--top-level-namespace` overrides
+    /// the default top-level namespace (i.e. the crate name).
+    #[test]
+    fn test_top_level_namespace() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--top-level-namespace=outer::inner"]);
+        let test_result = test_args.run().expect("--top-level-namespace should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(h_body.contains("namespace outer::inner {"), "{h_body}");
+        assert!(h_body.contains("}  // namespace outer::inner"), "{h_body}");
+        assert!(!h_body.contains("namespace test_crate"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_emit_ir` tests that `--emit-ir` writes a JSON description of the
+    /// bindings.
+    #[test]
+    fn test_emit_ir() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let ir_path = test_args.tempdir.path().join("test_crate_ir.json");
+        let test_args =
+            test_args.with_extra_crubit_args(&[&format!("--emit-ir={}", ir_path.display())]);
+        test_args.run().expect("--emit-ir should succeed");
+
+        let ir: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&ir_path)?)?;
+        assert_eq!(ir["crate_name"], "test_crate");
+        let items = ir["items"].as_array().expect("`items` should be an array");
+        assert_eq!(items.len(), 1, "{ir}");
+        assert_eq!(items[0]["kind"], "func");
+        assert_eq!(items[0]["cc_name"], "::test_crate::public_module::public_function");
+        assert_eq!(items[0]["ret_type"]["cc"], "void");
+        Ok(())
+    }
+
+    /// `test_error_report` tests that `--error-report` writes a JSON report of
+    /// the unsupported items.
+    #[test]
+    fn test_error_report() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let report_path = test_args.tempdir.path().join("test_crate_errors.json");
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--error-report={}", report_path.display())]);
+        test_args.run().expect("--error-report should succeed");
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&report_path)?)?;
+        assert_eq!(report["crate_name"], "test_crate");
+        // All the public items in the test crate are supported.
+        assert_eq!(report["errors"], serde_json::json!([]));
+        Ok(())
+    }
+
+    /// `test_stats` tests that `--stats` writes a JSON summary of the interop
+    /// coverage of the crate.
+    #[test]
+    fn test_stats() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let stats_path = test_args.tempdir.path().join("test_crate_stats.json");
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--stats={}", stats_path.display())])
+            .with_extra_rustc_args(&["--cfg=feature=\"unsupported_feature\""]);
+        test_args.run().expect("--stats should succeed");
+
+        let stats: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&stats_path)?)?;
+        assert_eq!(stats["crate_name"], "test_crate");
+        assert_eq!(stats["bound_items"], 1);
+        assert_eq!(stats["unsupported_items"], 1);
+        assert_eq!(stats["unsupported_reasons"][0]["reason"], "Tuples are not supported yet");
+        assert_eq!(stats["unsupported_reasons"][0]["error_kind"], "not_yet_implemented");
+        assert_eq!(stats["unsupported_reasons"][0]["code"], "CRUBIT-E0003");
+        assert_eq!(stats["unsupported_reasons"][0]["count"], 1);
+        Ok(())
+    }
+
+    /// `test_report_html` tests that `--report-html` writes an HTML report of
+    /// the interop coverage of the crate.
+    #[test]
+    fn test_report_html() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let report_dir = test_args.tempdir.path().join("report");
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--report-html={}", report_dir.display())])
+            .with_extra_rustc_args(&["--cfg=feature=\"unsupported_feature\""]);
+        test_args.run().expect("--report-html should succeed");
+
+        let html = std::fs::read_to_string(report_dir.join("index.html"))?;
+        assert!(html.contains("<title>C++ bindings of test_crate</title>"), "{html}");
+        assert!(
+            html.contains("Bindings have been generated for 1 out of 2 public items"),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<tr><td><code>public_module::public_function</code></td><td>function</td>\
+                 <td class=\"bound\">bound</td>\
+                 <td><code>void ::test_crate::public_module::public_function()</code></td>"
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<tr><td><code>public_module::unsupported_function</code></td><td>function</td>\
+                 <td class=\"unsupported\">unsupported</td>\
+                 <td><code>CRUBIT-E0003</code> Error handling parameter #0: \
+                 Tuples are not supported yet: (i32, i32) (b/254099023)\
+                 <br>Suggestion: Consider using a struct instead of a tuple</td>"
+            ),
+            "{html}"
+        );
+        // The source location links to the Rust source file.
+        assert!(html.contains("test_crate.rs:2</a>"), "{html}");
+        Ok(())
+    }
+
+    /// `test_fail_on_unsupported` tests that `--fail-on-unsupported` results in
+    /// an error when the crate has unsupported public items.
+    #[test]
+    fn test_fail_on_unsupported() -> anyhow::Result<()> {
+        let err = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--fail-on-unsupported"])
+            .with_extra_rustc_args(&["--cfg=feature=\"unsupported_feature\""])
+            .run()
+            .expect_err("--fail-on-unsupported should fail for an unsupported item");
+        let msg = format!("{err:#}");
+        assert!(
+            msg.starts_with(
+                "Bindings couldn't be generated for 1 public item(s) \
+                 (see `--fail-on-unsupported`):\n\
+                 `public_module::unsupported_function` (function) defined at "
+            ),
+            "{msg}"
+        );
+        assert!(msg.contains("Tuples are not supported yet"), "{msg}");
+        Ok(())
+    }
+
+    /// `test_fail_on_unsupported_without_unsupported_items` tests that
+    /// `--fail-on-unsupported` succeeds when all the public items are
+    /// supported.
+    #[test]
+    fn test_fail_on_unsupported_without_unsupported_items() -> anyhow::Result<()> {
+        TestArgs::default_args()?
+            .with_extra_crubit_args(&["--fail-on-unsupported"])
+            .run()
+            .expect("--fail-on-unsupported should succeed when all items are supported");
+        Ok(())
+    }
+
+    /// `test_fail_on_unsupported_with_filters` tests that unsupported items that
+    /// don't match `--fail-on-unsupported-kind` or `--fail-on-unsupported-path`
+    /// don't result in an error.
+    #[test]
+    fn test_fail_on_unsupported_with_filters() -> anyhow::Result<()> {
+        let run = |filter: &str| -> anyhow::Result<_> {
+            TestArgs::default_args()?
+                .with_extra_crubit_args(&["--fail-on-unsupported", filter])
+                .with_extra_rustc_args(&["--cfg=feature=\"unsupported_feature\""])
+                .run()
+        };
+        run("--fail-on-unsupported-kind=struct").expect("No unsupported structs");
+        run("--fail-on-unsupported-path=other_module::*").expect("No unsupported items there");
+        run("--fail-on-unsupported-kind=function").expect_err("Unsupported function");
+        run("--fail-on-unsupported-path=public_module::*").expect_err("Unsupported function");
+        Ok(())
+    }
+
+    /// `test_bind_only_and_skip` tests that `--bind-only` and `--skip` control
+    /// which items get bindings.
+    #[test]
+    fn test_bind_only_and_skip() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--bind-only=public_module", "--skip=*::public_function"]);
+        let test_result = test_args.run().expect("--bind-only and --skip should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(!h_body.contains("public_function"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_c_mode` tests that `--c-mode` results in a header without
+    /// namespaces.
+    #[test]
+    fn test_c_mode() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?.with_extra_crubit_args(&["--c-mode"]);
+        let test_result = test_args.run().expect("--c-mode should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(
+            h_body.contains("void test_crate_public_module_public_function(void);"),
+            "{h_body}"
+        );
+        assert!(!h_body.contains("namespace"), "{h_body}");
+
+        let rs_body = std::fs::read_to_string(&test_result.rs_path)?;
+        assert!(
+            rs_body.contains("extern \"C\" fn test_crate_public_module_public_function()"),
+            "{rs_body}"
+        );
+        Ok(())
+    }
+
+    /// `test_cc_std` tests that `--cc-std=c++20` results in a check that the
+    /// C++ bindings are compiled in C++20 mode.
+    #[test]
+    fn test_cc_std() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?.with_extra_crubit_args(&["--cc-std=c++20"]);
+        let test_result = test_args.run().expect("--cc-std=c++20 should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(h_body.contains("static_assert(__cplusplus >= 202002"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_cc_fn_naming` tests that `--cc-fn-case`, `--cc-fn-prefix`, and
+    /// `--cc-fn-suffix` change the C++ names of the generated functions.
+    #[test]
+    fn test_cc_fn_naming() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?.with_extra_crubit_args(&[
+            "--cc-fn-case=CamelCase",
+            "--cc-fn-prefix=Rs",
+            "--cc-fn-suffix=V2",
+        ]);
+        let test_result = test_args.run().expect("--cc-fn-case=CamelCase should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(h_body.contains("inline void RsPublicFunctionV2();"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_rename_map` tests that `--rename-map` changes the C++ names of the
+    /// generated bindings.
+    #[test]
+    fn test_rename_map() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let rename_map_path = test_args.tempdir.path().join("rename_map.json");
+        std::fs::write(
+            &rename_map_path,
+            r#"{
+                "crates": { "test_crate": "renamed_crate" },
+                "items": {
+                    "public_module": "renamed_module",
+                    "public_module::public_function": "RenamedFunction"
+                }
+            }"#,
+        )?;
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--rename-map={}", rename_map_path.display())]);
+        let test_result = test_args.run().expect("--rename-map should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(h_body.contains("namespace renamed_crate {"), "{h_body}");
+        assert!(h_body.contains("namespace renamed_module {"), "{h_body}");
+        assert!(h_body.contains("inline void RenamedFunction();"), "{h_body}");
+        assert!(!h_body.contains("public_function"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_rename_map_invalid` tests that an invalid `--rename-map` file is
+    /// reported as an error.
+    #[test]
+    fn test_rename_map_invalid() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let rename_map_path = test_args.tempdir.path().join("rename_map.json");
+        std::fs::write(&rename_map_path, r#"{ "unknown_key": {} }"#)?;
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--rename-map={}", rename_map_path.display())]);
+        let err = test_args.run().expect_err("Unknown keys should be rejected");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("Invalid rename map in"), "{msg}");
+        assert!(msg.contains("unknown field `unknown_key`"), "{msg}");
+        Ok(())
+    }
+
+    /// `test_type_map_invalid` tests that a `--type-map` entry with only one of
+    /// the conversion functions is reported as an error.
+    #[test]
+    fn test_type_map_invalid() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let type_map_path = test_args.tempdir.path().join("type_map.json");
+        std::fs::write(
+            &type_map_path,
+            r#"{
+                "types": {
+                    "public_module::Uuid": { "cc_type": "::base::Uuid", "to_cc": "ToCc" }
+                }
+            }"#,
+        )?;
+        let test_args =
+            test_args.with_extra_crubit_args(&[&format!("--type-map={}", type_map_path.display())]);
+        let err = test_args.run().expect_err("Missing `from_cc` should be rejected");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("Invalid type map in"), "{msg}");
+        assert!(msg.contains("`public_module::Uuid` needs both `to_cc` and `from_cc`"), "{msg}");
+        Ok(())
+    }
+
+    /// `test_target_pointer_size` tests that the generated header asserts the
+    /// pointer size of the `--target` passed to `rustc`.  (Only the standard
+    /// library of the host is available to the tests, so this can't cover a
+    /// target with a different pointer size.)
+    #[test]
+    fn test_target_pointer_size() -> anyhow::Result<()> {
+        let target = format!("--target={}", rustc_session::config::host_triple());
+        let test_args = TestArgs::default_args()?.with_extra_rustc_args(&[&target]);
+        let test_result = test_args.run().expect("--target should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        let pointer_size = get_target_pointer_size();
+        let expected_assertion = format!("static_assert(sizeof(void*) == {pointer_size},");
+        assert!(h_body.contains(&expected_assertion), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_extra_target_same_as_primary_target` tests that `--extra-target`
+    /// rejects targets that the generated `#if` conditions can't distinguish.
+    /// (Only the standard library of the host is available to the tests, so
+    /// the merging of the bindings of different targets is covered by the
+    /// tests of the `multi_target` module.)
+    #[test]
+    fn test_extra_target_same_as_primary_target() -> anyhow::Result<()> {
+        let host_triple = rustc_session::config::host_triple();
+        let test_args = TestArgs::default_args()?
+            .with_extra_crubit_args(&[&format!("--extra-target={host_triple}")]);
+        let err = test_args.run().expect_err("Indistinguishable targets should be rejected");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("The C preprocessor can't distinguish between"), "{msg}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_rustc_args_for_target() {
+        let rustc_args = ["exe", "--target", "a", "foo.rs", "--target=b", "--crate-type=lib"]
+            .map(str::to_string);
+        assert_eq!(
+            get_rustc_args_for_target(&rustc_args, "c"),
+            ["exe", "foo.rs", "--crate-type=lib", "--target=c"].map(str::to_string),
+        );
+    }
+
+    #[test]
+    fn test_get_rs_out_for_extra_target() {
+        assert_eq!(
+            get_rs_out_for_extra_target(Path::new("out/foo_impl.rs"), "x86_64-pc-windows-msvc"),
+            Path::new("out/foo_impl.x86_64-pc-windows-msvc.rs"),
+        );
+        assert_eq!(
+            get_rs_out_for_extra_target(Path::new("foo_impl.rs"), "specs/my-target.json"),
+            Path::new("foo_impl.my-target.rs"),
+        );
+    }
+
+    /// `test_cc_out` tests that `--cc-out` writes a companion C++ source file
+    /// that includes the generated header.
+    #[test]
+    fn test_cc_out() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let cc_path = test_args.tempdir.path().join("test_crate_cc_api.cc");
+        let test_args =
+            test_args.with_extra_crubit_args(&[&format!("--cc-out={}", cc_path.display())]);
+        let test_result = test_args.run().expect("--cc-out should succeed");
+
+        let cc_body = std::fs::read_to_string(&cc_path)?;
+        let expected_include = format!("#include \"{}\"\n", test_result.h_path.display());
+        assert!(cc_body.contains(&expected_include), "{cc_body}");
+        assert!(cc_body.contains("namespace test_crate {"), "{cc_body}");
+        Ok(())
+    }
+
+    /// `test_cc_self_test_out` tests that `--cc-self-test-out` writes a self-test
+    /// C++ source file that includes the generated header and verifies the
+    /// bindings of the test crate.
+    #[test]
+    fn test_cc_self_test_out() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let self_test_path = test_args.tempdir.path().join("test_crate_cc_api_test.cc");
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--cc-self-test-out={}", self_test_path.display())]);
+        let test_result = test_args.run().expect("--cc-self-test-out should succeed");
+
+        let self_test_body = std::fs::read_to_string(&self_test_path)?;
+        let expected_include = format!("#include \"{}\"\n", test_result.h_path.display());
+        assert!(self_test_body.contains(&expected_include), "{self_test_body}");
+        assert!(self_test_body.contains("#include <type_traits>\n"), "{self_test_body}");
+        assert!(
+            self_test_body.contains("decltype(::test_crate::public_module::public_function())"),
+            "{self_test_body}"
+        );
+        Ok(())
+    }
+
+    /// `test_emit_line_directives` tests that `--emit-line-directives` makes
+    /// the generated header point at the Rust source file, and then back at
+    /// the actual lines of the header.
+    #[test]
+    fn test_emit_line_directives() -> anyhow::Result<()> {
+        let test_args =
+            TestArgs::default_args()?.with_extra_crubit_args(&["--emit-line-directives"]);
+        let test_result = test_args.run().expect("--emit-line-directives should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        let rs_input_path = test_args.tempdir.path().join("test_crate.rs");
+        let expected_rs_line = format!("#line 2 \"{}\"\n", rs_input_path.display());
+        assert!(h_body.contains(&expected_rs_line), "{h_body}");
+
+        let expected_h_suffix = format!(" \"{}\"", test_result.h_path.display());
+        let h_lines = h_body
+            .lines()
+            .enumerate()
+            .filter_map(|(line_index, line)| {
+                let line_number = line.strip_prefix("#line ")?.strip_suffix(&expected_h_suffix)?;
+                Some((line_index, line_number))
+            })
+            .collect_vec();
+        assert!(!h_lines.is_empty(), "{h_body}");
+        for (line_index, line_number) in h_lines {
+            assert_eq!(line_number, (line_index + 2).to_string(), "{h_body}");
+        }
+        assert!(!h_body.contains("__crubit_header_line__"), "{h_body}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_remap_path_prefix() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let tempdir_path = test_args.tempdir.path().display().to_string();
+        let report_path = test_args.tempdir.path().join("report.json");
+        let test_args = test_args
+            .with_extra_crubit_args(&[
+                &format!("--remap-path-prefix={tempdir_path}=src"),
+                "--emit-line-directives",
+                "--include-guard=path",
+                &format!("--error-report={}", report_path.display()),
+            ])
+            .with_extra_rustc_args(&["--cfg=feature=\"unsupported_feature\""]);
+        test_args.run().expect("--remap-path-prefix should succeed");
+
+        let h_body = std::fs::read_to_string(test_args.tempdir.path().join("test_crate_cc_api.h"))?;
+        assert!(h_body.contains("Generated from: google3/src/test_crate.rs;l=2"), "{h_body}");
+        assert!(h_body.contains("#line 2 \"src/test_crate.rs\"\n"), "{h_body}");
+        assert!(h_body.contains("\"src/test_crate_cc_api.h\"\n"), "{h_body}");
+        assert!(h_body.contains("#ifndef SRC_TEST_CRATE_CC_API_H_\n"), "{h_body}");
+        assert!(!h_body.contains(&tempdir_path), "{h_body}");
+
+        let report = std::fs::read_to_string(&report_path)?;
+        assert!(report.contains("\"file\": \"src/test_crate.rs\""), "{report}");
+        assert!(!report.contains(&tempdir_path), "{report}");
+        Ok(())
+    }
+
+    /// `test_check` tests that `--check` accepts up-to-date bindings, that it
+    /// reports (but doesn't overwrite) out-of-date and missing files, and that
+    /// it doesn't write the other outputs (e.g. `--emit-ir`).
+    #[test]
+    fn test_check() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let test_result = test_args.run().expect("Default args should succeed");
+        let test_args = test_args.with_extra_crubit_args(&["--check"]);
+        test_args.run().expect("--check should succeed for up-to-date bindings");
+
+        // `--check` doesn't write the outputs other than the bindings.
+        let other_outputs = [
+            ("--emit-ir", test_args.tempdir.path().join("test_crate_ir.json")),
+            ("--stats", test_args.tempdir.path().join("test_crate_stats.json")),
+            ("--report-html", test_args.tempdir.path().join("report")),
+            ("--error-report", test_args.tempdir.path().join("test_crate_errors.json")),
+            ("--snippet-cache", test_args.tempdir.path().join("test_crate_cache.json")),
+        ];
+        let check_args = std::iter::once("--check".to_string())
+            .chain(other_outputs.iter().map(|(flag, path)| format!("{flag}={}", path.display())))
+            .collect_vec();
+        let test_args =
+            test_args.with_extra_crubit_args(&check_args.iter().map(String::as_str).collect_vec());
+        test_args.run().expect("--check should succeed with the other outputs");
+        for (flag, path) in other_outputs.iter() {
+            assert!(!path.exists(), "`--check` shouldn't write the `{flag}` output");
+        }
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        let stale_h_body = h_body.replace("public_function", "stale_function");
+        std::fs::write(&test_result.h_path, &stale_h_body)?;
+        let err = test_args.run().expect_err("--check should fail for out-of-date bindings");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("are out of date (see `--check`)"), "{msg}");
+        assert!(msg.contains(&format!("--- {}\n", test_result.h_path.display())), "{msg}");
+        assert!(msg.contains("\n-inline void stale_function();\n"), "{msg}");
+        assert!(msg.contains("\n+inline void public_function();\n"), "{msg}");
+        assert_eq!(stale_h_body, std::fs::read_to_string(&test_result.h_path)?);
+
+        std::fs::write(&test_result.h_path, &h_body)?;
+        std::fs::remove_file(&test_result.rs_path)?;
+        let err = test_args.run().expect_err("--check should fail for missing bindings");
+        let msg = format!("{err:#}");
+        assert!(msg.contains(&format!("{} can't be read", test_result.rs_path.display())), "{msg}");
+        assert!(!test_result.rs_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_line_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\n";
+        assert_eq!(
+            format_line_diff(old, new),
+            "@@ -2,7 +2,8 @@\n b\n c\n d\n-e\n-f\n-g\n-h\n+E\n+f\n+g\n+h\n+i\n"
+        );
+
+        let old = "a\nb\nc\n";
+        let new = "a\nB\nc\n";
+        assert_eq!(format_line_diff(old, new), "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+    }
+
+    /// `test_dep_file` tests that `--dep-file` writes a Makefile-style
+    /// dependency file that lists the Rust source file of the crate.
+    #[test]
+    fn test_dep_file() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let dep_path = test_args.tempdir.path().join("test_crate_cc_api.d");
+        let test_args =
+            test_args.with_extra_crubit_args(&[&format!("--dep-file={}", dep_path.display())]);
+        let test_result = test_args.run().expect("--dep-file should succeed");
+
+        let dep_body = std::fs::read_to_string(&dep_path)?;
+        let expected_targets =
+            format!("{} {}:", test_result.h_path.display(), test_result.rs_path.display());
+        assert!(dep_body.starts_with(&expected_targets), "{dep_body}");
+        let rs_input_path = test_args.tempdir.path().join("test_crate.rs");
+        assert!(dep_body.contains(&format!(" {}", rs_input_path.display())), "{dep_body}");
+        assert!(dep_body.contains(&format!("\n{}:\n", rs_input_path.display())), "{dep_body}");
+        Ok(())
+    }
+
+    /// `test_cmake_out` tests that `--cmake-out` writes a CMake script that
+    /// defines an imported target for the generated bindings.
+    #[test]
+    fn test_cmake_out() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let cmake_path = test_args.tempdir.path().join("crubit_targets.cmake");
+        let cc_path = test_args.tempdir.path().join("test_crate_cc_api.cc");
+        let lib_path = test_args.tempdir.path().join("libtest_crate.a");
+        let test_args = test_args.with_extra_crubit_args(&[
+            &format!("--cmake-out={}", cmake_path.display()),
+            &format!("--cmake-rust-lib={}", lib_path.display()),
+            &format!("--cc-out={}", cc_path.display()),
+        ]);
+        test_args.run().expect("--cmake-out should succeed");
+
+        let cmake_body = std::fs::read_to_string(&cmake_path)?;
+        assert!(cmake_body.contains("if(NOT TARGET test_crate_cc_api)\n"), "{cmake_body}");
+        assert!(
+            cmake_body.contains("add_library(test_crate_cc_api STATIC IMPORTED)\n"),
+            "{cmake_body}"
+        );
+        assert!(
+            cmake_body.contains(&format!("IMPORTED_LOCATION \"{}\"\n", lib_path.display())),
+            "{cmake_body}"
+        );
+        assert!(
+            cmake_body.contains(&format!(
+                "INTERFACE_INCLUDE_DIRECTORIES \"{}\"",
+                test_args.tempdir.path().display()
+            )),
+            "{cmake_body}"
+        );
+        assert!(
+            cmake_body.contains(&format!("INTERFACE_SOURCES \"{}\"\n", cc_path.display())),
+            "{cmake_body}"
+        );
+        assert!(cmake_body.contains("Threads::Threads"), "{cmake_body}");
+        Ok(())
+    }
+
+    /// `test_cmake_out_for_cdylib` tests that `--cmake-out` defines a shared
+    /// library target for a `cdylib` crate.
+    #[test]
+    fn test_cmake_out_for_cdylib() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let cmake_path = test_args.tempdir.path().join("crubit_targets.cmake");
+        let lib_path = test_args.tempdir.path().join("libtest_crate.so");
+        let test_args = test_args
+            .with_extra_crubit_args(&[
+                &format!("--cmake-out={}", cmake_path.display()),
+                &format!("--cmake-rust-lib={}", lib_path.display()),
+            ])
+            .with_extra_rustc_args(&["--crate-type=cdylib"]);
+        let test_result = test_args.run().expect("--cmake-out should succeed");
+
+        let cmake_body = std::fs::read_to_string(&cmake_path)?;
+        assert!(
+            cmake_body.contains("add_library(test_crate_cc_api SHARED IMPORTED)\n"),
+            "{cmake_body}"
+        );
+        assert!(
+            cmake_body.contains(&format!("IMPORTED_LOCATION \"{}\"\n", lib_path.display())),
+            "{cmake_body}"
+        );
+        assert!(!cmake_body.contains("Threads::Threads"), "{cmake_body}");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(
+            h_body.contains("#include \"crubit/support/for/tests/internal/dylib_import.h\""),
+            "{h_body}"
+        );
+        assert!(h_body.contains("Expected link line: `-L<dir> -ltest_crate`"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_watch_input_snapshot` tests that `InputSnapshot` (used by
+    /// `--watch`) notices deleted and newly created inputs.  (Modifications are
+    /// not tested, because the granularity of file timestamps depends on the
+    /// file system.)
+    #[test]
+    fn test_watch_input_snapshot() -> anyhow::Result<()> {
+        let tempdir = tempdir()?;
+        let rs_input_path = tempdir.path().join("test_crate.rs");
+        std::fs::write(&rs_input_path, "pub fn public_function() {}")?;
+
+        let snapshot = InputSnapshot::new(vec![rs_input_path.clone()]);
+        assert!(!snapshot.has_changed());
+        std::fs::remove_file(&rs_input_path)?;
+        assert!(snapshot.has_changed());
+
+        let snapshot = InputSnapshot::new(vec![rs_input_path.clone()]);
+        assert!(!snapshot.has_changed());
+        std::fs::write(&rs_input_path, "pub fn public_function() {}")?;
+        assert!(snapshot.has_changed());
+        Ok(())
+    }
+
+    /// Writes the source code of a crate from a `--batch` manifest, and returns
+    /// the `rustc_args` of the crate.
+    fn write_batch_crate(
+        tempdir: &TempDir,
+        crate_name: &str,
+        source: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let rs_input_path = tempdir.path().join(format!("{crate_name}.rs"));
+        std::fs::write(&rs_input_path, source)?;
+        Ok(vec![
+            "--codegen=panic=abort".to_string(),
+            "--crate-type=lib".to_string(),
+            format!("--sysroot={}", get_sysroot_for_testing().display()),
+            rs_input_path.display().to_string(),
+        ])
+    }
+
+    fn run_batch_for_testing(tempdir: &TempDir, manifest: serde_json::Value) -> anyhow::Result<()> {
+        let manifest_path = tempdir.path().join("crates.json");
+        std::fs::write(&manifest_path, manifest.to_string())?;
+        run_with_cmdline_args(&[
+            "cc_bindings_from_rs_unittest_executable".to_string(),
+            format!("--batch={}", manifest_path.display()),
+            "--crubit-support-path=crubit/support/for/tests".to_string(),
+            format!("--clang-format-exe-path={CLANG_FORMAT_EXE_PATH_FOR_TESTING}"),
+            format!("--rustfmt-exe-path={RUSTFMT_EXE_PATH_FOR_TESTING}"),
+        ])
+    }
+
+    #[test]
+    fn test_batch() -> anyhow::Result<()> {
+        let tempdir = tempdir()?;
+        let crate_a_args = write_batch_crate(&tempdir, "crate_a", "pub fn func_a() {}")?;
+        let crate_b_args = write_batch_crate(&tempdir, "crate_b", "pub fn func_b() {}")?;
+        let path = |file_name: &str| tempdir.path().join(file_name);
+        run_batch_for_testing(
+            &tempdir,
+            serde_json::json!({
+                "crates": [
+                    {
+                        "crate_name": "crate_a",
+                        "h_out": path("crate_a_cc_api.h"),
+                        "rs_out": path("crate_a_cc_api_impl.rs"),
+                        "rustc_args": crate_a_args,
+                    },
+                    {
+                        "crate_name": "crate_b",
+                        "h_out": path("crate_b_cc_api.h"),
+                        "rs_out": path("crate_b_cc_api_impl.rs"),
+                        "rustc_args": crate_b_args,
+                    },
+                ],
+            }),
+        )
+        .expect("--batch should succeed");
+
+        let h_body_a = std::fs::read_to_string(path("crate_a_cc_api.h"))?;
+        assert!(h_body_a.contains("namespace crate_a {"), "{h_body_a}");
+        assert!(h_body_a.contains("void func_a();"), "{h_body_a}");
+        let h_body_b = std::fs::read_to_string(path("crate_b_cc_api.h"))?;
+        assert!(h_body_b.contains("namespace crate_b {"), "{h_body_b}");
+        assert!(h_body_b.contains("void func_b();"), "{h_body_b}");
+        assert!(path("crate_a_cc_api_impl.rs").exists());
+        assert!(path("crate_b_cc_api_impl.rs").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_crate_name_mismatch() -> anyhow::Result<()> {
+        let tempdir = tempdir()?;
+        let crate_a_args = write_batch_crate(&tempdir, "crate_a", "pub fn func_a() {}")?;
+        let err = run_batch_for_testing(
+            &tempdir,
+            serde_json::json!({
+                "crates": [{
+                    "crate_name": "some_other_crate",
+                    "h_out": tempdir.path().join("crate_a_cc_api.h"),
+                    "rs_out": tempdir.path().join("crate_a_cc_api_impl.rs"),
+                    "rustc_args": crate_a_args,
+                }],
+            }),
+        )
+        .expect_err("Mismatched crate name should trigger an error");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("bindings of the `some_other_crate` crate"), "{msg}");
+        assert!(msg.contains("different crate name: `crate_a`"), "{msg}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_cmdlines() -> anyhow::Result<()> {
+        let cmdline = Cmdline::new(&[
+            "cc_bindings_from_rs_unittest_executable".to_string(),
+            "--batch=crates.json".to_string(),
+            "--crubit-support-path=crubit/support/for/tests".to_string(),
+            "--rustfmt-exe-path=rustfmt.exe".to_string(),
+            "--crate-header=crate_c=explicit/crate_c.h".to_string(),
+        ])?;
+        let manifest = serde_json::from_value(serde_json::json!({
+            "crates": [
+                {
+                    "crate_name": "crate_a",
+                    "h_out": "out/crate_a.h",
+                    "rs_out": "out/crate_a.rs",
+                    "rustc_args": ["crate_a.rs"],
+                },
+                {
+                    "crate_name": "crate_b",
+                    "h_out": "out/crate_b.h",
+                    "h_include": "crate_b.h",
+                    "rs_out": "out/crate_b.rs",
+                    "rustc_args": ["crate_b.rs"],
+                },
+                {
+                    "crate_name": "crate_c",
+                    "h_out": "out/crate_c.h",
+                    "rs_out": "out/crate_c.rs",
+                    "rustc_args": ["crate_c.rs"],
+                },
+            ],
+        }))?;
+        let batch_cmdlines = get_batch_cmdlines(&cmdline, manifest)?;
+        assert_eq!(
+            vec!["crate_a", "crate_b", "crate_c"],
+            batch_cmdlines.iter().map(|(crate_name, _)| crate_name.as_str()).collect_vec()
+        );
+
+        let crate_a_cmdline = &batch_cmdlines[0].1;
+        assert_eq!(Path::new("out/crate_a.h"), crate_a_cmdline.h_out());
+        assert_eq!(Path::new("out/crate_a.rs"), crate_a_cmdline.rs_out());
+        assert!(crate_a_cmdline.batch.is_none());
+        assert_eq!(
+            vec!["cc_bindings_from_rs_unittest_executable", "crate_a.rs"],
+            crate_a_cmdline.rustc_args
+        );
+        // The explicit `--crate-header` comes last (and therefore takes precedence).
+        assert_eq!(
+            vec![
+                ("crate_b".to_string(), "crate_b.h".to_string()),
+                ("crate_c".to_string(), "out/crate_c.h".to_string()),
+                ("crate_c".to_string(), "explicit/crate_c.h".to_string()),
+            ],
+            crate_a_cmdline.crate_headers
+        );
+
+        let crate_c_cmdline = &batch_cmdlines[2].1;
+        assert_eq!(
+            vec![
+                ("crate_a".to_string(), "out/crate_a.h".to_string()),
+                ("crate_b".to_string(), "crate_b.h".to_string()),
+                ("crate_c".to_string(), "explicit/crate_c.h".to_string()),
+            ],
+            crate_c_cmdline.crate_headers
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_duplicate_crates() -> anyhow::Result<()> {
+        let cmdline = Cmdline::new(&[
+            "cc_bindings_from_rs_unittest_executable".to_string(),
+            "--batch=crates.json".to_string(),
+            "--crubit-support-path=crubit/support/for/tests".to_string(),
+            "--rustfmt-exe-path=rustfmt.exe".to_string(),
+        ])?;
+        let crate_a = serde_json::json!({
+            "crate_name": "crate_a",
+            "h_out": "out/crate_a.h",
+            "rs_out": "out/crate_a.rs",
+            "rustc_args": ["crate_a.rs"],
+        });
+        let manifest = serde_json::from_value(serde_json::json!({ "crates": [crate_a, crate_a] }))?;
+        let err =
+            get_batch_cmdlines(&cmdline, manifest).expect_err("Duplicates should be rejected");
+        assert_eq!("Crate(s) listed more than once: crate_a", format!("{err}"));
+        Ok(())
+    }
+
+    /// `test_snippet_cache` tests that `--snippet-cache` writes a cache file
+    /// that can be reused by a later run of the tool.
+    #[test]
+    fn test_snippet_cache() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?;
+        let cache_path = test_args.tempdir.path().join("test_crate_cache.json");
+        let test_args = test_args
+            .with_extra_crubit_args(&[&format!("--snippet-cache={}", cache_path.display())]);
+
+        let test_result = test_args.run().expect("--snippet-cache should succeed");
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(cache_path.exists());
+
+        let test_result = test_args.run().expect("Reusing --snippet-cache should succeed");
+        assert_eq!(h_body, std::fs::read_to_string(&test_result.h_path)?);
+        Ok(())
+    }
+
+    /// `test_include_guard` tests that `--include-guard=path` results in
+    /// `#ifndef`-style include guards.
+    #[test]
+    fn test_include_guard() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?.with_extra_crubit_args(&["--include-guard=path"]);
+        let test_result = test_args.run().expect("--include-guard=path should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        let guard = h_body
+            .lines()
+            .find_map(|line| line.strip_prefix("#ifndef "))
+            .expect("The header should start with an include guard");
+        assert!(guard.ends_with("_TEST_CRATE_CC_API_H_"), "guard = {guard}");
+        assert!(h_body.contains(&format!("#define {guard}\n")), "{h_body}");
+        assert!(h_body.ends_with(&format!("#endif  // {guard}\n")), "{h_body}");
+        assert!(!h_body.contains("#pragma once"), "{h_body}");
+        Ok(())
+    }
+
+    /// `test_inline_namespace` tests that `--inline-namespace=crate-version=...`
+    /// wraps the bindings in an inline namespace.
+    #[test]
+    fn test_inline_namespace() -> anyhow::Result<()> {
+        let test_args = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--inline-namespace=crate-version=1.2.3"]);
+        let test_result = test_args.run().expect("--inline-namespace should succeed");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(h_body.contains("namespace test_crate {\ninline namespace v1_2_3 {"), "{h_body}");
+        Ok(())
+    }
+
     /// `test_cmdline_error_propagation` tests that errors from `Cmdline::new` get
     /// propagated. More detailed test coverage of various specific error types
     /// can be found in tests in `cmdline.rs`.
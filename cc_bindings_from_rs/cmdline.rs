@@ -2,21 +2,133 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Result};
 use clap::Parser;
-use std::path::PathBuf;
+use crubit_feature::{parse_crubit_features, CrubitFeature};
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Parser)]
+#[derive(Clone, Debug, Parser)]
 #[clap(name = "cc_bindings_from_rs")]
 #[clap(about = "Generates C++ bindings for a Rust crate", long_about = None)]
 pub struct Cmdline {
-    /// Output path for C++ header file with bindings.
+    /// Output path for C++ header file with bindings. Required unless
+    /// `--batch` is used.
+    #[clap(long, value_parser, value_name = "FILE", required_unless_present = "batch")]
+    pub h_out: Option<PathBuf>,
+
+    /// Output path for Rust implementation of the bindings. Required unless
+    /// `--batch` is used.
+    #[clap(long, value_parser, value_name = "FILE", required_unless_present = "batch")]
+    pub rs_out: Option<PathBuf>,
+
+    /// Path to a JSON manifest that lists multiple crates (with the `--h-out`
+    /// and `--rs-out` paths and the Rust compiler arguments of each crate).
+    /// The bindings of all the crates are generated by a single invocation of
+    /// the tool, using the same (other) command line flags. The bindings of
+    /// each crate can refer to the types of the other crates from the manifest
+    /// without `--crate-header` flags.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub batch: Option<PathBuf>,
+
+    /// Output path for a JSON description of the API exposed by the generated
+    /// bindings (e.g. functions and types, with their Rust and C++
+    /// signatures).
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub emit_ir: Option<PathBuf>,
+
+    /// Define a symbol whose name contains a hash of the bound API (the ABI
+    /// stamp) in the Rust side of the bindings, and refer to it from the
+    /// generated C++ header(s), so that C++ code that uses a header that is out
+    /// of sync with the Rust side of the bindings fails to link. Requires
+    /// linking the Rust side of the bindings (the `--rs-out` file) even when
+    /// only `extern "C"` functions are called from C++.
+    #[clap(long)]
+    pub abi_stamp: bool,
+
+    /// Output path for a companion C++ source file with the `static_assert`s
+    /// that verify the layouts of the C++ structs from the generated header.
+    /// If not specified, then the assertions are emitted into the generated
+    /// header.  Can't be used together with `--c-mode`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub cc_out: Option<PathBuf>,
+
+    /// Output path for a self-test C++ source file (e.g. `foo_cc_api_test.cc`)
+    /// that validates the generated bindings when it is compiled: the file
+    /// `static_assert`s the layouts and traits of the C++ bindings of the Rust
+    /// types (e.g. `std::is_trivially_destructible_v` and `kIsSend`), and calls
+    /// every bound function in an unevaluated `decltype` operand, so that the
+    /// file only has to be compiled (not linked or run). Can't be used together
+    /// with `--c-mode`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub cc_self_test_out: Option<PathBuf>,
+
+    /// Output path for a Makefile-style dependency file (e.g. for Ninja or
+    /// Make) that lists the inputs of the tool (Rust source files, dependency
+    /// crates, and config files), so that the build system can re-run the tool
+    /// when one of the inputs changes.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub dep_file: Option<PathBuf>,
+
+    /// Path to a file that caches the generated bindings of individual items
+    /// across runs of the tool. The bindings of the items that didn't change
+    /// since the previous run are reused (rather than regenerated), which
+    /// speeds up regenerating the bindings of large crates.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub snippet_cache: Option<PathBuf>,
+
+    /// Keep running after generating the bindings: watch the inputs of the tool
+    /// (the same ones as in the `--dep-file`) and regenerate the bindings
+    /// whenever one of them changes. Combine with `--snippet-cache` to only
+    /// regenerate the bindings of the items that have changed.
+    #[clap(long)]
+    pub watch: bool,
+
+    /// Don't write the generated bindings. Instead, verify that the existing
+    /// `--h-out`, `--rs-out`, `--cc-out`, and `--cc-self-test-out` files (and
+    /// the module headers from `--h-out-for-module`) are up to date,
+    /// and fail with a diff if they are not (e.g. in a presubmit check). The
+    /// other outputs (e.g. `--emit-ir` or `--snippet-cache`) are not written in
+    /// this mode. Can't be used together with `--watch`.
+    #[clap(long)]
+    pub check: bool,
+
+    /// Output path for a CMake script (e.g. `crubit_targets.cmake`) that
+    /// defines an imported `<CRATE_NAME>_cc_api` target for the generated
+    /// bindings (the `--h-out` header, the `--cc-out` source file, and the Rust
+    /// library from `--cmake-rust-lib`), so that CMake projects can use the
+    /// bindings via `target_link_libraries`.  Requires `--cmake-rust-lib`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub cmake_out: Option<PathBuf>,
+
+    /// Path to the Rust library (built from the crate together with the
+    /// `--rs-out` file) that the CMake target from `--cmake-out` links against:
+    /// a static library, or a shared library (the import library on Windows)
+    /// if the crate is a `cdylib`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub cmake_rust_lib: Option<PathBuf>,
+
+    /// Output path for a JSON report of the public items that the generated
+    /// bindings don't support (e.g. the path and source location of each item,
+    /// the error message, its stable error code like `CRUBIT-E0003`, and a
+    /// suggested workaround).  The report also groups the items by the root
+    /// cause of their errors.
     #[clap(long, value_parser, value_name = "FILE")]
-    pub h_out: PathBuf,
+    pub error_report: Option<PathBuf>,
 
-    /// Output path for Rust implementation of the bindings.
+    /// Output path for a JSON summary of the interop coverage of the crate:
+    /// the number of public items that have C++ bindings, and the number of
+    /// the unsupported items (grouped by the reason why bindings couldn't be
+    /// generated for them).
     #[clap(long, value_parser, value_name = "FILE")]
-    pub rs_out: PathBuf,
+    pub stats: Option<PathBuf>,
+
+    /// Output directory for a browsable HTML report of the interop coverage of
+    /// the crate (written as `index.html`). The report lists every public item
+    /// of the crate, together with the C++ signature of its bindings (or the
+    /// reason why bindings couldn't be generated) and a link to its Rust source
+    /// location.
+    #[clap(long, value_parser, value_name = "DIR")]
+    pub report_html: Option<PathBuf>,
 
     /// Path to the `crubit/support` directory in a format that should be used
     /// in the `#include` directives inside the generated C++ files.
@@ -27,10 +139,124 @@ pub struct Cmdline {
     // (not caring about path normalization, directory separator character, etc.).
     pub crubit_support_path: String,
 
+    /// Comma-separated list of the Crubit features enabled for the crate (the
+    /// same features as the ones enabled by the `aspect_hints` of
+    /// `rs_bindings_from_cc`): `supported` is required to generate any
+    /// bindings, and `experimental` enables capabilities that are not stable
+    /// yet. Defaults to `supported`. Example:
+    /// "--crubit-features=supported,experimental".
+    #[clap(long, value_parser = parse_crubit_features_arg, value_name = "FEATURES")]
+    pub crubit_features: Option<flagset::FlagSet<CrubitFeature>>,
+
+    /// Include path of the C++ bindings of a dependency crate, in the form of
+    /// `CRATE_NAME=INCLUDE_PATH`. Can be repeated. Example:
+    /// "--crate-header=other_crate=path/to/other_crate_cc_api.h".
+    #[clap(long = "crate-header", value_parser = parse_crate_header,
+           value_name = "CRATE_NAME=INCLUDE_PATH")]
+    pub crate_headers: Vec<(String, String)>,
+
+    /// Top-level C++ namespace of the C++ bindings of a dependency crate, in
+    /// the form of `CRATE_NAME=NAMESPACE`. Can be repeated. Defaults to the
+    /// name of the crate. Example: "--crate-namespace=other_crate=other::ns".
+    #[clap(long = "crate-namespace", value_parser = parse_crate_namespace,
+           value_name = "CRATE_NAME=NAMESPACE")]
+    pub crate_namespaces: Vec<(String, String)>,
+
+    /// Fail if bindings can't be generated for some of the public items of the
+    /// crate (instead of only explaining the problem in a comment in the
+    /// generated C++ header).
+    #[clap(long)]
+    pub fail_on_unsupported: bool,
+
+    /// Only fail due to `--fail-on-unsupported` for the given kind of items
+    /// (e.g. `function` or `struct`). Can be repeated.
+    #[clap(long = "fail-on-unsupported-kind", value_parser, value_name = "KIND")]
+    pub fail_on_unsupported_kinds: Vec<String>,
+
+    /// Only fail due to `--fail-on-unsupported` for items with a Rust path
+    /// (relative to the crate root) that matches the given pattern. `*`
+    /// matches any sequence of characters. Can be repeated. Example:
+    /// "--fail-on-unsupported-path=some_module::*".
+    #[clap(long = "fail-on-unsupported-path", value_parser, value_name = "PATTERN")]
+    pub fail_on_unsupported_paths: Vec<String>,
+
+    /// C++ preprocessor macro that should guard the bindings of items that are
+    /// only present when the given Cargo feature is enabled (e.g. when
+    /// `--cfg feature="FEATURE"` is passed to the Rust compiler), in the form
+    /// of `FEATURE=MACRO`. Can be repeated. Example:
+    /// "--feature-guard-macro=serde=MY_CRATE_HAS_SERDE".
+    #[clap(long = "feature-guard-macro", value_parser = parse_feature_guard_macro,
+           value_name = "FEATURE=MACRO")]
+    pub feature_guard_macros: Vec<(String, String)>,
+
+    /// Style of the include guards of the generated C++ headers: `pragma-once`
+    /// (the default), `path` (`#ifndef` guards with a macro name derived from
+    /// the path of the header), or `hash` (`#ifndef` guards with a macro name
+    /// derived from a hash of the path of the header).
+    #[clap(long, value_parser = parse_include_guard_style, value_name = "STYLE")]
+    pub include_guard: Option<IncludeGuardStyle>,
+
+    /// Wraps the bindings of the crate in a C++ inline namespace (e.g.
+    /// `namespace foo { inline namespace v1_2_3 { ... } }`), so that the
+    /// bindings of different versions of the crate can coexist in one C++
+    /// binary, and so that mixing them up fails at link time. The name of the
+    /// inline namespace is derived either from `crate-version=VERSION` (e.g.
+    /// `crate-version=1.2.3` results in `v1_2_3`), or from a hash of the
+    /// generated bindings (`abi-hash`). Can't be used together with
+    /// `--c-mode`.
+    #[clap(long, value_parser = parse_inline_namespace, value_name = "SOURCE")]
+    pub inline_namespace: Option<InlineNamespaceSource>,
+
+    /// Version of the C++ standard targeted by the generated C++ bindings:
+    /// `c++17` (the default) or `c++20`. Bindings that target C++20 may use
+    /// C++20 features and fail to compile in C++17 mode. Can't be used
+    /// together with `--c-mode`.
+    #[clap(long, value_parser = parse_cc_std, value_name = "STD")]
+    pub cc_std: Option<CcStd>,
+
+    /// Case of the C++ names of the bindings of functions and methods:
+    /// `snake_case` (the default - the Rust names are used as-is) or
+    /// `CamelCase` (`snake_case` Rust names are converted into `CamelCase`, as
+    /// recommended by the Google C++ Style Guide). Can be overridden for an
+    /// item (and for all the functions inside a module or an `impl` block) by
+    /// `#[crubit::cc_case(...)]`. Can't be used together with `--c-mode`.
+    #[clap(long, value_parser = parse_cc_fn_case, value_name = "CASE")]
+    pub cc_fn_case: Option<CcFnCase>,
+
+    /// Prefix of the C++ names of the bindings of functions and methods
+    /// (except for functions with a `#[crubit::rename(...)]` annotation).
+    /// Can't be used together with `--c-mode`. Example: "--cc-fn-prefix=Rs".
+    #[clap(long, value_parser, value_name = "PREFIX", empty_values = false)]
+    pub cc_fn_prefix: Option<String>,
+
+    /// Suffix of the C++ names of the bindings of functions and methods
+    /// (except for functions with a `#[crubit::rename(...)]` annotation).
+    /// Can't be used together with `--c-mode`. Example: "--cc-fn-suffix=_rs".
+    #[clap(long, value_parser, value_name = "SUFFIX", empty_values = false)]
+    pub cc_fn_suffix: Option<String>,
+
+    /// How the C++ callers of the bindings of `unsafe` Rust functions opt into
+    /// upholding their safety requirements: `token` (the default - the C++
+    /// functions take an extra, leading `rs_std::UnsafeCall` parameter) or
+    /// `suffix` (the C++ names of the functions get an `_unsafe` suffix). Can't
+    /// be used together with `--c-mode`.
+    #[clap(long, value_parser = parse_unsafe_fn_style, value_name = "STYLE")]
+    pub unsafe_fn_style: Option<UnsafeFnStyle>,
+
+    /// The library of the Rust standard library family that the Rust side of
+    /// the bindings may depend on: `std`, `alloc`, or `core`. Without `std`,
+    /// the generated Rust code is `#![no_std]` (and, without `alloc`, types
+    /// like `Cow<str>` are not supported). Defaults to `std`, unless the crate
+    /// is `#![no_std]` - then `alloc` is used if the crate depends on the
+    /// `alloc` crate, and `core` otherwise.
+    #[clap(long, value_parser = parse_std_lib, value_name = "LIB")]
+    pub rust_std_lib: Option<StdLib>,
+
     /// Path to a clang-format executable that will be used to format the
-    /// C++ header files generated by the tool.
+    /// C++ header files generated by the tool. If not specified, then a
+    /// built-in formatter is used instead.
     #[clap(long, value_parser, value_name = "FILE")]
-    pub clang_format_exe_path: PathBuf,
+    pub clang_format_exe_path: Option<PathBuf>,
 
     /// Path to a rustfmt executable that will be used to format the
     /// Rust source files generated by the tool.
@@ -42,6 +268,99 @@ pub struct Cmdline {
     #[clap(long, value_parser, value_name = "FILE")]
     pub rustfmt_config_path: Option<PathBuf>,
 
+    /// Only generate bindings for the items with a Rust path (relative to the
+    /// crate root) that is covered by the given filter. A filter covers the
+    /// item with the given path and all the items nested inside it, unless the
+    /// filter contains a `*` (which matches any sequence of characters). Can be
+    /// repeated. Example: "--bind-only=some_module::public_api".
+    #[clap(long, value_parser, value_name = "PATH", empty_values = false)]
+    pub bind_only: Vec<String>,
+
+    /// Don't generate bindings for the items with a Rust path (relative to the
+    /// crate root) that is covered by the given filter (see `--bind-only`).
+    /// Can be repeated. Example: "--skip=some_module::internal".
+    #[clap(long, value_parser, value_name = "PATH", empty_values = false)]
+    pub skip: Vec<String>,
+
+    /// Path to a JSON file that renames the C++ bindings without modifying the
+    /// Rust sources. The file may contain a "crates" object (mapping crate
+    /// names to top-level C++ namespaces, like `--crate-namespace`) and an
+    /// "items" object (mapping Rust paths of items to C++ names). Paths of the
+    /// items from the current crate are relative to the crate root. Renaming a
+    /// module renames its C++ namespace. The "items" entries take precedence
+    /// over `#[crubit::rename(...)]` annotations, and `--crate-namespace` takes
+    /// precedence over the "crates" entries.
+    /// Example: {"items": {"some_module::SomeStruct": "CcStruct"}}.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub rename_map: Option<PathBuf>,
+
+    /// Path to a JSON file that maps Rust types to pre-existing C++ types,
+    /// without modifying the Rust sources. The "types" object maps the Rust
+    /// paths of types (in the same format as the "items" of `--rename-map`) to
+    /// objects with a "cc_type" (a fully qualified C++ type), optional
+    /// "cc_headers" (the headers that declare the C++ type and the conversion
+    /// functions), and optional "to_cc" and "from_cc" (fully qualified names of
+    /// C++ functions that convert the C++ bindings of the Rust type into the
+    /// C++ type and back). Without the conversion functions, the C++ type has
+    /// to have the same layout as the Rust type, and the C++ bindings of the
+    /// Rust type are not generated.
+    /// Example: {"types": {"some_module::Uuid": {"cc_type": "::base::Uuid"}}}.
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub type_map: Option<PathBuf>,
+
+    /// Generate a header that only uses C-compatible declarations (no
+    /// namespaces, no references, no passing of structs by value), so that the
+    /// bindings can be used from C (and not only from C++). Structs are opaque
+    /// and `typedef`ed, and functions get `snake_case` names prefixed with the
+    /// top-level namespace and the module path.
+    #[clap(long)]
+    pub c_mode: bool,
+
+    /// Precede the declarations in the generated C++ header(s) with `#line`
+    /// directives that point at the Rust definitions of the corresponding
+    /// items, so that C++ compiler errors (and "go to definition" in IDEs) lead
+    /// to the Rust source code.
+    #[clap(long)]
+    pub emit_line_directives: bool,
+
+    /// Replace the `FROM` prefix with `TO` in the file paths that are embedded
+    /// into the generated bindings (e.g. into the comments with the source
+    /// locations of the Rust items), in the same way as the Rust compiler's
+    /// `--remap-path-prefix` (which is also respected). Can be repeated.
+    /// Example: "--remap-path-prefix=/home/user/src=".
+    #[clap(long = "remap-path-prefix", value_parser = parse_remap_path_prefix,
+           value_name = "FROM=TO")]
+    pub remap_path_prefixes: Vec<(String, String)>,
+
+    /// Additional Rust target triple to generate the bindings for (e.g.
+    /// `x86_64-pc-windows-msvc`). Can be repeated. The Rust compiler runs once
+    /// per target (the primary target is the one from the Rust compiler
+    /// arguments), and the generated C++ files serve all the targets: the lines
+    /// that differ between the targets (e.g. struct layouts) are guarded by
+    /// `#if defined(...)` conditions. The Rust side of the bindings for each
+    /// additional target is written next to the `--rs-out` file, with the
+    /// target triple added to the file name (e.g.
+    /// `foo_impl.x86_64-pc-windows-msvc.rs`). Can't be used together with
+    /// `--batch`, `--watch`, or `--emit-line-directives`.
+    #[clap(long = "extra-target", value_parser, value_name = "TRIPLE", empty_values = false)]
+    pub extra_targets: Vec<String>,
+
+    /// Output path for a separate C++ header with the bindings of the given
+    /// top-level module of the crate, in the form of `MODULE=FILE`. Can be
+    /// repeated. The bindings of the other items stay in the `--h-out` header,
+    /// which `#include`s all the module headers. The paths are also used in the
+    /// `#include`s of the generated headers. Example:
+    /// "--h-out-for-module=some_module=path/to/some_module_cc_api.h".
+    #[clap(long = "h-out-for-module", value_parser = parse_h_out_for_module,
+           value_name = "MODULE=FILE")]
+    pub h_out_for_modules: Vec<(String, String)>,
+
+    /// Top-level C++ namespace of the generated bindings. Nested namespaces
+    /// can be specified using `::` as a separator. Defaults to the name of
+    /// the crate. Example: "--top-level-namespace=foo::bar".
+    #[clap(long, value_parser, value_name = "NAMESPACE", empty_values = false)]
+    pub top_level_namespace: Option<String>,
+
     /// Command line arguments of the Rust compiler.
     #[clap(last = true, value_parser)]
     pub rustc_args: Vec<String>,
@@ -64,6 +383,94 @@ impl Cmdline {
 
         // Parse `args` using the parser `derive`d by the `clap` crate.
         let mut cmdline = Self::try_parse_from(args)?;
+        ensure!(
+            cmdline.fail_on_unsupported
+                || (cmdline.fail_on_unsupported_kinds.is_empty()
+                    && cmdline.fail_on_unsupported_paths.is_empty()),
+            "`--fail-on-unsupported-kind` and `--fail-on-unsupported-path` \
+             require `--fail-on-unsupported`"
+        );
+        ensure!(
+            !cmdline.c_mode || cmdline.cc_std.is_none(),
+            "`--cc-std` can't be used together with `--c-mode`"
+        );
+        ensure!(
+            !cmdline.c_mode
+                || (cmdline.cc_fn_case.is_none()
+                    && cmdline.cc_fn_prefix.is_none()
+                    && cmdline.cc_fn_suffix.is_none()),
+            "`--cc-fn-case`, `--cc-fn-prefix`, and `--cc-fn-suffix` can't be used together \
+             with `--c-mode`"
+        );
+        ensure!(
+            !cmdline.c_mode || cmdline.unsafe_fn_style.is_none(),
+            "`--unsafe-fn-style` can't be used together with `--c-mode`"
+        );
+        ensure!(
+            !cmdline.c_mode || cmdline.cc_out.is_none(),
+            "`--cc-out` can't be used together with `--c-mode`"
+        );
+        ensure!(
+            !cmdline.c_mode || cmdline.cc_self_test_out.is_none(),
+            "`--cc-self-test-out` can't be used together with `--c-mode`"
+        );
+        ensure!(
+            !cmdline.c_mode || cmdline.inline_namespace.is_none(),
+            "`--inline-namespace` can't be used together with `--c-mode`"
+        );
+        // The crates from the batch have different versions.
+        ensure!(
+            cmdline.batch.is_none()
+                || !matches!(
+                    cmdline.inline_namespace,
+                    Some(InlineNamespaceSource::CrateVersion(_))
+                ),
+            "`--inline-namespace=crate-version=...` can't be used together with `--batch`"
+        );
+        ensure!(
+            cmdline.cmake_out.is_some() == cmdline.cmake_rust_lib.is_some(),
+            "`--cmake-out` and `--cmake-rust-lib` have to be used together"
+        );
+        ensure!(
+            cmdline.batch.is_none()
+                || (cmdline.h_out.is_none()
+                    && cmdline.rs_out.is_none()
+                    && cmdline.rustc_args.is_empty()),
+            "`--h-out`, `--rs-out`, and the Rust compiler arguments of each crate have to be \
+             specified in the `--batch` manifest"
+        );
+        // The outputs of these flags would be overwritten by each crate from the batch.
+        ensure!(
+            cmdline.batch.is_none()
+                || (cmdline.emit_ir.is_none()
+                    && cmdline.cc_out.is_none()
+                    && cmdline.cc_self_test_out.is_none()
+                    && cmdline.dep_file.is_none()
+                    && cmdline.snippet_cache.is_none()
+                    && cmdline.cmake_out.is_none()
+                    && cmdline.error_report.is_none()
+                    && cmdline.stats.is_none()
+                    && cmdline.report_html.is_none()
+                    && cmdline.top_level_namespace.is_none()
+                    && cmdline.h_out_for_modules.is_empty()
+                    && !cmdline.watch),
+            "`--emit-ir`, `--cc-out`, `--cc-self-test-out`, `--dep-file`, `--snippet-cache`, \
+             `--cmake-out`, `--error-report`, `--stats`, `--report-html`, \
+             `--top-level-namespace`, `--h-out-for-module`, and `--watch` can't be used \
+             together with `--batch`"
+        );
+
+        ensure!(
+            cmdline.extra_targets.is_empty()
+                || (cmdline.batch.is_none() && !cmdline.watch && !cmdline.emit_line_directives),
+            "`--batch`, `--watch`, and `--emit-line-directives` can't be used together with \
+             `--extra-target`"
+        );
+
+        ensure!(
+            !cmdline.check || !cmdline.watch,
+            "`--watch` can't be used together with `--check`"
+        );
 
         // For compatibility with `rustc_driver` expectations, we prepend `exe_name` to
         // `rustc_args.  This is needed, because `rustc_driver::RunCompiler::new`
@@ -74,6 +481,170 @@ impl Cmdline {
 
         Ok(cmdline)
     }
+
+    /// Returns the `--h-out` path.
+    ///
+    /// Panics in `--batch` mode (`run_batch` uses a separate `Cmdline` with
+    /// the `--h-out` path of each crate from the batch).
+    pub fn h_out(&self) -> &Path {
+        self.h_out.as_deref().expect("`--h-out` is required unless `--batch` is used")
+    }
+
+    /// Returns the `--rs-out` path (see also `h_out`).
+    pub fn rs_out(&self) -> &Path {
+        self.rs_out.as_deref().expect("`--rs-out` is required unless `--batch` is used")
+    }
+}
+
+/// Source of the name of the C++ inline namespace of the generated bindings
+/// (see the `--inline-namespace` flag).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InlineNamespaceSource {
+    CrateVersion(String),
+    AbiHash,
+}
+
+/// Parses an `--inline-namespace` argument.
+fn parse_inline_namespace(s: &str) -> Result<InlineNamespaceSource> {
+    match s.split_once('=') {
+        None if s == "abi-hash" => Ok(InlineNamespaceSource::AbiHash),
+        Some(("crate-version", version)) if !version.is_empty() => {
+            Ok(InlineNamespaceSource::CrateVersion(version.to_string()))
+        }
+        _ => bail!("Expected `crate-version=VERSION` or `abi-hash`, but got `{s}`"),
+    }
+}
+
+/// Style of the include guards of the generated C++ headers (see the
+/// `--include-guard` flag).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IncludeGuardStyle {
+    PragmaOnce,
+    PathBased,
+    HashBased,
+}
+
+/// Parses an `--include-guard` argument.
+fn parse_include_guard_style(s: &str) -> Result<IncludeGuardStyle> {
+    match s {
+        "pragma-once" => Ok(IncludeGuardStyle::PragmaOnce),
+        "path" => Ok(IncludeGuardStyle::PathBased),
+        "hash" => Ok(IncludeGuardStyle::HashBased),
+        _ => bail!("Expected `pragma-once`, `path`, or `hash`, but got `{s}`"),
+    }
+}
+
+/// Version of the C++ standard targeted by the generated C++ bindings (see the
+/// `--cc-std` flag).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CcStd {
+    Cxx17,
+    Cxx20,
+}
+
+/// Parses a `--cc-std` argument.
+fn parse_cc_std(s: &str) -> Result<CcStd> {
+    match s {
+        "c++17" => Ok(CcStd::Cxx17),
+        "c++20" => Ok(CcStd::Cxx20),
+        _ => bail!("Expected `c++17` or `c++20`, but got `{s}`"),
+    }
+}
+
+/// Case of the C++ names of the bindings of functions and methods (see the
+/// `--cc-fn-case` flag).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CcFnCase {
+    SnakeCase,
+    CamelCase,
+}
+
+/// Parses a `--cc-fn-case` argument.
+fn parse_cc_fn_case(s: &str) -> Result<CcFnCase> {
+    match s {
+        "snake_case" => Ok(CcFnCase::SnakeCase),
+        "CamelCase" => Ok(CcFnCase::CamelCase),
+        _ => bail!("Expected `snake_case` or `CamelCase`, but got `{s}`"),
+    }
+}
+
+/// How the C++ callers of the bindings of `unsafe` Rust functions opt into
+/// calling them (see the `--unsafe-fn-style` flag).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnsafeFnStyle {
+    Token,
+    Suffix,
+}
+
+/// Parses an `--unsafe-fn-style` argument.
+fn parse_unsafe_fn_style(s: &str) -> Result<UnsafeFnStyle> {
+    match s {
+        "token" => Ok(UnsafeFnStyle::Token),
+        "suffix" => Ok(UnsafeFnStyle::Suffix),
+        _ => bail!("Expected `token` or `suffix`, but got `{s}`"),
+    }
+}
+
+/// The library of the Rust standard library family that the Rust side of the
+/// bindings may depend on (see the `--rust-std-lib` flag).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StdLib {
+    Std,
+    Alloc,
+    Core,
+}
+
+/// Parses a `--rust-std-lib` argument.
+fn parse_std_lib(s: &str) -> Result<StdLib> {
+    match s {
+        "std" => Ok(StdLib::Std),
+        "alloc" => Ok(StdLib::Alloc),
+        "core" => Ok(StdLib::Core),
+        _ => bail!("Expected `std`, `alloc`, or `core`, but got `{s}`"),
+    }
+}
+
+/// Parses a `--crubit-features` argument.
+fn parse_crubit_features_arg(s: &str) -> Result<flagset::FlagSet<CrubitFeature>> {
+    parse_crubit_features(s).map_err(|err| anyhow!(err))
+}
+
+/// Parses a `--crate-header` argument: `CRATE_NAME=INCLUDE_PATH`.
+fn parse_crate_header(s: &str) -> Result<(String, String)> {
+    parse_key_value_pair(s, "CRATE_NAME", "INCLUDE_PATH")
+}
+
+/// Parses an `--h-out-for-module` argument: `MODULE=FILE`.
+fn parse_h_out_for_module(s: &str) -> Result<(String, String)> {
+    parse_key_value_pair(s, "MODULE", "FILE")
+}
+
+/// Parses a `--crate-namespace` argument: `CRATE_NAME=NAMESPACE`.
+fn parse_crate_namespace(s: &str) -> Result<(String, String)> {
+    parse_key_value_pair(s, "CRATE_NAME", "NAMESPACE")
+}
+
+/// Parses a `--feature-guard-macro` argument: `FEATURE=MACRO`.
+fn parse_feature_guard_macro(s: &str) -> Result<(String, String)> {
+    parse_key_value_pair(s, "FEATURE", "MACRO")
+}
+
+/// Parses a `--remap-path-prefix` argument: `FROM=TO`.  Like in the Rust
+/// compiler, `TO` may be empty and `FROM` ends at the last `=`.
+fn parse_remap_path_prefix(s: &str) -> Result<(String, String)> {
+    let (from, to) =
+        s.rsplit_once('=').ok_or_else(|| anyhow!("Expected `FROM=TO`, but got `{s}`"))?;
+    ensure!(!from.is_empty(), "Empty FROM in `{s}`");
+    Ok((from.to_string(), to.to_string()))
+}
+
+fn parse_key_value_pair(s: &str, key_name: &str, value_name: &str) -> Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected `{key_name}={value_name}`, but got `{s}`"))?;
+    ensure!(!key.is_empty(), "Empty {key_name} in `{s}`");
+    ensure!(!value.is_empty(), "Empty {value_name} in `{s}`");
+    Ok((key.to_string(), value.to_string()))
 }
 
 #[cfg(test)]
@@ -105,98 +676,1292 @@ mod tests {
         ])
         .unwrap();
 
-        assert_eq!(Path::new("foo.h"), cmdline.h_out);
-        assert_eq!(Path::new("foo_impl.rs"), cmdline.rs_out);
+        assert_eq!(Path::new("foo.h"), cmdline.h_out());
+        assert_eq!(Path::new("foo_impl.rs"), cmdline.rs_out());
         assert_eq!("crubit/support/for/tests", &*cmdline.crubit_support_path);
-        assert_eq!(Path::new("clang-format.exe"), cmdline.clang_format_exe_path);
+        assert_eq!(Some(Path::new("clang-format.exe")), cmdline.clang_format_exe_path.as_deref());
         assert_eq!(Path::new("rustfmt.exe"), cmdline.rustfmt_exe_path);
         assert!(cmdline.rustfmt_config_path.is_none());
+        assert!(cmdline.crate_headers.is_empty());
+        assert!(cmdline.crate_namespaces.is_empty());
+        assert!(cmdline.top_level_namespace.is_none());
+        assert!(cmdline.include_guard.is_none());
+        assert!(cmdline.inline_namespace.is_none());
+        assert!(cmdline.cc_std.is_none());
+        assert!(cmdline.crubit_features.is_none());
+        assert!(cmdline.cc_fn_case.is_none());
+        assert!(cmdline.cc_fn_prefix.is_none());
+        assert!(cmdline.cc_fn_suffix.is_none());
+        assert!(cmdline.rust_std_lib.is_none());
+        assert!(cmdline.feature_guard_macros.is_empty());
+        assert!(cmdline.remap_path_prefixes.is_empty());
+        assert!(cmdline.extra_targets.is_empty());
+        assert!(cmdline.h_out_for_modules.is_empty());
+        assert!(cmdline.batch.is_none());
+        assert!(cmdline.emit_ir.is_none());
+        assert!(!cmdline.emit_line_directives);
+        assert!(!cmdline.abi_stamp);
+        assert!(cmdline.cc_out.is_none());
+        assert!(cmdline.cc_self_test_out.is_none());
+        assert!(cmdline.dep_file.is_none());
+        assert!(cmdline.snippet_cache.is_none());
+        assert!(!cmdline.watch);
+        assert!(!cmdline.check);
+        assert!(cmdline.cmake_out.is_none());
+        assert!(cmdline.cmake_rust_lib.is_none());
+        assert!(cmdline.error_report.is_none());
+        assert!(cmdline.stats.is_none());
+        assert!(cmdline.report_html.is_none());
+        assert!(!cmdline.fail_on_unsupported);
+        assert!(cmdline.fail_on_unsupported_kinds.is_empty());
+        assert!(cmdline.fail_on_unsupported_paths.is_empty());
+        assert!(cmdline.bind_only.is_empty());
+        assert!(cmdline.skip.is_empty());
+        assert!(cmdline.rename_map.is_none());
+        assert!(cmdline.type_map.is_none());
+        assert!(!cmdline.c_mode);
         // Ignoring `rustc_args` in this test - they are covered in a separate
         // test below: `test_rustc_args_happy_path`.
     }
 
     #[test]
-    fn test_rustc_args_happy_path() {
-        // Note that this test would fail without the `--` separator.
+    fn test_clang_format_exe_path_is_optional() {
         let cmdline = new_cmdline([
             "--h-out=foo.h",
             "--rs-out=foo_impl.rs",
             "--crubit-support-path=crubit/support/for/tests",
-            "--clang-format-exe-path=clang-format.exe",
             "--rustfmt-exe-path=rustfmt.exe",
-            "--",
-            "test.rs",
-            "--crate-type=lib",
         ])
         .unwrap();
+        assert!(cmdline.clang_format_exe_path.is_none());
+    }
 
-        let rustc_args = &cmdline.rustc_args;
-        assert!(
-            itertools::equal(
-                ["cc_bindings_from_rs_unittest_executable", "test.rs", "--crate-type=lib"],
-                rustc_args
-            ),
-            "rustc_args = {:?}",
-            rustc_args,
-        );
+    #[test]
+    fn test_emit_ir() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--emit-ir=foo_ir.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo_ir.json")), cmdline.emit_ir.as_deref());
     }
 
-    /// The `test_help` unit test below has multiple purposes:
-    /// - Direct/obvious purpose: testing that `--help` works
-    /// - Double-checking the overall shape of our cmdline "API" (i.e.
-    ///   verification that the way we use `clap` attributes results in the
-    ///   desired cmdline "API"). This is a good enough coverage to avoid having
-    ///   flag-specifc tests (e.g. avoiding hypothetical
-    ///   `test_h_out_missing_flag`, `test_h_out_missing_arg`,
-    ///   `test_h_out_duplicated`).
-    /// - Exhaustively checking runtime asserts (assumming that tests run in a
-    ///   debug build; other tests also trigger these asserts).  See also:
-    ///     - https://github.com/clap-rs/clap/issues/2740#issuecomment-907240414
-    ///     - `clap::builder::App::debug_assert`
-    ///
-    /// To regenerate `expected_msg` do the following steps:
-    /// - Run `bazel run :cc_bindings_from_rs_legacy_toolchain_runner -- --help`
-    /// - Copy&paste the output of the command below
-    /// - Replace the 2nd `cc_bindings_from_rs` with
-    ///   `cc_bindings_from_rs_unittest_executable`
     #[test]
-    fn test_help() {
-        let anyhow_err = new_cmdline(["--help"]).expect_err("--help should trigger an error");
-        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
-        let expected_msg = r#"cc_bindings_from_rs 
-Generates C++ bindings for a Rust crate
+    fn test_cc_out() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--cc-out=foo.cc",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo.cc")), cmdline.cc_out.as_deref());
+    }
 
-USAGE:
-    cc_bindings_from_rs_unittest_executable [OPTIONS] --h-out <FILE> --rs-out <FILE> --crubit-support-path <STRING> --clang-format-exe-path <FILE> --rustfmt-exe-path <FILE> [-- <RUSTC_ARGS>...]
+    #[test]
+    fn test_cc_self_test_out() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--cc-self-test-out=foo_test.cc",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo_test.cc")), cmdline.cc_self_test_out.as_deref());
+    }
 
-ARGS:
-    <RUSTC_ARGS>...    Command line arguments of the Rust compiler
+    #[test]
+    fn test_dep_file() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--dep-file=foo.d",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo.d")), cmdline.dep_file.as_deref());
+    }
 
-OPTIONS:
-        --clang-format-exe-path <FILE>
-            Path to a clang-format executable that will be used to format the C++ header files
-            generated by the tool
+    #[test]
+    fn test_snippet_cache() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--snippet-cache=foo_cache.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo_cache.json")), cmdline.snippet_cache.as_deref());
+    }
 
-        --crubit-support-path <STRING>
-            Path to the `crubit/support` directory in a format that should be used in the `#include`
-            directives inside the generated C++ files. Example: "crubit/support"
+    #[test]
+    fn test_watch() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--watch",
+            "--snippet-cache=foo_cache.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert!(cmdline.watch);
+    }
 
-        --h-out <FILE>
-            Output path for C++ header file with bindings
+    #[test]
+    fn test_batch() {
+        let cmdline = new_cmdline([
+            "--batch=crates.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("crates.json")), cmdline.batch.as_deref());
+        assert!(cmdline.h_out.is_none());
+        assert!(cmdline.rs_out.is_none());
+        assert!(itertools::equal(["cc_bindings_from_rs_unittest_executable"], &cmdline.rustc_args));
+    }
 
-    -h, --help
-            Print help information
+    #[test]
+    fn test_batch_with_h_out() {
+        let err = new_cmdline([
+            "--batch=crates.json",
+            "--h-out=foo.h",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .expect_err("--batch and --h-out should conflict");
+        assert_eq!(
+            "`--h-out`, `--rs-out`, and the Rust compiler arguments of each crate have to be \
+             specified in the `--batch` manifest",
+            format!("{err}")
+        );
+    }
 
-        --rs-out <FILE>
-            Output path for Rust implementation of the bindings
+    #[test]
+    fn test_batch_with_dep_file() {
+        let err = new_cmdline([
+            "--batch=crates.json",
+            "--dep-file=foo.d",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .expect_err("--batch and --dep-file should conflict");
+        assert!(format!("{err}").contains("can't be used together with `--batch`"), "err = {err}");
+    }
 
-        --rustfmt-config-path <FILE>
-            Path to a rustfmt.toml file that should replace the default formatting of the .rs files
-            generated by the tool
+    #[test]
+    fn test_cmake_out() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--cmake-out=crubit_targets.cmake",
+            "--cmake-rust-lib=libfoo.a",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("crubit_targets.cmake")), cmdline.cmake_out.as_deref());
+        assert_eq!(Some(Path::new("libfoo.a")), cmdline.cmake_rust_lib.as_deref());
+    }
 
-        --rustfmt-exe-path <FILE>
-            Path to a rustfmt executable that will be used to format the Rust source files generated
-            by the tool
+    #[test]
+    fn test_cmake_out_without_cmake_rust_lib() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--cmake-out=crubit_targets.cmake",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .expect_err("--cmake-out without --cmake-rust-lib should trigger an error");
+        assert_eq!(
+            "`--cmake-out` and `--cmake-rust-lib` have to be used together",
+            format!("{err}")
+        );
+    }
+
+    #[test]
+    fn test_cc_out_with_c_mode() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--cc-out=foo.cc",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--c-mode",
+        ])
+        .expect_err("--cc-out and --c-mode should conflict");
+        assert_eq!("`--cc-out` can't be used together with `--c-mode`", format!("{err}"));
+    }
+
+    #[test]
+    fn test_cc_self_test_out_with_c_mode() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--cc-self-test-out=foo_test.cc",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--c-mode",
+        ])
+        .expect_err("--cc-self-test-out and --c-mode should conflict");
+        assert_eq!("`--cc-self-test-out` can't be used together with `--c-mode`", format!("{err}"));
+    }
+
+    #[test]
+    fn test_error_report() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--error-report=foo_errors.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo_errors.json")), cmdline.error_report.as_deref());
+    }
+
+    #[test]
+    fn test_stats() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--stats=foo_stats.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo_stats.json")), cmdline.stats.as_deref());
+    }
+
+    #[test]
+    fn test_report_html() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--report-html=foo_report",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("foo_report")), cmdline.report_html.as_deref());
+    }
+
+    #[test]
+    fn test_rename_map() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--rename-map=rename_map.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("rename_map.json")), cmdline.rename_map.as_deref());
+    }
+
+    #[test]
+    fn test_type_map() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--type-map=type_map.json",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+        ])
+        .unwrap();
+        assert_eq!(Some(Path::new("type_map.json")), cmdline.type_map.as_deref());
+    }
+
+    #[test]
+    fn test_fail_on_unsupported() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--fail-on-unsupported",
+            "--fail-on-unsupported-kind=function",
+            "--fail-on-unsupported-kind=struct",
+            "--fail-on-unsupported-path=some_module::*",
+        ])
+        .unwrap();
+        assert!(cmdline.fail_on_unsupported);
+        assert_eq!(vec!["function", "struct"], cmdline.fail_on_unsupported_kinds);
+        assert_eq!(vec!["some_module::*"], cmdline.fail_on_unsupported_paths);
+    }
+
+    #[test]
+    fn test_fail_on_unsupported_filter_without_fail_on_unsupported() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--fail-on-unsupported-kind=function",
+        ])
+        .unwrap_err();
+        assert_eq!(
+            "`--fail-on-unsupported-kind` and `--fail-on-unsupported-path` \
+             require `--fail-on-unsupported`",
+            format!("{err}")
+        );
+    }
+
+    #[test]
+    fn test_bind_only_and_skip() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--bind-only=api",
+            "--bind-only=other_api::*",
+            "--skip=api::internal",
+        ])
+        .unwrap();
+        assert_eq!(vec!["api", "other_api::*"], cmdline.bind_only);
+        assert_eq!(vec!["api::internal"], cmdline.skip);
+    }
+
+    #[test]
+    fn test_c_mode() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--c-mode",
+        ])
+        .unwrap();
+        assert!(cmdline.c_mode);
+    }
+
+    #[test]
+    fn test_abi_stamp() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--abi-stamp",
+        ])
+        .unwrap();
+        assert!(cmdline.abi_stamp);
+    }
+
+    #[test]
+    fn test_check() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--check",
+        ])
+        .unwrap();
+        assert!(cmdline.check);
+    }
+
+    #[test]
+    fn test_check_with_watch() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--check",
+            "--watch",
+        ])
+        .unwrap_err();
+        assert_eq!(format!("{err:#}"), "`--watch` can't be used together with `--check`");
+    }
+
+    #[test]
+    fn test_emit_line_directives() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--emit-line-directives",
+        ])
+        .unwrap();
+        assert!(cmdline.emit_line_directives);
+    }
+
+    #[test]
+    fn test_rustc_args_happy_path() {
+        // Note that this test would fail without the `--` separator.
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--",
+            "test.rs",
+            "--crate-type=lib",
+        ])
+        .unwrap();
+
+        let rustc_args = &cmdline.rustc_args;
+        assert!(
+            itertools::equal(
+                ["cc_bindings_from_rs_unittest_executable", "test.rs", "--crate-type=lib"],
+                rustc_args
+            ),
+            "rustc_args = {:?}",
+            rustc_args,
+        );
+    }
+
+    #[test]
+    fn test_crate_headers() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--crate-header=dep1=path/to/dep1_cc_api.h",
+            "--crate-header=dep2=path/to/dep2_cc_api.h",
+        ])
+        .unwrap();
+
+        assert!(
+            itertools::equal(
+                [
+                    ("dep1".to_string(), "path/to/dep1_cc_api.h".to_string()),
+                    ("dep2".to_string(), "path/to/dep2_cc_api.h".to_string()),
+                ],
+                cmdline.crate_headers.iter().cloned(),
+            ),
+            "crate_headers = {:?}",
+            cmdline.crate_headers,
+        );
+    }
+
+    #[test]
+    fn test_crate_headers_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--crate-header=no_equal_sign",
+        ])
+        .expect_err("--crate-header without `=` should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `CRATE_NAME=INCLUDE_PATH`, but got `no_equal_sign`"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--top-level-namespace=foo::bar",
+            "--crate-namespace=dep1=dep1_ns",
+            "--crate-namespace=dep2=outer::dep2_ns",
+        ])
+        .unwrap();
+
+        assert_eq!(Some("foo::bar"), cmdline.top_level_namespace.as_deref());
+        assert!(
+            itertools::equal(
+                [
+                    ("dep1".to_string(), "dep1_ns".to_string()),
+                    ("dep2".to_string(), "outer::dep2_ns".to_string()),
+                ],
+                cmdline.crate_namespaces.iter().cloned(),
+            ),
+            "crate_namespaces = {:?}",
+            cmdline.crate_namespaces,
+        );
+    }
+
+    #[test]
+    fn test_include_guard() {
+        for (arg, expected_style) in [
+            ("--include-guard=pragma-once", IncludeGuardStyle::PragmaOnce),
+            ("--include-guard=path", IncludeGuardStyle::PathBased),
+            ("--include-guard=hash", IncludeGuardStyle::HashBased),
+        ] {
+            let cmdline = new_cmdline([
+                "--h-out=foo.h",
+                "--rs-out=foo_impl.rs",
+                "--crubit-support-path=crubit/support/for/tests",
+                "--clang-format-exe-path=clang-format.exe",
+                "--rustfmt-exe-path=rustfmt.exe",
+                arg,
+            ])
+            .unwrap();
+            assert_eq!(Some(expected_style), cmdline.include_guard, "arg = {arg}");
+        }
+    }
+
+    #[test]
+    fn test_include_guard_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--include-guard=ifndef",
+        ])
+        .expect_err("Unrecognized --include-guard style should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `pragma-once`, `path`, or `hash`, but got `ifndef`"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_inline_namespace() {
+        for (arg, expected_source) in [
+            (
+                "--inline-namespace=crate-version=1.2.3",
+                InlineNamespaceSource::CrateVersion("1.2.3".to_string()),
+            ),
+            ("--inline-namespace=abi-hash", InlineNamespaceSource::AbiHash),
+        ] {
+            let cmdline = new_cmdline([
+                "--h-out=foo.h",
+                "--rs-out=foo_impl.rs",
+                "--crubit-support-path=crubit/support/for/tests",
+                "--clang-format-exe-path=clang-format.exe",
+                "--rustfmt-exe-path=rustfmt.exe",
+                arg,
+            ])
+            .unwrap();
+            assert_eq!(Some(expected_source), cmdline.inline_namespace, "arg = {arg}");
+        }
+    }
+
+    #[test]
+    fn test_inline_namespace_invalid() {
+        for arg in ["--inline-namespace=crate-version=", "--inline-namespace=version"] {
+            let anyhow_err = new_cmdline([
+                "--h-out=foo.h",
+                "--rs-out=foo_impl.rs",
+                "--crubit-support-path=crubit/support/for/tests",
+                "--clang-format-exe-path=clang-format.exe",
+                "--rustfmt-exe-path=rustfmt.exe",
+                arg,
+            ])
+            .expect_err("Unrecognized --inline-namespace source should trigger an error");
+
+            let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+            let actual_msg = clap_err.to_string();
+            assert!(
+                actual_msg.contains("Expected `crate-version=VERSION` or `abi-hash`"),
+                "arg = {arg}, actual_msg = {actual_msg}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_inline_namespace_with_c_mode() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--c-mode",
+            "--inline-namespace=abi-hash",
+        ])
+        .expect_err("--inline-namespace and --c-mode should conflict");
+        assert_eq!("`--inline-namespace` can't be used together with `--c-mode`", format!("{err}"));
+    }
+
+    #[test]
+    fn test_cc_std() {
+        for (arg, expected_std) in
+            [("--cc-std=c++17", CcStd::Cxx17), ("--cc-std=c++20", CcStd::Cxx20)]
+        {
+            let cmdline = new_cmdline([
+                "--h-out=foo.h",
+                "--rs-out=foo_impl.rs",
+                "--crubit-support-path=crubit/support/for/tests",
+                "--rustfmt-exe-path=rustfmt.exe",
+                arg,
+            ])
+            .unwrap();
+            assert_eq!(Some(expected_std), cmdline.cc_std, "arg = {arg}");
+        }
+    }
+
+    #[test]
+    fn test_cc_std_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--cc-std=c++14",
+        ])
+        .expect_err("Unrecognized --cc-std should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `c++17` or `c++20`, but got `c++14`"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_rust_std_lib() {
+        for (arg, expected_lib) in [
+            ("--rust-std-lib=std", StdLib::Std),
+            ("--rust-std-lib=alloc", StdLib::Alloc),
+            ("--rust-std-lib=core", StdLib::Core),
+        ] {
+            let cmdline = new_cmdline([
+                "--h-out=foo.h",
+                "--rs-out=foo_impl.rs",
+                "--crubit-support-path=crubit/support/for/tests",
+                "--rustfmt-exe-path=rustfmt.exe",
+                arg,
+            ])
+            .unwrap();
+            assert_eq!(Some(expected_lib), cmdline.rust_std_lib, "arg = {arg}");
+        }
+    }
+
+    #[test]
+    fn test_rust_std_lib_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--rust-std-lib=proc_macro",
+        ])
+        .expect_err("Unrecognized --rust-std-lib should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `std`, `alloc`, or `core`, but got `proc_macro`"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_crubit_features() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--crubit-features=supported,experimental",
+        ])
+        .unwrap();
+        assert_eq!(
+            Some(CrubitFeature::Supported | CrubitFeature::Experimental),
+            cmdline.crubit_features
+        );
+    }
+
+    #[test]
+    fn test_crubit_features_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--crubit-features=supported,bogus",
+        ])
+        .expect_err("Unrecognized --crubit-features should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Unexpected Crubit feature: bogus"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_cc_std_with_c_mode() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--cc-std=c++20",
+            "--c-mode",
+        ])
+        .expect_err("--cc-std and --c-mode should conflict");
+        assert_eq!("`--cc-std` can't be used together with `--c-mode`", format!("{err}"));
+    }
+
+    #[test]
+    fn test_cc_fn_naming() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--cc-fn-case=CamelCase",
+            "--cc-fn-prefix=Rs",
+            "--cc-fn-suffix=_v2",
+        ])
+        .unwrap();
+        assert_eq!(Some(CcFnCase::CamelCase), cmdline.cc_fn_case);
+        assert_eq!(Some("Rs"), cmdline.cc_fn_prefix.as_deref());
+        assert_eq!(Some("_v2"), cmdline.cc_fn_suffix.as_deref());
+    }
+
+    #[test]
+    fn test_cc_fn_case_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--cc-fn-case=camelCase",
+        ])
+        .expect_err("Unrecognized --cc-fn-case should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `snake_case` or `CamelCase`, but got `camelCase`"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_cc_fn_naming_with_c_mode() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--cc-fn-prefix=Rs",
+            "--c-mode",
+        ])
+        .expect_err("--cc-fn-prefix and --c-mode should conflict");
+        assert_eq!(
+            "`--cc-fn-case`, `--cc-fn-prefix`, and `--cc-fn-suffix` can't be used together \
+             with `--c-mode`",
+            format!("{err}")
+        );
+    }
+
+    #[test]
+    fn test_unsafe_fn_style() {
+        for (arg, expected_style) in
+            [("token", UnsafeFnStyle::Token), ("suffix", UnsafeFnStyle::Suffix)]
+        {
+            let cmdline = new_cmdline([
+                "--h-out=foo.h",
+                "--rs-out=foo_impl.rs",
+                "--crubit-support-path=crubit/support/for/tests",
+                "--rustfmt-exe-path=rustfmt.exe",
+                &format!("--unsafe-fn-style={arg}"),
+            ])
+            .unwrap();
+            assert_eq!(Some(expected_style), cmdline.unsafe_fn_style, "arg = {arg}");
+        }
+    }
+
+    #[test]
+    fn test_unsafe_fn_style_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--unsafe-fn-style=prefix",
+        ])
+        .expect_err("Unrecognized --unsafe-fn-style should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `token` or `suffix`, but got `prefix`"),
+            "actual_msg = {actual_msg}",
+        );
+    }
+
+    #[test]
+    fn test_unsafe_fn_style_with_c_mode() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--unsafe-fn-style=suffix",
+            "--c-mode",
+        ])
+        .expect_err("--unsafe-fn-style and --c-mode should conflict");
+        assert_eq!("`--unsafe-fn-style` can't be used together with `--c-mode`", format!("{err}"));
+    }
+
+    #[test]
+    fn test_feature_guard_macros() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--feature-guard-macro=serde=HAS_SERDE",
+        ])
+        .unwrap();
+
+        assert!(
+            itertools::equal(
+                [("serde".to_string(), "HAS_SERDE".to_string())],
+                cmdline.feature_guard_macros.iter().cloned(),
+            ),
+            "feature_guard_macros = {:?}",
+            cmdline.feature_guard_macros,
+        );
+    }
+
+    #[test]
+    fn test_feature_guard_macros_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--feature-guard-macro=serde=",
+        ])
+        .expect_err("--feature-guard-macro without a macro name should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(actual_msg.contains("Empty MACRO in `serde=`"), "actual_msg = {actual_msg}");
+    }
+
+    #[test]
+    fn test_h_out_for_modules() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--h-out-for-module=m1=path/to/foo_m1.h",
+            "--h-out-for-module=m2=path/to/foo_m2.h",
+        ])
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("m1".to_string(), "path/to/foo_m1.h".to_string()),
+                ("m2".to_string(), "path/to/foo_m2.h".to_string()),
+            ],
+            cmdline.h_out_for_modules
+        );
+    }
+
+    #[test]
+    fn test_h_out_for_modules_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--h-out-for-module=path/to/foo_m1.h",
+        ])
+        .expect_err("--h-out-for-module without a module name should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `MODULE=FILE`, but got `path/to/foo_m1.h`"),
+            "actual_msg = {actual_msg}"
+        );
+    }
+
+    #[test]
+    fn test_remap_path_prefixes() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--remap-path-prefix=/home/user/src=",
+            "--remap-path-prefix=/tmp/a=b=/c",
+        ])
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("/home/user/src".to_string(), "".to_string()),
+                ("/tmp/a=b".to_string(), "/c".to_string()),
+            ],
+            cmdline.remap_path_prefixes
+        );
+    }
+
+    #[test]
+    fn test_extra_targets() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--extra-target=aarch64-apple-darwin",
+            "--extra-target=x86_64-pc-windows-msvc",
+        ])
+        .unwrap();
+        assert_eq!(
+            vec!["aarch64-apple-darwin".to_string(), "x86_64-pc-windows-msvc".to_string()],
+            cmdline.extra_targets
+        );
+    }
+
+    #[test]
+    fn test_extra_targets_with_watch() {
+        let err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--extra-target=aarch64-apple-darwin",
+            "--watch",
+        ])
+        .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "`--batch`, `--watch`, and `--emit-line-directives` can't be used together with \
+             `--extra-target`"
+        );
+    }
+
+    #[test]
+    fn test_remap_path_prefixes_invalid() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--remap-path-prefix=/home/user/src",
+        ])
+        .expect_err("--remap-path-prefix without `=` should trigger an error");
+
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let actual_msg = clap_err.to_string();
+        assert!(
+            actual_msg.contains("Expected `FROM=TO`, but got `/home/user/src`"),
+            "actual_msg = {actual_msg}"
+        );
+    }
+
+    /// The `test_help` unit test below has multiple purposes:
+    /// - Direct/obvious purpose: testing that `--help` works
+    /// - Double-checking the overall shape of our cmdline "API" (i.e.
+    ///   verification that the way we use `clap` attributes results in the
+    ///   desired cmdline "API"). This is a good enough coverage to avoid having
+    ///   flag-specifc tests (e.g. avoiding hypothetical
+    ///   `test_h_out_missing_flag`, `test_h_out_missing_arg`,
+    ///   `test_h_out_duplicated`).
+    /// - Exhaustively checking runtime asserts (assumming that tests run in a
+    ///   debug build; other tests also trigger these asserts).  See also:
+    ///     - https://github.com/clap-rs/clap/issues/2740#issuecomment-907240414
+    ///     - `clap::builder::App::debug_assert`
+    ///
+    /// To regenerate `expected_msg` do the following steps:
+    /// - Run `bazel run :cc_bindings_from_rs_legacy_toolchain_runner -- --help`
+    /// - Copy&paste the output of the command below
+    /// - Replace the 2nd `cc_bindings_from_rs` with
+    ///   `cc_bindings_from_rs_unittest_executable`
+    #[test]
+    fn test_help() {
+        let anyhow_err = new_cmdline(["--help"]).expect_err("--help should trigger an error");
+        let clap_err = anyhow_err.downcast::<clap::Error>().unwrap();
+        let expected_msg = r#"cc_bindings_from_rs 
+Generates C++ bindings for a Rust crate
+
+USAGE:
+    cc_bindings_from_rs_unittest_executable [OPTIONS] --crubit-support-path <STRING> --rustfmt-exe-path <FILE> [-- <RUSTC_ARGS>...]
+
+ARGS:
+    <RUSTC_ARGS>...    Command line arguments of the Rust compiler
+
+OPTIONS:
+        --abi-stamp
+            Define a symbol whose name contains a hash of the bound API (the ABI stamp) in the Rust
+            side of the bindings, and refer to it from the generated C++ header(s), so that C++ code
+            that uses a header that is out of sync with the Rust side of the bindings fails to link.
+            Requires linking the Rust side of the bindings (the `--rs-out` file) even when only
+            `extern "C"` functions are called from C++
+
+        --batch <FILE>
+            Path to a JSON manifest that lists multiple crates (with the `--h-out` and `--rs-out`
+            paths and the Rust compiler arguments of each crate). The bindings of all the crates are
+            generated by a single invocation of the tool, using the same (other) command line flags.
+            The bindings of each crate can refer to the types of the other crates from the manifest
+            without `--crate-header` flags
+
+        --bind-only <PATH>
+            Only generate bindings for the items with a Rust path (relative to the crate root) that
+            is covered by the given filter. A filter covers the item with the given path and all the
+            items nested inside it, unless the filter contains a `*` (which matches any sequence of
+            characters). Can be repeated. Example: "--bind-only=some_module::public_api"
+
+        --c-mode
+            Generate a header that only uses C-compatible declarations (no namespaces, no
+            references, no passing of structs by value), so that the bindings can be used from C
+            (and not only from C++). Structs are opaque and `typedef`ed, and functions get
+            `snake_case` names prefixed with the top-level namespace and the module path
+
+        --cc-fn-case <CASE>
+            Case of the C++ names of the bindings of functions and methods: `snake_case` (the
+            default - the Rust names are used as-is) or `CamelCase` (`snake_case` Rust names are
+            converted into `CamelCase`, as recommended by the Google C++ Style Guide). Can be
+            overridden for an item (and for all the functions inside a module or an `impl` block) by
+            `#[crubit::cc_case(...)]`. Can't be used together with `--c-mode`
+
+        --cc-fn-prefix <PREFIX>
+            Prefix of the C++ names of the bindings of functions and methods (except for functions
+            with a `#[crubit::rename(...)]` annotation). Can't be used together with `--c-mode`.
+            Example: "--cc-fn-prefix=Rs"
+
+        --cc-fn-suffix <SUFFIX>
+            Suffix of the C++ names of the bindings of functions and methods (except for functions
+            with a `#[crubit::rename(...)]` annotation). Can't be used together with `--c-mode`.
+            Example: "--cc-fn-suffix=_rs"
+
+        --cc-out <FILE>
+            Output path for a companion C++ source file with the `static_assert`s that verify the
+            layouts of the C++ structs from the generated header. If not specified, then the
+            assertions are emitted into the generated header. Can't be used together with `--c-mode`
+
+        --cc-self-test-out <FILE>
+            Output path for a self-test C++ source file (e.g. `foo_cc_api_test.cc`) that validates
+            the generated bindings when it is compiled: the file `static_assert`s the layouts and
+            traits of the C++ bindings of the Rust types (e.g. `std::is_trivially_destructible_v`
+            and `kIsSend`), and calls every bound function in an unevaluated `decltype` operand, so
+            that the file only has to be compiled (not linked or run). Can't be used together with
+            `--c-mode`
+
+        --cc-std <STD>
+            Version of the C++ standard targeted by the generated C++ bindings: `c++17` (the
+            default) or `c++20`. Bindings that target C++20 may use C++20 features and fail to
+            compile in C++17 mode. Can't be used together with `--c-mode`
+
+        --check
+            Don't write the generated bindings. Instead, verify that the existing `--h-out`,
+            `--rs-out`, `--cc-out`, and `--cc-self-test-out` files (and the module headers from
+            `--h-out-for-module`) are up to date, and fail with a diff if they are not (e.g. in a
+            presubmit check). The other outputs (e.g. `--emit-ir` or `--snippet-cache`) are not
+            written in this mode. Can't be used together with `--watch`
+
+        --clang-format-exe-path <FILE>
+            Path to a clang-format executable that will be used to format the C++ header files
+            generated by the tool. If not specified, then a built-in formatter is used instead
+
+        --cmake-out <FILE>
+            Output path for a CMake script (e.g. `crubit_targets.cmake`) that defines an imported
+            `<CRATE_NAME>_cc_api` target for the generated bindings (the `--h-out` header, the
+            `--cc-out` source file, and the Rust library from `--cmake-rust-lib`), so that CMake
+            projects can use the bindings via `target_link_libraries`. Requires `--cmake-rust-lib`
+
+        --cmake-rust-lib <FILE>
+            Path to the Rust library (built from the crate together with the `--rs-out` file) that
+            the CMake target from `--cmake-out` links against: a static library, or a shared library
+            (the import library on Windows) if the crate is a `cdylib`
+
+        --crate-header <CRATE_NAME=INCLUDE_PATH>
+            Include path of the C++ bindings of a dependency crate, in the form of
+            `CRATE_NAME=INCLUDE_PATH`. Can be repeated. Example:
+            "--crate-header=other_crate=path/to/other_crate_cc_api.h"
+
+        --crate-namespace <CRATE_NAME=NAMESPACE>
+            Top-level C++ namespace of the C++ bindings of a dependency crate, in the form of
+            `CRATE_NAME=NAMESPACE`. Can be repeated. Defaults to the name of the crate. Example:
+            "--crate-namespace=other_crate=other::ns"
+
+        --crubit-support-path <STRING>
+            Path to the `crubit/support` directory in a format that should be used in the `#include`
+            directives inside the generated C++ files. Example: "crubit/support"
+
+        --dep-file <FILE>
+            Output path for a Makefile-style dependency file (e.g. for Ninja or Make) that lists the
+            inputs of the tool (Rust source files, dependency crates, and config files), so that the
+            build system can re-run the tool when one of the inputs changes
+
+        --emit-ir <FILE>
+            Output path for a JSON description of the API exposed by the generated bindings (e.g.
+            functions and types, with their Rust and C++ signatures)
+
+        --emit-line-directives
+            Precede the declarations in the generated C++ header(s) with `#line` directives that
+            point at the Rust definitions of the corresponding items, so that C++ compiler errors
+            (and "go to definition" in IDEs) lead to the Rust source code
+
+        --error-report <FILE>
+            Output path for a JSON report of the public items that the generated bindings don't
+            support (e.g. the path and source location of each item, the error message, its stable
+            error code like `CRUBIT-E0003`, and a suggested workaround). The report also groups the
+            items by the root cause of their errors
+
+        --extra-target <TRIPLE>
+            Additional Rust target triple to generate the bindings for (e.g.
+            `x86_64-pc-windows-msvc`). Can be repeated. The Rust compiler runs once per target (the
+            primary target is the one from the Rust compiler arguments), and the generated C++ files
+            serve all the targets: the lines that differ between the targets (e.g. struct layouts)
+            are guarded by `#if defined(...)` conditions. The Rust side of the bindings for each
+            additional target is written next to the `--rs-out` file, with the target triple added
+            to the file name (e.g. `foo_impl.x86_64-pc-windows-msvc.rs`). Can't be used together
+            with `--batch`, `--watch`, or `--emit-line-directives`
+
+        --fail-on-unsupported
+            Fail if bindings can't be generated for some of the public items of the crate (instead
+            of only explaining the problem in a comment in the generated C++ header)
+
+        --fail-on-unsupported-kind <KIND>
+            Only fail due to `--fail-on-unsupported` for the given kind of items (e.g. `function` or
+            `struct`). Can be repeated
+
+        --fail-on-unsupported-path <PATTERN>
+            Only fail due to `--fail-on-unsupported` for items with a Rust path (relative to the
+            crate root) that matches the given pattern. `*` matches any sequence of characters. Can
+            be repeated. Example: "--fail-on-unsupported-path=some_module::*"
+
+        --feature-guard-macro <FEATURE=MACRO>
+            C++ preprocessor macro that should guard the bindings of items that are only present
+            when the given Cargo feature is enabled (e.g. when `--cfg feature="FEATURE"` is passed
+            to the Rust compiler), in the form of `FEATURE=MACRO`. Can be repeated. Example:
+            "--feature-guard-macro=serde=MY_CRATE_HAS_SERDE"
+
+        --h-out <FILE>
+            Output path for C++ header file with bindings. Required unless `--batch` is used
+
+        --h-out-for-module <MODULE=FILE>
+            Output path for a separate C++ header with the bindings of the given top-level module of
+            the crate, in the form of `MODULE=FILE`. Can be repeated. The bindings of the other
+            items stay in the `--h-out` header, which `#include`s all the module headers. The paths
+            are also used in the `#include`s of the generated headers. Example:
+            "--h-out-for-module=some_module=path/to/some_module_cc_api.h"
+
+    -h, --help
+            Print help information
+
+        --include-guard <STYLE>
+            Style of the include guards of the generated C++ headers: `pragma-once` (the default),
+            `path` (`#ifndef` guards with a macro name derived from the path of the header), or
+            `hash` (`#ifndef` guards with a macro name derived from a hash of the path of the
+            header)
+
+        --inline-namespace <SOURCE>
+            Wraps the bindings of the crate in a C++ inline namespace (e.g. `namespace foo { inline
+            namespace v1_2_3 { ... } }`), so that the bindings of different versions of the crate
+            can coexist in one C++ binary, and so that mixing them up fails at link time. The name
+            of the inline namespace is derived either from `crate-version=VERSION` (e.g.
+            `crate-version=1.2.3` results in `v1_2_3`), or from a hash of the generated bindings
+            (`abi-hash`). Can't be used together with `--c-mode`
+
+        --remap-path-prefix <FROM=TO>
+            Replace the `FROM` prefix with `TO` in the file paths that are embedded into the
+            generated bindings (e.g. into the comments with the source locations of the Rust items),
+            in the same way as the Rust compiler's `--remap-path-prefix` (which is also respected).
+            Can be repeated. Example: "--remap-path-prefix=/home/user/src="
+
+        --report-html <DIR>
+            Output directory for a browsable HTML report of the interop coverage of the crate
+            (written as `index.html`). The report lists every public item of the crate, together
+            with the C++ signature of its bindings (or the reason why bindings couldn't be
+            generated) and a link to its Rust source location
+
+        --rename-map <FILE>
+            Path to a JSON file that renames the C++ bindings without modifying the Rust sources.
+            The file may contain a "crates" object (mapping crate names to top-level C++ namespaces,
+            like `--crate-namespace`) and an "items" object (mapping Rust paths of items to C++
+            names). Paths of the items from the current crate are relative to the crate root.
+            Renaming a module renames its C++ namespace. The "items" entries take precedence over
+            `#[crubit::rename(...)]` annotations, and `--crate-namespace` takes precedence over the
+            "crates" entries. Example: {"items": {"some_module::SomeStruct": "CcStruct"}}
+
+        --rs-out <FILE>
+            Output path for Rust implementation of the bindings. Required unless `--batch` is used
+
+        --rust-std-lib <LIB>
+            The library of the Rust standard library family that the Rust side of the bindings may
+            depend on: `std`, `alloc`, or `core`. Without `std`, the generated Rust code is
+            `#![no_std]` (and, without `alloc`, types like `Cow<str>` are not supported). Defaults
+            to `std`, unless the crate is `#![no_std]` - then `alloc` is used if the crate depends
+            on the `alloc` crate, and `core` otherwise
+
+        --rustfmt-config-path <FILE>
+            Path to a rustfmt.toml file that should replace the default formatting of the .rs files
+            generated by the tool
+
+        --rustfmt-exe-path <FILE>
+            Path to a rustfmt executable that will be used to format the Rust source files generated
+            by the tool
+
+        --skip <PATH>
+            Don't generate bindings for the items with a Rust path (relative to the crate root) that
+            is covered by the given filter (see `--bind-only`). Can be repeated. Example:
+            "--skip=some_module::internal"
+
+        --snippet-cache <FILE>
+            Path to a file that caches the generated bindings of individual items across runs of the
+            tool. The bindings of the items that didn't change since the previous run are reused
+            (rather than regenerated), which speeds up regenerating the bindings of large crates
+
+        --stats <FILE>
+            Output path for a JSON summary of the interop coverage of the crate: the number of
+            public items that have C++ bindings, and the number of the unsupported items (grouped by
+            the reason why bindings couldn't be generated for them)
+
+        --top-level-namespace <NAMESPACE>
+            Top-level C++ namespace of the generated bindings. Nested namespaces can be specified
+            using `::` as a separator. Defaults to the name of the crate. Example:
+            "--top-level-namespace=foo::bar"
+
+        --type-map <FILE>
+            Path to a JSON file that maps Rust types to pre-existing C++ types, without modifying
+            the Rust sources. The "types" object maps the Rust paths of types (in the same format as
+            the "items" of `--rename-map`) to objects with a "cc_type" (a fully qualified C++ type),
+            optional "cc_headers" (the headers that declare the C++ type and the conversion
+            functions), and optional "to_cc" and "from_cc" (fully qualified names of C++ functions
+            that convert the C++ bindings of the Rust type into the C++ type and back). Without the
+            conversion functions, the C++ type has to have the same layout as the Rust type, and the
+            C++ bindings of the Rust type are not generated. Example: {"types":
+            {"some_module::Uuid": {"cc_type": "::base::Uuid"}}}
+
+        --unsafe-fn-style <STYLE>
+            How the C++ callers of the bindings of `unsafe` Rust functions opt into upholding their
+            safety requirements: `token` (the default - the C++ functions take an extra, leading
+            `rs_std::UnsafeCall` parameter) or `suffix` (the C++ names of the functions get an
+            `_unsafe` suffix). Can't be used together with `--c-mode`
+
+        --watch
+            Keep running after generating the bindings: watch the inputs of the tool (the same ones
+            as in the `--dep-file`) and regenerate the bindings whenever one of them changes.
+            Combine with `--snippet-cache` to only regenerate the bindings of the items that have
+            changed
 "#;
         let actual_msg = clap_err.to_string();
         assert_eq!(
@@ -227,8 +1992,8 @@ OPTIONS:
 
         let flag_file_arg = format!("@{}", tmpfile.display());
         let cmdline = new_cmdline([flag_file_arg.as_str()]).unwrap();
-        assert_eq!(Path::new("foo.h"), cmdline.h_out);
-        assert_eq!(Path::new("foo_impl.rs"), cmdline.rs_out);
+        assert_eq!(Path::new("foo.h"), cmdline.h_out());
+        assert_eq!(Path::new("foo_impl.rs"), cmdline.rs_out());
         let rustc_args = &cmdline.rustc_args;
         assert!(
             itertools::equal(
@@ -0,0 +1,579 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! The `fuzz` module feeds randomly generated Rust crates into
+//! `bindings::generate_bindings` and checks that the generator:
+//!
+//! - doesn't panic,
+//! - terminates in a reasonable amount of time (an infinite recursion
+//!   overflows the stack and aborts the test process - the seed of the input
+//!   is printed to stderr before each iteration, so that the crash can be
+//!   reproduced),
+//! - generates C++ code that compiles (only when `CRUBIT_FUZZ_CXX` is set -
+//!   see below).
+//!
+//! The inputs come from a grammar of Rust item declarations (structs, unions,
+//! enums, functions, `impl` blocks, traits, constants, statics, type aliases,
+//! `use` reexports, and nested modules) which by construction produces crates
+//! that are accepted by the Rust compiler.  The grammar intentionally covers
+//! corner cases like C++ keywords used as Rust identifiers, raw identifiers,
+//! self-referential structs, `#[repr(...)]` variations, and deeply nested
+//! types.
+//!
+//! The fuzzer is configured via the following environment variables:
+//!
+//! - `CRUBIT_FUZZ_SEED`: the seed of the first input (default: 0),
+//! - `CRUBIT_FUZZ_ITERATIONS`: the number of inputs to test (default: 16),
+//! - `CRUBIT_FUZZ_CXX`: the C++ compiler (e.g. `clang++`) used to check that
+//!   the generated C++ header compiles,
+//! - `CRUBIT_FUZZ_INCLUDE_DIR`: the directory with `crubit/support/...`
+//!   headers that is passed as `-I` to `CRUBIT_FUZZ_CXX`.
+//!
+//! Example of a longer fuzzing session:
+//!
+//! ```sh
+//! $ bazel test //cc_bindings_from_rs:cc_bindings_from_rs_fuzz_test \
+//!     --test_env=CRUBIT_FUZZ_SEED=$RANDOM \
+//!     --test_env=CRUBIT_FUZZ_CXX=/usr/bin/clang++ \
+//!     --test_env=CRUBIT_FUZZ_INCLUDE_DIR=$PWD/..
+//! ```
+
+use crate::bindings::{
+    detect_crate_linkage, detect_rust_std_lib, generate_bindings, CcStandard, FnNaming,
+    IncludeGuard, Input, UnsafeFnMarker,
+};
+use crate::run_compiler::tests::run_compiler_for_testing;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use code_gen_utils::format_cc_tokens;
+use crubit_feature::CrubitFeature;
+use rustc_middle::ty::TyCtxt; // See <internal link>/ty.html#import-conventions
+use std::collections::{HashMap, HashSet};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Upper bound on how long `generate_bindings` may take for a single fuzzer
+/// input.  The inputs are small, so hitting the limit indicates an
+/// exponential blowup (or a loop that eventually terminates).
+const MAX_GENERATION_TIME: Duration = Duration::from_secs(30);
+
+/// Maximum nesting depth of modules and of types (e.g. `*const [(i32, u8);
+/// 2]` has depth 3).
+const MAX_DEPTH: usize = 3;
+
+/// Rust primitive types (and `()`) that are used as the leaves of the
+/// generated types.
+const PRIMITIVE_TYPES: &[&str] = &[
+    "()", "bool", "char", "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize",
+    "f32", "f64",
+];
+
+/// Identifiers that are valid in Rust, but that require escaping (or
+/// renaming) in C++.
+const CC_KEYWORD_NAMES: &[&str] = &[
+    "auto",
+    "class",
+    "delete",
+    "double",
+    "explicit",
+    "float",
+    "friend",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "operator",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "short",
+    "signed",
+    "template",
+    "this",
+    "typename",
+    "union",
+    "unsigned",
+    "r#virtual",
+    "void",
+    "volatile",
+    "r#type",
+    "r#match",
+];
+
+/// SplitMix64 - good enough for fuzzing and doesn't require any dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the `0..n` range.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % (n as u64)) as usize
+    }
+
+    /// Returns `true` with the probability of `1 / n`.
+    fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// Generates the source code of a Rust crate from a grammar of item
+/// declarations.
+struct CrateGenerator {
+    rng: Rng,
+    next_id: usize,
+
+    /// Fully qualified paths (e.g. `crate::m1::S2`) of the structs and unions
+    /// that can be used by value in the types of subsequent items.  (Only the
+    /// structs declared *earlier* are used by value, which avoids infinitely
+    /// sized types - see `gen_fields` for self-references.)
+    structs: Vec<String>,
+
+    /// Fully qualified paths of all the items that can be reexported with
+    /// `pub use`.
+    reexportable: Vec<String>,
+}
+
+impl CrateGenerator {
+    fn new(seed: u64) -> Self {
+        Self { rng: Rng(seed), next_id: 0, structs: vec![], reexportable: vec![] }
+    }
+
+    fn generate(mut self) -> String {
+        // `run_compiler_for_testing` denies all warnings, but many of the
+        // generated items are legitimately unidiomatic (e.g. lowercase
+        // constants or `extern "C"` functions with non-FFI-safe types).
+        let mut src = "#![allow(warnings)]\n".to_string();
+        let mut names = HashSet::new();
+        let item_count = 1 + self.rng.below(12);
+        for _ in 0..item_count {
+            src += &self.gen_item("crate", &mut names, 0);
+        }
+        src
+    }
+
+    fn fresh_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Returns a name that is unique within a module: either one of
+    /// `CC_KEYWORD_NAMES` (occasionally) or `{prefix}{id}`.
+    fn gen_name(&mut self, prefix: &str, names: &mut HashSet<String>) -> String {
+        if self.rng.one_in(4) {
+            let name = self.rng.choose(CC_KEYWORD_NAMES).to_string();
+            if names.insert(name.clone()) {
+                return name;
+            }
+        }
+        let name = format!("{prefix}{}", self.fresh_id());
+        names.insert(name.clone());
+        name
+    }
+
+    fn gen_item(&mut self, module: &str, names: &mut HashSet<String>, depth: usize) -> String {
+        match self.rng.below(11) {
+            0 | 1 => self.gen_struct(module, names),
+            2 => self.gen_union(module, names),
+            3 => self.gen_enum(module, names),
+            4 | 5 => self.gen_fn(names),
+            6 => self.gen_const_or_static(names),
+            7 => {
+                let name = self.gen_name("Alias", names);
+                format!("pub type {name} = {};\n", self.gen_ty(0))
+            }
+            8 => self.gen_trait(module, names),
+            9 if !self.reexportable.is_empty() => {
+                let path = self.rng.choose(&self.reexportable).clone();
+                let name = self.gen_name("Reexport", names);
+                format!("pub use {path} as {name};\n")
+            }
+            _ if depth < MAX_DEPTH => {
+                let name = self.gen_name("m", names);
+                let path = format!("{module}::{name}");
+                let mut mod_names = HashSet::new();
+                let mut body = String::new();
+                for _ in 0..self.rng.below(5) {
+                    body += &self.gen_item(&path, &mut mod_names, depth + 1);
+                }
+                format!("pub mod {name} {{\n{body}}}\n")
+            }
+            _ => self.gen_fn(names),
+        }
+    }
+
+    /// Generates a type that can be used in a field or in a function
+    /// signature.
+    fn gen_ty(&mut self, depth: usize) -> String {
+        let choice = if depth >= MAX_DEPTH { 0 } else { self.rng.below(10) };
+        match choice {
+            1 => format!("*const {}", self.gen_ty(depth + 1)),
+            2 => format!("*mut {}", self.gen_ty(depth + 1)),
+            3 => format!("&'static {}", self.gen_ty(depth + 1)),
+            4 => format!("&'static mut {}", self.gen_ty(depth + 1)),
+            5 => format!("[{}; {}]", self.gen_ty(depth + 1), self.rng.below(4)),
+            6 => format!("({}, {})", self.gen_ty(depth + 1), self.gen_ty(depth + 1)),
+            7 => format!("Option<{}>", self.gen_ty(depth + 1)),
+            8 => format!("Box<{}>", self.gen_ty(depth + 1)),
+            9 if !self.structs.is_empty() => self.rng.choose(&self.structs).clone(),
+            _ => self.rng.choose(PRIMITIVE_TYPES).to_string(),
+        }
+    }
+
+    /// Generates a primitive type and a literal of that type.
+    fn gen_primitive_value(&mut self) -> (&'static str, &'static str) {
+        *self.rng.choose(&[
+            ("bool", "true"),
+            ("char", "'\\u{1F980}'"),
+            ("i8", "-128"),
+            ("i16", "-1"),
+            ("i32", "42"),
+            ("i64", "i64::MIN"),
+            ("isize", "0"),
+            ("u8", "255"),
+            ("u16", "0"),
+            ("u32", "u32::MAX"),
+            ("u64", "u64::MAX"),
+            ("usize", "1"),
+            ("f32", "f32::NAN"),
+            ("f64", "-0.0"),
+        ])
+    }
+
+    /// Generates `count` fields (`pub {name}: {ty}` or, in tuple structs,
+    /// just `pub {ty}`).  Returns the fields and whether all of them have
+    /// primitive types.
+    fn gen_fields(&mut self, count: usize, is_tuple: bool, self_path: &str) -> (Vec<String>, bool) {
+        let mut names = HashSet::new();
+        let mut all_primitive = true;
+        let mut fields = vec![];
+        for i in 0..count {
+            let ty = match self.rng.below(8) {
+                // Self-references have to go through a pointer.
+                0 => format!("*const {self_path}"),
+                1 => format!("Option<Box<{self_path}>>"),
+                2 | 3 => self.gen_ty(1),
+                _ => self.rng.choose(PRIMITIVE_TYPES).to_string(),
+            };
+            all_primitive &= PRIMITIVE_TYPES.contains(&ty.as_str());
+            let field = if is_tuple {
+                format!("pub {ty}")
+            } else {
+                let mut name = self.rng.choose(CC_KEYWORD_NAMES).to_string();
+                if !names.insert(name.clone()) {
+                    name = format!("f{i}");
+                }
+                format!("pub {name}: {ty}")
+            };
+            fields.push(field);
+        }
+        (fields, all_primitive)
+    }
+
+    fn gen_struct(&mut self, module: &str, names: &mut HashSet<String>) -> String {
+        let name = self.gen_name("S", names);
+        let path = format!("{module}::{name}");
+        let kind = self.rng.below(3);
+        let field_count = if kind == 2 { 0 } else { self.rng.below(4) };
+        let (fields, all_primitive) = self.gen_fields(field_count, kind == 1, &path);
+
+        let mut attrs = String::new();
+        match self.rng.below(5) {
+            0 | 1 => attrs += "#[repr(C)]\n",
+            2 if field_count == 1 => attrs += "#[repr(transparent)]\n",
+            3 if all_primitive => attrs += "#[repr(C, packed)]\n",
+            4 => attrs += "#[repr(align(16))]\n",
+            _ => (),
+        }
+        let is_copy = all_primitive && self.rng.one_in(2);
+        if is_copy {
+            attrs += "#[derive(Clone, Copy, Default)]\n";
+        }
+
+        let decl = match kind {
+            0 => format!("pub struct {name} {{ {} }}\n", fields.join(", ")),
+            1 => format!("pub struct {name}({});\n", fields.join(", ")),
+            _ => format!("pub struct {name};\n"),
+        };
+        let mut src = attrs + &decl;
+        src += &self.gen_impls(&path, !is_copy);
+        self.structs.push(path.clone());
+        self.reexportable.push(path);
+        src
+    }
+
+    fn gen_union(&mut self, module: &str, names: &mut HashSet<String>) -> String {
+        let name = self.gen_name("U", names);
+        let path = format!("{module}::{name}");
+        // Union fields have to be `Copy` (or `ManuallyDrop`), and a union
+        // needs at least one field.
+        let mut fields = vec![];
+        let mut field_names = HashSet::new();
+        for i in 0..1 + self.rng.below(3) {
+            let (ty, _) = self.gen_primitive_value();
+            let mut field_name = self.rng.choose(CC_KEYWORD_NAMES).to_string();
+            if !field_names.insert(field_name.clone()) {
+                field_name = format!("f{i}");
+            }
+            fields.push(format!("pub {field_name}: {ty}"));
+        }
+        let repr = if self.rng.one_in(2) { "#[repr(C)]\n" } else { "" };
+        let mut src =
+            format!("{repr}#[derive(Clone, Copy)]\npub union {name} {{ {} }}\n", fields.join(", "));
+        src += &self.gen_impls(&path, false);
+        self.structs.push(path.clone());
+        self.reexportable.push(path);
+        src
+    }
+
+    fn gen_enum(&mut self, module: &str, names: &mut HashSet<String>) -> String {
+        let name = self.gen_name("E", names);
+        let path = format!("{module}::{name}");
+        let variant_count = 1 + self.rng.below(4);
+        let is_fieldless = self.rng.one_in(2);
+        let mut variants = vec![];
+        for i in 0..variant_count {
+            let variant = if is_fieldless {
+                format!("V{i} = {}", i as i64 * 3 - 1)
+            } else {
+                match self.rng.below(3) {
+                    0 => format!("V{i}"),
+                    1 => format!("V{i}({})", self.gen_ty(1)),
+                    _ => format!("V{i} {{ x: {} }}", self.gen_ty(1)),
+                }
+            };
+            variants.push(variant);
+        }
+        let repr = match self.rng.below(4) {
+            0 => "#[repr(C)]\n",
+            1 => "#[repr(i32)]\n",
+            2 if is_fieldless => "#[repr(i8)]\n",
+            _ => "",
+        };
+        let mut src = format!("{repr}pub enum {name} {{ {} }}\n", variants.join(", "));
+        src += &self.gen_impls(&path, true);
+        self.reexportable.push(path);
+        src
+    }
+
+    /// Generates `impl` blocks (inherent and/or of traits) for the
+    /// struct/union/enum at `self_path`.
+    fn gen_impls(&mut self, self_path: &str, can_impl_default_and_drop: bool) -> String {
+        let mut src = String::new();
+        if self.rng.one_in(2) {
+            let mut names = HashSet::new();
+            let mut methods = String::new();
+            for _ in 0..self.rng.below(4) {
+                methods += &self.gen_method(&mut names);
+            }
+            src += &format!("impl {self_path} {{\n{methods}}}\n");
+        }
+        if can_impl_default_and_drop && self.rng.one_in(4) {
+            src += &format!(
+                "impl Default for {self_path} {{ fn default() -> Self {{ unimplemented!() }} }}\n"
+            );
+        }
+        if can_impl_default_and_drop && self.rng.one_in(4) {
+            src += &format!("impl Drop for {self_path} {{ fn drop(&mut self) {{}} }}\n");
+        }
+        src
+    }
+
+    fn gen_method(&mut self, names: &mut HashSet<String>) -> String {
+        let receiver = *self.rng.choose(&["", "&self", "&mut self", "self", "self: Box<Self>"]);
+        let name = self.gen_name("method", names);
+        let mut params: Vec<String> = vec![];
+        if !receiver.is_empty() {
+            params.push(receiver.to_string());
+        }
+        for i in 0..self.rng.below(3) {
+            params.push(format!("_p{i}: {}", self.gen_ty(0)));
+        }
+        let ret = match self.rng.below(3) {
+            0 => " -> Self".to_string(),
+            1 => format!(" -> {}", self.gen_ty(0)),
+            _ => "".to_string(),
+        };
+        let unsafety = if self.rng.one_in(4) { "unsafe " } else { "" };
+        format!("pub {unsafety}fn {name}({}){ret} {{ unimplemented!() }}\n", params.join(", "))
+    }
+
+    fn gen_fn(&mut self, names: &mut HashSet<String>) -> String {
+        let name = self.gen_name("f", names);
+        let mut params = vec![];
+        for i in 0..self.rng.below(4) {
+            params.push(format!("_p{i}: {}", self.gen_ty(0)));
+        }
+        let ret =
+            if self.rng.one_in(3) { "".to_string() } else { format!(" -> {}", self.gen_ty(0)) };
+        let (attrs, qualifiers) = match self.rng.below(5) {
+            0 => ("", "unsafe "),
+            1 => ("", "extern \"C\" "),
+            // `#[no_mangle]` symbols have to be unique in the whole crate (and
+            // the keyword names from `gen_name` are only unique per module).
+            2 if name.starts_with('f') && name[1..].parse::<usize>().is_ok() => {
+                ("#[no_mangle]\n", "unsafe extern \"C\" ")
+            }
+            3 => ("#[inline(always)]\n", ""),
+            _ => ("", ""),
+        };
+        format!(
+            "{attrs}pub {qualifiers}fn {name}({}){ret} {{ unimplemented!() }}\n",
+            params.join(", ")
+        )
+    }
+
+    fn gen_const_or_static(&mut self, names: &mut HashSet<String>) -> String {
+        let name = self.gen_name("C", names);
+        let (ty, value) = self.gen_primitive_value();
+        let keyword = *self.rng.choose(&["const", "static", "static mut"]);
+        format!("pub {keyword} {name}: {ty} = {value};\n")
+    }
+
+    fn gen_trait(&mut self, module: &str, names: &mut HashSet<String>) -> String {
+        let name = self.gen_name("T", names);
+        let path = format!("{module}::{name}");
+        let mut src = format!("pub trait {name} {{ fn method(&self) -> i32; }}\n");
+        if !self.structs.is_empty() {
+            let self_path = self.rng.choose(&self.structs).clone();
+            src += &format!("impl {path} for {self_path} {{ fn method(&self) -> i32 {{ 0 }} }}\n");
+        }
+        self.reexportable.push(path);
+        src
+    }
+}
+
+/// Returns the `Input` used for generating the bindings of a fuzzer input.
+fn fuzz_input(tcx: TyCtxt) -> Input {
+    Input {
+        tcx,
+        crubit_support_path: "crubit/support".into(),
+        crubit_features: flagset::FlagSet::<CrubitFeature>::full(),
+        crate_name_to_include_path: HashMap::new(),
+        split_headers: None,
+        crate_name_to_namespace: HashMap::new(),
+        feature_guard_macros: HashMap::new(),
+        include_guard: IncludeGuard::PragmaOnce,
+        inline_namespace: None,
+        bind_only: vec![],
+        skip: vec![],
+        rename_map: HashMap::new(),
+        type_map: HashMap::new(),
+        c_mode: false,
+        cc_std: CcStandard::Cxx17,
+        fn_naming: FnNaming::default(),
+        unsafe_fn_marker: UnsafeFnMarker::default(),
+        emit_line_directives: false,
+        emit_abi_stamp: false,
+        remap_path_prefixes: vec![],
+        rust_std_lib: detect_rust_std_lib(tcx),
+        crate_linkage: detect_crate_linkage(tcx),
+        cc_out_h_include: None,
+        snippet_cache: None,
+        interner: Default::default(),
+    }
+}
+
+fn format_panic_payload(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
+
+/// Generates the bindings for the Rust `source` and returns the generated C++
+/// header.  Returns an error if `generate_bindings` panics or takes longer
+/// than `MAX_GENERATION_TIME`.
+fn generate_cc_header(source: &str) -> Result<String> {
+    run_compiler_for_testing(source, |tcx| {
+        let input = fuzz_input(tcx);
+        let start = Instant::now();
+        let output = catch_unwind(AssertUnwindSafe(|| generate_bindings(&input)))
+            .map_err(|payload| anyhow!("Panic: {}", format_panic_payload(&*payload)))?
+            .context("`generate_bindings` failed")?;
+        let elapsed = start.elapsed();
+        ensure!(
+            elapsed <= MAX_GENERATION_TIME,
+            "`generate_bindings` took {elapsed:?} (more than {MAX_GENERATION_TIME:?})"
+        );
+        format_cc_tokens(output.h_body)
+    })
+}
+
+/// Checks that the C++ `header` compiles with `cxx` (e.g. `clang++`).
+fn check_cc_header_compiles(header: &str, cxx: &Path, include_dir: &Path) -> Result<()> {
+    let tmpdir = tempfile::tempdir()?;
+    let h_path = tmpdir.path().join("fuzz_input_cc_api.h");
+    std::fs::write(&h_path, header)?;
+    let output = Command::new(cxx)
+        .arg("-fsyntax-only")
+        .arg("-std=c++17")
+        .arg("-x")
+        .arg("c++")
+        .arg("-I")
+        .arg(include_dir)
+        .arg(&h_path)
+        .output()
+        .with_context(|| format!("Failed to run {}", cxx.display()))?;
+    if !output.status.success() {
+        bail!(
+            "The generated C++ header doesn't compile:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+fn get_env_or_default<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Err(_) => default,
+        Ok(value) => value.parse().unwrap_or_else(|_| panic!("Invalid value of {name}: {value}")),
+    }
+}
+
+#[test]
+fn test_generated_crates_are_deterministic() {
+    assert_eq!(CrateGenerator::new(123).generate(), CrateGenerator::new(123).generate());
+    assert_ne!(CrateGenerator::new(123).generate(), CrateGenerator::new(124).generate());
+}
+
+#[test]
+fn test_fuzz_generate_bindings() {
+    let first_seed: u64 = get_env_or_default("CRUBIT_FUZZ_SEED", 0);
+    let iterations: u64 = get_env_or_default("CRUBIT_FUZZ_ITERATIONS", 16);
+    let cxx = std::env::var_os("CRUBIT_FUZZ_CXX");
+    let include_dir = std::env::var_os("CRUBIT_FUZZ_INCLUDE_DIR").unwrap_or(".".into());
+
+    for seed in (0..iterations).map(|i| first_seed.wrapping_add(i)) {
+        // Printed *before* testing the input, so that crashes that can't be
+        // caught (e.g. stack overflows) can be reproduced.
+        eprintln!("Testing fuzzer input with CRUBIT_FUZZ_SEED={seed}");
+        let source = CrateGenerator::new(seed).generate();
+        let result = generate_cc_header(&source).and_then(|header| match cxx.as_ref() {
+            None => Ok(()),
+            Some(cxx) => check_cc_header_compiles(&header, Path::new(cxx), Path::new(&include_dir)),
+        });
+        if let Err(err) = result {
+            panic!(
+                "Fuzzer failure with CRUBIT_FUZZ_SEED={seed}: {err:#}\n\
+                 Rust input:\n{source}"
+            );
+        }
+    }
+}
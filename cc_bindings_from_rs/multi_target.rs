@@ -0,0 +1,418 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! The `multi_target` module merges the C++ bindings generated for multiple
+//! Rust targets (see `--extra-target`) into C++ files that serve all of them.
+//!
+//! The bindings of each target are generated by a separate run of the Rust
+//! compiler.  The merged files contain the lines that are the same for all
+//! the targets as-is, and the lines that differ between the targets (e.g.
+//! `static_assert`s of struct layouts, or items that only exist on some
+//! targets) are guarded by `#if` conditions that identify each target (see
+//! `format_target_condition`).
+
+use anyhow::{bail, ensure, Result};
+use itertools::Itertools;
+use rustc_middle::ty::TyCtxt; // See <internal link>/ty.html#import-conventions
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The C++ files generated for a single target.
+pub struct TargetFiles {
+    /// Target triple (used in error messages).
+    pub triple: String,
+
+    /// C preprocessor condition that is only true when compiling for the
+    /// target (see `format_target_condition`).
+    pub condition: String,
+
+    /// Paths and contents of the generated C++ files.
+    pub files: Vec<(PathBuf, String)>,
+}
+
+/// Formats a C preprocessor condition that identifies the target of `tcx`
+/// (i.e. the `--target` passed to `rustc`, or the host) based on its
+/// architecture, operating system, and (on Windows) its environment.
+pub fn format_target_condition(tcx: TyCtxt) -> Result<String> {
+    let target = &tcx.sess.target;
+    let arch = match target.arch.as_ref() {
+        "x86_64" => "(defined(__x86_64__) || defined(_M_X64))",
+        "x86" => "(defined(__i386__) || defined(_M_IX86))",
+        "aarch64" => "(defined(__aarch64__) || defined(_M_ARM64))",
+        "arm" => "(defined(__arm__) || defined(_M_ARM))",
+        "riscv64" => "(defined(__riscv) && __riscv_xlen == 64)",
+        "wasm32" => "defined(__wasm32__)",
+        arch => bail!("Multi-target bindings are not supported for the `{arch}` architecture"),
+    };
+    let os = match (target.os.as_ref(), target.env.as_ref()) {
+        ("linux", _) => "(defined(__linux__) && !defined(__ANDROID__))",
+        ("android", _) => "defined(__ANDROID__)",
+        ("macos", _) => "defined(__APPLE__)",
+        ("windows", "msvc") => "(defined(_WIN32) && defined(_MSC_VER))",
+        ("windows", "gnu") => "(defined(_WIN32) && defined(__MINGW32__))",
+        ("freebsd", _) => "defined(__FreeBSD__)",
+        ("fuchsia", _) => "defined(__Fuchsia__)",
+        (os, env) => bail!(
+            "Multi-target bindings are not supported for the `{os}` operating system \
+             (with the `{env}` environment)"
+        ),
+    };
+    Ok(format!("{arch} && {os}"))
+}
+
+/// Merges the C++ files generated for multiple `targets` (the first one being
+/// the primary target).  Files that are only generated for some of the
+/// targets are treated as empty for the other targets.
+pub fn merge_files(targets: &[TargetFiles]) -> Result<Vec<(PathBuf, String)>> {
+    let duplicates = targets
+        .iter()
+        .into_group_map_by(|target| target.condition.as_str())
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| group.iter().map(|target| format!("`{}`", target.triple)).join(" and "))
+        .sorted()
+        .collect_vec();
+    ensure!(
+        duplicates.is_empty(),
+        "The C preprocessor can't distinguish between the following targets: {}",
+        duplicates.join(", ")
+    );
+
+    let paths = targets
+        .iter()
+        .flat_map(|target| target.files.iter().map(|(path, _)| path))
+        .unique()
+        .collect_vec();
+    let merged_files = paths
+        .into_iter()
+        .map(|path| {
+            let versions = targets
+                .iter()
+                .map(|target| {
+                    let content = target
+                        .files
+                        .iter()
+                        .find(|(target_path, _)| target_path == path)
+                        .map_or("", |(_, content)| content.as_str());
+                    (target.condition.as_str(), content)
+                })
+                .collect_vec();
+            (path.clone(), merge_versions(&versions))
+        })
+        .collect();
+    Ok(merged_files)
+}
+
+/// Merges the `versions` of a file (pairs of a target condition and the
+/// content of the file for that target).  The first version is used as the
+/// base: its lines that are matched by the other versions (see `match_lines`)
+/// are emitted as-is, and the differing lines in between are emitted in
+/// `#if`-guarded sections.  The first such section fails the compilation
+/// for targets that don't match any of the conditions.
+fn merge_versions(versions: &[(&str, &str)]) -> String {
+    let lines = versions.iter().map(|(_, content)| content.lines().collect_vec()).collect_vec();
+    let (base, others) = lines.split_first().expect("There is always at least 1 version");
+    let matches = others
+        .iter()
+        .map(|other| {
+            let mut matched = vec![None; base.len()];
+            for (base_index, other_index) in match_lines(base, other) {
+                matched[base_index] = Some(other_index);
+            }
+            matched
+        })
+        .collect_vec();
+
+    let mut result = vec![];
+    let mut is_first_section = true;
+    // Index of the first line of each version that hasn't been emitted yet.
+    let mut starts = vec![0; lines.len()];
+    // The base lines that are matched by all the other versions (the indices of the line in
+    // each version), followed by the end of the file.
+    let anchors = (0..base.len())
+        .filter_map(|base_index| {
+            let mut indices = vec![base_index];
+            for matched in matches.iter() {
+                indices.push(matched[base_index]?);
+            }
+            Some(indices)
+        })
+        .chain(std::iter::once(lines.iter().map(Vec::len).collect_vec()));
+    for ends in anchors {
+        let sections = lines
+            .iter()
+            .zip(starts.iter().zip(ends.iter()))
+            .map(|(lines, (&start, &end))| &lines[start..end])
+            .collect_vec();
+        if sections.iter().all_equal() {
+            result.extend(sections[0].iter().copied().map(str::to_string));
+        } else {
+            result.extend(format_conditional_section(versions, &sections, is_first_section));
+            is_first_section = false;
+        }
+        // The anchor line itself (absent after the end of the file).
+        if let Some(line) = base.get(ends[0]) {
+            result.push(line.to_string());
+        }
+        starts = ends.iter().map(|end| end + 1).collect();
+    }
+
+    let mut result = result.join("\n");
+    if versions.iter().any(|(_, content)| content.ends_with('\n')) {
+        result.push('\n');
+    }
+    result
+}
+
+/// Formats the lines of an `#if`-guarded section with the `sections` of each
+/// of the `versions` (the versions with the same lines share a branch).
+fn format_conditional_section(
+    versions: &[(&str, &str)],
+    sections: &[&[&str]],
+    is_first_section: bool,
+) -> Vec<String> {
+    let mut groups: Vec<(&[&str], Vec<&str>)> = vec![];
+    for ((condition, _), &section) in versions.iter().zip(sections.iter()) {
+        match groups.iter_mut().find(|(lines, _)| *lines == section) {
+            Some((_, conditions)) => conditions.push(condition),
+            None => groups.push((section, vec![condition])),
+        }
+    }
+    // After the first section the compilation has already failed for unknown targets, so
+    // the targets without any lines can be skipped.
+    if !is_first_section {
+        groups.retain(|(lines, _)| !lines.is_empty());
+    }
+
+    let mut result = vec![];
+    for (i, (lines, conditions)) in groups.into_iter().enumerate() {
+        let directive = if i == 0 { "#if" } else { "#elif" };
+        let condition = match conditions.as_slice() {
+            [condition] => condition.to_string(),
+            _ => conditions.iter().map(|condition| format!("({condition})")).join(" || "),
+        };
+        result.push(format!("{directive} {condition}"));
+        result.extend(lines.iter().copied().map(str::to_string));
+    }
+    if is_first_section {
+        result.push("#else".to_string());
+        result.push("#error \"The bindings haven't been generated for this target\"".to_string());
+    }
+    result.push("#endif".to_string());
+    result
+}
+
+/// Returns pairs of indices of the lines of `a` and `b` that are matched by a
+/// line diff (in increasing order of both indices).  The diff uses the
+/// "patience diff" algorithm: the common prefix and suffix are matched first,
+/// then the lines that are unique in both `a` and `b` are used as anchors
+/// (their longest increasing subsequence), and the parts between the anchors
+/// are diffed recursively.
+fn match_lines(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let mut result = vec![];
+    match_lines_impl(a, 0, b, 0, &mut result);
+    result
+}
+
+fn match_lines_impl(
+    a: &[&str],
+    a_offset: usize,
+    b: &[&str],
+    b_offset: usize,
+    result: &mut Vec<(usize, usize)>,
+) {
+    let prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    result.extend((0..prefix).map(|i| (a_offset + i, b_offset + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (a_offset, b_offset) = (a_offset + prefix, b_offset + prefix);
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_mid, b_mid) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    let unique_lines = |lines: &[&str]| -> HashMap<String, usize> {
+        lines
+            .iter()
+            .enumerate()
+            .into_group_map_by(|(_, line)| line.to_string())
+            .into_iter()
+            .filter(|(_, occurrences)| occurrences.len() == 1)
+            .map(|(line, occurrences)| (line, occurrences[0].0))
+            .collect()
+    };
+    let b_unique = unique_lines(b_mid);
+    let a_unique = unique_lines(a_mid);
+    let candidates = a_mid
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| a_unique.contains_key(**line))
+        .filter_map(|(i, line)| b_unique.get(*line).map(|&j| (i, j)))
+        .collect_vec();
+    let anchors = longest_increasing_subsequence(&candidates);
+
+    let (mut a_start, mut b_start) = (0, 0);
+    for (i, j) in anchors {
+        match_lines_impl(
+            &a_mid[a_start..i],
+            a_offset + a_start,
+            &b_mid[b_start..j],
+            b_offset + b_start,
+            result,
+        );
+        result.push((a_offset + i, b_offset + j));
+        (a_start, b_start) = (i + 1, j + 1);
+    }
+    if a_start > 0 || b_start > 0 {
+        match_lines_impl(
+            &a_mid[a_start..],
+            a_offset + a_start,
+            &b_mid[b_start..],
+            b_offset + b_start,
+            result,
+        );
+    }
+
+    let (a_suffix_start, b_suffix_start) = (a_offset + a_mid.len(), b_offset + b_mid.len());
+    result.extend((0..suffix).map(|i| (a_suffix_start + i, b_suffix_start + i)));
+}
+
+/// Returns the longest subsequence of `pairs` (sorted by the 1st element)
+/// with increasing 2nd elements (using patience sorting).
+fn longest_increasing_subsequence(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // `tails[k]` is the index (in `pairs`) of the smallest tail of an increasing subsequence
+    // of length `k + 1`.
+    let mut tails: Vec<usize> = vec![];
+    let mut predecessors: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, &(_, value)) in pairs.iter().enumerate() {
+        let pos = tails.partition_point(|&tail| pairs[tail].1 < value);
+        predecessors[index] = pos.checked_sub(1).map(|prev| tails[prev]);
+        if pos == tails.len() {
+            tails.push(index);
+        } else {
+            tails[pos] = index;
+        }
+    }
+    let mut result = vec![];
+    let mut next = tails.last().copied();
+    while let Some(index) = next {
+        result.push(pairs[index]);
+        next = predecessors[index];
+    }
+    result.reverse();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(s: &str) -> Vec<&str> {
+        s.lines().collect()
+    }
+
+    #[test]
+    fn test_match_lines() {
+        let a = lines("a\nb\nc\nd\ne");
+        let b = lines("a\nx\nc\ne\ny");
+        assert_eq!(vec![(0, 0), (2, 2), (4, 3)], match_lines(&a, &b));
+    }
+
+    #[test]
+    fn test_match_lines_moved_line() {
+        let a = lines("a\nb\nc");
+        let b = lines("c\na\nb");
+        assert_eq!(vec![(0, 1), (1, 2)], match_lines(&a, &b));
+    }
+
+    #[test]
+    fn test_longest_increasing_subsequence() {
+        let pairs = [(0, 3), (1, 1), (2, 4), (3, 2), (4, 5)];
+        assert_eq!(vec![(1, 1), (3, 2), (4, 5)], longest_increasing_subsequence(&pairs));
+    }
+
+    #[test]
+    fn test_merge_versions_identical() {
+        let content = "// header\nint x;\n";
+        assert_eq!(content, merge_versions(&[("LINUX", content), ("WINDOWS", content)]));
+    }
+
+    #[test]
+    fn test_merge_versions() {
+        let linux = "#pragma once\nstatic_assert(sizeof(void*) == 8);\nstruct S {\n  \
+                     alignas(8) char x[16];\n};\nvoid linux_only();\n";
+        let mac = "#pragma once\nstatic_assert(sizeof(void*) == 8);\nstruct S {\n  \
+                   alignas(8) char x[16];\n};\n";
+        let windows = "#pragma once\nstatic_assert(sizeof(void*) == 4);\nstruct S {\n  \
+                       alignas(4) char x[12];\n};\n";
+        let merged = merge_versions(&[("LINUX", linux), ("MAC", mac), ("WINDOWS", windows)]);
+        assert_eq!(
+            merged,
+            "#pragma once\n\
+             #if (LINUX) || (MAC)\n\
+             static_assert(sizeof(void*) == 8);\n\
+             #elif WINDOWS\n\
+             static_assert(sizeof(void*) == 4);\n\
+             #else\n\
+             #error \"The bindings haven't been generated for this target\"\n\
+             #endif\n\
+             struct S {\n\
+             #if (LINUX) || (MAC)\n  \
+             alignas(8) char x[16];\n\
+             #elif WINDOWS\n  \
+             alignas(4) char x[12];\n\
+             #endif\n\
+             };\n\
+             #if LINUX\n\
+             void linux_only();\n\
+             #endif\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_files() {
+        let targets = [
+            TargetFiles {
+                triple: "x86_64-unknown-linux-gnu".to_string(),
+                condition: "LINUX".to_string(),
+                files: vec![
+                    ("foo.h".into(), "int x;\n".to_string()),
+                    ("foo_bar.h".into(), "int y;\n".to_string()),
+                ],
+            },
+            TargetFiles {
+                triple: "x86_64-pc-windows-msvc".to_string(),
+                condition: "WINDOWS".to_string(),
+                files: vec![("foo.h".into(), "int x;\n".to_string())],
+            },
+        ];
+        let merged = merge_files(&targets).unwrap();
+        assert_eq!(
+            vec![
+                (PathBuf::from("foo.h"), "int x;\n".to_string()),
+                (
+                    PathBuf::from("foo_bar.h"),
+                    "#if LINUX\nint y;\n#elif WINDOWS\n#else\n\
+                     #error \"The bindings haven't been generated for this target\"\n#endif\n"
+                        .to_string()
+                ),
+            ],
+            merged
+        );
+    }
+
+    #[test]
+    fn test_merge_files_indistinguishable_targets() {
+        let target = |triple: &str| TargetFiles {
+            triple: triple.to_string(),
+            condition: "LINUX".to_string(),
+            files: vec![],
+        };
+        let err =
+            merge_files(&[target("x86_64-unknown-linux-gnu"), target("x86_64-unknown-linux-musl")])
+                .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "The C preprocessor can't distinguish between the following targets: \
+             `x86_64-unknown-linux-gnu` and `x86_64-unknown-linux-musl`"
+        );
+    }
+}
@@ -116,6 +116,12 @@ where
         // compiling the crate via `rustc` and once when "compiling" the crate
         // via `cc_bindings_from_rs` (the `config` here affects the latter one).
         config.opts.lint_opts.push(("warnings".to_string(), rustc_lint_defs::Level::Allow));
+
+        // Generating bindings only depends on queries that are available after the
+        // analysis phase.  Asking only for the crate metadata (similarly to `cargo
+        // check`) ensures that an `--emit=...` flag in `rustc_args` can't make the
+        // bindings depend on queries that need codegen (e.g. `exported_symbols`).
+        config.opts.output_types = metadata_only_output_types();
     }
 
     fn after_analysis<'tcx>(
@@ -142,6 +148,13 @@ where
     }
 }
 
+/// Returns `OutputTypes` that don't trigger codegen (see also
+/// `rustc_session::config::OutputTypes::should_codegen`).
+fn metadata_only_output_types() -> rustc_session::config::OutputTypes {
+    use rustc_session::config::{OutputType, OutputTypes};
+    OutputTypes::new(&[(OutputType::Metadata, None /* PathBuf */)])
+}
+
 /// Helper (used by `run_compiler` and `run_compiler_for_testing`) for invoking
 /// functions operating on `TyCtxt`.
 fn enter_tcx<'tcx, F, T>(
@@ -243,6 +256,32 @@ pub mod tests {
         Ok(())
     }
 
+    /// `test_run_compiler_no_codegen` tests that `--emit=...` in `rustc_args`
+    /// doesn't make `run_compiler` ask for codegen.
+    #[test]
+    fn test_run_compiler_no_codegen() -> anyhow::Result<()> {
+        let tmpdir = tempdir()?;
+
+        let rs_path = tmpdir.path().join("input_crate.rs");
+        std::fs::write(&rs_path, DEFAULT_RUST_SOURCE_FOR_TESTING)?;
+
+        let rustc_args = vec![
+            // Default parameters.
+            "run_compiler_unittest_executable".to_string(),
+            "--crate-type=lib".to_string(),
+            format!("--sysroot={}", get_sysroot_for_testing().display()),
+            rs_path.display().to_string(),
+            // Test-specific parameter: asking for codegen output
+            "--emit=llvm-bc,link".to_string(),
+            format!("--out-dir={}", tmpdir.path().display()),
+        ];
+
+        let should_codegen =
+            run_compiler(&rustc_args, |tcx| Ok(tcx.sess.opts.output_types.should_codegen()))?;
+        assert!(!should_codegen);
+        Ok(())
+    }
+
     /// Returns the `rustc` sysroot that is suitable for the environment where unit
     /// tests run.
     ///
@@ -303,6 +342,13 @@ pub mod tests {
         run_compiler_for_testing("#![feature(const_ptr_offset_from)]", |_tcx| ())
     }
 
+    #[test]
+    fn test_run_compiler_for_testing_no_codegen() {
+        let should_codegen =
+            run_compiler_for_testing("", |tcx| tcx.sess.opts.output_types.should_codegen());
+        assert!(!should_codegen);
+    }
+
     /// `run_compiler_for_testing` is similar to `run_compiler`: it invokes the
     /// `callback` after parsing and analysis are done, but instead of taking
     /// `rustc_args` it:
@@ -315,17 +361,14 @@ pub mod tests {
         F: for<'tcx> FnOnce(TyCtxt<'tcx>) -> T + Send,
         T: Send,
     {
-        use rustc_session::config::{
-            CodegenOptions, CrateType, Input, Options, OutputType, OutputTypes,
-        };
+        use rustc_session::config::{CodegenOptions, CrateType, Input, Options};
 
         const TEST_FILENAME: &str = "crubit_unittests.rs";
 
-        // Setting `output_types` that will trigger code gen - otherwise some parts of
-        // the analysis will be missing (e.g. `tcx.exported_symbols()`).
-        // The choice of `Bitcode` is somewhat arbitrary (e.g. `Assembly`,
-        // `Mir`, etc. would also trigger code gen).
-        let output_types = OutputTypes::new(&[(OutputType::Bitcode, None /* PathBuf */)]);
+        // Using the same `output_types` as `run_compiler` - this helps to verify that
+        // the bindings don't depend on queries that are only available when
+        // triggering codegen.
+        let output_types = super::metadata_only_output_types();
 
         let opts = Options {
             crate_types: vec![CrateType::Rlib], // Test inputs simulate library crates.
@@ -0,0 +1,145 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! The `rustc_compat` module provides adapters over the `rustc_middle` queries
+//! whose APIs differ between the nightly versions of the Rust toolchain that
+//! `cc_bindings_from_rs` can be built with.
+//!
+//! The rest of `cc_bindings_from_rs` should call the adapters below (rather
+//! than the underlying `TyCtxt` methods), so that supporting a new nightly only
+//! requires changes in this module.  By default the adapters target the
+//! nightly used by Crubit's own build.  Newer nightlies are supported by
+//! passing the following `--cfg` flags when compiling `cc_bindings_from_rs`
+//! (e.g. via `rustc_flags` in Bazel, or via `RUSTFLAGS` in Cargo):
+//!
+//! - `--cfg=crubit_rustc_instantiate_identity` for nightlies that have renamed
+//!   `EarlyBinder::subst_identity` to `EarlyBinder::instantiate_identity`
+//!   (2023-07 and later),
+//! - `--cfg=crubit_rustc_instantiate_bound_regions` for nightlies that have
+//!   renamed `TyCtxt::erase_late_bound_regions` to
+//!   `TyCtxt::instantiate_bound_regions_with_erased` (2023-11 and later).
+
+use rustc_middle::ty::{self, Ty, TyCtxt}; // See <internal link>/ty.html#import-conventions
+use rustc_span::def_id::DefId;
+
+/// Returns the type of `def_id`, with the generic parameters of the item
+/// left as-is (i.e. `tcx.type_of(def_id).subst_identity()`).
+pub fn type_of(tcx: TyCtxt<'_>, def_id: DefId) -> Ty<'_> {
+    instantiate_identity(tcx.type_of(def_id))
+}
+
+/// Returns the signature of the function `def_id`, with the generic
+/// parameters of the function left as-is (i.e.
+/// `tcx.fn_sig(def_id).subst_identity()`).
+pub fn fn_sig(tcx: TyCtxt<'_>, def_id: DefId) -> ty::PolyFnSig<'_> {
+    instantiate_identity(tcx.fn_sig(def_id))
+}
+
+/// Returns the trait implemented by the `impl` block `impl_id`, or `None` for
+/// inherent `impl`s (i.e. `tcx.impl_trait_ref(impl_id)` with the generic
+/// parameters of the `impl` left as-is).
+pub fn impl_trait_ref(tcx: TyCtxt<'_>, impl_id: DefId) -> Option<ty::TraitRef<'_>> {
+    tcx.impl_trait_ref(impl_id).map(instantiate_identity)
+}
+
+/// Replaces the late-bound regions in `value` with `'erased` (i.e.
+/// `tcx.erase_late_bound_regions(value)`).
+pub fn erase_late_bound_regions<'tcx, T>(tcx: TyCtxt<'tcx>, value: ty::Binder<'tcx, T>) -> T
+where
+    T: ty::TypeFoldable<TyCtxt<'tcx>>,
+{
+    #[cfg(not(crubit_rustc_instantiate_bound_regions))]
+    let result = tcx.erase_late_bound_regions(value);
+
+    #[cfg(crubit_rustc_instantiate_bound_regions)]
+    let result = tcx.instantiate_bound_regions_with_erased(value);
+
+    result
+}
+
+fn instantiate_identity<T>(value: ty::EarlyBinder<T>) -> T {
+    #[cfg(not(crubit_rustc_instantiate_identity))]
+    let result = value.subst_identity();
+
+    #[cfg(crubit_rustc_instantiate_identity)]
+    let result = value.instantiate_identity();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_compiler::tests::run_compiler_for_testing;
+    use rustc_hir::{Item, ItemKind};
+
+    fn find_items<'tcx>(tcx: TyCtxt<'tcx>, predicate: impl Fn(&Item<'tcx>) -> bool) -> Vec<DefId> {
+        tcx.hir()
+            .items()
+            .map(|item_id| tcx.hir().item(item_id))
+            .filter(|item| predicate(item))
+            .map(|item| item.owner_id.to_def_id())
+            .collect()
+    }
+
+    fn find_def_id_by_name(tcx: TyCtxt, name: &str) -> DefId {
+        let def_ids = find_items(tcx, |item| item.ident.name.as_str() == name);
+        match def_ids.as_slice() {
+            [def_id] => *def_id,
+            _ => panic!("Expected exactly one item named `{name}`"),
+        }
+    }
+
+    #[test]
+    fn test_type_of() {
+        let test_src = r#"
+                pub struct SomeStruct<T>(pub T);
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let def_id = find_def_id_by_name(tcx, "SomeStruct");
+            assert_eq!("SomeStruct<T>", type_of(tcx, def_id).to_string());
+        });
+    }
+
+    #[test]
+    fn test_fn_sig_and_erase_late_bound_regions() {
+        let test_src = r#"
+                pub fn first<'a>(x: &'a [i32]) -> &'a i32 { &x[0] }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let def_id = find_def_id_by_name(tcx, "first");
+            let sig = erase_late_bound_regions(tcx, fn_sig(tcx, def_id));
+            assert_eq!("&i32", sig.output().to_string());
+            assert_eq!("&[i32]", sig.inputs()[0].to_string());
+        });
+    }
+
+    #[test]
+    fn test_impl_trait_ref() {
+        let test_src = r#"
+                pub struct SomeStruct;
+                impl Default for SomeStruct {
+                    fn default() -> Self { SomeStruct }
+                }
+                impl SomeStruct {
+                    pub fn new() -> Self { SomeStruct }
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let impls = find_items(tcx, |item| matches!(item.kind, ItemKind::Impl(_)));
+            assert_eq!(2, impls.len());
+            let trait_refs = impls
+                .iter()
+                .map(|&impl_id| {
+                    impl_trait_ref(tcx, impl_id).map(|trait_ref| {
+                        let trait_name = tcx.item_name(trait_ref.def_id);
+                        format!("{} for {}", trait_name, trait_ref.self_ty())
+                    })
+                })
+                .collect::<Vec<_>>();
+            assert!(trait_refs.contains(&Some("Default for SomeStruct".to_string())));
+            assert!(trait_refs.contains(&None));
+        });
+    }
+}
@@ -12,6 +12,11 @@ load("@bazel_skylib//lib:unittest.bzl", "analysistest", "asserts")
 load(
     "//cc_bindings_from_rs/bazel_support:cc_bindings_from_rust_rule.bzl",
     "cc_bindings_from_rust",
+    "cc_bindings_from_rust_aspect",
+)
+load(
+    "//cc_bindings_from_rs/bazel_support:providers.bzl",
+    "CcBindingsFromRustInfo",
 )
 
 def _find_action_by_mnemonic(env, expected_mnemonic):
@@ -36,6 +41,30 @@ def _find_action_by_mnemonic(env, expected_mnemonic):
     asserts.equals(env, 1, len(matching_actions))
     return matching_actions[0]
 
+def _find_action_by_output_prefix(env, expected_mnemonic, output_basename_prefix):
+    """Searches `target_actions` for a single one with `expected_mnemonic` and an output
+    whose basename starts with `output_basename_prefix`.
+
+    Args:
+      env: A test environment struct received from `analysistest.begin(ctx)`
+      expected_mnemonic: string to be compared against `Action.mnemonic`
+      output_basename_prefix: string to be compared against `File.basename`
+                              of the action outputs
+
+    Returns:
+      Action  - The matching action.
+    """
+    matching_actions = [
+        a
+        for a in analysistest.target_actions(env)
+        if a.mnemonic == expected_mnemonic and _has_file_with_name_prefix(
+            a.outputs.to_list(),
+            output_basename_prefix,
+        )
+    ]
+    asserts.equals(env, 1, len(matching_actions))
+    return matching_actions[0]
+
 def _remove_ext(f):
     """Takes a File and returns its `path` with `extension` removed.
 
@@ -118,15 +147,22 @@ def _header_generation_test_impl(ctx):
     # Verify that `CcBindingsFromRust` generates:
     # 1) `generated_header` ("..._cc_api.h")
     # 2) `generated_impl` ("..._cc_api_impl.rs")
+    # 3) `generated_cc` ("..._cc_api.cc")
     generated_outputs = generate_action.outputs.to_list()
-    asserts.equals(env, 2, len(generated_outputs))
+    asserts.equals(env, 3, len(generated_outputs))
     generated_header = generated_outputs[0]
     asserts.equals(env, "rusty_lib_cc_api.h", generated_header.basename)
     generated_impl = generated_outputs[1]
     asserts.equals(env, "rusty_lib_cc_api_impl.rs", generated_impl.basename)
+    generated_cc = generated_outputs[2]
+    asserts.equals(env, "rusty_lib_cc_api.cc", generated_cc.basename)
+
+    # Verify that `generated_cc` is compiled.
+    cpp_compile_action = _find_action_by_mnemonic(env, "CppCompile")
+    asserts.true(env, generated_cc in cpp_compile_action.inputs.to_list())
 
     # Verify that `generated_impl` is an input for `rustc_action`.
-    rustc_action = _find_action_by_mnemonic(env, "Rustc")
+    rustc_action = _find_action_by_output_prefix(env, "Rustc", "librusty_lib_cc_api_impl-")
     rustc_input_paths = [i.path for i in rustc_action.inputs.to_list()]
     asserts.true(env, generated_impl.path in rustc_input_paths)
 
@@ -138,8 +174,8 @@ def _header_generation_test_impl(ctx):
 
     # Verify that `cc_info.compilation_context.direct_headers` contains `generated_header`.
     target_under_test = analysistest.target_under_test(env)
-    asserts.true(env, CcInfo in target_under_test)
-    cc_info = target_under_test[CcInfo]
+    asserts.true(env, CcBindingsFromRustInfo in target_under_test)
+    cc_info = target_under_test[CcBindingsFromRustInfo].cc_info
     asserts.true(env, len(cc_info.compilation_context.direct_headers) == 1)
     cc_info_header = cc_info.compilation_context.direct_headers[0]
     asserts.equals(env, generated_header, cc_info_header)
@@ -157,7 +193,10 @@ def _header_generation_test_impl(ctx):
 
     return analysistest.end(env)
 
-header_generation_test = analysistest.make(_header_generation_test_impl)
+header_generation_test = analysistest.make(
+    _header_generation_test_impl,
+    extra_target_under_test_aspects = [cc_bindings_from_rust_aspect],
+)
 
 def _cmdline_flags_test_impl(ctx):
     env = analysistest.begin(ctx)
@@ -190,9 +229,48 @@ def _cmdline_flags_test_impl(ctx):
         "Expected to find `--extern=emptylib` on the command line, got {}.".format(cmdline),
     )
 
+    # ":emptylib" gets C++ bindings as well, which are used by the bindings of
+    # the crate for which we are generating bindings.
+    asserts.true(
+        env,
+        _has_arg_with_prefix(cmdline, "--crate-header=emptylib="),
+        "Expected to find `--crate-header=emptylib=...` on the command line, got {}.".format(
+            cmdline,
+        ),
+    )
+
     return analysistest.end(env)
 
-cmdline_flags_test = analysistest.make(_cmdline_flags_test_impl)
+cmdline_flags_test = analysistest.make(
+    _cmdline_flags_test_impl,
+    extra_target_under_test_aspects = [cc_bindings_from_rust_aspect],
+)
+
+def _bindings_rule_test_impl(ctx):
+    env = analysistest.begin(ctx)
+
+    # Verify that `cc_bindings_from_rust` exposes the bindings generated by
+    # `cc_bindings_from_rust_aspect`, including the bindings of the dependencies.
+    target_under_test = analysistest.target_under_test(env)
+    asserts.true(env, CcInfo in target_under_test)
+    headers = [
+        h.basename
+        for h in target_under_test[CcInfo].compilation_context.headers.to_list()
+    ]
+    asserts.true(
+        env,
+        "rusty_lib_cc_api.h" in headers,
+        "Expected to find `rusty_lib_cc_api.h` in the headers, got {}.".format(headers),
+    )
+    asserts.true(
+        env,
+        "emptylib_cc_api.h" in headers,
+        "Expected to find `emptylib_cc_api.h` in the headers, got {}.".format(headers),
+    )
+
+    return analysistest.end(env)
+
+bindings_rule_test = analysistest.make(_bindings_rule_test_impl)
 
 def _tests():
     rust_library(
@@ -215,11 +293,16 @@ def _tests():
 
     header_generation_test(
         name = "header_generation_test",
-        target_under_test = ":rusty_lib_bindings",
+        target_under_test = ":rusty_lib",
     )
 
     cmdline_flags_test(
         name = "cmdline_flags_test",
+        target_under_test = ":rusty_lib",
+    )
+
+    bindings_rule_test(
+        name = "bindings_rule_test",
         target_under_test = ":rusty_lib_bindings",
     )
 
@@ -235,5 +318,6 @@ def generating_files_test(name):
         tests = [
             ":header_generation_test",
             ":cmdline_flags_test",
+            ":bindings_rule_test",
         ],
     )
@@ -0,0 +1,34 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `consts_test.cc`.
+
+/// Test for `&'static str` constants (which are formatted as
+/// `inline constexpr std::string_view`).
+pub mod str_consts {
+
+    pub const VERSION: &str = "1.2.3";
+
+    pub static KEY: &str = concat!("some", "_", "key");
+
+    pub const UNICODE: &str = "żółw \"quoted\"";
+}
+
+/// Test for thread-local statics (which are accessed through generated
+/// accessor functions).
+pub mod thread_locals {
+    use std::cell::Cell;
+
+    thread_local! {
+        pub static COUNTER: Cell<u32> = Cell::new(0);
+    }
+
+    pub fn increment_counter() -> u32 {
+        COUNTER.with(|counter| {
+            counter.set(counter.get() + 1);
+            counter.get()
+        })
+    }
+}
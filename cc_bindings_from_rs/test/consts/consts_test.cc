@@ -0,0 +1,43 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <string_view>
+#include <thread>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/consts/consts_cc_api.h"
+
+namespace crubit {
+namespace {
+
+TEST(ConstsTest, StrConstsAreConstexprStringViews) {
+  namespace test = consts::str_consts;
+  static_assert(
+      std::is_same_v<decltype(test::VERSION), const std::string_view>);
+  static_assert(test::VERSION == "1.2.3");
+  static_assert(test::KEY == "some_key");
+  EXPECT_EQ(test::UNICODE, "żółw \"quoted\"");
+}
+
+TEST(ConstsTest, ThreadLocalAccessor) {
+  namespace test = consts::thread_locals;
+  std::uint32_t* counter = test::COUNTER();
+  EXPECT_EQ(0, *counter);
+  EXPECT_EQ(1, test::increment_counter());
+  EXPECT_EQ(1, *counter);
+  *counter = 10;
+  EXPECT_EQ(11, test::increment_counter());
+
+  // Other threads have their own instance of the thread-local static.
+  std::thread([] {
+    EXPECT_EQ(0, *test::COUNTER());
+    EXPECT_EQ(1, test::increment_counter());
+  }).join();
+  EXPECT_EQ(11, *test::COUNTER());
+}
+
+}  // namespace
+}  // namespace crubit
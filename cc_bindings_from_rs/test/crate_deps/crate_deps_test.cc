@@ -0,0 +1,25 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/crate_deps/uses_points_cc_api.h"
+
+namespace crubit {
+namespace {
+
+TEST(CrateDepsTest, TypeFromDependencyCrate) {
+  points::Point p = points::create(123, 456);
+  EXPECT_EQ(123, uses_points::get_x(std::move(p)));
+}
+
+TEST(CrateDepsTest, TypeFromDependencyCrateReturnedByValue) {
+  points::Point p = uses_points::mirror(points::create(123, 456));
+  EXPECT_EQ(456, uses_points::get_x(std::move(p)));
+}
+
+}  // namespace
+}  // namespace crubit
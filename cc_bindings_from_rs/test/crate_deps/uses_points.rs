@@ -0,0 +1,17 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `crate_deps_test.cc`.  The
+//! public API of this crate uses types from the `points` crate.
+
+use points::Point;
+
+pub fn get_x(p: Point) -> i32 {
+    p.x
+}
+
+pub fn mirror(p: Point) -> Point {
+    Point { x: p.y, y: p.x }
+}
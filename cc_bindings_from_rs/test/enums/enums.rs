@@ -0,0 +1,56 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `enums_test.cc`.
+
+/// Test for a fieldless enum with a primitive representation (which is
+/// formatted as a C++ `enum class`).
+pub mod repr_int {
+
+    #[repr(i32)]
+    pub enum Color {
+        Red = -1,
+        Green = 10,
+        Blue,
+    }
+
+    pub fn next(color: Color) -> Color {
+        match color {
+            Color::Red => Color::Green,
+            Color::Green => Color::Blue,
+            Color::Blue => Color::Red,
+        }
+    }
+
+    pub fn is_blue(color: *const Color) -> bool {
+        matches!(unsafe { &*color }, Color::Blue)
+    }
+
+    #[repr(u8)]
+    pub enum Flag {
+        A = 0x01,
+        B = 0x80,
+    }
+}
+
+/// Test for a `#[non_exhaustive]` fieldless enum with a primitive
+/// representation (which is formatted as a C++ `class` with named constants).
+pub mod non_exhaustive {
+
+    #[repr(u16)]
+    #[non_exhaustive]
+    pub enum Status {
+        Ok = 200,
+        NotFound = 404,
+    }
+
+    pub fn get_not_found() -> Status {
+        Status::NotFound
+    }
+
+    pub fn to_u16(status: Status) -> u16 {
+        status as u16
+    }
+}
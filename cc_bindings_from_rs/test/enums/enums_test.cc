@@ -0,0 +1,75 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <optional>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/enums/enums_cc_api.h"
+
+namespace crubit {
+namespace {
+
+TEST(EnumsTest, ReprIntUnderlyingType) {
+  namespace test = enums::repr_int;
+  static_assert(std::is_enum_v<test::Color>);
+  static_assert(
+      std::is_same_v<std::underlying_type_t<test::Color>, std::int32_t>);
+  static_assert(
+      std::is_same_v<std::underlying_type_t<test::Flag>, std::uint8_t>);
+}
+
+TEST(EnumsTest, ReprIntDiscriminants) {
+  namespace test = enums::repr_int;
+  static_assert(static_cast<std::int32_t>(test::Color::Red) == -1);
+  static_assert(static_cast<std::int32_t>(test::Color::Green) == 10);
+  static_assert(static_cast<std::int32_t>(test::Color::Blue) == 11);
+  static_assert(static_cast<std::uint8_t>(test::Flag::B) == 0x80);
+}
+
+TEST(EnumsTest, ReprIntPassedByValue) {
+  namespace test = enums::repr_int;
+  EXPECT_EQ(test::Color::Green, test::next(test::Color::Red));
+  EXPECT_EQ(test::Color::Red, test::next(test::Color::Blue));
+
+  test::Color color = test::Color::Blue;
+  EXPECT_TRUE(test::is_blue(&color));
+}
+
+TEST(EnumsTest, ReprIntFromIntAndToInt) {
+  namespace test = enums::repr_int;
+  static_assert(test::ColorFromInt(10) == test::Color::Green);
+  static_assert(test::ColorFromInt(12) == std::nullopt);
+  static_assert(test::ColorToInt(test::Color::Red) == -1);
+
+  EXPECT_EQ(test::FlagFromInt(0x80), test::Flag::B);
+  EXPECT_EQ(test::FlagFromInt(0x02), std::nullopt);
+  EXPECT_EQ(test::FlagToInt(test::Flag::A), 0x01);
+}
+
+TEST(EnumsTest, NonExhaustiveNamedConstants) {
+  namespace test = enums::non_exhaustive;
+  static_assert(!std::is_enum_v<test::Status>);
+  static_assert(test::Status::Ok.ToInt() == 200);
+  static_assert(test::Status::NotFound.IsKnown());
+  static_assert(test::Status::Ok != test::Status::NotFound);
+}
+
+TEST(EnumsTest, NonExhaustivePassedByValue) {
+  namespace test = enums::non_exhaustive;
+  test::Status status = test::get_not_found();
+  EXPECT_EQ(status, test::Status::NotFound);
+  EXPECT_EQ(404, test::to_u16(status));
+  EXPECT_EQ(200, test::to_u16(test::Status::Ok));
+}
+
+TEST(EnumsTest, NonExhaustiveFromInt) {
+  namespace test = enums::non_exhaustive;
+  static_assert(test::Status::FromInt(404) == test::Status::NotFound);
+  EXPECT_EQ(test::Status::FromInt(418), std::nullopt);
+}
+
+}  // namespace
+}  // namespace crubit
@@ -76,6 +76,78 @@ pub mod fn_param_ty_tests {
         // compatibility between Rust `char` and C++ `rs_std::rs_char`.
         c.to_ascii_lowercase()
     }
+
+    /// Testing slice references - they map to `rs_std::Slice<T>` and
+    /// `rs_std::MutSlice<T>` in C++.
+    pub fn sum_i32_slice(s: &[i32]) -> i32 {
+        s.iter().sum()
+    }
+
+    pub fn double_i32_slice_in_place(s: &mut [i32]) {
+        for x in s.iter_mut() {
+            *x *= 2;
+        }
+    }
+
+    /// Testing returning a slice that borrows from a parameter - the C++
+    /// parameter is annotated with `CRUBIT_LIFETIME_BOUND`.
+    pub fn first_half_of_i32_slice(s: &mut [i32]) -> &mut [i32] {
+        let len = s.len() / 2;
+        &mut s[..len]
+    }
+
+    /// Testing `MaybeUninit` out-parameters - `&mut MaybeUninit<T>` maps to
+    /// `T*` in C++.
+    pub fn init_i32_out_param(value: i32, out: &mut core::mem::MaybeUninit<i32>) {
+        out.write(value * 2);
+    }
+
+    /// Testing string slice references - they map to `rs_std::Str` in C++.
+    pub fn count_chars(s: &str) -> usize {
+        s.chars().count()
+    }
+
+    /// Testing `NonZero...` integers - they map to `rs_std::NonZero<T>` in C++,
+    /// and `Option<NonZero...>` maps to the plain integer type (with 0
+    /// representing `None`).
+    pub fn checked_add_nonzero_u32(
+        x: std::num::NonZeroU32,
+        y: Option<std::num::NonZeroU32>,
+    ) -> Option<std::num::NonZeroU32> {
+        y.and_then(|y| x.checked_add(y.get()))
+    }
+
+    /// Testing `Cow<str>` - it maps to `rs_std::CowStr` in C++.  The result is
+    /// only owned if `s` contains spaces.
+    pub fn remove_spaces(s: &str) -> std::borrow::Cow<'_, str> {
+        if s.contains(' ') {
+            std::borrow::Cow::Owned(s.replace(' ', ""))
+        } else {
+            std::borrow::Cow::Borrowed(s)
+        }
+    }
+
+    pub fn count_chars_in_cow_str(s: std::borrow::Cow<'_, str>) -> usize {
+        s.chars().count()
+    }
+
+    /// Testing `Vec<T>` - it maps to `rs_std::Vec<T>` in C++.
+    pub fn squares(n: i32) -> Vec<i32> {
+        (0..n).map(|i| i * i).collect()
+    }
+
+    pub fn sum_i32_vec(v: Vec<i32>) -> i32 {
+        v.iter().sum()
+    }
+
+    /// Testing `Box<T>` - it maps to `rs_std::Box<T>` in C++.
+    pub fn boxed_i32(x: i32) -> Box<i32> {
+        Box::new(x)
+    }
+
+    pub fn unbox_i32(b: Box<i32>) -> i32 {
+        *b
+    }
 }
 
 /// APIs for testing functions that return the unit / `()` / `void` type.
@@ -102,3 +174,30 @@ pub mod other_fn_param_tests {
         x + y
     }
 }
+
+pub mod unsafe_fn_tests {
+    /// # Safety
+    ///
+    /// `x` has to point to a valid `i32`.
+    pub unsafe fn read_i32_via_rust_abi(x: *const i32) -> i32 {
+        *x
+    }
+
+    /// # Safety
+    ///
+    /// `x` has to point to a valid `i32`.
+    #[no_mangle]
+    pub unsafe extern "C" fn read_i32_via_extern_c(x: *const i32) -> i32 {
+        *x
+    }
+
+    /// Testing `NonNull<T>` - it maps to `rs_std::NonNull<T>` (a non-nullable
+    /// annotated pointer) in C++.
+    ///
+    /// # Safety
+    ///
+    /// `x` has to point to a valid `i32`.
+    pub unsafe fn double_i32_via_nonnull(x: std::ptr::NonNull<i32>) {
+        *x.as_ptr() *= 2;
+    }
+}
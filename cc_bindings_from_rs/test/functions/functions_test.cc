@@ -2,10 +2,24 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#include <cstdint>
+#include <optional>
+#include <utility>
+#include <vector>
+
 #include "gmock/gmock.h"
 #include "gtest/gtest.h"
+#include "absl/types/span.h"
 #include "cc_bindings_from_rs/test/functions/functions_cc_api.h"
+#include "support/rs_std/rs_box.h"
 #include "support/rs_std/rs_char.h"
+#include "support/rs_std/rs_cow_str.h"
+#include "support/rs_std/rs_nonnull.h"
+#include "support/rs_std/rs_nonzero.h"
+#include "support/rs_std/rs_slice.h"
+#include "support/rs_std/rs_str.h"
+#include "support/rs_std/rs_vec.h"
+#include "support/rs_std/unsafe_call.h"
 
 namespace crubit {
 namespace {
@@ -67,6 +81,88 @@ TEST(FnParamTyTests, Int32Ptr) {
   EXPECT_EQ(12 + 34, sum);
 }
 
+TEST(FnParamTyTests, Int32Slice) {
+  std::vector<std::int32_t> v = {1, 2, 3};
+  EXPECT_EQ(1 + 2 + 3,
+            fn_param_ty_tests::sum_i32_slice(absl::MakeConstSpan(v)));
+  EXPECT_EQ(0, fn_param_ty_tests::sum_i32_slice(rs_std::Slice<std::int32_t>()));
+}
+
+TEST(FnParamTyTests, Int32MutSlice) {
+  std::vector<std::int32_t> v = {1, 2, 3};
+  fn_param_ty_tests::double_i32_slice_in_place(absl::MakeSpan(v));
+  EXPECT_THAT(v, testing::ElementsAre(2, 4, 6));
+}
+
+TEST(FnParamTyTests, Int32MutSliceReturnValue) {
+  std::vector<std::int32_t> v = {1, 2, 3, 4, 5};
+  rs_std::MutSlice<std::int32_t> slice = absl::MakeSpan(v);
+  rs_std::MutSlice<std::int32_t> first_half =
+      fn_param_ty_tests::first_half_of_i32_slice(slice);
+  ASSERT_EQ(2, first_half.size());
+  first_half[1] = 20;
+  EXPECT_EQ(v.data(), first_half.data());
+  EXPECT_THAT(v, testing::ElementsAre(1, 20, 3, 4, 5));
+}
+
+TEST(FnParamTyTests, MaybeUninitOutParam) {
+  std::int32_t out;  // Intentionally uninitialized.
+  fn_param_ty_tests::init_i32_out_param(21, &out);
+  EXPECT_EQ(42, out);
+}
+
+TEST(FnParamTyTests, Str) {
+  // "żółw" is 4 characters (but 7 bytes) long.
+  std::optional<rs_std::Str> s =
+      rs_std::Str::from_utf8("\xC5\xBC\xC3\xB3\xC5\x82w");
+  ASSERT_TRUE(s.has_value());
+  EXPECT_EQ(4, fn_param_ty_tests::count_chars(*s));
+  EXPECT_EQ(0, fn_param_ty_tests::count_chars(rs_std::Str()));
+}
+
+TEST(FnParamTyTests, CowStr) {
+  rs_std::CowStr borrowed =
+      fn_param_ty_tests::remove_spaces(*rs_std::Str::from_utf8("foo"));
+  EXPECT_TRUE(borrowed.is_borrowed());
+  EXPECT_EQ(borrowed, "foo");
+
+  rs_std::CowStr owned =
+      fn_param_ty_tests::remove_spaces(*rs_std::Str::from_utf8("f o o"));
+  EXPECT_TRUE(owned.is_owned());
+  EXPECT_EQ(owned, "foo");
+
+  EXPECT_EQ(3, fn_param_ty_tests::count_chars_in_cow_str(std::move(owned)));
+  EXPECT_EQ(3, fn_param_ty_tests::count_chars_in_cow_str(std::move(borrowed)));
+}
+
+TEST(FnParamTyTests, Vec) {
+  rs_std::Vec<std::int32_t> v = fn_param_ty_tests::squares(4);
+  EXPECT_THAT(v, testing::ElementsAre(0, 1, 4, 9));
+
+  v.push_back(16);
+  EXPECT_EQ(30, fn_param_ty_tests::sum_i32_vec(std::move(v)));
+  EXPECT_EQ(0, fn_param_ty_tests::sum_i32_vec(rs_std::Vec<std::int32_t>()));
+}
+
+TEST(FnParamTyTests, Box) {
+  rs_std::Box<std::int32_t> b = fn_param_ty_tests::boxed_i32(123);
+  EXPECT_EQ(123, *b);
+
+  *b = 456;
+  EXPECT_EQ(456, fn_param_ty_tests::unbox_i32(std::move(b)));
+  EXPECT_EQ(789, fn_param_ty_tests::unbox_i32(
+                     rs_std::Box<std::int32_t>::make(789)));
+}
+
+TEST(FnParamTyTests, NonZero) {
+  std::optional<rs_std::NonZeroU32> x = rs_std::NonZeroU32::from_int(2);
+  ASSERT_TRUE(x.has_value());
+  EXPECT_EQ(5, fn_param_ty_tests::checked_add_nonzero_u32(*x, 3));
+  // 0 stands for `None` (both in the parameter and in the return value).
+  EXPECT_EQ(0, fn_param_ty_tests::checked_add_nonzero_u32(*x, 0));
+  EXPECT_EQ(0, fn_param_ty_tests::checked_add_nonzero_u32(*x, UINT32_MAX));
+}
+
 TEST(OtherFnTests, VoidReturningFunction) {
   namespace tests = functions::unit_ret_ty_tests;
   tests::set_global_i32_via_extern_c_with_export_name(123);
@@ -82,5 +178,19 @@ TEST(OtherFnTests, DuplicatedParamNames) {
                          12, 34, 56, 78));
 }
 
+TEST(OtherFnTests, UnsafeFunctions) {
+  namespace tests = functions::unsafe_fn_tests;
+  std::int32_t x = 123;
+  EXPECT_EQ(123, tests::read_i32_via_rust_abi(rs_std::UnsafeCall(), &x));
+  EXPECT_EQ(123, tests::read_i32_via_extern_c(rs_std::UnsafeCall(), &x));
+}
+
+TEST(OtherFnTests, NonNull) {
+  namespace tests = functions::unsafe_fn_tests;
+  std::int32_t x = 123;
+  tests::double_i32_via_nonnull(rs_std::UnsafeCall(), rs_std::CheckNonNull(&x));
+  EXPECT_EQ(246, x);
+}
+
 }  // namespace
 }  // namespace crubit
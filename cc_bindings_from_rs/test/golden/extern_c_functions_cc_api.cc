@@ -0,0 +1,12 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+// Automatically @generated C++ bindings for the following Rust crate:
+// extern_c_functions
+
+#include <cstddef>
+
+#include "extern_c_functions_cc_api.h"
+
+namespace extern_c_functions {}
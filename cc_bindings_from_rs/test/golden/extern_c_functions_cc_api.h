@@ -0,0 +1,21 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+// Automatically @generated C++ bindings for the following Rust crate:
+// extern_c_functions
+
+#pragma once
+
+#include <cstdint>
+
+static_assert(sizeof(void*) == 8, "Bindings are for a different target");
+
+namespace extern_c_functions {
+
+// Adds two integers.
+//
+// Generated from: google3/extern_c_functions.rs;l=9
+extern "C" std::int32_t add(std::int32_t x, std::int32_t y);
+
+}  // namespace extern_c_functions
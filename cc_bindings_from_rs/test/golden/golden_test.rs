@@ -0,0 +1,202 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! Golden-file tests of `cc_bindings_from_rs`: generates the C++ bindings of
+//! each fixture crate (e.g. `foo.rs`) and compares them against the checked-in
+//! golden files (e.g. `foo_cc_api.h`, `foo_cc_api.cc`, and
+//! `foo_cc_api_impl.rs`).  See also `README.md`.
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::Parser;
+use itertools::Itertools;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Parser)]
+#[clap(name = "golden_test")]
+#[clap(about = "Golden-file tests of cc_bindings_from_rs", long_about = None)]
+struct Cmdline {
+    /// Path to the `cc_bindings_from_rs` executable.
+    #[clap(long, value_parser, value_name = "FILE")]
+    cc_bindings_from_rs: PathBuf,
+
+    /// Path to the clang-format executable used by `cc_bindings_from_rs`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    clang_format_exe_path: PathBuf,
+
+    /// Path to the rustfmt executable used by `cc_bindings_from_rs`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    rustfmt_exe_path: PathBuf,
+
+    /// Path to the rustfmt config used by `cc_bindings_from_rs`.
+    #[clap(long, value_parser, value_name = "FILE")]
+    rustfmt_config_path: Option<PathBuf>,
+
+    /// Path to the license header that is prepended to the golden files
+    /// (because they are checked in).
+    #[clap(long, value_parser, value_name = "FILE")]
+    license_header: PathBuf,
+
+    /// Overwrite the golden files with the newly generated bindings (instead
+    /// of comparing them).  Requires running the test via `bazel run`.
+    #[clap(long)]
+    update_goldens: bool,
+
+    /// Fixture crates (e.g. `foo.rs`) - the golden files are expected to be
+    /// in the same directory as the fixture.
+    #[clap(value_parser, value_name = "FILE", required = true)]
+    fixtures: Vec<PathBuf>,
+}
+
+/// Suffixes of the files generated for a fixture crate - e.g. `foo.rs` results
+/// in `foo_cc_api.h`, etc.
+const GOLDEN_SUFFIXES: [&str; 3] = ["_cc_api.h", "_cc_api.cc", "_cc_api_impl.rs"];
+
+/// Generates the bindings of the `fixture` crate.  Returns the names and the
+/// contents (prefixed with `license_header`) of the generated files.
+fn generate_bindings(
+    cmdline: &Cmdline,
+    license_header: &str,
+    fixture: &Path,
+) -> Result<Vec<(String, String)>> {
+    let crate_name = fixture
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .with_context(|| format!("Invalid fixture name: {}", fixture.display()))?;
+    let absolute = |path: &Path| {
+        path.canonicalize().with_context(|| format!("Failed to find {}", path.display()))
+    };
+
+    // The tool runs in a temporary directory and is given relative paths, so that
+    // the generated files (e.g. `#include`s and source locations) don't depend on
+    // the location of the temporary directory.
+    let tempdir = tempfile::tempdir()?;
+    let rs_input = format!("{crate_name}.rs");
+    std::fs::copy(fixture, tempdir.path().join(&rs_input))
+        .with_context(|| format!("Failed to copy {}", fixture.display()))?;
+    let [h_out, cc_out, rs_out] = GOLDEN_SUFFIXES.map(|suffix| format!("{crate_name}{suffix}"));
+
+    let mut command = Command::new(absolute(&cmdline.cc_bindings_from_rs)?);
+    command
+        .current_dir(tempdir.path())
+        .arg(format!("--h-out={h_out}"))
+        .arg(format!("--cc-out={cc_out}"))
+        .arg(format!("--rs-out={rs_out}"))
+        .arg("--crubit-support-path=crubit/support")
+        .arg(format!(
+            "--clang-format-exe-path={}",
+            absolute(&cmdline.clang_format_exe_path)?.display()
+        ))
+        .arg(format!("--rustfmt-exe-path={}", absolute(&cmdline.rustfmt_exe_path)?.display()));
+    if let Some(rustfmt_config_path) = cmdline.rustfmt_config_path.as_ref() {
+        command.arg(format!("--rustfmt-config-path={}", absolute(rustfmt_config_path)?.display()));
+    }
+    command.args(["--", &rs_input, "--crate-type=lib", "--codegen=panic=abort"]);
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}", cmdline.cc_bindings_from_rs.display()))?;
+    ensure!(
+        output.status.success(),
+        "Failed to generate the bindings of {}:\n{}",
+        fixture.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    [h_out, cc_out, rs_out]
+        .into_iter()
+        .map(|file_name| {
+            let path = tempdir.path().join(&file_name);
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            Ok((file_name, format!("{license_header}{content}")))
+        })
+        .collect()
+}
+
+/// Formats a line-based diff between the `expected` and `actual` contents of
+/// a golden file.  The diff is based on the longest common subsequence of lines.
+fn format_diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect_vec();
+    let actual = actual.lines().collect_vec();
+
+    // `lcs[i][j]` is the length of the longest common subsequence of
+    // `expected[i..]` and `actual[j..]`.
+    let mut lcs = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            result += &format!(" {}\n", expected[i]);
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            result += &format!("+{}\n", actual[j]);
+            j += 1;
+        } else {
+            result += &format!("-{}\n", expected[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
+fn run(cmdline: &Cmdline) -> Result<()> {
+    let license_header = std::fs::read_to_string(&cmdline.license_header)
+        .with_context(|| format!("Failed to read {}", cmdline.license_header.display()))?;
+    // `bazel run` runs the test in its runfiles directory, but provides the path
+    // to the source tree via `BUILD_WORKSPACE_DIRECTORY`.
+    let workspace_dir = if cmdline.update_goldens {
+        let dir = std::env::var_os("BUILD_WORKSPACE_DIRECTORY")
+            .context("`--update-goldens` requires running the test via `bazel run`")?;
+        Some(PathBuf::from(dir))
+    } else {
+        None
+    };
+
+    let mut failures = vec![];
+    for fixture in cmdline.fixtures.iter() {
+        let golden_dir = fixture.parent().unwrap_or_else(|| Path::new(""));
+        for (file_name, actual) in generate_bindings(cmdline, &license_header, fixture)? {
+            let golden_path = golden_dir.join(&file_name);
+            if let Some(workspace_dir) = workspace_dir.as_ref() {
+                let golden_path = workspace_dir.join(&golden_path);
+                std::fs::write(&golden_path, &actual)
+                    .with_context(|| format!("Failed to write {}", golden_path.display()))?;
+                println!("Updated {}", golden_path.display());
+                continue;
+            }
+            // A missing golden file is reported as a diff against an empty file.
+            let expected = std::fs::read_to_string(&golden_path).unwrap_or_default();
+            if expected != actual {
+                let diff = format_diff(&expected, &actual);
+                failures.push(format!("--- {}\n+++ (generated)\n{diff}", golden_path.display()));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "The generated bindings don't match {} golden file(s).  If the changes are \
+             expected, then run `bazel run //cc_bindings_from_rs/test/golden:golden_test -- \
+             --update-goldens`.\n\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    run(&Cmdline::parse())
+}
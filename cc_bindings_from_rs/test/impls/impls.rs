@@ -65,3 +65,104 @@ pub mod non_contiguous_method_decls_and_defs {
         }
     }
 }
+
+/// Test for methods taking `self` by value, by shared reference, and by
+/// mutable reference (which map to `&&`-qualified, `const`, and non-`const` C++
+/// member functions respectively).
+pub mod methods_taking_self {
+    pub struct Counter(i32);
+
+    impl Counter {
+        pub fn create(i: i32) -> Self {
+            Self(i)
+        }
+        pub fn get(&self) -> i32 {
+            self.0
+        }
+        pub fn add(&mut self, delta: i32) {
+            self.0 += delta;
+        }
+        pub fn into_i32(self) -> i32 {
+            self.0
+        }
+    }
+}
+
+/// Test for `impl`s of the compound assignment and unary operator traits
+/// (which map to C++ operators).
+pub mod operators {
+    use std::ops::{AddAssign, MulAssign, Neg, Not};
+
+    pub struct Vector {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    impl Vector {
+        pub fn create(x: i32, y: i32) -> Self {
+            Self { x, y }
+        }
+    }
+
+    impl AddAssign for Vector {
+        fn add_assign(&mut self, other: Self) {
+            self.x += other.x;
+            self.y += other.y;
+        }
+    }
+
+    impl MulAssign<i32> for Vector {
+        fn mul_assign(&mut self, factor: i32) {
+            self.x *= factor;
+            self.y *= factor;
+        }
+    }
+
+    impl Neg for Vector {
+        type Output = Self;
+        fn neg(self) -> Self::Output {
+            Self { x: -self.x, y: -self.y }
+        }
+    }
+
+    impl Not for Vector {
+        type Output = bool;
+        fn not(self) -> bool {
+            self.x == 0 && self.y == 0
+        }
+    }
+}
+
+pub mod views {
+    use std::borrow::Borrow;
+
+    pub struct Label {
+        text: &'static str,
+        bytes: [u8; 3],
+        values: [i32; 2],
+    }
+
+    impl Label {
+        pub fn create() -> Self {
+            Self { text: "foo", bytes: [1, 2, 3], values: [10, 20] }
+        }
+    }
+
+    impl AsRef<str> for Label {
+        fn as_ref(&self) -> &str {
+            self.text
+        }
+    }
+
+    impl AsRef<[u8]> for Label {
+        fn as_ref(&self) -> &[u8] {
+            &self.bytes
+        }
+    }
+
+    impl Borrow<[i32]> for Label {
+        fn borrow(&self) -> &[i32] {
+            &self.values
+        }
+    }
+}
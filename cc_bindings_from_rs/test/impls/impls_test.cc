@@ -2,6 +2,7 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#include <string_view>
 #include <utility>
 
 #include "gmock/gmock.h"
@@ -30,5 +31,38 @@ TEST(ImplsTest, NonContiguousMethodDeclsAndDefs) {
                                              test::S2::create(456)));
 }
 
+TEST(ImplsTest, MethodsTakingSelf) {
+  namespace test = impls::methods_taking_self;
+  test::Counter counter = test::Counter::create(123);
+  const test::Counter& const_ref = counter;
+  EXPECT_EQ(123, const_ref.get());
+  counter.add(456);
+  EXPECT_EQ(123 + 456, const_ref.get());
+  EXPECT_EQ(123 + 456, std::move(counter).into_i32());
+}
+
+TEST(ImplsTest, Operators) {
+  namespace test = impls::operators;
+  test::Vector v = test::Vector::create(1, 2);
+  v += test::Vector::create(10, 20);
+  v *= 2;
+  EXPECT_EQ(22, v.x);
+  EXPECT_EQ(44, v.y);
+
+  test::Vector negated = -std::move(v);
+  EXPECT_EQ(-22, negated.x);
+  EXPECT_EQ(-44, negated.y);
+  EXPECT_FALSE(!std::move(negated));
+  EXPECT_TRUE(!test::Vector::create(0, 0));
+}
+
+TEST(ImplsTest, AsRefAndBorrowAccessors) {
+  namespace test = impls::views;
+  const test::Label label = test::Label::create();
+  EXPECT_EQ(std::string_view(label.as_str()), "foo");
+  EXPECT_THAT(label.as_bytes(), testing::ElementsAre(1, 2, 3));
+  EXPECT_THAT(label.as_i32_slice(), testing::ElementsAre(10, 20));
+}
+
 }  // namespace
 }  // namespace crubit
@@ -0,0 +1,125 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `abi_test.cc`.  Each function
+//! below is called from C++ and the test verifies that all the arguments and
+//! the return value make it across the language boundary intact.
+//!
+//! The functions compute new values from *all* of their arguments, so that
+//! an argument that is passed in an unexpected register or stack slot is
+//! detected by the test.
+
+/// Tests of primitive types.
+pub mod primitives {
+    pub fn negate(b: bool) -> bool {
+        !b
+    }
+
+    pub fn i8_min() -> i8 {
+        i8::MIN
+    }
+
+    pub fn u64_max() -> u64 {
+        u64::MAX
+    }
+
+    pub fn next_char(c: char) -> char {
+        char::from_u32(c as u32 + 1).unwrap_or(c)
+    }
+}
+
+/// Tests of functions with more parameters than there are argument registers
+/// in the System V ABI (6 integer registers and 8 SSE registers).  The
+/// remaining arguments are passed on the stack.
+pub mod many_params {
+    #[allow(clippy::too_many_arguments)]
+    pub fn sum_ints(a: i8, b: i16, c: i32, d: i64, e: u8, f: u16, g: u32, h: u64) -> i64 {
+        a as i64 + b as i64 + c as i64 + d + e as i64 + f as i64 + g as i64 + h as i64
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn sum_floats(
+        a: f32,
+        b: f64,
+        c: f32,
+        d: f64,
+        e: f32,
+        f: f64,
+        g: f32,
+        h: f64,
+        i: f32,
+        j: f64,
+    ) -> f64 {
+        // Weighting the arguments by their position detects swapped arguments.
+        [a as f64, b, c as f64, d, e as f64, f, g as f64, h, i as f64, j]
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (index + 1) as f64 * value)
+            .sum()
+    }
+
+    pub fn interleaved(a: i32, b: f64, c: i64, d: f32, e: u8, f: f64) -> f64 {
+        a as f64
+            + 10.0 * b
+            + 100.0 * c as f64
+            + 1000.0 * d as f64
+            + 10000.0 * e as f64
+            + 100000.0 * f
+    }
+}
+
+/// Tests of structs with different ABI classifications (see also
+/// `cc_bindings_from_rs/test/structs/structs.rs`).
+pub mod structs {
+    /// Expected ABI classification: one integer eightbyte and one SSE
+    /// eightbyte.
+    #[repr(C)]
+    pub struct Mixed {
+        pub i: i8,
+        pub d: f64,
+    }
+
+    impl Mixed {
+        pub fn create(i: i8, d: f64) -> Self {
+            Self { i, d }
+        }
+
+        pub fn scale(m: Self, factor: i8) -> Self {
+            Self { i: m.i * factor, d: m.d * factor as f64 }
+        }
+
+        pub fn get_i(m: Self) -> i8 {
+            m.i
+        }
+
+        pub fn get_d(m: Self) -> f64 {
+            m.d
+        }
+    }
+
+    /// Expected ABI classification: memory (the struct is bigger than 16
+    /// bytes, so it is passed and returned via a hidden pointer).
+    pub struct Large {
+        a: u64,
+        b: u64,
+        c: u64,
+        d: u64,
+        e: u64,
+    }
+
+    impl Large {
+        pub fn create(seed: u64) -> Self {
+            Self { a: seed, b: seed + 1, c: seed + 2, d: seed + 3, e: seed + 4 }
+        }
+
+        pub fn combine(x: Self, y: Self) -> Self {
+            Self { a: x.a + y.e, b: x.b + y.d, c: x.c + y.c, d: x.d + y.b, e: x.e + y.a }
+        }
+
+        pub fn checksum(l: Self) -> u64 {
+            l.a + 10 * l.b + 100 * l.c + 1000 * l.d + 10000 * l.e
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <limits>
+#include <optional>
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/round_trip/abi_cc_api.h"
+#include "support/rs_std/rs_char.h"
+
+namespace crubit {
+namespace {
+
+using testing::DoubleEq;
+
+TEST(PrimitivesTest, Bool) {
+  EXPECT_FALSE(abi::primitives::negate(true));
+  EXPECT_TRUE(abi::primitives::negate(false));
+}
+
+TEST(PrimitivesTest, IntegerLimits) {
+  EXPECT_EQ(std::numeric_limits<std::int8_t>::min(), abi::primitives::i8_min());
+  EXPECT_EQ(std::numeric_limits<std::uint64_t>::max(),
+            abi::primitives::u64_max());
+}
+
+TEST(PrimitivesTest, Char) {
+  std::optional<const rs_std::rs_char> input = rs_std::rs_char::from_u32(U'A');
+  ASSERT_TRUE(input.has_value());
+  rs_std::rs_char output = abi::primitives::next_char(*input);
+  EXPECT_EQ(std::uint32_t{U'B'}, std::uint32_t{output});
+}
+
+TEST(ManyParamsTest, Ints) {
+  EXPECT_EQ(-1 - 2 - 3 - 4 + 5 + 6 + 7 + 8,
+            abi::many_params::sum_ints(-1, -2, -3, -4, 5, 6, 7, 8));
+}
+
+TEST(ManyParamsTest, Floats) {
+  // 1*1 + 2*2 + ... + 10*10
+  EXPECT_THAT(abi::many_params::sum_floats(1.0f, 2.0, 3.0f, 4.0, 5.0f, 6.0,
+                                           7.0f, 8.0, 9.0f, 10.0),
+              DoubleEq(385.0));
+}
+
+TEST(ManyParamsTest, Interleaved) {
+  EXPECT_THAT(abi::many_params::interleaved(1, 2.0, 3, 4.0f, 5, 6.0),
+              DoubleEq(654321.0));
+}
+
+TEST(StructsTest, Mixed) {
+  using abi::structs::Mixed;
+  Mixed m = Mixed::scale(Mixed::create(3, 1.5), 2);
+  EXPECT_EQ(6, m.i);
+  EXPECT_THAT(m.d, DoubleEq(3.0));
+  EXPECT_EQ(6, Mixed::get_i(std::move(m)));
+  EXPECT_THAT(Mixed::get_d(Mixed::create(-4, 0.25)), DoubleEq(0.25));
+}
+
+TEST(StructsTest, Large) {
+  using abi::structs::Large;
+  Large combined = Large::combine(Large::create(1), Large::create(10));
+  // All the fields of `combined` are equal to 15.
+  EXPECT_EQ(std::uint64_t{15 * 11111}, Large::checksum(std::move(combined)));
+}
+
+}  // namespace
+}  // namespace crubit
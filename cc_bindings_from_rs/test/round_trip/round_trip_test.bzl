@@ -0,0 +1,62 @@
+# Part of the Crubit project, under the Apache License v2.0 with LLVM
+# Exceptions. See /LICENSE for license information.
+# SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+"""`round_trip_test` macro for end-to-end tests of `cc_bindings_from_rs`.
+
+Disclaimer: This project is experimental, under heavy development, and should
+not be used yet.
+"""
+
+load(
+    "@rules_rust//rust:defs.bzl",
+    "rust_library",
+)
+load(
+    "//cc_bindings_from_rs/bazel_support:cc_bindings_from_rust_rule.bzl",
+    "cc_bindings_from_rust",
+)
+
+def round_trip_test(name, rs_srcs, cc_srcs, rs_deps = [], cc_deps = [], **kwargs):
+    """Tests the C++ bindings of a fixture crate by calling them from C++.
+
+    The macro 1) builds the fixture crate (as `rust_library(name = <name>)`),
+    2) generates its C++ bindings (as `cc_bindings_from_rust(name =
+    <name>_cc_api)`), and 3) compiles `cc_srcs` against the generated header
+    and thunks, links them with the fixture crate, and runs them (as
+    `cc_test(name = <name>_test)`).  `cc_srcs` are expected to use gtest to
+    verify that arguments and return values make it across the language
+    boundary intact - this catches ABI bugs that the token-matcher unit tests
+    of `bindings.rs` can't detect.
+
+    Args:
+      name: The name of the fixture crate.  The test can `#include` the
+          generated bindings as `"<package>/<name>_cc_api.h"`.
+      rs_srcs: The sources of the fixture crate.
+      cc_srcs: The sources of the C++ test.
+      rs_deps: Additional dependencies of the fixture crate.
+      cc_deps: Additional dependencies of the C++ test.
+      **kwargs: Additional arguments of the `cc_test` (e.g. `tags`).
+    """
+    rust_library(
+        name = name,
+        testonly = 1,
+        srcs = rs_srcs,
+        deps = ["//common:rust_allocator_shims"] + rs_deps,
+    )
+
+    cc_bindings_from_rust(
+        name = name + "_cc_api",
+        testonly = 1,
+        crate = ":" + name,
+    )
+
+    native.cc_test(
+        name = name + "_test",
+        srcs = cc_srcs,
+        deps = [
+            ":" + name + "_cc_api",
+            "@com_google_googletest//:gtest_main",
+        ] + cc_deps,
+        **kwargs
+    )
@@ -118,3 +118,145 @@ pub mod abi_classification {
         }
     }
 }
+
+/// Test for a struct with a custom `Drop` implementation (returned by value
+/// through an out-pointer and moved in C++ via `Default`-based move thunks).
+pub mod drop_impl {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static DROPPED_VALUES_SUM: AtomicI32 = AtomicI32::new(0);
+
+    #[derive(Default)]
+    pub struct DropCounter {
+        value: i32,
+    }
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPPED_VALUES_SUM.fetch_add(self.value, Ordering::SeqCst);
+        }
+    }
+
+    pub fn create(value: i32) -> DropCounter {
+        DropCounter { value }
+    }
+
+    /// Returns the sum of `value`s of all the `DropCounter`s that have been
+    /// dropped so far.  (Moved-from `DropCounter`s are left with the default
+    /// `value` of 0 and therefore don't affect the sum.)
+    pub fn get_dropped_values_sum() -> i32 {
+        DROPPED_VALUES_SUM.load(Ordering::SeqCst)
+    }
+}
+
+/// Test for the `kIsSend` and `kIsSync` constants (which reflect whether the
+/// Rust type implements the `Send` and `Sync` auto traits).
+pub mod thread_safety {
+    use std::cell::Cell;
+
+    pub struct SendAndSync {
+        pub value: i32,
+    }
+
+    pub struct SendButNotSync {
+        value: Cell<i32>,
+    }
+
+    impl SendButNotSync {
+        pub fn create(value: i32) -> Self {
+            Self { value: Cell::new(value) }
+        }
+
+        pub fn get(s: Self) -> i32 {
+            s.value.get()
+        }
+    }
+}
+
+/// Test for a struct with interior mutability: its fields are opaque in C++, and
+/// its `&self` methods are not `const` member functions.
+pub mod interior_mutability {
+    use std::cell::RefCell;
+
+    pub struct Counter {
+        count: RefCell<i32>,
+    }
+
+    impl Counter {
+        pub fn create() -> Self {
+            Self { count: RefCell::new(0) }
+        }
+
+        pub fn increment(&self) -> i32 {
+            let mut count = self.count.borrow_mut();
+            *count += 1;
+            *count
+        }
+
+        pub fn get(&self) -> i32 {
+            *self.count.borrow()
+        }
+    }
+}
+
+/// Test for methods that return lock guards (exposed to C++ as RAII
+/// `rs_std::LockGuard<T>`).
+pub mod lock_guard {
+    use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
+
+    pub struct SharedCounter {
+        count: Mutex<i32>,
+        total: RwLock<i64>,
+    }
+
+    impl SharedCounter {
+        pub fn create() -> Self {
+            Self { count: Mutex::new(0), total: RwLock::new(0) }
+        }
+
+        pub fn lock_count(&self) -> MutexGuard<'_, i32> {
+            self.count.lock().unwrap()
+        }
+
+        pub fn read_total(&self) -> RwLockReadGuard<'_, i64> {
+            self.total.read().unwrap()
+        }
+
+        pub fn add_to_total(&self, value: i64) {
+            *self.total.write().unwrap() += value;
+        }
+
+        pub fn is_count_locked(&self) -> bool {
+            self.count.try_lock().is_err()
+        }
+    }
+}
+
+/// Test for a recursive struct: a field of type `Option<Box<Self>>` is exposed
+/// to C++ as a pointer (which only requires a forward declaration).
+pub mod recursive {
+    #[derive(Default)]
+    pub struct Node {
+        pub value: i32,
+        pub next: Option<Box<Node>>,
+    }
+
+    /// Creates a linked list with the values `1..=len`.
+    pub fn create_list(len: i32) -> Node {
+        let mut head = Node { value: len, next: None };
+        for value in (1..len).rev() {
+            head = Node { value, next: Some(Box::new(head)) };
+        }
+        head
+    }
+
+    pub fn sum(list: Node) -> i32 {
+        let mut sum = list.value;
+        let mut next = list.next;
+        while let Some(node) = next {
+            sum += node.value;
+            next = node.next;
+        }
+        sum
+    }
+}
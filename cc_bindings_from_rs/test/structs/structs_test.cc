@@ -2,11 +2,15 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#include <cstdint>
+#include <type_traits>
 #include <utility>
 
 #include "gmock/gmock.h"
 #include "gtest/gtest.h"
 #include "cc_bindings_from_rs/test/structs/structs_cc_api.h"
+#include "support/rs_std/rs_lock_guard.h"
+#include "support/rs_std/thread_safety.h"
 
 namespace crubit {
 namespace {
@@ -48,5 +52,82 @@ TEST(StructsTest, StructMemory) {
   EXPECT_EQ(321 * 654, test::StructMemory::inspect(std::move(product)));
 }
 
+TEST(StructsTest, DropImplReturnedByValueAndDestroyed) {
+  namespace test = structs::drop_impl;
+  int initial_sum = test::get_dropped_values_sum();
+  {
+    test::DropCounter counter = test::create(123);
+    EXPECT_EQ(initial_sum, test::get_dropped_values_sum());
+  }
+  EXPECT_EQ(initial_sum + 123, test::get_dropped_values_sum());
+}
+
+TEST(StructsTest, DropImplMoved) {
+  namespace test = structs::drop_impl;
+  int initial_sum = test::get_dropped_values_sum();
+  {
+    test::DropCounter counter1 = test::create(100);
+    test::DropCounter counter2 = std::move(counter1);
+    test::DropCounter counter3 = test::create(20);
+    counter3 = std::move(counter2);  // Drops the old value of `counter3`.
+    EXPECT_EQ(initial_sum + 20, test::get_dropped_values_sum());
+  }
+  // Only `counter3` still holds a non-default value.
+  EXPECT_EQ(initial_sum + 120, test::get_dropped_values_sum());
+}
+
+TEST(StructsTest, ThreadSafety) {
+  namespace test = structs::thread_safety;
+  static_assert(test::SendAndSync::kIsSend);
+  static_assert(test::SendAndSync::kIsSync);
+  static_assert(rs_std::is_send_v<test::SendButNotSync>);
+  static_assert(!rs_std::is_sync_v<test::SendButNotSync>);
+
+  test::SendButNotSync s = test::SendButNotSync::create(123);
+  EXPECT_EQ(123, test::SendButNotSync::get(std::move(s)));
+}
+
+TEST(StructsTest, InteriorMutability) {
+  namespace test = structs::interior_mutability;
+  // Rust may modify the object through `&self`, so C++ can't call these
+  // methods on `const` objects.
+  static_assert(!std::is_invocable_v<decltype(&test::Counter::increment),
+                                     const test::Counter&>);
+  static_assert(!std::is_invocable_v<decltype(&test::Counter::get),
+                                     const test::Counter&>);
+
+  test::Counter counter = test::Counter::create();
+  EXPECT_EQ(1, counter.increment());
+  EXPECT_EQ(2, counter.increment());
+  EXPECT_EQ(2, counter.get());
+}
+
+TEST(StructsTest, LockGuard) {
+  namespace test = structs::lock_guard;
+  test::SharedCounter counter = test::SharedCounter::create();
+  {
+    rs_std::LockGuard<std::int32_t> guard = counter.lock_count();
+    EXPECT_TRUE(counter.is_count_locked());
+    *guard += 123;
+  }
+  EXPECT_FALSE(counter.is_count_locked());
+
+  rs_std::LockGuard<std::int32_t> guard = counter.lock_count();
+  EXPECT_EQ(123, *guard);
+  guard.unlock();
+  EXPECT_FALSE(guard.owns_lock());
+  EXPECT_FALSE(counter.is_count_locked());
+
+  counter.add_to_total(456);
+  rs_std::LockGuard<const std::int64_t> total = counter.read_total();
+  EXPECT_EQ(456, *total);
+}
+
+TEST(StructsTest, RecursiveStructWithBoxedField) {
+  namespace test = structs::recursive;
+  test::Node list = test::create_list(4);
+  EXPECT_EQ(1 + 2 + 3 + 4, test::sum(std::move(list)));
+}
+
 }  // namespace
 }  // namespace crubit
@@ -2,24 +2,51 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Delimiter, Group, Ident, Literal, Spacing, TokenStream, TokenTree};
 use quote::{format_ident, quote, ToTokens};
+use std::borrow::Cow;
 use std::collections::{BTreeSet, HashSet};
+use std::io::Write;
 use std::rc::Rc;
 
-/// Formats a C++ identifier. Returns an error when `ident` is a C++ reserved
-/// keyword or is an invalid identifier.
+/// Name of the tool whose attributes carry Crubit annotations of Rust items
+/// (e.g. `#[crubit_annotate::cpp_type = "::ns::SomeStruct"]`).  The
+/// annotations are written by the `#[crubit::...]` attributes from
+/// `support/crubit_attributes.rs` and by `rs_bindings_from_cc`, and are read by
+/// `cc_bindings_from_rs`.  Unlike `#[doc = "..."]` attributes, tool attributes
+/// don't show up in the generated rustdoc, but they are still preserved in the
+/// crate metadata - the annotations are therefore also visible when generating
+/// bindings for crates that depend on the annotated crate.
+///
+/// Crates that use the annotations need to register the tool with
+/// `#![feature(register_tool)]` and `#![register_tool(crubit_annotate)]`.
+pub const CRUBIT_ANNOTATION_TOOL: &str = "crubit_annotate";
+
+/// Formats a C++ identifier.  Escapes the identifier (see `escape_cc_ident`)
+/// if it is a C++ reserved keyword or a reserved identifier.  Returns an error
+/// when `ident` is an invalid identifier.
 pub fn format_cc_ident(ident: &str) -> Result<TokenStream> {
     ensure!(!ident.is_empty(), "Empty string is not a valid C++ identifier");
+    format_unescaped_cc_ident(&escape_cc_ident(ident))
+}
+
+/// Formats a C++ identifier *without* escaping it.  This should only be used
+/// for names that have to be spelled exactly as given - e.g. exported symbol
+/// names, internal names of the generated code (e.g. `__crubit_internal`), or
+/// names of existing C++ entities (e.g. ones from the implementation of the
+/// C++ standard library).  Returns an error when `ident` is a C++ reserved
+/// keyword or is an invalid identifier.
+pub fn format_unescaped_cc_ident(ident: &str) -> Result<TokenStream> {
+    ensure!(!ident.is_empty(), "Empty string is not a valid C++ identifier");
 
     // C++ doesn't have an equivalent of
     // https://doc.rust-lang.org/rust-by-example/compatibility/raw_identifiers.html and therefore
     // an error is returned when `ident` is a C++ reserved keyword.
     ensure!(
-        !RESERVED_CC_KEYWORDS.contains(ident),
+        !is_cc_reserved_keyword(ident),
         "`{}` is a C++ reserved keyword and can't be used as a C++ identifier",
         ident
     );
@@ -42,6 +69,55 @@ pub fn format_cc_ident(ident: &str) -> Result<TokenStream> {
     )
 }
 
+/// Returns the name that `format_cc_ident` uses for `ident`:
+///
+/// - Reserved identifiers (ones that contain `__` or that start with `_`
+///   followed by an uppercase letter - see
+///   https://en.cppreference.com/w/cpp/language/identifiers#In_declarations)
+///   are escaped by collapsing each run of underscores into a single
+///   underscore and by dropping the leading underscore that precedes an
+///   uppercase letter (e.g. `__foo` is formatted as `_foo`, `foo__bar` as
+///   `foo_bar`, and `_Foo` as `Foo`).
+/// - C++ reserved keywords are escaped by appending an underscore (e.g.
+///   `class` is formatted as `class_`).
+///
+/// Escaping may make two different names collide (e.g. `class` and `class_`)
+/// - callers that need unique names should detect collisions between the
+/// escaped names.  Escaping is idempotent.
+pub fn escape_cc_ident(ident: &str) -> Cow<'_, str> {
+    let mut escaped = Cow::Borrowed(ident);
+    if is_cc_reserved_identifier(&escaped) {
+        let mut collapsed = String::with_capacity(escaped.len());
+        for c in escaped.chars() {
+            if c != '_' || !collapsed.ends_with('_') {
+                collapsed.push(c);
+            }
+        }
+        escaped = Cow::Owned(match collapsed.strip_prefix('_') {
+            Some(rest) if rest.starts_with(char::is_uppercase) => rest.to_string(),
+            _ => collapsed,
+        });
+    }
+    if is_cc_reserved_keyword(&escaped) {
+        escaped = Cow::Owned(format!("{escaped}_"));
+    }
+    escaped
+}
+
+/// Returns whether `ident` is a C++ reserved keyword (which `format_cc_ident`
+/// escapes).
+pub fn is_cc_reserved_keyword(ident: &str) -> bool {
+    RESERVED_CC_KEYWORDS.contains(ident)
+}
+
+/// Returns whether `ident` is a reserved C++ identifier (which
+/// `format_cc_ident` escapes) - i.e. whether it contains `__` or starts with
+/// `_` followed by an uppercase letter.
+pub fn is_cc_reserved_identifier(ident: &str) -> bool {
+    ident.contains("__")
+        || ident.strip_prefix('_').map_or(false, |rest| rest.starts_with(char::is_uppercase))
+}
+
 /// Makes an 'Ident' to be used in the Rust source code. Escapes Rust keywords.
 /// Panics if `ident` is empty or is otherwise an invalid identifier.
 pub fn make_rs_ident(ident: &str) -> Ident {
@@ -67,24 +143,39 @@ impl NamespaceQualifier {
         // TODO(b/258265044): Catch most (all if possible) error conditions early.  For
         // example:
         // - Panic early if any strings are empty, or are not Rust identifiers
-        // - Report an error early if any strings are C++ reserved keywords
         // This may make `format_for_cc`, `format_with_cc_body`, and
         // `format_namespace_bound_cc_tokens` infallible.
         Self(iter.into_iter().map(Into::into).collect())
     }
 
+    /// Returns the individual names that make up the qualifier - e.g. `foo`,
+    /// `bar`, and `baz` for `foo::bar::baz`.
+    pub fn parts(&self) -> impl Iterator<Item = &Rc<str>> {
+        self.0.iter()
+    }
+
     /// Returns `foo::bar::baz::` (escaping Rust keywords as needed).
     pub fn format_for_rs(&self) -> TokenStream {
         let namespace_rs_idents = self.0.iter().map(|ns| make_rs_ident(ns));
         quote! { #(#namespace_rs_idents::)* }
     }
 
-    /// Returns `foo::bar::baz::` (reporting errors for C++ keywords).
+    /// Returns `foo::bar::baz::` (escaping C++ keywords and reserved
+    /// identifiers as needed - see `format_cc_ident`).
     pub fn format_for_cc(&self) -> Result<TokenStream> {
         let namespace_cc_idents = self.cc_idents()?;
         Ok(quote! { #(#namespace_cc_idents::)* })
     }
 
+    /// Returns `foo::bar::baz::` without escaping the names of the namespaces
+    /// (see `format_unescaped_cc_ident`).  This should be used for namespaces
+    /// of existing C++ entities.
+    pub fn format_unescaped_for_cc(&self) -> Result<TokenStream> {
+        let namespace_cc_idents =
+            self.0.iter().map(|ns| format_unescaped_cc_ident(ns)).collect::<Result<Vec<_>>>()?;
+        Ok(quote! { #(#namespace_cc_idents::)* })
+    }
+
     fn format_with_cc_body(&self, body: TokenStream) -> Result<TokenStream> {
         if self.0.is_empty() {
             Ok(body)
@@ -199,6 +290,62 @@ impl CcInclude {
         Self::SystemHeader("utility")
     }
 
+    /// Creates a `CcInclude` that represents `#include <type_traits>` and
+    /// provides C++ type traits like `std::is_same_v`.  See also
+    /// https://en.cppreference.com/w/cpp/header/type_traits
+    pub fn type_traits() -> Self {
+        Self::SystemHeader("type_traits")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <concepts>` and provides
+    /// C++20 concepts like `std::same_as`.  See also
+    /// https://en.cppreference.com/w/cpp/header/concepts
+    pub fn concepts() -> Self {
+        Self::SystemHeader("concepts")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <optional>` and provides
+    /// the C++ `std::optional` type.  See also
+    /// https://en.cppreference.com/w/cpp/header/optional
+    pub fn optional() -> Self {
+        Self::SystemHeader("optional")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <string_view>` and
+    /// provides the C++ `std::string_view` type.  See also
+    /// https://en.cppreference.com/w/cpp/header/string_view
+    pub fn string_view() -> Self {
+        Self::SystemHeader("string_view")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <assert.h>` and provides
+    /// the C11 `static_assert` macro.  See also
+    /// https://en.cppreference.com/w/c/error/static_assert
+    pub fn assert_h() -> Self {
+        Self::SystemHeader("assert.h")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <stdalign.h>` and
+    /// provides the C11 `alignas` and `alignof` macros.  See also
+    /// https://en.cppreference.com/w/c/types
+    pub fn stdalign_h() -> Self {
+        Self::SystemHeader("stdalign.h")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <stdbool.h>` and
+    /// provides the C `bool` type.  See also
+    /// https://en.cppreference.com/w/c/types/boolean
+    pub fn stdbool_h() -> Self {
+        Self::SystemHeader("stdbool.h")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <stdint.h>` and provides
+    /// C types like `int16_t` or `uint32_t`.  See also
+    /// https://en.cppreference.com/w/c/types/integer
+    pub fn stdint_h() -> Self {
+        Self::SystemHeader("stdint.h")
+    }
+
     /// Creates a user include: `#include "some/path/to/header.h"`.
     pub fn user_header(path: Rc<str>) -> Self {
         Self::UserHeader(path)
@@ -238,6 +385,484 @@ pub fn format_cc_includes(set_of_includes: &BTreeSet<CcInclude>) -> TokenStream
     tokens
 }
 
+/// Formats C++ `tokens` into indented, human-readable C++ source code without
+/// depending on an external `clang-format` binary.
+///
+/// `tokens` can use the same placeholders as `token_stream_printer` (i.e.
+/// `__NEWLINE__`, `__SPACE__`, `__HASH_TOKEN__`, and `__COMMENT__ "..."`).
+/// Two or more consecutive `__NEWLINE__`s result in a single empty line.
+///
+/// The output approximates the Google C++ Style Guide (2-space indentation,
+/// no indentation for the contents of a namespace, `}  // namespace foo`
+/// comments, etc.), but unlike `clang-format` it doesn't wrap long lines.
+pub fn format_cc_tokens(tokens: TokenStream) -> Result<String> {
+    let mut printer = CcPrinter::default();
+    printer.print(tokens)?;
+    printer.end_line();
+    Ok(printer.output)
+}
+
+/// Like `format_cc_tokens`, but writes the formatted C++ source code into
+/// `writer` as it is being formatted (rather than holding all of it in
+/// memory, which matters for the headers of big crates).
+pub fn write_cc_tokens(tokens: TokenStream, writer: &mut dyn Write) -> Result<()> {
+    let mut printer = CcPrinter { sink: Some(writer), ..Default::default() };
+    printer.print(tokens)?;
+    printer.end_line();
+    printer.flush()
+}
+
+/// `CcPrinter::output` is written into `CcPrinter::sink` (if any) after it
+/// grows beyond this size.
+const CC_PRINTER_FLUSH_THRESHOLD: usize = 64 * 1024;
+
+/// Keywords that should be separated by a space from a subsequent `(`, `::`,
+/// `*`, or `&` (in contrast to regular identifiers, which are followed by a
+/// space only if the next token is another identifier).
+static CC_KEYWORDS_FOLLOWED_BY_SPACE: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    [
+        "case",
+        "class",
+        "co_await",
+        "co_return",
+        "co_yield",
+        "const",
+        "constexpr",
+        "else",
+        "enum",
+        "explicit",
+        "extern",
+        "for",
+        "friend",
+        "if",
+        "inline",
+        "new",
+        "return",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "throw",
+        "typename",
+        "union",
+        "using",
+        "virtual",
+        "volatile",
+        "while",
+    ]
+    .into_iter()
+    .collect()
+});
+
+/// Multi-character C++ punctuators.  Longer punctuators need to come first,
+/// so that a sequence of `Spacing::Joint` characters can be split greedily.
+const CC_MULTI_CHAR_PUNCTUATORS: [&str; 25] = [
+    "<<=", ">>=", "->*", "...", "::", "->", ".*", "==", "!=", "<=", ">=", "&&", "||", "<<", ">>",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "++", "--",
+];
+
+/// Classification of the most recently printed token of the current line.
+/// The classification is used to decide whether a space is needed before the
+/// next token.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum CcPrevToken {
+    /// Nothing has been printed on the current line yet, or the last token
+    /// was an opening `(`, `[`, or `<`.
+    #[default]
+    Nothing,
+
+    /// An identifier, a literal, or a closing `)`, `]`, or `>`.
+    Word,
+
+    /// One of `CC_KEYWORDS_FOLLOWED_BY_SPACE`.
+    Keyword,
+
+    /// The `operator` keyword (e.g. the `operator` in `operator==`).
+    Operator,
+
+    /// A punctuator that should be followed by a space (e.g. `,`, `=`, or `*`
+    /// in `int* p`).
+    SpacedPunct,
+
+    /// A punctuator that shouldn't be followed by a space (e.g. `::`, `.`, or
+    /// `*` in `*p`).
+    AttachedPunct,
+
+    /// A closing `}`.  The next token (unless it is `;`, `,`, or `else`) will
+    /// be printed on a new line.
+    CloseBrace,
+
+    /// A `;` that ends a statement or a declaration (or a `>` that ends
+    /// `template <...>`).  The next token (unless it is a `__COMMENT__`) will
+    /// be printed on a new line.
+    Semicolon,
+}
+
+#[derive(Default)]
+struct CcPrinter<'a> {
+    /// Lines that have already been formatted (but not yet written into
+    /// `sink`).
+    output: String,
+
+    /// Where the formatted lines are written (see `write_cc_tokens`).  When
+    /// `None`, all the lines are accumulated in `output`.
+    sink: Option<&'a mut dyn Write>,
+
+    /// Whether any line has been formatted so far (`output` may be empty after
+    /// being written into `sink`).
+    has_output: bool,
+
+    /// The line that is currently being formatted (without indentation).
+    line: String,
+
+    /// Indentation level (in units of 2 spaces) of the current line.
+    indent: usize,
+
+    prev: CcPrevToken,
+
+    /// Number of enclosing `(...)` or `[...]` groups.
+    paren_depth: usize,
+
+    /// Number of enclosing (unclosed) template argument lists: `<...>`.
+    angle_depth: usize,
+
+    /// Number of consecutive `__NEWLINE__`s since the last printed line.
+    newlines: usize,
+
+    /// Whether the current line is the first line of a (non-namespace) block.
+    /// Empty lines are not printed at the start of such blocks.
+    is_at_block_start: bool,
+
+    /// Whether the current line is a preprocessor directive (e.g. `#include`).
+    is_directive: bool,
+
+    /// Whether the current line is an `#include` directive *and* the include
+    /// path is being printed (without any spaces between the tokens).
+    is_include_path: bool,
+
+    /// Whether the current line is an access specifier (e.g. `public:`).
+    is_access_specifier: bool,
+}
+
+impl CcPrinter<'_> {
+    fn print(&mut self, tokens: TokenStream) -> Result<()> {
+        let mut it = tokens.into_iter().peekable();
+        while let Some(tt) = it.next() {
+            if self.output.len() >= CC_PRINTER_FLUSH_THRESHOLD {
+                self.flush()?;
+            }
+            match tt {
+                TokenTree::Ident(ref id) if id == "__NEWLINE__" => {
+                    self.end_line();
+                    self.newlines += 1;
+                }
+                TokenTree::Ident(ref id) if id == "__SPACE__" => {
+                    if !self.line.is_empty() && !self.line.ends_with(' ') {
+                        self.line.push(' ');
+                    }
+                }
+                TokenTree::Ident(ref id) if id == "__HASH_TOKEN__" => {
+                    self.end_line();
+                    self.is_directive = true;
+                    self.line.push('#');
+                }
+                TokenTree::Ident(ref id) if id == "__COMMENT__" => match it.next() {
+                    Some(TokenTree::Literal(lit)) => self.print_comment(&lit)?,
+                    _ => bail!("__COMMENT__ must be followed by a literal"),
+                },
+                _ if self.is_include_path => self.line.push_str(&tt.to_string()),
+                TokenTree::Ident(id) => self.print_word(&id.to_string()),
+                TokenTree::Literal(lit) => self.print_word(&lit.to_string()),
+                TokenTree::Punct(punct) => {
+                    let mut chars = String::from(punct.as_char());
+                    let mut spacing = punct.spacing();
+                    while spacing == Spacing::Joint {
+                        match it.peek() {
+                            Some(TokenTree::Punct(next)) => {
+                                chars.push(next.as_char());
+                                spacing = next.spacing();
+                                it.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    self.print_joint_punct(&chars);
+                }
+                TokenTree::Group(group) => self.print_group(group)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Finishes the current line (if any) and appends it to `output`.
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        let line = line.trim_end();
+        if !line.is_empty() {
+            if self.newlines >= 2 && !self.is_at_block_start && self.has_output {
+                self.output.push('\n');
+            }
+            let indent = if self.is_directive {
+                0
+            } else if self.is_access_specifier && self.indent > 0 {
+                self.indent * 2 - 1
+            } else {
+                self.indent * 2
+            };
+            self.output.push_str(&" ".repeat(indent));
+            self.output.push_str(line);
+            self.output.push('\n');
+            self.has_output = true;
+            self.newlines = 0;
+            self.is_at_block_start = false;
+        }
+        self.prev = CcPrevToken::Nothing;
+        self.is_directive = false;
+        self.is_include_path = false;
+        self.is_access_specifier = false;
+    }
+
+    /// Writes `output` into `sink` (if any).
+    fn flush(&mut self) -> Result<()> {
+        if let Some(sink) = self.sink.as_mut() {
+            sink.write_all(self.output.as_bytes())?;
+            self.output.clear();
+        }
+        Ok(())
+    }
+
+    fn print_comment(&mut self, lit: &Literal) -> Result<()> {
+        let text = syn::parse_str::<syn::LitStr>(&lit.to_string())
+            .map_err(|_| anyhow!("__COMMENT__ must be followed by a string literal: {lit}"))?
+            .value();
+        for (i, comment_line) in text.split('\n').enumerate() {
+            let comment_line = comment_line.trim_end();
+            if i == 0 && !self.line.is_empty() {
+                self.line.push_str("  //");
+            } else {
+                self.end_line();
+                self.line.push_str("//");
+            }
+            if !comment_line.is_empty() {
+                self.line.push(' ');
+                self.line.push_str(comment_line);
+            }
+        }
+        self.end_line();
+        Ok(())
+    }
+
+    /// Starts a new line if required by the previous token (e.g. after `;`),
+    /// unless `token` can continue the current line (e.g. `else` after `}`).
+    fn break_line_if_needed(&mut self, token: &str) {
+        match self.prev {
+            CcPrevToken::Semicolon => self.end_line(),
+            CcPrevToken::CloseBrace if !matches!(token, ";" | "," | "else" | "while") => {
+                self.end_line()
+            }
+            _ => (),
+        }
+    }
+
+    fn print_word(&mut self, word: &str) {
+        self.break_line_if_needed(word);
+        if self.is_directive && self.line == "#" {
+            self.line.push_str(word);
+            if word == "include" {
+                self.line.push(' ');
+                self.is_include_path = true;
+            }
+            self.prev = CcPrevToken::Word;
+            return;
+        }
+        match self.prev {
+            CcPrevToken::Nothing | CcPrevToken::AttachedPunct => (),
+            CcPrevToken::Word
+            | CcPrevToken::Keyword
+            | CcPrevToken::Operator
+            | CcPrevToken::SpacedPunct
+            | CcPrevToken::CloseBrace
+            | CcPrevToken::Semicolon => self.line.push(' '),
+        }
+        self.line.push_str(word);
+        self.prev = if word == "operator" {
+            CcPrevToken::Operator
+        } else if CC_KEYWORDS_FOLLOWED_BY_SPACE.contains(word) {
+            CcPrevToken::Keyword
+        } else {
+            CcPrevToken::Word
+        };
+    }
+
+    /// Splits a sequence of `Spacing::Joint` punctuation characters into
+    /// separate C++ punctuators and prints them.
+    fn print_joint_punct(&mut self, mut chars: &str) {
+        while !chars.is_empty() {
+            let len = if self.angle_depth > 0 && chars.starts_with('>') {
+                1 // Closing a template argument list - e.g. `>>` in `A<B<C>>`.
+            } else {
+                CC_MULTI_CHAR_PUNCTUATORS
+                    .iter()
+                    .find(|punct| chars.starts_with(*punct))
+                    .map(|punct| punct.len())
+                    .unwrap_or(1)
+            };
+            let (punct, rest) = chars.split_at(len);
+            self.print_punct(punct);
+            chars = rest;
+        }
+    }
+
+    fn print_punct(&mut self, punct: &str) {
+        use CcPrevToken::*;
+        self.break_line_if_needed(punct);
+        let prev = self.prev;
+        if prev == Operator {
+            // E.g. `operator==`.
+            self.line.push_str(punct);
+            self.prev = Word;
+            return;
+        }
+        let (space_before, next_prev) = match punct {
+            "," => (false, SpacedPunct),
+            ";" if self.paren_depth == 0 && !self.is_directive => (false, Semicolon),
+            ";" => (false, SpacedPunct),
+            "::" => (matches!(prev, Keyword | SpacedPunct), AttachedPunct),
+            "." | "->" | ".*" | "->*" => (false, AttachedPunct),
+            "!" | "~" | "++" | "--" => (matches!(prev, Keyword | SpacedPunct), AttachedPunct),
+            "*" | "&" | "&&" => match prev {
+                // E.g. `int* p` or `T&& x` (but also `int** p`).
+                Word => (false, SpacedPunct),
+                SpacedPunct if self.line.ends_with(|c| c == '*' || c == '&') => {
+                    (false, SpacedPunct)
+                }
+                // E.g. `return *this` or `f(*p)`.
+                Keyword | SpacedPunct => (true, AttachedPunct),
+                Nothing | AttachedPunct | Operator | CloseBrace | Semicolon => {
+                    (false, AttachedPunct)
+                }
+            },
+            "<" if matches!(prev, Word | Keyword) => {
+                // E.g. `std::tuple<int, int>` or `template <typename T>`.
+                self.angle_depth += 1;
+                (prev == Keyword, Nothing)
+            }
+            ">" if self.angle_depth > 0 => {
+                self.angle_depth -= 1;
+                if self.angle_depth == 0 && self.line.starts_with("template") {
+                    // The declaration that follows `template <...>` starts on a new line.
+                    (false, Semicolon)
+                } else {
+                    (false, Word)
+                }
+            }
+            ":" if matches!(self.line.trim(), "public" | "protected" | "private") => {
+                self.line.push(':');
+                self.is_access_specifier = true;
+                self.end_line();
+                return;
+            }
+            "-" | "+" if matches!(prev, Nothing | Keyword | SpacedPunct | AttachedPunct) => {
+                // Unary minus or plus - e.g. `-x`.
+                (prev != Nothing && prev != AttachedPunct, AttachedPunct)
+            }
+            // Binary operators - e.g. `=`, `==`, or `:` in `class D : public B`.
+            _ => (prev != Nothing, SpacedPunct),
+        };
+        if space_before && !self.line.is_empty() {
+            self.line.push(' ');
+        }
+        self.line.push_str(punct);
+        self.prev = next_prev;
+    }
+
+    fn print_group(&mut self, group: Group) -> Result<()> {
+        use CcPrevToken::*;
+        let (open, close) = match group.delimiter() {
+            Delimiter::Parenthesis => ('(', ')'),
+            Delimiter::Bracket => ('[', ']'),
+            Delimiter::Brace => return self.print_brace_group(group),
+            Delimiter::None => return self.print(group.stream()),
+        };
+        self.break_line_if_needed(&open.to_string());
+        if matches!(self.prev, Keyword | SpacedPunct) {
+            self.line.push(' ');
+        }
+        self.line.push(open);
+        self.prev = Nothing;
+        self.paren_depth += 1;
+        let saved_angle_depth = std::mem::replace(&mut self.angle_depth, 0);
+        self.print(group.stream())?;
+        self.angle_depth = saved_angle_depth;
+        self.paren_depth -= 1;
+        self.line.push(close);
+        self.prev = Word;
+        Ok(())
+    }
+
+    fn print_brace_group(&mut self, group: Group) -> Result<()> {
+        use CcPrevToken::*;
+        self.break_line_if_needed("{");
+        let is_empty = group.stream().into_iter().all(
+            |tt| matches!(tt, TokenTree::Ident(ref id) if id == "__NEWLINE__" || id == "__SPACE__"),
+        );
+        let space_before = !matches!(self.prev, Nothing | AttachedPunct);
+        if is_empty || self.paren_depth > 0 {
+            // E.g. `S() {}` or an initializer list in `f({1, 2})`.
+            if space_before && is_empty {
+                self.line.push(' ');
+            }
+            self.line.push('{');
+            if !is_empty {
+                self.prev = Nothing;
+                self.print(group.stream())?;
+            }
+            self.line.push('}');
+            self.prev = if self.paren_depth > 0 { Word } else { CloseBrace };
+            return Ok(());
+        }
+
+        let line = self.line.strip_prefix("inline ").unwrap_or(&self.line);
+        let namespace_name = line.strip_prefix("namespace").and_then(|rest| {
+            if rest.is_empty() || rest.starts_with(' ') {
+                Some(rest.trim().to_string())
+            } else {
+                None
+            }
+        });
+        if space_before {
+            self.line.push(' ');
+        }
+        self.line.push('{');
+        self.end_line();
+        let saved_angle_depth = std::mem::replace(&mut self.angle_depth, 0);
+        if namespace_name.is_none() {
+            self.indent += 1;
+            self.is_at_block_start = true;
+        }
+        self.print(group.stream())?;
+        self.end_line();
+        if namespace_name.is_none() {
+            self.indent -= 1;
+            self.newlines = 0; // No empty lines before the closing `}` of a block.
+        }
+        self.angle_depth = saved_angle_depth;
+        self.line.push('}');
+        match namespace_name {
+            Some(name) => {
+                self.line.push_str("  // namespace");
+                if !name.is_empty() {
+                    self.line.push(' ');
+                    self.line.push_str(&name);
+                }
+                self.end_line();
+            }
+            None => self.prev = CloseBrace,
+        }
+        Ok(())
+    }
+}
+
 static RESERVED_CC_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     // `RESERVED_CC_KEYWORDS` are based on https://en.cppreference.com/w/cpp/keyword
     [
@@ -378,9 +1003,53 @@ pub mod tests {
 
     #[test]
     fn test_format_cc_ident_reserved_cc_keyword() {
-        let err = format_cc_ident("reinterpret_cast").unwrap_err();
+        assert_cc_matches!(
+            format_cc_ident("reinterpret_cast").unwrap(),
+            quote! { reinterpret_cast_ }
+        );
+        assert_cc_matches!(format_cc_ident("class").unwrap(), quote! { class_ });
+        assert_cc_matches!(format_cc_ident("new").unwrap(), quote! { new_ });
+        assert_cc_matches!(format_cc_ident("int").unwrap(), quote! { int_ });
+    }
+
+    #[test]
+    fn test_format_cc_ident_reserved_cc_identifier() {
+        assert_cc_matches!(format_cc_ident("__foo").unwrap(), quote! { _foo });
+        assert_cc_matches!(format_cc_ident("foo__bar").unwrap(), quote! { foo_bar });
+        assert_cc_matches!(format_cc_ident("foo___").unwrap(), quote! { foo_ });
+        assert_cc_matches!(format_cc_ident("_Foo").unwrap(), quote! { Foo });
+        assert_cc_matches!(format_cc_ident("__Foo").unwrap(), quote! { Foo });
+        assert_cc_matches!(format_cc_ident("__").unwrap(), quote! { _ });
+        assert_cc_matches!(format_cc_ident("_Class").unwrap(), quote! { Class });
+
+        // Not reserved outside of the global namespace.
+        assert_cc_matches!(format_cc_ident("_foo").unwrap(), quote! { _foo });
+        assert_cc_matches!(format_cc_ident("foo_").unwrap(), quote! { foo_ });
+    }
+
+    #[test]
+    fn test_escape_cc_ident() {
+        assert_eq!(escape_cc_ident("foo"), "foo");
+        assert!(matches!(escape_cc_ident("foo"), Cow::Borrowed(_)));
+        assert_eq!(escape_cc_ident("class"), "class_");
+        assert_eq!(escape_cc_ident("__class"), "_class");
+        assert_eq!(escape_cc_ident("_Foo__bar"), "Foo_bar");
+
+        // Escaping is idempotent.
+        for ident in ["class", "__foo", "_Foo", "foo__"] {
+            let escaped = escape_cc_ident(ident).into_owned();
+            assert_eq!(escape_cc_ident(&escaped), escaped);
+        }
+    }
+
+    #[test]
+    fn test_format_unescaped_cc_ident() {
+        assert_cc_matches!(format_unescaped_cc_ident("__foo").unwrap(), quote! { __foo });
+        assert_cc_matches!(format_unescaped_cc_ident("_Foo").unwrap(), quote! { _Foo });
+
+        let err = format_unescaped_cc_ident("class").unwrap_err();
         let msg = err.to_string();
-        assert!(msg.contains("`reinterpret_cast`"));
+        assert!(msg.contains("`class`"));
         assert!(msg.contains("C++ reserved keyword"));
     }
 
@@ -501,6 +1170,15 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_cc_include_to_tokens_for_c_system_header() {
+        let include = CcInclude::stdint_h();
+        assert_eq!(
+            "#include <stdint.h>\n",
+            cc_tokens_to_formatted_string_for_tests(quote! { #include }).unwrap()
+        );
+    }
+
     #[test]
     fn test_cc_include_to_tokens_for_user_header() {
         let include = CcInclude::user_header("some/path/to/header.h".into());
@@ -570,6 +1248,13 @@ pub mod tests {
         assert_cc_matches!(actual_cc, quote! { foo::bar:: });
     }
 
+    #[test]
+    fn test_namespace_qualifier_parts() {
+        let ns = NamespaceQualifier::new(["foo", "bar"]);
+        let actual = ns.parts().map(|part| part.to_string()).collect::<Vec<_>>();
+        assert_eq!(actual, vec!["foo".to_string(), "bar".to_string()]);
+    }
+
     #[test]
     fn test_namespace_qualifier_reserved_cc_keyword() {
         let ns = NamespaceQualifier::new(["foo", "impl", "bar"]);
@@ -584,10 +1269,17 @@ pub mod tests {
         let ns = NamespaceQualifier::new(["foo", "reinterpret_cast", "bar"]);
         let actual_rs = ns.format_for_rs();
         assert_rs_matches!(actual_rs, quote! { foo :: reinterpret_cast :: bar :: });
-        let cc_error = ns.format_for_cc().unwrap_err();
-        let msg = cc_error.to_string();
-        assert!(msg.contains("`reinterpret_cast`"));
-        assert!(msg.contains("C++ reserved keyword"));
+        let actual_cc = ns.format_for_cc().unwrap();
+        assert_cc_matches!(actual_cc, quote! { foo::reinterpret_cast_::bar:: });
+    }
+
+    #[test]
+    fn test_namespace_qualifier_reserved_cc_identifier() {
+        let ns = NamespaceQualifier::new(["std", "__u", "_Bar"]);
+        let actual_cc = ns.format_for_cc().unwrap();
+        assert_cc_matches!(actual_cc, quote! { std::_u::Bar:: });
+        let actual_cc = ns.format_unescaped_for_cc().unwrap();
+        assert_cc_matches!(actual_cc, quote! { std::__u::_Bar:: });
     }
 
     #[test]
@@ -653,9 +1345,9 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_namespace_bound_cc_tokens_with_reserved_cpp_keywords() {
+    fn test_format_namespace_bound_cc_tokens_with_invalid_identifiers() {
         let working_module = NamespaceQualifier::new(["foo", "working_module", "bar"]);
-        let broken_module = NamespaceQualifier::new(["foo", "reinterpret_cast", "bar"]);
+        let broken_module = NamespaceQualifier::new(["foo", "0", "bar"]);
         let input = vec![
             (broken_module.clone(), quote! { void broken_module_f1(); }),
             (broken_module.clone(), quote! { void broken_module_f2(); }),
@@ -666,9 +1358,9 @@ pub mod tests {
             (working_module.clone(), quote! { void working_module_f7(); }),
             (working_module.clone(), quote! { void working_module_f8(); }),
         ];
-        let broken_module_msg = "Failed to format namespace name `foo::reinterpret_cast::bar`: \
-                                 `reinterpret_cast` is a C++ reserved keyword \
-                                 and can't be used as a C++ identifier";
+        let broken_module_msg = "Failed to format namespace name `foo::0::bar`: \
+                                 The following character can't be used as a start of a C++ \
+                                 identifier: 0";
         assert_cc_matches!(
             format_namespace_bound_cc_tokens(input),
             quote! {
@@ -694,4 +1386,141 @@ pub mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_format_cc_tokens_includes_and_namespaces() {
+        let tokens = quote! {
+            __HASH_TOKEN__ include <cstdint> __NEWLINE__
+            __HASH_TOKEN__ include "foo/bar.h" __NEWLINE__
+            __NEWLINE__
+            namespace foo::bar {
+                __NEWLINE__ __NEWLINE__
+                inline std::int32_t add(std::int32_t x, const ::foo::S* s) {
+                    return x + s->y;
+                }
+                __NEWLINE__ __NEWLINE__
+            }
+        };
+        assert_eq!(
+            format_cc_tokens(tokens).unwrap(),
+            "#include <cstdint>\n\
+             #include \"foo/bar.h\"\n\
+             \n\
+             namespace foo::bar {\n\
+             \n\
+             inline std::int32_t add(std::int32_t x, const ::foo::S* s) {\n  \
+               return x + s->y;\n\
+             }\n\
+             \n\
+             }  // namespace foo::bar\n"
+        );
+    }
+
+    #[test]
+    fn test_format_cc_tokens_class() {
+        let tokens = quote! {
+            class S final : public Base {
+                public:
+                S() = default;
+                ~S();
+                std::tuple<int, std::vector<int>> Get() const { return {}; }
+                __NEWLINE__ __NEWLINE__
+                private:
+                int x_[2];
+            };
+        };
+        assert_eq!(
+            format_cc_tokens(tokens).unwrap(),
+            "class S final : public Base {\n \
+             public:\n  \
+               S() = default;\n  \
+               ~S();\n  \
+               std::tuple<int, std::vector<int>> Get() const {\n    \
+                 return {};\n  \
+               }\n\
+             \n \
+             private:\n  \
+               int x_[2];\n\
+             };\n"
+        );
+    }
+
+    #[test]
+    fn test_format_cc_tokens_operators_and_pointers() {
+        let tokens = quote! {
+            S& operator=(const S& other) { return *this; }
+            bool operator==(int** p) const;
+            template <typename T>
+            T* cast(void* p) { return reinterpret_cast<T*>(p); }
+        };
+        assert_eq!(
+            format_cc_tokens(tokens).unwrap(),
+            "S& operator=(const S& other) {\n  \
+               return *this;\n\
+             }\n\
+             bool operator==(int** p) const;\n\
+             template <typename T>\n\
+             T* cast(void* p) {\n  \
+               return reinterpret_cast<T*>(p);\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_format_cc_tokens_comments_and_directives() {
+        let tokens = quote! {
+            __COMMENT__ "Line 1\nLine 2"
+            __HASH_TOKEN__ pragma once __NEWLINE__
+            __NEWLINE__
+            __HASH_TOKEN__ ifndef FOO_H_ __NEWLINE__
+            void f(); __COMMENT__ "Trailing comment"
+            __HASH_TOKEN__ endif __COMMENT__ "FOO_H_"
+        };
+        assert_eq!(
+            format_cc_tokens(tokens).unwrap(),
+            "// Line 1\n\
+             // Line 2\n\
+             #pragma once\n\
+             \n\
+             #ifndef FOO_H_\n\
+             void f();  // Trailing comment\n\
+             #endif  // FOO_H_\n"
+        );
+    }
+
+    #[test]
+    fn test_format_cc_tokens_empty_lines_at_block_start() {
+        let tokens = quote! {
+            struct S {
+                __NEWLINE__ __NEWLINE__
+                int x;
+                __NEWLINE__ __NEWLINE__
+            };
+        };
+        assert_eq!(format_cc_tokens(tokens).unwrap(), "struct S {\n  int x;\n};\n");
+    }
+
+    #[test]
+    fn test_write_cc_tokens() {
+        // Big enough to be written in multiple chunks (see
+        // `CC_PRINTER_FLUSH_THRESHOLD`).
+        let tokens = (0..10_000)
+            .map(|i| {
+                let name = format_ident!("S{i}");
+                quote! { struct #name { int x; }; __NEWLINE__ __NEWLINE__ }
+            })
+            .collect::<TokenStream>();
+        let mut written = vec![];
+        write_cc_tokens(tokens.clone(), &mut written).unwrap();
+        let formatted = format_cc_tokens(tokens).unwrap();
+        assert!(formatted.len() > CC_PRINTER_FLUSH_THRESHOLD);
+        assert_eq!(String::from_utf8(written).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_cc_tokens_invalid_comment() {
+        assert!(format_cc_tokens(quote! { __COMMENT__ }).is_err());
+        assert!(format_cc_tokens(quote! { __COMMENT__ ident }).is_err());
+        assert!(format_cc_tokens(quote! { __COMMENT__ 123 }).is_err());
+    }
 }
@@ -0,0 +1,88 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! The set of Crubit features that can be enabled (or left disabled) per
+//! target.  Both `rs_bindings_from_cc` and `cc_bindings_from_rs` use the same
+//! features, so that they can be configured through the same aspect hints.
+
+flagset::flags! {
+    pub enum CrubitFeature : u8 {
+        Supported,
+        /// Experimental is never *set* without also setting Supported, but we allow it to be
+        /// *required* without also requiring Supported, so that error messages can be more direct.
+        Experimental,
+        /// Translates C++ exceptions thrown by potentially-throwing functions into
+        /// `Result<T, cc_std::CcException>` (this requires building with C++ exceptions
+        /// enabled).
+        Exceptions,
+        /// Imports object-like macros that expand to a numeric or string literal (e.g.
+        /// `#define MAX_LEN 128`) as Rust constants.
+        Macros,
+    }
+}
+
+impl CrubitFeature {
+    /// The name of this feature.
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::Supported => "supported",
+            Self::Experimental => "experimental",
+            Self::Exceptions => "exceptions",
+            Self::Macros => "macros",
+        }
+    }
+
+    /// The aspect hint required to enable this feature.
+    pub fn aspect_hint(&self) -> &'static str {
+        match self {
+            Self::Supported => "//third_party/crubit:supported",
+            Self::Experimental => "//third_party/crubit:experimental",
+            Self::Exceptions => "//third_party/crubit:exceptions",
+            Self::Macros => "//third_party/crubit:macros",
+        }
+    }
+
+    /// Returns the feature with the given `short_name`, or `None` if there is
+    /// no such feature.
+    pub fn from_short_name(short_name: &str) -> Option<Self> {
+        flagset::FlagSet::<Self>::full().into_iter().find(|f| f.short_name() == short_name)
+    }
+}
+
+/// Parses a comma-separated list of feature names (e.g.
+/// `"supported,experimental"`).  An empty string results in an empty set.
+pub fn parse_crubit_features(features: &str) -> Result<flagset::FlagSet<CrubitFeature>, String> {
+    let mut result = flagset::FlagSet::<CrubitFeature>::default();
+    for name in features.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        result |= CrubitFeature::from_short_name(name)
+            .ok_or_else(|| format!("Unexpected Crubit feature: {name}"))?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_name_round_trip() {
+        for feature in flagset::FlagSet::<CrubitFeature>::full() {
+            assert_eq!(CrubitFeature::from_short_name(feature.short_name()), Some(feature));
+        }
+        assert_eq!(CrubitFeature::from_short_name("no_such_feature"), None);
+    }
+
+    #[test]
+    fn test_parse_crubit_features() {
+        assert_eq!(parse_crubit_features("").unwrap(), flagset::FlagSet::default());
+        assert_eq!(
+            parse_crubit_features("supported, experimental").unwrap(),
+            CrubitFeature::Supported | CrubitFeature::Experimental
+        );
+        assert_eq!(
+            parse_crubit_features("supported,bogus").unwrap_err(),
+            "Unexpected Crubit feature: bogus"
+        );
+    }
+}
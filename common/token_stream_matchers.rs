@@ -37,6 +37,11 @@
 ///       quote!{ void foo() { bar(); } },
 ///       quote!{ void ... bar() });
 /// ```
+///
+/// Pattern can also use `$name` placeholders that match any single token tree
+/// (an identifier, a literal, a punctuation character, or a whole group).  All
+/// occurrences of the same placeholder have to match the same tokens.  See
+/// `assert_cc_captures!` for getting hold of the captured tokens.
 #[macro_export]
 macro_rules! assert_cc_matches {
     ($input:expr, $pattern:expr $(,)*) => {
@@ -62,6 +67,43 @@ macro_rules! assert_rs_matches {
     };
 }
 
+/// Like `assert_cc_matches!`, but returns the tokens captured by the `$name`
+/// placeholders in the `pattern` (as a map from `name` to the captured
+/// tokens), so that tests can make follow-up assertions about them.
+///
+/// Example:
+/// ```rust
+///    let captures = assert_cc_captures!(
+///       quote!{ void foo(int x) { bar(x); } },
+///       quote!{ void foo(int $param) { bar($param); } });
+///    assert_eq!(captures["param"].to_string(), "x");
+/// ```
+#[macro_export]
+macro_rules! assert_cc_captures {
+    ($input:expr, $pattern:expr $(,)*) => {
+        $crate::internal::match_tokens_with_captures(
+            &$input,
+            &$pattern,
+            &$crate::internal::cc_tokens_to_formatted_string_for_tests,
+        )
+        .expect("input unexpectedly didn't match the pattern")
+    };
+}
+
+/// Like `assert_cc_captures!`, but also formats the input in the error message
+/// using rustfmt.
+#[macro_export]
+macro_rules! assert_rs_captures {
+    ($input:expr, $pattern:expr $(,)*) => {
+        $crate::internal::match_tokens_with_captures(
+            &$input,
+            &$pattern,
+            &$crate::internal::rs_tokens_to_formatted_string_for_tests,
+        )
+        .expect("input unexpectedly didn't match the pattern")
+    };
+}
+
 /// Asserts that the `input` does not contain the `pattern`.
 ///
 /// Pattern can use `...` wildcard. See `assert_cc_matches` for details.
@@ -95,17 +137,23 @@ pub mod internal {
 
     use anyhow::{anyhow, Result};
     pub use proc_macro2::TokenStream;
-    use proc_macro2::TokenTree;
+    use proc_macro2::{Delimiter, TokenTree};
+    use std::collections::BTreeMap;
     use std::iter;
     pub use token_stream_printer::{
         cc_tokens_to_formatted_string_for_tests, rs_tokens_to_formatted_string,
         rs_tokens_to_formatted_string_for_tests,
     };
 
+    /// Tokens captured by the `$name` placeholders of a pattern (keyed by
+    /// `name`).
+    pub type Captures = BTreeMap<String, TokenStream>;
+
     #[derive(Debug)]
     enum MatchInfo {
-        // Successful match with the suffix of the `input` stream that follows the match.
-        Match { input_suffix: TokenStream },
+        // Successful match with the suffix of the `input` stream that follows the match
+        // and with the tokens captured so far.
+        Match { input_suffix: TokenStream, captures: Captures },
         Mismatch(Mismatch),
     }
 
@@ -113,6 +161,9 @@ pub mod internal {
     struct Mismatch {
         match_length: usize,
         messages: Vec<String>,
+        // Token-level diff showing where the pattern diverged from the input (see
+        // `format_diff`).  Populated by the innermost group containing the mismatch.
+        diff: Option<String>,
     }
 
     impl Mismatch {
@@ -122,6 +173,7 @@ pub mod internal {
                 messages: vec![
                     "not even a partial match of the pattern throughout the input".to_string(),
                 ],
+                diff: None,
             }
         }
 
@@ -137,6 +189,7 @@ pub mod internal {
                     format!("expected '{}' but the input already ended", pattern_suffix),
                     format!("expected '{}' got '{}'", pattern, input),
                 ],
+                diff: None,
             }
         }
     }
@@ -146,6 +199,17 @@ pub mod internal {
         pattern: &TokenStream,
         to_string_fn: &ToStringFn,
     ) -> Result<()>
+    where
+        ToStringFn: Fn(TokenStream) -> Result<String>,
+    {
+        match_tokens_with_captures(input, pattern, to_string_fn).map(|_| ())
+    }
+
+    pub fn match_tokens_with_captures<ToStringFn>(
+        input: &TokenStream,
+        pattern: &TokenStream,
+        to_string_fn: &ToStringFn,
+    ) -> Result<Captures>
     where
         ToStringFn: Fn(TokenStream) -> Result<String>,
     {
@@ -155,8 +219,8 @@ pub mod internal {
         let mut stack = vec![iter];
         while let Some(mut iter) = stack.pop() {
             loop {
-                match match_prefix(iter.clone(), pattern.clone()) {
-                    MatchInfo::Match { input_suffix: _ } => return Ok(()),
+                match match_prefix(iter.clone(), pattern.clone(), Captures::new()) {
+                    MatchInfo::Match { input_suffix: _, captures } => return Ok(captures),
                     MatchInfo::Mismatch(mismatch) => {
                         if best_mismatch.match_length < mismatch.match_length {
                             best_mismatch = mismatch
@@ -175,7 +239,13 @@ pub mod internal {
 
         assert!(!best_mismatch.messages.is_empty());
         let input_string = to_string_fn(input.clone())?;
-        let mut error = anyhow!(format!("input:\n\n```\n{}\n```", input_string));
+        let mut error = match best_mismatch.diff {
+            None => anyhow!(format!("input:\n\n```\n{}\n```", input_string)),
+            Some(diff) => anyhow!(format!(
+                "input:\n\n```\n{}\n```\n\nfirst difference between the pattern and the input:\n{}",
+                input_string, diff
+            )),
+        };
         for msg in best_mismatch.messages.into_iter().rev() {
             error = error.context(msg);
         }
@@ -208,9 +278,13 @@ pub mod internal {
     // reimplement this using NFA and end up with simpler, more regular code
     // while still providing reasonable error messages on mismatch.
     // TODO(hlopko): Try to reimplement matching using NFA.
+    //
+    // `captures` are the tokens captured by the part of the pattern that was
+    // matched before `pattern`.
     fn match_prefix(
         input: impl Iterator<Item = TokenTree> + Clone,
         pattern: TokenStream,
+        mut captures: Captures,
     ) -> MatchInfo {
         let mut input_iter = input.clone();
         let mut pattern_iter = pattern.clone().into_iter().peekable();
@@ -232,6 +306,7 @@ pub mod internal {
                     reinsert_token(input_iter.clone(), actual_token).into_iter(),
                     input.clone(),
                     skip_wildcard(pattern_iter.clone()),
+                    captures.clone(),
                 ) {
                     MatchInfo::Mismatch(mut mismatch) => {
                         mismatch.match_length += match_counter;
@@ -245,9 +320,27 @@ pub mod internal {
                 continue;
             }
 
-            if let Some(pattern_token) = pattern_iter.next() {
-                if let MatchInfo::Mismatch(mut mismatch) = match_tree(&actual_token, &pattern_token)
-                {
+            let match_info = if let Some(name) = capture_name(&pattern_iter) {
+                // skip the `$` and the `name`
+                pattern_iter.nth(1);
+                capture(name, actual_token, captures)
+            } else if let Some(pattern_token) = pattern_iter.next() {
+                match_tree(&actual_token, &pattern_token, captures)
+            } else {
+                return MatchInfo::Match {
+                    input_suffix: reinsert_token(input_iter, actual_token),
+                    captures,
+                };
+            };
+            match match_info {
+                MatchInfo::Match { input_suffix: _, captures: new_captures } => {
+                    captures = new_captures;
+                }
+                MatchInfo::Mismatch(mut mismatch) => {
+                    if mismatch.diff.is_none() {
+                        mismatch.diff =
+                            Some(format_diff(pattern.clone(), to_stream(&input), match_counter));
+                    }
                     mismatch.messages.push(format!(
                         "expected '{}' got '{}'",
                         pattern,
@@ -256,25 +349,27 @@ pub mod internal {
                     mismatch.match_length += match_counter;
                     return MatchInfo::Mismatch(mismatch);
                 }
-            } else {
-                return MatchInfo::Match { input_suffix: reinsert_token(input_iter, actual_token) };
             }
             match_counter += 1;
         }
 
         if pattern_iter.peek().is_none() {
-            return MatchInfo::Match { input_suffix: TokenStream::new() };
+            return MatchInfo::Match { input_suffix: TokenStream::new(), captures };
         }
         if is_wildcard(to_stream(&pattern_iter)) {
-            return MatchInfo::Match { input_suffix: TokenStream::new() };
+            return MatchInfo::Match { input_suffix: TokenStream::new(), captures };
         }
 
-        update_best_mismatch(Mismatch::for_input_ended(
-            match_counter,
-            to_stream(&pattern_iter),
-            pattern,
-            to_stream(&input),
-        ));
+        let diff = format_diff(pattern.clone(), to_stream(&input), match_counter);
+        update_best_mismatch(Mismatch {
+            diff: Some(diff),
+            ..Mismatch::for_input_ended(
+                match_counter,
+                to_stream(&pattern_iter),
+                pattern,
+                to_stream(&input),
+            )
+        });
         MatchInfo::Mismatch(best_mismatch)
     }
 
@@ -282,12 +377,13 @@ pub mod internal {
         input_iter: impl Iterator<Item = TokenTree> + Clone,
         input: impl Iterator<Item = TokenTree> + Clone,
         pattern: TokenStream,
+        captures: Captures,
     ) -> MatchInfo {
-        match match_prefix(input_iter.clone(), pattern.clone()) {
-            MatchInfo::Match { input_suffix } if input_suffix.is_empty() => {
-                MatchInfo::Match { input_suffix }
+        match match_prefix(input_iter.clone(), pattern.clone(), captures) {
+            MatchInfo::Match { input_suffix, captures } if input_suffix.is_empty() => {
+                MatchInfo::Match { input_suffix, captures }
             }
-            MatchInfo::Match { input_suffix } => {
+            MatchInfo::Match { input_suffix, captures: _ } => {
                 let match_input_length = input_iter.count() + 1;
                 let suffix_length = input_suffix.into_iter().count();
                 MatchInfo::Mismatch(Mismatch::for_input_ended(
@@ -316,6 +412,10 @@ pub mod internal {
         matches!(token, TokenTree::Ident(id) if id == "__NEWLINE__" || id == "__SPACE__")
     }
 
+    fn count_non_whitespace_tokens(stream: TokenStream) -> usize {
+        stream.into_iter().filter(|token| !is_whitespace_token(token)).count()
+    }
+
     fn is_wildcard(pattern: TokenStream) -> bool {
         format!("{}", pattern) == "..."
     }
@@ -329,7 +429,140 @@ pub mod internal {
         pattern.skip(3).collect::<TokenStream>()
     }
 
-    fn match_tree(actual_token: &TokenTree, pattern_token: &TokenTree) -> MatchInfo {
+    /// Number of tokens shown before and after the first difference in
+    /// `format_diff`.
+    const DIFF_CONTEXT: usize = 3;
+
+    /// Groups that are longer than this are abbreviated in `format_diff`.
+    const MAX_DIFF_TOKEN_WIDTH: usize = 24;
+
+    /// Formats the tokens of a single group of the `pattern` and the `input`
+    /// one above the other (with up to `DIFF_CONTEXT` tokens around the
+    /// `index` of the first difference) and marks the first difference with
+    /// `^`.  For example:
+    ///
+    /// ```text
+    /// pattern: ... 1 ; let c = 2 ;
+    ///   input: ... 1 ; let b = 2 ;
+    ///                      ^
+    /// ```
+    fn format_diff(pattern: TokenStream, input: TokenStream, index: usize) -> String {
+        let pattern = diff_tokens(pattern);
+        let input = diff_tokens(input);
+        let start = index.saturating_sub(DIFF_CONTEXT);
+        let end = index + DIFF_CONTEXT + 1;
+        let token_at = |tokens: &[String], i: usize| match tokens.get(i) {
+            Some(token) => token.clone(),
+            None if i == index => "<end>".to_string(),
+            None => String::new(),
+        };
+
+        let mut pattern_line = "pattern: ".to_string();
+        let mut input_line = "  input: ".to_string();
+        let mut marker_line = " ".repeat(pattern_line.len());
+        if start > 0 {
+            pattern_line += "... ";
+            input_line += "... ";
+            marker_line += "    ";
+        }
+        for i in start..end {
+            let (pattern_token, input_token) = (token_at(&pattern, i), token_at(&input, i));
+            if pattern_token.is_empty() && input_token.is_empty() {
+                break;
+            }
+            let width = pattern_token.len().max(input_token.len());
+            pattern_line += &format!("{pattern_token:width$} ");
+            input_line += &format!("{input_token:width$} ");
+            if i < index {
+                marker_line += &" ".repeat(width + 1);
+            } else if i == index {
+                marker_line += &"^".repeat(width);
+            }
+        }
+        if pattern.len() > end {
+            pattern_line += "...";
+        }
+        if input.len() > end {
+            input_line += "...";
+        }
+        [pattern_line, input_line, marker_line]
+            .iter()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Splits the `stream` into the (non-whitespace) tokens shown by
+    /// `format_diff`.  `$name` placeholders are kept together (because they
+    /// correspond to a single token of the input).
+    fn diff_tokens(stream: TokenStream) -> Vec<String> {
+        let mut tokens = vec![];
+        let mut iter = stream.into_iter().filter(|token| !is_whitespace_token(token)).peekable();
+        while let Some(token) = iter.next() {
+            if matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '$')
+                && matches!(iter.peek(), Some(TokenTree::Ident(_)))
+            {
+                tokens.push(format!("${}", iter.next().unwrap()));
+                continue;
+            }
+            let text = token.to_string();
+            match token {
+                TokenTree::Group(group) if text.len() > MAX_DIFF_TOKEN_WIDTH => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    tokens.push(format!("{open} ... {close}").trim().to_string());
+                }
+                _ => tokens.push(text),
+            }
+        }
+        tokens
+    }
+
+    /// Returns the `name` of the `$name` placeholder at the beginning of the
+    /// `pattern` (or `None` if the `pattern` doesn't start with a placeholder).
+    fn capture_name(pattern: &(impl Iterator<Item = TokenTree> + Clone)) -> Option<String> {
+        let mut pattern = pattern.clone();
+        match (pattern.next(), pattern.next()) {
+            (Some(TokenTree::Punct(punct)), Some(TokenTree::Ident(name)))
+                if punct.as_char() == '$' =>
+            {
+                Some(name.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Matches the `actual_token` against the `$name` placeholder.  The first
+    /// occurrence of a placeholder matches any token tree, but subsequent
+    /// occurrences have to match the same tokens as the first one.
+    fn capture(name: String, actual_token: TokenTree, mut captures: Captures) -> MatchInfo {
+        let actual: TokenStream = actual_token.into();
+        if let Some(captured) = captures.get(&name) {
+            if captured.to_string() != actual.to_string() {
+                return MatchInfo::Mismatch(Mismatch {
+                    match_length: 0,
+                    messages: vec![format!(
+                        "expected '${}' to match '{}' (captured earlier) but got '{}'",
+                        name, captured, actual
+                    )],
+                    diff: None,
+                });
+            }
+        } else {
+            captures.insert(name, actual);
+        }
+        MatchInfo::Match { input_suffix: TokenStream::new(), captures }
+    }
+
+    fn match_tree(
+        actual_token: &TokenTree,
+        pattern_token: &TokenTree,
+        captures: Captures,
+    ) -> MatchInfo {
         match (actual_token, pattern_token) {
             (TokenTree::Group(ref actual_group), TokenTree::Group(ref pattern_group)) => {
                 if actual_group.delimiter() != pattern_group.delimiter() {
@@ -342,28 +575,30 @@ pub mod internal {
                             actual_group.delimiter(),
                             Into::<TokenStream>::into(actual_token.clone()),
                         )],
+                        diff: None,
                     });
                 }
-                let match_info =
-                    match_prefix(actual_group.stream().into_iter(), pattern_group.stream());
+                let match_info = match_prefix(
+                    actual_group.stream().into_iter(),
+                    pattern_group.stream(),
+                    captures,
+                );
                 match match_info {
-                    MatchInfo::Match { input_suffix } => {
-                        if input_suffix
-                            .clone()
-                            .into_iter()
-                            .filter(|token| !is_whitespace_token(token))
-                            .count()
-                            != 0
-                        {
+                    MatchInfo::Match { input_suffix, captures } => {
+                        let suffix_length = count_non_whitespace_tokens(input_suffix.clone());
+                        if suffix_length != 0 {
+                            let actual = actual_group.stream();
+                            let index = count_non_whitespace_tokens(actual.clone()) - suffix_length;
                             MatchInfo::Mismatch(Mismatch {
                                 match_length: 0,
                                 messages: vec![format!(
                                     "matched the entire pattern but the input still contained '{}'",
                                     input_suffix
                                 )],
+                                diff: Some(format_diff(pattern_group.stream(), actual, index)),
                             })
                         } else {
-                            MatchInfo::Match { input_suffix: TokenStream::new() }
+                            MatchInfo::Match { input_suffix: TokenStream::new(), captures }
                         }
                     }
                     mismatch => mismatch,
@@ -373,11 +608,12 @@ pub mod internal {
                 let actual_src = format!("{}", actual);
                 let pattern_src = format!("{}", pattern);
                 if actual_src == pattern_src {
-                    MatchInfo::Match { input_suffix: TokenStream::new() }
+                    MatchInfo::Match { input_suffix: TokenStream::new(), captures }
                 } else {
                     MatchInfo::Mismatch(Mismatch {
                         match_length: 0,
                         messages: vec![format!("expected '{}' but got '{}'", pattern, actual)],
+                        diff: None,
                     })
                 }
             }
@@ -501,7 +737,12 @@ Caused by:
          int a;
          int b;
        };
-       ```"#
+       ```
+       
+       first difference between the pattern and the input:
+       pattern: struct B
+         input: struct A { int a ; int b ; } ;
+                       ^"#
         );
     }
 
@@ -525,7 +766,10 @@ Caused by:
        struct A {
            a: i64,
            b: i64,
-       }\n       \n       ```"
+       }\n       \n       ```\n       \n       first difference between the pattern and the input:
+       pattern: struct B
+         input: struct A { a : i64 , b : i64 }
+                       ^"
         );
     }
 
@@ -546,7 +790,12 @@ Caused by:
 ```
 fn foo() {}
 
-```"#
+```
+
+first difference between the pattern and the input:
+pattern: ... foo () { } struct X { }
+  input: ... foo () { } <end>
+                        ^^^^^^"#
         );
     }
 
@@ -565,7 +814,12 @@ fn foo() {}
 ```
 fn foo() {}
 
-```"#
+```
+
+first difference between the pattern and the input:
+pattern: fn foo () ()
+  input: fn foo () { }
+                   ^^^"#
         );
     }
 
@@ -585,7 +839,11 @@ fn foo() {}
              expected 'let a = 1 ; let c = 2 ;' got 'let a = 1 ; let b = 2 ;': \
              expected 'fn foo () { let a = 1 ; let c = 2 ; }' \
              got 'fn foo () { let a = 1 ; let b = 2 ; }': \
-             input:\n\n```\nfn foo() {\n    let a = 1;\n    let b = 2;\n}\n\n```"
+             input:\n\n```\nfn foo() {\n    let a = 1;\n    let b = 2;\n}\n\n```\n\n\
+             first difference between the pattern and the input:\n\
+             pattern: ... 1 ; let c = 2 ;\n\
+             \x20 input: ... 1 ; let b = 2 ;\n\
+             \x20                    ^"
         );
     }
 
@@ -636,7 +894,12 @@ impl Drop {
     }
 }
 
-```"#
+```
+
+first difference between the pattern and the input:
+pattern: <end>
+  input: drop_impl () ;
+         ^^^^^^^^^"#
         );
         assert_eq!(
             format!(
@@ -658,7 +921,12 @@ impl Drop {
     }
 }
 
-```"#
+```
+
+first difference between the pattern and the input:
+pattern: drop_impl1 () ; <end>
+  input: drop_impl1 () ; drop_impl2 () ;
+                         ^^^^^^^^^^"#
         );
     }
 
@@ -706,7 +974,11 @@ impl Drop {
             "expected 'c' but got 'b': \
             expected 'c' got 'b b': \
             expected '[a ... c]' got '[a b b]': \
-            input:\n\n```\n[a b b]\n```"
+            input:\n\n```\n[a b b]\n```\n\n\
+            first difference between the pattern and the input:\n\
+            pattern: c\n\
+            \x20 input: b b\n\
+            \x20        ^"
         );
         assert_eq!(
             format!(
@@ -720,7 +992,111 @@ impl Drop {
             "expected 'c' but got 'b': \
             expected 'b c' got 'b b': \
             expected '[a ... b c]' got '[a b b]': \
-            input:\n\n```\n[a b b]\n```"
+            input:\n\n```\n[a b b]\n```\n\n\
+            first difference between the pattern and the input:\n\
+            pattern: b c\n\
+            \x20 input: b b\n\
+            \x20          ^"
+        );
+    }
+
+    #[test]
+    fn test_diff_shows_context_around_the_first_difference() {
+        let err = match_tokens(
+            &quote! { a b c d e f g h i j k l },
+            &quote! { a b c d e f X },
+            &|tokens: TokenStream| Ok(tokens.to_string()),
+        )
+        .expect_err("unexpected match");
+        assert!(
+            format!("{:#}", err).ends_with(
+                "first difference between the pattern and the input:\n\
+                 pattern: ... d e f X\n\
+                 \x20 input: ... d e f g h i j ...\n\
+                 \x20                  ^"
+            ),
+            "{:#}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_diff_abbreviates_long_groups() {
+        let err = match_tokens(
+            &quote! { f(aaaa, bbbb, cccc, dddd, eeee); x },
+            &quote! { f(...); y },
+            &|tokens: TokenStream| Ok(tokens.to_string()),
+        )
+        .expect_err("unexpected match");
+        assert!(
+            format!("{:#}", err).ends_with(
+                "first difference between the pattern and the input:\n\
+                 pattern: f (...)   ; y\n\
+                 \x20 input: f ( ... ) ; x\n\
+                 \x20                    ^"
+            ),
+            "{:#}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_captures() {
+        let captures = assert_cc_captures!(
+            quote! { void foo(int x) { bar(x); } },
+            quote! { void $name(int $param) { ... } }
+        );
+        assert_eq!(captures["name"].to_string(), "foo");
+        assert_eq!(captures["param"].to_string(), "x");
+
+        let captures =
+            assert_rs_captures!(quote! { fn foo() -> (i32, i32) {} }, quote! { -> $ret });
+        assert_eq!(captures["ret"].to_string(), "(i32 , i32)");
+    }
+
+    #[test]
+    fn test_captures_inside_groups_and_after_wildcards() {
+        let captures = assert_cc_captures!(
+            quote! { struct S { int a; float b; }; },
+            quote! { struct S { ... $type b; } }
+        );
+        assert_eq!(captures["type"].to_string(), "float");
+    }
+
+    #[test]
+    fn test_repeated_capture_has_to_match_the_same_tokens() {
+        assert_rs_cc_matches!(quote! { a b c b }, quote! { $x c $x });
+        assert_cc_not_matches!(quote! { a b c d }, quote! { $x c $x });
+
+        // The matcher backtracks to find the occurrence that is consistent with the
+        // earlier capture.
+        let captures = assert_cc_captures!(
+            quote! { [ x = 1 ; y = 2 ; use(y) ] },
+            quote! { [ ... $var = $value ; use($var) ] }
+        );
+        assert_eq!(captures["var"].to_string(), "y");
+        assert_eq!(captures["value"].to_string(), "2");
+    }
+
+    #[test]
+    fn test_capture_error_message() {
+        assert_eq!(
+            format!(
+                "{:#}",
+                match_tokens(
+                    &quote! { a b c d },
+                    &quote! { $x b c $x },
+                    &|tokens: TokenStream| Ok(tokens.to_string())
+                )
+                .expect_err("unexpected match")
+            ),
+            "expected '$x' to match 'a' (captured earlier) but got 'd': \
+            expected '$ x b c $ x' got 'a b c d': \
+            input:\n\n```\na b c d\n```\n\n\
+            first difference between the pattern and the input:\n\
+            pattern: $x b c $x\n\
+            \x20 input: a  b c d\n\
+            \x20               ^^"
         );
     }
 }
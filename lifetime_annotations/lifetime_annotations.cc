@@ -12,6 +12,7 @@
 
 #include "absl/strings/str_cat.h"
 #include "lifetime_annotations/function_lifetimes.h"
+#include "lifetime_annotations/lifetime_substitutions.h"
 #include "lifetime_annotations/lifetime_symbol_table.h"
 #include "lifetime_annotations/pointee_type.h"
 #include "lifetime_annotations/type_lifetimes.h"
@@ -23,6 +24,7 @@
 #include "clang/AST/DeclCXX.h"
 #include "clang/AST/PrettyPrinter.h"
 #include "clang/AST/Type.h"
+#include "clang/AST/TypeLoc.h"
 #include "clang/Basic/LangOptions.h"
 #include "clang/Basic/SourceLocation.h"
 #include "clang/Lex/Pragma.h"
@@ -121,6 +123,31 @@ llvm::Expected<FunctionLifetimes> ParseLifetimeAnnotations(
   return ParseLifetimeAnnotations(func, symbol_table, lifetimes.str());
 }
 
+// Returns true if the implicit `this` parameter of `func` is annotated with
+// `[[clang::lifetimebound]]` (e.g. `const int& get() const
+// [[clang::lifetimebound]];`).
+bool IsThisLifetimeBound(const clang::FunctionDecl* func) {
+  const clang::TypeSourceInfo* type_source_info = func->getTypeSourceInfo();
+  if (!type_source_info) return false;
+  for (clang::TypeLoc type_loc = type_source_info->getTypeLoc();
+       auto attributed_type_loc =
+           type_loc.getAsAdjusted<clang::AttributedTypeLoc>();
+       type_loc = attributed_type_loc.getModifiedLoc()) {
+    if (attributed_type_loc.getAttrAs<clang::LifetimeBoundAttr>()) return true;
+  }
+  return false;
+}
+
+// Returns true if the `this` parameter or any of the parameters of `func` are
+// annotated with `[[clang::lifetimebound]]`.
+bool HasLifetimeBoundInputs(const clang::FunctionDecl* func) {
+  if (IsThisLifetimeBound(func)) return true;
+  for (const clang::ParmVarDecl* param : func->parameters()) {
+    if (param->hasAttr<clang::LifetimeBoundAttr>()) return true;
+  }
+  return false;
+}
+
 llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
     const clang::FunctionDecl* func, LifetimeSymbolTable& symbol_table,
     bool elision_enabled) {
@@ -161,8 +188,10 @@ llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
       return symbol_table.LookupNameAndMaybeDeclare(name_str);
     }
 
-    LifetimeFactory ParamLifetimeFactory() const {
-      return [this](const clang::Expr* name) -> llvm::Expected<Lifetime> {
+    LifetimeFactory ParamLifetimeFactory(
+        bool declare_elided_lifetimes = true) const {
+      return [this, declare_elided_lifetimes](
+                 const clang::Expr* name) -> llvm::Expected<Lifetime> {
         if (name) {
           Lifetime lifetime;
           if (llvm::Error err = LifetimeFromName(name).moveInto(lifetime)) {
@@ -185,11 +214,39 @@ llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
         }
 
         Lifetime lifetime = Lifetime::CreateVariable();
-        symbol_table.LookupLifetimeAndMaybeDeclare(lifetime);
+        if (declare_elided_lifetimes) {
+          symbol_table.LookupLifetimeAndMaybeDeclare(lifetime);
+        }
         return lifetime;
       };
     }
 
+    // Makes the pointee of a `[[clang::lifetimebound]]` input share its
+    // lifetime with the pointees of all the other `[[clang::lifetimebound]]`
+    // inputs.  An elided lifetime is replaced with the shared lifetime (if
+    // there already is one), but two different annotated lifetimes are an
+    // error.
+    llvm::Error ShareLifetimeBoundLifetime(
+        ValueLifetimes& value_lifetimes) const {
+      Lifetime lifetime = value_lifetimes.GetPointeeLifetimes().GetLifetime();
+      if (!lifetime_bound_lifetime.has_value()) {
+        lifetime_bound_lifetime = lifetime;
+      } else if (lifetime != *lifetime_bound_lifetime) {
+        if (lifetime.IsConstant() ||
+            symbol_table.LookupLifetime(lifetime).has_value()) {
+          return llvm::createStringError(
+              llvm::inconvertibleErrorCode(),
+              absl::StrCat("The [[clang::lifetimebound]] inputs of '",
+                           func->getNameAsString(),
+                           "' have different lifetimes"));
+        }
+        LifetimeSubstitutions subst;
+        subst.Add(lifetime, *lifetime_bound_lifetime);
+        value_lifetimes.SubstituteLifetimes(subst);
+      }
+      return llvm::Error::success();
+    }
+
     llvm::Expected<ValueLifetimes> CreateThisLifetimes(
         clang::QualType type, const clang::Expr* lifetime_name) const override {
       LifetimeFactory lifetime_factory = ParamLifetimeFactory();
@@ -210,6 +267,9 @@ llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
               lifetime_factory(lifetime_name).moveInto(object_lifetime)) {
         return std::move(err);
       }
+      if (IsThisLifetimeBound(func)) {
+        lifetime_bound_lifetime = object_lifetime;
+      }
 
       return ValueLifetimes::ForPointerLikeType(
           type, ObjectLifetimes(object_lifetime, value_lifetimes));
@@ -218,8 +278,37 @@ llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
     llvm::Expected<ValueLifetimes> CreateParamLifetimes(
         clang::QualType param_type,
         clang::TypeLoc param_type_loc) const override {
-      return ValueLifetimes::Create(param_type, param_type_loc,
-                                    ParamLifetimeFactory());
+      // `FunctionLifetimes::CreateForDecl` creates the lifetimes of the
+      // parameters in order.
+      unsigned param_index = next_param_index++;
+      bool is_lifetime_bound =
+          param_index < func->getNumParams() &&
+          func->getParamDecl(param_index)
+              ->hasAttr<clang::LifetimeBoundAttr>() &&
+          !PointeeType(param_type).isNull();
+      if (!is_lifetime_bound) {
+        return ValueLifetimes::Create(param_type, param_type_loc,
+                                      ParamLifetimeFactory());
+      }
+
+      // The elided lifetimes are only declared in the symbol table after
+      // `ShareLifetimeBoundLifetime` has (possibly) replaced one of them.
+      ValueLifetimes value_lifetimes;
+      if (llvm::Error err =
+              ValueLifetimes::Create(
+                  param_type, param_type_loc,
+                  ParamLifetimeFactory(/*declare_elided_lifetimes=*/false))
+                  .moveInto(value_lifetimes)) {
+        return std::move(err);
+      }
+      if (llvm::Error err = ShareLifetimeBoundLifetime(value_lifetimes)) {
+        return std::move(err);
+      }
+      std::as_const(value_lifetimes)
+          .Traverse([this](Lifetime lifetime, Variance) {
+            symbol_table.LookupLifetimeAndMaybeDeclare(lifetime);
+          });
+      return value_lifetimes;
     }
 
     static std::optional<Lifetime> GetSingleInputLifetime(
@@ -254,8 +343,12 @@ llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
         const std::optional<ValueLifetimes>& this_lifetimes) const override {
       // TODO(veluca): adapt to lifetime elision for function pointers.
 
+      // The output of a function with `[[clang::lifetimebound]]` inputs
+      // borrows from these inputs.
       std::optional<Lifetime> input_lifetime =
-          GetSingleInputLifetime(param_lifetimes, this_lifetimes);
+          lifetime_bound_lifetime.has_value()
+              ? lifetime_bound_lifetime
+              : GetSingleInputLifetime(param_lifetimes, this_lifetimes);
 
       return ValueLifetimes::Create(
           return_type, return_type_loc,
@@ -296,9 +389,16 @@ llvm::Expected<FunctionLifetimes> GetLifetimeAnnotationsInternal(
     bool elision_enabled;
     const clang::FunctionDecl* func;
     LifetimeSymbolTable& symbol_table;
+    mutable unsigned next_param_index = 0;
+    // The lifetime shared by the pointees of the `[[clang::lifetimebound]]`
+    // inputs (if any).
+    mutable std::optional<Lifetime> lifetime_bound_lifetime;
   };
 
-  Factory factory(elision_enabled, func, symbol_table);
+  // `[[clang::lifetimebound]]` annotations imply lifetime elision for the
+  // function.
+  Factory factory(elision_enabled || HasLifetimeBoundInputs(func), func,
+                  symbol_table);
   return FunctionLifetimes::CreateForDecl(func, factory);
 }
 }  // namespace
@@ -375,6 +375,56 @@ TEST_F(LifetimeAnnotationsTest, LifetimeElision_FailureTooManyInputLifetimes) {
                     "input lifetime"}})));
 }
 
+TEST_F(LifetimeAnnotationsTest, LifetimeBound_Param) {
+  EXPECT_THAT(GetNamedLifetimeAnnotations(R"(
+        // Note: this works even without #pragma clang lifetime_elision
+        const int& f(const int& x [[clang::lifetimebound]], const int& y);
+  )"),
+              IsOkAndHolds(LifetimesAre({{"f", "a, b -> a"}})));
+}
+
+TEST_F(LifetimeAnnotationsTest, LifetimeBound_MultipleParams) {
+  EXPECT_THAT(GetNamedLifetimeAnnotations(R"(
+        const int& f(const int& x [[clang::lifetimebound]],
+                     int* y,
+                     const int& z [[clang::lifetimebound]]);
+  )"),
+              IsOkAndHolds(LifetimesAre({{"f", "a, b, a -> a"}})));
+}
+
+TEST_F(LifetimeAnnotationsTest, LifetimeBound_This) {
+  EXPECT_THAT(GetNamedLifetimeAnnotations(R"(
+        struct S {
+          const int& get(const int& x) const [[clang::lifetimebound]];
+          const int& pick(const int& x [[clang::lifetimebound]]) const
+              [[clang::lifetimebound]];
+        };
+  )"),
+              IsOkAndHolds(LifetimesAre(
+                  {{"S::get", "a: b -> a"}, {"S::pick", "a: a -> a"}})));
+}
+
+TEST_F(LifetimeAnnotationsTest, LifetimeBound_WithAnnotatedLifetime) {
+  EXPECT_THAT(GetNamedLifetimeAnnotations(WithLifetimeMacros(R"(
+        const int& f(const int& $a x [[clang::lifetimebound]],
+                     const int& y [[clang::lifetimebound]]);
+  )")),
+              IsOkAndHolds(LifetimesAre({{"f", "a, a -> a"}})));
+}
+
+TEST_F(LifetimeAnnotationsTest,
+       LifetimeBound_Invalid_DifferentAnnotatedLifetimes) {
+  EXPECT_THAT(
+      GetNamedLifetimeAnnotations(WithLifetimeMacros(R"(
+        const int& f(const int& $a x [[clang::lifetimebound]],
+                     const int& $b y [[clang::lifetimebound]]);
+  )")),
+      IsOkAndHolds(LifetimesAre(
+          {{"f",
+            "ERROR: The [[clang::lifetimebound]] inputs of 'f' have different "
+            "lifetimes"}})));
+}
+
 TEST_F(LifetimeAnnotationsTest, LifetimeAnnotation_NoLifetimes) {
   EXPECT_THAT(GetNamedLifetimeAnnotations(R"_(
         [[clang::annotate("lifetimes", "()")]]
@@ -4,8 +4,13 @@
 
 #include "rs_bindings_from_cc/ast_util.h"
 
+#include <optional>
+#include <string>
+
+#include "clang/AST/Attr.h"
 #include "clang/AST/DeclCXX.h"
 #include "clang/AST/DeclTemplate.h"
+#include "clang/AST/Expr.h"
 
 namespace crubit {
 
@@ -25,4 +30,37 @@ bool IsFullClassTemplateSpecializationOrChild(const clang::Decl* decl) {
   return false;
 }
 
+std::optional<std::string> GetNodiscardMessage(const clang::Decl* decl) {
+  const auto* attr = decl->getAttr<clang::WarnUnusedResultAttr>();
+  if (attr == nullptr) return std::nullopt;
+  return attr->getMessage().str();
+}
+
+std::optional<std::string> GetDeprecationMessage(const clang::Decl* decl) {
+  const auto* attr = decl->getAttr<clang::DeprecatedAttr>();
+  if (attr == nullptr) return std::nullopt;
+  return attr->getMessage().str();
+}
+
+bool HasAnnotation(const clang::Decl* decl, llvm::StringRef annotation) {
+  for (const auto* attr : decl->specific_attrs<clang::AnnotateAttr>()) {
+    if (attr->getAnnotation() == annotation) return true;
+  }
+  return false;
+}
+
+std::optional<std::string> GetAnnotationStringArg(const clang::Decl* decl,
+                                                  llvm::StringRef annotation) {
+  for (const auto* attr : decl->specific_attrs<clang::AnnotateAttr>()) {
+    if (attr->getAnnotation() != annotation || attr->args_size() != 1) {
+      continue;
+    }
+    if (const auto* arg = clang::dyn_cast<clang::StringLiteral>(
+            (*attr->args_begin())->IgnoreParenImpCasts())) {
+      return arg->getString().str();
+    }
+  }
+  return std::nullopt;
+}
+
 }  // namespace crubit
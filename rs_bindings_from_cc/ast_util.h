@@ -5,7 +5,11 @@
 #ifndef CRUBIT_RS_BINDINGS_FROM_CC_AST_UTIL_H_
 #define CRUBIT_RS_BINDINGS_FROM_CC_AST_UTIL_H_
 
+#include <optional>
+#include <string>
+
 #include "clang/AST/DeclCXX.h"
+#include "llvm/ADT/StringRef.h"
 
 namespace crubit {
 
@@ -14,6 +18,29 @@ namespace crubit {
 // function decl) nested inside a ClassTemplateSpecializationDecl.
 bool IsFullClassTemplateSpecializationOrChild(const clang::Decl* decl);
 
+// Returns the message of the `[[nodiscard]]` attribute of `decl` (an empty
+// string if the attribute has no message), or nullopt if `decl` is not
+// `[[nodiscard]]`.  `__attribute__((warn_unused_result))` and
+// `[[clang::warn_unused_result]]` are treated the same as `[[nodiscard]]`.
+std::optional<std::string> GetNodiscardMessage(const clang::Decl* decl);
+
+// Returns the message of the `[[deprecated]]` attribute of `decl` (an empty
+// string if the attribute has no message), or nullopt if `decl` is not
+// `[[deprecated]]`.  `__attribute__((deprecated))` is treated the same as
+// `[[deprecated]]`.
+std::optional<std::string> GetDeprecationMessage(const clang::Decl* decl);
+
+// Returns true if `decl` has a `[[clang::annotate("<annotation>")]]`
+// attribute.
+bool HasAnnotation(const clang::Decl* decl, llvm::StringRef annotation);
+
+// Returns the string literal argument of a
+// `[[clang::annotate("<annotation>", "<argument>")]]` attribute of `decl`, or
+// nullopt if `decl` has no such attribute (or if the attribute doesn't have a
+// single string literal argument).
+std::optional<std::string> GetAnnotationStringArg(const clang::Decl* decl,
+                                                  llvm::StringRef annotation);
+
 }  // namespace crubit
 
 #endif  // CRUBIT_RS_BINDINGS_FROM_CC_AST_UTIL_H_
@@ -35,7 +35,7 @@ def _get_cc_info(providers):
             return provider
     fail("Couldn't find a CcInfo in the list of providers")
 
-def compile_rust(ctx, attr, src, extra_srcs, deps):
+def compile_rust(ctx, attr, src, extra_srcs, deps, crate_name = None):
     """Compiles a Rust source file.
 
     Args:
@@ -44,6 +44,7 @@ def compile_rust(ctx, attr, src, extra_srcs, deps):
       src: The source file to be compiled.
       extra_srcs: Additional source files to include in the crate.
       deps: List[DepVariantInfo]: A list of dependencies needed.
+      crate_name: The name of the compiled crate.  Defaults to the name of the current target.
 
     Returns:
       A DepVariantInfo provider.
@@ -52,8 +53,9 @@ def compile_rust(ctx, attr, src, extra_srcs, deps):
 
     output_hash = repr(hash(src.path))
 
-    # TODO(b/216587072): Remove this hacky escaping and use the import! macro once available
-    crate_name = ctx.label.name.replace("-", "_")
+    if not crate_name:
+        # TODO(b/216587072): Remove this hacky escaping and use the import! macro once available
+        crate_name = ctx.label.name.replace("-", "_")
 
     lib_name = "{prefix}{name}-{lib_hash}{extension}".format(
         prefix = "lib",
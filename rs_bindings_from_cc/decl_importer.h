@@ -136,7 +136,9 @@ class ImportContext {
   // Converts the Clang type `qual_type` into an equivalent `MappedType`.
   // Lifetimes for the type can optionally be specified using `lifetimes`.
   // If `qual_type` is a pointer type, `nullable` specifies whether the
-  // pointer can be null.
+  // pointer can be null, unless the pointer has a nullability annotation (e.g.
+  // `_Nonnull` or `absl::Nullable<T*>`) - such annotations are respected for
+  // all pointers contained in `qual_type`.
   // TODO(b/209390498): Currently, we're able to specify nullability only for
   // top-level pointers. Extend this so that we can specify nullability for
   // all pointers contained in `qual_type`, in the same way that `lifetimes`
@@ -148,6 +150,29 @@ class ImportContext {
       std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes,
       bool nullable = true) = 0;
 
+  // Converts the type of a function parameter or of a return value into an
+  // equivalent `MappedType`.  This is like `ConvertQualType`, except that some
+  // standard library types are mapped to their Rust equivalents (and converted
+  // at the boundary by the generated thunks) - e.g. `std::optional<T>` of a
+  // primitive type `T` is mapped to `Option<T>`, and `std::unique_ptr<T>` and
+  // `std::shared_ptr<T>` are mapped to `cc_std::UniquePtr<T>` and
+  // `cc_std::SharedPtr<T>`.
+  virtual absl::StatusOr<MappedType> ConvertParamOrReturnType(
+      clang::QualType qual_type,
+      std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes) = 0;
+
+  // Converts the type of a function parameter into an equivalent `MappedType`.
+  // This is like `ConvertParamOrReturnType`, except that additionally views
+  // that borrow from the caller are mapped to Rust references - e.g.
+  // `absl::string_view` is mapped to `&str`, and `absl::Span<T>` of a
+  // primitive type `T` is mapped to a slice.  (Returned views can't be mapped
+  // this way, because nothing bounds the lifetime of the returned reference.)
+  // Similarly, `std::function` and `absl::AnyInvocable` of a signature that
+  // only uses primitive types are mapped to Rust closures.
+  virtual absl::StatusOr<MappedType> ConvertParamType(
+      clang::QualType qual_type,
+      std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes) = 0;
+
   // Marks `decl` as successfully imported.  Other pieces of code can check
   // HasBeenAlreadySuccessfullyImported to avoid introducing dangling ItemIds
   // that refer to an unimportable `decl`.
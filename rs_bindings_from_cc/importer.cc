@@ -39,6 +39,7 @@
 #include "clang/AST/DeclBase.h"
 #include "clang/AST/DeclCXX.h"
 #include "clang/AST/DeclFriend.h"
+#include "clang/AST/DeclTemplate.h"
 #include "clang/AST/Mangle.h"
 #include "clang/AST/RawCommentList.h"
 #include "clang/AST/Type.h"
@@ -48,10 +49,17 @@
 #include "clang/Basic/SourceLocation.h"
 #include "clang/Basic/SourceManager.h"
 #include "clang/Basic/Specifiers.h"
+#include "clang/Basic/TokenKinds.h"
+#include "clang/Lex/MacroInfo.h"
+#include "clang/Lex/Preprocessor.h"
+#include "clang/Lex/Token.h"
 #include "clang/Sema/Sema.h"
+#include "llvm/ADT/APFloat.h"
+#include "llvm/ADT/ArrayRef.h"
 #include "llvm/ADT/STLExtras.h"
 #include "llvm/Support/Casting.h"
 #include "llvm/Support/FormatVariadic.h"
+#include "llvm/Support/JSON.h"
 #include "llvm/Support/Regex.h"
 
 namespace crubit {
@@ -238,6 +246,12 @@ Importer::SourceOrderKey Importer::GetSourceOrderKey(
   return SourceOrderKey(comment->getSourceRange());
 }
 
+Importer::SourceOrderKey Importer::GetSourceOrderKey(
+    const clang::MacroInfo* macro_info) const {
+  return SourceOrderKey(clang::SourceRange(macro_info->getDefinitionLoc(),
+                                           macro_info->getDefinitionEndLoc()));
+}
+
 class Importer::SourceLocationComparator {
  public:
   const bool operator()(const clang::SourceLocation& a,
@@ -272,6 +286,34 @@ class Importer::SourceLocationComparator {
   const clang::SourceManager& sm;
 };
 
+static bool IsExplicitFunctionTemplateInstantiation(
+    const clang::FunctionDecl* function_decl) {
+  switch (function_decl->getTemplateSpecializationKind()) {
+    case clang::TSK_ExplicitInstantiationDeclaration:
+    case clang::TSK_ExplicitInstantiationDefinition:
+      return function_decl->isFunctionTemplateSpecialization();
+    default:
+      return false;
+  }
+}
+
+// Returns the explicit instantiations of `function_template_decl` (e.g.
+// `template int Add<int>(int, int);` or `extern template int Add<int>(int,
+// int);`).  Clang doesn't represent explicit instantiations of function
+// templates as children of their `DeclContext`, so they have to be found
+// through the specializations of the template.
+static std::vector<clang::FunctionDecl*> GetExplicitInstantiations(
+    clang::FunctionTemplateDecl* function_template_decl) {
+  std::vector<clang::FunctionDecl*> result;
+  for (clang::FunctionDecl* specialization :
+       function_template_decl->specializations()) {
+    if (IsExplicitFunctionTemplateInstantiation(specialization)) {
+      result.push_back(specialization);
+    }
+  }
+  return result;
+}
+
 static std::vector<clang::Decl*> GetCanonicalChildren(
     const clang::DeclContext* decl_context) {
   std::vector<clang::Decl*> result;
@@ -327,6 +369,22 @@ std::vector<ItemId> Importer::GetItemIdsInSourceOrder(
 
   auto* decl_context = clang::cast<clang::DeclContext>(parent_decl);
   for (auto decl : GetCanonicalChildren(decl_context)) {
+    // Explicit instantiations of function templates are imported as separate
+    // functions (which may belong to the current target even if the template
+    // doesn't).
+    if (auto* function_template_decl =
+            llvm::dyn_cast<clang::FunctionTemplateDecl>(decl)) {
+      for (clang::FunctionDecl* instantiation :
+           GetExplicitInstantiations(function_template_decl)) {
+        if (!IsFromCurrentTarget(instantiation)) continue;
+        if (!GetDeclItem(instantiation).has_value()) continue;
+        auto item_id = GenerateItemId(instantiation);
+        if (visited_item_ids.insert(item_id).second) {
+          items.push_back({GetSourceOrderKey(instantiation), item_id});
+        }
+      }
+    }
+
     auto item = GetDeclItem(decl);
     // We generated IR for top level items coming from different targets,
     // however we shouldn't generate bindings for them, so we don't add them
@@ -354,6 +412,18 @@ std::vector<ItemId> Importer::GetItemIdsInSourceOrder(
                            ordered_comments.upper_bound(decl->getEndLoc()));
   }
 
+  // Macros don't belong to any decl context, so the constants defined by them
+  // are top-level items.
+  if (decl_context->isTranslationUnit()) {
+    for (const auto& [macro_info, _] : macro_constants_) {
+      items.push_back(
+          {GetSourceOrderKey(macro_info), GenerateItemId(macro_info)});
+      // We remove comments attached to a macro.
+      if (auto raw_comment = GetMacroComment(macro_info)) {
+        ordered_comments.erase(raw_comment->getBeginLoc());
+      }
+    }
+  }
   for (auto& [_, comment] : ordered_comments) {
     items.push_back({GetSourceOrderKey(comment), GenerateItemId(comment)});
   }
@@ -402,8 +472,154 @@ void Importer::ImportFreeComments() {
   llvm::sort(comments_, SourceLocationComparator(sm));
 }
 
+void Importer::ImportMacroConstants() {
+  clang::Preprocessor& preprocessor = sema_.getPreprocessor();
+  for (const auto& [identifier, _] : preprocessor.macros()) {
+    // `getMacroInfo` returns the definition that is active at the end of the
+    // translation unit (or null if the macro has been `#undef`ined).
+    const clang::MacroInfo* macro_info = preprocessor.getMacroInfo(identifier);
+    if (macro_info == nullptr || !macro_info->isObjectLike() ||
+        macro_info->isBuiltinMacro() || macro_info->isUsedForHeaderGuard() ||
+        GetOwningTargetOfLocation(macro_info->getDefinitionLoc()) !=
+            invocation_.target_) {
+      continue;
+    }
+    if (std::optional<Constant> constant =
+            ImportMacroConstant(identifier, macro_info)) {
+      macro_constants_.push_back({macro_info, *std::move(constant)});
+    }
+  }
+}
+
+std::optional<Constant> Importer::ImportMacroConstant(
+    const clang::IdentifierInfo* identifier,
+    const clang::MacroInfo* macro_info) {
+  llvm::StringRef name = identifier->getName();
+  // See the comment about unescapable identifiers in `GetTranslatedName`.
+  if (name == "crate" || name == "self" || name == "super" || name == "Self") {
+    return std::nullopt;
+  }
+
+  // Parentheses around the literal (e.g. `#define MINUS_ONE (-1)`) are
+  // ignored.
+  llvm::ArrayRef<clang::Token> tokens = macro_info->tokens();
+  while (tokens.size() >= 2 && tokens.front().is(clang::tok::l_paren) &&
+         tokens.back().is(clang::tok::r_paren)) {
+    tokens = tokens.drop_front().drop_back();
+  }
+  bool is_negated = !tokens.empty() && tokens.front().is(clang::tok::minus);
+  if (is_negated) {
+    tokens = tokens.drop_front();
+  }
+  if (tokens.empty() || llvm::any_of(tokens, [](const clang::Token& token) {
+        return token.hasUDSuffix();
+      })) {
+    return std::nullopt;
+  }
+
+  // The literals are parsed by `Sema`, so that they get the same types as in
+  // C++ code.  The diagnostics are suppressed, because macros that can't be
+  // imported are silently skipped.
+  clang::DiagnosticsEngine& diagnostics = sema_.getDiagnostics();
+  bool suppress_all_diagnostics = diagnostics.getSuppressAllDiagnostics();
+  diagnostics.setSuppressAllDiagnostics(true);
+  clang::ExprResult expr = clang::ExprError();
+  if (tokens.size() == 1 && tokens.front().is(clang::tok::numeric_constant)) {
+    expr = sema_.ActOnNumericConstant(tokens.front());
+    if (is_negated && expr.isUsable()) {
+      expr = sema_.CreateBuiltinUnaryOp(tokens.front().getLocation(),
+                                        clang::UO_Minus, expr.get());
+    }
+  } else if (!is_negated &&
+             llvm::all_of(tokens, [](const clang::Token& token) {
+               return token.is(clang::tok::string_literal);
+             })) {
+    expr = sema_.ActOnStringLiteral(tokens);
+  }
+  diagnostics.setSuppressAllDiagnostics(suppress_all_diagnostics);
+  if (!expr.isUsable()) {
+    return std::nullopt;
+  }
+
+  clang::QualType type = expr.get()->getType();
+  std::optional<std::variant<IntegerConstant, FloatConstant, std::string>>
+      value;
+  if (const auto* string_literal =
+          clang::dyn_cast<clang::StringLiteral>(expr.get())) {
+    // The contents are bound as a `&CStr` (which can't contain interior NULs)
+    // and are passed to the Rust side as UTF-8.
+    llvm::StringRef contents = string_literal->getString();
+    if (contents.contains('\0') || !llvm::json::isUTF8(contents)) {
+      return std::nullopt;
+    }
+    value = contents.str();
+    type = ctx_.getPointerType(ctx_.CharTy.withConst());
+  } else {
+    clang::Expr::EvalResult result;
+    if (!expr.get()->EvaluateAsRValue(result, ctx_)) {
+      return std::nullopt;
+    }
+    if (result.Val.isInt()) {
+      const llvm::APSInt& integer = result.Val.getInt();
+      if (integer.getSignificantBits() > 64) {
+        return std::nullopt;
+      }
+      value = IntegerConstant(integer);
+    } else if (result.Val.isFloat()) {
+      llvm::APFloat floating_point = result.Val.getFloat();
+      bool loses_info = false;
+      floating_point.convert(llvm::APFloat::IEEEdouble(),
+                             llvm::APFloat::rmNearestTiesToEven, &loses_info);
+      if (loses_info || !floating_point.isFinite()) {
+        return std::nullopt;
+      }
+      value = FloatConstant(floating_point.convertToDouble());
+    } else {
+      return std::nullopt;
+    }
+  }
+
+  std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+  absl::StatusOr<MappedType> mapped_type = ConvertQualType(type, no_lifetimes);
+  if (!mapped_type.ok()) {
+    return std::nullopt;
+  }
+
+  return Constant{
+      .identifier = Identifier(name.str()),
+      .id = GenerateItemId(macro_info),
+      .owning_target = invocation_.target_,
+      .doc_comment = GetCommentText(GetMacroComment(macro_info)),
+      .source_loc = ConvertSourceLocation(macro_info->getDefinitionLoc()),
+      .type = *std::move(mapped_type),
+      .value = *std::move(value),
+      .is_macro = true,
+      .enclosing_record_id = std::nullopt,
+      .enclosing_namespace_id = std::nullopt,
+  };
+}
+
+const clang::RawComment* Importer::GetMacroComment(
+    const clang::MacroInfo* macro_info) const {
+  clang::SourceManager& sm = ctx_.getSourceManager();
+  clang::SourceLocation definition_loc = macro_info->getDefinitionLoc();
+  // `comments_` are sorted by their source location.
+  auto next_comment = llvm::lower_bound(comments_, definition_loc,
+                                        SourceLocationComparator(sm));
+  if (next_comment == comments_.begin()) return nullptr;
+  const clang::RawComment* comment = *std::prev(next_comment);
+  if (comment->isTrailingComment() ||
+      sm.getFileID(comment->getEndLoc()) != sm.getFileID(definition_loc) ||
+      sm.getSpellingLineNumber(comment->getEndLoc()) + 1 !=
+          sm.getSpellingLineNumber(definition_loc)) {
+    return nullptr;
+  }
+  return comment;
+}
+
 void Importer::Import(clang::TranslationUnitDecl* translation_unit_decl) {
   ImportFreeComments();
+  ImportMacroConstants();
   clang::SourceManager& sm = ctx_.getSourceManager();
   std::vector<SourceLocationComparator::OrderedItem> ordered_items;
 
@@ -424,6 +640,9 @@ void Importer::Import(clang::TranslationUnitDecl* translation_unit_decl) {
       ordered_items.push_back({GetSourceOrderKey(decl), *item});
     }
   }
+  for (const auto& [macro_info, constant] : macro_constants_) {
+    ordered_items.push_back({GetSourceOrderKey(macro_info), constant});
+  }
 
   llvm::sort(ordered_items, SourceLocationComparator(sm));
 
@@ -445,6 +664,35 @@ void Importer::ImportDeclsFromDeclContext(
   for (auto decl : GetCanonicalChildren(decl_context)) {
     GetDeclItem(decl);
   }
+
+  // `GetCanonicalChildren` skips class template specializations, but an
+  // explicit instantiation definition (e.g. `template struct MyTemplate<int>;`)
+  // in a header of the current target requests bindings for the instantiation
+  // (similarly to the `cc_template!` macro).  Explicit instantiations of
+  // function templates request bindings for the instantiated function.
+  for (clang::Decl* decl : decl_context->decls()) {
+    if (auto* function_template_decl =
+            llvm::dyn_cast<clang::FunctionTemplateDecl>(decl)) {
+      for (clang::FunctionDecl* instantiation :
+           GetExplicitInstantiations(function_template_decl)) {
+        GetDeclItem(instantiation);
+      }
+      continue;
+    }
+    auto* specialization_decl =
+        llvm::dyn_cast<clang::ClassTemplateSpecializationDecl>(decl);
+    if (specialization_decl == nullptr ||
+        specialization_decl->getSpecializationKind() !=
+            clang::TSK_ExplicitInstantiationDefinition) {
+      continue;
+    }
+    if (GetOwningTargetOfLocation(
+            specialization_decl->getTemplateKeywordLoc()) !=
+        invocation_.target_) {
+      continue;
+    }
+    GetDeclItem(specialization_decl);
+  }
 }
 
 std::optional<IR::Item> Importer::GetDeclItem(clang::Decl* decl) {
@@ -547,8 +795,29 @@ bool IsTransitivelyInPrivate(clang::Decl* decl_to_check) {
   }
 }
 
+/// Returns true if a decl is a protected virtual method or a protected default
+/// constructor of a record which is not IsTransitivelyInPrivate.  Such members
+/// are needed for overriding the virtual methods of the record from Rust.
+bool IsProtectedMemberForOverrides(clang::Decl* decl) {
+  auto* method_decl = llvm::dyn_cast<clang::CXXMethodDecl>(decl);
+  if (method_decl == nullptr ||
+      method_decl->getAccess() != clang::AccessSpecifier::AS_protected) {
+    return false;
+  }
+  if (auto* ctor_decl =
+          llvm::dyn_cast<clang::CXXConstructorDecl>(method_decl)) {
+    if (!ctor_decl->isDefaultConstructor()) return false;
+  } else if (!method_decl->isVirtual() ||
+             llvm::isa<clang::CXXDestructorDecl>(method_decl)) {
+    return false;
+  }
+  return !IsTransitivelyInPrivate(method_decl->getParent());
+}
+
 std::optional<IR::Item> Importer::ImportDecl(clang::Decl* decl) {
-  if (IsTransitivelyInPrivate(decl)) return std::nullopt;
+  if (IsTransitivelyInPrivate(decl) && !IsProtectedMemberForOverrides(decl)) {
+    return std::nullopt;
+  }
   std::optional<IR::Item> result;
   for (auto& importer : decl_importers_) {
     if (importer->CanImport(decl)) {
@@ -573,8 +842,20 @@ BazelLabel Importer::GetOwningTarget(const clang::Decl* decl) const {
     return invocation_.target_;
   }
 
+  // Similarly, explicit instantiations of function templates belong to the
+  // target that contains the explicit instantiation.
+  if (const auto* function_decl = llvm::dyn_cast<clang::FunctionDecl>(decl);
+      function_decl != nullptr &&
+      IsExplicitFunctionTemplateInstantiation(function_decl)) {
+    return GetOwningTargetOfLocation(function_decl->getPointOfInstantiation());
+  }
+
+  return GetOwningTargetOfLocation(decl->getLocation());
+}
+
+BazelLabel Importer::GetOwningTargetOfLocation(
+    clang::SourceLocation source_location) const {
   clang::SourceManager& source_manager = ctx_.getSourceManager();
-  auto source_location = decl->getLocation();
 
   // If the header this decl comes from is not associated with a target we
   // consider it a textual header. In that case we go up the include stack
@@ -639,14 +920,16 @@ std::optional<std::string> Importer::GetComment(const clang::Decl* decl) const {
   // This does currently not distinguish between different types of comments.
   // In general it is not possible in C++ to reliably only extract doc comments.
   // This is going to be a heuristic that needs to be tuned over time.
+  return GetCommentText(ctx_.getRawCommentForDeclNoCache(decl));
+}
 
-  clang::SourceManager& sm = ctx_.getSourceManager();
-  clang::RawComment* raw_comment = ctx_.getRawCommentForDeclNoCache(decl);
-
+std::optional<std::string> Importer::GetCommentText(
+    const clang::RawComment* raw_comment) const {
   if (raw_comment == nullptr) {
     return {};
   }
 
+  clang::SourceManager& sm = ctx_.getSourceManager();
   std::string raw_comment_text =
       raw_comment->getFormattedText(sm, sm.getDiagnostics());
   std::string cleaned_comment_text = absl::StrJoin(
@@ -742,6 +1025,82 @@ absl::StatusOr<MappedType> Importer::ConvertTemplateSpecializationType(
   return ConvertTypeDecl(specialization_decl);
 }
 
+// Returns the template arguments of `type` if it is a specialization of the
+// class template `template_name` (e.g. "std::vector") with only type
+// arguments, or nullopt otherwise.
+static std::optional<std::vector<clang::QualType>> GetTemplateTypeArgs(
+    clang::QualType type, absl::string_view template_name) {
+  const auto* specialization_decl =
+      clang::dyn_cast_or_null<clang::ClassTemplateSpecializationDecl>(
+          type->getAsCXXRecordDecl());
+  if (!specialization_decl || specialization_decl->getSpecializedTemplate()
+                                      ->getQualifiedNameAsString() !=
+                                  template_name) {
+    return std::nullopt;
+  }
+  std::vector<clang::QualType> type_args;
+  for (const clang::TemplateArgument& arg :
+       specialization_decl->getTemplateArgs().asArray()) {
+    if (arg.getKind() != clang::TemplateArgument::Type) return std::nullopt;
+    type_args.push_back(arg.getAsType());
+  }
+  return type_args;
+}
+
+// Returns `T` if `type` is `template_name<T, default_arg_name<T>>` - e.g.
+// `std::vector<T, std::allocator<T>>` - or nullopt otherwise.
+static std::optional<clang::QualType> GetTypeArgWithDefaultSecondArg(
+    clang::QualType type, absl::string_view template_name,
+    absl::string_view default_arg_name) {
+  std::optional<std::vector<clang::QualType>> type_args =
+      GetTemplateTypeArgs(type, template_name);
+  if (!type_args.has_value() || type_args->size() != 2) return std::nullopt;
+  std::optional<std::vector<clang::QualType>> default_arg_type_args =
+      GetTemplateTypeArgs((*type_args)[1], default_arg_name);
+  if (!default_arg_type_args.has_value() ||
+      default_arg_type_args->size() != 1 ||
+      (*default_arg_type_args)[0].getCanonicalType() !=
+          (*type_args)[0].getCanonicalType()) {
+    return std::nullopt;
+  }
+  return (*type_args)[0];
+}
+
+// Returns the element type `T` if `type` is `std::vector<T>` with the default
+// allocator (i.e. `std::vector<T, std::allocator<T>>`), or nullopt otherwise.
+static std::optional<clang::QualType> GetStdVectorElementType(
+    clang::QualType type) {
+  return GetTypeArgWithDefaultSecondArg(type, "std::vector", "std::allocator");
+}
+
+absl::StatusOr<MappedType> Importer::ConvertPointeeType(
+    clang::QualType pointee_type,
+    std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes) {
+  std::optional<clang::QualType> element_type =
+      GetStdVectorElementType(pointee_type);
+  if (!element_type.has_value()) {
+    return ConvertQualType(pointee_type, lifetimes);
+  }
+  if (pointee_type.isVolatileQualified()) {
+    return absl::UnimplementedError(absl::StrCat(
+        "Unsupported `volatile` qualifier: ", pointee_type.getAsString()));
+  }
+
+  // The lifetimes of the elements of `std::vector` are not supported.
+  std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+  CRUBIT_ASSIGN_OR_RETURN(MappedType mapped_element_type,
+                          ConvertQualType(*element_type, no_lifetimes));
+  if (mapped_element_type.cc_type.is_const) {
+    return absl::UnimplementedError(
+        absl::StrCat("Unsupported `std::vector` of const elements: ",
+                     pointee_type.getAsString()));
+  }
+  MappedType mapped_vector_type =
+      MappedType::StdVectorOf(std::move(mapped_element_type));
+  mapped_vector_type.cc_type.is_const = pointee_type.isConstQualified();
+  return mapped_vector_type;
+}
+
 absl::StatusOr<MappedType> Importer::ConvertTypeDecl(clang::TypeDecl* decl) {
   if (!EnsureSuccessfullyImported(decl)) {
     return absl::NotFoundError(absl::Substitute(
@@ -752,6 +1111,30 @@ absl::StatusOr<MappedType> Importer::ConvertTypeDecl(clang::TypeDecl* decl) {
   return MappedType::WithDeclId(decl_id);
 }
 
+// Returns the nullability of the pointer `type` that is specified by a
+// nullability attribute (e.g. `int* _Nonnull`) or by the `absl::Nullable` and
+// `absl::Nonnull` alias templates (e.g. `absl::Nonnull<int*>`), or nullopt if
+// the nullability is not specified.
+static std::optional<clang::NullabilityKind> GetPointerNullability(
+    const clang::Type* type) {
+  if (std::optional<clang::NullabilityKind> nullability =
+          type->getNullability();
+      nullability.has_value()) {
+    return nullability;
+  }
+  // Some versions of Abseil define these alias templates without nullability
+  // attributes.
+  const auto* alias = type->getAs<clang::TemplateSpecializationType>();
+  if (alias == nullptr || !alias->isTypeAlias()) return std::nullopt;
+  const clang::TemplateDecl* alias_decl =
+      alias->getTemplateName().getAsTemplateDecl();
+  if (alias_decl == nullptr) return std::nullopt;
+  std::string alias_name = alias_decl->getQualifiedNameAsString();
+  if (alias_name == "absl::Nullable") return clang::NullabilityKind::Nullable;
+  if (alias_name == "absl::Nonnull") return clang::NullabilityKind::NonNull;
+  return std::nullopt;
+}
+
 absl::StatusOr<MappedType> Importer::ConvertType(
     const clang::Type* type,
     std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes,
@@ -782,6 +1165,17 @@ absl::StatusOr<MappedType> Importer::ConvertType(
                          type_string));
       }
 
+      // Refer to typedef'd function pointers (e.g. `callback_t` in
+      // `typedef void (*callback_t)(int, void*);`) through the generated type
+      // alias.  Fall back to spelling out the function pointer type if there
+      // are no bindings for the typedef.
+      if (const auto* typedef_type =
+              type->getAsAdjusted<clang::TypedefType>()) {
+        absl::StatusOr<MappedType> mapped_typedef =
+            ConvertTypeDecl(typedef_type->getDecl());
+        if (mapped_typedef.ok()) return mapped_typedef;
+      }
+
       clang::StringRef cc_call_conv =
           clang::FunctionType::getNameForCallConv(func_type->getCallConv());
       CRUBIT_ASSIGN_OR_RETURN(
@@ -811,8 +1205,15 @@ absl::StatusOr<MappedType> Importer::ConvertType(
     }
 
     CRUBIT_ASSIGN_OR_RETURN(MappedType mapped_pointee_type,
-                            ConvertQualType(pointee_type, lifetimes));
+                            ConvertPointeeType(pointee_type, lifetimes));
     if (type->isPointerType()) {
+      // Nullability annotations override the default nullability of the
+      // pointer (which only matters for pointers that have a lifetime).
+      if (std::optional<clang::NullabilityKind> nullability =
+              GetPointerNullability(type);
+          nullability.has_value()) {
+        nullable = *nullability != clang::NullabilityKind::NonNull;
+      }
       return MappedType::PointerTo(std::move(mapped_pointee_type), lifetime,
                                    nullable);
     } else if (type->isLValueReferenceType()) {
@@ -849,6 +1250,8 @@ absl::StatusOr<MappedType> Importer::ConvertType(
         return MappedType::Void();
         break;
       default:
+        // This also covers the character types (e.g. `wchar_t`, whose size
+        // and signedness depend on the target platform).
         if (builtin_type->isIntegerType()) {
           auto size = ctx_.getTypeSize(builtin_type);
           if (size == 8 || size == 16 || size == 32 || size == 64) {
@@ -860,6 +1263,17 @@ absl::StatusOr<MappedType> Importer::ConvertType(
         }
     }
   } else if (const auto* tag_type = type->getAsAdjusted<clang::TagType>()) {
+    // Types annotated with `crubit_rust_type` (e.g. the C++ bindings of Rust
+    // types generated by `cc_bindings_from_rs`) are mapped back to the
+    // original Rust type (rather than to a new Rust binding of the C++ type).
+    const clang::TagDecl* tag_decl = tag_type->getDecl();
+    if (const clang::TagDecl* definition = tag_decl->getDefinition()) {
+      tag_decl = definition;
+    }
+    if (std::optional<std::string> rust_type =
+            GetAnnotationStringArg(tag_decl, "crubit_rust_type")) {
+      return MappedType::Simple(*std::move(rust_type), type_string);
+    }
     return ConvertTypeDecl(tag_type->getDecl());
   } else if (const auto* typedef_type =
                  type->getAsAdjusted<clang::TypedefType>()) {
@@ -924,6 +1338,201 @@ absl::StatusOr<MappedType> Importer::ConvertQualType(
   return type;
 }
 
+// Returns the value type `T` if `type` is `std::optional<T>`, or nullopt
+// otherwise.
+static std::optional<clang::QualType> GetStdOptionalValueType(
+    clang::QualType type) {
+  std::optional<std::vector<clang::QualType>> type_args =
+      GetTemplateTypeArgs(type, "std::optional");
+  if (!type_args.has_value() || type_args->size() != 1) return std::nullopt;
+  return (*type_args)[0];
+}
+
+// Returns the pointee type `T` if `type` is `std::unique_ptr<T>` with the
+// default deleter (i.e. `std::unique_ptr<T, std::default_delete<T>>`), or
+// nullopt otherwise.
+static std::optional<clang::QualType> GetStdUniquePtrPointeeType(
+    clang::QualType type) {
+  return GetTypeArgWithDefaultSecondArg(type, "std::unique_ptr",
+                                        "std::default_delete");
+}
+
+// Returns the pointee type `T` if `type` is `std::shared_ptr<T>`, or nullopt
+// otherwise.
+static std::optional<clang::QualType> GetStdSharedPtrPointeeType(
+    clang::QualType type) {
+  std::optional<std::vector<clang::QualType>> type_args =
+      GetTemplateTypeArgs(type, "std::shared_ptr");
+  if (!type_args.has_value() || type_args->size() != 1) return std::nullopt;
+  return (*type_args)[0];
+}
+
+absl::StatusOr<MappedType> Importer::ConvertParamOrReturnType(
+    clang::QualType qual_type,
+    std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes) {
+  if (qual_type.isVolatileQualified()) {
+    return ConvertQualType(qual_type, lifetimes);
+  }
+  std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+
+  if (std::optional<clang::QualType> value_type =
+          GetStdOptionalValueType(qual_type);
+      value_type.has_value()) {
+    absl::StatusOr<MappedType> mapped_value_type =
+        ConvertQualType(*value_type, no_lifetimes);
+    // Only `std::optional` of primitive types is mapped.  `std::optional` of
+    // other types (e.g. of pointers or of structs) is treated like any other
+    // class template specialization.
+    if (mapped_value_type.ok() && !mapped_value_type->IsVoid() &&
+        !mapped_value_type->rs_type.decl_id.has_value() &&
+        mapped_value_type->rs_type.type_args.empty() &&
+        !mapped_value_type->cc_type.is_const) {
+      MappedType mapped_optional_type =
+          MappedType::StdOptionalOf(*std::move(mapped_value_type));
+      mapped_optional_type.cc_type.is_const = qual_type.isConstQualified();
+      return mapped_optional_type;
+    }
+  }
+
+  std::optional<clang::QualType> pointee_type =
+      GetStdUniquePtrPointeeType(qual_type);
+  bool is_shared_ptr = false;
+  if (!pointee_type.has_value()) {
+    pointee_type = GetStdSharedPtrPointeeType(qual_type);
+    is_shared_ptr = pointee_type.has_value();
+  }
+  if (pointee_type.has_value() && !(*pointee_type)->isIncompleteType()) {
+    absl::StatusOr<MappedType> mapped_pointee_type =
+        ConvertQualType(*pointee_type, no_lifetimes);
+    // Smart pointers to pointers and to const objects are not mapped.
+    if (mapped_pointee_type.ok() && !mapped_pointee_type->IsVoid() &&
+        mapped_pointee_type->rs_type.type_args.empty() &&
+        !mapped_pointee_type->cc_type.is_const) {
+      MappedType mapped_smart_ptr_type =
+          is_shared_ptr
+              ? MappedType::StdSharedPtrTo(*std::move(mapped_pointee_type))
+              : MappedType::StdUniquePtrTo(*std::move(mapped_pointee_type));
+      mapped_smart_ptr_type.cc_type.is_const = qual_type.isConstQualified();
+      return mapped_smart_ptr_type;
+    }
+  }
+
+  return ConvertQualType(qual_type, lifetimes);
+}
+
+// Returns true if `type` is `absl::string_view` - either a class, or (more
+// commonly) an alias of `std::string_view`.
+static bool IsAbslStringView(clang::QualType type) {
+  constexpr absl::string_view kAbslStringView = "absl::string_view";
+  while (const auto* typedef_type = type->getAs<clang::TypedefType>()) {
+    if (typedef_type->getDecl()->getQualifiedNameAsString() ==
+        kAbslStringView) {
+      return true;
+    }
+    type = typedef_type->desugar();
+  }
+  const clang::CXXRecordDecl* record_decl = type->getAsCXXRecordDecl();
+  return record_decl != nullptr &&
+         record_decl->getQualifiedNameAsString() == kAbslStringView;
+}
+
+// Returns the element type `T` if `type` is `absl::Span<T>`, or nullopt
+// otherwise.
+static std::optional<clang::QualType> GetAbslSpanElementType(
+    clang::QualType type) {
+  std::optional<std::vector<clang::QualType>> type_args =
+      GetTemplateTypeArgs(type, "absl::Span");
+  if (!type_args.has_value() || type_args->size() != 1) return std::nullopt;
+  return (*type_args)[0];
+}
+
+// Returns the signature `R(Args...)` if `type` is `template_name<R(Args...)>`
+// (e.g. `std::function<R(Args...)>`), or nullptr otherwise.  Signatures with
+// qualifiers (e.g. `absl::AnyInvocable<void() const>`), exception
+// specifications or C-style variadic parameters are not supported.
+static const clang::FunctionProtoType* GetCallableSignature(
+    clang::QualType type, absl::string_view template_name) {
+  std::optional<std::vector<clang::QualType>> type_args =
+      GetTemplateTypeArgs(type, template_name);
+  if (!type_args.has_value() || type_args->size() != 1) return nullptr;
+  const auto* signature = (*type_args)[0]->getAs<clang::FunctionProtoType>();
+  if (signature == nullptr || signature->isVariadic() ||
+      !signature->getMethodQuals().empty() ||
+      signature->getRefQualifier() != clang::RQ_None ||
+      signature->hasExceptionSpec()) {
+    return nullptr;
+  }
+  return signature;
+}
+
+// Returns true if `mapped_type` is a primitive type (e.g. `i32`) that needs
+// no conversion when it is passed between C++ and Rust.
+static bool IsPrimitive(const MappedType& mapped_type) {
+  return !mapped_type.rs_type.decl_id.has_value() &&
+         mapped_type.rs_type.type_args.empty();
+}
+
+absl::StatusOr<MappedType> Importer::ConvertParamType(
+    clang::QualType qual_type,
+    std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes) {
+  if (qual_type.isVolatileQualified()) {
+    return ConvertQualType(qual_type, lifetimes);
+  }
+
+  // `absl::StatusOr<T>` and `absl::Duration` are not mapped: mapping them to
+  // `Result<T, Status>` and `core::time::Duration` would require a Rust
+  // equivalent of `absl::Status`, and deciding how to handle negative and
+  // infinite durations.
+  if (IsAbslStringView(qual_type)) {
+    return MappedType::AbslStringViewParam();
+  }
+
+  if (std::optional<clang::QualType> element_type =
+          GetAbslSpanElementType(qual_type);
+      element_type.has_value()) {
+    std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+    absl::StatusOr<MappedType> mapped_element_type =
+        ConvertQualType(*element_type, no_lifetimes);
+    // Only spans of primitive types are mapped.  Spans of other types (e.g. of
+    // structs) are treated like any other class template specialization.
+    if (mapped_element_type.ok() && !mapped_element_type->IsVoid() &&
+        IsPrimitive(*mapped_element_type)) {
+      return MappedType::AbslSpanParamOf(*std::move(mapped_element_type));
+    }
+  }
+
+  for (absl::string_view template_name :
+       {internal::kCcStdFunction, internal::kCcAbslAnyInvocable}) {
+    const clang::FunctionProtoType* signature =
+        GetCallableSignature(qual_type, template_name);
+    if (signature == nullptr) continue;
+    // Only callables that take and return primitive types are mapped.
+    // Callables of other signatures (e.g. taking structs or
+    // `absl::string_view`) are treated like any other class template
+    // specialization.
+    std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+    absl::StatusOr<MappedType> return_type =
+        ConvertQualType(signature->getReturnType(), no_lifetimes);
+    if (!return_type.ok() || !IsPrimitive(*return_type)) break;
+    std::vector<MappedType> param_types;
+    for (clang::QualType param_type : signature->getParamTypes()) {
+      absl::StatusOr<MappedType> mapped_param_type =
+          ConvertQualType(param_type, no_lifetimes);
+      if (!mapped_param_type.ok() || !IsPrimitive(*mapped_param_type)) break;
+      param_types.push_back(*std::move(mapped_param_type));
+    }
+    if (param_types.size() != signature->getNumParams()) break;
+    if (template_name == internal::kCcStdFunction) {
+      return MappedType::StdFunctionParam(*std::move(return_type),
+                                          std::move(param_types));
+    }
+    return MappedType::AbslAnyInvocableParam(*std::move(return_type),
+                                             std::move(param_types));
+  }
+
+  return ConvertParamOrReturnType(qual_type, lifetimes);
+}
+
 std::string Importer::GetMangledName(const clang::NamedDecl* named_decl) const {
   if (auto record_decl = clang::dyn_cast<clang::RecordDecl>(named_decl)) {
     // Mangled record names are used to 1) provide valid Rust identifiers for
@@ -1040,6 +1649,8 @@ absl::StatusOr<UnqualifiedIdentifier> Importer::GetTranslatedName(
       return {SpecialName::kConstructor};
     case clang::DeclarationName::CXXDestructorName:
       return {SpecialName::kDestructor};
+    case clang::DeclarationName::CXXConversionFunctionName:
+      return {SpecialName::kConversion};
     case clang::DeclarationName::CXXOperatorName:
       switch (named_decl->getDeclName().getCXXOverloadedOperator()) {
         case clang::OO_None:
@@ -1057,7 +1668,6 @@ absl::StatusOr<UnqualifiedIdentifier> Importer::GetTranslatedName(
       }
       LOG(FATAL) << "The `switch` above should handle all cases";
     default:
-      // To be implemented later: CXXConversionFunctionName.
       // There are also e.g. literal operators, deduction guides, etc., but
       // we might not need to implement them at all. Full list at:
       // https://clang.llvm.org/doxygen/classclang_1_1DeclarationName.html#a9ab322d434446b43379d39e41af5cbe3
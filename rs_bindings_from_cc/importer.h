@@ -23,9 +23,11 @@
 #include "rs_bindings_from_cc/importers/function_template.h"
 #include "rs_bindings_from_cc/importers/namespace.h"
 #include "rs_bindings_from_cc/importers/typedef_name.h"
+#include "rs_bindings_from_cc/importers/var.h"
 #include "rs_bindings_from_cc/ir.h"
 #include "clang/AST/Mangle.h"
 #include "clang/AST/RawCommentList.h"
+#include "clang/Lex/MacroInfo.h"
 
 namespace crubit {
 
@@ -48,6 +50,7 @@ class Importer final : public ImportContext {
         std::make_unique<FunctionTemplateDeclImporter>(*this));
     decl_importers_.push_back(std::make_unique<NamespaceDeclImporter>(*this));
     decl_importers_.push_back(std::make_unique<TypedefNameDeclImporter>(*this));
+    decl_importers_.push_back(std::make_unique<VarDeclImporter>(*this));
   }
 
   // Import all visible declarations from a translation unit.
@@ -83,6 +86,14 @@ class Importer final : public ImportContext {
       clang::QualType qual_type,
       std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes,
       bool nullable = true) override;
+  absl::StatusOr<MappedType> ConvertParamOrReturnType(
+      clang::QualType qual_type,
+      std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes)
+      override;
+  absl::StatusOr<MappedType> ConvertParamType(
+      clang::QualType qual_type,
+      std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes)
+      override;
 
   void MarkAsSuccessfullyImported(const clang::TypeDecl* decl) override;
   bool HasBeenAlreadySuccessfullyImported(
@@ -104,10 +115,18 @@ class Importer final : public ImportContext {
   // Returns a SourceOrderKey for the given `comment` that should be used for
   // ordering Items.
   SourceOrderKey GetSourceOrderKey(const clang::RawComment* comment) const;
+  // Returns a SourceOrderKey for the given `macro_info` that should be used
+  // for ordering Items.
+  SourceOrderKey GetSourceOrderKey(const clang::MacroInfo* macro_info) const;
 
   // Returns a name for `decl` that should be used for ordering declarations.
   std::string GetNameForSourceOrder(const clang::Decl* decl) const;
 
+  // Returns the target that owns the header containing `source_location` (or
+  // the header that (transitively) #includes it, if it's a textual header).
+  BazelLabel GetOwningTargetOfLocation(
+      clang::SourceLocation source_location) const;
+
   // Returns the item ids of template instantiations that have been triggered
   // from the current target.  The returned items are in an arbitrary,
   // deterministic/reproducible order.
@@ -116,6 +135,23 @@ class Importer final : public ImportContext {
   std::optional<IR::Item> GetDeclItem(clang::Decl* decl) override;
   // Stores the comments of this target in source order.
   void ImportFreeComments();
+  // Stores the constants defined by the object-like macros of this target
+  // that expand to a numeric or string literal (e.g. `#define MAX_LEN 128`).
+  void ImportMacroConstants();
+  // Returns the constant defined by the `identifier` macro, or `std::nullopt`
+  // if the macro doesn't expand to a supported literal.
+  std::optional<Constant> ImportMacroConstant(
+      const clang::IdentifierInfo* identifier,
+      const clang::MacroInfo* macro_info);
+  // Returns the comment that ends on the line right before the definition of
+  // the macro (if any).  Such a comment is treated as the doc comment of the
+  // macro.
+  const clang::RawComment* GetMacroComment(
+      const clang::MacroInfo* macro_info) const;
+  // Returns the text of `raw_comment` without comment markers and lint
+  // directives (or `std::nullopt` if nothing is left).
+  std::optional<std::string> GetCommentText(
+      const clang::RawComment* raw_comment) const;
 
   absl::StatusOr<MappedType> ConvertType(
       const clang::Type* type,
@@ -128,6 +164,13 @@ class Importer final : public ImportContext {
   absl::StatusOr<MappedType> ConvertTemplateSpecializationType(
       const clang::TemplateSpecializationType* type);
 
+  // Converts the pointee of a pointer or a reference into a MappedType,
+  // mapping `std::vector<T>` to `::cc_std::Vector<T>` (which is only possible
+  // behind a pointer, because `std::vector` is not trivially relocatable).
+  absl::StatusOr<MappedType> ConvertPointeeType(
+      clang::QualType pointee_type,
+      std::optional<clang::tidy::lifetimes::ValueLifetimes>& lifetimes);
+
   std::vector<std::unique_ptr<DeclImporter>> decl_importers_;
   std::unique_ptr<clang::MangleContext> mangler_;
   absl::flat_hash_map<const clang::Decl*, std::optional<IR::Item>>
@@ -135,6 +178,7 @@ class Importer final : public ImportContext {
   absl::flat_hash_set<const clang::ClassTemplateSpecializationDecl*>
       class_template_instantiations_;
   std::vector<const clang::RawComment*> comments_;
+  std::vector<std::pair<const clang::MacroInfo*, Constant>> macro_constants_;
 
   // Set of decls that have been successfully imported (i.e. that will be
   // present in the IR output / that will not produce dangling ItemIds in the IR
@@ -11,10 +11,13 @@
 #include "absl/log/check.h"
 #include "absl/log/die_if_null.h"
 #include "absl/log/log.h"
+#include "absl/strings/str_cat.h"
 #include "rs_bindings_from_cc/ast_convert.h"
+#include "rs_bindings_from_cc/ast_util.h"
 #include "clang/AST/ASTContext.h"
 #include "clang/AST/CXXInheritance.h"
 #include "clang/AST/Decl.h"
+#include "clang/AST/DeclCXX.h"
 #include "clang/AST/PrettyPrinter.h"
 #include "clang/AST/RecordLayout.h"
 #include "clang/AST/Type.h"
@@ -46,6 +49,46 @@ const absl::flat_hash_set<absl::string_view>& FinalOverrides() {
   return final_overrides;
 }
 
+// Returns the last component of the Rust name of a (possibly nested) record.
+//
+// Anonymous structs and unions are named after the unnamed field that they
+// are the type of (e.g. `_unnamed_field1`), so that their full Rust name
+// matches the name of the field in the Rust struct of the enclosing record
+// (e.g. `Outer__unnamed_field1` for the `__unnamed_field1` field of `Outer`).
+std::string GetRecordRsNameComponent(const clang::RecordDecl& record) {
+  if (record.isAnonymousStructOrUnion()) {
+    const auto* parent =
+        clang::cast<clang::RecordDecl>(record.getDeclContext());
+    for (const clang::FieldDecl* field : parent->fields()) {
+      if (field->isAnonymousStructOrUnion() &&
+          field->getType()->getAsRecordDecl() == &record) {
+        return absl::StrCat("_unnamed_field", field->getFieldIndex());
+      }
+    }
+    LOG(FATAL) << "Couldn't find the field of an anonymous struct or union";
+  }
+  const clang::NamedDecl* named_decl = &record;
+  if (record.getName().empty()) {
+    named_decl = record.getTypedefNameForAnonDecl();
+    CHECK(named_decl != nullptr);
+  }
+  return named_decl->getName().str();
+}
+
+// Returns the prefix of the Rust name of a class nested in `parent`: the names
+// of `parent` and of its enclosing classes, each followed by an underscore
+// (e.g. `Outer_Middle_` for `Outer::Middle::Inner`).
+std::string GetNestedRecordRsNamePrefix(const clang::CXXRecordDecl& parent) {
+  std::string prefix;
+  for (const clang::DeclContext* context = &parent; context->isRecord();
+       context = context->getParent()) {
+    prefix = absl::StrCat(
+        GetRecordRsNameComponent(*clang::cast<clang::RecordDecl>(context)), "_",
+        prefix);
+  }
+  return prefix;
+}
+
 std::string GetClassTemplateSpecializationCcName(
     const clang::ASTContext& ast_context,
     const clang::ClassTemplateSpecializationDecl* specialization_decl,
@@ -148,9 +191,11 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
   if (record_decl->isImplicit()) {
     return std::nullopt;
   }
-  if (decl_context->isRecord()) {
-    return ictx_.ImportUnsupportedItem(record_decl,
-                                       "Nested classes are not supported yet");
+  if (const clang::CXXRecordDecl* definition = record_decl->getDefinition();
+      definition != nullptr &&
+      GetAnnotationStringArg(definition, "crubit_rust_type").has_value()) {
+    // The type already has a Rust equivalent (see `Importer::ConvertType`).
+    return std::nullopt;
   }
   if (clang::isa<clang::ClassTemplatePartialSpecializationDecl>(record_decl)) {
     return ictx_.ImportUnsupportedItem(
@@ -168,6 +213,39 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
   if (record_decl->isInvalidDecl()) {
     return std::nullopt;
   }
+  std::optional<ItemId> enclosing_record_id = std::nullopt;
+  std::string rs_name_prefix;
+  if (auto* parent = llvm::dyn_cast<clang::CXXRecordDecl>(
+          record_decl->getDeclContext())) {
+    // Class template specializations are emitted in the top-level namespace
+    // (see GetEnclosingNamespaceId), so their nested classes would need to
+    // be emitted there as well.
+    if (clang::isa<clang::ClassTemplateSpecializationDecl>(parent)) {
+      return ictx_.ImportUnsupportedItem(
+          record_decl, "Classes nested in class templates are not supported");
+    }
+    if (!ictx_.EnsureSuccessfullyImported(parent)) {
+      return ictx_.ImportUnsupportedItem(record_decl,
+                                         "Couldn't import the parent");
+    }
+    if (!record_decl->getDefinition()) {
+      return ictx_.ImportUnsupportedItem(
+          record_decl,
+          "Forward declarations of nested classes are not supported yet");
+    }
+    // The members of anonymous structs and unions are exposed through
+    // accessors of the enclosing record, and the anonymous records themselves
+    // can't be named in C++ (and so they can't have thunks).
+    if (record_decl->isAnonymousStructOrUnion() &&
+        !record_decl->isTriviallyCopyable()) {
+      return ictx_.ImportUnsupportedItem(
+          record_decl,
+          "Anonymous structs and unions that are not trivially copyable are "
+          "not supported");
+    }
+    enclosing_record_id = GenerateItemId(parent);
+    rs_name_prefix = GetNestedRecordRsNamePrefix(*parent);
+  }
   if (record_decl->isInStdNamespace() &&
       record_decl->hasAttr<clang::VisibilityAttr>()) {
     auto visibility = record_decl->getAttr<clang::VisibilityAttr>();
@@ -229,6 +307,13 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
           ictx_.GetComment(specialization_decl->getSpecializedTemplate());
     }
     source_loc = specialization_decl->getBeginLoc();
+  } else if (record_decl->isAnonymousStructOrUnion()) {
+    // Anonymous structs and unions have no C++ name, so their `cc_name` is
+    // the same as their synthetic `rs_name`.
+    rs_name = cc_name =
+        absl::StrCat(rs_name_prefix, GetRecordRsNameComponent(*record_decl));
+    doc_comment = ictx_.GetComment(record_decl);
+    source_loc = record_decl->getBeginLoc();
   } else {
     const clang::NamedDecl* named_decl = record_decl;
     if (record_decl->getName().empty()) {
@@ -244,7 +329,8 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
     absl::StatusOr<Identifier> record_name =
         ictx_.GetTranslatedIdentifier(named_decl);
     if (record_name.ok()) {
-      rs_name = cc_name = record_name->Ident();
+      cc_name = record_name->Ident();
+      rs_name = absl::StrCat(rs_name_prefix, cc_name);
       doc_comment = ictx_.GetComment(record_decl);
       source_loc = record_decl->getBeginLoc();
     } else {
@@ -272,7 +358,11 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
   // cannot fail.
   ictx_.MarkAsSuccessfullyImported(record_decl);
 
-  ictx_.sema_.ForceDeclarationOfImplicitMembers(record_decl);
+  // Anonymous structs and unions can't be named in C++, so there are no
+  // bindings for their special member functions.
+  if (!record_decl->isAnonymousStructOrUnion()) {
+    ictx_.sema_.ForceDeclarationOfImplicitMembers(record_decl);
+  }
 
   const clang::ASTRecordLayout& layout =
       ictx_.ctx_.getASTRecordLayout(record_decl);
@@ -302,6 +392,8 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
       .id = GenerateItemId(record_decl),
       .owning_target = ictx_.GetOwningTarget(record_decl),
       .doc_comment = std::move(doc_comment),
+      .nodiscard = GetNodiscardMessage(record_decl),
+      .deprecated = GetDeprecationMessage(record_decl),
       .source_loc = ictx_.ConvertSourceLocation(source_loc),
       .unambiguous_public_bases = GetUnambiguousPublicBases(*record_decl),
       .fields = std::move(fields),
@@ -316,12 +408,21 @@ std::optional<IR::Item> CXXRecordDeclImporter::Import(
       .is_trivial_abi = record_decl->canPassInRegisters(),
       .is_inheritable = !is_effectively_final,
       .is_abstract = record_decl->isAbstract(),
+      .has_private_virtual_methods = std::any_of(
+          record_decl->method_begin(), record_decl->method_end(),
+          [](const clang::CXXMethodDecl* method) {
+            return method->isVirtual() &&
+                   !clang::isa<clang::CXXDestructorDecl>(method) &&
+                   method->getAccess() == clang::AS_private;
+          }),
       .record_type = *record_type,
       .is_aggregate = record_decl->isAggregate(),
       .is_anon_record_with_typedef = anon_typedef != nullptr,
+      .is_anonymous_struct_or_union = record_decl->isAnonymousStructOrUnion(),
       .is_explicit_class_template_instantiation_definition =
           is_explicit_class_template_instantiation_definition,
       .child_item_ids = std::move(item_ids),
+      .enclosing_record_id = enclosing_record_id,
       .enclosing_namespace_id = GetEnclosingNamespaceId(record_decl),
   };
 
@@ -4,6 +4,8 @@
 
 #include "rs_bindings_from_cc/importers/enum.h"
 
+#include "rs_bindings_from_cc/ast_util.h"
+
 namespace crubit {
 
 std::optional<IR::Item> EnumDeclImporter::Import(clang::EnumDecl* enum_decl) {
@@ -34,6 +36,14 @@ std::optional<IR::Item> EnumDeclImporter::Import(clang::EnumDecl* enum_decl) {
         enum_decl,
         "Forward declared enums without type specifiers are not supported");
   }
+  bool is_closed = HasAnnotation(enum_decl, "crubit_closed_enum");
+  if (is_closed && !enum_decl->isScoped()) {
+    return ictx_.ImportUnsupportedItem(
+        enum_decl,
+        "The `crubit_closed_enum` annotation is only supported for scoped "
+        "enums (`enum class`)");
+  }
+
   std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
   absl::StatusOr<MappedType> type =
       ictx_.ConvertQualType(cc_type, no_lifetimes);
@@ -56,6 +66,8 @@ std::optional<IR::Item> EnumDeclImporter::Import(clang::EnumDecl* enum_decl) {
 
     enumerators.push_back(Enumerator{
         .identifier = *enumerator_name,
+        .doc_comment = ictx_.GetComment(enumerator),
+        .deprecated = GetDeprecationMessage(enumerator),
         .value = IntegerConstant(enumerator->getInitVal()),
     });
   }
@@ -64,9 +76,14 @@ std::optional<IR::Item> EnumDeclImporter::Import(clang::EnumDecl* enum_decl) {
       .identifier = *enum_name,
       .id = GenerateItemId(enum_decl),
       .owning_target = ictx_.GetOwningTarget(enum_decl),
+      .doc_comment = ictx_.GetComment(enum_decl),
+      .nodiscard = GetNodiscardMessage(enum_decl),
+      .deprecated = GetDeprecationMessage(enum_decl),
       .source_loc = ictx_.ConvertSourceLocation(enum_decl->getBeginLoc()),
       .underlying_type = *std::move(type),
       .enumerators = enumerators,
+      .is_scoped = enum_decl->isScoped(),
+      .is_closed = is_closed,
       .enclosing_namespace_id = GetEnclosingNamespaceId(enum_decl),
   };
 }
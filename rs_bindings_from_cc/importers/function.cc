@@ -5,11 +5,21 @@
 #include "rs_bindings_from_cc/importers/function.h"
 
 #include <optional>
+#include <string>
+#include <vector>
 
+#include "absl/strings/ascii.h"
+#include "absl/strings/str_join.h"
 #include "absl/strings/substitute.h"
 #include "rs_bindings_from_cc/ast_util.h"
+#include "clang/AST/Attr.h"
+#include "clang/AST/Expr.h"
+#include "clang/AST/PrettyPrinter.h"
+#include "clang/AST/Type.h"
 #include "clang/Sema/Sema.h"
+#include "llvm/ADT/SmallPtrSet.h"
 #include "llvm/ADT/StringRef.h"
+#include "llvm/Support/raw_ostream.h"
 
 namespace crubit {
 
@@ -24,6 +34,136 @@ static bool IsInStdNamespace(const clang::FunctionDecl* decl) {
   return false;
 }
 
+// Returns the C++ spelling of the template arguments of a function template
+// specialization (e.g. `<int, float>`), or `std::nullopt` if `decl` is not a
+// function template specialization.
+static std::optional<std::string> GetTemplateArgsCcSpelling(
+    const clang::ASTContext& ast_context, const clang::FunctionDecl* decl) {
+  const clang::TemplateArgumentList* template_args =
+      decl->getTemplateSpecializationArgs();
+  if (template_args == nullptr) return std::nullopt;
+
+  // See also `GetClassTemplateSpecializationCcName` in `cxx_record.cc`.
+  clang::PrintingPolicy policy(ast_context.getLangOpts());
+  policy.IncludeTagDefinition = false;
+  policy.PrintCanonicalTypes = true;
+  policy.AlwaysIncludeTypeForTemplateArgument = true;
+
+  std::string result;
+  llvm::raw_string_ostream os(result);
+  clang::printTemplateArgumentList(os, template_args->asArray(), policy);
+  return os.str();
+}
+
+// Returns the Rust name from a `[[clang::annotate("crubit_rust_name", "...")]]`
+// attribute of `decl`, or `std::nullopt` if there is no such attribute.
+static absl::StatusOr<std::optional<std::string>> GetAnnotatedRustName(
+    const clang::FunctionDecl* decl) {
+  std::optional<std::string> result;
+  for (const auto* attr : decl->specific_attrs<clang::AnnotateAttr>()) {
+    if (attr->getAnnotation() != "crubit_rust_name") continue;
+    if (result.has_value()) {
+      return absl::InvalidArgumentError(
+          "Only one `crubit_rust_name` annotation is allowed");
+    }
+    const auto* name =
+        attr->args_size() == 1
+            ? clang::dyn_cast<clang::StringLiteral>(
+                  (*attr->args_begin())->IgnoreParenImpCasts())
+            : nullptr;
+    if (name == nullptr) {
+      return absl::InvalidArgumentError(
+          "The `crubit_rust_name` annotation requires a single string literal "
+          "argument");
+    }
+    result = name->getString().str();
+  }
+  return result;
+}
+
+// Formats the C++ spelling of a parameter type (e.g. `const std::string &`) as
+// a part of a Rust name (e.g. `const_std_string_ref`).
+static std::string FormatParamTypeAsRustNameSuffix(absl::string_view cc_type) {
+  std::vector<std::string> parts;
+  std::string current;
+  auto flush = [&] {
+    if (!current.empty()) parts.push_back(std::move(current));
+    current.clear();
+  };
+  for (size_t i = 0; i < cc_type.size(); ++i) {
+    char c = cc_type[i];
+    if (absl::ascii_isalnum(c) || c == '_') {
+      current += c;
+      continue;
+    }
+    flush();
+    if (c == '*') {
+      parts.push_back("ptr");
+    } else if (c == '&' && i + 1 < cc_type.size() && cc_type[i + 1] == '&') {
+      parts.push_back("rvalue_ref");
+      ++i;
+    } else if (c == '&') {
+      parts.push_back("ref");
+    }
+  }
+  flush();
+  return absl::StrJoin(parts, "_");
+}
+
+// Returns a Rust name that disambiguates `decl` from the other functions in
+// its overload set by appending the types of its parameters to `name` (e.g.
+// `Overload_int` for `Overload(int)`), or `std::nullopt` if `decl` is not
+// overloaded.  Overload sets that contain function templates or
+// using-declarations are not disambiguated (the thunks of such overloads may
+// not be instantiable, see `test/golden/overloads.h`).
+static std::optional<std::string> GetOverloadRustName(
+    const clang::ASTContext& ast_context, const clang::FunctionDecl* decl,
+    const Identifier& name) {
+  if (decl->isFunctionTemplateSpecialization()) return std::nullopt;
+
+  llvm::SmallPtrSet<const clang::Decl*, 4> overloads;
+  for (const clang::NamedDecl* overload :
+       decl->getDeclContext()->lookup(decl->getDeclName())) {
+    const auto* function_decl = clang::dyn_cast<clang::FunctionDecl>(overload);
+    if (function_decl == nullptr) return std::nullopt;
+    overloads.insert(function_decl->getCanonicalDecl());
+  }
+  if (overloads.size() < 2 || decl->getNumParams() == 0) return std::nullopt;
+
+  clang::PrintingPolicy policy(ast_context.getLangOpts());
+  policy.SuppressTagKeyword = true;
+  std::vector<std::string> parts = {std::string(name.Ident())};
+  for (const clang::ParmVarDecl* param : decl->parameters()) {
+    parts.push_back(
+        FormatParamTypeAsRustNameSuffix(param->getType().getAsString(policy)));
+  }
+  return absl::StrJoin(parts, "_");
+}
+
+// Returns the Rust name of the method that an `explicit` conversion function
+// is bound as: `to_` followed by the snake case spelling of the target type
+// (e.g. `to_other_type` for `explicit operator OtherType()`).
+static std::string GetExplicitConversionRustName(
+    const clang::ASTContext& ast_context,
+    const clang::CXXConversionDecl* decl) {
+  clang::PrintingPolicy policy(ast_context.getLangOpts());
+  policy.SuppressTagKeyword = true;
+  policy.SuppressScope = true;
+  std::string type_name = FormatParamTypeAsRustNameSuffix(
+      decl->getConversionType().getAsString(policy));
+  std::string result = "to_";
+  for (size_t i = 0; i < type_name.size(); ++i) {
+    char c = type_name[i];
+    if (absl::ascii_isupper(c) && i > 0 &&
+        (absl::ascii_islower(type_name[i - 1]) ||
+         absl::ascii_isdigit(type_name[i - 1]))) {
+      result += '_';
+    }
+    result += absl::ascii_tolower(c);
+  }
+  return result;
+}
+
 Identifier FunctionDeclImporter::GetTranslatedParamName(
     const clang::ParmVarDecl* param_decl) {
   int param_pos = param_decl->getFunctionScopeIndex();
@@ -69,18 +209,28 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
     }
   }
   // Method is private, we don't need to import it.
+  bool is_protected = false;
   if (auto* method_decl =
           clang::dyn_cast<clang::CXXMethodDecl>(function_decl)) {
     switch (method_decl->getAccess()) {
       case clang::AS_public:
         break;
       case clang::AS_protected:
+        // Only protected virtual methods and default constructors get here
+        // (see `IsProtectedMemberForOverrides` in importer.cc).
+        is_protected = true;
+        break;
       case clang::AS_private:
       case clang::AS_none:
         // No need for IR to include Func representing private methods.
-        // TODO(lukasza): Revisit this for protected methods.
         return std::nullopt;
     }
+    // Anonymous structs and unions can only have implicit member functions,
+    // and these can't be called from thunks (because the type can't be named
+    // in C++).
+    if (method_decl->getParent()->isAnonymousStructOrUnion()) {
+      return std::nullopt;
+    }
   }
 
   // TODO(lukasza, mboehme): Consider changing the GetLifetimeAnnotations API to
@@ -101,6 +251,24 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
                                     translated_name.status().message()));
   }
 
+  absl::StatusOr<std::optional<std::string>> rs_name =
+      GetAnnotatedRustName(function_decl);
+  if (!rs_name.ok()) {
+    return ictx_.ImportUnsupportedItem(function_decl,
+                                       std::string(rs_name.status().message()));
+  }
+  if (!rs_name->has_value()) {
+    if (const auto* identifier = std::get_if<Identifier>(&*translated_name)) {
+      *rs_name = GetOverloadRustName(ictx_.ctx_, function_decl, *identifier);
+    } else if (const auto* conversion_decl =
+                   clang::dyn_cast<clang::CXXConversionDecl>(function_decl);
+               conversion_decl != nullptr && conversion_decl->isExplicit()) {
+      // Implicit conversions are bound as `From` impls, and explicit ones as
+      // methods.
+      *rs_name = GetExplicitConversionRustName(ictx_.ctx_, conversion_decl);
+    }
+  }
+
   std::vector<FuncParam> params;
   std::set<std::string> errors;
   auto add_error = [&errors](std::string msg) {
@@ -136,13 +304,34 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
     CHECK(lifetimes->IsValidForDecl(function_decl));
   }
 
+  // Rvalue references without lifetimes are taken by value (and the thunk
+  // moves from the value).  This doesn't apply to constructors and operators,
+  // because their bindings are trait impls (e.g. a move constructor taking
+  // `T` by value would conflict with the blanket `impl From<T> for T`).
+  bool takes_rvalue_references_by_move =
+      !clang::isa<clang::CXXConstructorDecl>(function_decl) &&
+      !function_decl->isOverloadedOperator();
+
+  // Only trailing parameters can have default arguments.
+  int num_default_params = 0;
   for (unsigned i = 0; i < function_decl->getNumParams(); ++i) {
     const clang::ParmVarDecl* param = function_decl->getParamDecl(i);
     std::optional<clang::tidy::lifetimes::ValueLifetimes> param_lifetimes;
     if (lifetimes) {
       param_lifetimes = lifetimes->GetParamLifetimes(i);
     }
-    auto param_type = ictx_.ConvertQualType(param->getType(), param_lifetimes);
+    absl::StatusOr<MappedType> param_type;
+    if (takes_rvalue_references_by_move &&
+        param->getType()->isRValueReferenceType() &&
+        !param_lifetimes.has_value()) {
+      param_type = ictx_.ConvertQualType(
+          param->getType().getNonReferenceType(), param_lifetimes);
+      if (param_type.ok()) {
+        param_type = MappedType::RValueReferenceByMove(*std::move(param_type));
+      }
+    } else {
+      param_type = ictx_.ConvertParamType(param->getType(), param_lifetimes);
+    }
     if (!param_type.ok()) {
       add_error(absl::Substitute("Parameter #$0 is not supported: $1", i,
                                  param_type.status().message()));
@@ -152,6 +341,9 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
     std::optional<Identifier> param_name = GetTranslatedParamName(param);
     CHECK(param_name.has_value());  // No known failure cases.
     params.push_back({*param_type, *std::move(param_name)});
+    if (param->hasDefaultArg()) {
+      ++num_default_params;
+    }
   }
 
   if (function_decl->getReturnType()->isUndeducedType()) {
@@ -167,8 +359,8 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
     return_lifetimes = lifetimes->GetReturnLifetimes();
   }
 
-  auto return_type =
-      ictx_.ConvertQualType(function_decl->getReturnType(), return_lifetimes);
+  auto return_type = ictx_.ConvertParamOrReturnType(
+      function_decl->getReturnType(), return_lifetimes);
   if (!return_type.ok()) {
     add_error(absl::StrCat("Return type is not supported: ",
                            return_type.status().message()));
@@ -218,7 +410,26 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
 
     member_func_metadata = MemberFuncMetadata{
         .record_id = GenerateItemId(method_decl->getParent()),
-        .instance_method_metadata = instance_metadata};
+        .instance_method_metadata = instance_metadata,
+        .is_protected = is_protected};
+  }
+
+  // C variadic functions are declared directly in an `extern "C"` block of the
+  // Rust bindings (a C++ thunk can't forward the variadic arguments).
+  bool is_variadic = function_decl->isVariadic();
+  if (is_variadic) {
+    if (clang::isa<clang::CXXMethodDecl>(function_decl)) {
+      add_error("Variadic member functions are not supported");
+    }
+    if (function_decl->isInlined() ||
+        function_decl->isTemplateInstantiation()) {
+      add_error(
+          "Variadic functions that may not be codegenned in the C++ library "
+          "(e.g. inline functions) are not supported");
+    }
+    if (num_default_params > 0) {
+      add_error("Variadic functions with default arguments are not supported");
+    }
   }
 
   if (!errors.empty()) {
@@ -228,11 +439,27 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
   bool has_c_calling_convention =
       function_decl->getType()->getAs<clang::FunctionType>()->getCallConv() ==
       clang::CC_C;
+  // The exception specification of implicitly declared special member
+  // functions is computed lazily, so it may need to be resolved first.
+  const auto* function_proto_type =
+      function_decl->getType()->getAs<clang::FunctionProtoType>();
+  if (function_proto_type != nullptr &&
+      clang::isUnresolvedExceptionSpec(
+          function_proto_type->getExceptionSpecType())) {
+    function_proto_type = ictx_.sema_.ResolveExceptionSpec(
+        function_decl->getLocation(), function_proto_type);
+  }
+  bool is_noexcept =
+      function_proto_type != nullptr && function_proto_type->isNothrow();
   bool is_member_or_descendant_of_class_template =
       IsFullClassTemplateSpecializationOrChild(function_decl);
+  std::optional<std::string> template_args =
+      GetTemplateArgsCcSpelling(ictx_.ctx_, function_decl);
 
   std::optional<std::string> doc_comment = ictx_.GetComment(function_decl);
-  if (!doc_comment.has_value() && is_member_or_descendant_of_class_template) {
+  if (!doc_comment.has_value() &&
+      (is_member_or_descendant_of_class_template ||
+       template_args.has_value())) {
     // Despite `is_member_or_descendant_of_class_template` check above, we are
     // not guaranteed that a `func_pattern` exists below.  For example, it may
     // be missing when `function_decl` is an implicitly defined constructor of a
@@ -258,17 +485,25 @@ std::optional<IR::Item> FunctionDeclImporter::Import(
 
   return Func{
       .name = *translated_name,
+      .rs_name = *std::move(rs_name),
       .owning_target = ictx_.GetOwningTarget(function_decl),
       .doc_comment = std::move(doc_comment),
+      .nodiscard = GetNodiscardMessage(function_decl),
+      .deprecated = GetDeprecationMessage(function_decl),
       .mangled_name = std::move(mangled_name),
       .return_type = *return_type,
       .params = std::move(params),
+      .num_default_params = num_default_params,
       .lifetime_params = std::move(lifetime_params),
       .is_inline = function_decl->isInlined(),
+      .has_internal_linkage = !function_decl->isExternallyVisible(),
       .member_func_metadata = std::move(member_func_metadata),
       .has_c_calling_convention = has_c_calling_convention,
+      .is_noexcept = is_noexcept,
+      .is_variadic = is_variadic,
       .is_member_or_descendant_of_class_template =
           is_member_or_descendant_of_class_template,
+      .template_args = std::move(template_args),
       .source_loc = ictx_.ConvertSourceLocation(function_decl->getBeginLoc()),
       .id = GenerateItemId(function_decl),
       .enclosing_namespace_id = GetEnclosingNamespaceId(function_decl),
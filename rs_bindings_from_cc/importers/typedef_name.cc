@@ -7,6 +7,7 @@
 #include <optional>
 
 #include "absl/log/check.h"
+#include "rs_bindings_from_cc/ast_util.h"
 #include "rs_bindings_from_cc/known_types_map.h"
 #include "clang/AST/ASTContext.h"
 #include "clang/AST/Decl.h"
@@ -67,6 +68,7 @@ std::optional<IR::Item> crubit::TypedefNameDeclImporter::Import(
         .id = GenerateItemId(typedef_name_decl),
         .owning_target = ictx_.GetOwningTarget(typedef_name_decl),
         .doc_comment = ictx_.GetComment(typedef_name_decl),
+        .deprecated = GetDeprecationMessage(typedef_name_decl),
         .underlying_type = *underlying_type,
         .source_loc =
             ictx_.ConvertSourceLocation(typedef_name_decl->getBeginLoc()),
@@ -0,0 +1,161 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "rs_bindings_from_cc/importers/var.h"
+
+#include <optional>
+#include <string>
+#include <variant>
+
+#include "absl/strings/str_cat.h"
+#include "clang/AST/APValue.h"
+#include "clang/AST/Decl.h"
+#include "clang/AST/DeclCXX.h"
+#include "clang/AST/DeclTemplate.h"
+#include "clang/AST/Type.h"
+#include "llvm/ADT/APFloat.h"
+
+namespace crubit {
+
+std::optional<IR::Item> VarDeclImporter::Import(clang::VarDecl* var_decl) {
+  // Local variables and function parameters are not imported.  Neither are
+  // variable template specializations and structured bindings.
+  if (!var_decl->isFileVarDecl() ||
+      llvm::isa<clang::VarTemplateSpecializationDecl>(var_decl) ||
+      llvm::isa<clang::DecompositionDecl>(var_decl)) {
+    return std::nullopt;
+  }
+  clang::QualType type = var_decl->getType();
+  auto* record_decl =
+      llvm::dyn_cast<clang::CXXRecordDecl>(var_decl->getDeclContext());
+  // Mutable global variables are not supported.
+  if (!type.isConstQualified() && record_decl == nullptr) {
+    return std::nullopt;
+  }
+
+  std::optional<ItemId> enclosing_record_id = std::nullopt;
+  if (record_decl != nullptr) {
+    if (record_decl->isDependentContext()) {
+      return std::nullopt;
+    }
+    if (!ictx_.EnsureSuccessfullyImported(record_decl)) {
+      return ictx_.ImportUnsupportedItem(var_decl,
+                                         "Couldn't import the parent");
+    }
+    enclosing_record_id = GenerateItemId(record_decl);
+  }
+
+  absl::StatusOr<Identifier> identifier =
+      ictx_.GetTranslatedIdentifier(var_decl);
+  if (!identifier.ok()) {
+    return ictx_.ImportUnsupportedItem(
+        var_decl, absl::StrCat("Constant name is not supported: ",
+                               identifier.status().message()));
+  }
+
+  const auto* builtin_type = type->getAs<clang::BuiltinType>();
+  bool is_integral_or_floating_point =
+      builtin_type != nullptr &&
+      (builtin_type->isInteger() || builtin_type->isFloatingPoint());
+
+  if (!type.isConstQualified()) {
+    if (!is_integral_or_floating_point) {
+      return ictx_.ImportUnsupportedItem(
+          var_decl,
+          "Only static data members of integral and floating-point types are "
+          "supported");
+    }
+    return ImportStaticDataMember(var_decl, *identifier, *enclosing_record_id);
+  }
+
+  if (!is_integral_or_floating_point) {
+    return ictx_.ImportUnsupportedItem(
+        var_decl,
+        "Only constants of integral and floating-point types are supported");
+  }
+
+  const clang::VarDecl* initializing_decl = nullptr;
+  if (var_decl->getAnyInitializer(initializing_decl) == nullptr) {
+    return ictx_.ImportUnsupportedItem(
+        var_decl, "Constants without an initializer are not supported");
+  }
+  const clang::APValue* evaluated_value = initializing_decl->evaluateValue();
+  if (evaluated_value == nullptr) {
+    return ictx_.ImportUnsupportedItem(
+        var_decl, "The initializer of the constant can't be evaluated");
+  }
+
+  std::optional<std::variant<IntegerConstant, FloatConstant, std::string>>
+      value;
+  if (evaluated_value->isInt()) {
+    const llvm::APSInt& integer = evaluated_value->getInt();
+    if (integer.getSignificantBits() > 64) {
+      return ictx_.ImportUnsupportedItem(
+          var_decl, "Integer constants wider than 64 bits are not supported");
+    }
+    value = IntegerConstant(integer);
+  } else if (evaluated_value->isFloat()) {
+    llvm::APFloat floating_point = evaluated_value->getFloat();
+    bool loses_info = false;
+    floating_point.convert(llvm::APFloat::IEEEdouble(),
+                           llvm::APFloat::rmNearestTiesToEven, &loses_info);
+    if (loses_info || !floating_point.isFinite()) {
+      return ictx_.ImportUnsupportedItem(
+          var_decl,
+          "Only finite floating-point constants that can be represented as "
+          "`double` are supported");
+    }
+    value = FloatConstant(floating_point.convertToDouble());
+  } else {
+    return ictx_.ImportUnsupportedItem(
+        var_decl, "The initializer of the constant can't be evaluated");
+  }
+
+  std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+  absl::StatusOr<MappedType> mapped_type =
+      ictx_.ConvertQualType(type.getUnqualifiedType(), no_lifetimes);
+  if (!mapped_type.ok()) {
+    return ictx_.ImportUnsupportedItem(var_decl,
+                                       mapped_type.status().ToString());
+  }
+
+  return Constant{
+      .identifier = *identifier,
+      .id = GenerateItemId(var_decl),
+      .owning_target = ictx_.GetOwningTarget(var_decl),
+      .doc_comment = ictx_.GetComment(var_decl),
+      .source_loc = ictx_.ConvertSourceLocation(var_decl->getBeginLoc()),
+      .type = *std::move(mapped_type),
+      .value = *std::move(value),
+      .is_macro = false,
+      .enclosing_record_id = enclosing_record_id,
+      .enclosing_namespace_id = GetEnclosingNamespaceId(var_decl),
+  };
+}
+
+std::optional<IR::Item> VarDeclImporter::ImportStaticDataMember(
+    clang::VarDecl* var_decl, const Identifier& identifier,
+    ItemId enclosing_record_id) {
+  std::optional<clang::tidy::lifetimes::ValueLifetimes> no_lifetimes;
+  absl::StatusOr<MappedType> mapped_type =
+      ictx_.ConvertQualType(var_decl->getType(), no_lifetimes);
+  if (!mapped_type.ok()) {
+    return ictx_.ImportUnsupportedItem(var_decl,
+                                       mapped_type.status().ToString());
+  }
+
+  return StaticDataMember{
+      .identifier = identifier,
+      .id = GenerateItemId(var_decl),
+      .owning_target = ictx_.GetOwningTarget(var_decl),
+      .doc_comment = ictx_.GetComment(var_decl),
+      .source_loc = ictx_.ConvertSourceLocation(var_decl->getBeginLoc()),
+      .type = *std::move(mapped_type),
+      .mangled_name = ictx_.GetMangledName(var_decl),
+      .enclosing_record_id = enclosing_record_id,
+      .enclosing_namespace_id = GetEnclosingNamespaceId(var_decl),
+  };
+}
+
+}  // namespace crubit
@@ -136,6 +136,93 @@ MappedType MappedType::RValueReferenceTo(MappedType pointee_type,
                               /*nullable=*/false);
 }
 
+MappedType MappedType::RValueReferenceByMove(MappedType pointee_type) {
+  MappedType by_move_type = {
+      .rs_type = std::move(pointee_type.rs_type),
+      .cc_type = CcType{.name = std::string(internal::kCcRValueRef)},
+  };
+  by_move_type.cc_type.type_args.push_back(std::move(pointee_type.cc_type));
+  return by_move_type;
+}
+
+MappedType MappedType::StdVectorOf(MappedType element_type) {
+  auto vector_type = MappedType::Simple(std::string(internal::kRustStdVector),
+                                        std::string(internal::kCcStdVector));
+  vector_type.rs_type.type_args.push_back(std::move(element_type.rs_type));
+  vector_type.cc_type.type_args.push_back(std::move(element_type.cc_type));
+  return vector_type;
+}
+
+MappedType MappedType::StdOptionalOf(MappedType value_type) {
+  auto optional_type =
+      MappedType::Simple(std::string(internal::kRustOption),
+                         std::string(internal::kCcStdOptional));
+  optional_type.rs_type.type_args.push_back(std::move(value_type.rs_type));
+  optional_type.cc_type.type_args.push_back(std::move(value_type.cc_type));
+  return optional_type;
+}
+
+MappedType MappedType::StdUniquePtrTo(MappedType pointee_type) {
+  auto unique_ptr_type =
+      MappedType::Simple(std::string(internal::kRustStdUniquePtr),
+                         std::string(internal::kCcStdUniquePtr));
+  unique_ptr_type.rs_type.type_args.push_back(std::move(pointee_type.rs_type));
+  unique_ptr_type.cc_type.type_args.push_back(std::move(pointee_type.cc_type));
+  return unique_ptr_type;
+}
+
+MappedType MappedType::StdSharedPtrTo(MappedType pointee_type) {
+  auto shared_ptr_type =
+      MappedType::Simple(std::string(internal::kRustStdSharedPtr),
+                         std::string(internal::kCcStdSharedPtr));
+  shared_ptr_type.rs_type.type_args.push_back(std::move(pointee_type.rs_type));
+  shared_ptr_type.cc_type.type_args.push_back(std::move(pointee_type.cc_type));
+  return shared_ptr_type;
+}
+
+MappedType MappedType::AbslStringViewParam() {
+  return MappedType::Simple(std::string(internal::kRustStrRef),
+                            std::string(internal::kCcAbslStringView));
+}
+
+MappedType MappedType::AbslSpanParamOf(MappedType element_type) {
+  auto span_type = MappedType::Simple(
+      std::string(element_type.cc_type.is_const ? internal::kRustSliceRef
+                                                : internal::kRustMutSliceRef),
+      std::string(internal::kCcAbslSpan));
+  span_type.rs_type.type_args.push_back(std::move(element_type.rs_type));
+  span_type.cc_type.type_args.push_back(std::move(element_type.cc_type));
+  return span_type;
+}
+
+// Returns the MappedType of a Rust closure parameter (see
+// `MappedType::StdFunctionParam`).
+static MappedType ClosureParam(absl::string_view rs_name,
+                               absl::string_view cc_name,
+                               MappedType return_type,
+                               std::vector<MappedType> param_types) {
+  auto closure_type =
+      MappedType::Simple(std::string(rs_name), std::string(cc_name));
+  param_types.push_back(std::move(return_type));
+  for (MappedType& type_arg : param_types) {
+    closure_type.rs_type.type_args.push_back(std::move(type_arg.rs_type));
+    closure_type.cc_type.type_args.push_back(std::move(type_arg.cc_type));
+  }
+  return closure_type;
+}
+
+MappedType MappedType::StdFunctionParam(MappedType return_type,
+                                        std::vector<MappedType> param_types) {
+  return ClosureParam(internal::kRustFn, internal::kCcStdFunction,
+                      std::move(return_type), std::move(param_types));
+}
+
+MappedType MappedType::AbslAnyInvocableParam(
+    MappedType return_type, std::vector<MappedType> param_types) {
+  return ClosureParam(internal::kRustFnMut, internal::kCcAbslAnyInvocable,
+                      std::move(return_type), std::move(param_types));
+}
+
 MappedType MappedType::FuncPtr(absl::string_view cc_call_conv,
                                absl::string_view rs_abi,
                                std::optional<LifetimeId> lifetime,
@@ -214,6 +301,12 @@ llvm::json::Value IntegerConstant::ToJson() const {
   };
 }
 
+llvm::json::Value FloatConstant::ToJson() const {
+  return llvm::json::Object{
+      {"bits", bits_},
+  };
+}
+
 llvm::json::Value Operator::ToJson() const {
   return llvm::json::Object{
       {"name", name_},
@@ -226,6 +319,8 @@ static std::string SpecialNameToString(SpecialName special_name) {
       return "Destructor";
     case SpecialName::kConstructor:
       return "Constructor";
+    case SpecialName::kConversion:
+      return "Conversion";
   }
 }
 
@@ -282,6 +377,7 @@ llvm::json::Value MemberFuncMetadata::ToJson() const {
   return llvm::json::Object{
       {"record_id", record_id},
       {"instance_method_metadata", instance_method_metadata},
+      {"is_protected", is_protected},
   };
 }
 
@@ -300,17 +396,25 @@ llvm::json::Value UseMod::ToJson() const {
 llvm::json::Value Func::ToJson() const {
   llvm::json::Object func{
       {"name", name},
+      {"rs_name", rs_name},
       {"owning_target", owning_target},
       {"doc_comment", doc_comment},
+      {"nodiscard", nodiscard},
+      {"deprecated", deprecated},
       {"mangled_name", mangled_name},
       {"return_type", return_type},
       {"params", params},
+      {"num_default_params", num_default_params},
       {"lifetime_params", lifetime_params},
       {"is_inline", is_inline},
+      {"has_internal_linkage", has_internal_linkage},
       {"member_func_metadata", member_func_metadata},
       {"has_c_calling_convention", has_c_calling_convention},
+      {"is_noexcept", is_noexcept},
+      {"is_variadic", is_variadic},
       {"is_member_or_descendant_of_class_template",
        is_member_or_descendant_of_class_template},
+      {"template_args", template_args},
       {"source_loc", source_loc},
       {"id", id},
       {"enclosing_namespace_id", enclosing_namespace_id},
@@ -415,6 +519,8 @@ llvm::json::Value Record::ToJson() const {
       {"id", id},
       {"owning_target", owning_target},
       {"doc_comment", doc_comment},
+      {"nodiscard", nodiscard},
+      {"deprecated", deprecated},
       {"source_loc", source_loc},
       {"unambiguous_public_bases", unambiguous_public_bases},
       {"fields", fields},
@@ -430,10 +536,13 @@ llvm::json::Value Record::ToJson() const {
       {"is_trivial_abi", is_trivial_abi},
       {"is_inheritable", is_inheritable},
       {"is_abstract", is_abstract},
+      {"has_private_virtual_methods", has_private_virtual_methods},
       {"record_type", RecordTypeToString(record_type)},
       {"is_aggregate", is_aggregate},
       {"is_anon_record_with_typedef", is_anon_record_with_typedef},
+      {"is_anonymous_struct_or_union", is_anonymous_struct_or_union},
       {"child_item_ids", std::move(json_item_ids)},
+      {"enclosing_record_id", enclosing_record_id},
       {"enclosing_namespace_id", enclosing_namespace_id},
   };
 
@@ -445,6 +554,8 @@ llvm::json::Value Record::ToJson() const {
 llvm::json::Value Enumerator::ToJson() const {
   return llvm::json::Object{
       {"identifier", identifier},
+      {"doc_comment", doc_comment},
+      {"deprecated", deprecated},
       {"value", value},
   };
 }
@@ -454,9 +565,14 @@ llvm::json::Value Enum::ToJson() const {
       {"identifier", identifier},
       {"id", id},
       {"owning_target", owning_target},
+      {"doc_comment", doc_comment},
+      {"nodiscard", nodiscard},
+      {"deprecated", deprecated},
       {"source_loc", source_loc},
       {"underlying_type", underlying_type},
       {"enumerators", enumerators},
+      {"is_scoped", is_scoped},
+      {"is_closed", is_closed},
       {"enclosing_namespace_id", enclosing_namespace_id},
   };
 
@@ -465,12 +581,58 @@ llvm::json::Value Enum::ToJson() const {
   };
 }
 
+llvm::json::Value Constant::ToJson() const {
+  llvm::json::Object value_json;
+  if (const auto* integer = std::get_if<IntegerConstant>(&value)) {
+    value_json["Integer"] = integer->ToJson();
+  } else if (const auto* floating_point = std::get_if<FloatConstant>(&value)) {
+    value_json["Float"] = floating_point->ToJson();
+  } else {
+    value_json["String"] = std::get<std::string>(value);
+  }
+  llvm::json::Object constant{
+      {"identifier", identifier},
+      {"id", id},
+      {"owning_target", owning_target},
+      {"doc_comment", doc_comment},
+      {"source_loc", source_loc},
+      {"type", type},
+      {"value", std::move(value_json)},
+      {"is_macro", is_macro},
+      {"enclosing_record_id", enclosing_record_id},
+      {"enclosing_namespace_id", enclosing_namespace_id},
+  };
+
+  return llvm::json::Object{
+      {"Constant", std::move(constant)},
+  };
+}
+
+llvm::json::Value StaticDataMember::ToJson() const {
+  llvm::json::Object static_data_member{
+      {"identifier", identifier},
+      {"id", id},
+      {"owning_target", owning_target},
+      {"doc_comment", doc_comment},
+      {"source_loc", source_loc},
+      {"type", type},
+      {"mangled_name", mangled_name},
+      {"enclosing_record_id", enclosing_record_id},
+      {"enclosing_namespace_id", enclosing_namespace_id},
+  };
+
+  return llvm::json::Object{
+      {"StaticDataMember", std::move(static_data_member)},
+  };
+}
+
 llvm::json::Value TypeAlias::ToJson() const {
   llvm::json::Object type_alias{
       {"identifier", identifier},
       {"id", id},
       {"owning_target", owning_target},
       {"doc_comment", doc_comment},
+      {"deprecated", deprecated},
       {"underlying_type", underlying_type},
       {"source_loc", source_loc},
       {"enclosing_record_id", enclosing_record_id},
@@ -31,7 +31,9 @@
 #include "clang/AST/DeclBase.h"
 #include "clang/AST/DeclTemplate.h"
 #include "clang/AST/RawCommentList.h"
+#include "clang/Lex/MacroInfo.h"
 #include "llvm/ADT/APSInt.h"
+#include "llvm/ADT/bit.h"
 #include "llvm/Support/FormatVariadic.h"
 #include "llvm/Support/JSON.h"
 
@@ -52,11 +54,43 @@ inline constexpr absl::string_view kRustRvalueRefConst =
 // Function pointers.
 inline constexpr absl::string_view kRustFuncPtr = "#funcPtr";
 
+// `std::vector<T>` (element type stored in `type_args[0]`).
+inline constexpr absl::string_view kRustStdVector = "::cc_std::Vector";
+
+// `std::optional<T>` (value type stored in `type_args[0]`).
+inline constexpr absl::string_view kRustOption = "::core::option::Option";
+
+// `std::unique_ptr<T>` (pointee type stored in `type_args[0]`).
+inline constexpr absl::string_view kRustStdUniquePtr = "::cc_std::UniquePtr";
+
+// `std::shared_ptr<T>` (pointee type stored in `type_args[0]`).
+inline constexpr absl::string_view kRustStdSharedPtr = "::cc_std::SharedPtr";
+
+// `absl::string_view` and `absl::Span<T>` parameters (element type stored in
+// `type_args[0]` of the slices).
+inline constexpr absl::string_view kRustStrRef = "&str";
+inline constexpr absl::string_view kRustSliceRef = "&[]";
+inline constexpr absl::string_view kRustMutSliceRef = "&mut []";
+
+// `std::function<R(Args...)>` and `absl::AnyInvocable<R(Args...)>` parameters
+// (parameter types stored in the leading `type_args`, and the return type in
+// the last one - like for function pointers).
+inline constexpr absl::string_view kRustFn = "impl Fn";
+inline constexpr absl::string_view kRustFnMut = "impl FnMut";
+
 // C++ types therein.
 inline constexpr absl::string_view kCcPtr = "*";
 inline constexpr absl::string_view kCcLValueRef = "&";
 inline constexpr absl::string_view kCcRValueRef = "&&";
 inline constexpr absl::string_view kCcFuncValue = "#funcValue";
+inline constexpr absl::string_view kCcStdVector = "std::vector";
+inline constexpr absl::string_view kCcStdOptional = "std::optional";
+inline constexpr absl::string_view kCcStdUniquePtr = "std::unique_ptr";
+inline constexpr absl::string_view kCcStdSharedPtr = "std::shared_ptr";
+inline constexpr absl::string_view kCcAbslStringView = "absl::string_view";
+inline constexpr absl::string_view kCcAbslSpan = "absl::Span";
+inline constexpr absl::string_view kCcStdFunction = "std::function";
+inline constexpr absl::string_view kCcAbslAnyInvocable = "absl::AnyInvocable";
 
 inline constexpr int kJsonIndent = 2;
 }  // namespace internal
@@ -107,6 +141,10 @@ inline ItemId GenerateItemId(const clang::RawComment* comment) {
   return ItemId(reinterpret_cast<uintptr_t>(comment));
 }
 
+inline ItemId GenerateItemId(const clang::MacroInfo* macro_info) {
+  return ItemId(reinterpret_cast<uintptr_t>(macro_info));
+}
+
 // Returns the ID of the parent namespace, if such exists, and `std::nullopt`
 // for top level decls. We use this function to assign a parent namespace to all
 // the IR items.
@@ -154,6 +192,10 @@ struct CcType {
   // - "int32_t", "std::ptrdiff_t", "long long", "bool"
   // - "void"
   // - "&" or "*" (pointee stored in `type_args[0]`)
+  // - "std::vector" (element type stored in `type_args[0]`)
+  // - "std::optional" (value type stored in `type_args[0]`)
+  // - "std::unique_ptr" (pointee type stored in `type_args[0]`)
+  // - "std::shared_ptr" (pointee type stored in `type_args[0]`)
   // - "#funcValue <callConv>" (compare with "#funcPtr <abi>" in RsType::name
   //   and note that Rust only supports function pointers; note that <callConv>
   //   in CcType doesn't map 1:1 to <abi> in RsType).
@@ -188,6 +230,11 @@ struct RsType {
   // - "i32" or "bool"
   // - "()" (the unit type, equivalent of "void" in CcType)
   // - "&", "&mut", "*const", "*mut" (pointee stored in `type_args[0]`)
+  // - "::cc_std::Vector" (element type stored in `type_args[0]`)
+  // - "::core::option::Option" (`std::optional`, value type stored in
+  //   `type_args[0]`)
+  // - "::cc_std::UniquePtr" (pointee type stored in `type_args[0]`)
+  // - "::cc_std::SharedPtr" (pointee type stored in `type_args[0]`)
   // - "Option" (e.g. representing nullable, lifetime-annotated C++ pointer as
   //   `Option<&'a SomeOtherType>` - in this case `type_args[0]` is the generic
   //    argument representing the Rust reference type).
@@ -258,6 +305,58 @@ struct MappedType {
   static MappedType RValueReferenceTo(MappedType pointee_type,
                                       LifetimeId lifetime);
 
+  // Creates the mapped type of an rvalue reference parameter (`T&&`) that has
+  // no lifetime.  Such a parameter is spelled as `T` in Rust: the Rust caller
+  // gives up the ownership of the value, and the generated thunk materializes
+  // the rvalue (by moving from the value).
+  static MappedType RValueReferenceByMove(MappedType pointee_type);
+
+  // Returns the MappedType for `std::vector<T>` (with the default allocator),
+  // which is spelled as `::cc_std::Vector<T>` in Rust.
+  static MappedType StdVectorOf(MappedType element_type);
+
+  // Returns the MappedType for `std::optional<T>`, which is spelled as
+  // `::core::option::Option<T>` in Rust.  The two types have different
+  // layouts, so the generated thunks convert between them.
+  static MappedType StdOptionalOf(MappedType value_type);
+
+  // Returns the MappedType for `std::unique_ptr<T>` (with the default
+  // deleter), which is spelled as `::cc_std::UniquePtr<T>` in Rust.  The
+  // generated thunks transfer the ownership of the pointee between the two.
+  static MappedType StdUniquePtrTo(MappedType pointee_type);
+
+  // Returns the MappedType for `std::shared_ptr<T>`, which is spelled as
+  // `::cc_std::SharedPtr<T>` in Rust.  The generated thunks pass the
+  // `std::shared_ptr` between C++ and Rust as an owning pointer to a
+  // heap-allocated copy of it.
+  static MappedType StdSharedPtrTo(MappedType pointee_type);
+
+  // Returns the MappedType of an `absl::string_view` parameter, which is
+  // spelled as `&str` in Rust.  The generated thunks pass the string between
+  // Rust and C++ as a pointer and a length.
+  static MappedType AbslStringViewParam();
+
+  // Returns the MappedType of an `absl::Span<T>` parameter, which is spelled
+  // as `&[T]` in Rust if `T` is const-qualified, and as `&mut [T]` otherwise.
+  // The generated thunks pass the elements between Rust and C++ as a pointer
+  // and a length.
+  static MappedType AbslSpanParamOf(MappedType element_type);
+
+  // Returns the MappedType of a `std::function<R(Args...)>` parameter, which
+  // is spelled as `impl Fn(Args...) -> R + Send + Sync + 'static` in Rust.
+  // The generated bindings box the Rust closure, and pass it to the C++ thunk
+  // together with a trampoline that calls it.  (`std::function` is copyable,
+  // and so its copies share the boxed closure.)
+  static MappedType StdFunctionParam(MappedType return_type,
+                                     std::vector<MappedType> param_types);
+
+  // Returns the MappedType of an `absl::AnyInvocable<R(Args...)>` parameter,
+  // which is spelled as `impl FnMut(Args...) -> R + Send + 'static` in Rust.
+  // The closure is passed to C++ the same way as for
+  // `MappedType::StdFunctionParam`.
+  static MappedType AbslAnyInvocableParam(MappedType return_type,
+                                          std::vector<MappedType> param_types);
+
   static MappedType FuncPtr(absl::string_view cc_call_conv,
                             absl::string_view rs_abi,
                             std::optional<LifetimeId> lifetime,
@@ -342,6 +441,22 @@ class IntegerConstant {
   uint64_t wrapped_value_;
 };
 
+// A floating-point value, represented by the bit pattern of the equivalent
+// `double`. This is intended to be used to produce floating-point literals in
+// Rust code while specifying the type out-of-band.
+class FloatConstant {
+ public:
+  explicit FloatConstant(double value)
+      : bits_(llvm::bit_cast<uint64_t>(value)) {}
+  FloatConstant(const FloatConstant& other) = default;
+  FloatConstant& operator=(const FloatConstant& other) = default;
+
+  llvm::json::Value ToJson() const;
+
+ private:
+  uint64_t bits_;
+};
+
 class Operator {
  public:
   explicit Operator(std::string name) : name_(std::move(name)) {
@@ -382,6 +497,9 @@ inline std::ostream& operator<<(std::ostream& o, const FuncParam& param) {
 enum SpecialName {
   kDestructor,
   kConstructor,
+  // A user-defined conversion function (e.g. `operator int()`).  The target
+  // type of the conversion is the return type of the function.
+  kConversion,
 };
 
 std::ostream& operator<<(std::ostream& o, const SpecialName& special_name);
@@ -422,6 +540,11 @@ struct MemberFuncMetadata {
   //
   // If null, this is a static method.
   std::optional<InstanceMethodMetadata> instance_method_metadata;
+
+  // Whether this is a protected member function.  Protected virtual methods
+  // and default constructors are only imported so that Rust code can override
+  // the virtual methods of the record (they can't be called from the thunks).
+  bool is_protected = false;
 };
 
 // A function involved in the bindings.
@@ -429,17 +552,44 @@ struct Func {
   llvm::json::Value ToJson() const;
 
   UnqualifiedIdentifier name;
+  // If present, the Rust name of the function (used instead of `name`).  This
+  // comes either from a `[[clang::annotate("crubit_rust_name", "...")]]`
+  // attribute, or is derived from the parameter types of an overloaded
+  // function (e.g. `Overload_int` for `Overload(int)`) or from the target type
+  // of an `explicit` conversion function (e.g. `to_int` for
+  // `explicit operator int()`).
+  std::optional<std::string> rs_name;
   BazelLabel owning_target;
   std::optional<std::string> doc_comment;
+  // If present, the C++ declaration is `[[nodiscard]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> nodiscard;
+  // If present, the C++ declaration is `[[deprecated]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> deprecated;
   std::string mangled_name;
   MappedType return_type;
   std::vector<FuncParam> params;
+  // Number of trailing `params` that have default arguments.
+  int num_default_params = 0;
   std::vector<LifetimeName> lifetime_params;
   bool is_inline;
+  // True if the function has internal linkage (e.g. a `static` function defined
+  // in a header), which means that the C++ library doesn't export a symbol for
+  // it.
+  bool has_internal_linkage = false;
   // If null, this is not a member function.
   std::optional<MemberFuncMetadata> member_func_metadata;
   bool has_c_calling_convention = true;
+  // True if the function can't throw C++ exceptions (e.g. if it is declared
+  // `noexcept`).
+  bool is_noexcept = false;
+  // True if the function is a C variadic function (e.g. `printf`).
+  bool is_variadic = false;
   bool is_member_or_descendant_of_class_template = false;
+  // If present, this is an explicit instantiation of a function template and
+  // this is the C++ spelling of its template arguments (e.g. `<int, float>`).
+  std::optional<std::string> template_args;
   std::string source_loc;
   ItemId id;
   std::optional<ItemId> enclosing_namespace_id;
@@ -557,6 +707,12 @@ struct Record {
   ItemId id;
   BazelLabel owning_target;
   std::optional<std::string> doc_comment;
+  // If present, the C++ declaration is `[[nodiscard]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> nodiscard;
+  // If present, the C++ declaration is `[[deprecated]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> deprecated;
   std::string source_loc;
   std::vector<BaseClass> unambiguous_public_bases;
   std::vector<Field> fields;
@@ -609,6 +765,10 @@ struct Record {
   // Whether this type is abstract.
   bool is_abstract = false;
 
+  // Whether this type declares virtual methods (other than the destructor)
+  // that are private.  Such methods can't be overridden from Rust.
+  bool has_private_virtual_methods = false;
+
   // Whether this `Record` corresponds to a C++ `union`, `struct`, or `class`.
   RecordType record_type;
 
@@ -622,12 +782,20 @@ struct Record {
   // It is an anoymous record with a typedef name.
   bool is_anon_record_with_typedef = false;
 
+  // It is an anonymous struct or union member (e.g. `struct { int x; };`)
+  // of the enclosing record.  Such records have a synthetic name and can't
+  // be named in C++.
+  bool is_anonymous_struct_or_union = false;
+
   // True when this record is created from an explicit class template
   // instantiation definition (which is also what cc_template!{} macro results
   // in).
   bool is_explicit_class_template_instantiation_definition = false;
 
   std::vector<ItemId> child_item_ids;
+  // The record that this record is nested in (e.g. `Outer` for
+  // `Outer::Inner`), if any.
+  std::optional<ItemId> enclosing_record_id;
   std::optional<ItemId> enclosing_namespace_id;
 };
 
@@ -646,6 +814,10 @@ struct Enumerator {
   llvm::json::Value ToJson() const;
 
   Identifier identifier;
+  std::optional<std::string> doc_comment;
+  // If present, the C++ enumerator is `[[deprecated]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> deprecated;
   IntegerConstant value;
 };
 
@@ -655,9 +827,68 @@ struct Enum {
   Identifier identifier;
   ItemId id;
   BazelLabel owning_target;
+  std::optional<std::string> doc_comment;
+  // If present, the C++ declaration is `[[nodiscard]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> nodiscard;
+  // If present, the C++ declaration is `[[deprecated]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> deprecated;
   std::string source_loc;
   MappedType underlying_type;
   std::vector<Enumerator> enumerators;
+  // True for scoped enumerations (i.e. `enum class` and `enum struct`).
+  bool is_scoped;
+  // True for scoped enumerations annotated with
+  // `[[clang::annotate("crubit_closed_enum")]]`, which promise that values of
+  // the enum are always one of its enumerators.  Such enums are bound as Rust
+  // `enum`s (rather than as newtypes with associated constants).
+  bool is_closed = false;
+  std::optional<ItemId> enclosing_namespace_id;
+};
+
+// A compile-time constant: a namespace-scope `constexpr` or `const` variable,
+// or a `static constexpr` (or `static const`) data member, with a constant
+// initializer of an integral or floating-point type.  Object-like macros that
+// expand to a numeric or string literal (e.g. `#define MAX_LEN 128`) are also
+// imported as constants.
+struct Constant {
+  llvm::json::Value ToJson() const;
+
+  Identifier identifier;
+  ItemId id;
+  BazelLabel owning_target;
+  std::optional<std::string> doc_comment;
+  std::string source_loc;
+  MappedType type;
+  // The evaluated value of the constant (`bool` and character types are
+  // represented as integers).  String literals are represented by their
+  // contents (without the terminating NUL), and their `type` is
+  // `const char*`.
+  std::variant<IntegerConstant, FloatConstant, std::string> value;
+  // True if the constant is defined by an object-like macro.
+  bool is_macro = false;
+  std::optional<ItemId> enclosing_record_id;
+  std::optional<ItemId> enclosing_namespace_id;
+};
+
+// A mutable (i.e. non-`const`) static data member of an integral or
+// floating-point type.  Static data members are bound as accessor functions
+// of the enclosing record (their values can change at runtime, so they can't
+// be bound as Rust constants).
+struct StaticDataMember {
+  llvm::json::Value ToJson() const;
+
+  Identifier identifier;
+  ItemId id;
+  BazelLabel owning_target;
+  std::optional<std::string> doc_comment;
+  std::string source_loc;
+  MappedType type;
+  // The mangled name of the static data member, used to build unique names
+  // for the accessor thunks.
+  std::string mangled_name;
+  ItemId enclosing_record_id;
   std::optional<ItemId> enclosing_namespace_id;
 };
 
@@ -673,6 +904,9 @@ struct TypeAlias {
   ItemId id;
   BazelLabel owning_target;
   std::optional<std::string> doc_comment;
+  // If present, the C++ declaration is `[[deprecated]]` (and this is the
+  // possibly empty message of the attribute).
+  std::optional<std::string> deprecated;
   MappedType underlying_type;
   std::string source_loc;
   std::optional<ItemId> enclosing_record_id;
@@ -772,7 +1006,8 @@ struct IR {
   BazelLabel current_target;
 
   using Item = std::variant<Func, Record, IncompleteRecord, Enum, TypeAlias,
-                            UnsupportedItem, Comment, Namespace, UseMod>;
+                            Constant, StaticDataMember, UnsupportedItem,
+                            Comment, Namespace, UseMod>;
   std::vector<Item> items;
   std::vector<ItemId> top_level_item_ids;
   // Empty string signals that the bindings should be generated in the crate
@@ -7,6 +7,7 @@
 //! information.
 
 use arc_anyhow::{anyhow, bail, Context, Error, Result};
+pub use crubit_feature::CrubitFeature;
 use once_cell::unsync::OnceCell;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
@@ -212,6 +213,20 @@ pub struct IntegerConstant {
     pub wrapped_value: u64,
 }
 
+/// A floating-point value, represented by the bit pattern of the equivalent
+/// `f64` (so that `FloatConstant` can implement `Eq` and `Hash`).
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FloatConstant {
+    pub bits: u64,
+}
+
+impl FloatConstant {
+    pub fn value(&self) -> f64 {
+        f64::from_bits(self.bits)
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Operator {
@@ -286,6 +301,9 @@ pub enum UnqualifiedIdentifier {
     Operator(Operator),
     Constructor,
     Destructor,
+    /// A user-defined conversion function (e.g. `operator int()`), converting
+    /// to the return type of the function.
+    Conversion,
 }
 
 impl UnqualifiedIdentifier {
@@ -304,6 +322,7 @@ impl fmt::Debug for UnqualifiedIdentifier {
             UnqualifiedIdentifier::Operator(op) => fmt::Debug::fmt(op, f),
             UnqualifiedIdentifier::Constructor => f.write_str("Constructor"),
             UnqualifiedIdentifier::Destructor => f.write_str("Destructor"),
+            UnqualifiedIdentifier::Conversion => f.write_str("Conversion"),
         }
     }
 }
@@ -328,6 +347,8 @@ pub struct InstanceMethodMetadata {
 pub struct MemberFuncMetadata {
     pub record_id: ItemId,
     pub instance_method_metadata: Option<InstanceMethodMetadata>,
+    /// Whether the member function is protected (see `is_protected` in ir.h).
+    pub is_protected: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
@@ -342,11 +363,22 @@ pub struct FuncParam {
 #[serde(deny_unknown_fields)]
 pub struct Func {
     pub name: UnqualifiedIdentifier,
+    /// Rust name of the function, if different from `name` (e.g. to
+    /// disambiguate overloads).
+    pub rs_name: Option<Rc<str>>,
     pub owning_target: BazelLabel,
     pub mangled_name: Rc<str>,
     pub doc_comment: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[nodiscard]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub nodiscard: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[deprecated]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub deprecated: Option<Rc<str>>,
     pub return_type: MappedType,
     pub params: Vec<FuncParam>,
+    /// Number of trailing `params` that have default arguments.
+    pub num_default_params: usize,
     /// For tests and internal use only.
     ///
     /// Prefer to reconstruct the lifetime params from the parameter types, as
@@ -354,9 +386,21 @@ pub struct Func {
     /// not originally part of the IR.
     pub lifetime_params: Vec<LifetimeName>,
     pub is_inline: bool,
+    /// True if the function has internal linkage (e.g. a `static` function
+    /// defined in a header), which means that the C++ library doesn't export a
+    /// symbol for it.
+    pub has_internal_linkage: bool,
     pub member_func_metadata: Option<MemberFuncMetadata>,
     pub has_c_calling_convention: bool,
+    /// True if the function can't throw C++ exceptions (e.g. if it is declared
+    /// `noexcept`).
+    pub is_noexcept: bool,
+    /// True if the function is a C variadic function (e.g. `printf`).
+    pub is_variadic: bool,
     pub is_member_or_descendant_of_class_template: bool,
+    /// C++ spelling of the template arguments of an explicit instantiation of
+    /// a function template (e.g. `<int, float>`).
+    pub template_args: Option<Rc<str>>,
     pub source_loc: Rc<str>,
     pub id: ItemId,
     pub enclosing_namespace_id: Option<ItemId>,
@@ -449,6 +493,12 @@ pub struct Record {
     pub id: ItemId,
     pub owning_target: BazelLabel,
     pub doc_comment: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[nodiscard]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub nodiscard: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[deprecated]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub deprecated: Option<Rc<str>>,
     pub source_loc: Rc<str>,
     pub unambiguous_public_bases: Vec<BaseClass>,
     pub fields: Vec<Field>,
@@ -464,10 +514,15 @@ pub struct Record {
     pub is_trivial_abi: bool,
     pub is_inheritable: bool,
     pub is_abstract: bool,
+    /// Whether the record declares virtual methods (other than the destructor)
+    /// that are private, and therefore can't be overridden from Rust.
+    pub has_private_virtual_methods: bool,
     pub record_type: RecordType,
     pub is_aggregate: bool,
     pub is_anon_record_with_typedef: bool,
+    pub is_anonymous_struct_or_union: bool,
     pub child_item_ids: Vec<ItemId>,
+    pub enclosing_record_id: Option<ItemId>,
     pub enclosing_namespace_id: Option<ItemId>,
 }
 
@@ -516,9 +571,19 @@ pub struct Enum {
     pub identifier: Identifier,
     pub id: ItemId,
     pub owning_target: BazelLabel,
+    pub doc_comment: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[nodiscard]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub nodiscard: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[deprecated]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub deprecated: Option<Rc<str>>,
     pub source_loc: Rc<str>,
     pub underlying_type: MappedType,
     pub enumerators: Vec<Enumerator>,
+    pub is_scoped: bool,
+    /// Whether the enum is annotated as closed (see `is_closed` in ir.h).
+    pub is_closed: bool,
     pub enclosing_namespace_id: Option<ItemId>,
 }
 
@@ -526,9 +591,53 @@ pub struct Enum {
 #[serde(deny_unknown_fields)]
 pub struct Enumerator {
     pub identifier: Identifier,
+    pub doc_comment: Option<Rc<str>>,
+    /// If present, the C++ enumerator is `[[deprecated]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub deprecated: Option<Rc<str>>,
     pub value: IntegerConstant,
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
+pub enum ConstantValue {
+    Integer(IntegerConstant),
+    Float(FloatConstant),
+    /// The contents of a string literal (without the terminating NUL).
+    String(Rc<str>),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Constant {
+    pub identifier: Identifier,
+    pub id: ItemId,
+    pub owning_target: BazelLabel,
+    pub doc_comment: Option<Rc<str>>,
+    pub source_loc: Rc<str>,
+    #[serde(rename(deserialize = "type"))]
+    pub type_: MappedType,
+    pub value: ConstantValue,
+    pub is_macro: bool,
+    pub enclosing_record_id: Option<ItemId>,
+    pub enclosing_namespace_id: Option<ItemId>,
+}
+
+/// A mutable static data member of an integral or floating-point type.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StaticDataMember {
+    pub identifier: Identifier,
+    pub id: ItemId,
+    pub owning_target: BazelLabel,
+    pub doc_comment: Option<Rc<str>>,
+    pub source_loc: Rc<str>,
+    #[serde(rename(deserialize = "type"))]
+    pub type_: MappedType,
+    pub mangled_name: Rc<str>,
+    pub enclosing_record_id: ItemId,
+    pub enclosing_namespace_id: Option<ItemId>,
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct TypeAlias {
@@ -536,6 +645,9 @@ pub struct TypeAlias {
     pub id: ItemId,
     pub owning_target: BazelLabel,
     pub doc_comment: Option<Rc<str>>,
+    /// If present, the C++ declaration is `[[deprecated]]` (and this is the
+    /// possibly empty message of the attribute).
+    pub deprecated: Option<Rc<str>>,
     pub underlying_type: MappedType,
     pub source_loc: Rc<str>,
     pub enclosing_record_id: Option<ItemId>,
@@ -639,6 +751,8 @@ pub enum Item {
     Record(Rc<Record>),
     Enum(Rc<Enum>),
     TypeAlias(Rc<TypeAlias>),
+    Constant(Rc<Constant>),
+    StaticDataMember(Rc<StaticDataMember>),
     UnsupportedItem(Rc<UnsupportedItem>),
     Comment(Rc<Comment>),
     Namespace(Rc<Namespace>),
@@ -653,6 +767,8 @@ impl Item {
             Item::Record(record) => record.id,
             Item::Enum(enum_) => enum_.id,
             Item::TypeAlias(type_alias) => type_alias.id,
+            Item::Constant(constant) => constant.id,
+            Item::StaticDataMember(member) => member.id,
             Item::UnsupportedItem(unsupported) => unsupported.id,
             Item::Comment(comment) => comment.id,
             Item::Namespace(namespace) => namespace.id,
@@ -667,6 +783,8 @@ impl Item {
             Item::Func(func) => func.enclosing_namespace_id,
             Item::Namespace(namespace) => namespace.enclosing_namespace_id,
             Item::TypeAlias(type_alias) => type_alias.enclosing_namespace_id,
+            Item::Constant(constant) => constant.enclosing_namespace_id,
+            Item::StaticDataMember(member) => member.enclosing_namespace_id,
             Item::Comment(..) => None,
             Item::UnsupportedItem(..) => None,
             Item::UseMod(..) => None,
@@ -687,6 +805,8 @@ impl Item {
             Item::Record(record) => Some(&record.owning_target),
             Item::Enum(e) => Some(&e.owning_target),
             Item::TypeAlias(type_alias) => Some(&type_alias.owning_target),
+            Item::Constant(constant) => Some(&constant.owning_target),
+            Item::StaticDataMember(member) => Some(&member.owning_target),
             Item::UnsupportedItem(..) => None,
             Item::Comment(..) => None,
             Item::Namespace(..) => None,
@@ -751,33 +871,6 @@ impl<'a> TryFrom<&'a Item> for &'a Rc<Comment> {
     }
 }
 
-flagset::flags! {
-    pub enum CrubitFeature : u8 {
-        Supported,
-        /// Experimental is never *set* without also setting Supported, but we allow it to be
-        /// *required* without also requiring Supported, so that error messages can be more direct.
-        Experimental,
-    }
-}
-
-impl CrubitFeature {
-    /// The name of this feature.
-    pub fn short_name(&self) -> &'static str {
-        match self {
-            Self::Supported => "supported",
-            Self::Experimental => "experimental",
-        }
-    }
-
-    /// The aspect hint required to enable this feature.
-    pub fn aspect_hint(&self) -> &'static str {
-        match self {
-            Self::Supported => "//third_party/crubit:supported",
-            Self::Experimental => "//third_party/crubit:experimental",
-        }
-    }
-}
-
 /// A newtype around a flagset of features, so that it can be deserialized from
 /// an array of strings instead of an integer.
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
@@ -790,15 +883,11 @@ impl<'de> serde::Deserialize<'de> for CrubitFeaturesIR {
     {
         let mut features = flagset::FlagSet::<CrubitFeature>::default();
         for feature in <Vec<String> as serde::Deserialize<'de>>::deserialize(deserializer)? {
-            features |= match &*feature {
-                "experimental" => CrubitFeature::Experimental,
-                "supported" => CrubitFeature::Supported,
-                other => {
-                    return Err(<D::Error as serde::de::Error>::custom(format!(
-                        "Unexpected Crubit feature: {other}"
-                    )));
-                }
-            };
+            features |= CrubitFeature::from_short_name(&feature).ok_or_else(|| {
+                <D::Error as serde::de::Error>::custom(format!(
+                    "Unexpected Crubit feature: {feature}"
+                ))
+            })?;
         }
         Ok(CrubitFeaturesIR(features))
     }
@@ -1068,6 +1157,7 @@ mod tests {
         );
         assert_eq!(format!("{:?}", UnqualifiedIdentifier::Constructor), "Constructor");
         assert_eq!(format!("{:?}", UnqualifiedIdentifier::Destructor), "Destructor");
+        assert_eq!(format!("{:?}", UnqualifiedIdentifier::Conversion), "Conversion");
     }
 
     #[test]
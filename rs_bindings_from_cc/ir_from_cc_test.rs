@@ -20,9 +20,12 @@ fn test_function() {
         quote! {
             Func {
                 name: "f",
+                rs_name: None,
                 owning_target: BazelLabel("//test:testing_target"),
                 mangled_name: "_Z1fii",
                 doc_comment: None,
+                nodiscard: None,
+                deprecated: None,
                 return_type: MappedType {
                     rs_type: RsType {
                         name: Some("i32"),
@@ -73,11 +76,16 @@ fn test_function() {
                         identifier: "b",
                     },
                 ],
+                num_default_params: 0,
                 lifetime_params: [],
                 is_inline: false,
+                has_internal_linkage: false,
                 member_func_metadata: None,
                 has_c_calling_convention: true,
+                is_noexcept: false,
+                is_variadic: false,
                 is_member_or_descendant_of_class_template: false,
+                template_args: None,
                 source_loc: "Generated from: google3/ir_from_cc_virtual_header.h;l=3",
                 id: ItemId(...),
                 enclosing_namespace_id: None,
@@ -172,6 +180,68 @@ fn test_unescapable_rust_keywords_in_enumerator_name() {
     );
 }
 
+#[test]
+fn test_enum_is_scoped() {
+    let ir = ir_from_cc("enum Unscoped { kA }; enum class Scoped { kB };").unwrap();
+    assert_ir_matches!(ir, quote! { Enum { identifier: "Unscoped", ... is_scoped: false, ... } });
+    assert_ir_matches!(ir, quote! { Enum { identifier: "Scoped", ... is_scoped: true, ... } });
+}
+
+#[test]
+fn test_enum_is_closed() {
+    let ir = ir_from_cc(
+        r#"
+        enum class Open { kA };
+        enum class [[clang::annotate("crubit_closed_enum")]] Closed { kB };
+        enum [[clang::annotate("crubit_closed_enum")]] Unscoped { kC };"#,
+    )
+    .unwrap();
+    assert_ir_matches!(ir, quote! { Enum { identifier: "Open", ... is_closed: false, ... } });
+    assert_ir_matches!(ir, quote! { Enum { identifier: "Closed", ... is_closed: true, ... } });
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "Unscoped", ...
+          message: "The `crubit_closed_enum` annotation is only supported for scoped enums (`enum class`)"
+          ...
+        }}
+    );
+}
+
+#[test]
+fn test_record_with_rust_type_annotation() {
+    let ir = ir_from_cc(
+        r#"
+        struct [[clang::annotate("crubit_rust_type", "::rust_crate::Point")]] Point {
+          int x;
+        };
+        void TakesPoint(const Point* p);"#,
+    )
+    .unwrap();
+    assert_ir_not_matches!(ir, quote! { Record { rs_name: "Point", ... } });
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "TakesPoint", ...
+                params: [
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType {
+                                name: Some("*const") ...
+                                type_args: [RsType {
+                                    name: Some("::rust_crate::Point") ...
+                                    decl_id: None,
+                                }] ...
+                            } ...
+                        } ...
+                    }
+                ] ...
+            }
+        }
+    );
+}
+
 #[test]
 fn test_unescapable_rust_keywords_in_anonymous_struct_type_alias() {
     let ir = ir_from_cc("typedef struct { int field; } Self;").unwrap();
@@ -258,6 +328,107 @@ fn test_function_with_custom_calling_convention() {
     );
 }
 
+#[test]
+fn test_function_is_noexcept() {
+    let ir = ir_from_cc(
+        "void MayThrow();
+        void NeverThrows() noexcept;
+        struct SomeStruct final {
+          ~SomeStruct();
+        };",
+    )
+    .unwrap();
+    assert_ir_matches!(ir, quote! { Func { name: "MayThrow", ... is_noexcept: false, ... } });
+    assert_ir_matches!(ir, quote! { Func { name: "NeverThrows", ... is_noexcept: true, ... } });
+    // Destructors are implicitly `noexcept`.
+    assert_ir_matches!(ir, quote! { Func { name: Destructor, ... is_noexcept: true, ... } });
+}
+
+#[test]
+fn test_function_has_internal_linkage() {
+    let ir = ir_from_cc(
+        "void External();
+        static void Internal() {}
+        struct SomeStruct final {
+          static void StaticMethod();
+        };",
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "External", ... has_internal_linkage: false, ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "Internal", ... has_internal_linkage: true, ... } }
+    );
+    // Static member functions have external linkage.
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "StaticMethod", ... has_internal_linkage: false, ... } }
+    );
+}
+
+#[test]
+fn test_function_is_variadic() {
+    let ir = ir_from_cc(
+        "int Printf(const char* format, ...);
+        int NotVariadic(const char* format);
+        inline void InlineVariadic(int x, ...) {}
+        struct SomeStruct final {
+          void VariadicMethod(int x, ...);
+        };",
+    )
+    .unwrap();
+    assert_ir_matches!(ir, quote! { Func { name: "Printf", ... is_variadic: true, ... } });
+    assert_ir_matches!(ir, quote! { Func { name: "NotVariadic", ... is_variadic: false, ... } });
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "InlineVariadic",
+          message: "Variadic functions that may not be codegenned in the C++ library (e.g. inline functions) are not supported" ...
+        }}
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "SomeStruct::VariadicMethod",
+          message: "Variadic member functions are not supported" ...
+        }}
+    );
+}
+
+#[test]
+fn test_rvalue_reference_param_without_lifetimes_is_taken_by_move() {
+    let ir = ir_from_cc("void TakesByMove(int&& x);").unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "TakesByMove", ...
+                params: [
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType {
+                                name: Some("i32"), ...
+                            },
+                            cc_type: CcType {
+                                name: Some("&&"),
+                                is_const: false,
+                                type_args: [CcType {
+                                    name: Some("int"), ...
+                                }],
+                                decl_id: None,
+                            },
+                        },
+                        identifier: "x",
+                    },
+                ], ...
+            }
+        }
+    );
+}
+
 #[test]
 fn test_functions_from_dependency_are_not_emitted() -> Result<()> {
     let ir = ir_from_cc_dependency("int Add(int a, int b);", "int Multiply(int a, int b);")?;
@@ -291,6 +462,47 @@ fn test_explicit_class_template_instantiation_declaration_not_supported_yet() {
     );
 }
 
+#[test]
+fn test_explicit_class_template_instantiation_definition() -> Result<()> {
+    let ir = ir_from_cc(
+        r#" #pragma clang lifetime_elision
+            template <typename T>
+            struct MyTemplate {
+                T GetValue() const { return field; }
+                T field;
+            };
+
+            template struct MyTemplate<int>; "#,
+    )?;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Record {
+            rs_name: "__CcTemplateInst10MyTemplateIiE", ...
+            cc_name: "MyTemplate<int>", ...
+            fields: [Field { identifier: Some("field"), ... }], ...
+          }
+        }
+    );
+    assert_ir_matches!(ir, quote! { Func { name: "GetValue", ...  } });
+    Ok(())
+}
+
+#[test]
+fn test_explicit_class_template_instantiation_definition_in_dependency() -> Result<()> {
+    let ir = ir_from_cc_dependency(
+        "// The explicit instantiation is in a header of a dependency.",
+        r#" template <typename T>
+            struct MyTemplate {
+                T field;
+            };
+
+            template struct MyTemplate<int>; "#,
+    )?;
+    assert_ir_not_matches!(ir, quote! { Record { ... cc_name: "MyTemplate<int>" ... } });
+    Ok(())
+}
+
 #[test]
 fn test_function_template_not_supported_yet() {
     let ir = ir_from_cc("template<typename SomeParam> void SomeFunctionTemplate() {};").unwrap();
@@ -303,6 +515,90 @@ fn test_function_template_not_supported_yet() {
     );
 }
 
+#[test]
+fn test_function_with_default_args() {
+    let ir = ir_from_cc("void f(int a, int b = 1, int c = 2);").unwrap();
+    assert_ir_matches!(ir, quote! { Func { name: "f", ... num_default_params: 2, ... } });
+}
+
+#[test]
+fn test_overloaded_function_rs_names() -> Result<()> {
+    let ir = ir_from_cc(
+        r#" struct Tag final {};
+            void Overload();
+            void Overload(int i, const char* s);
+            void Overload(const Tag& tag);
+            [[clang::annotate("crubit_rust_name", "OverloadFromDouble")]]
+            void Overload(double d);
+            void NotOverloaded(int i); "#,
+    )?;
+    assert_ir_matches!(ir, quote! { Func { name: "Overload", rs_name: None, ... } });
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "Overload", rs_name: Some("Overload_int_const_char_ptr"), ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "Overload", rs_name: Some("Overload_const_Tag_ref"), ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "Overload", rs_name: Some("OverloadFromDouble"), ... } }
+    );
+    assert_ir_matches!(ir, quote! { Func { name: "NotOverloaded", rs_name: None, ... } });
+    Ok(())
+}
+
+#[test]
+fn test_invalid_rust_name_annotation() {
+    let ir = ir_from_cc(r#"[[clang::annotate("crubit_rust_name", 123)]] void f();"#).unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "f",
+          message: "The `crubit_rust_name` annotation requires a single string literal argument" ...
+        }}
+    );
+}
+
+#[test]
+fn test_function_template_explicit_instantiation() -> Result<()> {
+    let ir = ir_from_cc(
+        r#" template <typename T>
+            T Add(T a, T b) { return a + b; }
+
+            template int Add<int>(int, int); "#,
+    )?;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Func {
+            name: "Add", ...
+            template_args: Some("<int>"), ...
+          }
+        }
+    );
+    // The template itself still can't be bound.
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "Add",
+          message: "Function templates are not supported yet" ...
+        }}
+    );
+    Ok(())
+}
+
+#[test]
+fn test_function_template_explicit_instantiation_of_template_from_dependency() -> Result<()> {
+    let ir = ir_from_cc_dependency(
+        "template int Add<int>(int, int);",
+        "template <typename T> T Add(T a, T b) { return a + b; }",
+    )?;
+    assert_ir_matches!(ir, quote! { Func { name: "Add", ... template_args: Some("<int>"), ... } });
+    Ok(())
+}
+
 #[test]
 fn test_record_member_variable_access_specifiers() {
     let ir = ir_from_cc(
@@ -526,8 +822,8 @@ fn test_struct_with_unnamed_struct_and_union_members() {
     )
     .unwrap();
 
-    // TODO(b/200067824): `type_` should not be `Err(...)` in the expectations below
-    // / we should support nested structs eventually.
+    let struct_id = retrieve_record(&ir, "StructWithUnnamedMembers__unnamed_field0").id;
+    let union_id = retrieve_record(&ir, "StructWithUnnamedMembers__unnamed_field1").id;
     assert_ir_matches!(
         ir,
         quote! {
@@ -537,20 +833,84 @@ fn test_struct_with_unnamed_struct_and_union_members() {
                 fields: [
                     Field {
                         identifier: None, ...
-                        type_ : Err(...), ...
+                        type_ : Ok(MappedType {
+                            rs_type: RsType { decl_id: Some(ItemId(#struct_id)) ... } ...
+                        }), ...
                         offset: 0, ...
                     } ...
                     Field {
                         identifier: None, ...
-                        type_ : Err(...), ...
+                        type_ : Ok(MappedType {
+                            rs_type: RsType { decl_id: Some(ItemId(#union_id)) ... } ...
+                        }), ...
                         offset: 64, ...
                     } ...
                 ], ...
                 size: 12, ...
                 alignment: 4, ...
+                is_anonymous_struct_or_union: false, ...
+            }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Record {
+                rs_name: "StructWithUnnamedMembers__unnamed_field0" ...
+                fields: [
+                    Field { identifier: Some("anonymous_struct_field_1"), ... offset: 0, ... },
+                    Field { identifier: Some("anonymous_struct_field_2"), ... offset: 32, ... },
+                ], ...
+                size: 8, ...
+                record_type: Struct, ...
+                is_anonymous_struct_or_union: true, ...
+            }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Record {
+                rs_name: "StructWithUnnamedMembers__unnamed_field1" ...
+                size: 4, ...
+                record_type: Union, ...
+                is_anonymous_struct_or_union: true, ...
             }
         }
     );
+    // Anonymous structs and unions have no bindable special member functions.
+    assert_ir_not_matches!(
+        ir,
+        quote! { Func { ... member_func_metadata: Some(MemberFuncMetadata {
+            record_id: ItemId(#struct_id) ...
+        }) ... } }
+    );
+}
+
+#[test]
+fn test_struct_with_non_trivially_copyable_anonymous_union_member() {
+    let ir = ir_from_cc(
+        r#"
+        struct NonTrivial {
+          ~NonTrivial();
+        };
+        struct StructWithAnonymousUnion {
+          StructWithAnonymousUnion();
+          ~StructWithAnonymousUnion();
+          union {
+            NonTrivial non_trivial;
+            int i;
+          };
+        }; "#,
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "StructWithAnonymousUnion::(anonymous union at ./ir_from_cc_virtual_header.h:8:11)",
+          message: "Anonymous structs and unions that are not trivially copyable are not supported" ...
+        }}
+    );
 }
 
 #[test]
@@ -690,23 +1050,441 @@ fn test_pointer_member_variable() {
 }
 
 #[test]
-fn test_doc_comment() -> Result<()> {
+fn test_std_vector_behind_pointer() {
     let ir = ir_from_cc(
-        r#"
-            /// Doc comment
-            ///
-            ///  * with three slashes
-            struct DocCommentSlashes {};
-
-            //! Doc comment
-            //!
-            //!  * with slashes and bang
-            struct DocCommentBang {};
-
-            /** Multiline comment
+        "// We mock types from the C++ standard library because it's hard to
+        // make headers that aren't part of the compiler available to a unit test.
+        namespace std {
+          template <typename T> class allocator {};
+          template <typename T, typename A = allocator<T>> class vector {
+            T* begin_;
+            T* end_;
+            T* end_cap_;
+          };
+        }
+        int Sum(const std::vector<int>& values);",
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "Sum", ...
+                params: [FuncParam {
+                    type_: MappedType {
+                        rs_type: RsType {
+                            name: Some("*const") ...
+                            type_args: [RsType {
+                                name: Some("::cc_std::Vector") ...
+                                type_args: [RsType { name: Some("i32") ... }],
+                                decl_id: None,
+                            }],
+                            decl_id: None,
+                        },
+                        cc_type: CcType {
+                            name: Some("&"),
+                            is_const: false,
+                            type_args: [CcType {
+                                name: Some("std::vector"),
+                                is_const: true,
+                                type_args: [CcType { name: Some("int") ... }],
+                                decl_id: None,
+                            }],
+                            decl_id: None,
+                        },
+                    },
+                    identifier: "values",
+                }],
+                ...
+            }
+        }
+    );
+}
 
-                * with two stars */
-            struct MultilineCommentTwoStars {};
+#[test]
+fn test_std_vector_by_value_is_not_mapped() {
+    let ir = ir_from_cc(
+        "namespace std {
+          template <typename T> class allocator {};
+          template <typename T, typename A = allocator<T>> class vector {
+            T* begin_;
+            T* end_;
+            T* end_cap_;
+          };
+        }
+        void TakesVectorByValue(std::vector<int> values);",
+    )
+    .unwrap();
+    assert_ir_not_matches!(ir, quote! { name: Some("::cc_std::Vector") });
+    assert_ir_not_matches!(ir, quote! { name: Some("std::vector") });
+}
+
+#[test]
+fn test_std_optional_by_value() {
+    let ir = ir_from_cc(
+        "// We mock types from the C++ standard library because it's hard to
+        // make headers that aren't part of the compiler available to a unit test.
+        namespace std {
+          template <typename T> class optional {
+            T value_;
+            bool has_value_;
+          };
+        }
+        std::optional<float> Sqrt(std::optional<float> x);",
+    )
+    .unwrap();
+    let optional_float = quote! {
+        MappedType {
+            rs_type: RsType {
+                name: Some("::core::option::Option") ...
+                type_args: [RsType { name: Some("f32") ... }],
+                decl_id: None,
+            },
+            cc_type: CcType {
+                name: Some("std::optional"),
+                is_const: false,
+                type_args: [CcType { name: Some("float") ... }],
+                decl_id: None,
+            },
+        }
+    };
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "Sqrt", ...
+                return_type: #optional_float, ...
+                params: [FuncParam {
+                    type_: #optional_float,
+                    identifier: "x",
+                }],
+                ...
+            }
+        }
+    );
+}
+
+#[test]
+fn test_std_optional_of_struct_is_not_mapped() {
+    let ir = ir_from_cc(
+        "namespace std {
+          template <typename T> class optional {
+            T value_;
+            bool has_value_;
+          };
+        }
+        struct S final { int field; };
+        void TakesOptionalStruct(std::optional<S> s);
+        struct WithOptionalField final { std::optional<int> field; };",
+    )
+    .unwrap();
+    assert_ir_not_matches!(ir, quote! { name: Some("::core::option::Option") });
+    assert_ir_not_matches!(ir, quote! { name: Some("std::optional") });
+}
+
+#[test]
+fn test_absl_string_view_and_span_params() {
+    let ir = ir_from_cc(
+        "namespace std {
+          class string_view {
+            const char* data_;
+            decltype(sizeof(0)) size_;
+          };
+        }
+        namespace absl {
+          using string_view = std::string_view;
+          template <typename T> class Span {
+            T* data_;
+            decltype(sizeof(0)) size_;
+          };
+        }
+        void TakesViews(absl::string_view text, absl::Span<const int> values,
+                        absl::Span<float> out);",
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "TakesViews", ...
+                params: [
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType { name: Some("&str") ... },
+                            cc_type: CcType { name: Some("absl::string_view") ... },
+                        },
+                        identifier: "text",
+                    },
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType {
+                                name: Some("&[]") ...
+                                type_args: [RsType { name: Some("i32") ... }], ...
+                            },
+                            cc_type: CcType {
+                                name: Some("absl::Span"), ...
+                                type_args: [CcType { name: Some("int"), is_const: true ... }], ...
+                            },
+                        },
+                        identifier: "values",
+                    },
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType {
+                                name: Some("&mut []") ...
+                                type_args: [RsType { name: Some("f32") ... }], ...
+                            },
+                            cc_type: CcType {
+                                name: Some("absl::Span"), ...
+                                type_args: [CcType { name: Some("float"), is_const: false ... }], ...
+                            },
+                        },
+                        identifier: "out",
+                    },
+                ], ...
+            }
+        }
+    );
+}
+
+#[test]
+fn test_absl_views_are_only_mapped_as_params() {
+    let ir = ir_from_cc(
+        "namespace std {
+          class string_view {
+            const char* data_;
+            decltype(sizeof(0)) size_;
+          };
+        }
+        namespace absl {
+          using string_view = std::string_view;
+          template <typename T> class Span {
+            T* data_;
+            decltype(sizeof(0)) size_;
+          };
+        }
+        struct S final { int field; };
+        absl::string_view ReturnsStringView();
+        void TakesStringViewSpan(absl::Span<S> structs);
+        void TakesStdStringView(std::string_view text);",
+    )
+    .unwrap();
+    assert_ir_not_matches!(ir, quote! { name: Some("&str") });
+    assert_ir_not_matches!(ir, quote! { name: Some("absl::string_view") });
+    assert_ir_not_matches!(ir, quote! { name: Some("absl::Span") });
+}
+
+#[test]
+fn test_std_function_and_absl_any_invocable_params() {
+    let ir = ir_from_cc(
+        "namespace std {
+          template <typename Signature> class function;
+          template <typename R, typename... Args> class function<R(Args...)> {
+            void* state_[4];
+          };
+        }
+        namespace absl {
+          template <typename Signature> class AnyInvocable {
+            void* state_[4];
+          };
+        }
+        void TakesCallables(std::function<int(int, float)> f, absl::AnyInvocable<void()> g);",
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "TakesCallables", ...
+                params: [
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType {
+                                name: Some("impl Fn") ...
+                                type_args: [
+                                    RsType { name: Some("i32") ... },
+                                    RsType { name: Some("f32") ... },
+                                    RsType { name: Some("i32") ... },
+                                ], ...
+                            },
+                            cc_type: CcType {
+                                name: Some("std::function"), ...
+                                type_args: [
+                                    CcType { name: Some("int") ... },
+                                    CcType { name: Some("float") ... },
+                                    CcType { name: Some("int") ... },
+                                ], ...
+                            },
+                        },
+                        identifier: "f",
+                    },
+                    FuncParam {
+                        type_: MappedType {
+                            rs_type: RsType {
+                                name: Some("impl FnMut") ...
+                                type_args: [RsType { name: Some("()") ... }], ...
+                            },
+                            cc_type: CcType {
+                                name: Some("absl::AnyInvocable"), ...
+                                type_args: [CcType { name: Some("void") ... }], ...
+                            },
+                        },
+                        identifier: "g",
+                    },
+                ], ...
+            }
+        }
+    );
+}
+
+#[test]
+fn test_callables_are_only_mapped_as_params_of_primitive_signatures() {
+    let ir = ir_from_cc(
+        "namespace std {
+          template <typename Signature> class function;
+          template <typename R, typename... Args> class function<R(Args...)> {
+            void* state_[4];
+          };
+        }
+        namespace absl {
+          template <typename Signature> class AnyInvocable {
+            void* state_[4];
+          };
+        }
+        struct S final { int field; };
+        std::function<void()> ReturnsFunction();
+        void TakesStructCallback(std::function<void(S)> f);
+        void TakesConstCallback(absl::AnyInvocable<void() const> f);",
+    )
+    .unwrap();
+    assert_ir_not_matches!(ir, quote! { name: Some("impl Fn") });
+    assert_ir_not_matches!(ir, quote! { name: Some("impl FnMut") });
+}
+
+#[test]
+fn test_std_unique_ptr_by_value() {
+    let ir = ir_from_cc(
+        "// We mock types from the C++ standard library because it's hard to
+        // make headers that aren't part of the compiler available to a unit test.
+        namespace std {
+          template <typename T> struct default_delete {};
+          template <typename T, typename D = default_delete<T>> class unique_ptr {
+            T* ptr_;
+          };
+        }
+        struct Widget final { int id; };
+        std::unique_ptr<Widget> MakeWidget();
+        void TakeWidget(std::unique_ptr<Widget> widget);
+        void TakeWidgetByRef(const std::unique_ptr<Widget>& widget);",
+    )
+    .unwrap();
+    let unique_ptr_to_widget = quote! {
+        MappedType {
+            rs_type: RsType {
+                name: Some("::cc_std::UniquePtr") ...
+                type_args: [RsType { name: None ... decl_id: Some(...) }],
+                decl_id: None,
+            },
+            cc_type: CcType {
+                name: Some("std::unique_ptr"),
+                is_const: false,
+                type_args: [CcType { name: None ... decl_id: Some(...) }],
+                decl_id: None,
+            },
+        }
+    };
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "MakeWidget", ... return_type: #unique_ptr_to_widget, ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "TakeWidget", ...
+                params: [FuncParam {
+                    type_: #unique_ptr_to_widget,
+                    identifier: "widget",
+                }],
+                ...
+            }
+        }
+    );
+    // `std::unique_ptr` behind a reference is not mapped.
+    assert_ir_not_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "TakeWidgetByRef", ... name: Some("::cc_std::UniquePtr") ...
+            }
+        }
+    );
+}
+
+#[test]
+fn test_std_shared_ptr_by_value() {
+    let ir = ir_from_cc(
+        "// We mock types from the C++ standard library because it's hard to
+        // make headers that aren't part of the compiler available to a unit test.
+        namespace std {
+          template <typename T> class shared_ptr {
+            T* ptr_;
+            void* control_block_;
+          };
+        }
+        struct Widget final { int id; };
+        std::shared_ptr<Widget> ShareWidget(std::shared_ptr<Widget> widget);",
+    )
+    .unwrap();
+    let shared_ptr_to_widget = quote! {
+        MappedType {
+            rs_type: RsType {
+                name: Some("::cc_std::SharedPtr") ...
+                type_args: [RsType { name: None ... decl_id: Some(...) }],
+                decl_id: None,
+            },
+            cc_type: CcType {
+                name: Some("std::shared_ptr"),
+                is_const: false,
+                type_args: [CcType { name: None ... decl_id: Some(...) }],
+                decl_id: None,
+            },
+        }
+    };
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "ShareWidget", ...
+                return_type: #shared_ptr_to_widget, ...
+                params: [FuncParam {
+                    type_: #shared_ptr_to_widget,
+                    identifier: "widget",
+                }],
+                ...
+            }
+        }
+    );
+}
+
+#[test]
+fn test_doc_comment() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            /// Doc comment
+            ///
+            ///  * with three slashes
+            struct DocCommentSlashes {};
+
+            //! Doc comment
+            //!
+            //!  * with slashes and bang
+            struct DocCommentBang {};
+
+            /** Multiline comment
+
+                * with two stars */
+            struct MultilineCommentTwoStars {};
 
             // Line comment
             //
@@ -791,6 +1569,121 @@ fn test_doc_comment_vs_tooling_directives() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_doc_comment_enum() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            /// Doc comment for the enum
+            enum Color {
+              /// Doc comment for an enumerator
+              kRed,
+              kBlue,
+            };
+        "#,
+    )?;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Enum {
+            identifier: "Color", ...
+            doc_comment: Some("Doc comment for the enum"), ...
+            enumerators: [
+              Enumerator {
+                identifier: "kRed",
+                doc_comment: Some("Doc comment for an enumerator"), ...
+              },
+              Enumerator {
+                identifier: "kBlue",
+                doc_comment: None, ...
+              },
+            ], ...
+          }
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_doc_comment_macro_constant() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            /** Doc comment for `MAX_LEN` */
+            #define MAX_LEN 128
+
+            // Not a doc comment for `MIN_LEN` (separated by an empty line)
+
+            #define MIN_LEN 1
+            #define INLINE_LEN 4 // Trailing comments are not doc comments
+            #define OTHER_LEN 8
+        "#,
+    )?;
+    let comments: HashMap<&str, Option<&str>> = ir
+        .items()
+        .filter_map(|item| match item {
+            Item::Constant(constant) => {
+                Some((constant.identifier.identifier.as_ref(), constant.doc_comment.as_deref()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(comments["MAX_LEN"], Some("Doc comment for `MAX_LEN` "));
+    assert_eq!(comments["MIN_LEN"], None);
+    assert_eq!(comments["INLINE_LEN"], None);
+    assert_eq!(comments["OTHER_LEN"], None);
+    Ok(())
+}
+
+#[test]
+fn test_deprecated() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            [[deprecated("Use `NewFunc` instead")]] void OldFunc();
+            struct [[deprecated]] OldStruct {};
+            enum [[deprecated]] OldEnum {
+              kOld [[deprecated("Use `kNew` instead")]],
+              kNew,
+            };
+            using OldAlias [[deprecated]] = int;
+            __attribute__((deprecated)) void LegacyFunc();
+        "#,
+    )?;
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: "OldFunc", ... deprecated: Some("Use `NewFunc` instead"), ... } }
+    );
+    assert_ir_matches!(ir, quote! { Func { name: "LegacyFunc", ... deprecated: Some(""), ... } });
+    assert_ir_matches!(
+        ir,
+        quote! { Record { rs_name: "OldStruct", ... deprecated: Some(""), ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Enum {
+            identifier: "OldEnum", ...
+            deprecated: Some(""), ...
+            enumerators: [
+              Enumerator {
+                identifier: "kOld",
+                doc_comment: None,
+                deprecated: Some("Use `kNew` instead"), ...
+              },
+              Enumerator {
+                identifier: "kNew",
+                doc_comment: None,
+                deprecated: None, ...
+              },
+            ], ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { TypeAlias { identifier: "OldAlias", ... deprecated: Some(""), ... } }
+    );
+    Ok(())
+}
+
 // TOOD(rosica): Reenable once b/208377928 is fixed. (Also disallow dead code.)
 // #[test]
 #[allow(dead_code)]
@@ -899,6 +1792,8 @@ fn test_type_conversion() -> Result<()> {
     // We cannot map C++ char32_t or wchar_t to Rust char,
     // because Rust requires that chars are valid UTF scalar values.
     assert_eq!(type_mapping["char32_t"], "u32");
+    // `wchar_t` is mapped based on its size and signedness on the target
+    // platform (which is x86-64 Linux in this test).
     assert_eq!(type_mapping["wchar_t"], "i32");
 
     assert_eq!(type_mapping["short"], "i16");
@@ -950,64 +1845,337 @@ fn test_type_conversion() -> Result<()> {
 }
 
 #[test]
-fn test_typedef() -> Result<()> {
+fn test_typedef() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            // Doc comment for MyTypedefDecl.
+            typedef int MyTypedefDecl;
+
+            // Doc comment for MyTypeAliasDecl.
+            using MyTypeAliasDecl = int;
+        "#,
+    )?;
+
+    let int = quote! {
+      MappedType {
+        rs_type: RsType {
+          name: Some("i32"),
+          lifetime_args: [],
+          type_args: [],
+          decl_id: None,
+        },
+        cc_type: CcType {
+          name: Some("int"),
+          is_const: false,
+          type_args: [],
+          decl_id: None,
+        },
+      }
+    };
+    assert_ir_matches!(
+        ir,
+        quote! {
+          TypeAlias {
+            identifier: "MyTypedefDecl",
+            id: ItemId(...),
+            owning_target: BazelLabel("//test:testing_target"),
+            doc_comment: Some("Doc comment for MyTypedefDecl."),
+            deprecated: None,
+            underlying_type: #int,
+            source_loc: ...
+            enclosing_record_id: None,
+            enclosing_namespace_id: None,
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          TypeAlias {
+            identifier: "MyTypeAliasDecl",
+            id: ItemId(...),
+            owning_target: BazelLabel("//test:testing_target"),
+            doc_comment: Some("Doc comment for MyTypeAliasDecl."),
+            deprecated: None,
+            underlying_type: #int,
+            source_loc: ...,
+            enclosing_record_id: None,
+            enclosing_namespace_id: None,
+          }
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_constants() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            // Doc comment for kAnswer.
+            constexpr int kAnswer = 42;
+            const long long kNegative = -1;
+            constexpr bool kFlag = true;
+            constexpr double kDouble = 1.5;
+            static constexpr float kFloat = 0.25f;
+            constexpr int kComputed = kAnswer * 2;
+        "#,
+    )?;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kAnswer",
+            id: ItemId(...),
+            owning_target: BazelLabel("//test:testing_target"),
+            doc_comment: Some("Doc comment for kAnswer."),
+            source_loc: ...,
+            type_: MappedType {
+              rs_type: RsType { name: Some("i32"), ... },
+              cc_type: CcType { name: Some("int"), is_const: false, ... },
+            },
+            value: Integer(IntegerConstant { is_negative: false, wrapped_value: 42 }),
+            is_macro: false,
+            enclosing_record_id: None,
+            enclosing_namespace_id: None,
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kNegative", ...
+            value: Integer(IntegerConstant {
+              is_negative: true, wrapped_value: 18446744073709551615
+            }), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kFlag", ...
+            value: Integer(IntegerConstant { is_negative: false, wrapped_value: 1 }), ...
+          }
+        }
+    );
+    // The bits of 1.5 and 0.25 (converted to `double`).
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kDouble", ...
+            value: Float(FloatConstant { bits: 4609434218613702656 }), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kFloat", ...
+            type_: MappedType { rs_type: RsType { name: Some("f32"), ... }, ... },
+            value: Float(FloatConstant { bits: 4598175219545276416 }), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kComputed", ...
+            value: Integer(IntegerConstant { is_negative: false, wrapped_value: 84 }), ...
+          }
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_static_constexpr_member() -> Result<()> {
     let ir = ir_from_cc(
         r#"
-            // Doc comment for MyTypedefDecl.
-            typedef int MyTypedefDecl;
+            struct SomeStruct final {
+              static constexpr int kPublic = 1;
+              static const unsigned kStaticConst = 2;
+             private:
+              static constexpr int kPrivate = 3;
+            };
+        "#,
+    )?;
+    let record_id = retrieve_record(&ir, "SomeStruct").id;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kPublic", ...
+            value: Integer(IntegerConstant { is_negative: false, wrapped_value: 1 }),
+            enclosing_record_id: Some(ItemId(#record_id)), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "kStaticConst", ...
+            type_: MappedType { rs_type: RsType { name: Some("u32"), ... }, ... },
+            value: Integer(IntegerConstant { is_negative: false, wrapped_value: 2 }),
+            enclosing_record_id: Some(ItemId(#record_id)), ...
+          }
+        }
+    );
+    assert_ir_not_matches!(ir, quote! { Constant { identifier: "kPrivate", ... } });
+    Ok(())
+}
 
-            // Doc comment for MyTypeAliasDecl.
-            using MyTypeAliasDecl = int;
+#[test]
+fn test_static_data_members() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            struct SomeStruct final {
+              static int counter;
+              static double* pointer;
+            };
         "#,
     )?;
+    let record_id = retrieve_record(&ir, "SomeStruct").id;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          StaticDataMember {
+            identifier: "counter", ...
+            type_: MappedType { rs_type: RsType { name: Some("i32"), ... }, ... },
+            mangled_name: "_ZN10SomeStruct7counterE",
+            enclosing_record_id: ItemId(#record_id), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          UnsupportedItem {
+            name: "SomeStruct::pointer",
+            message: "Only static data members of integral and floating-point types are supported" ...
+          }
+        }
+    );
+    Ok(())
+}
 
-    let int = quote! {
-      MappedType {
-        rs_type: RsType {
-          name: Some("i32"),
-          lifetime_args: [],
-          type_args: [],
-          decl_id: None,
-        },
-        cc_type: CcType {
-          name: Some("int"),
-          is_const: false,
-          type_args: [],
-          decl_id: None,
-        },
-      }
-    };
+#[test]
+fn test_constants_not_supported() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            struct SomeStruct { int field; };
+            constexpr SomeStruct kStruct = {1};
+            constexpr const char* kString = "string";
+            extern const int kExtern;
+            int kMutable = 1;
+        "#,
+    )?;
     assert_ir_matches!(
         ir,
         quote! {
-          TypeAlias {
-            identifier: "MyTypedefDecl",
-            id: ItemId(...),
-            owning_target: BazelLabel("//test:testing_target"),
-            doc_comment: Some("Doc comment for MyTypedefDecl."),
-            underlying_type: #int,
-            source_loc: ...
-            enclosing_record_id: None,
-            enclosing_namespace_id: None,
+          UnsupportedItem {
+            name: "kStruct",
+            message: "Only constants of integral and floating-point types are supported" ...
           }
         }
     );
     assert_ir_matches!(
         ir,
         quote! {
-          TypeAlias {
-            identifier: "MyTypeAliasDecl",
+          UnsupportedItem {
+            name: "kString",
+            message: "Only constants of integral and floating-point types are supported" ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          UnsupportedItem {
+            name: "kExtern",
+            message: "Constants without an initializer are not supported" ...
+          }
+        }
+    );
+    // Mutable global variables are not imported (yet).
+    assert_ir_not_matches!(ir, quote! { Constant { identifier: "kMutable", ... } });
+    assert_ir_not_matches!(ir, quote! { UnsupportedItem { name: "kMutable", ... } });
+    Ok(())
+}
+
+#[test]
+fn test_macro_constants() -> Result<()> {
+    let ir = ir_from_cc(
+        r#"
+            #define MAX_LEN 128
+            #define MINUS_ONE (-1)
+            #define RATIO 0.5f
+            #define GREETING "Hello, " "world!"
+            #define SQUARE(x) ((x) * (x))
+            #define NOT_A_LITERAL MAX_LEN + 1
+            #define UNDEFINED 1
+            #undef UNDEFINED
+        "#,
+    )?;
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "MAX_LEN",
             id: ItemId(...),
             owning_target: BazelLabel("//test:testing_target"),
-            doc_comment: Some("Doc comment for MyTypeAliasDecl."),
-            underlying_type: #int,
+            doc_comment: None,
             source_loc: ...,
+            type_: MappedType {
+              rs_type: RsType { name: Some("i32"), ... },
+              cc_type: CcType { name: Some("int"), ... },
+            },
+            value: Integer(IntegerConstant { is_negative: false, wrapped_value: 128 }),
+            is_macro: true,
             enclosing_record_id: None,
             enclosing_namespace_id: None,
           }
         }
     );
-
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "MINUS_ONE", ...
+            value: Integer(IntegerConstant {
+              is_negative: true, wrapped_value: 18446744073709551615
+            }), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "RATIO", ...
+            type_: MappedType { rs_type: RsType { name: Some("f32"), ... }, ... },
+            value: Float(FloatConstant { bits: 4602678819172646912 }), ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Constant {
+            identifier: "GREETING", ...
+            value: String("Hello, world!"), ...
+          }
+        }
+    );
+    assert_ir_not_matches!(ir, quote! { Constant { identifier: "SQUARE", ... } });
+    assert_ir_not_matches!(ir, quote! { Constant { identifier: "NOT_A_LITERAL", ... } });
+    assert_ir_not_matches!(ir, quote! { Constant { identifier: "UNDEFINED", ... } });
     Ok(())
 }
 
@@ -2261,25 +3429,82 @@ fn test_ignore_union_typedef_from_decl_context_redecl_from_multiple_targets() {
 }
 
 #[test]
-fn test_records_nested_in_records_not_supported_yet() {
-    let ir = ir_from_cc("struct SomeStruct { struct NestedStruct {}; };").unwrap();
+fn test_records_nested_in_records() {
+    let ir = ir_from_cc(
+        r#"
+        struct SomeStruct {
+          struct NestedStruct {
+            struct DoublyNestedStruct {};
+          };
+        };"#,
+    )
+    .unwrap();
+    let outer = retrieve_record(&ir, "SomeStruct");
+    let outer_id = outer.id;
+    let nested_id = retrieve_record(&ir, "NestedStruct").id;
+    assert!(outer.child_item_ids.contains(&nested_id));
+    assert_eq!(outer.enclosing_record_id, None);
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Record {
+                rs_name: "SomeStruct_NestedStruct", cc_name: "NestedStruct", ...
+                enclosing_record_id: Some(ItemId(#outer_id)), ...
+            }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Record {
+                rs_name: "SomeStruct_NestedStruct_DoublyNestedStruct",
+                cc_name: "DoublyNestedStruct", ...
+                enclosing_record_id: Some(ItemId(#nested_id)), ...
+            }
+        }
+    );
+}
+
+#[test]
+fn test_records_nested_in_class_template_specializations_not_supported() {
+    let ir = ir_from_cc(
+        r#"
+        template <typename T>
+        struct MyTemplate {
+          struct NestedStruct {};
+        };
+        using MyAlias = MyTemplate<int>;"#,
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! { UnsupportedItem {
+          name: "MyTemplate<int>::NestedStruct",
+          message: "Classes nested in class templates are not supported" ...
+        }}
+    );
+}
+
+#[test]
+fn test_forward_declared_nested_records_not_supported_yet() {
+    let ir = ir_from_cc("struct SomeStruct { struct NestedStruct; };").unwrap();
     assert_ir_matches!(
         ir,
         quote! { UnsupportedItem {
           name: "SomeStruct::NestedStruct",
-          message: "Nested classes are not supported yet" ...
+          message: "Forward declarations of nested classes are not supported yet" ...
         }}
     );
 }
 
 #[test]
 fn test_record_with_unsupported_field_type() -> Result<()> {
-    // Using a nested struct because it's currently not supported.
+    // Using a packed struct because it's currently not supported.
     // But... any other unsupported type would also work for this test.
     let ir = ir_from_cc(
         r#"
         struct StructWithUnsupportedField {
-          struct NestedStruct {};
+          struct __attribute__((packed)) NestedStruct {};
 
           // Doc comment for `my_field`.
           NestedStruct my_field;
@@ -2319,7 +3544,7 @@ fn test_record_with_unsupported_field_type() -> Result<()> {
         quote! {
             UnsupportedItem {
                 name: "StructWithUnsupportedField::NestedStruct",
-                message: "Nested classes are not supported yet",
+                message: "Records with packed layout are not supported",
                 ...
             }
         }
@@ -2327,11 +3552,88 @@ fn test_record_with_unsupported_field_type() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_record_with_private_virtual_methods() {
+    let ir = ir_from_cc(
+        r#"
+        class Public {
+         public:
+          virtual void Method();
+         private:
+          virtual ~Public();
+        };
+        class Protected {
+         protected:
+          virtual void Method();
+        };
+        class Private {
+          virtual void Method();
+        };"#,
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! { Record { rs_name: "Public", ... has_private_virtual_methods: false, ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Record { rs_name: "Protected", ... has_private_virtual_methods: false, ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Record { rs_name: "Private", ... has_private_virtual_methods: true, ... } }
+    );
+}
+
+#[test]
+fn test_protected_members_for_overrides() {
+    let ir = ir_from_cc(
+        r#"
+        class Base {
+         public:
+          virtual ~Base();
+         protected:
+          Base();
+          explicit Base(int i);
+          virtual void VirtualMethod();
+          void NonVirtualMethod();
+        };"#,
+    )
+    .unwrap();
+    // Protected virtual methods and default constructors are imported (for
+    // overriding the virtual methods from Rust)...
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: "VirtualMethod", ...
+                member_func_metadata: Some(MemberFuncMetadata {
+                    ...
+                    is_protected: true
+                }), ...
+            }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! {
+            Func {
+                name: Constructor, ...
+                params: [FuncParam { ... identifier: "__this", ... }], ...
+                member_func_metadata: Some(MemberFuncMetadata { ... is_protected: true }), ...
+            }
+        }
+    );
+    // ... but other protected members are not.
+    assert_ir_not_matches!(ir, quote! { Func { name: "NonVirtualMethod", ... } });
+    assert_ir_not_matches!(ir, quote! { FuncParam { ... identifier: "i", ... } });
+}
+
 #[test]
 fn test_record_with_unsupported_base() -> Result<()> {
     let ir = ir_from_cc(
         r#" struct OuterStruct {
-              struct NestedStruct {
+              struct __attribute__((packed)) NestedStruct {
                 // Having a field here avoids empty base class optimization
                 // and forces `derived_field` to be at a non-zero offset.
                 // See also: https://en.cppreference.com/w/cpp/language/ebo
@@ -2339,7 +3641,7 @@ fn test_record_with_unsupported_base() -> Result<()> {
               };
             };
 
-            // Using a nested struct as a base class because nested structs are
+            // Using a packed struct as a base class because packed structs are
             // currently unsupported.  But... any other unsupported base class
             // would also work for this test.
             struct DerivedClass : public OuterStruct::NestedStruct {
@@ -2382,7 +3684,7 @@ fn test_record_with_unsupported_base() -> Result<()> {
         quote! {
            UnsupportedItem {
                name: "OuterStruct::NestedStruct",
-               message: "Nested classes are not supported yet",
+               message: "Records with packed layout are not supported",
                ...
            }
         }
@@ -2392,11 +3694,11 @@ fn test_record_with_unsupported_base() -> Result<()> {
 
 #[test]
 fn test_do_not_import_static_member_functions_when_record_not_supported_yet() {
-    // only using nested struct as an example of a record we cannot import yet.
+    // only using packed struct as an example of a record we cannot import yet.
     let ir = ir_from_cc(
         "
         struct SomeStruct {
-          struct NestedStruct {
+          struct __attribute__((packed)) NestedStruct {
             static void StaticMemberFunction();
           };
         };",
@@ -2412,11 +3714,11 @@ fn test_do_not_import_static_member_functions_when_record_not_supported_yet() {
 
 #[test]
 fn test_do_not_import_nonstatic_member_functions_when_record_not_supported_yet() {
-    // only using nested struct as an example of a record we cannot import yet.
+    // only using packed struct as an example of a record we cannot import yet.
     let ir = ir_from_cc(
         "
         struct SomeStruct {
-          struct NestedStruct {
+          struct __attribute__((packed)) NestedStruct {
             void NonStaticMemberFunction();
           };
         };",
@@ -2434,16 +3736,13 @@ fn test_do_not_import_nonstatic_member_functions_when_record_not_supported_yet()
 fn test_dont_import_injected_class_name() {
     let ir = ir_from_cc("struct SomeStruct {};").unwrap();
     let names = ir.records().map(|r| r.rs_name.as_ref()).filter(|n| n.contains("SomeStruct"));
-    // if we do support nested structs, we should not emit record for injected class
-    // name
+    // We should not emit a nested record for the injected class name...
     assert_eq!(names.count(), 1);
-    // if we don't support nested structs, we should not emit unsupported item for
-    // injected class name
+    // ...nor an unsupported item.
     assert_ir_not_matches!(
         ir,
         quote! { UnsupportedItem {
-          name: "SomeStruct::SomeStruct",
-          message: "Nested classes are not supported yet" ...
+          name: "SomeStruct::SomeStruct" ...
         }}
     );
 }
@@ -2835,6 +4134,38 @@ fn test_member_function_constructor() {
     }
 }
 
+#[test]
+fn test_conversion_functions() {
+    let ir = ir_from_cc(
+        r#"
+        struct OtherType final {};
+        struct SomeStruct final {
+          operator int() const;
+          explicit operator OtherType() const;
+          explicit operator const char*() const;
+        };"#,
+    )
+    .unwrap();
+    assert_ir_matches!(
+        ir,
+        quote! {
+          Func {
+            name: Conversion,
+            rs_name: None, ...
+            return_type: MappedType { rs_type: RsType { name: Some("i32"), ... }, ... }, ...
+          }
+        }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: Conversion, rs_name: Some("to_other_type") ... } }
+    );
+    assert_ir_matches!(
+        ir,
+        quote! { Func { name: Conversion, rs_name: Some("to_const_char_ptr") ... } }
+    );
+}
+
 fn get_func_names(definition: &str) -> Vec<ir::UnqualifiedIdentifier> {
     let ir = ir_from_cc(definition).unwrap();
     ir.functions().map(|f| f.name.clone()).collect()
@@ -3259,11 +4590,7 @@ fn test_record_items() {
               }
             },
             quote! {
-              ... UnsupportedItem {
-                  name: "TopLevelStruct::operator int",
-                  message: "Function name is not supported: Unsupported name: operator int",
-                  ...
-              }
+              ... Func { name: Conversion, rs_name: Some("to_int") ... }
             },
         ]
     );
@@ -52,9 +52,12 @@ std::optional<absl::string_view> MapKnownCcTypeToRsType(
           {"std::uint32_t", "u32"},
           {"std::uint64_t", "u64"},
 
+          // `wchar_t` is not listed here, because its size and signedness
+          // depend on the target platform (e.g. it is `u16` on Windows and
+          // `i32` on x86-64 Linux).  It is mapped like other builtin integer
+          // types in `Importer::ConvertType`.
           {"char16_t", "u16"},
           {"char32_t", "u32"},
-          {"wchar_t", "i32"},
 
           // `class rs_std::rs_char` key covers direct usage of
           // `rs_std::rs_char`.  `rs_std::rs_char` key covers scenarios when
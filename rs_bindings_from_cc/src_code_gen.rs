@@ -12,7 +12,7 @@ use itertools::Itertools;
 use once_cell::sync::Lazy;
 use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{format_ident, quote, ToTokens};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet};
 use std::ffi::{OsStr, OsString};
 use std::fmt::Write as _;
 use std::iter::{self, Iterator};
@@ -235,6 +235,14 @@ fn generate_bindings(
     Ok(Bindings { rs_api, rs_api_impl })
 }
 
+/// Returns true if the bindings of `func` translate the C++ exceptions thrown
+/// by it into `Err(::cc_std::CcException)` (which is opt-in - see
+/// `ir::CrubitFeature::Exceptions`).
+fn translates_cc_exceptions(ir: &IR, func: &Func) -> bool {
+    !func.is_noexcept
+        && ir.target_crubit_features(&func.owning_target).contains(ir::CrubitFeature::Exceptions)
+}
+
 /// If we know the original C++ function is codegenned and already compatible
 /// with `extern "C"` calling convention we skip creating/calling the C++ thunk
 /// since we can call the original C++ directly.
@@ -256,12 +264,38 @@ fn can_skip_cc_thunk(db: &dyn BindingsGenerator, func: &Func) -> bool {
     if func.is_inline {
         return false;
     }
+    // ## Functions with internal linkage
+    //
+    // Similarly to inline functions, `static` functions defined in headers don't
+    // have a symbol that Rust could link against.  The thunk gets its own copy of
+    // the function (from the header included by the generated C++ file).
+    if func.has_internal_linkage {
+        return false;
+    }
     // ## Member functions (or descendants) of class templates
     //
     // A thunk is required to force/guarantee template instantiation.
     if func.is_member_or_descendant_of_class_template {
         return false;
     }
+    // ## Explicit instantiations of function templates
+    //
+    // A thunk is required, because an explicit instantiation *declaration* (i.e.
+    // `extern template`) doesn't guarantee that the instantiation is codegenned
+    // in the C++ library.
+    if func.template_args.is_some() {
+        return false;
+    }
+    // ## Omitted default arguments
+    //
+    // Default arguments are filled in by the C++ compiler at the call site, so a
+    // thunk is required to call a function without passing all of them (see
+    // `funcs_with_default_args_omitted`).
+    if let Ok(cc_func) = db.ir().find_decl::<Rc<Func>>(func.id) {
+        if cc_func.params.len() != func.params.len() {
+            return false;
+        }
+    }
     // ## Virtual functions
     //
     // When calling virtual `A::Method()`, it's not necessarily the case that we'll
@@ -293,6 +327,14 @@ fn can_skip_cc_thunk(db: &dyn BindingsGenerator, func: &Func) -> bool {
     if !func.has_c_calling_convention {
         return false;
     }
+    // ## Translating C++ exceptions.
+    //
+    // The thunk catches the exceptions thrown by the C++ function, so that they
+    // can be returned to Rust as `Err(CcException)` (see
+    // `translates_cc_exceptions`).
+    if translates_cc_exceptions(&db.ir(), func) {
+        return false;
+    }
 
     // ## Returning structs be value.
     //
@@ -329,10 +371,29 @@ fn can_skip_cc_thunk(db: &dyn BindingsGenerator, func: &Func) -> bool {
             }
         }
     }
+    // ## Rvalue reference parameters taken by value.
+    //
+    // The thunk materializes the rvalue reference from the value passed by Rust (see
+    // `is_rvalue_reference_taken_by_move`).
+    if func.params.iter().any(is_rvalue_reference_taken_by_move) {
+        return false;
+    }
 
     true
 }
 
+/// Returns true if `param` is an rvalue reference (`T&&`) without a lifetime, which the
+/// bindings take as a `T` by value (see `MappedType::RValueReferenceByMove` in `ir.h`).
+fn is_rvalue_reference_taken_by_move(param: &FuncParam) -> bool {
+    param.type_.cc_type.name.as_deref() == Some("&&")
+        && !param
+            .type_
+            .rs_type
+            .name
+            .as_deref()
+            .map_or(false, |name| name.starts_with("#RvalueReference"))
+}
+
 /// Uniquely identifies a generated Rust function.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct FunctionId {
@@ -358,6 +419,11 @@ fn cxx_function_name(func: &Func, ir: &IR) -> String {
         UnqualifiedIdentifier::Constructor => {
             record.expect("constructor must be associated with a record").to_string()
         }
+        UnqualifiedIdentifier::Conversion => {
+            let target_type = format_cc_type_inner(&func.return_type.cc_type, ir, true)
+                .map_or_else(|_| "<unsupported type>".to_string(), |ty| ty.to_string());
+            format!("operator {target_type}")
+        }
     };
 
     if let Some(record_name) = record {
@@ -430,6 +496,9 @@ enum TraitName {
     PartialEq { params: Rc<[RsTypeKind]> },
     /// The PartialOrd trait.
     PartialOrd { params: Rc<[RsTypeKind]> },
+    /// The Index trait, with the index type as the single param and the
+    /// referent of the returned reference as the `Output` associated type.
+    Index { params: Rc<[RsTypeKind]>, output: RsTypeKind },
     /// Any other trait, e.g. Eq.
     Other { name: Rc<str>, params: Rc<[RsTypeKind]>, is_unsafe_fn: bool },
 }
@@ -442,6 +511,7 @@ impl TraitName {
             | Self::UnpinConstructor { params, .. }
             | Self::PartialEq { params }
             | Self::PartialOrd { params }
+            | Self::Index { params, .. }
             | Self::Other { params, .. } => params.iter(),
         }
     }
@@ -484,6 +554,11 @@ impl TraitName {
                     quote! {PartialOrd #formatted_params}
                 }
             }
+            Self::Index { params, .. } => {
+                let formatted_params =
+                    format_generic_params_replacing_by_self(&**params, trait_record);
+                quote! {::core::ops::Index #formatted_params}
+            }
             Self::CtorNew(arg_types) => {
                 let formatted_arg_types =
                     format_tuple_except_singleton_replacing_by_self(arg_types, trait_record);
@@ -625,6 +700,16 @@ enum ImplFor {
     /// }
     /// ```
     RefT,
+    /// Implement the trait for the return type of the function, which takes
+    /// `T` as a regular (i.e. non-`self`) parameter.  This is used for the
+    /// `From` impls of conversion functions.
+    ///
+    /// ```
+    /// impl<'a> From<&'a T> for U {
+    ///     fn from(__this: &'a T) -> U;
+    /// }
+    /// ```
+    ReturnType,
 }
 
 /// Returns whether an argument of this type causes ADL to include the `record`.
@@ -741,6 +826,12 @@ fn api_func_shape(
     let ir = db.ir();
     let op_meta = &*OPERATOR_METADATA;
 
+    // Protected member functions can't be called from the thunks. They are only
+    // imported for overriding virtual methods (see `cc_struct_overrides_impl`).
+    if func.member_func_metadata.as_ref().map_or(false, |meta| meta.is_protected) {
+        return Ok(None);
+    }
+
     let maybe_record: Option<&Rc<Record>> = ir.record_for_member_func(func);
     let has_pointer_params = param_types.iter().any(|p| matches!(p, RsTypeKind::Pointer { .. }));
     let impl_kind: ImplKind;
@@ -876,6 +967,72 @@ fn api_func_shape(
                 _ => bail!("operator< where operator== is missing."),
             }
         }
+        UnqualifiedIdentifier::Operator(op) if op.name.as_ref() == "[]" => {
+            assert_eq!(
+                param_types.len(),
+                2,
+                "Unexpected number of parameters in operator[]: {func:?}"
+            );
+            let record =
+                maybe_record.ok_or_else(|| anyhow!("operator[] must be a member function."))?;
+            let this_lifetime = match &param_types[0] {
+                RsTypeKind::Reference { mutability: Mutability::Const, lifetime, .. }
+                    if param_types[0].is_ref_to(record) =>
+                {
+                    lifetime.clone()
+                }
+                RsTypeKind::Reference { mutability: Mutability::Mut, .. } => {
+                    // Binding it as `IndexMut` would require a matching const operator[] (to
+                    // implement the `Index` supertrait).
+                    bail!("Non-const operator[] is not supported")
+                }
+                _ => bail!("operator[] where `this` is not a const reference to the record"),
+            };
+            let return_type = db
+                .rs_type_kind(func.return_type.rs_type.clone())
+                .with_context(|| format!("Failed to format return type for {:?}", &func))?;
+            let output = match return_type {
+                RsTypeKind::Reference { referent, mutability: Mutability::Const, lifetime }
+                    if lifetime == this_lifetime =>
+                {
+                    (*referent).clone()
+                }
+                _ => bail!(
+                    "operator[] that doesn't return a const reference with the lifetime of `this`"
+                ),
+            };
+            materialize_ctor_in_caller(func, param_types);
+            func_name = make_rs_ident("index");
+            impl_kind = ImplKind::new_trait(
+                TraitName::Index { params: Rc::from(&param_types[1..]), output },
+                record.clone(),
+                /* format_first_param_as_self= */ true,
+                /* force_const_reference_params= */ false,
+            )?;
+        }
+        UnqualifiedIdentifier::Operator(op) if op.name.as_ref() == "()" => {
+            let record =
+                maybe_record.ok_or_else(|| anyhow!("operator() must be a member function."))?;
+            let first_param = param_types
+                .first()
+                .ok_or_else(|| anyhow!("Missing `__this` parameter in operator(): {:?}", func))?;
+            // Stable Rust doesn't allow implementing the `Fn*` traits, so `operator()`
+            // is bound as an inherent `call` (or `call_mut`) method instead.
+            let is_const = match first_param {
+                RsTypeKind::Reference { mutability, .. }
+                | RsTypeKind::Pointer { mutability, .. } => *mutability == Mutability::Const,
+                RsTypeKind::RvalueReference { .. } => {
+                    bail!("Not yet supported for rvalue references (b/219826128)")
+                }
+                _ => bail!("operator() where `this` is not a reference to the record"),
+            };
+            func_name = make_rs_ident(if is_const { "call" } else { "call_mut" });
+            impl_kind = ImplKind::Struct {
+                record: record.clone(),
+                format_first_param_as_self: first_param.is_ref_to(record),
+                is_unsafe: has_pointer_params,
+            };
+        }
         UnqualifiedIdentifier::Operator(op) if op.name.as_ref() == "=" => {
             assert_eq!(
                 param_types.len(),
@@ -1010,8 +1167,62 @@ fn api_func_shape(
                 );
             }
         },
+        UnqualifiedIdentifier::Conversion => {
+            let record = maybe_record
+                .ok_or_else(|| anyhow!("Conversion functions must be member functions."))?;
+            let first_param = param_types.first().ok_or_else(|| {
+                anyhow!("Missing `__this` parameter in a conversion function: {:?}", func)
+            })?;
+            if let Some(rs_name) = &func.rs_name {
+                // `explicit` conversion functions (and the ones with a `crubit_rust_name`
+                // annotation) are bound as inherent methods.
+                func_name = make_rs_ident(rs_name);
+                impl_kind = ImplKind::Struct {
+                    record: record.clone(),
+                    format_first_param_as_self: first_param.is_ref_to(record),
+                    is_unsafe: has_pointer_params,
+                };
+            } else {
+                // Implicit conversion functions are bound as `impl From<&T> for U` (and, for
+                // Unpin `T`, also as `impl From<T> for U` - see `generate_func`).
+                match first_param {
+                    RsTypeKind::Pointer { .. } => bail!(
+                        "`self` has no lifetime. Use lifetime annotations or `#pragma clang lifetime_elision` to create bindings for this function."
+                    ),
+                    _ if first_param.is_shared_ref_to(record) => {}
+                    _ => bail!("Non-const implicit conversion functions are not supported"),
+                }
+                let return_type = db
+                    .rs_type_kind(func.return_type.rs_type.clone())
+                    .with_context(|| format!("Failed to format return type for {:?}", &func))?;
+                match return_type.unalias() {
+                    RsTypeKind::Record { record: target_record, .. }
+                        if target_record.is_unpin() && target_record != record => {}
+                    RsTypeKind::Other { type_args, .. } if type_args.is_empty() => {}
+                    _ => bail!(
+                        "Implicit conversions to `{}` are not supported",
+                        return_type.to_token_stream()
+                    ),
+                }
+                func_name = make_rs_ident("from");
+                impl_kind = ImplKind::Trait {
+                    record: record.clone(),
+                    trait_name: TraitName::Other {
+                        name: Rc::from("From"),
+                        params: Rc::from([first_param.clone()]),
+                        is_unsafe_fn: false,
+                    },
+                    impl_for: ImplFor::ReturnType,
+                    trait_generic_params: Rc::new([]),
+                    format_first_param_as_self: false,
+                    drop_return: false,
+                    associated_return_type: None,
+                    force_const_reference_params: false,
+                };
+            }
+        }
         UnqualifiedIdentifier::Identifier(id) => {
-            func_name = make_rs_ident(&id.identifier);
+            func_name = make_rs_ident(&get_func_rs_name(func, id));
             match maybe_record {
                 None => {
                     impl_kind = ImplKind::None { is_unsafe: has_pointer_params };
@@ -1257,10 +1468,53 @@ fn materialize_ctor_in_caller(func: &Func, params: &mut [RsTypeKind]) {
 ///    destructor might be mapped to no `Drop` impl at all.)
 ///  * `Ok((rs_api, rs_thunk, function_id))`: The Rust function definition,
 ///    thunk FFI definition, and function ID.
+/// Returns the Rust name of the function `func` named `id` in C++.
+fn get_func_rs_name(func: &Func, id: &Identifier) -> String {
+    match (func.rs_name.as_deref(), func.template_args.as_deref()) {
+        (Some(rs_name), _) => rs_name.to_string(),
+        (None, None) => id.identifier.to_string(),
+        (None, Some(template_args)) => {
+            format!("{}_{}", id.identifier, format_template_args_as_rs_name_suffix(template_args))
+        }
+    }
+}
+
+/// Returns the variants of `func` that omit some of its trailing parameters
+/// with default arguments.  For example, for `void f(int a, int b = 0, int c =
+/// 0)` these are `f_without_c(a, b)` and `f_without_b_c(a)`.
+///
+/// The default arguments are filled in by the C++ thunks of these variants.
+fn funcs_with_default_args_omitted(func: &Func) -> Vec<Rc<Func>> {
+    // Default arguments of constructors and operators are not supported.
+    let UnqualifiedIdentifier::Identifier(id) = &func.name else {
+        return vec![];
+    };
+    let rs_name = get_func_rs_name(func, id);
+    (1..=func.num_default_params)
+        .map(|num_omitted| {
+            let num_params = func.params.len() - num_omitted;
+            let omitted_names = func.params[num_params..]
+                .iter()
+                .map(|p| p.identifier.identifier.as_ref())
+                .join("_");
+            Rc::new(Func {
+                rs_name: Some(format!("{rs_name}_without_{omitted_names}").into()),
+                mangled_name: format!("{}__without_{omitted_names}", func.mangled_name).into(),
+                params: func.params[..num_params].to_vec(),
+                num_default_params: func.num_default_params - num_omitted,
+                ..func.clone()
+            })
+        })
+        .collect()
+}
+
 fn generate_func(
     db: &dyn BindingsGenerator,
     func: Rc<Func>,
 ) -> Result<Option<(Rc<GeneratedItem>, Rc<FunctionId>)>> {
+    if func.is_variadic {
+        return generate_variadic_func(db, &func).map(Some);
+    }
     let ir = db.ir();
     let crate_root_path = crate_root_path_tokens(&ir);
     let mut features = BTreeSet::new();
@@ -1350,6 +1604,32 @@ fn generate_func(
         &mut return_type,
     )?;
 
+    let translates_cc_exceptions = translates_cc_exceptions(&ir, &func);
+    if translates_cc_exceptions {
+        // The exceptions thrown by constructors and operators (which are bound as trait impls,
+        // with signatures that can't return a `Result`), and by functions that return their value
+        // through an out parameter, are not translated.
+        if let ImplKind::Trait { .. } = impl_kind {
+            bail!(
+                "Translating C++ exceptions is not supported yet for functions bound as trait \
+                 impls (consider declaring the C++ function `noexcept`)"
+            );
+        }
+        if !return_type.is_c_abi_compatible_by_value() {
+            bail!(
+                "Translating C++ exceptions is not supported yet for functions returning \
+                 '{type}' (consider declaring the C++ function `noexcept`)",
+                type = quote! {#return_type}
+            );
+        }
+        let value_type = if quoted_return_type.is_empty() {
+            quote! {()}
+        } else {
+            quoted_return_type
+        };
+        quoted_return_type = quote! { ::core::result::Result<#value_type, ::cc_std::CcException> };
+    }
+
     let api_func_def = {
         let thunk_ident = thunk_ident(&func);
         let func_body = match &impl_kind {
@@ -1376,7 +1656,52 @@ fn generate_func(
                 // not generate the thunk at all, but this would be a bit of extra work.
                 //
                 // TODO(jeanpierreda): separately handle non-Unpin and non-trivial types.
-                let mut body = if return_type.is_c_abi_compatible_by_value() {
+                let mut body = if let Some(value_type) = return_type.optional_value_type() {
+                    quote! {
+                        let mut __return = ::core::mem::MaybeUninit::<#value_type>::uninit();
+                        if #crate_root_path::detail::#thunk_ident(
+                            __return.as_mut_ptr()
+                            #( , #clone_prefixes #thunk_args #clone_suffixes )*
+                        ) {
+                            ::core::option::Option::Some(__return.assume_init())
+                        } else {
+                            ::core::option::Option::None
+                        }
+                    }
+                } else if return_type.unique_ptr_pointee_type().is_some() {
+                    let delete_thunk_ident = return_value_thunk_ident(&func, "delete");
+                    quote! {
+                        ::cc_std::UniquePtr::from_raw(
+                            #crate_root_path::detail::#thunk_ident(
+                                #( #clone_prefixes #thunk_args #clone_suffixes ),*
+                            ),
+                            #crate_root_path::detail::#delete_thunk_ident,
+                        )
+                    }
+                } else if return_type.shared_ptr_pointee_type().is_some() {
+                    let get_thunk_ident = return_value_thunk_ident(&func, "get");
+                    let clone_thunk_ident = return_value_thunk_ident(&func, "clone");
+                    let delete_thunk_ident = return_value_thunk_ident(&func, "delete");
+                    quote! {
+                        ::cc_std::SharedPtr::from_raw(
+                            #crate_root_path::detail::#thunk_ident(
+                                #( #clone_prefixes #thunk_args #clone_suffixes ),*
+                            ),
+                            #crate_root_path::detail::#get_thunk_ident,
+                            #crate_root_path::detail::#clone_thunk_ident,
+                            #crate_root_path::detail::#delete_thunk_ident,
+                        )
+                    }
+                } else if translates_cc_exceptions {
+                    quote! {
+                        let mut __exception = ::cc_std::CcExceptionSlot::new();
+                        let __result = #crate_root_path::detail::#thunk_ident(
+                            #( #clone_prefixes #thunk_args #clone_suffixes , )*
+                            &mut __exception
+                        );
+                        __exception.into_result(__result)
+                    }
+                } else if return_type.is_c_abi_compatible_by_value() {
                     quote! {
                         #crate_root_path::detail::#thunk_ident(
                             #( #clone_prefixes #thunk_args #clone_suffixes ),*
@@ -1516,11 +1841,21 @@ fn generate_func(
         Some(&func.source_loc),
         db.generate_source_loc_doc_comment(),
     );
+    // `#[must_use]` has no effect on trait methods, and Clippy warns about `#[must_use]` functions
+    // that don't return anything.
+    let must_use = if quoted_return_type.is_empty() {
+        quote! {}
+    } else {
+        generate_must_use(func.nodiscard.as_deref())
+    };
+    // `#[deprecated]` has no effect on trait methods (and `rustc` rejects it there), so just like
+    // `#[must_use]` it is only generated for free functions and inherent methods.
+    let deprecated = generate_deprecated(func.deprecated.as_deref());
     let api_func: TokenStream;
     let function_id: FunctionId;
     match impl_kind {
         ImplKind::None { .. } => {
-            api_func = quote! { #doc_comment #api_func_def };
+            api_func = quote! { #doc_comment #must_use #deprecated #api_func_def };
             function_id = FunctionId {
                 self_type: None,
                 function_path: syn::parse2(quote! { #namespace_qualifier #func_name }).unwrap(),
@@ -1528,7 +1863,9 @@ fn generate_func(
         }
         ImplKind::Struct { record, .. } => {
             let record_name = make_rs_ident(record.rs_name.as_ref());
-            api_func = quote! { impl #record_name { #doc_comment #api_func_def } };
+            api_func = quote! {
+                impl #record_name { #doc_comment #must_use #deprecated #api_func_def }
+            };
             function_id = FunctionId {
                 self_type: None,
                 function_path: syn::parse2(quote! {
@@ -1554,11 +1891,16 @@ fn generate_func(
                 quote! {
                     type #name = #quoted_return_type;
                 }
+            } else if let TraitName::Index { ref output, .. } = trait_name {
+                let quoted_output = output.to_token_stream_replacing_by_self(Some(&trait_record));
+                quote! {
+                    type Output = #quoted_output;
+                }
             } else if let TraitName::PartialOrd { ref params } = trait_name {
                 let param = params.get(0).ok_or_else(|| anyhow!("No parameter to PartialOrd"))?;
                 let quoted_param_or_self = match impl_for {
                     ImplFor::T => param.to_token_stream_replacing_by_self(Some(&trait_record)),
-                    ImplFor::RefT => quote! { #param },
+                    ImplFor::RefT | ImplFor::ReturnType => quote! { #param },
                 };
                 quote! {
                     #[inline(always)]
@@ -1605,10 +1947,30 @@ fn generate_func(
                         extra_items = quote! {}
                     }
                 }
+                TraitName::Other { .. }
+                    if impl_for == ImplFor::ReturnType && trait_record.is_unpin() =>
+                {
+                    // Unpin values can also be converted by value.
+                    extra_items = quote! {
+                        impl From<#record_name> for #return_type {
+                            #[inline(always)]
+                            fn from(value: #record_name) -> Self {
+                                <Self as From<&#record_name>>::from(&value)
+                            }
+                        }
+                    };
+                }
                 _ => {
                     extra_items = quote! {};
                 }
             };
+            let self_type = if impl_for == ImplFor::ReturnType {
+                quote! { #return_type }
+            } else {
+                let record_qualifier =
+                    namespace_qualifier_of_item(trait_record.id, &ir)?.format_for_rs();
+                quote! { #record_qualifier #record_name }
+            };
             let (trait_name_without_trait_record, impl_for) = match impl_for {
                 ImplFor::T => (
                     trait_name.to_token_stream_removing_trait_record(Some(&trait_record)),
@@ -1618,6 +1980,7 @@ fn generate_func(
                     let param = &param_types[0];
                     (quote! { #trait_name }, quote! { #param })
                 }
+                ImplFor::ReturnType => (quote! { #trait_name }, quote! { #return_type }),
             };
             api_func = quote! {
                 #doc_comment
@@ -1627,10 +1990,8 @@ fn generate_func(
                 }
                 #extra_items
             };
-            let record_qualifier =
-                namespace_qualifier_of_item(trait_record.id, &ir)?.format_for_rs();
             function_id = FunctionId {
-                self_type: Some(syn::parse2(quote! { #record_qualifier #record_name }).unwrap()),
+                self_type: Some(syn::parse2(self_type).unwrap()),
                 function_path: syn::parse2(quote! { #trait_name :: #func_name }).unwrap(),
             };
         }
@@ -1646,6 +2007,87 @@ fn generate_func(
     Ok(Some((Rc::new(generated_item), Rc::new(function_id))))
 }
 
+/// Generates the bindings of a C variadic function (e.g. `printf`).
+///
+/// Neither a Rust wrapper nor a C++ thunk can forward the variadic arguments,
+/// so the bindings declare the C++ function directly in an `extern "C"` block
+/// (which means that calling it is always `unsafe`).
+fn generate_variadic_func(
+    db: &dyn BindingsGenerator,
+    func: &Func,
+) -> Result<(Rc<GeneratedItem>, Rc<FunctionId>)> {
+    let ir = db.ir();
+    let UnqualifiedIdentifier::Identifier(id) = &func.name else {
+        bail!("Variadic operators, constructors and destructors are not supported");
+    };
+    ensure!(func.member_func_metadata.is_none(), "Variadic member functions are not supported");
+    ensure!(
+        !func.params.is_empty(),
+        "Variadic functions without named parameters are not supported"
+    );
+    ensure!(
+        func.has_c_calling_convention,
+        "Variadic functions with a custom calling convention are not supported"
+    );
+    // C variadic functions can't be called through a thunk, so they need to be
+    // exported by the C++ library.
+    ensure!(
+        !func.is_inline && !func.has_internal_linkage,
+        "Inline variadic functions and variadic functions with internal linkage are not supported"
+    );
+    ensure!(
+        !translates_cc_exceptions(&ir, func),
+        "Potentially-throwing variadic functions can't translate C++ exceptions"
+    );
+    let param_types = func
+        .params
+        .iter()
+        .map(|p| db.rs_type_kind(p.type_.rs_type.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    let return_type = db.rs_type_kind(func.return_type.rs_type.clone())?;
+    for type_ in param_types.iter().chain([&return_type]) {
+        ensure!(
+            type_.is_c_abi_compatible_by_value(),
+            "Variadic functions can't take or return `{}` by value",
+            type_.to_token_stream()
+        );
+    }
+
+    let func_name = make_rs_ident(&get_func_rs_name(func, id));
+    let param_idents = func.params.iter().map(|p| make_rs_ident(&p.identifier.identifier));
+    let lifetimes = unique_lifetimes(param_types.iter().chain([&return_type])).collect_vec();
+    let generic_params = format_generic_params(&lifetimes, std::iter::empty::<syn::Ident>());
+    let return_type_fragment = return_type.format_as_return_type_fragment(None);
+    let link_name = func.mangled_name.as_ref();
+    let doc_comment = generate_doc_comment(
+        func.doc_comment.as_deref(),
+        Some(&func.source_loc),
+        db.generate_source_loc_doc_comment(),
+    );
+    let item = quote! {
+        extern "C" {
+            #doc_comment
+            #[link_name = #link_name]
+            pub fn #func_name #generic_params(
+                #( #param_idents: #param_types, )* ...
+            ) #return_type_fragment;
+        }
+    };
+
+    let namespace_qualifier = namespace_qualifier_of_item(func.id, &ir)?.format_for_rs();
+    let function_id = FunctionId {
+        self_type: None,
+        function_path: syn::parse2(quote! { #namespace_qualifier #func_name }).unwrap(),
+    };
+    // Variadic functions are only bound for targets that use the experimental Crubit features.
+    let generated_item = GeneratedItem {
+        item,
+        crubit_features: ir::CrubitFeature::Experimental.into(),
+        ..Default::default()
+    };
+    Ok((Rc::new(generated_item), Rc::new(function_id)))
+}
+
 /// The function signature for a function's bindings.
 struct BindingsSignature {
     /// The lifetime parameters for the Rust function.
@@ -1727,7 +2169,46 @@ fn function_signature(
             } else {
                 quote! {#type_}
             };
-            if type_.is_c_abi_compatible_by_value() {
+            if let Some(value_type) = type_.optional_value_type() {
+                api_params.push(quote! {#ident: #quoted_type_or_self});
+                thunk_args.push(quote! {
+                    match &#ident {
+                        ::core::option::Option::Some(value) => value as *const #value_type,
+                        ::core::option::Option::None => ::core::ptr::null(),
+                    }
+                });
+            } else if type_.unique_ptr_pointee_type().is_some() {
+                api_params.push(quote! {#ident: #quoted_type_or_self});
+                thunk_args.push(quote! {::cc_std::UniquePtr::into_raw(#ident)});
+            } else if type_.shared_ptr_pointee_type().is_some() {
+                api_params.push(quote! {#ident: #quoted_type_or_self});
+                thunk_args.push(quote! {::cc_std::SharedPtr::into_raw(#ident)});
+            } else if type_.is_slice_ref() {
+                api_params.push(quote! {#ident: #quoted_type_or_self});
+                thunk_args.push(quote! {::cc_std::SliceRef::from(#ident)});
+            } else if let Some((mutability, closure_param_types, closure_return_type)) =
+                type_.closure_signature()
+            {
+                // `impl Fn` can't be used in the signature of a trait method.
+                if let ImplKind::Trait { .. } = &impl_kind {
+                    bail!("Closure parameters are not supported in trait implementations");
+                }
+                let args_type = quote! { ( #( #closure_param_types, )* ) };
+                let return_type = match closure_return_type {
+                    RsTypeKind::Unit => quote! { () },
+                    other_type => quote! { #other_type },
+                };
+                let from_closure = match mutability {
+                    Mutability::Const => quote! { from_fn },
+                    Mutability::Mut => quote! { from_fn_mut },
+                };
+                api_params.push(quote! {#ident: #quoted_type_or_self});
+                thunk_args.push(quote! {
+                    ::cc_std::RustClosure::#from_closure::<#args_type, #return_type, _>(
+                        &mut ::core::mem::ManuallyDrop::new(#ident)
+                    )
+                });
+            } else if type_.is_c_abi_compatible_by_value() {
                 api_params.push(quote! {#ident: #quoted_type_or_self});
                 thunk_args.push(quote! {#ident});
             } else {
@@ -1820,7 +2301,9 @@ fn function_signature(
             .ok_or_else(|| anyhow!("No parameter to format as 'self': {:?}", func))?;
         // If param_types[0] exists, so do api_params[0] and thunk_args[0].
         match impl_kind {
-            ImplKind::None { .. } => unreachable!(),
+            ImplKind::None { .. } | ImplKind::Trait { impl_for: ImplFor::ReturnType, .. } => {
+                unreachable!()
+            }
             ImplKind::Struct { .. } | ImplKind::Trait { impl_for: ImplFor::T, .. } => {
                 // In the ImplFor::T reference style (which is implied for ImplKind::Struct) the
                 // impl block is for `T`. The `self` parameter has a type determined by the
@@ -1886,6 +2369,14 @@ fn generate_func_thunk(
             )
         })?);
         out_param_ident = Some(param_idents.next().unwrap().clone());
+    } else if let Some(value_type) = return_type.optional_value_type() {
+        out_param = Some(quote! { *mut #value_type });
+        out_param_ident = Some(make_rs_ident("__return"));
+        return_type_fragment = quote! { -> bool };
+    } else if let Some(pointee_type) = return_type.unique_ptr_pointee_type() {
+        return_type_fragment = quote! { -> *mut #pointee_type };
+    } else if return_type.shared_ptr_pointee_type().is_some() {
+        return_type_fragment = quote! { -> *mut ::core::ffi::c_void };
     } else if !return_type.is_c_abi_compatible_by_value() {
         // For return types that can't be passed by value, create a new out parameter.
         // The lifetime doesn't matter, so we can insert a new anonymous lifetime here.
@@ -1896,22 +2387,61 @@ fn generate_func_thunk(
         return_type_fragment = quote! {};
     }
 
+    // The last parameter is the slot for the C++ exception thrown by the function, if any.
+    let (exception_param, exception_param_ident) = if translates_cc_exceptions(&db.ir(), func) {
+        (Some(quote! { *mut ::cc_std::CcExceptionSlot }), Some(make_rs_ident("__exception")))
+    } else {
+        (None, None)
+    };
+
     let thunk_ident = thunk_ident(func);
 
     let generic_params = format_generic_params(&lifetimes, std::iter::empty::<syn::Ident>());
     let param_idents = out_param_ident.as_ref().into_iter().chain(param_idents);
     let param_types = out_param.into_iter().chain(param_types.map(|t| {
-        if !t.is_c_abi_compatible_by_value() {
+        if let Some(value_type) = t.optional_value_type() {
+            quote! {*const #value_type}
+        } else if let Some(pointee_type) = t.unique_ptr_pointee_type() {
+            quote! {*mut #pointee_type}
+        } else if t.shared_ptr_pointee_type().is_some() {
+            quote! {*mut ::core::ffi::c_void}
+        } else if t.is_slice_ref() {
+            quote! {::cc_std::SliceRef}
+        } else if t.closure_signature().is_some() {
+            quote! {::cc_std::RustClosure}
+        } else if !t.is_c_abi_compatible_by_value() {
             quote! {&mut #t}
         } else {
             quote! {#t}
         }
     }));
+    let param_idents = param_idents.chain(exception_param_ident.as_ref());
+    let param_types = param_types.chain(exception_param);
+
+    let return_value_thunks = if let Some(pointee_type) = return_type.unique_ptr_pointee_type() {
+        let delete_thunk_ident = return_value_thunk_ident(func, "delete");
+        quote! {
+            pub(crate) fn #delete_thunk_ident(ptr: *mut #pointee_type);
+        }
+    } else if let Some(pointee_type) = return_type.shared_ptr_pointee_type() {
+        let get_thunk_ident = return_value_thunk_ident(func, "get");
+        let clone_thunk_ident = return_value_thunk_ident(func, "clone");
+        let delete_thunk_ident = return_value_thunk_ident(func, "delete");
+        quote! {
+            pub(crate) fn #get_thunk_ident(handle: *mut ::core::ffi::c_void) -> *mut #pointee_type;
+            pub(crate) fn #clone_thunk_ident(
+                handle: *mut ::core::ffi::c_void) -> *mut ::core::ffi::c_void;
+            pub(crate) fn #delete_thunk_ident(handle: *mut ::core::ffi::c_void);
+        }
+    } else {
+        quote! {}
+    };
 
     Ok(quote! {
         #thunk_attr
         pub(crate) fn #thunk_ident #generic_params( #( #param_idents: #param_types ),*
         ) #return_type_fragment ;
+        #return_value_thunks
     })
 }
 fn generate_doc_comment(
@@ -1935,6 +2465,26 @@ fn generate_doc_comment(
     quote! {#[doc = #doc_comment]}
 }
 
+/// Generates the `#[must_use]` attribute of the bindings of a `[[nodiscard]]`
+/// C++ declaration (see `ir::Func::nodiscard`).
+fn generate_must_use(nodiscard: Option<&str>) -> TokenStream {
+    match nodiscard {
+        None => quote! {},
+        Some("") => quote! {#[must_use]},
+        Some(message) => quote! {#[must_use = #message]},
+    }
+}
+
+/// Generates the `#[deprecated]` attribute of the bindings of a `[[deprecated]]`
+/// C++ declaration (see `ir::Func::deprecated`).
+fn generate_deprecated(deprecated: Option<&str>) -> TokenStream {
+    match deprecated {
+        None => quote! {},
+        Some("") => quote! {#[deprecated]},
+        Some(message) => quote! {#[deprecated = #message]},
+    }
+}
+
 fn format_generic_params<'a, T: ToTokens>(
     lifetimes: impl IntoIterator<Item = &'a Lifetime>,
     types: impl IntoIterator<Item = T>,
@@ -2056,10 +2606,42 @@ fn namespace_qualifier_of_item(item_id: ItemId, ir: &IR) -> Result<NamespaceQual
     Ok(NamespaceQualifier::new(namespaces.into_iter().rev()))
 }
 
+/// Returns the symbol that identifies the C++ record `cc_name` in
+/// `forward_declare::symbol!`.
+///
+/// The symbol is the fully-qualified C++ name of the record (e.g.
+/// `ns::Outer::Inner`), so that a forward declaration of a record matches its
+/// definition even if they are bound in different crates, and so that records
+/// with the same name in different namespaces don't match each other.
+fn forward_declare_symbol(
+    ir: &IR,
+    item_id: ItemId,
+    cc_name: &str,
+    enclosing_record_id: Option<ItemId>,
+) -> Result<String> {
+    let mut symbol = match enclosing_record_id {
+        Some(parent_id) => {
+            let parent = ir.find_decl::<Rc<Record>>(parent_id)?;
+            let parent_symbol =
+                forward_declare_symbol(ir, parent.id, &parent.cc_name, parent.enclosing_record_id)?;
+            format!("{parent_symbol}::")
+        }
+        None => namespace_qualifier_of_item(item_id, ir)?
+            .parts()
+            .map(|ns| format!("{ns}::"))
+            .collect::<String>(),
+    };
+    symbol.push_str(cc_name);
+    Ok(symbol)
+}
+
 /// Generates Rust source code for a given incomplete record declaration.
-fn generate_incomplete_record(incomplete_record: &IncompleteRecord) -> Result<GeneratedItem> {
+fn generate_incomplete_record(
+    ir: &IR,
+    incomplete_record: &IncompleteRecord,
+) -> Result<GeneratedItem> {
     let ident = make_rs_ident(incomplete_record.rs_name.as_ref());
-    let name = incomplete_record.rs_name.as_ref();
+    let name = forward_declare_symbol(ir, incomplete_record.id, &incomplete_record.cc_name, None)?;
     Ok(quote! {
         forward_declare::forward_declare!(
             pub #ident __SPACE__ = __SPACE__ forward_declare::symbol!(#name)
@@ -2386,14 +2968,14 @@ fn generate_record(
         quote! {}
     };
 
-    // TODO(b/227442773): After namespace support is added, use the fully-namespaced
-    // name.
-    let incomplete_symbol = record.cc_name.as_ref();
+    let incomplete_symbol =
+        forward_declare_symbol(&ir, record.id, &record.cc_name, record.enclosing_record_id)?;
     let incomplete_definition = quote! {
         forward_declare::unsafe_define!(forward_declare::symbol!(#incomplete_symbol), #qualified_ident);
     };
 
     let no_unique_address_accessors = cc_struct_no_unique_address_impl(db, record)?;
+    let anonymous_member_accessors = cc_struct_anonymous_member_accessors_impl(db, record)?;
     let mut record_generated_items = record
         .child_item_ids
         .iter()
@@ -2406,10 +2988,18 @@ fn generate_record(
         .collect::<Result<Vec<_>>>()?;
 
     record_generated_items.push(cc_struct_upcast_impl(record, &ir)?);
+    record_generated_items.push(cc_struct_bitfield_accessors_impl(db, record)?);
+    record_generated_items.push(cc_struct_overrides_impl(db, record)?);
+    record_generated_items.push(cc_struct_interface_impl(db, record)?);
 
     let mut items = vec![];
     let mut thunks_from_record_items = vec![];
-    let mut thunk_impls_from_record_items = vec![cc_struct_layout_assertion(db, record)?];
+    let mut thunk_impls_from_record_items = vec![];
+    // The C++ layout of anonymous structs and unions is verified by the
+    // assertions of the enclosing record.
+    if !record.is_anonymous_struct_or_union {
+        thunk_impls_from_record_items.push(cc_struct_layout_assertion(db, record)?);
+    }
     let mut assertions_from_record_items = vec![];
 
     for generated in record_generated_items {
@@ -2426,8 +3016,19 @@ fn generate_record(
         features.extend(generated.features.clone());
     }
 
+    let must_use = generate_must_use(record.nodiscard.as_deref());
+    let deprecated = generate_deprecated(record.deprecated.as_deref());
+    let cpp_type_annotation = if record.is_anonymous_struct_or_union {
+        quote! {}
+    } else {
+        let cpp_type = cc_qualified_name_for_record(record, &ir)?;
+        quote! { #[crubit_annotate::cpp_type = #cpp_type] }
+    };
     let record_tokens = quote! {
         #doc_comment
+        #cpp_type_annotation
+        #must_use
+        #deprecated
         #derives
         #recursively_pinned_attribute
         #[repr(#( #repr_attributes ),*)]
@@ -2440,6 +3041,8 @@ fn generate_record(
 
         #no_unique_address_accessors
 
+        #anonymous_member_accessors
+
         __NEWLINE__ __NEWLINE__
         #( #items __NEWLINE__ __NEWLINE__)*
     };
@@ -2540,32 +3143,58 @@ fn generate_derives(record: &Record) -> Vec<Ident> {
 }
 
 fn generate_enum(db: &Database, enum_: &Enum) -> Result<GeneratedItem> {
+    if enum_.is_closed {
+        return generate_closed_enum(db, enum_);
+    }
     let name = make_rs_ident(&enum_.identifier.identifier);
+    let doc_comment = generate_doc_comment(
+        enum_.doc_comment.as_deref(),
+        Some(&enum_.source_loc),
+        db.generate_source_loc_doc_comment(),
+    );
     let underlying_type = db.rs_type_kind(enum_.underlying_type.rs_type.clone())?;
+    let enumerator_doc_comments = enum_.enumerators.iter().map(|enumerator| {
+        generate_doc_comment(
+            enumerator.doc_comment.as_deref(),
+            None,
+            db.generate_source_loc_doc_comment(),
+        )
+    });
+    let enumerator_deprecations = enum_
+        .enumerators
+        .iter()
+        .map(|enumerator| generate_deprecated(enumerator.deprecated.as_deref()));
     let enumerator_names =
         enum_.enumerators.iter().map(|enumerator| make_rs_ident(&enumerator.identifier.identifier));
-    let enumerator_values = enum_.enumerators.iter().map(|enumerator| {
-        if underlying_type.is_bool() {
-            if enumerator.value.wrapped_value == 0 {
-                quote! {false}
-            } else {
-                quote! {true}
-            }
-        } else {
-            if enumerator.value.is_negative {
-                Literal::i64_unsuffixed(enumerator.value.wrapped_value as i64).into_token_stream()
-            } else {
-                Literal::u64_unsuffixed(enumerator.value.wrapped_value).into_token_stream()
-            }
-        }
-    });
+    let enumerator_values = enum_
+        .enumerators
+        .iter()
+        .map(|enumerator| format_integer_constant(&enumerator.value, &underlying_type));
+    // Unscoped enumerations are often used as bit flags (and in C++ their
+    // values implicitly convert to integers, so that they can be combined with
+    // the bitwise operators).
+    let bitwise_operators = if enum_.is_scoped {
+        quote! {}
+    } else {
+        generate_enum_bitwise_operators(db, enum_)
+    };
+
+    let must_use = generate_must_use(enum_.nodiscard.as_deref());
+    let deprecated = generate_deprecated(enum_.deprecated.as_deref());
 
     Ok(quote! {
+        #doc_comment
+        #must_use
+        #deprecated
         #[repr(transparent)]
         #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
         pub struct #name(#underlying_type);
         impl #name {
-            #(pub const #enumerator_names: #name = #name(#enumerator_values);)*
+            #(
+                #enumerator_doc_comments
+                #enumerator_deprecations
+                pub const #enumerator_names: #name = #name(#enumerator_values);
+            )*
         }
         impl From<#underlying_type> for #name {
             fn from(value: #underlying_type) -> #name {
@@ -2577,22 +3206,334 @@ fn generate_enum(db: &Database, enum_: &Enum) -> Result<GeneratedItem> {
                 value.0
             }
         }
+        #bitwise_operators
     }
     .into())
 }
 
-fn generate_type_alias(db: &Database, type_alias: &TypeAlias) -> Result<GeneratedItem> {
-    let ident = make_rs_ident(&type_alias.identifier.identifier);
+/// Generates a Rust `enum` for a closed C++ enum (see `Enum::is_closed`).
+///
+/// Enumerators with the same value as a preceding enumerator become associated
+/// constants (Rust `enum`s can't have multiple variants with the same
+/// discriminant).
+fn generate_closed_enum(db: &Database, enum_: &Enum) -> Result<GeneratedItem> {
+    let name = make_rs_ident(&enum_.identifier.identifier);
+    let underlying_type = db.rs_type_kind(enum_.underlying_type.rs_type.clone())?;
+    let repr = match underlying_type.unalias() {
+        RsTypeKind::Other { name: type_name, type_args }
+            if type_args.is_empty()
+                && matches!(
+                    &**type_name,
+                    "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" | "isize" | "usize"
+                ) =>
+        {
+            make_rs_ident(type_name)
+        }
+        _ => bail!(
+            "Closed enums are only supported with integral underlying types, not `{}`",
+            underlying_type.to_token_stream()
+        ),
+    };
+    if enum_.enumerators.is_empty() {
+        bail!("Closed enums without enumerators are not supported");
+    }
+    let mut variant_names_by_value = HashMap::new();
+    let mut variants = vec![];
+    let mut aliases = vec![];
+    let mut match_arms = vec![];
+    for enumerator in &enum_.enumerators {
+        let doc_comment = generate_doc_comment(
+            enumerator.doc_comment.as_deref(),
+            None,
+            db.generate_source_loc_doc_comment(),
+        );
+        let deprecated = generate_deprecated(enumerator.deprecated.as_deref());
+        let enumerator_name = make_rs_ident(&enumerator.identifier.identifier);
+        let value = format_integer_constant(&enumerator.value, &underlying_type);
+        match variant_names_by_value
+            .entry((enumerator.value.is_negative, enumerator.value.wrapped_value))
+        {
+            Entry::Occupied(entry) => {
+                let variant_name = entry.get();
+                aliases.push(quote! {
+                    #doc_comment
+                    #deprecated
+                    pub const #enumerator_name: #name = #name::#variant_name;
+                });
+            }
+            Entry::Vacant(entry) => {
+                variants.push(quote! {
+                    #doc_comment
+                    #deprecated
+                    #enumerator_name = #value,
+                });
+                match_arms.push(quote! {
+                    #value => ::core::result::Result::Ok(#name::#enumerator_name),
+                });
+                entry.insert(enumerator_name);
+            }
+        }
+    }
+    let aliases = if aliases.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            impl #name {
+                #( #aliases )*
+            }
+        }
+    };
+
     let doc_comment = generate_doc_comment(
-        type_alias.doc_comment.as_deref(),
-        Some(&type_alias.source_loc),
+        enum_.doc_comment.as_deref(),
+        Some(&enum_.source_loc),
         db.generate_source_loc_doc_comment(),
     );
-    let underlying_type = db
-        .rs_type_kind(type_alias.underlying_type.rs_type.clone())
-        .with_context(|| format!("Failed to format underlying type for {:?}", type_alias))?;
+    let must_use = generate_must_use(enum_.nodiscard.as_deref());
+    let deprecated = generate_deprecated(enum_.deprecated.as_deref());
+
     Ok(quote! {
         #doc_comment
+        #must_use
+        #deprecated
+        #[repr(#repr)]
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+        pub enum #name {
+            #( #variants )*
+        }
+        #aliases
+        impl ::core::convert::TryFrom<#underlying_type> for #name {
+            type Error = #underlying_type;
+            fn try_from(value: #underlying_type) -> ::core::result::Result<#name, #underlying_type> {
+                match value {
+                    #( #match_arms )*
+                    _ => ::core::result::Result::Err(value),
+                }
+            }
+        }
+        impl From<#name> for #underlying_type {
+            fn from(value: #name) -> #underlying_type {
+                value as #underlying_type
+            }
+        }
+    }
+    .into())
+}
+/// Returns the implementations of the bitwise operators for the newtype
+/// generated for `enum_`, except for the operators that are overloaded in C++
+/// (and so get their own bindings).
+fn generate_enum_bitwise_operators(db: &Database, enum_: &Enum) -> TokenStream {
+    let ir = db.ir();
+    let refers_to_enum = |param: &FuncParam| {
+        let rs_type = &param.type_.rs_type;
+        rs_type.decl_id == Some(enum_.id)
+            || rs_type.type_args.iter().any(|type_arg| type_arg.decl_id == Some(enum_.id))
+    };
+    let overloaded_operators: HashSet<&str> = ir
+        .functions()
+        .filter(|func| func.params.first().map_or(false, refers_to_enum))
+        .filter_map(|func| match &func.name {
+            UnqualifiedIdentifier::Operator(op) => Some(op.name.as_ref()),
+            _ => None,
+        })
+        .collect();
+
+    let name = make_rs_ident(&enum_.identifier.identifier);
+    let mut impls = vec![];
+    let binary_operators = [
+        ("&", "BitAnd", "bitand", quote! { & }),
+        ("|", "BitOr", "bitor", quote! { | }),
+        ("^", "BitXor", "bitxor", quote! { ^ }),
+    ];
+    for (cc_name, trait_name, method_name, op) in binary_operators {
+        if !overloaded_operators.contains(cc_name) {
+            let trait_name = make_rs_ident(trait_name);
+            let method_name = make_rs_ident(method_name);
+            impls.push(quote! {
+                impl ::core::ops::#trait_name for #name {
+                    type Output = #name;
+                    fn #method_name(self, other: #name) -> #name {
+                        #name(self.0 #op other.0)
+                    }
+                }
+            });
+        }
+        if !overloaded_operators.contains(format!("{}=", cc_name).as_str()) {
+            let assign_trait_name = make_rs_ident(&format!("{}Assign", trait_name));
+            let assign_method_name = make_rs_ident(&format!("{}_assign", method_name));
+            impls.push(quote! {
+                impl ::core::ops::#assign_trait_name for #name {
+                    fn #assign_method_name(&mut self, other: #name) {
+                        self.0 = self.0 #op other.0;
+                    }
+                }
+            });
+        }
+    }
+    // C++ `operator!` is bound to the Rust `Not` trait (see `OPERATOR_METADATA`).
+    if !overloaded_operators.contains("!") {
+        impls.push(quote! {
+            impl ::core::ops::Not for #name {
+                type Output = #name;
+                fn not(self) -> #name {
+                    #name(!self.0)
+                }
+            }
+        });
+    }
+    quote! { #( #impls )* }
+}
+
+/// Formats `value` as a literal of type `type_` (which has to be an integral
+/// type, or `bool`).
+fn format_integer_constant(value: &IntegerConstant, type_: &RsTypeKind) -> TokenStream {
+    if type_.is_bool() {
+        if value.wrapped_value == 0 {
+            quote! {false}
+        } else {
+            quote! {true}
+        }
+    } else {
+        if value.is_negative {
+            Literal::i64_unsuffixed(value.wrapped_value as i64).into_token_stream()
+        } else {
+            Literal::u64_unsuffixed(value.wrapped_value).into_token_stream()
+        }
+    }
+}
+
+fn generate_constant(db: &Database, constant: &Constant) -> Result<GeneratedItem> {
+    let ident = make_rs_ident(&constant.identifier.identifier);
+    let doc_comment = generate_doc_comment(
+        constant.doc_comment.as_deref(),
+        Some(&constant.source_loc),
+        db.generate_source_loc_doc_comment(),
+    );
+    let type_ = db
+        .rs_type_kind(constant.type_.rs_type.clone())
+        .with_context(|| format!("Failed to format the type of {:?}", constant))?;
+    let (type_, value) = match &constant.value {
+        ConstantValue::Integer(value) => {
+            (type_.to_token_stream(), format_integer_constant(value, &type_))
+        }
+        ConstantValue::Float(value) => {
+            let value = match type_.unalias() {
+                RsTypeKind::Other { name, .. } if &**name == "f32" => {
+                    // `f32` values are widened to `f64` without any loss of precision.
+                    Literal::f32_unsuffixed(value.value() as f32).into_token_stream()
+                }
+                _ => Literal::f64_unsuffixed(value.value()).into_token_stream(),
+            };
+            (type_.to_token_stream(), value)
+        }
+        // String literals are bound as `&CStr` (rather than as the `*const c_char` they
+        // decay to).  The importer guarantees that they don't contain interior NULs.
+        ConstantValue::String(value) => {
+            let bytes = Literal::byte_string(format!("{value}\0").as_bytes());
+            (
+                quote! { &::core::ffi::CStr },
+                quote! { unsafe { ::core::ffi::CStr::from_bytes_with_nul_unchecked(#bytes) } },
+            )
+        }
+    };
+    let constant_item = quote! {
+        #doc_comment
+        pub const #ident: #type_ = #value;
+    };
+    let Some(record_id) = constant.enclosing_record_id else {
+        let mut generated_item = GeneratedItem::from(constant_item);
+        if constant.is_macro {
+            generated_item.crubit_features |= ir::CrubitFeature::Macros;
+        }
+        return Ok(generated_item);
+    };
+    // Static data members become associated constants.
+    let ir = db.ir();
+    let record: &Rc<Record> = ir.find_decl(record_id)?;
+    let record_ident = make_rs_ident(record.rs_name.as_ref());
+    Ok(quote! {
+        impl #record_ident {
+            #constant_item
+        }
+    }
+    .into())
+}
+
+/// Generates a getter (`S::name()`) and a setter (`S::set_name(value)`) for a
+/// mutable static data member.  Like accesses to a Rust `static mut`, both
+/// accessors are `unsafe`: the static data member may be concurrently accessed
+/// by other threads (or by C++ code).
+fn generate_static_data_member(
+    db: &Database,
+    static_data_member: &StaticDataMember,
+) -> Result<GeneratedItem> {
+    let ir = db.ir();
+    let identifier = &static_data_member.identifier.identifier;
+    let getter_ident = make_rs_ident(identifier);
+    let setter_ident = make_rs_ident(&format!("set_{identifier}"));
+    let doc_comment = generate_doc_comment(
+        static_data_member.doc_comment.as_deref(),
+        Some(&static_data_member.source_loc),
+        db.generate_source_loc_doc_comment(),
+    );
+    let type_ = db
+        .rs_type_kind(static_data_member.type_.rs_type.clone())
+        .with_context(|| format!("Failed to format the type of {:?}", static_data_member))?;
+    let cc_type = format_cc_type(&static_data_member.type_.cc_type, &ir)?;
+    let record: &Rc<Record> = ir.find_decl(static_data_member.enclosing_record_id)?;
+    let record_ident = make_rs_ident(record.rs_name.as_ref());
+    let cc_member_name = {
+        let record_name = cc_tagless_type_name_for_record(record, &ir)?;
+        let cc_ident = format_cc_ident(identifier);
+        quote! { #record_name :: #cc_ident }
+    };
+    let mangled_name = static_data_member.mangled_name.as_ref();
+    let getter_thunk_ident = format_ident!("__rust_thunk__{}__get", mangled_name);
+    let setter_thunk_ident = format_ident!("__rust_thunk__{}__set", mangled_name);
+    Ok(GeneratedItem {
+        item: quote! {
+            impl #record_ident {
+                #doc_comment
+                #[inline(always)]
+                pub unsafe fn #getter_ident() -> #type_ {
+                    crate::detail::#getter_thunk_ident()
+                }
+                #[inline(always)]
+                pub unsafe fn #setter_ident(value: #type_) {
+                    crate::detail::#setter_thunk_ident(value)
+                }
+            }
+        },
+        thunks: quote! {
+            pub(crate) fn #getter_thunk_ident() -> #type_;
+            pub(crate) fn #setter_thunk_ident(value: #type_);
+        },
+        thunk_impls: quote! {
+            extern "C" #cc_type #getter_thunk_ident() {
+                return #cc_member_name;
+            }
+            extern "C" void #setter_thunk_ident(#cc_type value) {
+                #cc_member_name = value;
+            }
+        },
+        ..Default::default()
+    })
+}
+
+fn generate_type_alias(db: &Database, type_alias: &TypeAlias) -> Result<GeneratedItem> {
+    let ident = make_rs_ident(&type_alias.identifier.identifier);
+    let doc_comment = generate_doc_comment(
+        type_alias.doc_comment.as_deref(),
+        Some(&type_alias.source_loc),
+        db.generate_source_loc_doc_comment(),
+    );
+    let underlying_type = db
+        .rs_type_kind(type_alias.underlying_type.rs_type.clone())
+        .with_context(|| format!("Failed to format underlying type for {:?}", type_alias))?;
+    let deprecated = generate_deprecated(type_alias.deprecated.as_deref());
+    Ok(quote! {
+        #doc_comment
+        #deprecated
         pub type #ident = #underlying_type;
     }
     .into())
@@ -2724,6 +3665,17 @@ struct GeneratedItem {
     crubit_features: flagset::FlagSet<ir::CrubitFeature>,
 }
 
+impl GeneratedItem {
+    fn extend(&mut self, other: GeneratedItem) {
+        self.item.extend(other.item);
+        self.thunks.extend(other.thunks);
+        self.thunk_impls.extend(other.thunk_impls);
+        self.assertions.extend(other.assertions);
+        self.features.extend(other.features);
+        self.crubit_features |= other.crubit_features;
+    }
+}
+
 impl From<TokenStream> for GeneratedItem {
     fn from(item: TokenStream) -> Self {
         GeneratedItem { item, ..Default::default() }
@@ -2792,32 +3744,19 @@ fn generate_item(
             return Ok(GeneratedItem::default());
         }
     }
-    let overloaded_funcs = db.overloaded_funcs();
     let generated_item = match item {
-        Item::Func(func) => match db.generate_func(func.clone()) {
-            Err(e) => generate_unsupported(
-                &make_unsupported_fn(func, &ir, format!("{e}").as_str())?,
-                errors,
-                db.generate_source_loc_doc_comment(),
-            )?,
-            Ok(None) => GeneratedItem::default(),
-            Ok(Some((item, function_id))) => {
-                if overloaded_funcs.contains(&function_id) {
-                    generate_unsupported(
-                        &make_unsupported_fn(
-                            func,
-                            &ir,
-                            "Cannot generate bindings for overloaded function",
-                        )?,
-                        errors,
-                        db.generate_source_loc_doc_comment(),
-                    )?
-                } else {
-                    (*item).clone()
+        Item::Func(func) => {
+            let mut generated_item = generate_func_item(db, func, errors)?;
+            if let Ok(Some(_)) = db.generate_func(func.clone()) {
+                for func in funcs_with_default_args_omitted(func) {
+                    generated_item.extend(generate_func_item(db, &func, errors)?);
                 }
             }
-        },
-        Item::IncompleteRecord(incomplete_record) => generate_incomplete_record(incomplete_record)?,
+            generated_item
+        }
+        Item::IncompleteRecord(incomplete_record) => {
+            generate_incomplete_record(&ir, incomplete_record)?
+        }
         Item::Record(record) => generate_record(db, record, errors)?,
         Item::Enum(enum_) => generate_enum(db, enum_)?,
         Item::TypeAlias(type_alias) => {
@@ -2832,6 +3771,10 @@ fn generate_item(
                 generate_type_alias(db, type_alias)?
             }
         }
+        Item::Constant(constant) => generate_constant(db, constant)?,
+        Item::StaticDataMember(static_data_member) => {
+            generate_static_data_member(db, static_data_member)?
+        }
         Item::UnsupportedItem(unsupported) => {
             generate_unsupported(unsupported, errors, db.generate_source_loc_doc_comment())?
         }
@@ -2862,14 +3805,49 @@ fn generate_item(
     Ok(generated_item)
 }
 
+fn generate_func_item(
+    db: &Database,
+    func: &Rc<Func>,
+    errors: &mut dyn ErrorReporting,
+) -> Result<GeneratedItem> {
+    let ir = db.ir();
+    match db.generate_func(func.clone()) {
+        Err(e) => generate_unsupported(
+            &make_unsupported_fn(func, &ir, format!("{e}").as_str())?,
+            errors,
+            db.generate_source_loc_doc_comment(),
+        ),
+        Ok(None) => Ok(GeneratedItem::default()),
+        Ok(Some((item, function_id))) => {
+            if db.overloaded_funcs().contains(&function_id) {
+                generate_unsupported(
+                    &make_unsupported_fn(
+                        func,
+                        &ir,
+                        "Cannot generate bindings for overloaded function",
+                    )?,
+                    errors,
+                    db.generate_source_loc_doc_comment(),
+                )
+            } else {
+                Ok((*item).clone())
+            }
+        }
+    }
+}
+
 /// Identifies all functions having overloads that we can't import (yet).
 ///
 /// TODO(b/213280424): Implement support for overloaded functions.
 fn overloaded_funcs(db: &dyn BindingsGenerator) -> Rc<HashSet<Rc<FunctionId>>> {
     let mut seen_funcs = HashSet::new();
     let mut overloaded_funcs = HashSet::new();
-    for func in db.ir().functions() {
-        if let Ok(Some(f)) = db.generate_func(func.clone()) {
+    let ir = db.ir();
+    let funcs = ir.functions().flat_map(|func| {
+        std::iter::once(func.clone()).chain(funcs_with_default_args_omitted(func))
+    });
+    for func in funcs {
+        if let Ok(Some(f)) = db.generate_func(func) {
             let (.., function_id) = &f;
             if !seen_funcs.insert(function_id.clone()) {
                 overloaded_funcs.insert(function_id.clone());
@@ -2917,6 +3895,9 @@ fn generate_bindings_tokens(
     // For #![rustfmt::skip].
     features.insert(make_rs_ident("custom_inner_attributes"));
 
+    // For the `#[crubit_annotate::...]` annotations read by `cc_bindings_from_rs`.
+    features.insert(make_rs_ident("register_tool"));
+
     for top_level_item_id in ir.top_level_item_ids() {
         let item =
             ir.find_decl(*top_level_item_id).context("Failed to look up ir.top_level_item_ids")?;
@@ -2955,12 +3936,19 @@ fn generate_bindings_tokens(
         }
     };
 
+    // The annotations let `cc_bindings_from_rs` refer to the original C++ types
+    // (rather than generating new C++ bindings for their Rust bindings) when the
+    // types appear in the API of a Rust crate (see `cpp_type` in
+    // `generate_record`).
+    let cpp_header_annotations = ir.public_headers().map(|hdr| hdr.name.as_ref()).collect_vec();
+
     let features = if features.is_empty() {
         quote! {}
     } else {
         quote! {
             #![feature( #(#features),* )]  __NEWLINE__
-            #![allow(stable_features)]
+            #![allow(stable_features)] __NEWLINE__
+            #![register_tool(crubit_annotate)]
         }
     };
 
@@ -2979,7 +3967,11 @@ fn generate_bindings_tokens(
             #![allow(non_snake_case)] __NEWLINE__
             #![allow(non_upper_case_globals)] __NEWLINE__
 
-            #![deny(warnings)] __NEWLINE__ __NEWLINE__
+            // The bindings refer to the `#[deprecated]` items they define:
+            #![allow(deprecated)] __NEWLINE__
+
+            #![deny(warnings)] __NEWLINE__
+            #( #![crubit_annotate::cpp_header = #cpp_header_annotations] __NEWLINE__ )* __NEWLINE__
 
             #( #items __NEWLINE__ __NEWLINE__ )*
 
@@ -2991,9 +3983,11 @@ fn generate_bindings_tokens(
     })
 }
 
-/// Formats a C++ identifier.  Panics if `ident` is a C++ reserved keyword.
+/// Formats a C++ identifier of an existing C++ entity (without escaping it).
+/// Panics if `ident` is a C++ reserved keyword.
 fn format_cc_ident(ident: &str) -> TokenStream {
-    code_gen_utils::format_cc_ident(ident).expect("IR should only contain valid C++ identifiers")
+    code_gen_utils::format_unescaped_cc_ident(ident)
+        .expect("IR should only contain valid C++ identifiers")
 }
 
 /// Returns Some(crate_ident) if this is an imported crate.
@@ -3190,10 +4184,106 @@ impl RsTypeKind {
             // TODO(b/274177296): Return `true` for structs where bindings replicate the type of
             // all the fields.
             RsTypeKind::Record { .. } => false,
+            // `Option<T>` doesn't have the same layout as `std::optional<T>` (the thunks convert
+            // between the two - see `optional_value_type`).
+            _ if self.optional_value_type().is_some() => false,
+            // `UniquePtr<T>` is passed to and from the thunks as a raw `*mut T` pointer (see
+            // `unique_ptr_pointee_type`).
+            _ if self.unique_ptr_pointee_type().is_some() => false,
+            // `SharedPtr<T>` is passed to and from the thunks as a raw pointer to a heap-allocated
+            // `std::shared_ptr<T>` (see `shared_ptr_pointee_type`).
+            _ if self.shared_ptr_pointee_type().is_some() => false,
+            // `&str` and slices are passed to the thunks as a `::cc_std::SliceRef` (see
+            // `is_slice_ref`).
+            _ if self.is_slice_ref() => false,
+            // Closures are passed to the thunks as a `::cc_std::RustClosure` (see
+            // `closure_signature`).
+            _ if self.closure_signature().is_some() => false,
             _ => true,
         }
     }
 
+    /// Returns `T` if the type is the `Option<T>` that a by-value `std::optional<T>`
+    /// parameter or return value is mapped to.
+    ///
+    /// Such values are passed to the thunks as nullable `*const T` pointers, and are returned
+    /// from the thunks through a `*mut T` out parameter (which is only initialized if the thunk
+    /// returns `true`).
+    pub fn optional_value_type(&self) -> Option<&RsTypeKind> {
+        match self {
+            RsTypeKind::Other { name, type_args } if &**name == "::core::option::Option" => {
+                type_args.first()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `T` if the type is the `::cc_std::UniquePtr<T>` that a by-value
+    /// `std::unique_ptr<T>` parameter or return value is mapped to.
+    ///
+    /// Such values are passed to and returned from the thunks as raw `*mut T` pointers that
+    /// transfer the ownership of the pointee.  Returned pointers are deleted by a separate
+    /// thunk (see `return_value_thunk_ident`).
+    pub fn unique_ptr_pointee_type(&self) -> Option<&RsTypeKind> {
+        match self {
+            RsTypeKind::Other { name, type_args } if &**name == "::cc_std::UniquePtr" => {
+                type_args.first()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `T` if the type is the `::cc_std::SharedPtr<T>` that a by-value
+    /// `std::shared_ptr<T>` parameter or return value is mapped to.
+    ///
+    /// Such values are passed to and returned from the thunks as owning `*mut c_void` pointers
+    /// to a heap-allocated `std::shared_ptr<T>`.  Returned pointers are accessed, copied and
+    /// deleted by separate thunks (see `return_value_thunk_ident`).
+    pub fn shared_ptr_pointee_type(&self) -> Option<&RsTypeKind> {
+        match self {
+            RsTypeKind::Other { name, type_args } if &**name == "::cc_std::SharedPtr" => {
+                type_args.first()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the type is the `&str`, `&[T]` or `&mut [T]` that a by-value
+    /// `absl::string_view` or `absl::Span<T>` parameter is mapped to.
+    ///
+    /// Such values are passed to the thunks as a `::cc_std::SliceRef` (i.e. as a pointer and a
+    /// length), from which the C++ thunk reconstructs the view.
+    pub fn is_slice_ref(&self) -> bool {
+        match self {
+            RsTypeKind::Reference { referent, .. } => matches!(
+                &**referent,
+                RsTypeKind::Other { name, .. } if matches!(&**name, "str" | "[]")
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns the mutability (`Const` for `Fn`, `Mut` for `FnMut`), the parameter types and the
+    /// return type of the closure that a by-value `std::function` or `absl::AnyInvocable`
+    /// parameter is mapped to.
+    ///
+    /// Such closures are passed to the thunks as a `::cc_std::RustClosure`, from which the C++
+    /// thunk moves the closure into a `crubit::RustClosureFunctor`.
+    pub fn closure_signature(&self) -> Option<(Mutability, &[RsTypeKind], &RsTypeKind)> {
+        match self {
+            RsTypeKind::Other { name, type_args } => {
+                let mutability = match &**name {
+                    "impl Fn" => Mutability::Const,
+                    "impl FnMut" => Mutability::Mut,
+                    _ => return None,
+                };
+                let (return_type, param_types) = type_args.split_last()?;
+                Some((mutability, param_types, return_type))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns true if the type is known to be move-constructible, false
     /// otherwise.
     ///
@@ -3303,6 +4393,7 @@ impl RsTypeKind {
             RsTypeKind::IncompleteRecord { .. } => false,
             RsTypeKind::Record { record, .. } => should_derive_copy(record),
             RsTypeKind::TypeAlias { underlying_type, .. } => underlying_type.implements_copy(),
+            _ if self.closure_signature().is_some() => false,
             RsTypeKind::Other { type_args, .. } => {
                 // All types that may appear here without `type_args` (e.g.
                 // primitive types like `i32`) implement `Copy`. Generic types
@@ -3338,6 +4429,15 @@ impl RsTypeKind {
         }
     }
 
+    /// Returns the type that `self` is an alias of (recursively), or `self`
+    /// if it is not an alias.
+    pub fn unalias(&self) -> &RsTypeKind {
+        match self {
+            RsTypeKind::TypeAlias { underlying_type, .. } => underlying_type.unalias(),
+            _ => self,
+        }
+    }
+
     pub fn is_bool(&self) -> bool {
         match self {
             RsTypeKind::Other { name, .. } => &**name == "bool",
@@ -3427,11 +4527,19 @@ impl RsTypeKind {
                     quote! { #crate_path #ident }
                 }
             }
+            RsTypeKind::Other { name, type_args } if &**name == "[]" => {
+                let element_types = type_args.iter().map(|element_type| {
+                    element_type.to_token_stream_replacing_by_self(self_record)
+                });
+                quote! {[ #( #element_types )* ]}
+            }
+            // Closures only take and return primitive types (so `Self` never appears there).
+            _ if self.closure_signature().is_some() => self.to_token_stream(),
             RsTypeKind::Other { name, type_args } => {
-                let ident = make_rs_ident(name);
+                let name = format_rs_type_name(name);
                 let generic_params =
                     format_generic_params_replacing_by_self(type_args.iter(), self_record);
-                quote! {#ident #generic_params}
+                quote! {#name #generic_params}
             }
             _ => self.to_token_stream(),
         }
@@ -3490,16 +4598,45 @@ impl ToTokens for RsTypeKind {
             // This doesn't affect void in function return values, as those are special-cased to be
             // omitted.
             RsTypeKind::Unit => quote! {::core::ffi::c_void},
+            RsTypeKind::Other { name, type_args } if &**name == "[]" => {
+                quote! {[ #( #type_args )* ]}
+            }
+            _ if self.closure_signature().is_some() => {
+                let (mutability, param_types, return_type) = self.closure_signature().unwrap();
+                let return_frag = return_type.format_as_return_type_fragment(None);
+                // The C++ callee may call the closure (and copies of `std::function` may call
+                // the shared closure) from any thread, and after the Rust caller returns.
+                match mutability {
+                    Mutability::Const => quote! {
+                        impl Fn( #( #param_types ),* ) #return_frag + Send + Sync + 'static
+                    },
+                    Mutability::Mut => quote! {
+                        impl FnMut( #( #param_types ),* ) #return_frag + Send + 'static
+                    },
+                }
+            }
             RsTypeKind::Other { name, type_args } => {
-                let ident = make_rs_ident(name);
+                let name = format_rs_type_name(name);
                 let generic_params =
                     format_generic_params(/* lifetimes= */ &[], type_args.iter());
-                quote! {#ident #generic_params}
+                quote! {#name #generic_params}
             }
         }
     }
 }
 
+/// Formats the `name` of `RsTypeKind::Other`, which is either an identifier
+/// (e.g. `i32` or `Option`) or an absolute path (e.g. `::cc_std::Vector`).
+fn format_rs_type_name(name: &str) -> TokenStream {
+    match name.strip_prefix("::") {
+        None => make_rs_ident(name).into_token_stream(),
+        Some(path) => {
+            let idents = path.split("::").map(make_rs_ident);
+            quote! { #( :: #idents )* }
+        }
+    }
+}
+
 struct RsTypeKindIter<'ty> {
     todo: Vec<&'ty RsTypeKind>,
 }
@@ -3629,6 +4766,30 @@ fn rs_type_kind(db: &dyn BindingsGenerator, ty: ir::RsType) -> Result<RsTypeKind
                 mutability: Mutability::Const,
                 lifetime: get_lifetime()?,
             },
+            // `absl::string_view` and `absl::Span<T>` parameters (see `is_slice_ref`).
+            "&str" => RsTypeKind::Reference {
+                referent: Rc::new(RsTypeKind::Other {
+                    name: "str".into(),
+                    type_args: Rc::from(Vec::new()),
+                }),
+                mutability: Mutability::Const,
+                lifetime: Lifetime::new("_"),
+            },
+            "&[]" | "&mut []" => {
+                ensure!(
+                    ty.type_args.len() == 1,
+                    "Missing slice element type (need exactly 1 type argument): {:?}",
+                    ty
+                );
+                RsTypeKind::Reference {
+                    referent: Rc::new(RsTypeKind::Other {
+                        name: "[]".into(),
+                        type_args: Rc::from(get_type_args()?),
+                    }),
+                    mutability: if name == "&[]" { Mutability::Const } else { Mutability::Mut },
+                    lifetime: Lifetime::new("_"),
+                }
+            }
             "#RvalueReference mut" => RsTypeKind::RvalueReference {
                 referent: get_pointee()?,
                 mutability: Mutability::Mut,
@@ -3639,6 +4800,11 @@ fn rs_type_kind(db: &dyn BindingsGenerator, ty: ir::RsType) -> Result<RsTypeKind
                 mutability: Mutability::Const,
                 lifetime: get_lifetime()?,
             },
+            // `std::function` and `absl::AnyInvocable` parameters (see `closure_signature`).
+            "impl Fn" | "impl FnMut" => {
+                ensure!(!ty.type_args.is_empty(), "No return type in closure type: {:?}", ty);
+                RsTypeKind::Other { name: name.into(), type_args: Rc::from(get_type_args()?) }
+            }
             name => {
                 let mut type_args = get_type_args()?;
                 match name.strip_prefix("#funcPtr ") {
@@ -3665,9 +4831,34 @@ fn cc_type_name_for_record(record: &Record, ir: &IR) -> Result<TokenStream> {
     Ok(quote! { #tag_kind #tagless })
 }
 
+/// Returns the fully qualified C++ name of `record` (e.g. `::ns::Outer::Inner`).
+fn cc_qualified_name_for_record(record: &Record, ir: &IR) -> Result<String> {
+    if record.is_anonymous_struct_or_union {
+        bail!("Anonymous structs and unions can't be named in C++: `{}`", record.rs_name);
+    }
+    let qualifier = match record.enclosing_record_id {
+        Some(parent_id) => {
+            cc_qualified_name_for_record(ir.find_decl::<Rc<Record>>(parent_id)?, ir)?
+        }
+        None => namespace_qualifier_of_item(record.id, ir)?
+            .parts()
+            .map(|namespace| format!("::{namespace}"))
+            .collect(),
+    };
+    Ok(format!("{qualifier}::{}", record.cc_name))
+}
+
 fn cc_tagless_type_name_for_record(record: &Record, ir: &IR) -> Result<TokenStream> {
+    if record.is_anonymous_struct_or_union {
+        bail!("Anonymous structs and unions can't be named in C++: `{}`", record.rs_name);
+    }
     let ident = format_cc_ident(record.cc_name.as_ref());
-    let namespace_qualifier = namespace_qualifier_of_item(record.id, ir)?.format_for_cc()?;
+    if let Some(parent_id) = record.enclosing_record_id {
+        let parent = cc_tagless_type_name_for_record(ir.find_decl::<Rc<Record>>(parent_id)?, ir)?;
+        return Ok(quote! { #parent :: #ident });
+    }
+    let namespace_qualifier =
+        namespace_qualifier_of_item(record.id, ir)?.format_unescaped_for_cc()?;
     Ok(quote! { #namespace_qualifier #ident })
 }
 
@@ -3676,7 +4867,7 @@ fn cc_type_name_for_item(item: &ir::Item, ir: &IR) -> Result<TokenStream> {
         Item::IncompleteRecord(incomplete_record) => {
             let ident = format_cc_ident(incomplete_record.cc_name.as_ref());
             let namespace_qualifier =
-                namespace_qualifier_of_item(incomplete_record.id, ir)?.format_for_cc()?;
+                namespace_qualifier_of_item(incomplete_record.id, ir)?.format_unescaped_for_cc()?;
             let tag_kind = incomplete_record.record_type;
             Ok(quote! { #tag_kind #namespace_qualifier #ident })
         }
@@ -3689,7 +4880,7 @@ fn cc_type_name_for_item(item: &ir::Item, ir: &IR) -> Result<TokenStream> {
                 Ok(quote! { #parent :: #ident })
             } else {
                 let namespace_qualifier =
-                    namespace_qualifier_of_item(type_alias.id, ir)?.format_for_cc()?;
+                    namespace_qualifier_of_item(type_alias.id, ir)?.format_unescaped_for_cc()?;
                 Ok(quote! { #namespace_qualifier #ident })
             }
         }
@@ -3720,6 +4911,11 @@ fn format_cc_call_conv_as_clang_attribute(rs_abi: &str) -> Result<TokenStream> {
     }
 }
 
+/// The generic C++ standard library types that the importer maps to Rust types
+/// (e.g. `std::vector<T>` to `::cc_std::Vector<T>`).
+const GENERIC_CC_STD_TYPES: &[&str] =
+    &["std::vector", "std::optional", "std::unique_ptr", "std::shared_ptr", "absl::Span"];
+
 fn format_cc_type(ty: &ir::CcType, ir: &IR) -> Result<TokenStream> {
     // Formatting *both* pointers *and* references as pointers, because:
     // - Pointers and references have the same representation in the ABI.
@@ -3727,6 +4923,20 @@ fn format_cc_type(ty: &ir::CcType, ir: &IR) -> Result<TokenStream> {
     //   function thunks declared as `extern "C"` (see b/238681766).
     format_cc_type_inner(ty, ir, /* references_ok= */ false)
 }
+/// Formats the signature `R(Args...)` of a `std::function<R(Args...)>` or
+/// `absl::AnyInvocable<R(Args...)>` type (see `RsTypeKind::closure_signature`).
+fn format_cc_closure_signature(ty: &ir::CcType, ir: &IR) -> Result<TokenStream> {
+    match ty.type_args.split_last() {
+        None => bail!("Closure type without a return type: {:?}", ty),
+        Some((ret_type, param_types)) => {
+            let ret_type = format_cc_type(ret_type, ir)?;
+            let param_types =
+                param_types.iter().map(|t| format_cc_type(t, ir)).collect::<Result<Vec<_>>>()?;
+            Ok(quote! { #ret_type ( #( #param_types ),* ) })
+        }
+    }
+}
+
 fn format_cc_type_inner(ty: &ir::CcType, ir: &IR, references_ok: bool) -> Result<TokenStream> {
     let const_fragment = if ty.is_const {
         quote! {const}
@@ -3751,16 +4961,29 @@ fn format_cc_type_inner(ty: &ir::CcType, ir: &IR, references_ok: bool) -> Result
                 };
                 Ok(quote! {#nested_type #ptr #const_fragment})
             }
+            cc_type_name @ ("std::function" | "absl::AnyInvocable") => {
+                let cc_ident: TokenStream = cc_type_name.parse().unwrap();
+                let signature = format_cc_closure_signature(ty, ir)?;
+                Ok(quote! { #cc_ident < #signature > #const_fragment })
+            }
             cc_type_name => match cc_type_name.strip_prefix("#funcValue ") {
                 None => {
-                    if !ty.type_args.is_empty() {
-                        bail!("Type not yet supported: {:?}", ty);
-                    }
                     // Not using `code_gen_utils::format_cc_ident`, because
                     // `cc_type_name` may be a C++ reserved keyword (e.g.
                     // `int`).
                     let cc_ident: TokenStream = cc_type_name.parse().unwrap();
-                    Ok(quote! { #cc_ident #const_fragment })
+                    if ty.type_args.is_empty() {
+                        return Ok(quote! { #cc_ident #const_fragment });
+                    }
+                    if !GENERIC_CC_STD_TYPES.contains(&cc_type_name) {
+                        bail!("Type not yet supported: {:?}", ty);
+                    }
+                    let type_args = ty
+                        .type_args
+                        .iter()
+                        .map(|t| format_cc_type_inner(t, ir, references_ok))
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok(quote! { #cc_ident < #( #type_args ),* > #const_fragment })
                 }
                 Some(abi) => match ty.type_args.split_last() {
                     None => bail!("funcValue type without a return type: {:?}", ty),
@@ -3796,11 +5019,9 @@ fn format_cc_type_inner(ty: &ir::CcType, ir: &IR, references_ok: bool) -> Result
 }
 
 fn cc_struct_layout_assertion(db: &Database, record: &Record) -> Result<TokenStream> {
-    let record_ident = format_cc_ident(record.cc_name.as_ref());
-    let namespace_qualifier = namespace_qualifier_of_item(record.id, &db.ir())?.format_for_cc()?;
+    let record_name = cc_type_name_for_record(record, &db.ir())?;
     let cc_size = Literal::usize_unsuffixed(record.original_cc_size);
     let alignment = Literal::usize_unsuffixed(record.alignment);
-    let tag_kind = cc_tag_kind(record);
     let field_assertions = record
         .fields
         .iter()
@@ -3819,14 +5040,14 @@ fn cc_struct_layout_assertion(db: &Database, record: &Record) -> Result<TokenStr
 
             let field_ident = format_cc_ident(&field.identifier.as_ref().unwrap().identifier);
             let actual_offset = quote! {
-                CRUBIT_OFFSET_OF(#field_ident, #tag_kind #namespace_qualifier #record_ident)
+                CRUBIT_OFFSET_OF(#field_ident, #record_name)
             };
 
             quote! { static_assert( #actual_offset == #expected_offset); }
         });
     Ok(quote! {
-        static_assert(sizeof(#tag_kind #namespace_qualifier #record_ident) == #cc_size);
-        static_assert(alignof(#tag_kind #namespace_qualifier #record_ident) == #alignment);
+        static_assert(sizeof(#record_name) == #cc_size);
+        static_assert(alignof(#record_name) == #alignment);
         #( #field_assertions )*
     })
 }
@@ -3871,25 +5092,208 @@ fn cc_struct_no_unique_address_impl(db: &Database, record: &Record) -> Result<To
     })
 }
 
-fn crate_root_path_tokens(ir: &IR) -> TokenStream {
-    match ir.crate_root_path().as_deref().map(make_rs_ident) {
-        None => quote! { crate },
-        Some(crate_root_path) => quote! { crate :: #crate_root_path },
+// Returns the accessor functions for the members of anonymous struct and union
+// members (which C++ lets access as if they were members of the enclosing
+// record).
+fn cc_struct_anonymous_member_accessors_impl(
+    db: &Database,
+    record: &Record,
+) -> Result<TokenStream> {
+    let mut accessors = vec![];
+    for (field_index, field) in record.fields.iter().enumerate() {
+        if field.access != AccessSpecifier::Public || field.identifier.is_some() {
+            continue;
+        }
+        let Ok(rs_type) = get_field_rs_type_for_layout(field) else {
+            continue;
+        };
+        let RsTypeKind::Record { record: anonymous_record, .. } =
+            db.rs_type_kind(rs_type.clone())?
+        else {
+            continue;
+        };
+        if !anonymous_record.is_anonymous_struct_or_union {
+            continue;
+        }
+        let anonymous_field = make_rs_field_ident(field, field_index);
+        // Accessing union fields is unsafe.
+        let unsafe_ = if record.is_union() || anonymous_record.is_union() {
+            quote! { unsafe }
+        } else {
+            quote! {}
+        };
+        for (member_index, member) in anonymous_record.fields.iter().enumerate() {
+            if member.access != AccessSpecifier::Public || member.is_bitfield {
+                continue;
+            }
+            let Some(member_name) = &member.identifier else {
+                continue;
+            };
+            let Ok(member_rs_type) = get_field_rs_type_for_layout(member) else {
+                continue;
+            };
+            let member_type = db.rs_type_kind(member_rs_type.clone())?;
+            let member_ident = make_rs_field_ident(member, member_index);
+            accessors.push(quote! {
+                pub #unsafe_ fn #member_ident(&self) -> &#member_type {
+                    &self.#anonymous_field.#member_ident
+                }
+            });
+            if record.is_unpin() {
+                let member_mut_ident = make_rs_ident(&format!("{}_mut", member_name.identifier));
+                accessors.push(quote! {
+                    pub #unsafe_ fn #member_mut_ident(&mut self) -> &mut #member_type {
+                        &mut self.#anonymous_field.#member_ident
+                    }
+                });
+            }
+        }
+    }
+
+    if accessors.is_empty() {
+        return Ok(quote! {});
     }
+
+    let ident = make_rs_ident(record.rs_name.as_ref());
+    Ok(quote! {
+        impl #ident {
+            #( #accessors )*
+        }
+    })
 }
 
-/// Returns the implementation of base class conversions, for converting a type
-/// to its unambiguous public base classes.
-fn cc_struct_upcast_impl(record: &Rc<Record>, ir: &IR) -> Result<GeneratedItem> {
-    let mut impls = Vec::with_capacity(record.unambiguous_public_bases.len());
-    let mut thunks = vec![];
-    let mut cc_impls = vec![];
-    for base in &record.unambiguous_public_bases {
-        let base_record: &Rc<Record> = ir
-            .find_decl(base.base_record_id)
+/// Returns the getter and setter functions for bitfield member variables.
+///
+/// The storage of the bitfields stays opaque (see the `__bitfields` fields
+/// generated by `generate_record`), because their layout is
+/// implementation-defined.  The accessors call into C++ thunks instead.
+fn cc_struct_bitfield_accessors_impl(db: &Database, record: &Rc<Record>) -> Result<GeneratedItem> {
+    // Anonymous structs and unions can't be named in the C++ thunks.
+    if record.is_anonymous_struct_or_union {
+        return Ok(GeneratedItem::default());
+    }
+    let ir = db.ir();
+    let method_names: HashSet<&str> = record
+        .child_item_ids
+        .iter()
+        .filter_map(|id| ir.find_decl::<Rc<Func>>(*id).ok())
+        .filter_map(|func| match &func.name {
+            UnqualifiedIdentifier::Identifier(id) => Some(id.identifier.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let record_cc_name = cc_type_name_for_record(record, &ir)?;
+    let record_rs_name = RsTypeKind::new_record(record.clone(), &ir)?.into_token_stream();
+    let crate_root_path = crate_root_path_tokens(&ir);
+    let mut accessors = vec![];
+    let mut thunks = vec![];
+    let mut cc_impls = vec![];
+    for field in &record.fields {
+        if field.access != AccessSpecifier::Public || !field.is_bitfield {
+            continue;
+        }
+        let (Some(field_name), Ok(mapped_type)) = (&field.identifier, &field.type_) else {
+            continue;
+        };
+        let field_name = field_name.identifier.as_ref();
+        if method_names.contains(field_name) {
+            continue;
+        }
+        let rs_type = db.rs_type_kind(mapped_type.rs_type.clone())?;
+        // Bitfields can only have integral or enumeration types.
+        if !rs_type.implements_copy() {
+            continue;
+        }
+        let cc_type = format_cc_type(&mapped_type.cc_type, &ir)?;
+        let field_ident = make_rs_ident(field_name);
+        let cc_field_ident = format_cc_ident(field_name);
+        let get_thunk_ident = make_rs_ident(&format!(
+            "__crubit_bitfield_get__{}__{}",
+            record.mangled_cc_name, field_name
+        ));
+        thunks.push(quote! {
+            pub(crate) fn #get_thunk_ident(__this: *const #record_rs_name) -> #rs_type;
+        });
+        cc_impls.push(quote! {
+            extern "C" #cc_type #get_thunk_ident(const #record_cc_name* __this) {
+                return __this->#cc_field_ident;
+            }
+        });
+        accessors.push(quote! {
+            pub fn #field_ident(&self) -> #rs_type {
+                unsafe { #crate_root_path::detail::#get_thunk_ident(self) }
+            }
+        });
+
+        let setter_name = format!("set_{}", field_name);
+        if mapped_type.cc_type.is_const || method_names.contains(setter_name.as_str()) {
+            continue;
+        }
+        let setter_ident = make_rs_ident(&setter_name);
+        let set_thunk_ident = make_rs_ident(&format!(
+            "__crubit_bitfield_set__{}__{}",
+            record.mangled_cc_name, field_name
+        ));
+        thunks.push(quote! {
+            pub(crate) fn #set_thunk_ident(__this: *mut #record_rs_name, value: #rs_type);
+        });
+        cc_impls.push(quote! {
+            extern "C" void #set_thunk_ident(#record_cc_name* __this, #cc_type value) {
+                __this->#cc_field_ident = value;
+            }
+        });
+        // Assigning to a bitfield doesn't move the record, so the setter can
+        // also be provided for `!Unpin` records.
+        let (self_param, this_arg) = if record.is_unpin() {
+            (quote! { &mut self }, quote! { self })
+        } else {
+            (
+                quote! { self: ::core::pin::Pin<&mut Self> },
+                quote! { ::core::pin::Pin::into_inner_unchecked(self) },
+            )
+        };
+        accessors.push(quote! {
+            pub fn #setter_ident(#self_param, value: #rs_type) {
+                unsafe { #crate_root_path::detail::#set_thunk_ident(#this_arg, value) }
+            }
+        });
+    }
+
+    if accessors.is_empty() {
+        return Ok(GeneratedItem::default());
+    }
+    let ident = make_rs_ident(record.rs_name.as_ref());
+    Ok(GeneratedItem {
+        item: quote! {
+            impl #ident {
+                #( #accessors )*
+            }
+        },
+        thunks: quote! { #( #thunks )* },
+        thunk_impls: quote! { #( #cc_impls )* },
+        ..Default::default()
+    })
+}
+
+fn crate_root_path_tokens(ir: &IR) -> TokenStream {
+    match ir.crate_root_path().as_deref().map(make_rs_ident) {
+        None => quote! { crate },
+        Some(crate_root_path) => quote! { crate :: #crate_root_path },
+    }
+}
+
+/// Returns the implementation of base class conversions, for converting a type
+/// to its unambiguous public base classes.
+fn cc_struct_upcast_impl(record: &Rc<Record>, ir: &IR) -> Result<GeneratedItem> {
+    let mut impls = Vec::with_capacity(record.unambiguous_public_bases.len());
+    let mut thunks = vec![];
+    let mut cc_impls = vec![];
+    let derived_name = RsTypeKind::new_record(record.clone(), ir)?.into_token_stream();
+    for base in &record.unambiguous_public_bases {
+        let base_record: &Rc<Record> = ir
+            .find_decl(base.base_record_id)
             .with_context(|| format!("Can't find a base record of {:?}", record))?;
         let base_name = RsTypeKind::new_record(base_record.clone(), ir)?.into_token_stream();
-        let derived_name = RsTypeKind::new_record(record.clone(), ir)?.into_token_stream();
         let body;
         if let Some(offset) = base.offset {
             let offset = Literal::i64_unsuffixed(offset);
@@ -3923,6 +5327,51 @@ fn cc_struct_upcast_impl(record: &Rc<Record>, ir: &IR) -> Result<GeneratedItem>
         });
     }
 
+    // `Deref` to the base class lets Rust code call the (`&self`) methods of the
+    // base class on the derived class, and pass `&Derived` where `&Base` is
+    // expected.  There can be only one `Deref` target, so this is limited to the
+    // base class from which all the other base classes are reachable (e.g. in
+    // the absence of multiple inheritance).
+    let deref_base = record.unambiguous_public_bases.iter().find_map(|base| {
+        let base_record: &Rc<Record> = ir.find_decl(base.base_record_id).ok()?;
+        let reaches_all_bases = record.unambiguous_public_bases.iter().all(|other| {
+            other.base_record_id == base.base_record_id
+                || base_record
+                    .unambiguous_public_bases
+                    .iter()
+                    .any(|indirect| indirect.base_record_id == other.base_record_id)
+        });
+        reaches_all_bases.then_some(base_record)
+    });
+    if let Some(base_record) = deref_base {
+        let base_name = RsTypeKind::new_record(base_record.clone(), ir)?.into_token_stream();
+        impls.push(quote! {
+            impl ::core::ops::Deref for #derived_name {
+                type Target = #base_name;
+                #[inline(always)]
+                fn deref(&self) -> &#base_name {
+                    unsafe { &*<Self as oops::Inherits<#base_name>>::upcast_ptr(self) }
+                }
+            }
+        });
+    } else {
+        // With multiple inheritance there is no single `Deref` target, so
+        // `AsRef` is provided instead for each of the unambiguous public bases
+        // (using the same, offset-adjusting upcasts as `oops::Inherits`).
+        for base in &record.unambiguous_public_bases {
+            let base_record: &Rc<Record> = ir.find_decl(base.base_record_id)?;
+            let base_name = RsTypeKind::new_record(base_record.clone(), ir)?.into_token_stream();
+            impls.push(quote! {
+                impl ::core::convert::AsRef<#base_name> for #derived_name {
+                    #[inline(always)]
+                    fn as_ref(&self) -> &#base_name {
+                        unsafe { &*<Self as oops::Inherits<#base_name>>::upcast_ptr(self) }
+                    }
+                }
+            });
+        }
+    }
+
     Ok(GeneratedItem {
         item: quote! {#(#impls)*},
         thunks: quote! {#(#thunks)*},
@@ -3931,10 +5380,418 @@ fn cc_struct_upcast_impl(record: &Rc<Record>, ir: &IR) -> Result<GeneratedItem>
     })
 }
 
+/// Returns whether values of type `ty` can be passed between the C++ overrides
+/// of virtual methods and their Rust implementations (see
+/// `cc_struct_overrides_impl`).
+fn is_supported_in_overrides(ty: &RsTypeKind) -> bool {
+    match ty {
+        RsTypeKind::Unit | RsTypeKind::Pointer { .. } => true,
+        RsTypeKind::Other { type_args, .. } => type_args.is_empty(),
+        RsTypeKind::TypeAlias { underlying_type, .. } => is_supported_in_overrides(underlying_type),
+        _ => false,
+    }
+}
+
+/// Generates the bindings that let Rust code implement (i.e. override) the
+/// virtual methods of `record`:
+///
+/// * A `FooOverrides` trait with a method for each virtual method of `Foo`.
+/// * A C++ class deriving from `Foo`, which forwards the calls of its virtual
+///   methods to an implementation of the trait.
+/// * A `FooWithOverrides` Rust type that owns an instance of the C++ class (and
+///   borrows the Rust implementation of the trait for its lifetime).
+///
+/// Protected virtual methods can be overridden too, and the C++ class can use a
+/// protected default constructor of `Foo`.
+///
+/// Nothing is generated if some of the virtual methods can't be overridden -
+/// e.g. if they are private, or take or return types other than primitive
+/// types and pointers.
+fn cc_struct_overrides_impl(
+    db: &dyn BindingsGenerator,
+    record: &Rc<Record>,
+) -> Result<GeneratedItem> {
+    let ir = db.ir();
+    // Overriding the virtual methods of base classes is not supported.
+    if !record.is_inheritable
+        || record.is_derived_class
+        || record.has_private_virtual_methods
+        || record.destructor == SpecialMemberFunc::Unavailable
+    {
+        return Ok(GeneratedItem::default());
+    }
+    let mut has_default_constructor = false;
+    let mut virtual_methods = vec![];
+    for item_id in &record.child_item_ids {
+        let func = match ir.find_decl::<Item>(*item_id)? {
+            Item::Func(func) => func,
+            // An unsupported method might be a pure virtual method, which would make the
+            // C++ class overriding the virtual methods abstract.
+            Item::UnsupportedItem(_) => return Ok(GeneratedItem::default()),
+            _ => continue,
+        };
+        let virtual_method = func
+            .member_func_metadata
+            .as_ref()
+            .and_then(|meta| meta.instance_method_metadata.as_ref())
+            .filter(|instance_method| instance_method.is_virtual);
+        match (&func.name, virtual_method) {
+            (UnqualifiedIdentifier::Constructor, _) if func.params.len() == 1 => {
+                has_default_constructor = true;
+            }
+            (UnqualifiedIdentifier::Destructor, _) | (_, None) => {}
+            (UnqualifiedIdentifier::Identifier(id), Some(instance_method)) => {
+                virtual_methods.push((func, id, instance_method));
+            }
+            (_, Some(_)) => return Ok(GeneratedItem::default()),
+        }
+    }
+    if !has_default_constructor || virtual_methods.is_empty() {
+        return Ok(GeneratedItem::default());
+    }
+
+    let crate_root_path = crate_root_path_tokens(&ir);
+    let record_name = RsTypeKind::new_record(record.clone(), &ir)?.into_token_stream();
+    let record_cc_name = cc_type_name_for_record(record, &ir)?;
+    let record_tagless_cc_name = cc_tagless_type_name_for_record(record, &ir)?;
+    let trait_name = make_rs_ident(&format!("{}Overrides", record.rs_name));
+    let owner_name = make_rs_ident(&format!("{}WithOverrides", record.rs_name));
+    let shim_class = format_cc_ident(&format!("__crubit_overrides__{}", record.mangled_cc_name));
+    let shim_vtable =
+        format_cc_ident(&format!("__crubit_overrides_vtable__{}", record.mangled_cc_name));
+    let new_fn = make_rs_ident(&format!("__crubit_overrides_new__{}", record.mangled_cc_name));
+    let delete_fn =
+        make_rs_ident(&format!("__crubit_overrides_delete__{}", record.mangled_cc_name));
+
+    let mut trait_methods = vec![];
+    let mut rs_vtable_fields = vec![];
+    let mut rs_vtable_values = vec![];
+    let mut rs_forwarding_fns = vec![];
+    let mut cc_vtable_fields = vec![];
+    let mut cc_overrides = vec![];
+    for (func, id, instance_method) in virtual_methods {
+        // `this` is replaced by the `rust_self` pointer in the forwarding functions.
+        let params = &func.params[1..];
+        let param_types = params
+            .iter()
+            .map(|p| db.rs_type_kind(p.type_.rs_type.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let return_type = db.rs_type_kind(func.return_type.rs_type.clone())?;
+        if instance_method.reference != ReferenceQualification::Unqualified
+            || !func.has_c_calling_convention
+            || !param_types.iter().chain([&return_type]).all(is_supported_in_overrides)
+        {
+            return Ok(GeneratedItem::default());
+        }
+
+        let method_name = make_rs_ident(&get_func_rs_name(func, id));
+        let forwarding_fn = format_ident!("__crubit_override_{}", method_name);
+        let param_idents =
+            params.iter().map(|p| make_rs_ident(&p.identifier.identifier)).collect_vec();
+        let return_fragment = return_type.format_as_return_type_fragment(None);
+        let (self_param, rust_self) = if instance_method.is_const {
+            (quote! { &self }, quote! { &*(rust_self as *const T) })
+        } else {
+            (quote! { &mut self }, quote! { &mut *(rust_self as *mut T) })
+        };
+        trait_methods.push(quote! {
+            fn #method_name(#self_param #( , #param_idents: #param_types )*) #return_fragment;
+        });
+        rs_vtable_fields.push(quote! {
+            #method_name: unsafe extern "C" fn(
+                *mut ::core::ffi::c_void #( , #param_types )*
+            ) #return_fragment,
+        });
+        rs_vtable_values.push(quote! { #method_name: #forwarding_fn::<T>, });
+        rs_forwarding_fns.push(quote! {
+            unsafe extern "C" fn #forwarding_fn<T: #trait_name>(
+                rust_self: *mut ::core::ffi::c_void #( , #param_idents: #param_types )*
+            ) #return_fragment {
+                <T as #trait_name>::#method_name(#rust_self #( , #param_idents )*)
+            }
+        });
+
+        let cc_method_name = format_cc_ident(&id.identifier);
+        let cc_field_name = format_cc_ident(&get_func_rs_name(func, id));
+        let cc_param_idents =
+            params.iter().map(|p| format_cc_ident(&p.identifier.identifier)).collect_vec();
+        let cc_param_types = params
+            .iter()
+            .map(|p| format_cc_type(&p.type_.cc_type, &ir))
+            .collect::<Result<Vec<_>>>()?;
+        let cc_return_type = format_cc_type(&func.return_type.cc_type, &ir)?;
+        let const_qualifier = if instance_method.is_const {
+            quote! { const }
+        } else {
+            quote! {}
+        };
+        cc_vtable_fields.push(quote! {
+            #cc_return_type (* #cc_field_name)(void* #( , #cc_param_types )*);
+        });
+        cc_overrides.push(quote! {
+            #cc_return_type #cc_method_name( #( #cc_param_types #cc_param_idents ),* )
+                #const_qualifier override {
+                return vtable_.#cc_field_name(rust_self_ #( , #cc_param_idents )*);
+            }
+        });
+    }
+
+    let trait_doc = format!(
+        "Rust implementation of the virtual methods of `{}` (see `{}::new`).",
+        record.rs_name, owner_name
+    );
+    let owner_doc = format!(
+        "A C++ `{}` whose virtual methods are implemented in Rust by a `{}`.",
+        record.rs_name, trait_name
+    );
+    // The C++ object copies the `vtable`, and only uses `overrides` while the
+    // `#owner_name` (which borrows `overrides`) is alive.
+    let item = quote! {
+        #[doc = #trait_doc]
+        pub trait #trait_name {
+            #( #trait_methods )*
+        }
+        __NEWLINE__ __NEWLINE__
+
+        #[doc = #owner_doc]
+        pub struct #owner_name<'a> {
+            cc_obj: *mut #record_name,
+            _overrides: ::core::marker::PhantomData<&'a mut ()>,
+        }
+        __NEWLINE__ __NEWLINE__
+
+        impl<'a> #owner_name<'a> {
+            pub fn new<T: #trait_name>(overrides: &'a mut T) -> Self {
+                #[allow(dead_code)]
+                #[repr(C)]
+                struct Vtable {
+                    #( #rs_vtable_fields )*
+                }
+                #( #rs_forwarding_fns )*
+                let vtable = Vtable { #( #rs_vtable_values )* };
+                let cc_obj = unsafe {
+                    #crate_root_path::detail::#new_fn(
+                        overrides as *mut T as *mut ::core::ffi::c_void,
+                        &vtable as *const Vtable as *const ::core::ffi::c_void,
+                    )
+                };
+                Self { cc_obj, _overrides: ::core::marker::PhantomData }
+            }
+
+            pub fn as_pin_mut(&mut self) -> ::core::pin::Pin<&mut #record_name> {
+                unsafe { ::core::pin::Pin::new_unchecked(&mut *self.cc_obj) }
+            }
+
+            pub fn as_mut_ptr(&mut self) -> *mut #record_name {
+                self.cc_obj
+            }
+        }
+        __NEWLINE__ __NEWLINE__
+
+        impl ::core::ops::Deref for #owner_name<'_> {
+            type Target = #record_name;
+            fn deref(&self) -> &#record_name {
+                unsafe { &*self.cc_obj }
+            }
+        }
+        __NEWLINE__ __NEWLINE__
+
+        impl Drop for #owner_name<'_> {
+            fn drop(&mut self) {
+                unsafe { #crate_root_path::detail::#delete_fn(self.cc_obj) }
+            }
+        }
+    };
+    let thunks = quote! {
+        pub(crate) fn #new_fn(
+            rust_self: *mut ::core::ffi::c_void,
+            vtable: *const ::core::ffi::c_void,
+        ) -> *mut #record_name;
+        pub(crate) fn #delete_fn(cc_obj: *mut #record_name);
+    };
+    let thunk_impls = quote! {
+        struct #shim_vtable {
+            #( #cc_vtable_fields )*
+        };
+        __NEWLINE__ __NEWLINE__
+        class #shim_class final : public #record_tagless_cc_name {
+        public:
+            #shim_class(void* rust_self, const #shim_vtable& vtable)
+                : rust_self_(rust_self), vtable_(vtable) {}
+            #( #cc_overrides )*
+        private:
+            void* rust_self_;
+            #shim_vtable vtable_;
+        };
+        __NEWLINE__ __NEWLINE__
+        extern "C" #record_cc_name* #new_fn(void* rust_self, const #shim_vtable* vtable) {
+            return new #shim_class(rust_self, *vtable);
+        }
+        __NEWLINE__ __NEWLINE__
+        extern "C" void #delete_fn(#record_cc_name* cc_obj) {
+            delete static_cast<#shim_class*>(cc_obj);
+        }
+    };
+    Ok(GeneratedItem { item, thunks, thunk_impls, ..Default::default() })
+}
+
+/// Generates the bindings that let Rust code use the C++ implementations of an
+/// abstract `record` as trait objects:
+///
+/// * A `FooInterface` trait with a method for each virtual method of `Foo`.
+/// * An implementation of the trait for `Foo`, which calls the virtual methods
+///   (and so dispatches to the C++ implementation of the dynamic type of the
+///   object). Derived classes deref to `Foo`, so `&*derived` can be used as a
+///   `&dyn FooInterface`.
+/// * An implementation of the trait for `UniquePtr<Foo>`, so that an owned C++
+///   object can be held as a `Box<dyn FooInterface>`.
+///
+/// Nothing is generated if some of the virtual methods can't be called through
+/// the trait - e.g. if they are not public, or take or return types other than
+/// primitive types and pointers.
+fn cc_struct_interface_impl(
+    db: &dyn BindingsGenerator,
+    record: &Rc<Record>,
+) -> Result<GeneratedItem> {
+    let ir = db.ir();
+    if !record.is_abstract || record.has_private_virtual_methods {
+        return Ok(GeneratedItem::default());
+    }
+    let mut virtual_methods = vec![];
+    for item_id in &record.child_item_ids {
+        let func = match ir.find_decl::<Item>(*item_id)? {
+            Item::Func(func) => func,
+            // An unsupported method might be a virtual method, which would then be
+            // missing from the trait.
+            Item::UnsupportedItem(_) => return Ok(GeneratedItem::default()),
+            _ => continue,
+        };
+        let virtual_method = func
+            .member_func_metadata
+            .as_ref()
+            .and_then(|meta| meta.instance_method_metadata.as_ref())
+            .filter(|instance_method| instance_method.is_virtual);
+        match (&func.name, virtual_method) {
+            (UnqualifiedIdentifier::Destructor, _) | (_, None) => {}
+            (UnqualifiedIdentifier::Identifier(id), Some(instance_method)) => {
+                virtual_methods.push((func, id, instance_method));
+            }
+            (_, Some(_)) => return Ok(GeneratedItem::default()),
+        }
+    }
+    if virtual_methods.is_empty() {
+        return Ok(GeneratedItem::default());
+    }
+
+    let crate_root_path = crate_root_path_tokens(&ir);
+    let record_name = RsTypeKind::new_record(record.clone(), &ir)?.into_token_stream();
+    let trait_name = make_rs_ident(&format!("{}Interface", record.rs_name));
+
+    let mut trait_methods = vec![];
+    let mut record_methods = vec![];
+    let mut unique_ptr_methods = vec![];
+    for (func, id, instance_method) in virtual_methods {
+        let this_type = db.rs_type_kind(func.params[0].type_.rs_type.clone())?;
+        let params = &func.params[1..];
+        let param_types = params
+            .iter()
+            .map(|p| db.rs_type_kind(p.type_.rs_type.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let return_type = db.rs_type_kind(func.return_type.rs_type.clone())?;
+        if instance_method.reference != ReferenceQualification::Unqualified
+            || translates_cc_exceptions(&ir, func)
+            || !param_types.iter().chain([&return_type]).all(is_supported_in_overrides)
+            || !matches!(db.generate_func(func.clone()), Ok(Some(_)))
+        {
+            return Ok(GeneratedItem::default());
+        }
+
+        let method_name = make_rs_ident(&get_func_rs_name(func, id));
+        let thunk_ident = thunk_ident(func);
+        let param_idents =
+            params.iter().map(|p| make_rs_ident(&p.identifier.identifier)).collect_vec();
+        let return_fragment = return_type.format_as_return_type_fragment(None);
+        let (self_param, this_arg, unique_ptr_self) = if instance_method.is_const {
+            (quote! { &self }, quote! { self }, quote! { &**self })
+        } else {
+            // The thunks of methods without lifetimes take `__this` as a raw pointer.
+            let this_arg = match this_type {
+                RsTypeKind::Pointer { .. } => {
+                    quote! { ::core::pin::Pin::into_inner_unchecked(self) }
+                }
+                _ => quote! { self },
+            };
+            (
+                quote! { self: ::core::pin::Pin<&mut Self> },
+                this_arg,
+                quote! {
+                    ::core::pin::Pin::get_mut(self)
+                        .as_mut()
+                        .expect("Calling a method through a null `UniquePtr`")
+                },
+            )
+        };
+        trait_methods.push(quote! {
+            fn #method_name(#self_param #( , #param_idents: #param_types )*) #return_fragment;
+        });
+        record_methods.push(quote! {
+            #[inline(always)]
+            fn #method_name(#self_param #( , #param_idents: #param_types )*) #return_fragment {
+                unsafe {
+                    #crate_root_path::detail::#thunk_ident(#this_arg #( , #param_idents )*)
+                }
+            }
+        });
+        unique_ptr_methods.push(quote! {
+            #[inline(always)]
+            fn #method_name(#self_param #( , #param_idents: #param_types )*) #return_fragment {
+                <#record_name as #trait_name>::#method_name(#unique_ptr_self #( , #param_idents )*)
+            }
+        });
+    }
+
+    let trait_doc = format!(
+        "The virtual methods of `{}`, for using its C++ implementations as trait objects.",
+        record.rs_name
+    );
+    let item = quote! {
+        #[doc = #trait_doc]
+        pub trait #trait_name {
+            #( #trait_methods )*
+        }
+        __NEWLINE__ __NEWLINE__
+
+        impl #trait_name for #record_name {
+            #( #record_methods )*
+        }
+        __NEWLINE__ __NEWLINE__
+
+        impl #trait_name for ::cc_std::UniquePtr<#record_name> {
+            #( #unique_ptr_methods )*
+        }
+    };
+    Ok(GeneratedItem { item, ..Default::default() })
+}
+/// Formats the C++ template arguments of an instantiated function template
+/// (e.g. `<int, std::string>`) as a suffix of the Rust name of the function
+/// (e.g. `int_std_string`).
+fn format_template_args_as_rs_name_suffix(template_args: &str) -> String {
+    template_args
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|part| !part.is_empty())
+        .join("_")
+}
+
 fn thunk_ident(func: &Func) -> Ident {
     format_ident!("__rust_thunk__{}", func.mangled_name.as_ref())
 }
 
+/// Returns the name of the thunk that performs `operation` (e.g. "delete") on
+/// the smart pointer returned by `func`.
+fn return_value_thunk_ident(func: &Func, operation: &str) -> Ident {
+    format_ident!("__rust_thunk__{}__{}_return_value", func.mangled_name.as_ref(), operation)
+}
+
 fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<TokenStream> {
     if can_skip_cc_thunk(db, func) {
         return Ok(quote! {});
@@ -3946,23 +5803,43 @@ fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<T
             let name = syn::parse_str::<TokenStream>(&op.name)?;
             quote! { operator #name }
         }
+        UnqualifiedIdentifier::Conversion => {
+            let target_type = format_cc_type_inner(
+                &func.return_type.cc_type,
+                &ir,
+                /* references_ok= */ true,
+            )?;
+            quote! { operator #target_type }
+        }
         UnqualifiedIdentifier::Identifier(id) => {
             let fn_ident = format_cc_ident(&id.identifier);
+            let fn_ident = match func.template_args.as_deref() {
+                None => fn_ident,
+                Some(template_args) => {
+                    let template_args = template_args.parse::<TokenStream>().map_err(|err| {
+                        anyhow!("Can't parse template arguments `{template_args}`: {err}")
+                    })?;
+                    quote! { #fn_ident #template_args }
+                }
+            };
             match func.member_func_metadata.as_ref() {
                 Some(meta) => {
                     if meta.instance_method_metadata.is_some() {
                         quote! { #fn_ident }
                     } else {
                         let record: &Rc<Record> = ir.find_decl(meta.record_id)?;
-                        let record_ident = format_cc_ident(record.cc_name.as_ref());
-                        let namespace_qualifier =
-                            namespace_qualifier_of_item(record.id, &ir)?.format_for_cc()?;
-                        quote! { #namespace_qualifier #record_ident :: #fn_ident }
+                        let record_name = cc_tagless_type_name_for_record(record, &ir)?;
+                        quote! { #record_name :: #fn_ident }
                     }
                 }
+                // Friend functions may be defined entirely within a class (i.e. "hidden
+                // friends") and then they can only be found via ADL - a qualified name
+                // wouldn't find them. `api_func_shape` already checked that the
+                // function is visible by ADL.
+                None if func.adl_enclosing_record.is_some() => quote! { #fn_ident },
                 None => {
                     let namespace_qualifier =
-                        namespace_qualifier_of_item(func.id, &ir)?.format_for_cc()?;
+                        namespace_qualifier_of_item(func.id, &ir)?.format_unescaped_for_cc()?;
                     quote! { #namespace_qualifier #fn_ident }
                 }
             }
@@ -3986,8 +5863,31 @@ fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<T
         .params
         .iter()
         .map(|p| {
+            let rs_type_kind = db.rs_type_kind(p.type_.rs_type.clone())?;
+            if rs_type_kind.optional_value_type().is_some() {
+                // `std::optional<T>` is passed as a nullable pointer to `T`.
+                let value_type = format_cc_type(single_type_arg(&p.type_.cc_type)?, &ir)?;
+                return Ok(quote! {#value_type const *});
+            }
+            if rs_type_kind.unique_ptr_pointee_type().is_some() {
+                // `std::unique_ptr<T>` is passed as an owning pointer to `T`.
+                let pointee_type = format_cc_type(single_type_arg(&p.type_.cc_type)?, &ir)?;
+                return Ok(quote! {#pointee_type *});
+            }
+            if rs_type_kind.is_slice_ref() {
+                // `absl::string_view` and `absl::Span<T>` are passed as a pointer and a length.
+                return Ok(quote! {crubit::SliceRef});
+            }
+            if rs_type_kind.closure_signature().is_some() {
+                // `std::function` and `absl::AnyInvocable` are passed as a Rust closure.
+                return Ok(quote! {crubit::RustClosure});
+            }
+            if is_rvalue_reference_taken_by_move(p) && rs_type_kind.is_c_abi_compatible_by_value() {
+                // `T&&` taken by value is passed as a `T` (the rvalue is materialized below).
+                return format_cc_type(single_type_arg(&p.type_.cc_type)?, &ir);
+            }
             let formatted = format_cc_type(&p.type_.cc_type, &ir)?;
-            if !db.rs_type_kind(p.type_.rs_type.clone())?.is_c_abi_compatible_by_value() {
+            if !rs_type_kind.is_c_abi_compatible_by_value() {
                 // non-Unpin types are wrapped by a pointer in the thunk.
                 Ok(quote! {#formatted *})
             } else {
@@ -4001,9 +5901,44 @@ fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<T
         .iter()
         .map(|p| {
             let ident = format_cc_ident(&p.identifier.identifier);
+            if is_rvalue_reference_taken_by_move(p)
+                && db.rs_type_kind(p.type_.rs_type.clone())?.is_c_abi_compatible_by_value()
+            {
+                return Ok(quote! { std::move(#ident) });
+            }
             match p.type_.cc_type.name.as_deref() {
                 Some("&") => Ok(quote! { * #ident }),
                 Some("&&") => Ok(quote! { std::move(* #ident) }),
+                Some("std::optional") => {
+                    let value_type = format_cc_type(single_type_arg(&p.type_.cc_type)?, &ir)?;
+                    Ok(quote! { (#ident ? std::optional<#value_type>(* #ident) : std::nullopt) })
+                }
+                Some("std::unique_ptr") => {
+                    let pointee_type = format_cc_type(single_type_arg(&p.type_.cc_type)?, &ir)?;
+                    Ok(quote! { std::unique_ptr<#pointee_type>(#ident) })
+                }
+                Some("std::shared_ptr") => {
+                    // `std::shared_ptr<T>` is passed as an owning pointer to a heap-allocated
+                    // `std::shared_ptr<T>` - the temporary `std::unique_ptr` deletes it after
+                    // its contents are moved into the argument.
+                    let shared_ptr_type = format_cc_type(&p.type_.cc_type, &ir)?;
+                    Ok(quote! { std::move(* std::unique_ptr<#shared_ptr_type>(#ident)) })
+                }
+                Some("absl::string_view") => Ok(quote! {
+                    absl::string_view(static_cast<const char*>(#ident.data), #ident.size)
+                }),
+                Some("std::function" | "absl::AnyInvocable") => {
+                    // The functor takes the ownership of the Rust closure.
+                    let callable_type = format_cc_type(&p.type_.cc_type, &ir)?;
+                    let signature = format_cc_closure_signature(&p.type_.cc_type, &ir)?;
+                    Ok(quote! { #callable_type(crubit::RustClosureFunctor<#signature>(#ident)) })
+                }
+                Some("absl::Span") => {
+                    let element_type = format_cc_type(single_type_arg(&p.type_.cc_type)?, &ir)?;
+                    Ok(quote! {
+                        absl::Span<#element_type>(static_cast<#element_type*>(#ident.data), #ident.size)
+                    })
+                }
                 _ => {
                     // non-Unpin types are wrapped by a pointer in the thunk.
                     if !db.rs_type_kind(p.type_.rs_type.clone())?.is_c_abi_compatible_by_value() {
@@ -4020,19 +5955,43 @@ fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<T
     // value across `extern "C"` ABI.  (We do this after the arg_expressions
     // computation, so that it's only in the parameter list, not the argument
     // list.)
-    let is_return_value_c_abi_compatible =
-        db.rs_type_kind(func.return_type.rs_type.clone())?.is_c_abi_compatible_by_value();
+    let return_type_kind = db.rs_type_kind(func.return_type.rs_type.clone())?;
+    let is_return_value_optional = return_type_kind.optional_value_type().is_some();
+    let is_return_value_unique_ptr = return_type_kind.unique_ptr_pointee_type().is_some();
+    let is_return_value_shared_ptr = return_type_kind.shared_ptr_pointee_type().is_some();
+    let is_return_value_c_abi_compatible = return_type_kind.is_c_abi_compatible_by_value();
     let mut return_type_name = format_cc_type(&func.return_type.cc_type, &ir)?;
-    if !is_return_value_c_abi_compatible {
+    if is_return_value_optional {
+        // `std::optional<T>` is returned through an out parameter (initialized only if
+        // the returned `bool` is `true`).
+        let value_type = format_cc_type(single_type_arg(&func.return_type.cc_type)?, &ir)?;
+        param_idents.insert(0, format_cc_ident("__return"));
+        param_types.insert(0, quote! {#value_type *});
+        return_type_name = quote! {bool};
+    } else if is_return_value_unique_ptr {
+        let pointee_type = format_cc_type(single_type_arg(&func.return_type.cc_type)?, &ir)?;
+        return_type_name = quote! {#pointee_type *};
+    } else if is_return_value_shared_ptr {
+        // `std::shared_ptr<T>` is returned as an owning pointer to a heap-allocated
+        // `std::shared_ptr<T>`.
+        return_type_name = quote! {#return_type_name *};
+    } else if !is_return_value_c_abi_compatible {
         param_idents.insert(0, format_cc_ident("__return"));
         param_types.insert(0, quote! {#return_type_name *});
         return_type_name = quote! {void};
     }
+    let translates_cc_exceptions = translates_cc_exceptions(&ir, func);
+    if translates_cc_exceptions {
+        param_idents.push(format_cc_ident("__exception"));
+        param_types.push(quote! {crubit::CcExceptionSlot *});
+    }
 
     let this_ref_qualification =
         func.member_func_metadata.as_ref().and_then(|meta| match &func.name {
             UnqualifiedIdentifier::Constructor | UnqualifiedIdentifier::Destructor => None,
-            UnqualifiedIdentifier::Identifier(_) | UnqualifiedIdentifier::Operator(_) => meta
+            UnqualifiedIdentifier::Identifier(_)
+            | UnqualifiedIdentifier::Operator(_)
+            | UnqualifiedIdentifier::Conversion => meta
                 .instance_method_metadata
                 .as_ref()
                 .map(|instance_method| instance_method.reference),
@@ -4059,7 +6018,21 @@ fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<T
         };
 
     let return_expr = quote! {#implementation_function( #( #arg_expressions ),* )};
-    let return_stmt = if !is_return_value_c_abi_compatible {
+    let return_stmt = if is_return_value_optional {
+        let out_param = &param_idents[0];
+        quote! {
+            auto __result = #return_expr;
+            if (__result.has_value()) {
+                *#out_param = *__result;
+            }
+            return __result.has_value()
+        }
+    } else if is_return_value_unique_ptr {
+        quote! { return #return_expr.release() }
+    } else if is_return_value_shared_ptr {
+        let shared_ptr_type = format_cc_type(&func.return_type.cc_type, &ir)?;
+        quote! { return new #shared_ptr_type(#return_expr) }
+    } else if !is_return_value_c_abi_compatible {
         // Explicitly use placement `new` so that we get guaranteed copy elision in C++17.
         let out_param = &param_idents[0];
         quote! {new(#out_param) auto(#return_expr)}
@@ -4086,13 +6059,70 @@ fn generate_func_thunk_impl(db: &dyn BindingsGenerator, func: &Func) -> Result<T
         }
     };
 
+    let return_value_thunks = if is_return_value_unique_ptr {
+        let delete_thunk_ident = return_value_thunk_ident(func, "delete");
+        let pointee_type = format_cc_type(single_type_arg(&func.return_type.cc_type)?, &ir)?;
+        quote! {
+            extern "C" void #delete_thunk_ident(#pointee_type * ptr) {
+                delete ptr;
+            }
+        }
+    } else if is_return_value_shared_ptr {
+        let get_thunk_ident = return_value_thunk_ident(func, "get");
+        let clone_thunk_ident = return_value_thunk_ident(func, "clone");
+        let delete_thunk_ident = return_value_thunk_ident(func, "delete");
+        let shared_ptr_type = format_cc_type(&func.return_type.cc_type, &ir)?;
+        let pointee_type = format_cc_type(single_type_arg(&func.return_type.cc_type)?, &ir)?;
+        quote! {
+            extern "C" #pointee_type * #get_thunk_ident(#shared_ptr_type * handle) {
+                return handle->get();
+            }
+            extern "C" #shared_ptr_type * #clone_thunk_ident(#shared_ptr_type * handle) {
+                return new #shared_ptr_type(*handle);
+            }
+            extern "C" void #delete_thunk_ident(#shared_ptr_type * handle) {
+                delete handle;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let body = if translates_cc_exceptions {
+        // The value returned after catching an exception is ignored by the Rust caller.
+        let return_after_exception = if func.return_type.cc_type.name.as_deref() == Some("void") {
+            quote! {}
+        } else {
+            quote! { return {}; }
+        };
+        quote! {
+            try {
+                #return_stmt;
+            } catch (...) {
+                __exception->StoreCurrentException();
+                #return_after_exception
+            }
+        }
+    } else {
+        quote! { #return_stmt; }
+    };
+
     Ok(quote! {
         extern "C" #return_type_name #thunk_ident( #( #param_types #param_idents ),* ) {
-            #return_stmt;
+            #body
         }
+        #return_value_thunks
     })
 }
 
+/// Returns the only type argument of `ty` (e.g. the `T` of `std::optional<T>`).
+fn single_type_arg(ty: &ir::CcType) -> Result<&ir::CcType> {
+    match ty.type_args.as_slice() {
+        [type_arg] => Ok(type_arg),
+        _ => bail!("Invalid type (need exactly 1 type argument): {:?}", ty),
+    }
+}
+
 fn generate_rs_api_impl_includes(
     db: &mut Database,
     crubit_support_path: &str,
@@ -4104,7 +6134,21 @@ fn generate_rs_api_impl_includes(
     if ir.records().next().is_some() {
         internal_includes.insert(CcInclude::cstddef());
     };
-    for crubit_header in ["internal/cxx20_backports.h", "internal/offsetof.h"] {
+    let mut crubit_headers = vec!["internal/cxx20_backports.h", "internal/offsetof.h"];
+    if ir.target_crubit_features(ir.current_target()).contains(ir::CrubitFeature::Exceptions) {
+        crubit_headers.push("internal/cc_exception.h");
+    }
+    if ir.functions().flat_map(|func| &func.params).any(|param| {
+        matches!(param.type_.cc_type.name.as_deref(), Some("absl::string_view" | "absl::Span"))
+    }) {
+        crubit_headers.push("internal/slice_ref.h");
+    }
+    if ir.functions().flat_map(|func| &func.params).any(|param| {
+        matches!(param.type_.cc_type.name.as_deref(), Some("std::function" | "absl::AnyInvocable"))
+    }) {
+        crubit_headers.push("internal/rust_closure.h");
+    }
+    for crubit_header in crubit_headers {
         internal_includes.insert(CcInclude::user_header(
             format!("{crubit_support_path}/{crubit_header}").into(),
         ));
@@ -4257,6 +6301,111 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_function_with_internal_linkage() -> Result<()> {
+        let ir = ir_from_cc("static int Add(int a, int b) { return a + b; }")?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                extern "C" {
+                    pub(crate) fn __rust_thunk___ZL3Addii(a: i32, b: i32) -> i32;
+                }
+            }
+        );
+        assert_rs_not_matches!(rs_api, quote! { #[link_name = "_ZL3Addii"] });
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___ZL3Addii(int a, int b) {
+                    return Add(a, b);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_template_explicit_instantiations() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            template <typename T>
+            T Add(T a, T b) { return a + b; }
+
+            template int Add<int>(int, int);
+            extern template float Add<float>(float, float); "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(rs_api, quote! { pub fn Add_int(a: i32, b: i32) -> i32 { ... } });
+        assert_rs_matches!(rs_api, quote! { pub fn Add_float(a: f32, b: f32) -> f32 { ... } });
+        assert_cc_matches!(rs_api_impl, quote! { return Add<int>(a, b); });
+        assert_cc_matches!(rs_api_impl, quote! { return Add<float>(a, b); });
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_template_args_as_rs_name_suffix() {
+        assert_eq!(format_template_args_as_rs_name_suffix("<int>"), "int");
+        assert_eq!(
+            format_template_args_as_rs_name_suffix("<unsigned int, ns::Foo<char>>"),
+            "unsigned_int_ns_Foo_char"
+        );
+    }
+
+    #[test]
+    fn test_function_with_default_args() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                int f(int a, int b = 1, int c = 2);
+                struct S final {
+                  void Method(int x = 0);
+                }; "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(rs_api, quote! { pub fn f(a: i32, b: i32, c: i32) -> i32 { ... } });
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn f_without_c(a: i32, b: i32) -> i32 {
+                    unsafe { crate::detail::__rust_thunk___Z1fiii__without_c(a, b) }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn f_without_b_c(a: i32) -> i32 {
+                    unsafe { crate::detail::__rust_thunk___Z1fiii__without_b_c(a) }
+                }
+            }
+        );
+        assert_rs_matches!(rs_api, quote! { pub fn Method_without_x<'a>(&'a mut self) { ... } });
+
+        // The full-arity function doesn't need a thunk, but the variants that omit
+        // some of the default arguments do.
+        assert_cc_not_matches!(rs_api_impl, quote! { __rust_thunk___Z1fiii });
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___Z1fiii__without_c(int a, int b) {
+                    return f(a, b);
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___Z1fiii__without_b_c(int a) {
+                    return f(a);
+                }
+            }
+        );
+        assert_cc_matches!(rs_api_impl, quote! { __this->Method(); });
+        Ok(())
+    }
+
     #[test]
     fn test_simple_function_with_types_from_other_target() -> Result<()> {
         let ir = ir_from_cc_dependency(
@@ -4637,14 +6786,55 @@ mod tests {
     }
 
     #[test]
-    fn test_unsigned_int_in_thunk_impls() -> Result<()> {
-        let ir = ir_from_cc("inline void foo(unsigned int i) {} ")?;
-        let rs_api_impl = generate_bindings_tokens(ir)?.rs_api_impl;
+    fn test_std_optional_by_value() -> Result<()> {
+        let ir = ir_from_cc(
+            "namespace std {
+              template <typename T> class optional {
+                T value_;
+                bool has_value_;
+              };
+            }
+            std::optional<int> Twice(std::optional<int> x);",
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn Twice(x: ::core::option::Option<i32>) -> ::core::option::Option<i32> {
+                    unsafe {
+                        let mut __return = ::core::mem::MaybeUninit::<i32>::uninit();
+                        if crate::detail::__rust_thunk___Z5TwiceSt8optionalIiE(
+                            __return.as_mut_ptr(),
+                            match &x {
+                                ::core::option::Option::Some(value) => value as *const i32,
+                                ::core::option::Option::None => ::core::ptr::null(),
+                            }
+                        ) {
+                            ::core::option::Option::Some(__return.assume_init())
+                        } else {
+                            ::core::option::Option::None
+                        }
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z5TwiceSt8optionalIiE(
+                    __return: *mut i32, x: *const i32) -> bool;
+            }
+        );
         assert_cc_matches!(
             rs_api_impl,
             quote! {
-                extern "C" void __rust_thunk___Z3fooj(unsigned int i) {
-                    foo(i);
+                extern "C" bool __rust_thunk___Z5TwiceSt8optionalIiE(int* __return, int const* x) {
+                    auto __result = Twice((x ? std::optional<int>(*x) : std::nullopt));
+                    if (__result.has_value()) {
+                        *__return = *__result;
+                    }
+                    return __result.has_value();
                 }
             }
         );
@@ -4652,19 +6842,55 @@ mod tests {
     }
 
     #[test]
-    fn test_record_static_methods_qualify_call_in_thunk() -> Result<()> {
+    fn test_std_unique_ptr_by_value() -> Result<()> {
         let ir = ir_from_cc(
-            r#"
-            struct SomeStruct {
-                static inline int some_func() { return 42; }
-            }; "#,
+            "namespace std {
+              template <typename T> struct default_delete {};
+              template <typename T, typename D = default_delete<T>> class unique_ptr {
+                T* ptr_;
+              };
+            }
+            struct Widget final { int id; };
+            std::unique_ptr<Widget> Recycle(std::unique_ptr<Widget> widget);",
         )?;
-
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn Recycle(
+                    widget: ::cc_std::UniquePtr<crate::Widget>
+                ) -> ::cc_std::UniquePtr<crate::Widget> {
+                    unsafe {
+                        ::cc_std::UniquePtr::from_raw(
+                            crate::detail::__rust_thunk___Z7RecycleSt10unique_ptrI6WidgetSt14default_deleteIS0_EE(
+                                ::cc_std::UniquePtr::into_raw(widget)
+                            ),
+                            crate::detail::__rust_thunk___Z7RecycleSt10unique_ptrI6WidgetSt14default_deleteIS0_EE__delete_return_value,
+                        )
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z7RecycleSt10unique_ptrI6WidgetSt14default_deleteIS0_EE(
+                    widget: *mut crate::Widget) -> *mut crate::Widget;
+                pub(crate) fn __rust_thunk___Z7RecycleSt10unique_ptrI6WidgetSt14default_deleteIS0_EE__delete_return_value(
+                    ptr: *mut crate::Widget);
+            }
+        );
         assert_cc_matches!(
-            generate_bindings_tokens(ir)?.rs_api_impl,
+            rs_api_impl,
             quote! {
-                extern "C" int __rust_thunk___ZN10SomeStruct9some_funcEv() {
-                    return SomeStruct::some_func();
+                extern "C" struct Widget* __rust_thunk___Z7RecycleSt10unique_ptrI6WidgetSt14default_deleteIS0_EE(
+                        struct Widget* widget) {
+                    return Recycle(std::unique_ptr<struct Widget>(widget)).release();
+                }
+                extern "C" void __rust_thunk___Z7RecycleSt10unique_ptrI6WidgetSt14default_deleteIS0_EE__delete_return_value(
+                        struct Widget* ptr) {
+                    delete ptr;
                 }
             }
         );
@@ -4672,20 +6898,70 @@ mod tests {
     }
 
     #[test]
-    fn test_record_instance_methods_deref_this_in_thunk() -> Result<()> {
+    fn test_std_shared_ptr_by_value() -> Result<()> {
         let ir = ir_from_cc(
-            r#"
-            struct SomeStruct {
-                inline int some_func(int arg) const { return 42 + arg; }
-            }; "#,
+            "namespace std {
+              template <typename T> class shared_ptr {
+                T* ptr_;
+                void* control_block_;
+              };
+            }
+            struct Widget final { int id; };
+            std::shared_ptr<Widget> Share(std::shared_ptr<Widget> widget);",
         )?;
-
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn Share(
+                    widget: ::cc_std::SharedPtr<crate::Widget>
+                ) -> ::cc_std::SharedPtr<crate::Widget> {
+                    unsafe {
+                        ::cc_std::SharedPtr::from_raw(
+                            crate::detail::__rust_thunk___Z5ShareSt10shared_ptrI6WidgetE(
+                                ::cc_std::SharedPtr::into_raw(widget)
+                            ),
+                            crate::detail::__rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__get_return_value,
+                            crate::detail::__rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__clone_return_value,
+                            crate::detail::__rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__delete_return_value,
+                        )
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE(
+                    widget: *mut ::core::ffi::c_void) -> *mut ::core::ffi::c_void;
+                pub(crate) fn __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__get_return_value(
+                    handle: *mut ::core::ffi::c_void) -> *mut crate::Widget;
+                pub(crate) fn __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__clone_return_value(
+                    handle: *mut ::core::ffi::c_void) -> *mut ::core::ffi::c_void;
+                pub(crate) fn __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__delete_return_value(
+                    handle: *mut ::core::ffi::c_void);
+            }
+        );
         assert_cc_matches!(
-            generate_bindings_tokens(ir)?.rs_api_impl,
+            rs_api_impl,
             quote! {
-                extern "C" int __rust_thunk___ZNK10SomeStruct9some_funcEi(
-                        const struct SomeStruct* __this, int arg) {
-                    return __this->some_func(arg);
+                extern "C" std::shared_ptr<struct Widget>* __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE(
+                        std::shared_ptr<struct Widget>* widget) {
+                    return new std::shared_ptr<struct Widget>(Share(
+                        std::move(*std::unique_ptr<std::shared_ptr<struct Widget>>(widget))));
+                }
+                extern "C" struct Widget* __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__get_return_value(
+                        std::shared_ptr<struct Widget>* handle) {
+                    return handle->get();
+                }
+                extern "C" std::shared_ptr<struct Widget>* __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__clone_return_value(
+                        std::shared_ptr<struct Widget>* handle) {
+                    return new std::shared_ptr<struct Widget>(*handle);
+                }
+                extern "C" void __rust_thunk___Z5ShareSt10shared_ptrI6WidgetE__delete_return_value(
+                        std::shared_ptr<struct Widget>* handle) {
+                    delete handle;
                 }
             }
         );
@@ -4693,75 +6969,641 @@ mod tests {
     }
 
     #[test]
-    fn test_record_with_unsupported_field_type() -> Result<()> {
-        // Using a nested struct because it's currently not supported.
-        // But... any other unsupported type would also work for this test.
+    fn test_absl_string_view_and_span_params() -> Result<()> {
         let ir = ir_from_cc(
-            r#"
-            struct StructWithUnsupportedField {
-              struct NestedStruct {
-                int nested_field;
+            "namespace absl {
+              class string_view {
+                const char* data_;
+                decltype(sizeof(0)) size_;
               };
-
-              // Doc comment for `my_field`.
-              NestedStruct my_field;
-            };
-        "#,
+              template <typename T> class Span {
+                T* data_;
+                decltype(sizeof(0)) size_;
+              };
+            }
+            void Print(absl::string_view text);
+            int Sum(absl::Span<const int> values);
+            void Fill(absl::Span<int> values);",
         )?;
-        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
         assert_rs_matches!(
             rs_api,
             quote! {
-                #[repr(C, align(4))]
-                pub struct StructWithUnsupportedField {
-                    #[doc = " Doc comment for `my_field`.\n \n Reason for representing this field as a blob of bytes:\n Unsupported type 'struct StructWithUnsupportedField::NestedStruct': No generated bindings found for 'NestedStruct'"]
-                    pub(crate) my_field: [::core::mem::MaybeUninit<u8>; 4],
+                #[inline(always)]
+                pub fn Print(text: &str) {
+                    unsafe {
+                        crate::detail::__rust_thunk___Z5PrintN4absl11string_viewE(
+                            ::cc_std::SliceRef::from(text)
+                        )
+                    }
                 }
-                ...
-                const _: () = assert!(
-                    memoffset::offset_of!(crate::StructWithUnsupportedField, my_field) == 0);
             }
         );
-        Ok(())
-    }
-
-    #[test]
-    fn test_struct_with_unnamed_bitfield_member() -> Result<()> {
-        // This test input causes `field_decl->getName()` to return an empty string.
-        // This example is based on `struct timex` from
-        // /usr/grte/v5/include/bits/timex.h
-        let ir = ir_from_cc(
-            r#"
-            struct SomeStruct {
-                int first_field;
-                int :32;
-                int last_field;
-            }; "#,
-        )?;
-        let BindingsTokens { rs_api, .. } = generate_bindings_tokens(ir)?;
         assert_rs_matches!(
             rs_api,
             quote! {
-               #[repr(C)]
-               pub struct SomeStruct {
-                   pub first_field: i32, ...
-                   __bitfields1: [::core::mem::MaybeUninit<u8>; 4],
-                   pub last_field: i32,
-               }
-               ...
-               const _: () = assert!(memoffset::offset_of!(crate::SomeStruct, first_field) == 0);
-               const _: () = assert!(memoffset::offset_of!(crate::SomeStruct, last_field) == 8);
+                #[inline(always)]
+                pub fn Sum(values: &[i32]) -> i32 {
+                    unsafe {
+                        crate::detail::__rust_thunk___Z3SumN4absl4SpanIKiEE(
+                            ::cc_std::SliceRef::from(values)
+                        )
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn Fill(values: &mut [i32]) {
+                    unsafe {
+                        crate::detail::__rust_thunk___Z4FillN4absl4SpanIiEE(
+                            ::cc_std::SliceRef::from(values)
+                        )
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z5PrintN4absl11string_viewE(text: ::cc_std::SliceRef);
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                __HASH_TOKEN__ include "crubit/rs_bindings_support/internal/slice_ref.h"
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z5PrintN4absl11string_viewE(crubit::SliceRef text) {
+                    Print(absl::string_view(static_cast<const char*>(text.data), text.size));
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___Z3SumN4absl4SpanIKiEE(crubit::SliceRef values) {
+                    return Sum(absl::Span<int const>(static_cast<int const*>(values.data), values.size));
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z4FillN4absl4SpanIiEE(crubit::SliceRef values) {
+                    Fill(absl::Span<int>(static_cast<int*>(values.data), values.size));
+                }
             }
         );
         Ok(())
     }
 
     #[test]
-    fn test_struct_with_inheritable_field() -> Result<()> {
+    fn test_absl_string_view_return_value_is_not_mapped() -> Result<()> {
         let ir = ir_from_cc(
-            r#"
-            struct TrivialButInheritable {
-              int x;
+            "namespace absl {
+              class string_view {
+                const char* data_;
+                decltype(sizeof(0)) size_;
+              };
+            }
+            absl::string_view GetName();",
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! { -> &str });
+        assert_rs_matches!(rs_api, quote! { pub fn GetName() -> crate::absl::string_view });
+        Ok(())
+    }
+
+    #[test]
+    fn test_std_function_and_absl_any_invocable_params() -> Result<()> {
+        let ir = ir_from_cc(
+            "namespace std {
+              template <typename Signature> class function;
+              template <typename R, typename... Args> class function<R(Args...)> {
+                void* state_[4];
+              };
+            }
+            namespace absl {
+              template <typename Signature> class AnyInvocable {
+                void* state_[4];
+              };
+            }
+            int ApplyTwice(std::function<int(int)> f, int value);
+            void Run(absl::AnyInvocable<void()> f);",
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn ApplyTwice(
+                    f: impl Fn(i32) -> i32 + Send + Sync + 'static,
+                    value: i32
+                ) -> i32 {
+                    unsafe {
+                        crate::detail::__rust_thunk___Z10ApplyTwiceSt8functionIFiiEEi(
+                            ::cc_std::RustClosure::from_fn::<(i32,), i32, _>(
+                                &mut ::core::mem::ManuallyDrop::new(f)
+                            ),
+                            value
+                        )
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn Run(f: impl FnMut() + Send + 'static) {
+                    unsafe {
+                        crate::detail::__rust_thunk___Z3RunN4absl12AnyInvocableIFvvEEE(
+                            ::cc_std::RustClosure::from_fn_mut::<(), (), _>(
+                                &mut ::core::mem::ManuallyDrop::new(f)
+                            )
+                        )
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z3RunN4absl12AnyInvocableIFvvEEE(
+                    f: ::cc_std::RustClosure
+                );
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                __HASH_TOKEN__ include "crubit/rs_bindings_support/internal/rust_closure.h"
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___Z10ApplyTwiceSt8functionIFiiEEi(
+                    crubit::RustClosure f, int value
+                ) {
+                    return ApplyTwice(
+                        std::function<int(int)>(crubit::RustClosureFunctor<int(int)>(f)),
+                        value
+                    );
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z3RunN4absl12AnyInvocableIFvvEEE(
+                    crubit::RustClosure f
+                ) {
+                    Run(absl::AnyInvocable<void()>(crubit::RustClosureFunctor<void()>(f)));
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ref_to_std_vector() -> Result<()> {
+        let ir = ir_from_cc(
+            "namespace std {
+              template <typename T> class allocator {};
+              template <typename T, typename A = allocator<T>> class vector {
+                T* begin_;
+                T* end_;
+                T* end_cap_;
+              };
+            }
+            #pragma clang lifetime_elision
+            inline int Sum(const std::vector<int>& values) { return 0; }",
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub fn Sum<'a>(values: &'a ::cc_std::Vector<i32>) -> i32 { ... }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int ... (std::vector<int> const* values) {
+                    return Sum(*values);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsigned_int_in_thunk_impls() -> Result<()> {
+        let ir = ir_from_cc("inline void foo(unsigned int i) {} ")?;
+        let rs_api_impl = generate_bindings_tokens(ir)?.rs_api_impl;
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z3fooj(unsigned int i) {
+                    foo(i);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_static_methods_qualify_call_in_thunk() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct SomeStruct {
+                static inline int some_func() { return 42; }
+            }; "#,
+        )?;
+
+        assert_cc_matches!(
+            generate_bindings_tokens(ir)?.rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___ZN10SomeStruct9some_funcEv() {
+                    return SomeStruct::some_func();
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_static_member_functions_are_associated_functions() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            namespace ns {
+            struct Counter final {
+                static Counter Create(int initial_value);
+                static inline int Max(int a, int b) { return a > b ? a : b; }
+                int value;
+            };
+            }  // namespace ns
+            "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Counter {
+                    #[inline(always)]
+                    pub fn Create(initial_value: i32) -> crate::ns::Counter { ... }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Counter {
+                    #[inline(always)]
+                    pub fn Max(a: i32, b: i32) -> i32 { ... }
+                }
+            }
+        );
+        assert_rs_not_matches!(rs_api, quote! { pub fn Max(&self ...) });
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___ZN2ns7Counter3MaxEii(int a, int b) {
+                    return ns::Counter::Max(a, b);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_instance_methods_deref_this_in_thunk() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct SomeStruct {
+                inline int some_func(int arg) const { return 42 + arg; }
+            }; "#,
+        )?;
+
+        assert_cc_matches!(
+            generate_bindings_tokens(ir)?.rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___ZNK10SomeStruct9some_funcEi(
+                        const struct SomeStruct* __this, int arg) {
+                    return __this->some_func(arg);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_const_correct_method_receivers() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct Unpinned final {
+                int Get() const;
+                void Set(int value);
+            };
+            struct Pinned {
+                ~Pinned();
+                int Get() const;
+                void Set(int value);
+            }; "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Unpinned {
+                    #[inline(always)]
+                    pub fn Get<'a>(&'a self) -> i32 { ... }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Unpinned {
+                    #[inline(always)]
+                    pub fn Set<'a>(&'a mut self, value: i32) { ... }
+                }
+            }
+        );
+        // Only mutating methods of `!Unpin` records need a `Pin`.
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Pinned {
+                    #[inline(always)]
+                    pub fn Get<'a>(&'a self) -> i32 { ... }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Pinned {
+                    #[inline(always)]
+                    pub fn Set<'a>(self: ::core::pin::Pin<&'a mut Self>, value: i32) { ... }
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_with_unsupported_field_type() -> Result<()> {
+        // Using a packed struct because it's currently not supported.
+        // But... any other unsupported type would also work for this test.
+        let ir = ir_from_cc(
+            r#"
+            struct StructWithUnsupportedField {
+              struct __attribute__((packed)) NestedStruct {
+                int nested_field;
+              };
+
+              // Doc comment for `my_field`.
+              NestedStruct my_field;
+            };
+        "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[repr(C, align(4))]
+                pub struct StructWithUnsupportedField {
+                    #[doc = " Doc comment for `my_field`.\n \n Reason for representing this field as a blob of bytes:\n Unsupported type 'struct StructWithUnsupportedField::NestedStruct': No generated bindings found for 'NestedStruct'"]
+                    pub(crate) my_field: [::core::mem::MaybeUninit<u8>; 4],
+                }
+                ...
+                const _: () = assert!(
+                    memoffset::offset_of!(crate::StructWithUnsupportedField, my_field) == 0);
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_record() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            namespace ns {
+            struct Outer final {
+              struct Inner final {
+                int Get() const;
+                static Inner Make();
+                int value;
+              };
+              Inner inner;
+            };
+            }  // namespace ns"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub struct Outer {
+                    pub inner: crate::ns::Outer_Inner,
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub struct Outer_Inner {
+                    pub value: i32,
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl Outer_Inner {
+                    #[inline(always)]
+                    pub fn Get<'a>(&'a self) -> i32 {
+                        unsafe { crate::detail::__rust_thunk___ZNK2ns5Outer5Inner3GetEv(self) }
+                    }
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___ZN2ns5Outer5Inner4MakeEv(
+                        struct ns::Outer::Inner* __return) {
+                    new (__return) auto(ns::Outer::Inner::Make());
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                static_assert(sizeof(struct ns::Outer::Inner) == 4);
+                static_assert(alignof(struct ns::Outer::Inner) == 4);
+                static_assert(CRUBIT_OFFSET_OF(value, struct ns::Outer::Inner) == 0);
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_with_unnamed_bitfield_member() -> Result<()> {
+        // This test input causes `field_decl->getName()` to return an empty string.
+        // This example is based on `struct timex` from
+        // /usr/grte/v5/include/bits/timex.h
+        let ir = ir_from_cc(
+            r#"
+            struct SomeStruct {
+                int first_field;
+                int :32;
+                int last_field;
+            }; "#,
+        )?;
+        let BindingsTokens { rs_api, .. } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+               #[repr(C)]
+               pub struct SomeStruct {
+                   pub first_field: i32, ...
+                   __bitfields1: [::core::mem::MaybeUninit<u8>; 4],
+                   pub last_field: i32,
+               }
+               ...
+               const _: () = assert!(memoffset::offset_of!(crate::SomeStruct, first_field) == 0);
+               const _: () = assert!(memoffset::offset_of!(crate::SomeStruct, last_field) == 8);
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_with_bitfields() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct SomeStruct final {
+                int first_field;
+                int bitfield : 3;
+                const unsigned const_bitfield : 5;
+                bool bool_bitfield : 1;
+                int last_field;
+            }; "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+               pub struct SomeStruct {
+                   pub first_field: i32, ...
+                   __bitfields1: [::core::mem::MaybeUninit<u8>; 4],
+                   pub last_field: i32,
+               }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeStruct {
+                    pub fn bitfield(&self) -> i32 {
+                        unsafe { crate::detail::__crubit_bitfield_get__10SomeStruct__bitfield(self) }
+                    }
+                    pub fn set_bitfield(&mut self, value: i32) {
+                        unsafe {
+                            crate::detail::__crubit_bitfield_set__10SomeStruct__bitfield(self, value)
+                        }
+                    }
+                    pub fn const_bitfield(&self) -> u32 { ... }
+                    pub fn bool_bitfield(&self) -> bool { ... }
+                    pub fn set_bool_bitfield(&mut self, value: bool) { ... }
+                }
+            }
+        );
+        // Const bitfields can't be assigned to.
+        assert_rs_not_matches!(rs_api, quote! { set_const_bitfield });
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __crubit_bitfield_get__10SomeStruct__bitfield(
+                    __this: *const crate::SomeStruct
+                ) -> i32;
+                pub(crate) fn __crubit_bitfield_set__10SomeStruct__bitfield(
+                    __this: *mut crate::SomeStruct, value: i32
+                );
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __crubit_bitfield_get__10SomeStruct__bitfield(
+                    const struct SomeStruct* __this) {
+                    return __this->bitfield;
+                }
+                extern "C" void __crubit_bitfield_set__10SomeStruct__bitfield(
+                    struct SomeStruct* __this, int value) {
+                    __this->bitfield = value;
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_with_bitfields_not_unpin() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct SomeStruct {
+                int bitfield : 3;
+            }; "#,
+        )?;
+        let BindingsTokens { rs_api, .. } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub fn set_bitfield(self: ::core::pin::Pin<&mut Self>, value: i32) {
+                    unsafe {
+                        crate::detail::__crubit_bitfield_set__10SomeStruct__bitfield(
+                            ::core::pin::Pin::into_inner_unchecked(self),
+                            value
+                        )
+                    }
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_with_bitfield_and_setter_method() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct SomeStruct final {
+                int bitfield : 3;
+                void set_bitfield(int value);
+            }; "#,
+        )?;
+        let BindingsTokens { rs_api, .. } = generate_bindings_tokens(ir)?;
+        // The C++ method takes precedence over the generated setter.
+        assert_rs_matches!(rs_api, quote! { pub fn bitfield(&self) -> i32 });
+        assert_rs_not_matches!(rs_api, quote! { __crubit_bitfield_set__ });
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_with_inheritable_field() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct TrivialButInheritable {
+              int x;
             };
             struct StructWithInheritable final {
               TrivialButInheritable t;
@@ -4939,20 +7781,15 @@ mod tests {
               int last_field;
             }; "#,
         )?;
-        let BindingsTokens { rs_api, .. } = generate_bindings_tokens(ir)?;
-        // TODO(b/200067824): Once nested structs anhd unions are supported,
-        // `__unnamed_field1` and `__unnamed_field2` should have a real, usable
-        // type.
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
         assert_rs_matches!(
             rs_api,
             quote! {
-               #[repr(C, align(4))]
+               #[repr(C)]
                pub struct StructWithUnnamedMembers {
                    pub first_field: i32,
-                   #[doc =" Reason for representing this field as a blob of bytes:\n Unsupported type 'struct StructWithUnnamedMembers::(anonymous at ./ir_from_cc_virtual_header.h:7:15)': No generated bindings found for ''"]
-                   pub(crate) __unnamed_field1: [::core::mem::MaybeUninit<u8>; 8],
-                   #[doc =" Reason for representing this field as a blob of bytes:\n Unsupported type 'union StructWithUnnamedMembers::(anonymous at ./ir_from_cc_virtual_header.h:11:15)': No generated bindings found for ''"]
-                   pub(crate) __unnamed_field2: [::core::mem::MaybeUninit<u8>; 4],
+                   pub __unnamed_field1: crate::StructWithUnnamedMembers__unnamed_field1,
+                   pub __unnamed_field2: crate::StructWithUnnamedMembers__unnamed_field2,
                    pub last_field: i32,
                }
                ...
@@ -4966,6 +7803,49 @@ mod tests {
                        crate::StructWithUnnamedMembers, last_field) == 16);
             }
         );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+               #[repr(C)]
+               pub struct StructWithUnnamedMembers__unnamed_field1 {
+                   pub anonymous_struct_field_1: i32,
+                   pub anonymous_struct_field_2: i32,
+               }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+               #[repr(C)]
+               pub union StructWithUnnamedMembers__unnamed_field2 {
+                   pub anonymous_union_field_1: i32,
+                   pub anonymous_union_field_2: i32,
+               }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+               impl StructWithUnnamedMembers {
+                   pub fn anonymous_struct_field_1(&self) -> &i32 {
+                       &self.__unnamed_field1.anonymous_struct_field_1
+                   }
+                   pub fn anonymous_struct_field_1_mut(&mut self) -> &mut i32 {
+                       &mut self.__unnamed_field1.anonymous_struct_field_1
+                   }
+                   ...
+                   pub unsafe fn anonymous_union_field_1(&self) -> &i32 {
+                       &self.__unnamed_field2.anonymous_union_field_1
+                   }
+                   pub unsafe fn anonymous_union_field_1_mut(&mut self) -> &mut i32 {
+                       &mut self.__unnamed_field2.anonymous_union_field_1
+                   }
+                   ...
+               }
+            }
+        );
+        // The anonymous records can't be named in C++.
+        assert_cc_not_matches!(rs_api_impl, quote! { StructWithUnnamedMembers__unnamed_field1 });
         Ok(())
     }
 
@@ -5112,6 +7992,96 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_wide_character_types() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" inline void f(const wchar_t* ws, const char16_t* s16, const char32_t* s32,
+                              wchar_t wc); "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub unsafe fn f(ws: *const i32, s16: *const u16, s32: *const u32, wc: i32) {
+                    crate::detail::__rust_thunk___Z1fPKwPKDsPKDiw(ws, s16, s32, wc)
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z1fPKwPKDsPKDiw(
+                    wchar_t const * ws, char16_t const * s16, char32_t const * s32, wchar_t wc) {
+                    f(ws, s16, s32, wc);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lifetimebound_params() -> Result<()> {
+        // `[[clang::lifetimebound]]` implies lifetime elision (even without the
+        // `lifetime_elision` pragma).
+        let ir = ir_from_cc(
+            r#"
+            const int& GetFirst(const int& x [[clang::lifetimebound]], const int& y);
+            struct S final {
+              const int& Get() const [[clang::lifetimebound]];
+            };
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! { pub fn GetFirst<'a, 'b>(x: &'a i32, y: &'b i32) -> &'a i32 { ... } }
+        );
+        assert_rs_matches!(rs_api, quote! { pub fn Get<'a>(&'a self) -> &'a i32 { ... } });
+        Ok(())
+    }
+
+    #[test]
+    fn test_nullability_annotated_pointers() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            namespace absl {
+            template <typename T> using Nullable = T;
+            template <typename T> using Nonnull = T;
+            }
+
+            void TakesNonnull(int* _Nonnull p);
+            void TakesNullable(const int* _Nullable p);
+            void TakesUnannotated(int* p);
+            int* _Nonnull ReturnsNonnull(int* _Nonnull p);
+            void TakesAbslNonnull(absl::Nonnull<int*> p);
+            void TakesAbslNullable(absl::Nullable<int*> p);
+            void TakesNestedNonnull(int* _Nonnull* _Nullable p);
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(rs_api, quote! { pub fn TakesNonnull<'a>(p: &'a mut i32) { ... } });
+        assert_rs_matches!(rs_api, quote! { pub fn TakesNullable<'a>(p: Option<&'a i32>) { ... } });
+        assert_rs_matches!(
+            rs_api,
+            quote! { pub fn TakesUnannotated<'a>(p: Option<&'a mut i32>) { ... } }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { pub fn ReturnsNonnull<'a>(p: &'a mut i32) -> &'a mut i32 { ... } }
+        );
+        assert_rs_matches!(rs_api, quote! { pub fn TakesAbslNonnull<'a>(p: &'a mut i32) { ... } });
+        assert_rs_matches!(
+            rs_api,
+            quote! { pub fn TakesAbslNullable<'a>(p: Option<&'a mut i32>) { ... } }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { pub fn TakesNestedNonnull<'a, 'b>(p: Option<&'b mut &'a mut i32>) { ... } }
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_func_ptr_where_params_are_primitive_types() -> Result<()> {
         let ir = ir_from_cc(r#" int (*get_ptr_to_func())(float, double); "#)?;
@@ -5322,6 +8292,128 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_typedefd_func_ptr() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            typedef void (*callback_t)(int, void*);
+            void RegisterCallback(callback_t callback, void* user_data);
+            inline callback_t inline_get_callback() { return nullptr; }
+        "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub type callback_t = Option<extern "C" fn(i32, *mut ::core::ffi::c_void)>;
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub unsafe fn RegisterCallback(
+                    callback: crate::callback_t, user_data: *mut ::core::ffi::c_void) {
+                    crate::detail::__rust_thunk___Z16RegisterCallbackPFviPvES_(callback, user_data)
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn inline_get_callback() -> crate::callback_t { ... }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" callback_t __rust_thunk___Z19inline_get_callbackv() {
+                    return inline_get_callback();
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_variadic_function() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            // Doc comment of my_printf.
+            int my_printf(const char* format, ...);
+            extern "C" void c_log(int level, const char* format, ...);
+        "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                extern "C" {
+                    #[doc = " Doc comment of my_printf.\n \n Generated from: google3/ir_from_cc_virtual_header.h;l=5"]
+                    #[link_name = "_Z9my_printfPKcz"]
+                    pub fn my_printf(format: *const i8, ...) -> i32;
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                extern "C" {
+                    ...
+                    #[link_name = "c_log"]
+                    pub fn c_log(level: i32, format: *const i8, ...);
+                }
+            }
+        );
+        // Variadic arguments can't be forwarded by C++ thunks.
+        assert_rs_not_matches!(rs_api, quote! { __rust_thunk___Z9my_printfPKcz });
+        assert_cc_not_matches!(rs_api_impl, quote! { my_printf });
+        Ok(())
+    }
+
+    #[test]
+    fn test_variadic_function_requires_experimental_features() -> Result<()> {
+        let mut ir = ir_from_cc("int my_printf(const char* format, ...);")?;
+        *ir.target_crubit_features_mut(&ir.current_target().clone()) =
+            ir::CrubitFeature::Supported.into();
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! { my_printf });
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_variadic_functions() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            void NoNamedParams(...);
+            struct S final { int field; };
+            void TakesStructByValue(S s, ...);
+            inline void InlineVariadic(int x, ...) {}
+        "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_cc_matches!(rs_api, {
+            let txt = "Generated from: google3/ir_from_cc_virtual_header.h;l=4\n\
+                       Error while generating bindings for item 'NoNamedParams':\n\
+                       Variadic functions without named parameters are not supported";
+            quote! { __COMMENT__ #txt }
+        });
+        assert_cc_matches!(rs_api, {
+            let txt = "Generated from: google3/ir_from_cc_virtual_header.h;l=6\n\
+                       Error while generating bindings for item 'TakesStructByValue':\n\
+                       Variadic functions can't take or return `crate :: S` by value";
+            quote! { __COMMENT__ #txt }
+        });
+        assert_cc_matches!(rs_api, {
+            let txt = "Generated from: google3/ir_from_cc_virtual_header.h;l=7\n\
+                       Error while generating bindings for item 'InlineVariadic':\n\
+                       Inline variadic functions and variadic functions with internal linkage are not supported";
+            quote! { __COMMENT__ #txt }
+        });
+        Ok(())
+    }
+
     #[test]
     fn test_func_ptr_with_custom_abi_thunk() -> Result<()> {
         // Using an `inline` keyword forces generation of a C++ thunk in
@@ -5723,25 +8815,68 @@ mod tests {
         assert_rs_matches!(
             rs_api,
             quote! {
-                #[repr(transparent)]
+                #[repr(transparent)]
+                #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+                pub struct Color(i32);
+                impl Color {
+                    pub const kRed: Color = Color(-5);
+                    pub const kBlue: Color = Color(-4);
+                }
+                impl From<i32> for Color {
+                    fn from(value: i32) -> Color {
+                        Color(value)
+                    }
+                }
+                impl From<Color> for i32 {
+                    fn from(value: Color) -> i32 {
+                        value.0
+                    }
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_closed_enum() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"enum class [[clang::annotate("crubit_closed_enum")]] Color : unsigned char {
+                kRed,
+                kBlue = 5,
+                kDefault = kRed,
+            };"#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[repr(u8)]
                 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
-                pub struct Color(i32);
-                impl Color {
-                    pub const kRed: Color = Color(-5);
-                    pub const kBlue: Color = Color(-4);
+                pub enum Color {
+                    kRed = 0,
+                    kBlue = 5,
                 }
-                impl From<i32> for Color {
-                    fn from(value: i32) -> Color {
-                        Color(value)
+                impl Color {
+                    pub const kDefault: Color = Color::kRed;
+                }
+                impl ::core::convert::TryFrom<u8> for Color {
+                    type Error = u8;
+                    fn try_from(value: u8) -> ::core::result::Result<Color, u8> {
+                        match value {
+                            0 => ::core::result::Result::Ok(Color::kRed),
+                            5 => ::core::result::Result::Ok(Color::kBlue),
+                            _ => ::core::result::Result::Err(value),
+                        }
                     }
                 }
-                impl From<Color> for i32 {
-                    fn from(value: Color) -> i32 {
-                        value.0
+                impl From<Color> for u8 {
+                    fn from(value: Color) -> u8 {
+                        value as u8
                     }
                 }
             }
         );
+        assert_rs_not_matches!(rs_api, quote! { pub struct Color });
         Ok(())
     }
 
@@ -5933,6 +9068,220 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_generate_enum_bitwise_operators() -> Result<()> {
+        let ir = ir_from_cc("enum Flags : unsigned { kNone = 0, kRead = 1, kWrite = 2 };")?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl ::core::ops::BitAnd for Flags {
+                    type Output = Flags;
+                    fn bitand(self, other: Flags) -> Flags {
+                        Flags(self.0 & other.0)
+                    }
+                }
+                impl ::core::ops::BitAndAssign for Flags {
+                    fn bitand_assign(&mut self, other: Flags) {
+                        self.0 = self.0 & other.0;
+                    }
+                }
+                impl ::core::ops::BitOr for Flags {
+                    type Output = Flags;
+                    fn bitor(self, other: Flags) -> Flags {
+                        Flags(self.0 | other.0)
+                    }
+                }
+                impl ::core::ops::BitOrAssign for Flags {
+                    fn bitor_assign(&mut self, other: Flags) {
+                        self.0 = self.0 | other.0;
+                    }
+                }
+                impl ::core::ops::BitXor for Flags {
+                    type Output = Flags;
+                    fn bitxor(self, other: Flags) -> Flags {
+                        Flags(self.0 ^ other.0)
+                    }
+                }
+                impl ::core::ops::BitXorAssign for Flags {
+                    fn bitxor_assign(&mut self, other: Flags) {
+                        self.0 = self.0 ^ other.0;
+                    }
+                }
+                impl ::core::ops::Not for Flags {
+                    type Output = Flags;
+                    fn not(self) -> Flags {
+                        Flags(!self.0)
+                    }
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_scoped_enum_without_bitwise_operators() -> Result<()> {
+        let ir = ir_from_cc("enum class Color { kRed, kBlue };")?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! { BitOr });
+        assert_rs_not_matches!(rs_api, quote! { Not });
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_enum_with_overloaded_bitwise_operator() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+                enum Flags { kRead = 1, kWrite = 2 };
+                Flags operator|(Flags lhs, Flags rhs);
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        // Only the binding of the C++ `operator|` implements `BitOr`.
+        assert_rs_matches!(rs_api, quote! { fn bitor });
+        assert_rs_not_matches!(rs_api, quote! { fn bitor(self, other: Flags) -> Flags });
+        assert_rs_matches!(rs_api, quote! { impl ::core::ops::BitOrAssign for Flags });
+        assert_rs_matches!(rs_api, quote! { impl ::core::ops::BitAnd for Flags });
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_constants() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+                // Doc comment for kAnswer.
+                constexpr int kAnswer = 42;
+                constexpr long long kMin = -9223372036854775807 - 1LL;
+                constexpr unsigned long long kMax = 18446744073709551615ULL;
+                constexpr bool kFlag = true;
+                constexpr double kDouble = 1.5;
+                constexpr float kFloat = -0.1f;
+                using MyBool = bool;
+                constexpr MyBool kAliasedFlag = false;
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[doc = " Doc comment for kAnswer.\n \n Generated from: google3/ir_from_cc_virtual_header.h;l=5"]
+                pub const kAnswer: i32 = 42;
+            }
+        );
+        assert_rs_matches!(rs_api, quote! { pub const kMin: i64 = -9223372036854775808; });
+        assert_rs_matches!(rs_api, quote! { pub const kMax: u64 = 18446744073709551615; });
+        assert_rs_matches!(rs_api, quote! { pub const kFlag: bool = true; });
+        assert_rs_matches!(rs_api, quote! { pub const kDouble: f64 = 1.5; });
+        // `-0.1f` is formatted as the nearest `f32` (rather than as the `f64` it
+        // was widened to).
+        assert_rs_matches!(rs_api, quote! { pub const kFloat: f32 = -0.1; });
+        assert_rs_matches!(rs_api, quote! { pub const kAliasedFlag: crate::MyBool = false; });
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_macro_constants() -> Result<()> {
+        let mut ir = ir_from_cc(
+            r#"
+                #define MAX_LEN 128
+                #define GREETING "Hello"
+            "#,
+        )?;
+        *ir.target_crubit_features_mut(&ir.current_target().clone()) |= ir::CrubitFeature::Macros;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(rs_api, quote! { pub const MAX_LEN: i32 = 128; });
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub const GREETING: &::core::ffi::CStr = unsafe {
+                    ::core::ffi::CStr::from_bytes_with_nul_unchecked(b"Hello\0")
+                };
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_macro_constants_require_macros_feature() -> Result<()> {
+        let ir = ir_from_cc("#define MAX_LEN 128")?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! { MAX_LEN });
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_static_constexpr_member() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+                namespace ns {
+                struct SomeStruct final {
+                  static constexpr int kSize = 16;
+                };
+                }  // namespace ns
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeStruct {
+                    #[doc = " Generated from: google3/ir_from_cc_virtual_header.h;l=6"]
+                    pub const kSize: i32 = 16;
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_static_data_member() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+                namespace ns {
+                struct SomeStruct final {
+                  static int counter;
+                };
+                }  // namespace ns
+            "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeStruct {
+                    #[doc = " Generated from: google3/ir_from_cc_virtual_header.h;l=6"]
+                    #[inline(always)]
+                    pub unsafe fn counter() -> i32 {
+                        crate::detail::__rust_thunk___ZN2ns10SomeStruct7counterE__get()
+                    }
+                    #[inline(always)]
+                    pub unsafe fn set_counter(value: i32) {
+                        crate::detail::__rust_thunk___ZN2ns10SomeStruct7counterE__set(value)
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___ZN2ns10SomeStruct7counterE__get() -> i32;
+                pub(crate) fn __rust_thunk___ZN2ns10SomeStruct7counterE__set(value: i32);
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___ZN2ns10SomeStruct7counterE__get() {
+                    return ns::SomeStruct::counter;
+                }
+                extern "C" void __rust_thunk___ZN2ns10SomeStruct7counterE__set(int value) {
+                    ns::SomeStruct::counter = value;
+                }
+            }
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_doc_comment_func() -> Result<()> {
         let ir = ir_from_cc(
@@ -5969,17 +9318,223 @@ mod tests {
         )?;
 
         assert_rs_matches!(
-            generate_bindings_tokens(ir)?.rs_api,
+            generate_bindings_tokens(ir)?.rs_api,
+            quote! {
+                #[doc = " Doc Comment\n \n  * with bullet\n \n Generated from: google3/ir_from_cc_virtual_header.h;l=6"]
+                #[crubit_annotate::cpp_type = "::SomeStruct"]
+                #[derive(Clone, Copy)]
+                #[repr(C)]
+                pub struct SomeStruct {
+                    # [doc = " Field doc"]
+                    pub field: i32,
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpp_type_annotations() -> Result<()> {
+        let ir = ir_from_cc(
+            "namespace ns {
+                struct Outer final {
+                    struct Inner final {};
+                };
+            }",
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #![crubit_annotate::cpp_header = "ir_from_cc_virtual_header.h"]
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[crubit_annotate::cpp_type = "::ns::Outer"]
+                ...
+                pub struct Outer { ... }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[crubit_annotate::cpp_type = "::ns::Outer::Inner"]
+                ...
+                pub struct Inner { ... }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_doc_comment_enum() -> Result<()> {
+        let ir = ir_from_cc(
+            "// Doc Comment\n\
+            enum Color {\n\
+                // Enumerator doc\n\
+                kRed,\n\
+                kBlue,\n\
+            };",
+        )?;
+
+        assert_rs_matches!(
+            generate_bindings_tokens(ir)?.rs_api,
+            quote! {
+                #[doc = " Doc Comment\n \n Generated from: google3/ir_from_cc_virtual_header.h;l=4"]
+                #[repr(transparent)]
+                #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+                pub struct Color(u32);
+                impl Color {
+                    #[doc = " Enumerator doc"]
+                    pub const kRed: Color = Color(0);
+                    pub const kBlue: Color = Color(1);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_doc_comment_macro_constant() -> Result<()> {
+        let mut ir = ir_from_cc(
+            "// Maximum length\n\
+            #define MAX_LEN 128",
+        )?;
+        *ir.target_crubit_features_mut(&ir.current_target().clone()) |= ir::CrubitFeature::Macros;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[doc = " Maximum length\n \n Generated from: google3/ir_from_cc_virtual_header.h;l=4"]
+                pub const MAX_LEN: i32 = 128;
+            }
+        );
+        // The comment is not repeated as a free-standing comment.
+        assert_rs_not_matches!(rs_api, quote! { __COMMENT__ "Maximum length" });
+        Ok(())
+    }
+
+    #[test]
+    fn test_nodiscard_func() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            [[nodiscard]] int Compute();
+            [[nodiscard("the error code has to be checked")]] int TryCompute();
+            __attribute__((warn_unused_result)) int LegacyCompute();
+            struct S final {
+              [[nodiscard]] int Get() const;
+            };
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! { #[must_use] #[inline(always)] pub fn Compute() -> i32 }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[must_use = "the error code has to be checked"]
+                #[inline(always)]
+                pub fn TryCompute() -> i32
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { #[must_use] #[inline(always)] pub fn LegacyCompute() -> i32 }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { #[must_use] #[inline(always)] pub fn Get<'a>(&'a self) -> i32 }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nodiscard_record_and_enum() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct [[nodiscard]] Status final { int code; };
+            enum class [[nodiscard("check the result")]] Result { kOk, kError };
+            struct NotNodiscard final { int field; };
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[must_use]
+                #[derive(Clone, Copy)]
+                #[repr(C)]
+                pub struct Status
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[must_use = "check the result"]
+                #[repr(transparent)]
+                #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+                pub struct Result(i32);
+            }
+        );
+        assert_rs_not_matches!(
+            rs_api,
+            quote! { #[must_use] #[derive(Clone, Copy)] #[repr(C)] pub struct NotNodiscard }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deprecated() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            [[deprecated("Use `NewFunc` instead")]] void OldFunc();
+            struct [[deprecated]] OldStruct final {
+              [[deprecated]] void OldMethod();
+            };
+            enum class OldEnum {
+              kOld [[deprecated("Use `kNew` instead")]],
+              kNew,
+            };
+            using OldAlias [[deprecated]] = int;
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
             quote! {
-                #[doc = " Doc Comment\n \n  * with bullet\n \n Generated from: google3/ir_from_cc_virtual_header.h;l=6"]
+                #[deprecated = "Use `NewFunc` instead"]
+                #[inline(always)]
+                pub fn OldFunc()
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[deprecated]
                 #[derive(Clone, Copy)]
                 #[repr(C)]
-                pub struct SomeStruct {
-                    # [doc = " Field doc"]
-                    pub field: i32,
+                pub struct OldStruct
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { #[deprecated] #[inline(always)] pub fn OldMethod<'a>(&'a mut self) }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl OldEnum {
+                    #[deprecated = "Use `kNew` instead"]
+                    pub const kOld: OldEnum = OldEnum(0);
+                    pub const kNew: OldEnum = OldEnum(1);
                 }
             }
         );
+        assert_rs_matches!(rs_api, quote! { #[deprecated] pub type OldAlias = i32; });
         Ok(())
     }
 
@@ -6308,93 +9863,396 @@ mod tests {
     }
 
     #[test]
-    fn test_unambiguous_public_bases() -> Result<()> {
-        let ir = ir_from_cc_dependency(
-            "
-            struct VirtualBase {};
-            struct PrivateBase {};
-            struct ProtectedBase {};
-            struct UnambiguousPublicBase {};
-            struct AmbiguousPublicBase {};
-            struct MultipleInheritance : UnambiguousPublicBase, AmbiguousPublicBase {};
-            struct Derived : private PrivateBase, protected ProtectedBase, MultipleInheritance, AmbiguousPublicBase, virtual VirtualBase {};
-        ",
-            "",
+    fn test_unambiguous_public_bases() -> Result<()> {
+        let ir = ir_from_cc_dependency(
+            "
+            struct VirtualBase {};
+            struct PrivateBase {};
+            struct ProtectedBase {};
+            struct UnambiguousPublicBase {};
+            struct AmbiguousPublicBase {};
+            struct MultipleInheritance : UnambiguousPublicBase, AmbiguousPublicBase {};
+            struct Derived : private PrivateBase, protected ProtectedBase, MultipleInheritance, AmbiguousPublicBase, virtual VirtualBase {};
+        ",
+            "",
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                unsafe impl oops::Inherits<crate::VirtualBase> for crate::Derived {
+                    unsafe fn upcast_ptr(derived: *const Self) -> *const crate::VirtualBase {
+                        crate::detail::__crubit_dynamic_upcast__7Derived__to__11VirtualBase(derived)
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { unsafe impl oops::Inherits<crate::UnambiguousPublicBase> for crate::Derived }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! { unsafe impl oops::Inherits<crate::MultipleInheritance> for crate::Derived }
+        );
+        assert_rs_not_matches!(
+            rs_api,
+            quote! {unsafe impl oops::Inherits<crate::PrivateBase> for crate::Derived}
+        );
+        assert_rs_not_matches!(
+            rs_api,
+            quote! {unsafe impl oops::Inherits<crate::ProtectedBase> for crate::Derived}
+        );
+        assert_rs_not_matches!(
+            rs_api,
+            quote! {unsafe impl oops::Inherits<crate::AmbiguousPublicBase> for crate::Derived}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deref_to_base_class() -> Result<()> {
+        let ir = ir_from_cc(
+            "
+            struct Base1 {};
+            struct Base2 {};
+            struct Intermediate : Base1 {};
+            struct SingleInheritance : Intermediate {};
+            struct MultipleInheritance : Base1, Base2 {};
+        ",
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl ::core::ops::Deref for crate::Intermediate {
+                    type Target = crate::Base1;
+                    #[inline(always)]
+                    fn deref(&self) -> &crate::Base1 {
+                        unsafe { &*<Self as oops::Inherits<crate::Base1>>::upcast_ptr(self) }
+                    }
+                }
+            }
+        );
+        // `Base1` is reachable through `Intermediate`.
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl ::core::ops::Deref for crate::SingleInheritance {
+                    type Target = crate::Intermediate;
+                    ...
+                }
+            }
+        );
+        assert_rs_not_matches!(
+            rs_api,
+            quote! { impl ::core::ops::Deref for crate::MultipleInheritance }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_as_ref_to_base_classes_with_multiple_inheritance() -> Result<()> {
+        let ir = ir_from_cc(
+            "
+            struct Base1 { long long x; };
+            struct Base2 { int y; };
+            struct Derived : Base1, Base2 {};
+            struct Single : Base1 {};
+        ",
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                unsafe impl oops::Inherits<crate::Base2> for crate::Derived {
+                    unsafe fn upcast_ptr(derived: *const Self) -> *const crate::Base2 {
+                        (derived as *const _ as *const u8).offset(8) as *const crate::Base2
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl ::core::convert::AsRef<crate::Base1> for crate::Derived {
+                    #[inline(always)]
+                    fn as_ref(&self) -> &crate::Base1 {
+                        unsafe { &*<Self as oops::Inherits<crate::Base1>>::upcast_ptr(self) }
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl ::core::convert::AsRef<crate::Base2> for crate::Derived {
+                    #[inline(always)]
+                    fn as_ref(&self) -> &crate::Base2 {
+                        unsafe { &*<Self as oops::Inherits<crate::Base2>>::upcast_ptr(self) }
+                    }
+                }
+            }
+        );
+        // Classes with a `Deref` target don't need `AsRef`.
+        assert_rs_not_matches!(
+            rs_api,
+            quote! { impl ::core::convert::AsRef<crate::Base1> for crate::Single }
+        );
+        Ok(())
+    }
+
+    /// Contrary to intuitions: a base class conversion is ambiguous even if the
+    /// ambiguity is from a private base class cast that you can't even
+    /// perform.
+    ///
+    /// Explanation (courtesy James Dennett):
+    ///
+    /// > Once upon a time, there was a rule in C++ that changing all access
+    /// > specifiers to "public" would not change the meaning of code.
+    /// > That's no longer true, but some of its effects can still be seen.
+    ///
+    /// So, we need to be sure to not allow casting to privately-ambiguous
+    /// bases.
+    #[test]
+    fn test_unambiguous_public_bases_private_ambiguity() -> Result<()> {
+        let ir = ir_from_cc_dependency(
+            "
+            struct Base {};
+            struct Intermediate : public Base {};
+            struct Derived : Base, private Intermediate {};
+        ",
+            "",
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(
+            rs_api,
+            quote! { unsafe impl oops::Inherits<crate::Base> for Derived }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtual_thunk() -> Result<()> {
+        let ir = ir_from_cc("struct Polymorphic { virtual void Foo(); };")?;
+
+        assert_cc_matches!(
+            generate_bindings_tokens(ir)?.rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___ZN11Polymorphic3FooEv(struct Polymorphic * __this)
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtual_method_overrides() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                class Listener {
+                 public:
+                  virtual ~Listener();
+                  virtual void OnEvent(int event) = 0;
+                  virtual int GetPriority() const;
+                };"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub trait ListenerOverrides {
+                    fn OnEvent(&mut self, event: i32);
+                    fn GetPriority(&self) -> i32;
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl<'a> ListenerWithOverrides<'a> {
+                    pub fn new<T: ListenerOverrides>(overrides: &'a mut T) -> Self {
+                        ...
+                        unsafe extern "C" fn __crubit_override_OnEvent<T: ListenerOverrides>(
+                            rust_self: *mut ::core::ffi::c_void, event: i32
+                        ) {
+                            <T as ListenerOverrides>::OnEvent(&mut *(rust_self as *mut T), event)
+                        }
+                        unsafe extern "C" fn __crubit_override_GetPriority<T: ListenerOverrides>(
+                            rust_self: *mut ::core::ffi::c_void
+                        ) -> i32 {
+                            <T as ListenerOverrides>::GetPriority(&*(rust_self as *const T))
+                        }
+                        ...
+                    }
+                    ...
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                class __crubit_overrides__8Listener final : public Listener {
+                public:
+                    ...
+                    void OnEvent(int event) override {
+                        return vtable_.OnEvent(rust_self_, event);
+                    }
+                    int GetPriority() const override {
+                        return vtable_.GetPriority(rust_self_);
+                    }
+                    ...
+                };
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtual_method_overrides_protected() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                class Handler {
+                 public:
+                  virtual ~Handler();
+                 protected:
+                  Handler();
+                  virtual bool Handle(int request) = 0;
+                };"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub trait HandlerOverrides {
+                    fn Handle(&mut self, request: i32) -> bool;
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                class __crubit_overrides__7Handler final : public Handler {
+                public:
+                    ...
+                    bool Handle(int request) override {
+                        return vtable_.Handle(rust_self_, request);
+                    }
+                    ...
+                };
+            }
+        );
+        // The protected members can't be called through the bindings.
+        assert_rs_not_matches!(rs_api, quote! { pub fn Handle });
+        assert_rs_not_matches!(rs_api, quote! { impl ::ctor::CtorNew<()> for Handler });
+        assert_cc_not_matches!(rs_api_impl, quote! { __rust_thunk___ZN7Handler6HandleEi });
+        assert_cc_not_matches!(rs_api_impl, quote! { __rust_thunk___ZN7HandlerC1Ev });
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtual_method_overrides_unsupported() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                struct S final {};
+                class TakesRecord {
+                 public:
+                  virtual void Method(S s);
+                };
+                class Private {
+                  virtual void Method();
+                };
+                class Final final {
+                 public:
+                  virtual void Method();
+                };"#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! { TakesRecordOverrides });
+        assert_rs_not_matches!(rs_api, quote! { PrivateOverrides });
+        assert_rs_not_matches!(rs_api, quote! { FinalOverrides });
+        Ok(())
+    }
+
+    #[test]
+    fn test_abstract_class_interface() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                class Shape {
+                 public:
+                  virtual ~Shape();
+                  virtual double Area() const = 0;
+                  virtual void Scale(double factor) = 0;
+                };"#,
         )?;
         let rs_api = generate_bindings_tokens(ir)?.rs_api;
         assert_rs_matches!(
             rs_api,
             quote! {
-                unsafe impl oops::Inherits<crate::VirtualBase> for crate::Derived {
-                    unsafe fn upcast_ptr(derived: *const Self) -> *const crate::VirtualBase {
-                        crate::detail::__crubit_dynamic_upcast__7Derived__to__11VirtualBase(derived)
-                    }
+                pub trait ShapeInterface {
+                    fn Area(&self) -> f64;
+                    fn Scale(self: ::core::pin::Pin<&mut Self>, factor: f64);
                 }
             }
         );
         assert_rs_matches!(
             rs_api,
-            quote! { unsafe impl oops::Inherits<crate::UnambiguousPublicBase> for crate::Derived }
+            quote! {
+                impl ShapeInterface for crate::Shape {
+                    #[inline(always)]
+                    fn Area(&self) -> f64 {
+                        unsafe { crate::detail::__rust_thunk___ZNK5Shape4AreaEv(self) }
+                    }
+                    #[inline(always)]
+                    fn Scale(self: ::core::pin::Pin<&mut Self>, factor: f64) {
+                        unsafe { crate::detail::__rust_thunk___ZN5Shape5ScaleEd(self, factor) }
+                    }
+                }
+            }
         );
         assert_rs_matches!(
             rs_api,
-            quote! { unsafe impl oops::Inherits<crate::MultipleInheritance> for crate::Derived }
-        );
-        assert_rs_not_matches!(
-            rs_api,
-            quote! {unsafe impl oops::Inherits<crate::PrivateBase> for crate::Derived}
-        );
-        assert_rs_not_matches!(
-            rs_api,
-            quote! {unsafe impl oops::Inherits<crate::ProtectedBase> for crate::Derived}
-        );
-        assert_rs_not_matches!(
-            rs_api,
-            quote! {unsafe impl oops::Inherits<crate::AmbiguousPublicBase> for crate::Derived}
+            quote! {
+                impl ShapeInterface for ::cc_std::UniquePtr<crate::Shape> {
+                    #[inline(always)]
+                    fn Area(&self) -> f64 {
+                        <crate::Shape as ShapeInterface>::Area(&**self)
+                    }
+                    #[inline(always)]
+                    fn Scale(self: ::core::pin::Pin<&mut Self>, factor: f64) {
+                        <crate::Shape as ShapeInterface>::Scale(
+                            ::core::pin::Pin::get_mut(self)
+                                .as_mut()
+                                .expect("Calling a method through a null `UniquePtr`"),
+                            factor
+                        )
+                    }
+                }
+            }
         );
         Ok(())
     }
 
-    /// Contrary to intuitions: a base class conversion is ambiguous even if the
-    /// ambiguity is from a private base class cast that you can't even
-    /// perform.
-    ///
-    /// Explanation (courtesy James Dennett):
-    ///
-    /// > Once upon a time, there was a rule in C++ that changing all access
-    /// > specifiers to "public" would not change the meaning of code.
-    /// > That's no longer true, but some of its effects can still be seen.
-    ///
-    /// So, we need to be sure to not allow casting to privately-ambiguous
-    /// bases.
     #[test]
-    fn test_unambiguous_public_bases_private_ambiguity() -> Result<()> {
-        let ir = ir_from_cc_dependency(
-            "
-            struct Base {};
-            struct Intermediate : public Base {};
-            struct Derived : Base, private Intermediate {};
-        ",
-            "",
+    fn test_abstract_class_interface_unsupported() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                struct S final {};
+                class Concrete {
+                 public:
+                  virtual void Method();
+                };
+                class TakesRecord {
+                 public:
+                  virtual void Method(S s) = 0;
+                };
+                class Private {
+                  virtual void Method() = 0;
+                };"#,
         )?;
         let rs_api = generate_bindings_tokens(ir)?.rs_api;
-        assert_rs_not_matches!(
-            rs_api,
-            quote! { unsafe impl oops::Inherits<crate::Base> for Derived }
-        );
-        Ok(())
-    }
-
-    #[test]
-    fn test_virtual_thunk() -> Result<()> {
-        let ir = ir_from_cc("struct Polymorphic { virtual void Foo(); };")?;
-
-        assert_cc_matches!(
-            generate_bindings_tokens(ir)?.rs_api_impl,
-            quote! {
-                extern "C" void __rust_thunk___ZN11Polymorphic3FooEv(struct Polymorphic * __this)
-            }
-        );
+        assert_rs_not_matches!(rs_api, quote! { ConcreteInterface });
+        assert_rs_not_matches!(rs_api, quote! { TakesRecordInterface });
+        assert_rs_not_matches!(rs_api, quote! { PrivateInterface });
         Ok(())
     }
 
@@ -6747,6 +10605,99 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_impl_from_for_implicit_conversion_function() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct SomeStruct final {
+                operator int() const { return i; }
+                int i;
+            };"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl<'a> From<&'a crate::SomeStruct> for i32 {
+                    #[inline(always)]
+                    fn from(__this: &'a crate::SomeStruct) -> i32 {
+                        unsafe { crate::detail::__rust_thunk___ZNK10SomeStructcviEv(__this) }
+                    }
+                }
+                impl From<SomeStruct> for i32 {
+                    #[inline(always)]
+                    fn from(value: SomeStruct) -> Self {
+                        <Self as From<&SomeStruct>>::from(&value)
+                    }
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___ZNK10SomeStructcviEv(
+                        const struct SomeStruct* __this) {
+                    return __this->operator int();
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_explicit_conversion_functions_are_methods() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct OtherStruct final { int i; };
+            struct SomeStruct final {
+                explicit operator bool() const { return i != 0; }
+                explicit operator OtherStruct() const { return OtherStruct{i}; }
+                int i;
+            };"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeStruct {
+                    #[inline(always)]
+                    pub fn to_bool<'a>(&'a self) -> bool {
+                        unsafe { crate::detail::__rust_thunk___ZNK10SomeStructcvbEv(self) }
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeStruct {
+                    #[inline(always)]
+                    pub fn to_other_struct<'a>(&'a self) -> crate::OtherStruct { ... }
+                }
+            }
+        );
+        assert_rs_not_matches!(rs_api, quote! { From<&'a crate::SomeStruct> });
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" bool __rust_thunk___ZNK10SomeStructcvbEv(
+                        const struct SomeStruct* __this) {
+                    return __this->operator bool();
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___ZNK10SomeStructcv11OtherStructEv(
+                        struct OtherStruct* __return, const struct SomeStruct* __this) {
+                    new (__return) auto(__this->operator struct OtherStruct());
+                }
+            }
+        );
+        Ok(())
+    }
+
     /// Methods with missing lifetimes for `self` should give a useful error
     /// message.
     #[test]
@@ -6848,6 +10799,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_impl_eq_and_add_for_hidden_friends_in_namespace() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            namespace ns {
+            struct SomeStruct final {
+                friend bool operator==(const SomeStruct& lhs, const SomeStruct& rhs) {
+                    return lhs.i == rhs.i;
+                }
+                friend SomeStruct operator+(const SomeStruct& lhs, const SomeStruct& rhs) {
+                    return SomeStruct{lhs.i + rhs.i};
+                }
+                friend int get_i(const SomeStruct& s) { return s.i; }
+                int i;
+            };
+            }  // namespace ns"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl PartialEq for SomeStruct {
+                    #[inline(always)]
+                    fn eq<'a, 'b>(&'a self, rhs: &'b Self) -> bool { ... }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl<'a, 'b> ::core::ops::Add<&'b crate::ns::SomeStruct> for &'a crate::ns::SomeStruct {
+                    type Output = crate::ns::SomeStruct;
+                    ...
+                }
+            }
+        );
+        // Hidden friends can only be found via ADL, so the thunks have to use
+        // unqualified names.
+        assert_cc_matches!(rs_api_impl, quote! { return operator==(*lhs, *rhs); });
+        assert_cc_matches!(rs_api_impl, quote! { new (__return) auto(operator+(*lhs, *rhs)); });
+        assert_cc_matches!(rs_api_impl, quote! { return get_i(*s); });
+        assert_cc_not_matches!(rs_api_impl, quote! { ns::get_i });
+        Ok(())
+    }
+
     #[test]
     fn test_impl_eq_for_free_function_by_value() -> Result<()> {
         let ir = ir_from_cc(
@@ -7138,35 +11134,135 @@ mod tests {
     }
 
     #[test]
-    fn test_impl_lt_rhs_by_value() -> Result<()> {
+    fn test_impl_lt_rhs_by_value() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct SomeStruct final {
+                inline bool operator==(const SomeStruct& other) const {
+                    return i == other.i;
+                }
+                int i;
+                bool operator<(SomeStruct other) const;
+            };"#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! {impl PartialOrd});
+        Ok(())
+    }
+
+    #[test]
+    fn test_impl_lt_missing_eq_impl() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct SomeStruct final {
+                inline bool operator<(const SomeStruct& other) const {
+                    return i < other.i;
+                }
+                int i;
+            };"#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! {impl PartialOrd});
+        Ok(())
+    }
+
+    #[test]
+    fn test_impl_index() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct SomeStruct final {
+                inline const int& operator[](int index) const {
+                    return values[index];
+                }
+                int values[4];
+            };"#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl ::core::ops::Index<i32> for SomeStruct {
+                    type Output = i32;
+                    #[inline(always)]
+                    fn index<'a>(&'a self, index: i32) -> &'a i32 {
+                        unsafe { crate::detail::__rust_thunk___ZNK10SomeStructixEi(self, index) }
+                    }
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int const& __rust_thunk___ZNK10SomeStructixEi(
+                        const struct SomeStruct* __this, int index) {
+                    return __this->operator[](index);
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_impl_index_non_const() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            struct SomeStruct final {
+                int& operator[](int index);
+            };"#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_not_matches!(rs_api, quote! {::core::ops::Index});
+        assert_rs_not_matches!(rs_api, quote! {::core::ops::IndexMut});
+        Ok(())
+    }
+
+    #[test]
+    fn test_impl_index_returning_value() -> Result<()> {
         let ir = ir_from_cc(
             r#"#pragma clang lifetime_elision
             struct SomeStruct final {
-                inline bool operator==(const SomeStruct& other) const {
-                    return i == other.i;
-                }
-                int i;
-                bool operator<(SomeStruct other) const;
+                int operator[](int index) const;
             };"#,
         )?;
         let rs_api = generate_bindings_tokens(ir)?.rs_api;
-        assert_rs_not_matches!(rs_api, quote! {impl PartialOrd});
+        assert_rs_not_matches!(rs_api, quote! {::core::ops::Index});
         Ok(())
     }
 
     #[test]
-    fn test_impl_lt_missing_eq_impl() -> Result<()> {
+    fn test_call_operator() -> Result<()> {
         let ir = ir_from_cc(
             r#"#pragma clang lifetime_elision
             struct SomeStruct final {
-                inline bool operator<(const SomeStruct& other) const {
-                    return i < other.i;
-                }
-                int i;
+                int operator()(int x) const;
+            };
+            struct SomeMutableStruct final {
+                int operator()(int x);
             };"#,
         )?;
         let rs_api = generate_bindings_tokens(ir)?.rs_api;
-        assert_rs_not_matches!(rs_api, quote! {impl PartialOrd});
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeStruct {
+                    #[inline(always)]
+                    pub fn call<'a>(&'a self, x: i32) -> i32 {
+                        unsafe { crate::detail::__rust_thunk___ZNK10SomeStructclEi(self, x) }
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl SomeMutableStruct {
+                    #[inline(always)]
+                    pub fn call_mut<'a>(&'a mut self, x: i32) -> i32 {
+                        unsafe { crate::detail::__rust_thunk___ZN17SomeMutableStructclEi(self, x) }
+                    }
+                }
+            }
+        );
         Ok(())
     }
 
@@ -7276,9 +11372,6 @@ mod tests {
 
     #[test]
     fn test_overloaded_functions() -> Result<()> {
-        // TODO(b/213280424): We don't support creating bindings for overloaded
-        // functions yet, except in the case of overloaded constructors with a
-        // single parameter.
         let ir = ir_from_cc(
             r#" #pragma clang lifetime_elision
                 void f() {}
@@ -7299,31 +11392,23 @@ mod tests {
                 namespace bar { void not_overloaded(); }
             "#,
         )?;
-        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
-
-        // Cannot overload free functions.
-        assert_cc_matches!(rs_api, {
-            let txt = "Generated from: google3/ir_from_cc_virtual_header.h;l=4\n\
-                           Error while generating bindings for item 'f':\n\
-                           Cannot generate bindings for overloaded function";
-            quote! { __COMMENT__ #txt }
-        });
-        assert_rs_not_matches!(rs_api, quote! {pub fn f()});
-        assert_rs_not_matches!(rs_api, quote! {pub fn f(i: i32)});
-
-        assert_cc_matches!(rs_api, {
-            let txt = "Generated from: google3/ir_from_cc_virtual_header.h;l=7\n\
-                           Error while generating bindings for item 'S1::f':\n\
-                           Cannot generate bindings for overloaded function";
-            quote! { __COMMENT__ #txt }
-        });
-        assert_rs_not_matches!(rs_api, quote! {pub fn f(... S1 ...)});
+        let BindingsTokens { rs_api, .. } = generate_bindings_tokens(ir)?;
 
-        // And thunks aren't generated for either.
-        assert_cc_not_matches!(rs_api_impl, quote! {f});
+        // Overloads get Rust names derived from their parameter types.
+        assert_rs_matches!(rs_api, quote! {pub fn f()});
+        assert_rs_matches!(rs_api, quote! {pub fn f_int(i: i32)});
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                impl S1 {
+                    ...
+                    pub fn f_int<'a>(&'a mut self, i: i32) { ... }
+                    ...
+                }
+            }
+        );
 
-        // But we can import member functions that have the same name as a free
-        // function.
+        // Member functions can have the same name as a free function.
         assert_rs_matches!(rs_api, quote! {pub fn f<'a>(&'a mut self)});
 
         // We can also import overloaded single-parameter constructors.
@@ -7336,6 +11421,45 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_overloaded_functions_with_rust_name_annotation() -> Result<()> {
+        let ir = ir_from_cc(
+            r#" [[clang::annotate("crubit_rust_name", "g_from_double")]] void g(double d);
+                void g(int i);
+            "#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(rs_api, quote! {pub fn g_from_double(d: f64)});
+        assert_rs_matches!(rs_api, quote! {pub fn g_int(i: i32)});
+        Ok(())
+    }
+
+    #[test]
+    fn test_overloaded_functions_with_colliding_rust_names() -> Result<()> {
+        // Overloads that only differ in the qualifiers of `this` get the same Rust
+        // name.
+        let ir = ir_from_cc(
+            r#" #pragma clang lifetime_elision
+                struct S final {
+                  void f() {}
+                  void f() const {}
+                };
+            "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_cc_matches!(rs_api, {
+            let txt = "Generated from: google3/ir_from_cc_virtual_header.h;l=3\n\
+                           Error while generating bindings for item 'S::f':\n\
+                           Cannot generate bindings for overloaded function";
+            quote! { __COMMENT__ #txt }
+        });
+        assert_rs_not_matches!(rs_api, quote! {pub fn f});
+
+        // And thunks aren't generated for either.
+        assert_cc_not_matches!(rs_api_impl, quote! {f});
+        Ok(())
+    }
+
     #[test]
     fn test_type_alias() -> Result<()> {
         let ir = ir_from_cc(
@@ -8118,6 +12242,137 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_unpin_move_only_param_and_return_value() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"#pragma clang lifetime_elision
+            // This type is `Unpin`, but it can't be copied.
+            struct [[clang::trivial_abi]] MoveOnly final {
+                MoveOnly(MoveOnly&&);
+                MoveOnly(const MoveOnly&) = delete;
+                ~MoveOnly();
+                int i;
+            };
+
+            void TakesByValue(MoveOnly x);
+            MoveOnly ReturnsByValue();
+            "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        // Move-only types can be moved in Rust, but they are not `Clone`.
+        assert_rs_not_matches!(rs_api, quote! { #[derive(Clone, Copy)] });
+        assert_rs_not_matches!(rs_api, quote! { impl Clone for MoveOnly });
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub fn TakesByValue(mut x: crate::MoveOnly) {
+                    unsafe { crate::detail::__rust_thunk___Z12TakesByValue8MoveOnly(&mut x) }
+                }
+            }
+        );
+        assert_rs_matches!(rs_api, quote! { pub fn ReturnsByValue() -> crate::MoveOnly { ... } });
+        // The thunks use the move constructor (and never the deleted copy
+        // constructor).
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z12TakesByValue8MoveOnly(struct MoveOnly* x) {
+                    TakesByValue(std::move(*x));
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z14ReturnsByValuev(struct MoveOnly* __return) {
+                    new (__return) auto(ReturnsByValue());
+                }
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rvalue_reference_param_without_lifetimes() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            struct Trivial final {
+              int trivial_field;
+            };
+            // This type must be `!Unpin`.
+            struct Nontrivial {
+                Nontrivial(Nontrivial&&);
+                ~Nontrivial();
+            };
+
+            void TakesByMove(Trivial&& x);
+            void TakesIntByMove(int&& x);
+            void TakesNontrivialByMove(Nontrivial&& x);
+            "#,
+        )?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub fn TakesByMove(mut x: crate::Trivial) {
+                    unsafe { crate::detail::__rust_thunk___Z11TakesByMoveO7Trivial(&mut x) }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z11TakesByMoveO7Trivial(x: &mut crate::Trivial);
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z11TakesByMoveO7Trivial(struct Trivial* x) {
+                    TakesByMove(std::move(*x));
+                }
+            }
+        );
+
+        // The thunk materializes the rvalue reference even for types that can be passed by value.
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub fn TakesIntByMove(x: i32) {
+                    unsafe { crate::detail::__rust_thunk___Z14TakesIntByMoveOi(x) }
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z14TakesIntByMoveOi(int x) {
+                    TakesIntByMove(std::move(x));
+                }
+            }
+        );
+
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub fn TakesNontrivialByMove(x: impl ::ctor::Ctor<Output=crate::Nontrivial>) {
+                    unsafe {
+                        crate::detail::__rust_thunk___Z21TakesNontrivialByMoveO10Nontrivial(::core::pin::Pin::into_inner_unchecked(::ctor::emplace!(x)))
+                    }
+                }
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" void __rust_thunk___Z21TakesNontrivialByMoveO10Nontrivial(struct Nontrivial* x) {
+                    TakesNontrivialByMove(std::move(*x));
+                }
+            }
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_nonunpin_trait_param() -> Result<()> {
         let ir = ir_from_cc(
@@ -8258,6 +12513,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_forward_declared_in_namespace() -> Result<()> {
+        let ir = ir_from_cc(
+            r#"
+            namespace ns1 {
+              struct ForwardDeclared;
+              void TakesPointer(ForwardDeclared* p);
+            }
+            namespace ns2 {
+              struct Defined final {
+                struct Nested final {};
+              };
+            }"#,
+        )?;
+        let rs_api = generate_bindings_tokens(ir)?.rs_api;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub mod ns1 {
+                    forward_declare::forward_declare!(pub ForwardDeclared = forward_declare::symbol!("ns1::ForwardDeclared"));
+                    ...
+                    pub unsafe fn TakesPointer(p: *mut crate::ns1::ForwardDeclared) { ... }
+                    ...
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                forward_declare::unsafe_define!(forward_declare::symbol!("ns2::Defined"), crate::ns2::Defined);
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                forward_declare::unsafe_define!(forward_declare::symbol!("ns2::Defined::Nested"), crate::ns2::Defined_Nested);
+            }
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_namespace_module_items() -> Result<()> {
         let rs_api = generate_bindings_tokens(ir_from_cc(
@@ -8639,7 +12935,7 @@ mod tests {
             rs_api,
             quote! {
                 ...
-                forward_declare::forward_declare!(pub __CcTemplateInstN23test_namespace_bindings10MyTemplateINS_5ParamEEE = forward_declare::symbol!("__CcTemplateInstN23test_namespace_bindings10MyTemplateINS_5ParamEEE"));
+                forward_declare::forward_declare!(pub __CcTemplateInstN23test_namespace_bindings10MyTemplateINS_5ParamEEE = forward_declare::symbol!("test_namespace_bindings::MyTemplate<test_namespace_bindings::Param>"));
                 ...
             }
         );
@@ -8793,4 +13089,94 @@ mod tests {
         }
         Ok(())
     }
+    #[test]
+    fn test_cc_exceptions_translated_to_result() -> Result<()> {
+        let mut ir = ir_from_cc(
+            "int MayThrow(int x);
+            void NeverThrows() noexcept;",
+        )?;
+        *ir.target_crubit_features_mut(&ir.current_target().clone()) |=
+            ir::CrubitFeature::Exceptions;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn MayThrow(x: i32) -> ::core::result::Result<i32, ::cc_std::CcException> {
+                    unsafe {
+                        let mut __exception = ::cc_std::CcExceptionSlot::new();
+                        let __result = crate::detail::__rust_thunk___Z8MayThrowi(x, &mut __exception);
+                        __exception.into_result(__result)
+                    }
+                }
+            }
+        );
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                pub(crate) fn __rust_thunk___Z8MayThrowi(
+                    x: i32, __exception: *mut ::cc_std::CcExceptionSlot) -> i32;
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                __HASH_TOKEN__ include "crubit/rs_bindings_support/internal/cc_exception.h"
+            }
+        );
+        assert_cc_matches!(
+            rs_api_impl,
+            quote! {
+                extern "C" int __rust_thunk___Z8MayThrowi(
+                        int x, crubit::CcExceptionSlot* __exception) {
+                    try {
+                        return MayThrow(x);
+                    } catch (...) {
+                        __exception->StoreCurrentException();
+                        return {};
+                    }
+                }
+            }
+        );
+
+        // `noexcept` functions are bound as usual.
+        assert_rs_matches!(
+            rs_api,
+            quote! {
+                #[inline(always)]
+                pub fn NeverThrows() {
+                    unsafe { crate::detail::__rust_thunk___Z11NeverThrowsv() }
+                }
+            }
+        );
+        assert_cc_not_matches!(rs_api_impl, quote! { __rust_thunk___Z11NeverThrowsv });
+        Ok(())
+    }
+
+    #[test]
+    fn test_cc_exceptions_not_translated_without_feature() -> Result<()> {
+        let ir = ir_from_cc("int MayThrow(int x);")?;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_matches!(rs_api, quote! { pub fn MayThrow(x: i32) -> i32 { ... } });
+        assert_rs_not_matches!(rs_api, quote! { CcException });
+        assert_cc_not_matches!(
+            rs_api_impl,
+            quote! { "crubit/rs_bindings_support/internal/cc_exception.h" }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cc_exceptions_unsupported_return_type() -> Result<()> {
+        let mut ir = ir_from_cc(
+            "struct SomeStruct final { int x; };
+            SomeStruct MayThrow();",
+        )?;
+        *ir.target_crubit_features_mut(&ir.current_target().clone()) |=
+            ir::CrubitFeature::Exceptions;
+        let BindingsTokens { rs_api, rs_api_impl } = generate_bindings_tokens(ir)?;
+        assert_rs_not_matches!(rs_api, quote! { fn MayThrow });
+        assert_cc_not_matches!(rs_api_impl, quote! { MayThrow });
+        Ok(())
+    }
 }
@@ -0,0 +1,33 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_FUNCTION_FUNCTION_APIS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_FUNCTION_FUNCTION_APIS_H_
+
+#include <functional>
+#include <utility>
+
+namespace crubit_function {
+
+inline int ApplyTwice(std::function<int(int)> f, int value) {
+  return f(f(value));
+}
+
+// Stores the callback, so that it outlives the call to `SetCallback`.
+inline std::function<void(int)>& StoredCallback() {
+  static auto* callback = new std::function<void(int)>();
+  return *callback;
+}
+
+inline void SetCallback(std::function<void(int)> callback) {
+  StoredCallback() = std::move(callback);
+}
+
+inline void RunCallback(int value) { StoredCallback()(value); }
+
+inline void ClearCallback() { StoredCallback() = nullptr; }
+
+}  // namespace crubit_function
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_FUNCTION_FUNCTION_APIS_H_
@@ -0,0 +1,30 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use function_apis::crubit_function::*;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn test_closure() {
+    let offset = 10;
+    assert_eq!(ApplyTwice(move |x| x + offset, 1), 21);
+}
+
+#[test]
+fn test_stored_closure() {
+    let sum = Arc::new(AtomicI32::new(0));
+    let sum_clone = sum.clone();
+    SetCallback(move |x| {
+        sum_clone.fetch_add(x, Ordering::SeqCst);
+    });
+    RunCallback(1);
+    RunCallback(2);
+    assert_eq!(sum.load(Ordering::SeqCst), 3);
+
+    // Destroying the `std::function` drops the closure (and its clone of `sum`).
+    assert_eq!(Arc::strong_count(&sum), 2);
+    ClearCallback();
+    assert_eq!(Arc::strong_count(&sum), 1);
+}
@@ -0,0 +1,28 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_OPTIONAL_OPTIONAL_APIS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_OPTIONAL_OPTIONAL_APIS_H_
+
+#include <optional>
+
+namespace crubit_optional {
+
+inline std::optional<int> ParseDigit(char c) {
+  if (c < '0' || c > '9') return std::nullopt;
+  return c - '0';
+}
+
+inline int ValueOr(std::optional<int> value, int default_value) {
+  return value.value_or(default_value);
+}
+
+inline std::optional<double> Halve(std::optional<double> value) {
+  if (!value.has_value()) return std::nullopt;
+  return *value / 2;
+}
+
+}  // namespace crubit_optional
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_OPTIONAL_OPTIONAL_APIS_H_
@@ -0,0 +1,23 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use optional_apis::crubit_optional::{Halve, ParseDigit, ValueOr};
+
+#[test]
+fn test_return_optional() {
+    assert_eq!(ParseDigit(b'7' as i8), Some(7));
+    assert_eq!(ParseDigit(b'x' as i8), None);
+}
+
+#[test]
+fn test_pass_optional() {
+    assert_eq!(ValueOr(Some(42), 0), 42);
+    assert_eq!(ValueOr(None, 123), 123);
+}
+
+#[test]
+fn test_pass_and_return_optional() {
+    assert_eq!(Halve(Some(3.0)), Some(1.5));
+    assert_eq!(Halve(None), None);
+}
@@ -0,0 +1,29 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_SHARED_PTR_SHARED_PTR_APIS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_SHARED_PTR_SHARED_PTR_APIS_H_
+
+#include <memory>
+
+namespace crubit_shared_ptr {
+
+struct Widget final {
+  int id;
+};
+
+inline std::shared_ptr<Widget> MakeWidget(int id) {
+  return std::make_shared<Widget>(Widget{id});
+}
+
+inline std::shared_ptr<Widget> MakeNullWidget() { return nullptr; }
+
+inline int GetUseCount(std::shared_ptr<Widget> widget) {
+  // Not counting the `widget` parameter itself.
+  return widget.use_count() - 1;
+}
+
+}  // namespace crubit_shared_ptr
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_SHARED_PTR_SHARED_PTR_APIS_H_
@@ -0,0 +1,44 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use shared_ptr_apis::crubit_shared_ptr::*;
+
+#[test]
+fn test_deref() {
+    let widget = MakeWidget(42);
+    assert_eq!(widget.id, 42);
+    assert_eq!(widget.as_ref().unwrap().id, 42);
+}
+
+#[test]
+fn test_null() {
+    let widget = MakeNullWidget();
+    assert!(widget.is_null());
+    assert!(widget.as_ref().is_none());
+}
+
+#[test]
+fn test_clone_and_drop() {
+    let widget = MakeWidget(1);
+    // `GetUseCount` takes its argument by value, so it gets its own clone.
+    assert_eq!(GetUseCount(widget.clone()), 1);
+
+    let widget_clone = widget.clone();
+    assert!(cc_std::SharedPtr::ptr_eq(&widget, &widget_clone));
+    assert_eq!(GetUseCount(widget.clone()), 2);
+
+    drop(widget);
+    assert_eq!(widget_clone.id, 1);
+    assert_eq!(GetUseCount(widget_clone.clone()), 1);
+}
+
+#[test]
+fn test_pass_ownership_to_cc() {
+    let widget = MakeWidget(2);
+    let widget_clone = widget.clone();
+    // Passing a `SharedPtr` by value moves it into the C++ parameter (without
+    // incrementing the reference count), so only `widget_clone` is counted.
+    assert_eq!(GetUseCount(widget), 1);
+    assert_eq!(widget_clone.id, 2);
+}
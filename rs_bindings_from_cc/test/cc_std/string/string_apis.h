@@ -0,0 +1,33 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_STRING_STRING_APIS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_STRING_STRING_APIS_H_
+
+#include <string>
+
+#define $static [[clang::annotate_type("lifetime", "static")]]
+
+namespace crubit_string {
+
+inline const std::string& $static GetHelloWorld() {
+  static const std::string* const kHelloWorld =
+      new std::string("Hello, world!");
+  return *kHelloWorld;
+}
+
+inline const std::string& $static GetInvalidUtf8() {
+  static const std::string* const kInvalidUtf8 =
+      new std::string("Not a UTF-8 byte: \xff");
+  return *kInvalidUtf8;
+}
+
+inline const std::string& $static GetEmpty() {
+  static const std::string* const kEmpty = new std::string();
+  return *kEmpty;
+}
+
+}  // namespace crubit_string
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_STRING_STRING_APIS_H_
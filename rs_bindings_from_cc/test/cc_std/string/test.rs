@@ -0,0 +1,23 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use string_apis::crubit_string::{GetEmpty, GetHelloWorld, GetInvalidUtf8};
+
+#[test]
+fn test_valid_utf8_str() {
+    let hello_str: &str = GetHelloWorld().try_into().unwrap();
+    assert_eq!(hello_str, "Hello, world!");
+}
+
+#[test]
+fn test_invalid_utf8_str() {
+    let _ = GetInvalidUtf8().to_str().unwrap_err();
+    assert_eq!(GetInvalidUtf8().as_bytes(), b"Not a UTF-8 byte: \xff");
+}
+
+#[test]
+fn test_empty_str() {
+    let empty: &[u8] = GetEmpty().into();
+    assert!(empty.is_empty());
+}
@@ -0,0 +1,49 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use unique_ptr_apis::crubit_unique_ptr::*;
+
+#[test]
+fn test_deref() {
+    let mut widget = MakeWidget(42);
+    assert_eq!(widget.id, 42);
+    widget.id = 43;
+    assert_eq!(widget.as_ref().unwrap().id, 43);
+}
+
+#[test]
+fn test_null() {
+    let widget = MakeNullWidget();
+    assert!(widget.is_null());
+    assert!(widget.as_ref().is_none());
+}
+
+#[test]
+fn test_pass_ownership_to_cc() {
+    assert_eq!(ConsumeWidget(MakeWidget(123)), 123);
+    assert_eq!(ConsumeWidget(MakeNullWidget()), -1);
+}
+
+#[test]
+fn test_into_raw_and_from_raw() {
+    let widget = MakeWidget(7);
+    let ptr = widget.into_raw();
+    assert_eq!(unsafe { (*ptr).id }, 7);
+    // `ConsumeWidget` takes the ownership back (and deletes the pointee from
+    // C++), so the deleter is never called.
+    unsafe extern "C" fn unreachable_deleter(_: *mut Widget) {
+        unreachable!()
+    }
+    let widget = unsafe { cc_std::UniquePtr::from_raw(ptr, unreachable_deleter) };
+    assert_eq!(ConsumeWidget(widget), 7);
+}
+
+#[test]
+fn test_drop_deletes_pointee() {
+    let initial_count = GetLiveWidgetCount();
+    let widget = MakeCountedWidget();
+    assert_eq!(GetLiveWidgetCount(), initial_count + 1);
+    drop(widget);
+    assert_eq!(GetLiveWidgetCount(), initial_count);
+}
@@ -0,0 +1,45 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_UNIQUE_PTR_UNIQUE_PTR_APIS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_UNIQUE_PTR_UNIQUE_PTR_APIS_H_
+
+#include <memory>
+
+namespace crubit_unique_ptr {
+
+struct Widget final {
+  int id;
+};
+
+inline int& LiveWidgetCount() {
+  static int count = 0;
+  return count;
+}
+
+struct CountedWidget final {
+  CountedWidget() { ++LiveWidgetCount(); }
+  ~CountedWidget() { --LiveWidgetCount(); }
+  int id = 0;
+};
+
+inline std::unique_ptr<Widget> MakeWidget(int id) {
+  return std::make_unique<Widget>(Widget{id});
+}
+
+inline std::unique_ptr<Widget> MakeNullWidget() { return nullptr; }
+
+inline int ConsumeWidget(std::unique_ptr<Widget> widget) {
+  return widget ? widget->id : -1;
+}
+
+inline std::unique_ptr<CountedWidget> MakeCountedWidget() {
+  return std::make_unique<CountedWidget>();
+}
+
+inline int GetLiveWidgetCount() { return LiveWidgetCount(); }
+
+}  // namespace crubit_unique_ptr
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_UNIQUE_PTR_UNIQUE_PTR_APIS_H_
@@ -0,0 +1,36 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use vector_apis::crubit_vector::{GetEmpty, GetMutableValues, GetPrimes, Sum};
+
+#[test]
+fn test_indexing_and_iteration() {
+    let primes = GetPrimes();
+    assert_eq!(primes.len(), 4);
+    assert_eq!(primes[2], 5);
+    assert_eq!(primes.iter().copied().collect::<Vec<i32>>(), vec![2, 3, 5, 7]);
+    assert_eq!(primes.as_slice(), &[2, 3, 5, 7]);
+}
+
+#[test]
+fn test_empty() {
+    let empty = GetEmpty();
+    assert!(empty.is_empty());
+    assert_eq!(empty.as_slice(), &[] as &[i32]);
+}
+
+#[test]
+fn test_passing_vector_to_cc() {
+    assert_eq!(Sum(GetPrimes()), 17);
+}
+
+#[test]
+fn test_modifying_elements_in_place() {
+    let values = GetMutableValues();
+    for value in values.iter_mut() {
+        *value *= 10;
+    }
+    values[0] += 1;
+    assert_eq!(Sum(values), 61);
+}
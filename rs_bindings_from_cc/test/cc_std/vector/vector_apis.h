@@ -0,0 +1,42 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_VECTOR_VECTOR_APIS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_VECTOR_VECTOR_APIS_H_
+
+#include <vector>
+
+#pragma clang lifetime_elision
+
+#define $static [[clang::annotate_type("lifetime", "static")]]
+
+namespace crubit_vector {
+
+inline const std::vector<int>& $static GetPrimes() {
+  static const std::vector<int>* const kPrimes =
+      new std::vector<int>{2, 3, 5, 7};
+  return *kPrimes;
+}
+
+inline const std::vector<int>& $static GetEmpty() {
+  static const std::vector<int>* const kEmpty = new std::vector<int>();
+  return *kEmpty;
+}
+
+inline int Sum(const std::vector<int>& values) {
+  int sum = 0;
+  for (int value : values) {
+    sum += value;
+  }
+  return sum;
+}
+
+inline std::vector<int>& $static GetMutableValues() {
+  static std::vector<int>* const kValues = new std::vector<int>{1, 2, 3};
+  return *kValues;
+}
+
+}  // namespace crubit_vector
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CC_STD_VECTOR_VECTOR_APIS_H_
@@ -0,0 +1,30 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSTANTS_CONSTANTS_H_
+#define CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSTANTS_CONSTANTS_H_
+
+#include <cstdint>
+
+#pragma clang lifetime_elision
+
+namespace crubit_constants {
+
+constexpr int kInt = 42;
+const int64_t kInt64Min = INT64_MIN;
+constexpr uint64_t kUint64Max = UINT64_MAX;
+constexpr bool kBool = true;
+constexpr char kChar = 'a';
+constexpr double kDouble = 0.1;
+constexpr float kFloat = 0.1f;
+constexpr int kComputed = kInt * 2 + 1;
+
+struct Buffer final {
+  static constexpr int kCapacity = 16;
+  static const unsigned kAlignment = 8;
+};
+
+}  // namespace crubit_constants
+
+#endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSTANTS_CONSTANTS_H_
@@ -0,0 +1,17 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSTANTS_MACROS_H_
+#define CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSTANTS_MACROS_H_
+
+#define MAX_LEN 128
+#define MINUS_ONE (-1)
+#define BIG_UNSIGNED 4000000000u
+#define RATIO 0.5
+#define GREETING "Hello, " "world!"
+
+// Function-like macros are not imported.
+#define SQUARE(x) ((x) * (x))
+
+#endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSTANTS_MACROS_H_
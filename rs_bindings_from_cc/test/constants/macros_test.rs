@@ -0,0 +1,27 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#[cfg(test)]
+mod tests {
+    use macros::*;
+
+    #[test]
+    fn test_numeric_macros() {
+        assert_eq!(MAX_LEN, 128i32);
+        assert_eq!(MINUS_ONE, -1i32);
+        assert_eq!(BIG_UNSIGNED, 4000000000u32);
+        assert_eq!(RATIO, 0.5f64);
+    }
+
+    #[test]
+    fn test_string_macros() {
+        assert_eq!(GREETING.to_bytes(), b"Hello, world!");
+    }
+
+    #[test]
+    fn test_macros_are_usable_in_const_contexts() {
+        let buffer = [0u8; MAX_LEN as usize];
+        assert_eq!(buffer.len(), 128);
+    }
+}
@@ -0,0 +1,36 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#[cfg(test)]
+mod tests {
+    use constants::crubit_constants::*;
+
+    #[test]
+    fn test_integral_constants() {
+        assert_eq!(kInt, 42);
+        assert_eq!(kInt64Min, i64::MIN);
+        assert_eq!(kUint64Max, u64::MAX);
+        assert!(kBool);
+        assert_eq!(kChar, b'a' as core::ffi::c_char);
+        assert_eq!(kComputed, 85);
+    }
+
+    #[test]
+    fn test_floating_point_constants() {
+        assert_eq!(kDouble, 0.1f64);
+        assert_eq!(kFloat, 0.1f32);
+    }
+
+    #[test]
+    fn test_static_data_member_constants() {
+        assert_eq!(Buffer::kCapacity, 16);
+        assert_eq!(Buffer::kAlignment, 8);
+    }
+
+    #[test]
+    fn test_constants_are_usable_in_const_contexts() {
+        let buffer = [0u8; Buffer::kCapacity as usize];
+        assert_eq!(buffer.len(), 16);
+    }
+}
@@ -0,0 +1,35 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSUME_ABSL_VIEWS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSUME_ABSL_VIEWS_H_
+
+#include <cstddef>
+
+#include "absl/strings/string_view.h"
+#include "absl/types/span.h"
+
+namespace crubit_absl_views {
+
+inline size_t CountSpaces(absl::string_view text) {
+  size_t count = 0;
+  for (char c : text) {
+    if (c == ' ') ++count;
+  }
+  return count;
+}
+
+inline int Sum(absl::Span<const int> values) {
+  int sum = 0;
+  for (int value : values) sum += value;
+  return sum;
+}
+
+inline void Double(absl::Span<int> values) {
+  for (int& value : values) value *= 2;
+}
+
+}  // namespace crubit_absl_views
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_CONSUME_ABSL_VIEWS_H_
@@ -0,0 +1,24 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use views::crubit_absl_views::*;
+
+#[test]
+fn test_string_view() {
+    assert_eq!(CountSpaces(""), 0);
+    assert_eq!(CountSpaces("a b c"), 2);
+}
+
+#[test]
+fn test_const_span() {
+    assert_eq!(Sum(&[]), 0);
+    assert_eq!(Sum(&[1, 2, 3]), 6);
+}
+
+#[test]
+fn test_mutable_span() {
+    let mut values = [1, 2, 3];
+    Double(&mut values);
+    assert_eq!(values, [2, 4, 6]);
+}
@@ -0,0 +1,19 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_EXCEPTIONS_EXCEPTIONS_H_
+#define THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_EXCEPTIONS_EXCEPTIONS_H_
+
+#include <stdexcept>
+
+inline int CheckedNegate(int x) {
+  if (x < 0) throw std::invalid_argument("x is negative");
+  return -x;
+}
+
+inline void ThrowInt() { throw 42; }
+
+inline int NeverThrows() noexcept { return 123; }
+
+#endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_EXCEPTIONS_EXCEPTIONS_H_
@@ -0,0 +1,32 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#[cfg(test)]
+mod tests {
+    use exceptions::*;
+
+    #[test]
+    fn test_no_exception() {
+        assert_eq!(CheckedNegate(1).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_std_exception() {
+        let exception = CheckedNegate(-1).unwrap_err();
+        assert_eq!(exception.what(), "x is negative");
+        assert_eq!(exception.to_string(), "x is negative");
+    }
+
+    #[test]
+    fn test_non_std_exception() {
+        let exception = ThrowInt().unwrap_err();
+        assert_eq!(exception.what(), "Unknown C++ exception");
+    }
+
+    #[test]
+    fn test_noexcept() {
+        // `noexcept` functions don't return a `Result`.
+        assert_eq!(NeverThrows(), 123);
+    }
+}
@@ -31,4 +31,8 @@ namespace foo {
 inline int forward_declared_doubler(int x) { return x * 2; }
 }  // namespace foo
 
+// Functions with internal linkage are not exported by the C++ library (just
+// like inline functions), so they also need to be called through a thunk.
+static int static_tripler(int x) { return x * 3; }
+
 #endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_FUNCTION_INLINE_HELLO_WORLD_H_
@@ -26,4 +26,9 @@ mod tests {
     fn test_forward_declared_doubler() {
         assert_eq!(foo::forward_declared_doubler(124), 248);
     }
+
+    #[test]
+    fn test_static_tripler() {
+        assert_eq!(static_tripler(5), 15);
+    }
 }
@@ -4,6 +4,8 @@
 
 #include "rs_bindings_from_cc/test/function/no_elided_lifetimes/no_elided_lifetimes.h"
 
+#include <utility>
+
 namespace {
 
 const int* g_int_ptr = nullptr;
@@ -13,3 +15,10 @@ const int* g_int_ptr = nullptr;
 void StorePointer(const int& int_ref) { g_int_ptr = &int_ref; }
 
 int ReadStoredPointer() { return *g_int_ptr; }
+
+int ConsumeCounter(Counter&& counter) {
+  Counter consumed = std::move(counter);
+  return consumed.count;
+}
+
+int ConsumeInt(int&& value) { return value; }
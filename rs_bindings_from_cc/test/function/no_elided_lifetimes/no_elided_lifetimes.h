@@ -20,4 +20,12 @@
 void StorePointer(const int& int_ref);
 int ReadStoredPointer();
 
+// Rvalue references without lifetime annotations are taken by value (the
+// generated thunk moves from the value passed by the Rust caller).
+struct Counter final {
+  int count = 0;
+};
+int ConsumeCounter(Counter&& counter);
+int ConsumeInt(int&& value);
+
 #endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_FUNCTION_NO_ELIDED_LIFETIMES_NO_ELIDED_LIFETIMES_H_
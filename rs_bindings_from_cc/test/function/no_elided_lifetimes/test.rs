@@ -33,4 +33,11 @@ mod tests {
         drop(boxed_int);
         //assert_eq!(456, ReadStoredPointer());
     }
+
+    #[test]
+    fn test_rvalue_reference_taken_by_move() {
+        let counter = Counter { count: 42 };
+        assert_eq!(42, ConsumeCounter(counter));
+        assert_eq!(123, ConsumeInt(123));
+    }
 }
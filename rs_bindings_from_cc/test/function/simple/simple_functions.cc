@@ -38,3 +38,5 @@ int multiply_with_keyword_named_parameters(int self, int crate, int super) {
 
 int (*get_pointer_to_multiply_function())(int, int) { return multiply; }
 int (&get_reference_to_multiply_function())(int, int) { return multiply; }
+
+int apply_binary_op(binary_op_t op, int x, int y) { return op(x, y); }
@@ -30,6 +30,9 @@ inline int (*inline_get_pointer_to_multiply_function())(int, int) {
   return multiply;
 }
 
+typedef int (*binary_op_t)(int, int);
+int apply_binary_op(binary_op_t op, int x, int y);
+
 // TODO(b/217419782): Add testcases for pointers to functions that take or
 // return takes/returns non-trivially-movable types by value. In particular,
 // some function signatures might require going through a C++ thunk - such
@@ -96,4 +96,13 @@ mod tests {
         let mul_fn = maybe_mul_fn.expect("Expecting non-null / non-None function pointer");
         assert_eq!(mul_fn(123, 456), 123 * 456);
     }
+
+    #[test]
+    fn test_typedefd_function_pointer() {
+        extern "C" fn subtract(x: i32, y: i32) -> i32 {
+            x - y
+        }
+        let op: simple_functions::binary_op_t = Some(subtract);
+        assert_eq!(simple_functions::apply_binary_op(op, 456, 123), 456 - 123);
+    }
 }
@@ -0,0 +1,31 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{c_char, c_int, CStr};
+
+    #[test]
+    fn test_variadic_function() {
+        let sum = unsafe { variadic::SumInts(3, 1 as c_int, 20 as c_int, 300 as c_int) };
+        assert_eq!(sum, 321);
+    }
+
+    #[test]
+    fn test_variadic_extern_c_function() {
+        let mut buffer = [0 as c_char; 32];
+        let len = unsafe {
+            variadic::FormatToBuffer(
+                buffer.as_mut_ptr(),
+                buffer.len() as c_int,
+                b"%d-%s\0".as_ptr() as *const c_char,
+                42 as c_int,
+                b"foo\0".as_ptr() as *const c_char,
+            )
+        };
+        assert_eq!(len, 6);
+        let formatted = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+        assert_eq!(formatted.to_str().unwrap(), "42-foo");
+    }
+}
@@ -0,0 +1,27 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "rs_bindings_from_cc/test/function/variadic/variadic.h"
+
+#include <cstdarg>
+#include <cstdio>
+
+int SumInts(int count, ...) {
+  va_list args;
+  va_start(args, count);
+  int sum = 0;
+  for (int i = 0; i < count; ++i) {
+    sum += va_arg(args, int);
+  }
+  va_end(args);
+  return sum;
+}
+
+int FormatToBuffer(char* buffer, int size, const char* format, ...) {
+  va_list args;
+  va_start(args, format);
+  int result = std::vsnprintf(buffer, size, format, args);
+  va_end(args);
+  return result;
+}
@@ -0,0 +1,20 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_RS_BINDINGS_FROM_CC_TEST_FUNCTION_VARIADIC_VARIADIC_H_
+#define CRUBIT_RS_BINDINGS_FROM_CC_TEST_FUNCTION_VARIADIC_VARIADIC_H_
+
+// Returns the sum of the `count` `int` arguments that follow `count`.
+int SumInts(int count, ...);
+
+extern "C" {
+
+// Formats `format` and the arguments that follow it (using `vsnprintf`) into
+// the `size`-byte buffer `buffer`, returning the length of the formatted
+// string.
+int FormatToBuffer(char* buffer, int size, const char* format, ...);
+
+}  // extern "C"
+
+#endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_FUNCTION_VARIADIC_VARIADIC_H_
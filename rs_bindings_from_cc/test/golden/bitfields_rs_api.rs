@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/bitfields.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::WithBitfields"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(4))]
 pub struct WithBitfields {
@@ -134,6 +138,64 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for WithBitfields {
     }
 }
 
+impl WithBitfields {
+    pub fn f1(&self) -> i32 {
+        unsafe { crate::detail::__crubit_bitfield_get__13WithBitfields__f1(self) }
+    }
+    pub fn set_f1(self: ::core::pin::Pin<&mut Self>, value: i32) {
+        unsafe {
+            crate::detail::__crubit_bitfield_set__13WithBitfields__f1(
+                ::core::pin::Pin::into_inner_unchecked(self),
+                value,
+            )
+        }
+    }
+    pub fn f3(&self) -> i32 {
+        unsafe { crate::detail::__crubit_bitfield_get__13WithBitfields__f3(self) }
+    }
+    pub fn set_f3(self: ::core::pin::Pin<&mut Self>, value: i32) {
+        unsafe {
+            crate::detail::__crubit_bitfield_set__13WithBitfields__f3(
+                ::core::pin::Pin::into_inner_unchecked(self),
+                value,
+            )
+        }
+    }
+    pub fn f4(&self) -> i32 {
+        unsafe { crate::detail::__crubit_bitfield_get__13WithBitfields__f4(self) }
+    }
+    pub fn set_f4(self: ::core::pin::Pin<&mut Self>, value: i32) {
+        unsafe {
+            crate::detail::__crubit_bitfield_set__13WithBitfields__f4(
+                ::core::pin::Pin::into_inner_unchecked(self),
+                value,
+            )
+        }
+    }
+    pub fn f6(&self) -> i32 {
+        unsafe { crate::detail::__crubit_bitfield_get__13WithBitfields__f6(self) }
+    }
+    pub fn set_f6(self: ::core::pin::Pin<&mut Self>, value: i32) {
+        unsafe {
+            crate::detail::__crubit_bitfield_set__13WithBitfields__f6(
+                ::core::pin::Pin::into_inner_unchecked(self),
+                value,
+            )
+        }
+    }
+    pub fn f8(&self) -> i32 {
+        unsafe { crate::detail::__crubit_bitfield_get__13WithBitfields__f8(self) }
+    }
+    pub fn set_f8(self: ::core::pin::Pin<&mut Self>, value: i32) {
+        unsafe {
+            crate::detail::__crubit_bitfield_set__13WithBitfields__f8(
+                ::core::pin::Pin::into_inner_unchecked(self),
+                value,
+            )
+        }
+    }
+}
+
 // CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_BITFIELDS_H_
 
 mod detail {
@@ -159,6 +221,41 @@ mod detail {
             __this: ::core::pin::Pin<&'a mut crate::WithBitfields>,
             __param_0: ::ctor::RvalueReference<'b, crate::WithBitfields>,
         ) -> ::core::pin::Pin<&'a mut crate::WithBitfields>;
+        pub(crate) fn __crubit_bitfield_get__13WithBitfields__f1(
+            __this: *const crate::WithBitfields,
+        ) -> i32;
+        pub(crate) fn __crubit_bitfield_set__13WithBitfields__f1(
+            __this: *mut crate::WithBitfields,
+            value: i32,
+        );
+        pub(crate) fn __crubit_bitfield_get__13WithBitfields__f3(
+            __this: *const crate::WithBitfields,
+        ) -> i32;
+        pub(crate) fn __crubit_bitfield_set__13WithBitfields__f3(
+            __this: *mut crate::WithBitfields,
+            value: i32,
+        );
+        pub(crate) fn __crubit_bitfield_get__13WithBitfields__f4(
+            __this: *const crate::WithBitfields,
+        ) -> i32;
+        pub(crate) fn __crubit_bitfield_set__13WithBitfields__f4(
+            __this: *mut crate::WithBitfields,
+            value: i32,
+        );
+        pub(crate) fn __crubit_bitfield_get__13WithBitfields__f6(
+            __this: *const crate::WithBitfields,
+        ) -> i32;
+        pub(crate) fn __crubit_bitfield_set__13WithBitfields__f6(
+            __this: *mut crate::WithBitfields,
+            value: i32,
+        );
+        pub(crate) fn __crubit_bitfield_get__13WithBitfields__f8(
+            __this: *const crate::WithBitfields,
+        ) -> i32;
+        pub(crate) fn __crubit_bitfield_set__13WithBitfields__f8(
+            __this: *mut crate::WithBitfields,
+            value: i32,
+        );
     }
 }
 
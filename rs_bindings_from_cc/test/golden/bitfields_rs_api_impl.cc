@@ -49,4 +49,45 @@ extern "C" struct WithBitfields* __rust_thunk___ZN13WithBitfieldsaSEOS_(
   return &__this->operator=(std::move(*__param_0));
 }
 
+extern "C" int __crubit_bitfield_get__13WithBitfields__f1(
+    const struct WithBitfields* __this) {
+  return __this->f1;
+}
+extern "C" void __crubit_bitfield_set__13WithBitfields__f1(
+    struct WithBitfields* __this, int value) {
+  __this->f1 = value;
+}
+extern "C" int __crubit_bitfield_get__13WithBitfields__f3(
+    const struct WithBitfields* __this) {
+  return __this->f3;
+}
+extern "C" void __crubit_bitfield_set__13WithBitfields__f3(
+    struct WithBitfields* __this, int value) {
+  __this->f3 = value;
+}
+extern "C" int __crubit_bitfield_get__13WithBitfields__f4(
+    const struct WithBitfields* __this) {
+  return __this->f4;
+}
+extern "C" void __crubit_bitfield_set__13WithBitfields__f4(
+    struct WithBitfields* __this, int value) {
+  __this->f4 = value;
+}
+extern "C" int __crubit_bitfield_get__13WithBitfields__f6(
+    const struct WithBitfields* __this) {
+  return __this->f6;
+}
+extern "C" void __crubit_bitfield_set__13WithBitfields__f6(
+    struct WithBitfields* __this, int value) {
+  __this->f6 = value;
+}
+extern "C" int __crubit_bitfield_get__13WithBitfields__f8(
+    const struct WithBitfields* __this) {
+  return __this->f8;
+}
+extern "C" void __crubit_bitfield_set__13WithBitfields__f8(
+    struct WithBitfields* __this, int value) {
+  __this->f8 = value;
+}
+
 #pragma clang diagnostic pop
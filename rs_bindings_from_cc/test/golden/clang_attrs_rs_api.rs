@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/clang_attrs.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::HasCustomAlignment"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(64))]
 pub struct HasCustomAlignment {
@@ -120,6 +124,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for HasCustomAlignmen
     }
 }
 
+#[crubit_annotate::cpp_type = "::HasFieldWithCustomAlignment"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct HasFieldWithCustomAlignment {
@@ -220,6 +225,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for HasFieldWithCusto
     }
 }
 
+#[crubit_annotate::cpp_type = "::InheritsFromBaseWithCustomAlignment"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(64))]
 pub struct InheritsFromBaseWithCustomAlignment {
@@ -335,7 +341,15 @@ unsafe impl oops::Inherits<crate::HasCustomAlignment>
         (derived as *const _ as *const u8).offset(0) as *const crate::HasCustomAlignment
     }
 }
+impl ::core::ops::Deref for crate::InheritsFromBaseWithCustomAlignment {
+    type Target = crate::HasCustomAlignment;
+    #[inline(always)]
+    fn deref(&self) -> &crate::HasCustomAlignment {
+        unsafe { &*<Self as oops::Inherits<crate::HasCustomAlignment>>::upcast_ptr(self) }
+    }
+}
 
+#[crubit_annotate::cpp_type = "::HasCustomAlignmentWithGnuAttr"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(64))]
 pub struct HasCustomAlignmentWithGnuAttr {
@@ -456,6 +470,7 @@ pub mod template_with_preferred_name {
 
 /// Based on `llvm/include/c++/v1/__fwd/string_view.h` - mimics
 /// forward declaration of `basic_string_view` class template.
+#[crubit_annotate::cpp_type = "::template_with_preferred_name::SomeTemplate<int>"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct __CcTemplateInstN28template_with_preferred_name12SomeTemplateIiEE {
@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/comment.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -27,6 +30,7 @@
 // a
 
 /// Foo
+#[crubit_annotate::cpp_type = "::Foo"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct Foo {
@@ -99,6 +103,7 @@ pub fn foo() {
 }
 
 /// Bar
+#[crubit_annotate::cpp_type = "::Bar"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct Bar {
@@ -147,6 +152,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for Bar {
 }
 
 /// d
+#[crubit_annotate::cpp_type = "::HasNoComments"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct HasNoComments {
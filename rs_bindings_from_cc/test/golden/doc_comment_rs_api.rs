@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/doc_comment.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -23,6 +26,7 @@
 /// Doc comment
 ///
 ///  * with three slashes
+#[crubit_annotate::cpp_type = "::DocCommentSlashes"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct DocCommentSlashes {
@@ -119,6 +123,7 @@ impl DocCommentSlashes {
 /// Doc comment
 ///
 ///  * with slashes and bang
+#[crubit_annotate::cpp_type = "::DocCommentBang"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct DocCommentBang {
@@ -170,6 +175,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for DocCommentBa
 /// Multiline comment
 ///
 ///  with two stars
+#[crubit_annotate::cpp_type = "::MultilineCommentTwoStars"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct MultilineCommentTwoStars {
@@ -224,6 +230,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for MultilineCom
 /// Line comment
 ///
 ///  * with two slashes
+#[crubit_annotate::cpp_type = "::LineComment"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct LineComment {
@@ -275,6 +282,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for LineComment
 /// Multiline comment
 ///
 ///  with one star
+#[crubit_annotate::cpp_type = "::MultilineOneStar"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct MultilineOneStar {
@@ -359,6 +367,7 @@ pub type MySpecializedInstantiation = crate::__CcTemplateInst10MyTemplateIfE;
 // CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_DOC_COMMENT_H_
 
 /// Class template.
+#[crubit_annotate::cpp_type = "::MyTemplate<int>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIiE {
@@ -425,6 +434,7 @@ impl __CcTemplateInst10MyTemplateIiE {
 }
 
 /// Class template specialization.
+#[crubit_annotate::cpp_type = "::MyTemplate<float>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIfE {
@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/enums.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -34,6 +37,45 @@ impl From<Empty> for u32 {
         value.0
     }
 }
+impl ::core::ops::BitAnd for Empty {
+    type Output = Empty;
+    fn bitand(self, other: Empty) -> Empty {
+        Empty(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for Empty {
+    fn bitand_assign(&mut self, other: Empty) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for Empty {
+    type Output = Empty;
+    fn bitor(self, other: Empty) -> Empty {
+        Empty(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for Empty {
+    fn bitor_assign(&mut self, other: Empty) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for Empty {
+    type Output = Empty;
+    fn bitxor(self, other: Empty) -> Empty {
+        Empty(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for Empty {
+    fn bitxor_assign(&mut self, other: Empty) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for Empty {
+    type Output = Empty;
+    fn not(self) -> Empty {
+        Empty(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -49,6 +91,45 @@ impl From<EmptyBool> for bool {
         value.0
     }
 }
+impl ::core::ops::BitAnd for EmptyBool {
+    type Output = EmptyBool;
+    fn bitand(self, other: EmptyBool) -> EmptyBool {
+        EmptyBool(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for EmptyBool {
+    fn bitand_assign(&mut self, other: EmptyBool) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for EmptyBool {
+    type Output = EmptyBool;
+    fn bitor(self, other: EmptyBool) -> EmptyBool {
+        EmptyBool(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for EmptyBool {
+    fn bitor_assign(&mut self, other: EmptyBool) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for EmptyBool {
+    type Output = EmptyBool;
+    fn bitxor(self, other: EmptyBool) -> EmptyBool {
+        EmptyBool(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for EmptyBool {
+    fn bitxor_assign(&mut self, other: EmptyBool) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for EmptyBool {
+    type Output = EmptyBool;
+    fn not(self) -> EmptyBool {
+        EmptyBool(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -64,6 +145,45 @@ impl From<EmptyInt> for u32 {
         value.0
     }
 }
+impl ::core::ops::BitAnd for EmptyInt {
+    type Output = EmptyInt;
+    fn bitand(self, other: EmptyInt) -> EmptyInt {
+        EmptyInt(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for EmptyInt {
+    fn bitand_assign(&mut self, other: EmptyInt) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for EmptyInt {
+    type Output = EmptyInt;
+    fn bitor(self, other: EmptyInt) -> EmptyInt {
+        EmptyInt(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for EmptyInt {
+    fn bitor_assign(&mut self, other: EmptyInt) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for EmptyInt {
+    type Output = EmptyInt;
+    fn bitxor(self, other: EmptyInt) -> EmptyInt {
+        EmptyInt(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for EmptyInt {
+    fn bitxor_assign(&mut self, other: EmptyInt) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for EmptyInt {
+    type Output = EmptyInt;
+    fn not(self) -> EmptyInt {
+        EmptyInt(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -79,6 +199,45 @@ impl From<EmptyChar> for u8 {
         value.0
     }
 }
+impl ::core::ops::BitAnd for EmptyChar {
+    type Output = EmptyChar;
+    fn bitand(self, other: EmptyChar) -> EmptyChar {
+        EmptyChar(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for EmptyChar {
+    fn bitand_assign(&mut self, other: EmptyChar) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for EmptyChar {
+    type Output = EmptyChar;
+    fn bitor(self, other: EmptyChar) -> EmptyChar {
+        EmptyChar(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for EmptyChar {
+    fn bitor_assign(&mut self, other: EmptyChar) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for EmptyChar {
+    type Output = EmptyChar;
+    fn bitxor(self, other: EmptyChar) -> EmptyChar {
+        EmptyChar(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for EmptyChar {
+    fn bitxor_assign(&mut self, other: EmptyChar) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for EmptyChar {
+    type Output = EmptyChar;
+    fn not(self) -> EmptyChar {
+        EmptyChar(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -97,6 +256,45 @@ impl From<NonEmptyBool> for bool {
         value.0
     }
 }
+impl ::core::ops::BitAnd for NonEmptyBool {
+    type Output = NonEmptyBool;
+    fn bitand(self, other: NonEmptyBool) -> NonEmptyBool {
+        NonEmptyBool(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for NonEmptyBool {
+    fn bitand_assign(&mut self, other: NonEmptyBool) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for NonEmptyBool {
+    type Output = NonEmptyBool;
+    fn bitor(self, other: NonEmptyBool) -> NonEmptyBool {
+        NonEmptyBool(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for NonEmptyBool {
+    fn bitor_assign(&mut self, other: NonEmptyBool) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for NonEmptyBool {
+    type Output = NonEmptyBool;
+    fn bitxor(self, other: NonEmptyBool) -> NonEmptyBool {
+        NonEmptyBool(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for NonEmptyBool {
+    fn bitxor_assign(&mut self, other: NonEmptyBool) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for NonEmptyBool {
+    type Output = NonEmptyBool;
+    fn not(self) -> NonEmptyBool {
+        NonEmptyBool(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -115,6 +313,45 @@ impl From<NonEmptyInt> for u32 {
         value.0
     }
 }
+impl ::core::ops::BitAnd for NonEmptyInt {
+    type Output = NonEmptyInt;
+    fn bitand(self, other: NonEmptyInt) -> NonEmptyInt {
+        NonEmptyInt(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for NonEmptyInt {
+    fn bitand_assign(&mut self, other: NonEmptyInt) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for NonEmptyInt {
+    type Output = NonEmptyInt;
+    fn bitor(self, other: NonEmptyInt) -> NonEmptyInt {
+        NonEmptyInt(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for NonEmptyInt {
+    fn bitor_assign(&mut self, other: NonEmptyInt) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for NonEmptyInt {
+    type Output = NonEmptyInt;
+    fn bitxor(self, other: NonEmptyInt) -> NonEmptyInt {
+        NonEmptyInt(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for NonEmptyInt {
+    fn bitxor_assign(&mut self, other: NonEmptyInt) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for NonEmptyInt {
+    type Output = NonEmptyInt;
+    fn not(self) -> NonEmptyInt {
+        NonEmptyInt(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -133,6 +370,45 @@ impl From<NonEmptyChar> for u8 {
         value.0
     }
 }
+impl ::core::ops::BitAnd for NonEmptyChar {
+    type Output = NonEmptyChar;
+    fn bitand(self, other: NonEmptyChar) -> NonEmptyChar {
+        NonEmptyChar(self.0 & other.0)
+    }
+}
+impl ::core::ops::BitAndAssign for NonEmptyChar {
+    fn bitand_assign(&mut self, other: NonEmptyChar) {
+        self.0 = self.0 & other.0;
+    }
+}
+impl ::core::ops::BitOr for NonEmptyChar {
+    type Output = NonEmptyChar;
+    fn bitor(self, other: NonEmptyChar) -> NonEmptyChar {
+        NonEmptyChar(self.0 | other.0)
+    }
+}
+impl ::core::ops::BitOrAssign for NonEmptyChar {
+    fn bitor_assign(&mut self, other: NonEmptyChar) {
+        self.0 = self.0 | other.0;
+    }
+}
+impl ::core::ops::BitXor for NonEmptyChar {
+    type Output = NonEmptyChar;
+    fn bitxor(self, other: NonEmptyChar) -> NonEmptyChar {
+        NonEmptyChar(self.0 ^ other.0)
+    }
+}
+impl ::core::ops::BitXorAssign for NonEmptyChar {
+    fn bitxor_assign(&mut self, other: NonEmptyChar) {
+        self.0 = self.0 ^ other.0;
+    }
+}
+impl ::core::ops::Not for NonEmptyChar {
+    type Output = NonEmptyChar;
+    fn not(self) -> NonEmptyChar {
+        NonEmptyChar(!self.0)
+    }
+}
 
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
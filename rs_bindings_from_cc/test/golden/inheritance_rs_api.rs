@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/inheritance.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -23,6 +26,7 @@
 /// Using classes to force these to be non-POD.
 /// In the Itanium ABI, the tail padding of POD types cannot be reused by other
 /// objects, even if the POD type is potentially-overlapping.
+#[crubit_annotate::cpp_type = "::Base0"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct Base0 {
@@ -120,6 +124,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for Base0 {
     }
 }
 
+#[crubit_annotate::cpp_type = "::Base1"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct Base1 {
@@ -223,6 +228,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for Base1 {
     }
 }
 
+#[crubit_annotate::cpp_type = "::Base2"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(2))]
 pub struct Base2 {
@@ -323,6 +329,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for Base2 {
     }
 }
 
+#[crubit_annotate::cpp_type = "::Derived"]
 #[derive(Clone, Copy)]
 #[repr(C, align(8))]
 pub struct Derived {
@@ -386,7 +393,26 @@ unsafe impl oops::Inherits<crate::Base2> for crate::Derived {
         (derived as *const _ as *const u8).offset(10) as *const crate::Base2
     }
 }
+impl ::core::convert::AsRef<crate::Base0> for crate::Derived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::Base0 {
+        unsafe { &*<Self as oops::Inherits<crate::Base0>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<crate::Base1> for crate::Derived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::Base1 {
+        unsafe { &*<Self as oops::Inherits<crate::Base1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<crate::Base2> for crate::Derived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::Base2 {
+        unsafe { &*<Self as oops::Inherits<crate::Base2>>::upcast_ptr(self) }
+    }
+}
 
+#[crubit_annotate::cpp_type = "::VirtualBase1"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct VirtualBase1 {
@@ -489,7 +515,15 @@ unsafe impl oops::Inherits<crate::Base1> for crate::VirtualBase1 {
         crate::detail::__crubit_dynamic_upcast__12VirtualBase1__to__5Base1(derived)
     }
 }
+impl ::core::ops::Deref for crate::VirtualBase1 {
+    type Target = crate::Base1;
+    #[inline(always)]
+    fn deref(&self) -> &crate::Base1 {
+        unsafe { &*<Self as oops::Inherits<crate::Base1>>::upcast_ptr(self) }
+    }
+}
 
+#[crubit_annotate::cpp_type = "::VirtualBase2"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct VirtualBase2 {
@@ -592,7 +626,15 @@ unsafe impl oops::Inherits<crate::Base1> for crate::VirtualBase2 {
         crate::detail::__crubit_dynamic_upcast__12VirtualBase2__to__5Base1(derived)
     }
 }
+impl ::core::ops::Deref for crate::VirtualBase2 {
+    type Target = crate::Base1;
+    #[inline(always)]
+    fn deref(&self) -> &crate::Base1 {
+        unsafe { &*<Self as oops::Inherits<crate::Base1>>::upcast_ptr(self) }
+    }
+}
 
+#[crubit_annotate::cpp_type = "::VirtualDerived"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct VirtualDerived {
@@ -705,7 +747,26 @@ unsafe impl oops::Inherits<crate::VirtualBase2> for crate::VirtualDerived {
         crate::detail::__crubit_dynamic_upcast__14VirtualDerived__to__12VirtualBase2(derived)
     }
 }
+impl ::core::convert::AsRef<crate::VirtualBase1> for crate::VirtualDerived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::VirtualBase1 {
+        unsafe { &*<Self as oops::Inherits<crate::VirtualBase1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<crate::Base1> for crate::VirtualDerived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::Base1 {
+        unsafe { &*<Self as oops::Inherits<crate::Base1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<crate::VirtualBase2> for crate::VirtualDerived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::VirtualBase2 {
+        unsafe { &*<Self as oops::Inherits<crate::VirtualBase2>>::upcast_ptr(self) }
+    }
+}
 
+#[crubit_annotate::cpp_type = "::MyAbstractClass"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct MyAbstractClass {
@@ -732,6 +793,7 @@ impl<'b> ::ctor::Assign<&'b Self> for MyAbstractClass {
 }
 
 /// Method inheritance
+#[crubit_annotate::cpp_type = "::MethodBase1"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct MethodBase1 {
@@ -850,6 +912,7 @@ impl MethodBase1 {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MethodBase2"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct MethodBase2 {
@@ -961,6 +1024,7 @@ impl MethodBase2 {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MethodDerived"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct MethodDerived {
@@ -1018,6 +1082,18 @@ unsafe impl oops::Inherits<crate::MethodBase2> for crate::MethodDerived {
         (derived as *const _ as *const u8).offset(0) as *const crate::MethodBase2
     }
 }
+impl ::core::convert::AsRef<crate::MethodBase1> for crate::MethodDerived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::MethodBase1 {
+        unsafe { &*<Self as oops::Inherits<crate::MethodBase1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<crate::MethodBase2> for crate::MethodDerived {
+    #[inline(always)]
+    fn as_ref(&self) -> &crate::MethodBase2 {
+        unsafe { &*<Self as oops::Inherits<crate::MethodBase2>>::upcast_ptr(self) }
+    }
+}
 
 // CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_INHERITANCE_H_
 
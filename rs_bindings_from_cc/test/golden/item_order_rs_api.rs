@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/item_order.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::FirstStruct"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct FirstStruct {
@@ -72,6 +76,7 @@ pub fn first_func() -> i32 {
     unsafe { crate::detail::__rust_thunk___Z10first_funcv() }
 }
 
+#[crubit_annotate::cpp_type = "::SecondStruct"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct SecondStruct {
@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/lifetimes.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -28,7 +31,7 @@ pub fn AddHook(__param_0: Option<extern "C" fn()>) {
 pub type FunctionPointer = Option<extern "C" fn()>;
 
 #[inline(always)]
-pub fn AddHookWithTypedef(hook: Option<extern "C" fn()>) {
+pub fn AddHookWithTypedef(hook: crate::FunctionPointer) {
     unsafe { crate::detail::__rust_thunk___Z18AddHookWithTypedefPFvvE(hook) }
 }
 
@@ -40,7 +43,7 @@ pub fn AddAnotherHook(__param_0: extern "C" fn()) {
 pub type FunctionReference = extern "C" fn();
 
 #[inline(always)]
-pub fn AddAnotherHookWithTypedef(hook: extern "C" fn()) {
+pub fn AddAnotherHookWithTypedef(hook: crate::FunctionReference) {
     unsafe { crate::detail::__rust_thunk___Z25AddAnotherHookWithTypedefRFvvE(hook) }
 }
 
@@ -66,11 +69,11 @@ mod detail {
         #[link_name = "_Z7AddHookPFvvE"]
         pub(crate) fn __rust_thunk___Z7AddHookPFvvE(__param_0: Option<extern "C" fn()>);
         #[link_name = "_Z18AddHookWithTypedefPFvvE"]
-        pub(crate) fn __rust_thunk___Z18AddHookWithTypedefPFvvE(hook: Option<extern "C" fn()>);
+        pub(crate) fn __rust_thunk___Z18AddHookWithTypedefPFvvE(hook: crate::FunctionPointer);
         #[link_name = "_Z14AddAnotherHookRFvvE"]
         pub(crate) fn __rust_thunk___Z14AddAnotherHookRFvvE(__param_0: extern "C" fn());
         #[link_name = "_Z25AddAnotherHookWithTypedefRFvvE"]
-        pub(crate) fn __rust_thunk___Z25AddAnotherHookWithTypedefRFvvE(hook: extern "C" fn());
+        pub(crate) fn __rust_thunk___Z25AddAnotherHookWithTypedefRFvvE(hook: crate::FunctionReference);
         #[link_name = "_Z12ConsumeArrayPi"]
         pub(crate) fn __rust_thunk___Z12ConsumeArrayPi(pair: *mut i32);
         #[link_name = "_Z23ConsumeArrayWithTypedefPi"]
@@ -7,27 +7,31 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/namespace.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
 pub mod test_namespace_bindings {
+    #[crubit_annotate::cpp_type = "::test_namespace_bindings::S"]
     #[derive(Clone, Copy)]
     #[repr(C)]
     pub struct S {
         pub i: i32,
     }
     forward_declare::unsafe_define!(
-        forward_declare::symbol!("S"),
+        forward_declare::symbol!("test_namespace_bindings::S"),
         crate::test_namespace_bindings::S
     );
 
@@ -117,13 +121,14 @@ pub mod test_namespace_bindings_reopened_0 {
     }
 
     pub mod inner_0 {
+        #[crubit_annotate::cpp_type = "::test_namespace_bindings_reopened::inner::S"]
         #[derive(Clone, Copy)]
         #[repr(C)]
         pub struct S {
             __non_field_data: [::core::mem::MaybeUninit<u8>; 1],
         }
         forward_declare::unsafe_define!(
-            forward_declare::symbol!("S"),
+            forward_declare::symbol!("test_namespace_bindings_reopened::inner::S"),
             crate::test_namespace_bindings_reopened::inner::S
         );
 
@@ -203,13 +208,14 @@ pub mod test_namespace_bindings_reopened {
 
 pub mod test_namespace_bindings_inline {
     pub mod inner {
+        #[crubit_annotate::cpp_type = "::test_namespace_bindings_inline::inner::StructInInlineNamespace"]
         #[derive(Clone, Copy)]
         #[repr(C)]
         pub struct StructInInlineNamespace {
             __non_field_data: [::core::mem::MaybeUninit<u8>; 1],
         }
         forward_declare::unsafe_define!(
-            forward_declare::symbol!("StructInInlineNamespace"),
+            forward_declare::symbol!("test_namespace_bindings_inline::inner::StructInInlineNamespace"),
             crate::test_namespace_bindings_inline::inner::StructInInlineNamespace
         );
 
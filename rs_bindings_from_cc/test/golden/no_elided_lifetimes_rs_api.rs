@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls)]
+#![feature(custom_inner_attributes, negative_impls, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/no_elided_lifetimes.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -25,6 +28,7 @@ pub unsafe fn free_function(p1: *mut i32) -> *mut i32 {
     crate::detail::__rust_thunk___Z13free_functionRi(p1)
 }
 
+#[crubit_annotate::cpp_type = "::S"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct S {
@@ -61,6 +65,7 @@ impl S {
     }
 }
 
+#[crubit_annotate::cpp_type = "::TriviallyCopyableButNontriviallyDestructible"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct TriviallyCopyableButNontriviallyDestructible {
@@ -89,6 +94,7 @@ pub unsafe fn take_pointer(p: *mut i32) {
     crate::detail::__rust_thunk___Z12take_pointerPi(p)
 }
 
+#[crubit_annotate::cpp_type = "::WrappedValue"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(4))]
 pub struct WrappedValue {
@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/no_unique_address.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -25,6 +28,7 @@
 /// rs_bindings_from_cc/test/golden/ because the format provides end-to-end
 /// coverage for working accessor functions, while the latter helps manually
 /// inspect and verify the expected layout of the generated Rust struct.
+#[crubit_annotate::cpp_type = "::Struct"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct Struct {
@@ -99,6 +103,7 @@ impl Struct {
 /// between `field1` and `field2`).  The verification is mostly done through
 /// compile-time assertions of field offsets in the generated Rust code.  Before
 /// cl/448287893 `field2` would be incorrectly placed at offset 1.
+#[crubit_annotate::cpp_type = "::PaddingBetweenFields"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct PaddingBetweenFields {
@@ -174,6 +179,7 @@ impl PaddingBetweenFields {
 /// - dsize (size without padding): 5
 ///   (4 bytes for `inner_int_field`, 1 byte for `inner_char_field`)
 /// - size: 8 (dsize adjusted up to account for alignment)
+#[crubit_annotate::cpp_type = "::FieldInTailPadding_InnerStruct"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct FieldInTailPadding_InnerStruct {
@@ -258,6 +264,7 @@ impl ::ctor::PinnedDrop for FieldInTailPadding_InnerStruct {
 /// done through compile-time assertions of field offsets in the generated Rust
 /// code.  The initial alignment-based fix idea for b/232418721 would incorrectly
 /// put `char_in_tail_padding_of_prev_field` at offset 8.
+#[crubit_annotate::cpp_type = "::FieldInTailPadding"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C, align(4))]
 pub struct FieldInTailPadding {
@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/nontrivial_type.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -25,6 +28,7 @@
 ///
 /// This makes it nontrivial for calls (so not trivially relocatable), as well
 /// as specifically giving it a nontrivial move constructor and destructor.
+#[crubit_annotate::cpp_type = "::Nontrivial"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct Nontrivial {
@@ -247,6 +251,7 @@ impl Nontrivial {
 ///
 /// This makes it nontrivial for calls (so not trivially relocatable), as well
 /// as specifically giving it a nontrivial move constructor and destructor.
+#[crubit_annotate::cpp_type = "::NontrivialInline"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct NontrivialInline {
@@ -421,6 +426,7 @@ impl NontrivialInline {
 /// This changes how the destructor / drop impl work -- instead of calling
 /// the destructor for NontrivialMembers, it just calls the destructors for
 /// each field.
+#[crubit_annotate::cpp_type = "::NontrivialMembers"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct NontrivialMembers {
@@ -529,6 +535,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for NontrivialMembers
 }
 
 /// Nontrivial, but trivially relocatable and final (and therefore Unpin).
+#[crubit_annotate::cpp_type = "::NontrivialUnpin"]
 #[repr(C)]
 pub struct NontrivialUnpin {
     __non_field_data: [::core::mem::MaybeUninit<u8>; 0],
@@ -749,6 +756,7 @@ pub fn TakesUnpinByConstRvalueReference<'a>(
 }
 
 /// Finally, testing for strange by-value APIs.
+#[crubit_annotate::cpp_type = "::NontrivialByValue"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct NontrivialByValue {
@@ -854,6 +862,7 @@ impl<'other> ::ctor::Assign<::ctor::RvalueReference<'other, crate::Nontrivial>>
 // Error while generating bindings for item 'NontrivialByValue::operator==':
 // operator== where lhs operand is not record nor const reference to record
 
+#[crubit_annotate::cpp_type = "::Nonmovable"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct Nonmovable {
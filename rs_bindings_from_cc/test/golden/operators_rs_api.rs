@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/operators.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::AddableConstMember"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct AddableConstMember {
@@ -85,6 +89,7 @@ impl<'a, 'b> ::core::ops::Add<&'b crate::AddableConstMember> for &'a crate::Adda
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableNonConstMember"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct AddableNonConstMember {
@@ -156,6 +161,7 @@ impl<'a, 'b> ::core::ops::Add<&'b crate::AddableNonConstMember>
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableFriend"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct AddableFriend {
@@ -218,6 +224,7 @@ impl<'a, 'b> ::core::ops::Add<&'b crate::AddableFriend> for &'a crate::AddableFr
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableFreeByConstRef"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddableFreeByConstRef {
@@ -268,6 +275,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for AddableFreeB
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableFreeByMutRef"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddableFreeByMutRef {
@@ -318,6 +326,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for AddableFreeB
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableFreeByValue"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddableFreeByValue {
@@ -368,6 +377,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for AddableFreeB
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableFreeByRValueRef"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddableFreeByRValueRef {
@@ -465,6 +475,7 @@ impl ::core::ops::Add<Self> for AddableFreeByValue {
 // Error while generating bindings for item 'operator+':
 // Not yet supported for rvalue references (b/219826128)
 
+#[crubit_annotate::cpp_type = "::Overloaded"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct Overloaded {
@@ -528,6 +539,7 @@ impl<'a> ::core::ops::Add<u32> for &'a crate::Overloaded {
     }
 }
 
+#[crubit_annotate::cpp_type = "::IncompatibleLHS"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct IncompatibleLHS {
@@ -584,6 +596,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for Incompatible
 // Error while generating bindings for item 'operator+':
 // Expected first parameter referent to be a record
 
+#[crubit_annotate::cpp_type = "::AddableReturnsVoid"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct AddableReturnsVoid {
@@ -645,6 +658,7 @@ impl<'a, 'b> ::core::ops::Add<&'b crate::AddableReturnsVoid> for &'a crate::Adda
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddableConstMemberNonunpin"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C, align(4))]
 pub struct AddableConstMemberNonunpin {
@@ -741,6 +755,7 @@ impl ::ctor::PinnedDrop for AddableConstMemberNonunpin {
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddAssignMemberInt"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignMemberInt {
@@ -800,6 +815,7 @@ impl ::core::ops::AddAssign<i32> for AddAssignMemberInt {
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddAssignMemberByConstRef"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignMemberByConstRef {
@@ -859,6 +875,7 @@ impl<'b> ::core::ops::AddAssign<&'b Self> for AddAssignMemberByConstRef {
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddAssignFreeByConstRef"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignFreeByConstRef {
@@ -912,6 +929,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for AddAssignFre
 // Error while generating bindings for item 'operator+=':
 // Not yet supported for pointers with unknown lifetime (b/219826128)
 
+#[crubit_annotate::cpp_type = "::AddAssignFreeByValue"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignFreeByValue {
@@ -971,6 +989,7 @@ impl ::core::ops::AddAssign<Self> for AddAssignFreeByValue {
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddAssignFriendByConstRef"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignFriendByConstRef {
@@ -1021,6 +1040,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for AddAssignFri
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddAssignFriendByValue"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignFriendByValue {
@@ -1080,6 +1100,7 @@ impl ::core::ops::AddAssign<Self> for AddAssignFriendByValue {
     }
 }
 
+#[crubit_annotate::cpp_type = "::AddAssignProhibitedConstMember"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignProhibitedConstMember {
@@ -1137,6 +1158,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for AddAssignPro
 // Error while generating bindings for item 'AddAssignProhibitedConstMember::operator+=':
 // Compound assignment with const left-hand side is not supported
 
+#[crubit_annotate::cpp_type = "::AddAssignProhibitedFriendConstLhs"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct AddAssignProhibitedFriendConstLhs {
@@ -1198,6 +1220,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>>
 // Error while generating bindings for item 'operator+=':
 // Compound assignment with const left-hand side is not supported
 
+#[crubit_annotate::cpp_type = "::ManyOperators"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct ManyOperators {
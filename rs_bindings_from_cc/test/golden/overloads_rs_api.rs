@@ -7,24 +7,31 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/overloads.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
-// Error while generating bindings for item 'Overload':
-// Cannot generate bindings for overloaded function
+#[inline(always)]
+pub fn Overload() {
+    unsafe { crate::detail::__rust_thunk___Z8Overloadv() }
+}
 
-// Error while generating bindings for item 'Overload':
-// Cannot generate bindings for overloaded function
+#[inline(always)]
+pub fn Overload_int(__param_0: i32) {
+    unsafe { crate::detail::__rust_thunk___Z8Overloadi(__param_0) }
+}
 
 // Error while generating bindings for item 'UncallableOverload':
 // Cannot generate bindings for overloaded function
@@ -52,6 +59,10 @@ mod detail {
     #[allow(unused_imports)]
     use super::*;
     extern "C" {
+        #[link_name = "_Z8Overloadv"]
+        pub(crate) fn __rust_thunk___Z8Overloadv();
+        #[link_name = "_Z8Overloadi"]
+        pub(crate) fn __rust_thunk___Z8Overloadi(__param_0: i32);
         pub(crate) fn __rust_thunk___Z20AlsoTemplateOverloadv();
     }
 }
@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/polymorphic.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::PolymorphicBase"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C, align(8))]
 pub struct PolymorphicBase {
@@ -89,6 +93,7 @@ impl ::ctor::PinnedDrop for PolymorphicBase {
     }
 }
 
+#[crubit_annotate::cpp_type = "::PolymorphicBase2"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C, align(8))]
 pub struct PolymorphicBase2 {
@@ -165,6 +170,60 @@ impl ::ctor::PinnedDrop for PolymorphicBase2 {
     }
 }
 
+/// Rust implementation of the virtual methods of `PolymorphicBase2` (see `PolymorphicBase2WithOverrides::new`).
+pub trait PolymorphicBase2Overrides {
+    fn Foo(&mut self);
+}
+
+/// A C++ `PolymorphicBase2` whose virtual methods are implemented in Rust by a `PolymorphicBase2Overrides`.
+pub struct PolymorphicBase2WithOverrides<'a> {
+    cc_obj: *mut crate::PolymorphicBase2,
+    _overrides: ::core::marker::PhantomData<&'a mut ()>,
+}
+
+impl<'a> PolymorphicBase2WithOverrides<'a> {
+    pub fn new<T: PolymorphicBase2Overrides>(overrides: &'a mut T) -> Self {
+        #[allow(dead_code)]
+        #[repr(C)]
+        struct Vtable {
+            Foo: unsafe extern "C" fn(*mut ::core::ffi::c_void),
+        }
+        unsafe extern "C" fn __crubit_override_Foo<T: PolymorphicBase2Overrides>(
+            rust_self: *mut ::core::ffi::c_void,
+        ) {
+            <T as PolymorphicBase2Overrides>::Foo(&mut *(rust_self as *mut T))
+        }
+        let vtable = Vtable { Foo: __crubit_override_Foo::<T> };
+        let cc_obj = unsafe {
+            crate::detail::__crubit_overrides_new__16PolymorphicBase2(
+                overrides as *mut T as *mut ::core::ffi::c_void,
+                &vtable as *const Vtable as *const ::core::ffi::c_void,
+            )
+        };
+        Self { cc_obj, _overrides: ::core::marker::PhantomData }
+    }
+    pub fn as_pin_mut(&mut self) -> ::core::pin::Pin<&mut crate::PolymorphicBase2> {
+        unsafe { ::core::pin::Pin::new_unchecked(&mut *self.cc_obj) }
+    }
+    pub fn as_mut_ptr(&mut self) -> *mut crate::PolymorphicBase2 {
+        self.cc_obj
+    }
+}
+
+impl ::core::ops::Deref for PolymorphicBase2WithOverrides<'_> {
+    type Target = crate::PolymorphicBase2;
+    fn deref(&self) -> &crate::PolymorphicBase2 {
+        unsafe { &*self.cc_obj }
+    }
+}
+
+impl Drop for PolymorphicBase2WithOverrides<'_> {
+    fn drop(&mut self) {
+        unsafe { crate::detail::__crubit_overrides_delete__16PolymorphicBase2(self.cc_obj) }
+    }
+}
+
+#[crubit_annotate::cpp_type = "::PolymorphicDerived"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C, align(8))]
 pub struct PolymorphicDerived {
@@ -309,6 +368,13 @@ mod detail {
         pub(crate) fn __rust_thunk___ZN16PolymorphicBase2D1Ev<'a>(
             __this: ::core::pin::Pin<&'a mut crate::PolymorphicBase2>,
         );
+        pub(crate) fn __crubit_overrides_new__16PolymorphicBase2(
+            rust_self: *mut ::core::ffi::c_void,
+            vtable: *const ::core::ffi::c_void,
+        ) -> *mut crate::PolymorphicBase2;
+        pub(crate) fn __crubit_overrides_delete__16PolymorphicBase2(
+            cc_obj: *mut crate::PolymorphicBase2,
+        );
         pub(crate) fn __rust_thunk___ZN18PolymorphicDerivedC1Ev<'a>(
             __this: &'a mut ::core::mem::MaybeUninit<crate::PolymorphicDerived>,
         );
@@ -69,6 +69,34 @@ extern "C" void __rust_thunk___ZN16PolymorphicBase2D1Ev(
   std::destroy_at(__this);
 }
 
+struct __crubit_overrides_vtable__16PolymorphicBase2 {
+  void (*Foo)(void*);
+};
+
+class __crubit_overrides__16PolymorphicBase2 final : public PolymorphicBase2 {
+ public:
+  __crubit_overrides__16PolymorphicBase2(
+      void* rust_self,
+      const __crubit_overrides_vtable__16PolymorphicBase2& vtable)
+      : rust_self_(rust_self), vtable_(vtable) {}
+  void Foo() override { return vtable_.Foo(rust_self_); }
+
+ private:
+  void* rust_self_;
+  __crubit_overrides_vtable__16PolymorphicBase2 vtable_;
+};
+
+extern "C" class PolymorphicBase2* __crubit_overrides_new__16PolymorphicBase2(
+    void* rust_self,
+    const __crubit_overrides_vtable__16PolymorphicBase2* vtable) {
+  return new __crubit_overrides__16PolymorphicBase2(rust_self, *vtable);
+}
+
+extern "C" void __crubit_overrides_delete__16PolymorphicBase2(
+    class PolymorphicBase2* cc_obj) {
+  delete static_cast<__crubit_overrides__16PolymorphicBase2*>(cc_obj);
+}
+
 static_assert(sizeof(class PolymorphicDerived) == 16);
 static_assert(alignof(class PolymorphicDerived) == 8);
 
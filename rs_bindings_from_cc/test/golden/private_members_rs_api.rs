@@ -7,20 +7,24 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/private_members.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
 pub mod test_namespace_bindings {
+    #[crubit_annotate::cpp_type = "::test_namespace_bindings::SomeClass"]
     #[derive(Clone, Copy)]
     #[repr(C, align(4))]
     pub struct SomeClass {
@@ -31,7 +35,7 @@ pub mod test_namespace_bindings {
         pub(crate) private_member_variable_: [::core::mem::MaybeUninit<u8>; 4],
     }
     forward_declare::unsafe_define!(
-        forward_declare::symbol!("SomeClass"),
+        forward_declare::symbol!("test_namespace_bindings::SomeClass"),
         crate::test_namespace_bindings::SomeClass
     );
 
@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/templates.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::DifferentScope"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct DifferentScope {
@@ -76,13 +80,14 @@ pub mod test_namespace_bindings {
     pub type OtherTypeAliasInSameTarget =
         crate::__CcTemplateInstN23test_namespace_bindings10MyTemplateIiEE;
 
+    #[crubit_annotate::cpp_type = "::test_namespace_bindings::TemplateParam"]
     #[derive(Clone, Copy)]
     #[repr(C)]
     pub struct TemplateParam {
         __non_field_data: [::core::mem::MaybeUninit<u8>; 1],
     }
     forward_declare::unsafe_define!(
-        forward_declare::symbol!("TemplateParam"),
+        forward_declare::symbol!("test_namespace_bindings::TemplateParam"),
         crate::test_namespace_bindings::TemplateParam
     );
 
@@ -212,13 +217,14 @@ pub mod forward_declared_template {
 // namespace forward_declared_template
 
 pub mod private_classes {
+    #[crubit_annotate::cpp_type = "::private_classes::HasPrivateType"]
     #[::ctor::recursively_pinned]
     #[repr(C)]
     pub struct HasPrivateType {
         __non_field_data: [::core::mem::MaybeUninit<u8>; 1],
     }
     forward_declare::unsafe_define!(
-        forward_declare::symbol!("HasPrivateType"),
+        forward_declare::symbol!("private_classes::HasPrivateType"),
         crate::private_classes::HasPrivateType
     );
 
@@ -304,6 +310,7 @@ pub mod private_classes {
 
 // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_TEMPLATES_H_
 
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::MyTemplate<DifferentScope>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInstN23test_namespace_bindings10MyTemplateI14DifferentScopeEE {
@@ -385,6 +392,7 @@ impl __CcTemplateInstN23test_namespace_bindings10MyTemplateI14DifferentScopeEE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::MyTemplate<test_namespace_bindings::TemplateParam>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInstN23test_namespace_bindings10MyTemplateINS_13TemplateParamEEE {
@@ -468,6 +476,7 @@ impl __CcTemplateInstN23test_namespace_bindings10MyTemplateINS_13TemplateParamEE
     }
 }
 
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::MyTemplate<int>"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct __CcTemplateInstN23test_namespace_bindings10MyTemplateIiEE {
@@ -547,6 +556,7 @@ impl __CcTemplateInstN23test_namespace_bindings10MyTemplateIiEE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::TemplateWithTwoParams<test_namespace_bindings::TemplateWithTwoParams<int, int>, int>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInstN23test_namespace_bindings21TemplateWithTwoParamsINS0_IiiEEiEE {
@@ -606,6 +616,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>>
     }
 }
 
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::TemplateWithTwoParams<int, float>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInstN23test_namespace_bindings21TemplateWithTwoParamsIifEE {
@@ -663,6 +674,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>>
     }
 }
 
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::TemplateWithTwoParams<int, int>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInstN23test_namespace_bindings21TemplateWithTwoParamsIiiEE {
@@ -722,6 +734,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>>
 
 /// Explicit class template specialization with definition should be imported
 /// even when not instantiated if there is a type alias for it.
+#[crubit_annotate::cpp_type = "::test_namespace_bindings::MyStruct<char>"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct __CcTemplateInstN23test_namespace_bindings8MyStructIcEE {
@@ -820,6 +833,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>>
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTopLevelTemplate<test_namespace_bindings::TemplateParam>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst18MyTopLevelTemplateIN23test_namespace_bindings13TemplateParamEE {
@@ -876,8 +890,9 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>>
     }
 }
 
-forward_declare::forward_declare!(pub __CcTemplateInst18MyTopLevelTemplateIiE = forward_declare::symbol!("__CcTemplateInst18MyTopLevelTemplateIiE"));
+forward_declare::forward_declare!(pub __CcTemplateInst18MyTopLevelTemplateIiE = forward_declare::symbol!("MyTopLevelTemplate<int>"));
 
+#[crubit_annotate::cpp_type = "::template_template_params::MyTemplate<template_template_params::Policy>"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct __CcTemplateInstN24template_template_params10MyTemplateINS_6PolicyEEE {
@@ -993,7 +1008,7 @@ impl __CcTemplateInstN24template_template_params10MyTemplateINS_6PolicyEEE {
     }
 }
 
-forward_declare::forward_declare!(pub __CcTemplateInstN25forward_declared_template23ForwardDeclaredTemplateIiEE = forward_declare::symbol!("__CcTemplateInstN25forward_declared_template23ForwardDeclaredTemplateIiEE"));
+forward_declare::forward_declare!(pub __CcTemplateInstN25forward_declared_template23ForwardDeclaredTemplateIiEE = forward_declare::symbol!("forward_declared_template::ForwardDeclaredTemplate<int>"));
 
 mod detail {
     #[allow(unused_imports)]
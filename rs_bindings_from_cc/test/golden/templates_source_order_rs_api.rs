@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/templates_source_order.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -23,6 +26,7 @@
 // Error while generating bindings for item 'MyTemplate':
 // Class templates are not supported yet
 
+#[crubit_annotate::cpp_type = "::TopLevel"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct TopLevel {
@@ -58,13 +62,14 @@ pub type Alias5 = crate::__CcTemplateInst10MyTemplateIbE;
 pub type Alias6 = crate::__CcTemplateInst10MyTemplateIS_I8TopLevelEE;
 
 pub mod test_namespace_bindings {
+    #[crubit_annotate::cpp_type = "::test_namespace_bindings::Inner"]
     #[derive(Clone, Copy)]
     #[repr(C)]
     pub struct Inner {
         __non_field_data: [::core::mem::MaybeUninit<u8>; 1],
     }
     forward_declare::unsafe_define!(
-        forward_declare::symbol!("Inner"),
+        forward_declare::symbol!("test_namespace_bindings::Inner"),
         crate::test_namespace_bindings::Inner
     );
 
@@ -94,6 +99,7 @@ pub mod test_namespace_bindings {
 
 // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_TEMPLATES_SOURCE_ORDER_H_
 
+#[crubit_annotate::cpp_type = "::MyTemplate<TopLevel>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateI8TopLevelE {
@@ -129,6 +135,7 @@ impl __CcTemplateInst10MyTemplateI8TopLevelE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<test_namespace_bindings::Inner>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIN23test_namespace_bindings5InnerEE {
@@ -164,6 +171,7 @@ impl __CcTemplateInst10MyTemplateIN23test_namespace_bindings5InnerEE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<MyTemplate<TopLevel>>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIS_I8TopLevelEE {
@@ -202,6 +210,7 @@ impl __CcTemplateInst10MyTemplateIS_I8TopLevelEE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<MyTemplate<test_namespace_bindings::Inner>>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIS_IN23test_namespace_bindings5InnerEEE {
@@ -240,6 +249,7 @@ impl __CcTemplateInst10MyTemplateIS_IN23test_namespace_bindings5InnerEEE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<bool>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIbE {
@@ -275,6 +285,7 @@ impl __CcTemplateInst10MyTemplateIbE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<char>"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct __CcTemplateInst10MyTemplateIcE {
@@ -310,6 +321,7 @@ impl __CcTemplateInst10MyTemplateIcE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<double>"]
 #[derive(Clone, Copy)]
 #[repr(C, align(8))]
 pub struct __CcTemplateInst10MyTemplateIdE {
@@ -345,6 +357,7 @@ impl __CcTemplateInst10MyTemplateIdE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<float>"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct __CcTemplateInst10MyTemplateIfE {
@@ -380,6 +393,7 @@ impl __CcTemplateInst10MyTemplateIfE {
     }
 }
 
+#[crubit_annotate::cpp_type = "::MyTemplate<int>"]
 #[derive(Clone, Copy)]
 #[repr(C, align(4))]
 pub struct __CcTemplateInst10MyTemplateIiE {
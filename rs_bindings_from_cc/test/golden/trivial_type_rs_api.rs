@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/trivial_type.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -23,12 +26,13 @@
 pub mod ns {
     /// Implicitly defined special member functions are trivial on a struct with
     /// only trivial members.
+    #[crubit_annotate::cpp_type = "::ns::Trivial"]
     #[derive(Clone, Copy)]
     #[repr(C)]
     pub struct Trivial {
         pub trivial_field: i32,
     }
-    forward_declare::unsafe_define!(forward_declare::symbol!("Trivial"), crate::ns::Trivial);
+    forward_declare::unsafe_define!(forward_declare::symbol!("ns::Trivial"), crate::ns::Trivial);
 
     impl Default for Trivial {
         #[inline(always)]
@@ -118,13 +122,14 @@ pub mod ns {
 
     /// This struct is trivial, and therefore trivially relocatable etc., but still
     /// not safe to pass by reference as it is not final.
+    #[crubit_annotate::cpp_type = "::ns::TrivialNonfinal"]
     #[::ctor::recursively_pinned]
     #[repr(C)]
     pub struct TrivialNonfinal {
         pub trivial_field: i32,
     }
     forward_declare::unsafe_define!(
-        forward_declare::symbol!("TrivialNonfinal"),
+        forward_declare::symbol!("ns::TrivialNonfinal"),
         crate::ns::TrivialNonfinal
     );
 
@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/typedefs.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::SomeStruct"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct SomeStruct {
@@ -123,6 +127,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for SomeStruct {
 // Error while generating bindings for item 'SomeStruct':
 // Typedef only used to introduce a name in C. Not importing.
 
+#[crubit_annotate::cpp_type = "::SomeOtherStruct"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct SomeOtherStruct {
@@ -223,6 +228,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for SomeOtherStruct {
     }
 }
 
+#[crubit_annotate::cpp_type = "::SomeUnion"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union SomeUnion {
@@ -273,6 +279,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for SomeUnion {
 // Error while generating bindings for item 'SomeUnion':
 // Typedef only used to introduce a name in C. Not importing.
 
+#[crubit_annotate::cpp_type = "::SomeOtherUnion"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union SomeOtherUnion {
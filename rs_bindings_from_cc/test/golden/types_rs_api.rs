@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/types.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -26,6 +29,7 @@
 // Error while generating bindings for item 'Size':
 // Unsupported type 'decltype(sizeof (0))': Unsupported clang::Type class 'Decltype'
 
+#[crubit_annotate::cpp_type = "::SomeStruct"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct SomeStruct {
@@ -75,6 +79,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for SomeStruct {
 
 forward_declare::forward_declare!(pub ForwardDeclaredStruct = forward_declare::symbol!("ForwardDeclaredStruct"));
 
+#[crubit_annotate::cpp_type = "::FieldTypeTestStruct"]
 #[derive(Clone, Copy)]
 #[repr(C, align(8))]
 pub struct FieldTypeTestStruct {
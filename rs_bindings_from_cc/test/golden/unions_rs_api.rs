@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/unions.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::EmptyUnion"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union EmptyUnion {
@@ -67,6 +71,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for EmptyUnion {
     }
 }
 
+#[crubit_annotate::cpp_type = "::Nontrivial"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct Nontrivial {
@@ -118,6 +123,7 @@ impl<'b> ::ctor::CtorNew<(::ctor::RvalueReference<'b, Self>,)> for Nontrivial {
     }
 }
 
+#[crubit_annotate::cpp_type = "::TriviallyCopyableButNontriviallyDestructible"]
 #[::ctor::recursively_pinned(PinnedDrop)]
 #[repr(C)]
 pub struct TriviallyCopyableButNontriviallyDestructible {
@@ -169,6 +175,7 @@ impl ::ctor::PinnedDrop for TriviallyCopyableButNontriviallyDestructible {
     }
 }
 
+#[crubit_annotate::cpp_type = "::NonEmptyUnion"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union NonEmptyUnion {
@@ -219,6 +226,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for NonEmptyUnio
     }
 }
 
+#[crubit_annotate::cpp_type = "::NonCopyUnion"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub union NonCopyUnion {
@@ -227,6 +235,7 @@ pub union NonCopyUnion {
 }
 forward_declare::unsafe_define!(forward_declare::symbol!("NonCopyUnion"), crate::NonCopyUnion);
 
+#[crubit_annotate::cpp_type = "::NonCopyUnion2"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub union NonCopyUnion2 {
@@ -263,6 +272,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for NonCopyUnion2 {
     }
 }
 
+#[crubit_annotate::cpp_type = "::UnionWithOpaqueField"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union UnionWithOpaqueField {
@@ -315,6 +325,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for UnionWithOpa
     }
 }
 
+#[crubit_annotate::cpp_type = "::TrivialButInheritable"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct TrivialButInheritable {
@@ -415,6 +426,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for TrivialButInherit
     }
 }
 
+#[crubit_annotate::cpp_type = "::UnionWithInheritable"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub union UnionWithInheritable {
@@ -515,6 +527,7 @@ impl<'b> ::ctor::Assign<::ctor::RvalueReference<'b, Self>> for UnionWithInherita
     }
 }
 
+#[crubit_annotate::cpp_type = "::TypedefUnion"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union TypedefUnion {
@@ -562,6 +575,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for TypedefUnion
     }
 }
 
+#[crubit_annotate::cpp_type = "::TypedefUnionWithInheritable"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub union TypedefUnionWithInheritable {
@@ -12,9 +12,6 @@ struct TrivialCustomType final {
   // added.
   bool operator||(const TrivialCustomType&) const;
 
-  // clang::DeclarationName::NameKind::CXXConversionFunctionName
-  explicit operator int() const { return i; }
-
   int i;
 };
 
@@ -42,7 +39,7 @@ struct PackedLayout final {
 volatile int* MultipleReasons(volatile int* n);
 
 struct ContainingStruct final {
-  struct NestedStruct final {
+  struct __attribute__((packed)) NestedStruct final {
     void NonStaticMemberFunction();
     void StaticMemberFunction();
   };
@@ -7,19 +7,23 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/unsupported.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#[crubit_annotate::cpp_type = "::TrivialCustomType"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct TrivialCustomType {
@@ -73,9 +77,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for TrivialCusto
 // Error while generating bindings for item 'TrivialCustomType::operator||':
 // Bindings for this kind of operator (operator || with 2 parameter(s)) are not supported
 
-// Error while generating bindings for item 'TrivialCustomType::operator int':
-// Function name is not supported: Unsupported name: operator int
-
+#[crubit_annotate::cpp_type = "::NontrivialCustomType"]
 #[::ctor::recursively_pinned]
 #[repr(C)]
 pub struct NontrivialCustomType {
@@ -124,6 +126,7 @@ impl<'b> ::ctor::CtorNew<(::ctor::RvalueReference<'b, Self>,)> for NontrivialCus
 //
 // Return type is not supported: Unsupported type 'volatile int *': Unsupported `volatile` qualifier: volatile int
 
+#[crubit_annotate::cpp_type = "::ContainingStruct"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct ContainingStruct {
@@ -179,7 +182,7 @@ impl<'b> ::ctor::UnpinAssign<::ctor::RvalueReference<'b, Self>> for ContainingSt
 }
 
 // Error while generating bindings for item 'ContainingStruct::NestedStruct':
-// Nested classes are not supported yet
+// Records with packed layout are not supported
 
 // CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_UNSUPPORTED_H_
 
@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes, negative_impls, type_alias_impl_trait)]
+#![feature(custom_inner_attributes, negative_impls, register_tool, type_alias_impl_trait)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/user_of_base_class.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -25,6 +28,7 @@
 /// This tests inheritance across library boundaries.
 ///
 /// TODO(b/216195042): Correctly namespace base classes in generated Rust code.
+#[crubit_annotate::cpp_type = "::Derived2"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct Derived2 {
@@ -138,7 +142,26 @@ unsafe impl oops::Inherits<inheritance_cc::Base2> for crate::Derived2 {
         (derived as *const _ as *const u8).offset(18) as *const inheritance_cc::Base2
     }
 }
+impl ::core::convert::AsRef<inheritance_cc::Base0> for crate::Derived2 {
+    #[inline(always)]
+    fn as_ref(&self) -> &inheritance_cc::Base0 {
+        unsafe { &*<Self as oops::Inherits<inheritance_cc::Base0>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<inheritance_cc::Base1> for crate::Derived2 {
+    #[inline(always)]
+    fn as_ref(&self) -> &inheritance_cc::Base1 {
+        unsafe { &*<Self as oops::Inherits<inheritance_cc::Base1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<inheritance_cc::Base2> for crate::Derived2 {
+    #[inline(always)]
+    fn as_ref(&self) -> &inheritance_cc::Base2 {
+        unsafe { &*<Self as oops::Inherits<inheritance_cc::Base2>>::upcast_ptr(self) }
+    }
+}
 
+#[crubit_annotate::cpp_type = "::VirtualDerived2"]
 #[::ctor::recursively_pinned]
 #[repr(C, align(8))]
 pub struct VirtualDerived2 {
@@ -254,6 +277,24 @@ unsafe impl oops::Inherits<inheritance_cc::VirtualBase2> for crate::VirtualDeriv
         crate::detail::__crubit_dynamic_upcast__15VirtualDerived2__to__12VirtualBase2(derived)
     }
 }
+impl ::core::convert::AsRef<inheritance_cc::VirtualBase1> for crate::VirtualDerived2 {
+    #[inline(always)]
+    fn as_ref(&self) -> &inheritance_cc::VirtualBase1 {
+        unsafe { &*<Self as oops::Inherits<inheritance_cc::VirtualBase1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<inheritance_cc::Base1> for crate::VirtualDerived2 {
+    #[inline(always)]
+    fn as_ref(&self) -> &inheritance_cc::Base1 {
+        unsafe { &*<Self as oops::Inherits<inheritance_cc::Base1>>::upcast_ptr(self) }
+    }
+}
+impl ::core::convert::AsRef<inheritance_cc::VirtualBase2> for crate::VirtualDerived2 {
+    #[inline(always)]
+    fn as_ref(&self) -> &inheritance_cc::VirtualBase2 {
+        unsafe { &*<Self as oops::Inherits<inheritance_cc::VirtualBase2>>::upcast_ptr(self) }
+    }
+}
 
 // CRUBIT_RS_BINDINGS_FROM_CC_TEST_GOLDEN_USER_OF_BASE_CLASS_H_
 
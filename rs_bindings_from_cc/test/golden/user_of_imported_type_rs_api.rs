@@ -7,14 +7,17 @@
 // Features: experimental, supported
 
 #![rustfmt::skip]
-#![feature(custom_inner_attributes)]
+#![feature(custom_inner_attributes, register_tool)]
 #![allow(stable_features)]
+#![register_tool(crubit_annotate)]
 #![no_std]
 #![allow(improper_ctypes)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(deprecated)]
 #![deny(warnings)]
+#![crubit_annotate::cpp_header = "rs_bindings_from_cc/test/golden/user_of_imported_type.h"]
 
 // Part of the Crubit project, under the Apache License v2.0 with LLVM
 // Exceptions. See /LICENSE for license information.
@@ -29,6 +32,7 @@ pub fn UsesImportedType(mut t: trivial_type_cc::ns::Trivial) -> trivial_type_cc:
     }
 }
 
+#[crubit_annotate::cpp_type = "::UserOfImportedType"]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct UserOfImportedType {
@@ -0,0 +1,31 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_ANONYMOUS_MEMBERS_ANONYMOUS_MEMBERS_H_
+#define CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_ANONYMOUS_MEMBERS_ANONYMOUS_MEMBERS_H_
+
+#pragma clang lifetime_elision
+
+struct StructWithAnonymousMembers final {
+  int before;
+  struct {
+    int x;
+    int y;
+  };
+  union {
+    int as_int;
+    unsigned int as_uint;
+  };
+  int after;
+};
+
+inline int SumOfAnonymousStructMembers(const StructWithAnonymousMembers& s) {
+  return s.x + s.y;
+}
+
+inline int GetAnonymousUnionInt(const StructWithAnonymousMembers& s) {
+  return s.as_int;
+}
+
+#endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_ANONYMOUS_MEMBERS_ANONYMOUS_MEMBERS_H_
@@ -0,0 +1,40 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#[cfg(test)]
+mod tests {
+    use anonymous_members::*;
+
+    fn new_struct() -> StructWithAnonymousMembers {
+        StructWithAnonymousMembers {
+            before: 1,
+            __unnamed_field1: StructWithAnonymousMembers__unnamed_field1 { x: 2, y: 3 },
+            __unnamed_field2: StructWithAnonymousMembers__unnamed_field2 { as_int: 4 },
+            after: 5,
+        }
+    }
+
+    #[test]
+    fn test_anonymous_struct_accessors() {
+        let mut s = new_struct();
+        assert_eq!(2, *s.x());
+        assert_eq!(3, *s.y());
+        *s.x_mut() = 20;
+        assert_eq!(20, *s.x());
+        assert_eq!(23, SumOfAnonymousStructMembers(&s));
+        assert_eq!(1, s.before);
+        assert_eq!(5, s.after);
+    }
+
+    #[test]
+    fn test_anonymous_union_accessors() {
+        let mut s = new_struct();
+        unsafe {
+            assert_eq!(4, *s.as_int());
+            *s.as_uint_mut() = 40;
+            assert_eq!(40, *s.as_int());
+        }
+        assert_eq!(40, GetAnonymousUnionInt(&s));
+    }
+}
@@ -27,4 +27,14 @@ class SomeClass final {
   int private_field_ = 0;
 };
 
+struct StructWithBitfields final {
+  int before;
+  int small_int : 3;
+  unsigned small_uint : 4;
+  bool flag : 1;
+  int after;
+};
+
+inline int GetSmallInt(const StructWithBitfields& s) { return s.small_int; }
+
 #endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_FIELDS_FIELDS_H_
@@ -15,4 +15,26 @@ mod tests {
         assert_eq!(s.char_var, 1);
         assert_eq!(s.int_var, 2);
     }
+
+    #[test]
+    fn test_bitfields() {
+        let mut s = StructWithBitfields::default();
+        s.before = 1;
+        s.after = 2;
+        s.set_small_int(-3);
+        s.set_small_uint(15);
+        s.set_flag(true);
+        assert_eq!(s.small_int(), -3);
+        assert_eq!(s.small_uint(), 15);
+        assert!(s.flag());
+        assert_eq!(GetSmallInt(&s), -3);
+
+        // Values that don't fit are truncated in the same way as in C++.
+        s.set_small_uint(16);
+        assert_eq!(s.small_uint(), 0);
+
+        // Setting the bitfields doesn't clobber the neighbouring fields.
+        assert_eq!(s.before, 1);
+        assert_eq!(s.after, 2);
+    }
 }
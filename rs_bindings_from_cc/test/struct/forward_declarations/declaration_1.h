@@ -27,4 +27,12 @@ inline int InlineFunctionTakingNonunpinStruct(const NonunpinStruct& s) {
   return ReadNonunpinStruct(s);
 }
 
+namespace forward_declarations {
+
+struct NamespacedStruct;
+
+int ReadNamespacedStruct(const NamespacedStruct& s);
+
+}  // namespace forward_declarations
+
 #endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_FORWARD_DECLARATIONS_DECLARATION_1_H_
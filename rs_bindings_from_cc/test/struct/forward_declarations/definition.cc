@@ -7,3 +7,9 @@ int ReadUnpinStruct(const UnpinStruct& s) { return s.field; }
 void WriteUnpinStruct(UnpinStruct& s, int value) { s.field = value; }
 int ReadNonunpinStruct(const NonunpinStruct& s) { return s.field; }
 void WriteNonunpinStruct(NonunpinStruct& s, int value) { s.field = value; }
+
+namespace forward_declarations {
+
+int ReadNamespacedStruct(const NamespacedStruct& s) { return s.field; }
+
+}  // namespace forward_declarations
@@ -19,4 +19,14 @@ void WriteUnpinStruct(UnpinStruct& s, int value);
 int ReadNonunpinStruct(const NonunpinStruct& s);
 void WriteNonunpinStruct(NonunpinStruct& s, int value);
 
+namespace forward_declarations {
+
+struct NamespacedStruct final {
+  int field = 0;
+};
+
+int ReadNamespacedStruct(const NamespacedStruct& s);
+
+}  // namespace forward_declarations
+
 #endif  // THIRD_PARTY_CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_FORWARD_DECLARATIONS_DEFINITION_H_
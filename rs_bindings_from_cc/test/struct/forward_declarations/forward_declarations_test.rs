@@ -191,6 +191,19 @@ fn test_inline_functions_with_incomplete_parameters() {
     assert_eq!(123, declaration_1::InlineFunctionTakingNonunpinStruct(nonunpin_ref.cc_cast()));
 }
 
+#[test]
+fn test_namespaced_incomplete() {
+    let s = definition::forward_declarations::NamespacedStruct { field: 42 };
+    let s = &s;
+
+    // Cast from complete to incomplete, both in the same C++ namespace.
+    let decl1_s: &declaration_1::forward_declarations::NamespacedStruct = s.cc_cast();
+    assert_eq!(declaration_1::forward_declarations::ReadNamespacedStruct(decl1_s), 42);
+
+    // Cast from incomplete to complete:
+    assert_eq!(definition::forward_declarations::ReadNamespacedStruct(decl1_s.cc_cast()), 42);
+}
+
 #[test]
 fn test_forward_declared_used_as_field_type() {
     // This is a regression test for b/246962427.  This mostly verifies that the
@@ -0,0 +1,23 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_INHERITANCE_OVERRIDES_H_
+#define CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_INHERITANCE_OVERRIDES_H_
+
+#pragma clang lifetime_elision
+
+class Listener {
+ public:
+  virtual ~Listener() = default;
+  virtual void OnEvent(int event) = 0;
+  virtual int GetPriority() const = 0;
+};
+
+// Calls the virtual methods of `listener` (which may be implemented in Rust).
+inline int NotifyListener(Listener* listener, int event) {
+  listener->OnEvent(event);
+  return listener->GetPriority();
+}
+
+#endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_INHERITANCE_OVERRIDES_H_
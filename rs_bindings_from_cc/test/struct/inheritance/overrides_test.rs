@@ -0,0 +1,34 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+#[cfg(test)]
+mod tests {
+    use overrides::*;
+
+    #[derive(Default)]
+    struct RecordingListener {
+        events: Vec<i32>,
+    }
+
+    impl ListenerOverrides for RecordingListener {
+        fn OnEvent(&mut self, event: i32) {
+            self.events.push(event);
+        }
+
+        fn GetPriority(&self) -> i32 {
+            self.events.len() as i32 * 10
+        }
+    }
+
+    #[test]
+    fn test_virtual_methods_implemented_in_rust() {
+        let mut rust_listener = RecordingListener::default();
+        {
+            let mut listener = ListenerWithOverrides::new(&mut rust_listener);
+            assert_eq!(unsafe { NotifyListener(listener.as_mut_ptr(), 1) }, 10);
+            assert_eq!(unsafe { NotifyListener(listener.as_mut_ptr(), 2) }, 20);
+            assert_eq!(listener.GetPriority(), 20);
+        }
+        assert_eq!(rust_listener.events, vec![1, 2]);
+    }
+}
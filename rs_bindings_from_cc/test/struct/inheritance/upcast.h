@@ -56,6 +56,18 @@ struct Derived final : Base0, Base1, Base4 {
   char private_field;  // NOLINT(clang-diagnostic-unused-private-field)
 };
 
+// The only direct base class is at a non-zero offset (after the vtable
+// pointer).
+class PolymorphicDerived : public Base4 {
+ public:
+  virtual ~PolymorphicDerived() = default;
+
+  size_t base4_address() const {
+    const Base4* base = this;
+    return reinterpret_cast<size_t>(base);
+  }
+};
+
 namespace virtual_inheritance {
 
 class VirtualBase2 : public virtual Base1 {};
@@ -24,6 +24,41 @@ mod tests {
         assert_eq!(base4 as *const _ as usize, derived.base4_address());
     }
 
+    #[test]
+    fn test_as_ref_to_bases() {
+        let derived = Derived::default();
+        let base1: &Base1 = derived.as_ref();
+        assert_eq!(base1 as *const _ as usize, derived.base1_address());
+        let base4: &Base4 = derived.as_ref();
+        assert_eq!(base4 as *const _ as usize, derived.base4_address());
+        let base3: &Base3 = derived.as_ref();
+        assert_eq!(base3 as *const _ as usize, derived.base3_address());
+        assert_ne!(base3 as *const _ as usize, &derived as *const _ as usize);
+    }
+
+    #[test]
+    fn test_deref_to_base() {
+        ctor::emplace! {
+            let derived = PolymorphicDerived::ctor_new(());
+        }
+        let derived = &*derived;
+
+        let base4: &Base4 = derived;
+        assert_eq!(base4 as *const _ as usize, derived.base4_address());
+        assert_ne!(base4 as *const _ as usize, derived as *const _ as usize);
+    }
+
+    #[test]
+    fn test_virtual_deref_to_base() {
+        ctor::emplace! {
+            let derived = another_namespace::VirtualBase2::ctor_new(());
+        }
+        let derived = &*derived;
+
+        let base1: &Base1 = derived;
+        assert_eq!(base1 as *const _ as usize, derived.base1_address());
+    }
+
     #[test]
     fn test_virtual_upcast() {
         use upcast::virtual_inheritance::*;
@@ -0,0 +1,37 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_NESTED_NESTED_H_
+#define CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_NESTED_NESTED_H_
+
+#pragma clang lifetime_elision
+
+namespace crubit_nested {
+
+struct Outer final {
+  struct Inner final {
+    static Inner Create(int value) { return Inner{value}; }
+    int Get() const { return value; }
+    void Set(int new_value) { value = new_value; }
+
+    struct Innermost final {
+      int innermost_value;
+    };
+
+    int value;
+  };
+
+  Inner GetInner() const { return inner; }
+
+  Inner inner;
+  Inner::Innermost innermost;
+};
+
+inline int SumOfInnerValues(Outer::Inner a, Outer::Inner b) {
+  return a.value + b.value;
+}
+
+}  // namespace crubit_nested
+
+#endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_NESTED_NESTED_H_
@@ -0,0 +1,36 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#[cfg(test)]
+mod tests {
+    use nested::crubit_nested::*;
+
+    #[test]
+    fn test_nested_struct_fields() {
+        let outer = Outer {
+            inner: Outer_Inner { value: 123 },
+            innermost: Outer_Inner_Innermost { innermost_value: 456 },
+        };
+        assert_eq!(123, outer.inner.value);
+        assert_eq!(456, outer.innermost.innermost_value);
+    }
+
+    #[test]
+    fn test_nested_struct_methods() {
+        let mut inner = Outer_Inner::Create(1);
+        assert_eq!(1, inner.Get());
+        inner.Set(2);
+        assert_eq!(2, inner.Get());
+    }
+
+    #[test]
+    fn test_nested_struct_as_param_and_return_type() {
+        let outer = Outer {
+            inner: Outer_Inner::Create(3),
+            innermost: Outer_Inner_Innermost { innermost_value: 0 },
+        };
+        assert_eq!(3, outer.GetInner().value);
+        assert_eq!(7, SumOfInnerValues(outer.GetInner(), Outer_Inner::Create(4)));
+    }
+}
@@ -230,4 +230,27 @@ struct ManyOperators final {
   int i;
 };
 
+// Should generate `impl Index<i32> for Indexable` (with `type Output = i32`).
+struct Indexable final {
+  inline const int& operator[](int index) const { return values[index]; }
+
+  int values[3];
+};
+
+// Should generate inherent `call` and `call_mut` methods.
+struct Callable final {
+  inline int operator()(int x) const { return x * factor; }
+
+  int factor;
+};
+
+struct CallableMut final {
+  inline int operator()(int x) {
+    total += x;
+    return total;
+  }
+
+  int total;
+};
+
 #endif  // CRUBIT_RS_BINDINGS_FROM_CC_TEST_STRUCT_OPERATORS_OPERATORS_H_
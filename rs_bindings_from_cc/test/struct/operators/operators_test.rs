@@ -316,4 +316,24 @@ mod tests {
         s1 >>= s2;
         assert_eq!(0, s1.i);
     }
+
+    #[test]
+    fn test_index() {
+        let s = Indexable { values: [10, 20, 30] };
+        assert_eq!(10, s[0]);
+        assert_eq!(30, s[2]);
+    }
+
+    #[test]
+    fn test_call() {
+        let c = Callable { factor: 3 };
+        assert_eq!(21, c.call(7));
+    }
+
+    #[test]
+    fn test_call_mut() {
+        let mut c = CallableMut { total: 1 };
+        assert_eq!(3, c.call_mut(2));
+        assert_eq!(6, c.call_mut(3));
+    }
 }
@@ -0,0 +1,102 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+/// A C++ exception caught at the FFI boundary.
+///
+/// When the `exceptions` Crubit feature is enabled, `rs_bindings_from_cc`
+/// generates bindings that return `Result<T, CcException>` for
+/// potentially-throwing C++ functions (i.e. functions not declared
+/// `noexcept`).  The exception itself doesn't cross the FFI boundary - only
+/// its (possibly truncated) `what()` message is preserved.
+///
+/// SAFETY: the layout below has to match the layout of `crubit::CcException`
+/// in `support/internal/cc_exception.h`.
+#[repr(C)]
+#[derive(Clone)]
+pub struct CcException {
+    what_len: usize,
+    what: [u8; CcException::MAX_WHAT_LEN],
+}
+
+impl CcException {
+    /// The maximum length of the preserved `what()` message (longer messages
+    /// are truncated).
+    pub const MAX_WHAT_LEN: usize = 255;
+
+    /// Returns the `what()` message of the exception (or a fixed message for
+    /// exceptions that don't derive from `std::exception`) as raw bytes.
+    pub fn what_bytes(&self) -> &[u8] {
+        &self.what[..self.what_len.min(Self::MAX_WHAT_LEN)]
+    }
+
+    /// Returns the `what()` message of the exception, up to the first byte
+    /// that is not valid UTF8 (truncating a long message may split a
+    /// multibyte character).
+    pub fn what(&self) -> &str {
+        let bytes = self.what_bytes();
+        match core::str::from_utf8(bytes) {
+            Ok(what) => what,
+            Err(error) => {
+                // `valid_up_to` guarantees that the prefix is valid UTF8.
+                unsafe { core::str::from_utf8_unchecked(&bytes[..error.valid_up_to()]) }
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for CcException {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.what())
+    }
+}
+
+impl core::fmt::Debug for CcException {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CcException").field("what", &self.what()).finish()
+    }
+}
+
+/// The slot where the generated C++ thunks store the exception thrown by the
+/// wrapped C++ function (if any).  Only used by the generated bindings.
+///
+/// SAFETY: the layout below has to match the layout of
+/// `crubit::CcExceptionSlot` in `support/internal/cc_exception.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct CcExceptionSlot {
+    thrown: bool,
+    exception: CcException,
+}
+
+impl CcExceptionSlot {
+    pub fn new() -> Self {
+        Self {
+            thrown: false,
+            exception: CcException { what_len: 0, what: [0; CcException::MAX_WHAT_LEN] },
+        }
+    }
+
+    /// Returns `Err` if the C++ thunk has stored an exception in the slot, or
+    /// `Ok(value)` otherwise (where `value` is the value returned by the
+    /// thunk).
+    pub fn into_result<T>(self, value: T) -> Result<T, CcException> {
+        if self.thrown { Err(self.exception) } else { Ok(value) }
+    }
+
+    /// Panics if the C++ thunk has stored an exception in the slot, or returns
+    /// `value` otherwise.  This converts C++ exceptions into Rust panics, for
+    /// callers that can't handle a `Result`.
+    pub fn into_value_or_panic<T>(self, value: T) -> T {
+        match self.into_result(value) {
+            Ok(value) => value,
+            Err(exception) => panic!("C++ exception: {exception}"),
+        }
+    }
+}
+
+impl Default for CcExceptionSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
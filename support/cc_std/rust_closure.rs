@@ -0,0 +1,118 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use core::ffi::c_void;
+use core::mem::ManuallyDrop;
+
+/// The FFI-safe representation of the Rust closures that `rs_bindings_from_cc`
+/// maps by-value `std::function` and `absl::AnyInvocable` parameters to.  Only
+/// used by the generated bindings, which pass it to the C++ thunks (where the
+/// closure is moved into a C++ functor that calls it through a trampoline).
+///
+/// SAFETY: the layout below has to match the layout of `crubit::RustClosure`
+/// in `support/internal/rust_closure.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct RustClosure {
+    data: *mut c_void,
+    size: usize,
+    align: usize,
+    call: unsafe extern "C" fn(),
+    drop: unsafe extern "C" fn(*mut c_void),
+}
+
+impl RustClosure {
+    /// Returns the `RustClosure` for the `Fn` closure `f` (which may be called
+    /// concurrently through shared references).
+    ///
+    /// # Safety
+    ///
+    /// The returned `RustClosure` takes the ownership of `f`: it has to be
+    /// passed to a C++ thunk (which moves the closure out of `f`) exactly once
+    /// before `f` goes out of scope.  `f` can't be used afterwards.
+    pub unsafe fn from_fn<Args, R, F: RustFn<Args, R>>(f: &mut ManuallyDrop<F>) -> Self {
+        Self::new(f, F::trampoline())
+    }
+
+    /// Returns the `RustClosure` for the `FnMut` closure `f`.
+    ///
+    /// # Safety
+    ///
+    /// Same as for `from_fn`.  Additionally, the C++ callee must not call the
+    /// closure concurrently.
+    pub unsafe fn from_fn_mut<Args, R, F: RustFnMut<Args, R>>(f: &mut ManuallyDrop<F>) -> Self {
+        Self::new(f, F::trampoline())
+    }
+
+    fn new<F>(f: &mut ManuallyDrop<F>, call: unsafe extern "C" fn()) -> Self {
+        unsafe extern "C" fn drop<F>(data: *mut c_void) {
+            core::ptr::drop_in_place(data as *mut F)
+        }
+        Self {
+            data: &mut **f as *mut F as *mut c_void,
+            size: core::mem::size_of::<F>(),
+            align: core::mem::align_of::<F>(),
+            call,
+            drop: drop::<F>,
+        }
+    }
+}
+
+/// Implemented by the `Fn` closures that take `Args` (a tuple of parameter
+/// types) and return `R`.  Only used by `RustClosure::from_fn`.
+#[doc(hidden)]
+pub trait RustFn<Args, R> {
+    /// Returns the type-erased trampoline that calls the closure.
+    fn trampoline() -> unsafe extern "C" fn();
+}
+
+/// Implemented by the `FnMut` closures that take `Args` (a tuple of parameter
+/// types) and return `R`.  Only used by `RustClosure::from_fn_mut`.
+#[doc(hidden)]
+pub trait RustFnMut<Args, R> {
+    /// Returns the type-erased trampoline that calls the closure.
+    fn trampoline() -> unsafe extern "C" fn();
+}
+
+macro_rules! impl_closure_traits {
+    ($($arg:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<F: Fn($($arg),*) -> R, $($arg,)* R> RustFn<($($arg,)*), R> for F {
+            fn trampoline() -> unsafe extern "C" fn() {
+                unsafe extern "C" fn call<F: Fn($($arg),*) -> R, $($arg,)* R>(
+                    data: *mut c_void,
+                    $($arg: $arg),*
+                ) -> R {
+                    (*(data as *const F))($($arg),*)
+                }
+                let call: unsafe extern "C" fn(*mut c_void, $($arg),*) -> R =
+                    call::<F, $($arg,)* R>;
+                unsafe { core::mem::transmute(call) }
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<F: FnMut($($arg),*) -> R, $($arg,)* R> RustFnMut<($($arg,)*), R> for F {
+            fn trampoline() -> unsafe extern "C" fn() {
+                unsafe extern "C" fn call<F: FnMut($($arg),*) -> R, $($arg,)* R>(
+                    data: *mut c_void,
+                    $($arg: $arg),*
+                ) -> R {
+                    (*(data as *mut F))($($arg),*)
+                }
+                let call: unsafe extern "C" fn(*mut c_void, $($arg),*) -> R =
+                    call::<F, $($arg,)* R>;
+                unsafe { core::mem::transmute(call) }
+            }
+        }
+    };
+}
+
+impl_closure_traits!();
+impl_closure_traits!(A0);
+impl_closure_traits!(A0, A1);
+impl_closure_traits!(A0, A1, A2);
+impl_closure_traits!(A0, A1, A2, A3);
+impl_closure_traits!(A0, A1, A2, A3, A4);
+impl_closure_traits!(A0, A1, A2, A3, A4, A5);
@@ -0,0 +1,118 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use core::ffi::c_void;
+use core::ops::Deref;
+
+/// Rust equivalent of a C++ `std::shared_ptr<T>`.
+///
+/// `rs_bindings_from_cc` maps by-value `std::shared_ptr<T>` parameters and
+/// return values to `SharedPtr<T>`.  A `SharedPtr<T>` owns a heap-allocated
+/// C++ `std::shared_ptr<T>` (the "handle") and so it shares the ownership of
+/// the pointee with all the other `std::shared_ptr`s and `SharedPtr`s that
+/// point to it.  Cloning a `SharedPtr` increments the reference count of the
+/// pointee, and dropping a `SharedPtr` decrements it (deleting the pointee
+/// when the count drops to zero).
+///
+/// The pointee is shared, so `SharedPtr` only provides shared (`&T`) access to
+/// it.
+pub struct SharedPtr<T> {
+    handle: *mut c_void,
+    ptr: *mut T,
+    clone: unsafe extern "C" fn(*mut c_void) -> *mut c_void,
+    delete: unsafe extern "C" fn(*mut c_void),
+}
+
+impl<T> SharedPtr<T> {
+    /// Takes the ownership of `handle`.
+    ///
+    /// # Safety
+    ///
+    /// `handle` has to point to a heap-allocated C++ `std::shared_ptr<T>` that
+    /// is exclusively owned by the returned `SharedPtr`, and that was
+    /// allocated with C++ `new`.  `get(handle)` has to return the pointee of
+    /// the handle (i.e. `handle->get()`), `clone(handle)` has to return a new
+    /// heap-allocated copy of the handle, and `delete(handle)` has to delete
+    /// the handle.
+    pub unsafe fn from_raw(
+        handle: *mut c_void,
+        get: unsafe extern "C" fn(*mut c_void) -> *mut T,
+        clone: unsafe extern "C" fn(*mut c_void) -> *mut c_void,
+        delete: unsafe extern "C" fn(*mut c_void),
+    ) -> Self {
+        // The pointee of a `std::shared_ptr` doesn't change unless the
+        // `std::shared_ptr` is assigned to, and nothing can assign to a handle
+        // owned by a `SharedPtr`.
+        let ptr = get(handle);
+        Self { handle, ptr, clone, delete }
+    }
+
+    /// Releases the ownership of the handle, returning the raw pointer to the
+    /// heap-allocated C++ `std::shared_ptr<T>`.  The caller becomes
+    /// responsible for deleting the handle.
+    pub fn into_raw(self) -> *mut c_void {
+        let handle = self.handle;
+        core::mem::forget(self);
+        handle
+    }
+
+    /// Returns the raw pointer to the pointee (which may be null).
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    /// Returns `true` if the `SharedPtr` doesn't point to any object.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// Returns a shared reference to the pointee, or `None` if the
+    /// `SharedPtr` is null.
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns `true` if both `SharedPtr`s point to the same object (or if
+    /// both are null).
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+}
+
+/// Returns a new `SharedPtr` that points to the same object, incrementing
+/// its reference count.
+impl<T> Clone for SharedPtr<T> {
+    fn clone(&self) -> Self {
+        let handle = unsafe { (self.clone)(self.handle) };
+        Self { handle, ptr: self.ptr, clone: self.clone, delete: self.delete }
+    }
+}
+
+/// Dereferences the pointee.
+///
+/// Panics if the `SharedPtr` is null (where dereferencing in C++ would be
+/// undefined behavior).
+impl<T> Deref for SharedPtr<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.as_ref().expect("Dereferencing a null `SharedPtr`")
+    }
+}
+
+/// Decrements the reference count of the pointee (deleting it if this was the
+/// last `std::shared_ptr` or `SharedPtr` that pointed to it).
+impl<T> Drop for SharedPtr<T> {
+    fn drop(&mut self) {
+        unsafe { (self.delete)(self.handle) }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for SharedPtr<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.as_ref() {
+            Some(pointee) => f.debug_tuple("SharedPtr").field(pointee).finish(),
+            None => f.write_str("SharedPtr(null)"),
+        }
+    }
+}
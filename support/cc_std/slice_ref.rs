@@ -0,0 +1,39 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use core::ffi::c_void;
+
+/// The FFI-safe representation of the `&str`, `&[T]` and `&mut [T]`
+/// references that `rs_bindings_from_cc` maps by-value `absl::string_view`
+/// and `absl::Span<T>` parameters to.  Only used by the generated bindings,
+/// which pass it to the C++ thunks (where the view is reconstructed from the
+/// pointer and the number of elements).
+///
+/// SAFETY: the layout below has to match the layout of `crubit::SliceRef` in
+/// `support/internal/slice_ref.h`.
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SliceRef {
+    data: *mut c_void,
+    size: usize,
+}
+
+impl From<&str> for SliceRef {
+    fn from(s: &str) -> Self {
+        Self { data: s.as_ptr() as *mut c_void, size: s.len() }
+    }
+}
+
+impl<T> From<&[T]> for SliceRef {
+    fn from(slice: &[T]) -> Self {
+        Self { data: slice.as_ptr() as *mut c_void, size: slice.len() }
+    }
+}
+
+impl<T> From<&mut [T]> for SliceRef {
+    fn from(slice: &mut [T]) -> Self {
+        Self { data: slice.as_mut_ptr() as *mut c_void, size: slice.len() }
+    }
+}
@@ -0,0 +1,49 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use crate::std::string;
+use core::convert::TryFrom;
+use core::ptr;
+
+impl string {
+    /// Returns the contents of the C++ string as a Rust byte slice, without
+    /// copying them.
+    ///
+    /// The returned slice borrows from `self`, and so it can't outlive the
+    /// string, nor be used while the string is being mutated.
+    pub fn as_bytes(&self) -> &[u8] {
+        let data = unsafe { string::c_str(self) } as *const u8;
+        let size = unsafe { string::length(self) };
+        // `c_str` never returns null, but the Rust slice below would be unsound
+        // if it did.
+        debug_assert!(!data.is_null());
+        unsafe { &*ptr::slice_from_raw_parts(data, size) }
+    }
+
+    /// Returns the contents of the C++ string as a Rust string, failing if the
+    /// string is not UTF8.  Doesn't copy the contents of the string.
+    pub fn to_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.as_bytes())
+    }
+}
+
+impl<'a> From<&'a string> for &'a [u8] {
+    fn from(s: &'a string) -> Self {
+        s.as_bytes()
+    }
+}
+
+/// Converts a C++ string to a Rust string, failing if the string is not UTF8.
+impl<'a> TryFrom<&'a string> for &'a str {
+    type Error = core::str::Utf8Error;
+    fn try_from(s: &'a string) -> Result<Self, Self::Error> {
+        s.to_str()
+    }
+}
+
+impl AsRef<[u8]> for string {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
@@ -0,0 +1,109 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+
+/// Rust equivalent of a C++ `std::unique_ptr<T>` (with the default deleter).
+///
+/// `rs_bindings_from_cc` maps by-value `std::unique_ptr<T>` parameters and
+/// return values to `UniquePtr<T>`, transferring the ownership of the pointee
+/// at the boundary.  Dropping a (non-null) `UniquePtr<T>` deletes the pointee
+/// (by calling the deleter that the `UniquePtr` was created with - for
+/// `UniquePtr`s returned from C++ this is a thunk that invokes C++ `delete`).
+pub struct UniquePtr<T> {
+    ptr: *mut T,
+    deleter: unsafe extern "C" fn(*mut T),
+}
+
+impl<T> UniquePtr<T> {
+    /// Takes the ownership of `ptr` (which may be null).
+    ///
+    /// # Safety
+    ///
+    /// If `ptr` is not null, then it has to point to a valid `T` that is
+    /// exclusively owned by the returned `UniquePtr`, and that can be deleted
+    /// by calling `deleter(ptr)`.  (For example, `ptr` can come from
+    /// `UniquePtr::into_raw` of a `UniquePtr` created with the same
+    /// `deleter`.)  Additionally, if the returned `UniquePtr` is passed to C++,
+    /// then the pointee has to be deletable by C++ `delete` (because C++
+    /// `std::unique_ptr` takes over its ownership).
+    pub unsafe fn from_raw(ptr: *mut T, deleter: unsafe extern "C" fn(*mut T)) -> Self {
+        Self { ptr, deleter }
+    }
+
+    /// Releases the ownership of the pointee, returning the raw pointer to it
+    /// (which may be null).  The caller becomes responsible for deleting the
+    /// pointee.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        core::mem::forget(self);
+        ptr
+    }
+
+    /// Returns the raw pointer to the pointee (which may be null), without
+    /// releasing the ownership of the pointee.
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Returns `true` if the `UniquePtr` doesn't own any object.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// Returns a shared reference to the pointee, or `None` if the
+    /// `UniquePtr` is null.
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns a pinned mutable reference to the pointee, or `None` if the
+    /// `UniquePtr` is null.
+    ///
+    /// The pointee is pinned, because it stays at the same address for as
+    /// long as it lives (and so moving it is not necessary).
+    pub fn as_mut(&mut self) -> Option<Pin<&mut T>> {
+        unsafe { self.ptr.as_mut().map(|pointee| Pin::new_unchecked(pointee)) }
+    }
+}
+
+/// Dereferences the pointee.
+///
+/// Panics if the `UniquePtr` is null (where dereferencing in C++ would be
+/// undefined behavior).
+impl<T> Deref for UniquePtr<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.as_ref().expect("Dereferencing a null `UniquePtr`")
+    }
+}
+
+/// Mutably dereferences the pointee.  This requires `T: Unpin` - use
+/// `UniquePtr::as_mut` to get a `Pin<&mut T>` otherwise.
+///
+/// Panics if the `UniquePtr` is null (where dereferencing in C++ would be
+/// undefined behavior).
+impl<T: Unpin> DerefMut for UniquePtr<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.ptr.as_mut() }.expect("Dereferencing a null `UniquePtr`")
+    }
+}
+
+impl<T> Drop for UniquePtr<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { (self.deleter)(self.ptr) }
+        }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for UniquePtr<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.as_ref() {
+            Some(pointee) => f.debug_tuple("UniquePtr").field(pointee).finish(),
+            None => f.write_str("UniquePtr(null)"),
+        }
+    }
+}
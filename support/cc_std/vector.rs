@@ -0,0 +1,125 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+/// Rust equivalent of a C++ `std::vector<T>` (with the default allocator).
+///
+/// `rs_bindings_from_cc` maps pointers and references to `std::vector<T>` to
+/// pointers and references to `Vector<T>`.  `Vector<T>` can't be created or
+/// resized from Rust (because `std::vector` is not trivially relocatable, and
+/// because growing it requires calling into the C++ allocator), but it can be
+/// read and modified in place - e.g. indexed or iterated over - via the
+/// `Deref<Target = [T]>` implementation.
+///
+/// SAFETY: the layout below has to match the layout of `std::vector` in
+/// libc++.
+#[repr(C)]
+pub struct Vector<T> {
+    begin: *mut T,
+    end: *mut T,
+    end_cap: *mut T,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Vector<T> {
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.begin == self.end
+    }
+
+    /// Returns the number of elements that the vector can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        if self.begin.is_null() {
+            return 0;
+        }
+        unsafe { self.end_cap.offset_from(self.begin) as usize }
+    }
+
+    /// Returns a slice containing all the elements of the vector, without
+    /// copying them.
+    pub fn as_slice(&self) -> &[T] {
+        // Unlike C++, Rust does not allow for null data pointers in slices.
+        if self.begin.is_null() {
+            return &[];
+        }
+        unsafe {
+            let size = self.end.offset_from(self.begin) as usize;
+            &*ptr::slice_from_raw_parts(self.begin, size)
+        }
+    }
+}
+
+impl<T: Unpin> Vector<T> {
+    /// Returns a mutable slice containing all the elements of the vector,
+    /// without copying them.
+    ///
+    /// This requires `T: Unpin`, because the slice lets the elements be moved
+    /// (e.g. swapped), which is only safe for elements that are trivially
+    /// relocatable.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.begin.is_null() {
+            return &mut [];
+        }
+        unsafe {
+            let size = self.end.offset_from(self.begin) as usize;
+            &mut *ptr::slice_from_raw_parts_mut(self.begin, size)
+        }
+    }
+}
+
+impl<T> Deref for Vector<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Unpin> DerefMut for Vector<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> AsRef<[T]> for Vector<T> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Vector<T> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl<'a, T: Unpin> IntoIterator for &'a mut Vector<T> {
+    type Item = &'a mut T;
+    type IntoIter = core::slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_mut_slice().iter_mut()
+    }
+}
+
+impl<T: PartialEq> PartialEq<[T]> for Vector<T> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Vector<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
@@ -0,0 +1,234 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! Attributes that give Rust library authors control over the C++ bindings
+//! generated for their crate by `cc_bindings_from_rs`.  This crate is meant to
+//! be depended upon under the name `crubit`, so that the attributes are
+//! spelled as `#[crubit::rename("CcName")]`, etc.
+//!
+//! The attributes don't change the Rust item.  They only attach a
+//! `#[crubit_annotate::...]` tool attribute to it, which is how
+//! `cc_bindings_from_rs` discovers the annotations (tool attributes don't
+//! affect rustdoc, but are preserved in the crate metadata and therefore are
+//! also visible when generating bindings for other crates that depend on the
+//! annotated crate).  This means that crates using the attributes need to
+//! register the tool:
+//!
+//! ```ignore
+//! #![feature(register_tool)]
+//! #![register_tool(crubit_annotate)]
+//! ```
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+
+/// Name of the tool whose attributes `cc_bindings_from_rs` recognizes as
+/// annotations.
+const ANNOTATION_TOOL: &str = "crubit_annotate";
+
+/// `#[crubit::rename("CcName")]` makes the C++ bindings use `CcName` instead
+/// of the Rust name of the annotated function, struct, enum, or union.
+#[proc_macro_attribute]
+pub fn rename(args: TokenStream, item: TokenStream) -> TokenStream {
+    into_token_stream(annotate_with_string_arg("rename", args.into(), item.into()))
+}
+
+/// `#[crubit::skip]` suppresses the C++ bindings of the annotated item (and,
+/// for modules, of all the items inside the module).
+#[proc_macro_attribute]
+pub fn skip(args: TokenStream, item: TokenStream) -> TokenStream {
+    into_token_stream(annotate_without_args("skip", args.into(), item.into()))
+}
+
+/// `#[crubit::must_bind]` makes `cc_bindings_from_rs` fail if it can't
+/// generate C++ bindings for the annotated item (rather than only explaining
+/// the problem in a comment in the generated header).
+#[proc_macro_attribute]
+pub fn must_bind(args: TokenStream, item: TokenStream) -> TokenStream {
+    into_token_stream(annotate_without_args("must_bind", args.into(), item.into()))
+}
+
+/// `#[crubit::cc_namespace("x::y")]` puts the C++ bindings of the annotated
+/// item (or, for modules, of all the items inside the module) into the
+/// `x::y` namespace (nested inside the top-level namespace of the crate)
+/// instead of the namespace derived from the Rust module path.
+#[proc_macro_attribute]
+pub fn cc_namespace(args: TokenStream, item: TokenStream) -> TokenStream {
+    into_token_stream(annotate_with_string_arg("cc_namespace", args.into(), item.into()))
+}
+
+/// `#[crubit::cc_case("CamelCase")]` or `#[crubit::cc_case("snake_case")]`
+/// overrides the `--cc-fn-case` of `cc_bindings_from_rs` for the annotated
+/// function (or, for modules and `impl` blocks, for all the functions inside).
+#[proc_macro_attribute]
+pub fn cc_case(args: TokenStream, item: TokenStream) -> TokenStream {
+    into_token_stream(annotate_with_cc_case(args.into(), item.into()))
+}
+
+/// `#[crubit::cc_builder]` generates a fluent C++ builder class for the
+/// annotated struct (e.g. `SomeStructBuilder().set_x(1).set_y(2).Build()`).
+/// The struct has to implement `Default` (which provides the values of the
+/// fields that are not set by the builder).
+#[proc_macro_attribute]
+pub fn cc_builder(args: TokenStream, item: TokenStream) -> TokenStream {
+    into_token_stream(annotate_without_args("cc_builder", args.into(), item.into()))
+}
+
+fn into_token_stream(result: syn::Result<proc_macro2::TokenStream>) -> TokenStream {
+    match result {
+        Ok(t) => t.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+/// A separate function for calling from tests (see also
+/// `recursively_pinned_impl` in `ctor_proc_macros.rs`).
+fn annotate_with_string_arg(
+    name: &str,
+    args: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let arg = syn::parse2::<syn::LitStr>(args)?;
+    let value = arg.value();
+    if value.is_empty() {
+        return Err(syn::Error::new(arg.span(), format!("`{name}` requires a non-empty string")));
+    }
+    Ok(annotate(name, Some(&value), item))
+}
+
+fn annotate_with_cc_case(
+    args: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let arg = syn::parse2::<syn::LitStr>(args)?;
+    let value = arg.value();
+    if value != "CamelCase" && value != "snake_case" {
+        return Err(syn::Error::new(
+            arg.span(),
+            "`cc_case` requires either \"CamelCase\" or \"snake_case\"",
+        ));
+    }
+    Ok(annotate("cc_case", Some(&value), item))
+}
+
+fn annotate_without_args(
+    name: &str,
+    args: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(args, format!("`{name}` doesn't take any arguments")));
+    }
+    Ok(annotate(name, None, item))
+}
+
+fn annotate(
+    name: &str,
+    value: Option<&str>,
+    item: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let tool = format_ident!("{ANNOTATION_TOOL}");
+    let name = format_ident!("{name}");
+    match value {
+        Some(value) => quote! {
+            #[#tool::#name = #value]
+            #item
+        },
+        None => quote! {
+            #[#tool::#name]
+            #item
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use token_stream_matchers::assert_rs_matches;
+
+    #[test]
+    fn test_rename() {
+        let result =
+            annotate_with_string_arg("rename", quote! { "CcName" }, quote! { pub fn foo() {} })
+                .unwrap();
+        assert_rs_matches!(
+            result,
+            quote! {
+                #[crubit_annotate::rename = "CcName"]
+                pub fn foo() {}
+            }
+        );
+    }
+
+    #[test]
+    fn test_rename_without_string() {
+        let err =
+            annotate_with_string_arg("rename", quote! {}, quote! { pub fn foo() {} }).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected end of input, expected string literal");
+    }
+
+    #[test]
+    fn test_rename_with_empty_string() {
+        let err = annotate_with_string_arg("rename", quote! { "" }, quote! { pub fn foo() {} })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "`rename` requires a non-empty string");
+    }
+
+    #[test]
+    fn test_skip() {
+        let result = annotate_without_args("skip", quote! {}, quote! { pub struct S; }).unwrap();
+        assert_rs_matches!(
+            result,
+            quote! {
+                #[crubit_annotate::skip]
+                pub struct S;
+            }
+        );
+    }
+
+    #[test]
+    fn test_skip_with_args() {
+        let err =
+            annotate_without_args("skip", quote! { "foo" }, quote! { pub struct S; }).unwrap_err();
+        assert_eq!(err.to_string(), "`skip` doesn't take any arguments");
+    }
+
+    #[test]
+    fn test_cc_builder() {
+        let result = annotate_without_args(
+            "cc_builder",
+            quote! {},
+            quote! { #[derive(Default)] pub struct S { pub x: i32 } },
+        )
+        .unwrap();
+        assert_rs_matches!(
+            result,
+            quote! {
+                #[crubit_annotate::cc_builder]
+                #[derive(Default)]
+                pub struct S { pub x: i32 }
+            }
+        );
+    }
+
+    #[test]
+    fn test_cc_case() {
+        let result =
+            annotate_with_cc_case(quote! { "CamelCase" }, quote! { pub fn foo() {} }).unwrap();
+        assert_rs_matches!(
+            result,
+            quote! {
+                #[crubit_annotate::cc_case = "CamelCase"]
+                pub fn foo() {}
+            }
+        );
+    }
+
+    #[test]
+    fn test_cc_case_with_invalid_case() {
+        let err =
+            annotate_with_cc_case(quote! { "camelCase" }, quote! { pub fn foo() {} }).unwrap_err();
+        assert_eq!(err.to_string(), "`cc_case` requires either \"CamelCase\" or \"snake_case\"");
+    }
+}
@@ -0,0 +1,73 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_CC_EXCEPTION_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_CC_EXCEPTION_H_
+
+#include <cstddef>
+#include <cstring>
+#include <exception>
+
+namespace crubit {
+
+// `CcException` stores the message of a C++ exception caught by the C++ thunks
+// that `rs_bindings_from_cc` generates for potentially-throwing functions when
+// the `exceptions` Crubit feature is enabled.
+//
+// The layout of `CcException` and `CcExceptionSlot` has to match the layout of
+// `cc_std::CcException` and `cc_std::CcExceptionSlot` in Rust.
+struct CcException {
+  // Longer messages are truncated.
+  static constexpr size_t kMaxWhatLength = 255;
+
+  size_t what_length;
+  char what[kMaxWhatLength];
+};
+
+// `CcExceptionSlot` is passed by the Rust caller to the C++ thunk, which uses
+// it to report whether the wrapped function has thrown an exception:
+//
+//     ```cc
+//     extern "C" int __rust_thunk_for_foo(int arg,
+//                                         CcExceptionSlot* __exception) {
+//       try {
+//         return foo(arg);
+//       } catch (...) {
+//         __exception->StoreCurrentException();
+//         return {};
+//       }
+//     }
+//     ```
+//
+// Exceptions are not propagated across the FFI boundary (this would be
+// Undefined Behavior) - instead the generated Rust bindings return
+// `Err(cc_std::CcException)` if `thrown` is set.
+struct CcExceptionSlot {
+  // Stores the exception that is currently being handled.  Can only be called
+  // from a `catch` block.
+  void StoreCurrentException() noexcept {
+    const char* what = "Unknown C++ exception";
+    try {
+      throw;
+    } catch (const std::exception& e) {
+      what = e.what();
+    } catch (...) {
+    }
+    size_t what_length = 0;
+    while (what_length < CcException::kMaxWhatLength &&
+           what[what_length] != '\0') {
+      ++what_length;
+    }
+    thrown = true;
+    exception.what_length = what_length;
+    std::memcpy(exception.what, what, what_length);
+  }
+
+  bool thrown;
+  CcException exception;
+};
+
+}  // namespace crubit
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_CC_EXCEPTION_H_
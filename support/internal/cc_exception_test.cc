@@ -0,0 +1,54 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/internal/cc_exception.h"
+
+#include <stdexcept>
+#include <string>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+
+namespace crubit {
+namespace {
+
+std::string What(const CcExceptionSlot& slot) {
+  return std::string(slot.exception.what, slot.exception.what_length);
+}
+
+TEST(CcExceptionSlotTest, StdException) {
+  CcExceptionSlot slot = {};
+  try {
+    throw std::runtime_error("Oops");
+  } catch (...) {
+    slot.StoreCurrentException();
+  }
+  EXPECT_TRUE(slot.thrown);
+  EXPECT_EQ(What(slot), "Oops");
+}
+
+TEST(CcExceptionSlotTest, NonStdException) {
+  CcExceptionSlot slot = {};
+  try {
+    throw 42;
+  } catch (...) {
+    slot.StoreCurrentException();
+  }
+  EXPECT_TRUE(slot.thrown);
+  EXPECT_EQ(What(slot), "Unknown C++ exception");
+}
+
+TEST(CcExceptionSlotTest, LongMessageIsTruncated) {
+  CcExceptionSlot slot = {};
+  try {
+    throw std::runtime_error(std::string(1000, 'x'));
+  } catch (...) {
+    slot.StoreCurrentException();
+  }
+  EXPECT_TRUE(slot.thrown);
+  EXPECT_EQ(What(slot), std::string(CcException::kMaxWhatLength, 'x'));
+}
+
+}  // namespace
+}  // namespace crubit
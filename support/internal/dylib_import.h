@@ -0,0 +1,23 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_DYLIB_IMPORT_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_DYLIB_IMPORT_H_
+
+// `CRUBIT_DYLIB_IMPORT` annotates the declarations of the functions and
+// variables that the C++ bindings generated by `cc_bindings_from_rs` import
+// from a Rust `cdylib` (i.e. from a shared library that exports the Rust side
+// of the bindings).
+//
+// On Windows the symbols have to be declared as `__declspec(dllimport)`.  On
+// other platforms the symbols are declared with the default visibility, so
+// that the references to them are resolved against the shared library even if
+// the C++ code is compiled with `-fvisibility=hidden`.
+#if defined(_WIN32)
+#define CRUBIT_DYLIB_IMPORT __declspec(dllimport)
+#else
+#define CRUBIT_DYLIB_IMPORT __attribute__((visibility("default")))
+#endif
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_DYLIB_IMPORT_H_
@@ -0,0 +1,26 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_LIFETIME_BOUND_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_LIFETIME_BOUND_H_
+
+// `CRUBIT_LIFETIME_BOUND` annotates the parameters (and the implicit object
+// parameters) of the C++ bindings of Rust functions whose return value borrows
+// from the parameter (e.g. `fn first_half(s: &mut [i32]) -> &mut [i32]`).
+//
+// With Clang this expands to `[[clang::lifetimebound]]`, which lets the
+// compiler diagnose some of the cases where the returned value outlives the
+// object it borrows from (e.g. a `rs_std::MutSlice` returned for a temporary
+// `std::vector`).  With other compilers this expands to nothing.
+#if defined(__has_cpp_attribute)
+#if __has_cpp_attribute(clang::lifetimebound)
+#define CRUBIT_LIFETIME_BOUND [[clang::lifetimebound]]
+#endif
+#endif
+
+#ifndef CRUBIT_LIFETIME_BOUND
+#define CRUBIT_LIFETIME_BOUND
+#endif
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_LIFETIME_BOUND_H_
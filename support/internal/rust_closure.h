@@ -0,0 +1,81 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_RUST_CLOSURE_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_RUST_CLOSURE_H_
+
+#include <cstddef>
+#include <cstring>
+#include <memory>
+#include <new>
+
+namespace crubit {
+
+// `RustClosure` is the FFI-safe representation of the Rust closures that
+// `rs_bindings_from_cc` maps `std::function` and `absl::AnyInvocable`
+// parameters to.  `data` points to the closure (of the given `size` and
+// `align`) that the Rust caller has given up the ownership of, `call` is the
+// type-erased trampoline that calls the closure, and `drop` drops it.
+//
+// The layout of `RustClosure` has to match the layout of `cc_std::RustClosure`
+// in Rust.
+struct RustClosure {
+  void* data;
+  size_t size;
+  size_t align;
+  void (*call)();
+  void (*drop)(void*);
+};
+
+// `RustClosureFunctor<R(Args...)>` is a C++ functor that calls a Rust closure.
+// The C++ thunks use it to construct the `std::function` or the
+// `absl::AnyInvocable` passed to the wrapped C++ function:
+//
+//     ```cc
+//     extern "C" void __rust_thunk_for_foo(crubit::RustClosure f) {
+//       foo(std::function<int(int)>(crubit::RustClosureFunctor<int(int)>(f)));
+//     }
+//     ```
+//
+// The functor moves the Rust closure to the heap (Rust values can be moved by
+// copying their bytes), and so the closure outlives the Rust caller.  Copies of
+// the functor share the closure, which is dropped together with the last copy.
+template <typename Signature>
+class RustClosureFunctor;
+
+template <typename R, typename... Args>
+class RustClosureFunctor<R(Args...)> {
+ public:
+  explicit RustClosureFunctor(RustClosure closure)
+      : call_(reinterpret_cast<R (*)(void*, Args...)>(closure.call)),
+        data_(MoveToHeap(closure), Deleter{closure.drop, closure.align}) {}
+
+  R operator()(Args... args) const { return call_(data_.get(), args...); }
+
+ private:
+  struct Deleter {
+    void operator()(void* data) const {
+      drop(data);
+      ::operator delete(data, std::align_val_t(align));
+    }
+
+    void (*drop)(void*);
+    size_t align;
+  };
+
+  static void* MoveToHeap(const RustClosure& closure) {
+    // `operator new` doesn't support zero-sized allocations.
+    void* data = ::operator new(closure.size == 0 ? 1 : closure.size,
+                                std::align_val_t(closure.align));
+    std::memcpy(data, closure.data, closure.size);
+    return data;
+  }
+
+  R (*call_)(void*, Args...);
+  std::shared_ptr<void> data_;
+};
+
+}  // namespace crubit
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_RUST_CLOSURE_H_
@@ -0,0 +1,74 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/internal/rust_closure.h"
+
+#include <functional>
+#include <memory>
+
+#include "gtest/gtest.h"
+
+namespace crubit {
+namespace {
+
+// Stands in for a Rust closure that captures an `int` (the trampolines below
+// play the role of the trampolines generated by `cc_std::RustClosure`).
+struct FakeClosure {
+  int offset;
+  int* drop_count;
+};
+
+int CallFakeClosure(void* data, int value) {
+  return static_cast<FakeClosure*>(data)->offset + value;
+}
+
+void DropFakeClosure(void* data) {
+  ++*static_cast<FakeClosure*>(data)->drop_count;
+}
+
+RustClosure MakeRustClosure(FakeClosure& closure) {
+  return RustClosure{
+      .data = &closure,
+      .size = sizeof(FakeClosure),
+      .align = alignof(FakeClosure),
+      .call = reinterpret_cast<void (*)()>(&CallFakeClosure),
+      .drop = &DropFakeClosure,
+  };
+}
+
+TEST(RustClosureTest, CallsTheClosure) {
+  int drop_count = 0;
+  FakeClosure closure{.offset = 100, .drop_count = &drop_count};
+  std::function<int(int)> f =
+      RustClosureFunctor<int(int)>(MakeRustClosure(closure));
+  EXPECT_EQ(f(1), 101);
+  EXPECT_EQ(f(2), 102);
+}
+
+TEST(RustClosureTest, MovesTheClosureToTheHeap) {
+  int drop_count = 0;
+  std::function<int(int)> f;
+  {
+    FakeClosure closure{.offset = 100, .drop_count = &drop_count};
+    f = RustClosureFunctor<int(int)>(MakeRustClosure(closure));
+    closure.offset = 0;
+  }
+  EXPECT_EQ(f(1), 101);
+}
+
+TEST(RustClosureTest, DropsTheClosureTogetherWithTheLastCopy) {
+  int drop_count = 0;
+  FakeClosure closure{.offset = 100, .drop_count = &drop_count};
+  auto f = std::make_unique<std::function<int(int)>>(
+      RustClosureFunctor<int(int)>(MakeRustClosure(closure)));
+  auto copy = std::make_unique<std::function<int(int)>>(*f);
+  f.reset();
+  EXPECT_EQ(drop_count, 0);
+  EXPECT_EQ((*copy)(1), 101);
+  copy.reset();
+  EXPECT_EQ(drop_count, 1);
+}
+
+}  // namespace
+}  // namespace crubit
@@ -0,0 +1,32 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_SLICE_REF_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_SLICE_REF_H_
+
+#include <cstddef>
+
+namespace crubit {
+
+// `SliceRef` is the FFI-safe representation of the Rust `&str`, `&[T]` and
+// `&mut [T]` references that `rs_bindings_from_cc` maps `absl::string_view`
+// and `absl::Span<T>` parameters to.  The C++ thunks reconstruct the view from
+// the pointer and the number of elements:
+//
+//     ```cc
+//     extern "C" void __rust_thunk_for_foo(crubit::SliceRef s) {
+//       foo(absl::string_view(static_cast<const char*>(s.data), s.size));
+//     }
+//     ```
+//
+// The layout of `SliceRef` has to match the layout of `cc_std::SliceRef` in
+// Rust.
+struct SliceRef {
+  void* data;
+  size_t size;
+};
+
+}  // namespace crubit
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_SLICE_REF_H_
@@ -0,0 +1,180 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! Runtime support for passing `String`, `Vec<T>`, and `Box<T>` between Rust
+//! and C++.
+//!
+//! The layouts of `String` and `Vec<T>` are not guaranteed, and therefore the
+//! `extern "C"` thunks generated by `cc_bindings_from_rs` convert them into
+//! `StringRawParts` and `VecRawParts<T>` (and back) at the FFI boundary - the
+//! C++ side of the bindings converts the raw parts into `rs_std::String` and
+//! `rs_std::Vec<T>` (see `support/rs_std/rs_string.h` and
+//! `support/rs_std/rs_vec.h`):
+//!
+//! ```ignore
+//! #[no_mangle]
+//! extern "C" fn __crubit_thunk_foo(s: ::alloc_bridge::StringRawParts) -> ::alloc_bridge::StringRawParts {
+//!     ::alloc_bridge::StringRawParts::from_string(::some_crate::foo(unsafe {
+//!         ::alloc_bridge::StringRawParts::into_string(s)
+//!     }))
+//! }
+//! ```
+//!
+//! `Box<T>` is passed as a raw pointer (see `box_into_raw` and `box_from_raw`),
+//! which the C++ side of the bindings wraps into `rs_std::Box<T>` (see
+//! `support/rs_std/rs_box.h`).
+//!
+//! The crate only depends on `alloc`, so that it can also be used by the
+//! bindings of `#![no_std]` crates.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem::ManuallyDrop;
+
+/// The raw parts of a `String`.  Only used by the generated bindings.
+///
+/// SAFETY: the layout below has to match the layout of
+/// `rs_std::String::RawParts` in `support/rs_std/rs_string.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct StringRawParts {
+    data: *mut u8,
+    size: usize,
+    capacity: usize,
+}
+
+impl StringRawParts {
+    /// Converts `s` into its raw parts.  The ownership of the allocation is
+    /// transferred to the `StringRawParts` (which doesn't free the allocation
+    /// when dropped).
+    pub fn from_string(s: String) -> Self {
+        let mut s = ManuallyDrop::new(s);
+        Self { data: s.as_mut_ptr(), size: s.len(), capacity: s.capacity() }
+    }
+
+    /// Converts the raw parts back into a `String`.
+    ///
+    /// # Safety
+    ///
+    /// The raw parts have to come from `from_string` or from
+    /// `rs_std::String::release` in C++.
+    pub unsafe fn into_string(self) -> String {
+        if self.capacity == 0 {
+            // `rs_std::String` may use a null `data` pointer when it doesn't
+            // own an allocation (while Rust requires a non-null, dangling
+            // pointer in this case).
+            String::new()
+        } else {
+            String::from_raw_parts(self.data, self.size, self.capacity)
+        }
+    }
+}
+
+/// The raw parts of a `Vec<T>`.  Only used by the generated bindings.
+///
+/// SAFETY: the layout below has to match the layout of
+/// `rs_std::Vec<T>::RawParts` in `support/rs_std/rs_vec.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct VecRawParts<T> {
+    data: *mut T,
+    size: usize,
+    capacity: usize,
+}
+
+impl<T> VecRawParts<T> {
+    /// Converts `v` into its raw parts.  The ownership of the allocation and
+    /// of the elements is transferred to the `VecRawParts` (which doesn't drop
+    /// them when dropped).
+    pub fn from_vec(v: Vec<T>) -> Self {
+        let mut v = ManuallyDrop::new(v);
+        Self { data: v.as_mut_ptr(), size: v.len(), capacity: v.capacity() }
+    }
+
+    /// Converts the raw parts back into a `Vec<T>`.
+    ///
+    /// # Safety
+    ///
+    /// The raw parts have to come from `from_vec` or from
+    /// `rs_std::Vec<T>::release` in C++ (which, like Rust, uses a dangling,
+    /// aligned pointer when the capacity is 0).
+    pub unsafe fn into_vec(self) -> Vec<T> {
+        Vec::from_raw_parts(self.data, self.size, self.capacity)
+    }
+}
+
+/// Converts `b` into a raw pointer.  The ownership of the pointee is
+/// transferred to the caller (i.e. to `rs_std::Box<T>` in C++).
+#[doc(hidden)]
+pub fn box_into_raw<T>(b: Box<T>) -> *mut T {
+    Box::into_raw(b)
+}
+
+/// Converts a raw pointer back into a `Box<T>`.
+///
+/// # Safety
+///
+/// `ptr` has to come from `box_into_raw` or from `rs_std::Box<T>::release` in
+/// C++ (and therefore it can't be null).
+#[doc(hidden)]
+pub unsafe fn box_from_raw<T>(ptr: *mut T) -> Box<T> {
+    Box::from_raw(ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_round_trip() {
+        let s = String::from("foo");
+        let data = s.as_ptr();
+        let parts = StringRawParts::from_string(s);
+        assert_eq!(parts.size, 3);
+        let s = unsafe { parts.into_string() };
+        assert_eq!(s.as_ptr(), data);
+        assert_eq!(s, "foo");
+    }
+
+    #[test]
+    fn test_string_without_allocation() {
+        let parts = StringRawParts { data: std::ptr::null_mut(), size: 0, capacity: 0 };
+        let s = unsafe { parts.into_string() };
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_vec_round_trip() {
+        let v = vec![1, 2, 3];
+        let data = v.as_ptr();
+        let parts = VecRawParts::from_vec(v);
+        assert_eq!(parts.size, 3);
+        let v = unsafe { parts.into_vec() };
+        assert_eq!(v.as_ptr(), data);
+        assert_eq!(v, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_vec_without_allocation() {
+        let parts = VecRawParts::<u64>::from_vec(Vec::new());
+        assert_eq!(parts.capacity, 0);
+        assert!(!parts.data.is_null());
+        let v = unsafe { parts.into_vec() };
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_box_round_trip() {
+        let b = Box::new(123);
+        let ptr = box_into_raw(b);
+        let b = unsafe { box_from_raw(ptr) };
+        assert_eq!(&*b as *const i32, ptr as *const i32);
+        assert_eq!(*b, 123);
+    }
+}
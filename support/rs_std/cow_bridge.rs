@@ -0,0 +1,115 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! Runtime support for passing `Cow<'_, str>` between Rust and C++.
+//!
+//! The layout of `Cow<'_, str>` is not guaranteed, and therefore the
+//! `extern "C"` thunks generated by `cc_bindings_from_rs` convert `Cow<str>`
+//! into `CowStrRawParts` (and back) at the FFI boundary - the C++ side of the
+//! bindings converts `CowStrRawParts` into `rs_std::CowStr` (see
+//! `support/rs_std/rs_cow_str.h`):
+//!
+//! ```ignore
+//! #[no_mangle]
+//! extern "C" fn __crubit_thunk_foo(s: ::cow_bridge::CowStrRawParts) -> ::cow_bridge::CowStrRawParts {
+//!     ::cow_bridge::CowStrRawParts::from_cow(::some_crate::foo(unsafe { s.into_cow() }))
+//! }
+//! ```
+//!
+//! The crate only depends on `alloc`, so that it can also be used by the
+//! bindings of `#![no_std]` crates.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::mem::ManuallyDrop;
+
+/// The raw parts of a `Cow<'_, str>`.  Only used by the generated bindings.
+///
+/// SAFETY: the layout below has to match the layout of
+/// `rs_std::internal::CowStrRawParts` in `support/rs_std/rs_cow_str.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct CowStrRawParts {
+    data: *mut u8,
+    size: usize,
+    capacity: usize,
+    is_owned: bool,
+}
+
+impl CowStrRawParts {
+    /// Converts `cow` into its raw parts.  The ownership of an owned string is
+    /// transferred to the `CowStrRawParts` (which doesn't free the string when
+    /// dropped).
+    pub fn from_cow(cow: Cow<'_, str>) -> Self {
+        match cow {
+            Cow::Borrowed(s) => {
+                Self { data: s.as_ptr() as *mut u8, size: s.len(), capacity: 0, is_owned: false }
+            }
+            Cow::Owned(s) => {
+                let mut s = ManuallyDrop::new(s);
+                Self { data: s.as_mut_ptr(), size: s.len(), capacity: s.capacity(), is_owned: true }
+            }
+        }
+    }
+
+    /// Converts the raw parts back into a `Cow<'a, str>`.
+    ///
+    /// # Safety
+    ///
+    /// The raw parts have to come from `from_cow` or from
+    /// `rs_std::CowStr::release` in C++.  Additionally, the bytes of a borrowed
+    /// string have to stay alive (and must not be mutated) for `'a`.
+    pub unsafe fn into_cow<'a>(self) -> Cow<'a, str> {
+        if !self.is_owned {
+            let bytes = core::slice::from_raw_parts(self.data as *const u8, self.size);
+            Cow::Borrowed(core::str::from_utf8_unchecked(bytes))
+        } else if self.capacity == 0 {
+            // `rs_std::String` may use a null `data` pointer when it doesn't
+            // own an allocation (while Rust requires a non-null, dangling
+            // pointer in this case).
+            Cow::Owned(String::new())
+        } else {
+            Cow::Owned(String::from_raw_parts(self.data, self.size, self.capacity))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borrowed_round_trip() {
+        let s = "foo";
+        let parts = CowStrRawParts::from_cow(Cow::Borrowed(s));
+        assert!(!parts.is_owned);
+        assert_eq!(parts.data as *const u8, s.as_ptr());
+        let cow = unsafe { parts.into_cow() };
+        assert!(matches!(cow, Cow::Borrowed("foo")));
+    }
+
+    #[test]
+    fn test_owned_round_trip() {
+        let s = String::from("foo");
+        let data = s.as_ptr();
+        let parts = CowStrRawParts::from_cow(Cow::Owned(s));
+        assert!(parts.is_owned);
+        assert_eq!(parts.size, 3);
+        let cow = unsafe { parts.into_cow() };
+        assert_eq!(cow.as_ptr(), data);
+        assert!(matches!(cow, Cow::Owned(s) if s == "foo"));
+    }
+
+    #[test]
+    fn test_owned_without_allocation() {
+        let parts =
+            CowStrRawParts { data: std::ptr::null_mut(), size: 0, capacity: 0, is_owned: true };
+        let cow = unsafe { parts.into_cow() };
+        assert!(matches!(cow, Cow::Owned(s) if s.is_empty()));
+    }
+}
@@ -0,0 +1,111 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! Runtime support for passing lock guards (e.g. `MutexGuard<'_, T>`) from
+//! Rust to C++.
+//!
+//! The `extern "C"` thunks generated by `cc_bindings_from_rs` move the guard
+//! into a heap allocation and return `LockGuardRawParts` - the C++ side of the
+//! bindings wraps them into an RAII `rs_std::LockGuard<T>` (see
+//! `support/rs_std/rs_lock_guard.h`), whose destructor releases the lock by
+//! calling back into Rust:
+//!
+//! ```ignore
+//! #[no_mangle]
+//! extern "C" fn __crubit_thunk_lock(__self: &'static ::some_crate::Counter) -> ::lock_bridge::LockGuardRawParts {
+//!     ::lock_bridge::LockGuardRawParts::new_mut(::some_crate::Counter::lock(__self))
+//! }
+//! ```
+
+use std::ffi::c_void;
+use std::ops::{Deref, DerefMut};
+
+/// The raw parts of a lock guard.  Only used by the generated bindings.
+///
+/// SAFETY: the layout below has to match the layout of
+/// `rs_std::internal::LockGuardRawParts` in `support/rs_std/rs_lock_guard.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct LockGuardRawParts {
+    /// Pointer to the value protected by the lock.
+    data: *mut c_void,
+
+    /// Pointer to the heap-allocated guard.
+    guard: *mut c_void,
+
+    /// Drops the guard (releasing the lock) and frees its heap allocation.
+    release: unsafe extern "C" fn(guard: *mut c_void),
+}
+
+impl LockGuardRawParts {
+    /// Converts a guard that provides shared access to the protected value
+    /// (e.g. `RwLockReadGuard<'_, T>`) into its raw parts.  The lock is held
+    /// until C++ calls `release`.
+    pub fn new<G: Deref>(guard: G) -> Self
+    where
+        G::Target: Sized,
+    {
+        let data = &*guard as *const G::Target as *mut c_void;
+        Self::from_guard_and_data(guard, data)
+    }
+
+    /// Converts a guard that provides exclusive access to the protected value
+    /// (e.g. `MutexGuard<'_, T>` or `RwLockWriteGuard<'_, T>`) into its raw
+    /// parts.  The lock is held until C++ calls `release`.
+    pub fn new_mut<G: DerefMut>(mut guard: G) -> Self
+    where
+        G::Target: Sized,
+    {
+        let data = &mut *guard as *mut G::Target as *mut c_void;
+        Self::from_guard_and_data(guard, data)
+    }
+
+    fn from_guard_and_data<G>(guard: G, data: *mut c_void) -> Self {
+        // The protected value lives in the lock (not in the guard), and
+        // therefore `data` stays valid after moving the guard into a `Box`.
+        let guard = Box::into_raw(Box::new(guard)) as *mut c_void;
+        Self { data, guard, release: release::<G> }
+    }
+}
+
+/// # Safety
+///
+/// `guard` has to come from `LockGuardRawParts::from_guard_and_data::<G>`, and
+/// `release` may only be called once for a given `guard`.  Guards that are not
+/// `Send` (e.g. `MutexGuard`) have to be released on the thread that acquired
+/// the lock.
+unsafe extern "C" fn release<G>(guard: *mut c_void) {
+    drop(Box::from_raw(guard as *mut G));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, RwLock};
+
+    #[test]
+    fn test_mutex_guard() {
+        let mutex = Mutex::new(123);
+        let parts = LockGuardRawParts::new_mut(mutex.lock().unwrap());
+        assert!(mutex.try_lock().is_err());
+        unsafe {
+            *(parts.data as *mut i32) = 456;
+            (parts.release)(parts.guard);
+        }
+        assert_eq!(456, *mutex.try_lock().unwrap());
+    }
+
+    #[test]
+    fn test_rwlock_read_guard() {
+        let rwlock = RwLock::new(123);
+        let parts = LockGuardRawParts::new(rwlock.read().unwrap());
+        assert!(rwlock.try_write().is_err());
+        assert!(rwlock.try_read().is_ok());
+        unsafe {
+            assert_eq!(123, *(parts.data as *const i32));
+            (parts.release)(parts.guard);
+        }
+        assert!(rwlock.try_write().is_ok());
+    }
+}
@@ -0,0 +1,155 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! Runtime support for reporting Rust panics to C++ callers.
+//!
+//! Unwinding a Rust panic out of an `extern "C"` function aborts the process,
+//! and unwinding it into C++ frames is not supported either.  Instead, the
+//! `extern "C"` thunks generated by `cc_bindings_from_rs` can catch the panic
+//! with `catch_panic` and report it through a `PanicSlot` - the C++ side of the
+//! bindings then throws `rs_std::Panic` (see `support/rs_std/rs_panic.h`):
+//!
+//! ```ignore
+//! #[no_mangle]
+//! extern "C" fn __crubit_thunk_foo(x: i32, __panic: &mut ::panic_bridge::PanicSlot) -> i32 {
+//!     ::panic_bridge::catch_panic(__panic, || ::some_crate::foo(x)).unwrap_or_default()
+//! }
+//! ```
+
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// The slot where the generated Rust thunks store the message of a caught
+/// panic (if any).  Only used by the generated bindings.
+///
+/// SAFETY: the layout below has to match the layout of
+/// `rs_std::internal::PanicSlot` in `support/rs_std/rs_panic.h`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct PanicSlot {
+    panicked: bool,
+    message_len: usize,
+    message: [u8; PanicSlot::MAX_MESSAGE_LEN],
+}
+
+impl PanicSlot {
+    /// The maximum length of the preserved panic message (longer messages are
+    /// truncated at a `char` boundary).
+    pub const MAX_MESSAGE_LEN: usize = 255;
+
+    pub fn new() -> Self {
+        Self { panicked: false, message_len: 0, message: [0; Self::MAX_MESSAGE_LEN] }
+    }
+
+    /// Returns whether a panic has been stored in the slot.
+    pub fn panicked(&self) -> bool {
+        self.panicked
+    }
+
+    /// Returns the (possibly truncated) message of the stored panic.
+    pub fn message(&self) -> &str {
+        let bytes = &self.message[..self.message_len.min(Self::MAX_MESSAGE_LEN)];
+        // `store` only ever copies whole `char`s into `message`.
+        std::str::from_utf8(bytes).unwrap_or_default()
+    }
+
+    /// Stores the message of the panic with the given `payload` (as returned by
+    /// `std::panic::catch_unwind`).
+    pub fn store(&mut self, payload: &(dyn Any + Send)) {
+        let message = payload_message(payload);
+        let mut len = message.len().min(Self::MAX_MESSAGE_LEN);
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.panicked = true;
+        self.message_len = len;
+        self.message[..len].copy_from_slice(&message.as_bytes()[..len]);
+    }
+}
+
+impl Default for PanicSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the message of the panic with the given `payload`.
+///
+/// `panic!` payloads are either `&'static str` (for panics without formatting
+/// arguments) or `String`.  Other payloads (e.g. ones passed to
+/// `std::panic::panic_any`) are described the same way as by the default
+/// panic hook.
+pub fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+/// Calls `f`, catching the panic (if any) and storing it in `slot`.  Returns
+/// `None` if `f` has panicked.
+///
+/// The arguments of the wrapped function come from C++ and are not observed by
+/// Rust after the panic, which is why `f` doesn't need to be `UnwindSafe`.
+pub fn catch_panic<R>(slot: &mut PanicSlot, f: impl FnOnce() -> R) -> Option<R> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            slot.store(&*payload);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panic_without_panic() {
+        let mut slot = PanicSlot::new();
+        assert_eq!(catch_panic(&mut slot, || 42), Some(42));
+        assert!(!slot.panicked());
+        assert_eq!(slot.message(), "");
+    }
+
+    #[test]
+    fn test_catch_panic_with_str_payload() {
+        let mut slot = PanicSlot::new();
+        assert_eq!(catch_panic(&mut slot, || -> i32 { panic!("boom") }), None);
+        assert!(slot.panicked());
+        assert_eq!(slot.message(), "boom");
+    }
+
+    #[test]
+    fn test_catch_panic_with_string_payload() {
+        let mut slot = PanicSlot::new();
+        let x = 123;
+        assert_eq!(catch_panic(&mut slot, || -> i32 { panic!("x = {x}") }), None);
+        assert!(slot.panicked());
+        assert_eq!(slot.message(), "x = 123");
+    }
+
+    #[test]
+    fn test_catch_panic_with_other_payload() {
+        let mut slot = PanicSlot::new();
+        assert_eq!(catch_panic(&mut slot, || -> i32 { std::panic::panic_any(123) }), None);
+        assert!(slot.panicked());
+        assert_eq!(slot.message(), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn test_long_message_is_truncated_at_char_boundary() {
+        // 'ż' is encoded as 2 bytes, and therefore `MAX_MESSAGE_LEN` (an odd
+        // number) falls in the middle of the last character.
+        let message = "ż".repeat(PanicSlot::MAX_MESSAGE_LEN);
+        let mut slot = PanicSlot::new();
+        slot.store(&message);
+        assert!(slot.panicked());
+        assert_eq!(slot.message(), "ż".repeat(PanicSlot::MAX_MESSAGE_LEN / 2));
+    }
+}
@@ -0,0 +1,95 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_BOX_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_BOX_H_
+
+#include <memory>
+#include <new>
+#include <type_traits>
+#include <utility>
+
+#include "support/rs_std/rust_allocator.h"
+
+namespace rs_std {
+
+// `rs_std::Box<T>` is a C++ representation of the `Box<T>` type from Rust: a
+// pointer to a Rust heap allocation with unique ownership.  When the
+// `rs_std::Box<T>` is destroyed, it drops the pointee (which runs the Rust drop
+// glue through the destructor of the C++ struct generated by
+// `cc_bindings_from_rs` for the Rust type - if any) and then frees the
+// allocation via the Rust allocator.
+//
+// `Box<T>` from Rust is never null, but a moved-from (or `release`d)
+// `rs_std::Box<T>` is.
+//
+// Rust guarantees that `Box<T>` (for a `Sized` `T`) has the same ABI as a
+// pointer (see https://doc.rust-lang.org/std/boxed/index.html#memory-layout),
+// and therefore `rs_std::Box<T>` has the same layout as `T*`.
+template <typename T>
+class Box final {
+  static_assert(!std::is_reference_v<T> && !std::is_array_v<T>);
+
+ public:
+  // Allocates memory on the Rust heap and then moves `value` into it.
+  //
+  // This function mimics Rust's `Box::new`:
+  // https://doc.rust-lang.org/std/boxed/struct.Box.html#method.new
+  static Box make(T value) {
+    void* ptr = internal::RustAllocOrDie(sizeof(T), alignof(T));
+    return Box(new (ptr) T(std::move(value)));
+  }
+
+  // Takes ownership of `ptr` (e.g. of a pointer returned by `Box::into_raw`
+  // in Rust).
+  //
+  // `ptr` has to point to an initialized `T` in a Rust heap allocation
+  // (allocated with the layout of `T`).
+  //
+  // This function mimics Rust's `Box::from_raw`:
+  // https://doc.rust-lang.org/std/boxed/struct.Box.html#method.from_raw
+  //
+  // TODO(b/254095482): Figure out how to annotate/expose unsafe functions in
+  // C++.
+  static Box from_raw(T* ptr) { return Box(ptr); }
+
+  Box(const Box&) = delete;
+  Box& operator=(const Box&) = delete;
+
+  Box(Box&& other) noexcept : ptr_(std::exchange(other.ptr_, nullptr)) {}
+  Box& operator=(Box&& other) noexcept {
+    if (this != &other) {
+      Box old = std::move(*this);
+      ptr_ = std::exchange(other.ptr_, nullptr);
+    }
+    return *this;
+  }
+
+  ~Box() {
+    if (ptr_ == nullptr) return;
+    std::destroy_at(ptr_);
+    __rust_dealloc(ptr_, sizeof(T), alignof(T));
+  }
+
+  T* get() const { return ptr_; }
+  T& operator*() const { return *ptr_; }
+  T* operator->() const { return ptr_; }
+
+  // Releases the ownership of the pointee (so that it can be passed back to
+  // Rust - see `Box::from_raw` in Rust).  The `rs_std::Box<T>` is left null.
+  //
+  // This function mimics Rust's `Box::into_raw`:
+  // https://doc.rust-lang.org/std/boxed/struct.Box.html#method.into_raw
+  T* release() && { return std::exchange(ptr_, nullptr); }
+  T* into_raw() && { return std::move(*this).release(); }
+
+ private:
+  explicit Box(T* ptr) : ptr_(ptr) {}
+
+  T* ptr_ = nullptr;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_BOX_H_
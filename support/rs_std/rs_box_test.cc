@@ -0,0 +1,88 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_box.h"
+
+#include <cstdint>
+#include <type_traits>
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "support/rs_std/rust_allocator_for_testing.h"
+
+namespace {
+
+using ::rs_std::testing::GetLiveRustAllocations;
+
+static_assert(sizeof(rs_std::Box<int32_t>) == sizeof(int32_t*));
+static_assert(alignof(rs_std::Box<int32_t>) == alignof(int32_t*));
+static_assert(!std::is_copy_constructible_v<rs_std::Box<int32_t>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::Box<int32_t>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::Box<int32_t>>);
+
+// Counts the live instances, so that the tests can verify that dropping a
+// `Box<T>` destroys the pointee.
+struct Counted {
+  explicit Counted(int value) : value(value) { ++live_instances; }
+  Counted(Counted&& other) : value(other.value) { ++live_instances; }
+  ~Counted() { --live_instances; }
+  static int live_instances;
+  int value;
+};
+int Counted::live_instances = 0;
+
+TEST(RsBoxTest, Make) {
+  {
+    rs_std::Box<int32_t> b = rs_std::Box<int32_t>::make(123);
+    EXPECT_EQ(123, *b);
+    *b = 456;
+    EXPECT_EQ(456, *b.get());
+    EXPECT_EQ(1, GetLiveRustAllocations());
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsBoxTest, DropDestroysPointee) {
+  {
+    rs_std::Box<Counted> b = rs_std::Box<Counted>::make(Counted(42));
+    EXPECT_EQ(42, b->value);
+    EXPECT_EQ(1, Counted::live_instances);
+  }
+  EXPECT_EQ(0, Counted::live_instances);
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsBoxTest, Move) {
+  {
+    rs_std::Box<int32_t> b1 = rs_std::Box<int32_t>::make(1);
+    rs_std::Box<int32_t> b2 = std::move(b1);
+    EXPECT_EQ(nullptr, b1.get());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ(1, *b2);
+
+    b1 = rs_std::Box<int32_t>::make(2);
+    EXPECT_EQ(2, GetLiveRustAllocations());
+    b2 = std::move(b1);
+    EXPECT_EQ(1, GetLiveRustAllocations());
+    EXPECT_EQ(2, *b2);
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsBoxTest, RawRoundTrip) {
+  rs_std::Box<Counted> b1 = rs_std::Box<Counted>::make(Counted(7));
+  Counted* ptr = std::move(b1).into_raw();
+  EXPECT_EQ(nullptr, b1.get());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1, Counted::live_instances);
+  EXPECT_EQ(1, GetLiveRustAllocations());
+
+  {
+    rs_std::Box<Counted> b2 = rs_std::Box<Counted>::from_raw(ptr);
+    EXPECT_EQ(7, b2->value);
+  }
+  EXPECT_EQ(0, Counted::live_instances);
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+}  // namespace
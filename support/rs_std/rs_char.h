@@ -5,8 +5,10 @@
 #ifndef CRUBIT_SUPPORT_RS_STD_CHAR_H_
 #define CRUBIT_SUPPORT_RS_STD_CHAR_H_
 
+#include <cstddef>
 #include <cstdint>
 #include <optional>
+#include <string_view>
 
 #include "absl/base/optimization.h"
 
@@ -62,6 +64,54 @@ class rs_char final {
   ~rs_char() = default;
 
   explicit constexpr operator std::uint32_t() const { return value_; }
+  explicit constexpr operator char32_t() const { return value_; }
+
+  // Returns the number of bytes this `rs_char` would need if encoded in UTF-8
+  // (always between 1 and 4).
+  //
+  // This function mimics Rust's `char::len_utf8`:
+  // https://doc.rust-lang.org/std/primitive.char.html#method.len_utf8
+  constexpr size_t len_utf8() const {
+    if (value_ < 0x80) return 1;
+    if (value_ < 0x800) return 2;
+    if (value_ < 0x10000) return 3;
+    return 4;
+  }
+
+  // Encodes this `rs_char` as UTF-8 into `dst`, and then returns a view of the
+  // first `len_utf8()` bytes of `dst` that contain the encoded character.
+  //
+  // This function mimics Rust's `char::encode_utf8` (4 bytes are always enough
+  // to encode a `char`, and therefore - unlike the Rust function - this
+  // function can't fail because of a buffer that is too small):
+  // https://doc.rust-lang.org/std/primitive.char.html#method.encode_utf8
+  constexpr std::string_view encode_utf8(char (&dst)[4]) const {
+    const auto byte = [](std::uint32_t bits) {
+      return static_cast<char>(bits);
+    };
+    const size_t len = len_utf8();
+    switch (len) {
+      case 1:
+        dst[0] = byte(value_);
+        break;
+      case 2:
+        dst[0] = byte(0xC0 | (value_ >> 6));
+        dst[1] = byte(0x80 | (value_ & 0x3F));
+        break;
+      case 3:
+        dst[0] = byte(0xE0 | (value_ >> 12));
+        dst[1] = byte(0x80 | ((value_ >> 6) & 0x3F));
+        dst[2] = byte(0x80 | (value_ & 0x3F));
+        break;
+      default:
+        dst[0] = byte(0xF0 | (value_ >> 18));
+        dst[1] = byte(0x80 | ((value_ >> 12) & 0x3F));
+        dst[2] = byte(0x80 | ((value_ >> 6) & 0x3F));
+        dst[3] = byte(0x80 | (value_ & 0x3F));
+        break;
+    }
+    return std::string_view(dst, len);
+  }
 
   constexpr bool operator==(const rs_char& other) const {
     return value_ == other.value_;
@@ -6,6 +6,8 @@
 
 #include <stdint.h>
 
+#include <optional>
+#include <string_view>
 #include <type_traits>
 
 #include "gmock/gmock.h"
@@ -162,4 +164,39 @@ TEST(RsCharTest, DefaultConstructedValue) {
   EXPECT_EQ(0, uint32_t{c});
 }
 
+TEST(RsCharTest, ToChar32) {
+  std::optional<const rs_std::rs_char> c = rs_std::rs_char::from_u32(U'🦀');
+  ASSERT_TRUE(c.has_value());
+  EXPECT_EQ(U'🦀', char32_t{*c});
+}
+
+TEST(RsCharTest, LenUtf8) {
+  // Boundaries between encodings of different lengths (see the table in
+  // https://en.wikipedia.org/wiki/UTF-8#Encoding).
+  EXPECT_EQ(1, rs_std::rs_char::from_u32(0x00)->len_utf8());
+  EXPECT_EQ(1, rs_std::rs_char::from_u32(0x7f)->len_utf8());
+  EXPECT_EQ(2, rs_std::rs_char::from_u32(0x80)->len_utf8());
+  EXPECT_EQ(2, rs_std::rs_char::from_u32(0x7ff)->len_utf8());
+  EXPECT_EQ(3, rs_std::rs_char::from_u32(0x800)->len_utf8());
+  EXPECT_EQ(3, rs_std::rs_char::from_u32(0xffff)->len_utf8());
+  EXPECT_EQ(4, rs_std::rs_char::from_u32(0x10000)->len_utf8());
+  EXPECT_EQ(4, rs_std::rs_char::MAX.len_utf8());
+}
+
+TEST(RsCharTest, EncodeUtf8) {
+  char buf[4];
+  EXPECT_EQ("x", rs_std::rs_char::from_u32(U'x')->encode_utf8(buf));
+  EXPECT_EQ("\xC5\x81", rs_std::rs_char::from_u32(U'Ł')->encode_utf8(buf));
+  EXPECT_EQ("\xE7\x8C\xAB",
+            rs_std::rs_char::from_u32(U'猫')->encode_utf8(buf));
+  EXPECT_EQ("\xF0\x9F\xA6\x80",
+            rs_std::rs_char::from_u32(U'🦀')->encode_utf8(buf));
+  EXPECT_EQ("\xF4\x8F\xBF\xBF", rs_std::rs_char::MAX.encode_utf8(buf));
+
+  // The returned `std::string_view` points into `buf`.
+  std::string_view encoded = rs_std::rs_char::from_u32(U'Ł')->encode_utf8(buf);
+  EXPECT_EQ(buf, encoded.data());
+  EXPECT_EQ(2, encoded.size());
+}
+
 }  // namespace
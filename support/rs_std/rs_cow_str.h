@@ -0,0 +1,130 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_COW_STR_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_COW_STR_H_
+
+#include <cstddef>
+#include <string_view>
+#include <utility>
+#include <variant>
+
+#include "support/rs_std/rs_str.h"
+#include "support/rs_std/rs_string.h"
+
+namespace rs_std {
+
+namespace internal {
+
+// The raw parts of a Rust `Cow<'_, str>`.  `data` and `size` describe the
+// bytes of the string, and (only when `is_owned` is true) `capacity` describes
+// the Rust allocation that owns them.
+//
+// The layout of `CowStrRawParts` has to match the layout of
+// `cow_bridge::CowStrRawParts` in Rust (see `support/rs_std/cow_bridge.rs`).
+struct CowStrRawParts {
+  char* data;
+  size_t size;
+  size_t capacity;
+  bool is_owned;
+};
+
+}  // namespace internal
+
+// `rs_std::CowStr` is a C++ representation of Rust's `Cow<'_, str>`: either a
+// borrowed string slice (`rs_std::Str` - the bytes are owned by somebody else
+// and have to outlive the `rs_std::CowStr`), or an owned string
+// (`rs_std::String`).
+//
+// The layout of Rust's `Cow<'_, str>` is not guaranteed, and therefore the
+// generated bindings convert between `Cow<'_, str>` and `rs_std::CowStr` by
+// passing the raw parts of the string (see `from_raw_parts` and `release`).
+class CowStr final {
+ public:
+  // Creates an empty, borrowed string.
+  //
+  // This constructor mimics `Cow::default` in Rust.
+  CowStr() = default;
+
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  CowStr(Str borrowed) : value_(borrowed) {}
+
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  CowStr(String owned) : value_(std::move(owned)) {}
+
+  CowStr(const CowStr&) = delete;
+  CowStr& operator=(const CowStr&) = delete;
+  CowStr(CowStr&&) noexcept = default;
+  CowStr& operator=(CowStr&&) noexcept = default;
+  ~CowStr() = default;
+
+  // Takes ownership of the raw parts of a Rust `Cow<'_, str>` (see `release`
+  // and `cow_bridge::CowStrRawParts::from_cow` in Rust).
+  static CowStr from_raw_parts(internal::CowStrRawParts parts) {
+    if (!parts.is_owned) {
+      return Str::from_utf8_unchecked(std::string_view(parts.data, parts.size));
+    }
+    return String::from_raw_parts(parts.data, parts.size, parts.capacity);
+  }
+
+  bool is_borrowed() const { return std::holds_alternative<Str>(value_); }
+  bool is_owned() const { return std::holds_alternative<String>(value_); }
+
+  // Borrows the bytes of the string (mimics the `Deref` implementation of
+  // Rust's `Cow`).
+  Str as_str() const {
+    if (const Str* borrowed = std::get_if<Str>(&value_)) return *borrowed;
+    return std::get<String>(value_);
+  }
+
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  operator std::string_view() const { return as_str(); }
+
+  const char* data() const { return as_str().data(); }
+  size_t size() const { return as_str().size(); }
+  bool empty() const { return size() == 0; }
+
+  // Returns the owned string, copying the bytes of a borrowed string into a
+  // new `rs_std::String`.
+  //
+  // This function mimics Rust's `Cow::into_owned`:
+  // https://doc.rust-lang.org/std/borrow/enum.Cow.html#method.into_owned
+  String into_owned() && {
+    if (String* owned = std::get_if<String>(&value_)) return std::move(*owned);
+    // The bytes of `Str` are always valid UTF-8.
+    return *String::from_utf8(std::get<Str>(value_));
+  }
+
+  // Releases the ownership of the string (if owned) so that it can be passed
+  // to Rust (see `cow_bridge::CowStrRawParts::into_cow` in Rust).  The
+  // `rs_std::CowStr` is left empty.
+  internal::CowStrRawParts release() && {
+    CowStr old = std::move(*this);
+    value_ = Str();
+    if (String* owned = std::get_if<String>(&old.value_)) {
+      String::RawParts parts = std::move(*owned).release();
+      return internal::CowStrRawParts{parts.data, parts.size, parts.capacity,
+                                      /* is_owned= */ true};
+    }
+    Str borrowed = std::get<Str>(old.value_);
+    // Rust doesn't mutate the bytes of a borrowed `Cow`.
+    return internal::CowStrRawParts{const_cast<char*>(borrowed.data()),
+                                    borrowed.size(), /* capacity= */ 0,
+                                    /* is_owned= */ false};
+  }
+
+  bool operator==(std::string_view other) const {
+    return std::string_view(*this) == other;
+  }
+  bool operator!=(std::string_view other) const {
+    return std::string_view(*this) != other;
+  }
+
+ private:
+  std::variant<Str, String> value_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_COW_STR_H_
@@ -0,0 +1,99 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_cow_str.h"
+
+#include <string_view>
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+#include "support/rs_std/rs_str.h"
+#include "support/rs_std/rs_string.h"
+#include "support/rs_std/rust_allocator_for_testing.h"
+
+namespace {
+
+using ::rs_std::testing::GetLiveRustAllocations;
+
+static_assert(!std::is_copy_constructible_v<rs_std::CowStr>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::CowStr>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::CowStr>);
+
+TEST(RsCowStrTest, Default) {
+  rs_std::CowStr s;
+  EXPECT_TRUE(s.is_borrowed());
+  EXPECT_TRUE(s.empty());
+  EXPECT_EQ(s, "");
+}
+
+TEST(RsCowStrTest, Borrowed) {
+  rs_std::Str str = *rs_std::Str::from_utf8("foo");
+  rs_std::CowStr s = str;
+  EXPECT_TRUE(s.is_borrowed());
+  EXPECT_FALSE(s.is_owned());
+  EXPECT_EQ(str.data(), s.data());
+  EXPECT_EQ(s, "foo");
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsCowStrTest, Owned) {
+  {
+    rs_std::CowStr s = *rs_std::String::from_utf8("foo");
+    EXPECT_TRUE(s.is_owned());
+    EXPECT_FALSE(s.is_borrowed());
+    EXPECT_EQ(s, "foo");
+    EXPECT_EQ(1, GetLiveRustAllocations());
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsCowStrTest, IntoOwned) {
+  {
+    rs_std::CowStr borrowed = *rs_std::Str::from_utf8("foo");
+    rs_std::String s1 = std::move(borrowed).into_owned();
+    EXPECT_EQ(s1, "foo");
+    EXPECT_EQ(1, GetLiveRustAllocations());
+
+    rs_std::CowStr owned = *rs_std::String::from_utf8("bar");
+    const char* data = owned.data();
+    rs_std::String s2 = std::move(owned).into_owned();
+    EXPECT_EQ(data, s2.data());  // No copying.
+    EXPECT_EQ(2, GetLiveRustAllocations());
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsCowStrTest, BorrowedRawPartsRoundTrip) {
+  rs_std::Str str = *rs_std::Str::from_utf8("foo");
+  rs_std::CowStr s1 = str;
+  rs_std::internal::CowStrRawParts parts = std::move(s1).release();
+  EXPECT_FALSE(parts.is_owned);
+  EXPECT_EQ(str.data(), parts.data);
+  EXPECT_EQ(3, parts.size);
+  EXPECT_TRUE(s1.empty());  // NOLINT(bugprone-use-after-move)
+
+  rs_std::CowStr s2 = rs_std::CowStr::from_raw_parts(parts);
+  EXPECT_TRUE(s2.is_borrowed());
+  EXPECT_EQ(s2, "foo");
+}
+
+TEST(RsCowStrTest, OwnedRawPartsRoundTrip) {
+  rs_std::CowStr s1 = *rs_std::String::from_utf8("foo");
+  rs_std::internal::CowStrRawParts parts = std::move(s1).release();
+  EXPECT_TRUE(parts.is_owned);
+  EXPECT_EQ(3, parts.size);
+  EXPECT_EQ(3, parts.capacity);
+  EXPECT_TRUE(s1.empty());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1, GetLiveRustAllocations());
+
+  {
+    rs_std::CowStr s2 = rs_std::CowStr::from_raw_parts(parts);
+    EXPECT_TRUE(s2.is_owned());
+    EXPECT_EQ(s2, "foo");
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+}  // namespace
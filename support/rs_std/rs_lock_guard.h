@@ -0,0 +1,94 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_LOCK_GUARD_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_LOCK_GUARD_H_
+
+#include <type_traits>
+#include <utility>
+
+namespace rs_std {
+
+namespace internal {
+
+// The raw parts of a Rust lock guard (e.g. `MutexGuard<'_, T>`): a pointer to
+// the value protected by the lock, a pointer to the heap-allocated guard, and
+// a Rust function that drops the guard (releasing the lock).
+//
+// The layout of `LockGuardRawParts` has to match the layout of
+// `lock_bridge::LockGuardRawParts` in Rust (see
+// `support/rs_std/lock_bridge.rs`).
+struct LockGuardRawParts {
+  void* data;
+  void* guard;
+  void (*release)(void* guard);
+};
+
+}  // namespace internal
+
+// `rs_std::LockGuard<T>` is an RAII C++ representation of a Rust lock guard
+// returned by the bindings of a Rust function:
+// - `MutexGuard<'_, T>` and `RwLockWriteGuard<'_, T>` map to
+//   `rs_std::LockGuard<T>` (exclusive access to the protected value),
+// - `RwLockReadGuard<'_, T>` maps to `rs_std::LockGuard<const T>` (shared
+//   access to the protected value).
+//
+// The lock is held until the `LockGuard` is destroyed (or until `unlock` is
+// called) - this also happens during stack unwinding, so the locking APIs are
+// exception-safe.  The `LockGuard` must not outlive the Rust lock, and (like
+// `MutexGuard` in Rust) it has to be destroyed on the thread that acquired the
+// lock.
+template <typename T>
+class [[nodiscard]] LockGuard final {
+  static_assert(!std::is_reference_v<T>);
+
+ public:
+  // Takes ownership of the raw parts of a Rust lock guard (see
+  // `lock_bridge::LockGuardRawParts` in Rust).
+  static LockGuard from_raw_parts(internal::LockGuardRawParts parts) {
+    return LockGuard(parts);
+  }
+
+  LockGuard(const LockGuard&) = delete;
+  LockGuard& operator=(const LockGuard&) = delete;
+
+  LockGuard(LockGuard&& other) noexcept
+      : parts_(std::exchange(other.parts_, internal::LockGuardRawParts{})) {}
+  LockGuard& operator=(LockGuard&& other) noexcept {
+    if (this != &other) {
+      unlock();
+      parts_ = std::exchange(other.parts_, internal::LockGuardRawParts{});
+    }
+    return *this;
+  }
+
+  ~LockGuard() { unlock(); }
+
+  // Releases the lock before the `LockGuard` is destroyed (mimics
+  // `drop(guard)` in Rust).  The protected value may not be accessed through
+  // this `LockGuard` afterwards.
+  void unlock() {
+    if (parts_.guard != nullptr) {
+      parts_.release(parts_.guard);
+      parts_ = internal::LockGuardRawParts{};
+    }
+  }
+
+  // Returns whether the lock is still held (i.e. whether `unlock` hasn't been
+  // called, and the `LockGuard` hasn't been moved from).
+  bool owns_lock() const { return parts_.guard != nullptr; }
+
+  T* get() const { return static_cast<T*>(parts_.data); }
+  T& operator*() const { return *get(); }
+  T* operator->() const { return get(); }
+
+ private:
+  explicit LockGuard(internal::LockGuardRawParts parts) : parts_(parts) {}
+
+  internal::LockGuardRawParts parts_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_LOCK_GUARD_H_
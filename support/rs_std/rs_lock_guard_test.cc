@@ -0,0 +1,88 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_lock_guard.h"
+
+#include <stdexcept>
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+static_assert(!std::is_copy_constructible_v<rs_std::LockGuard<int>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::LockGuard<int>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::LockGuard<int>>);
+
+// A fake lock: `guard` points at the number of held locks.
+void ReleaseFakeLock(void* guard) { --*static_cast<int*>(guard); }
+
+template <typename T>
+rs_std::LockGuard<T> AcquireFakeLock(int& lock_count, T& value) {
+  ++lock_count;
+  return rs_std::LockGuard<T>::from_raw_parts(
+      {const_cast<std::remove_const_t<T>*>(&value), &lock_count,
+       &ReleaseFakeLock});
+}
+
+TEST(RsLockGuardTest, ReleasesLockInDestructor) {
+  int lock_count = 0;
+  int value = 123;
+  {
+    rs_std::LockGuard<int> guard = AcquireFakeLock(lock_count, value);
+    EXPECT_EQ(1, lock_count);
+    EXPECT_TRUE(guard.owns_lock());
+    EXPECT_EQ(123, *guard);
+    *guard = 456;
+  }
+  EXPECT_EQ(0, lock_count);
+  EXPECT_EQ(456, value);
+}
+
+TEST(RsLockGuardTest, SharedAccess) {
+  int lock_count = 0;
+  const int value = 123;
+  rs_std::LockGuard<const int> guard = AcquireFakeLock(lock_count, value);
+  static_assert(std::is_same_v<decltype(*guard), const int&>);
+  EXPECT_EQ(123, *guard);
+}
+
+TEST(RsLockGuardTest, Unlock) {
+  int lock_count = 0;
+  int value = 123;
+  rs_std::LockGuard<int> guard = AcquireFakeLock(lock_count, value);
+  guard.unlock();
+  EXPECT_EQ(0, lock_count);
+  EXPECT_FALSE(guard.owns_lock());
+  guard.unlock();
+  EXPECT_EQ(0, lock_count);
+}
+
+TEST(RsLockGuardTest, Move) {
+  int lock_count = 0;
+  int value1 = 1;
+  int value2 = 2;
+  rs_std::LockGuard<int> guard1 = AcquireFakeLock(lock_count, value1);
+  rs_std::LockGuard<int> guard2 = std::move(guard1);
+  EXPECT_FALSE(guard1.owns_lock());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1, lock_count);
+
+  guard2 = AcquireFakeLock(lock_count, value2);
+  EXPECT_EQ(1, lock_count);
+  EXPECT_EQ(2, *guard2);
+}
+
+TEST(RsLockGuardTest, ReleasesLockWhenUnwinding) {
+  int lock_count = 0;
+  int value = 123;
+  try {
+    rs_std::LockGuard<int> guard = AcquireFakeLock(lock_count, value);
+    throw std::runtime_error("test");
+  } catch (const std::runtime_error&) {
+  }
+  EXPECT_EQ(0, lock_count);
+}
+
+}  // namespace
@@ -0,0 +1,47 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_NONNULL_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_NONNULL_H_
+
+#include <cstdio>
+#include <cstdlib>
+
+#include "absl/base/optimization.h"
+
+namespace rs_std {
+
+// `rs_std::NonNull<T>` is a C++ representation of the `NonNull<T>` type from
+// Rust: a raw `*mut T` pointer that is guaranteed to be non-null.  In C++ it is
+// a plain pointer with the `_Nonnull` annotation, which lets Clang (and static
+// analysis tools) flag passing a null pointer to a Rust API that takes
+// `NonNull<T>`.
+//
+// A null `NonNull<T>` would be Undefined Behavior in Rust, and the annotation
+// is not enforced at runtime - C++ callers that are not sure whether a pointer
+// is null should use `rs_std::CheckNonNull` below.
+//
+// `rust_builtin_type_abi_assumptions.md` documents the ABI compatibility of
+// `NonNull<T>` and `rs_std::NonNull<T>`.
+template <typename T>
+using NonNull = T* _Nonnull;
+
+// Returns `ptr` as a `rs_std::NonNull<T>`, or aborts the process if `ptr` is
+// null.  The check is done in all build modes (because a null `NonNull<T>`
+// would be Undefined Behavior in Rust).
+//
+// This function mimics `NonNull::new(ptr).unwrap()` in Rust:
+// https://doc.rust-lang.org/std/ptr/struct.NonNull.html#method.new
+template <typename T>
+NonNull<T> CheckNonNull(T* _Nullable ptr) {
+  if (ABSL_PREDICT_FALSE(ptr == nullptr)) {
+    std::fputs("rs_std::CheckNonNull: unexpected null pointer\n", stderr);
+    std::abort();
+  }
+  return ptr;
+}
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_NONNULL_H_
@@ -0,0 +1,39 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_nonnull.h"
+
+#include <cstdint>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// `rs_std::NonNull<T>` is a plain (annotated) pointer.  This is important,
+// because the generated bindings rely on `NonNull<T>` having the same layout
+// as `*mut T` in Rust.
+static_assert(std::is_same_v<std::remove_cv_t<rs_std::NonNull<std::int32_t>>,
+                             std::int32_t*>);
+static_assert(sizeof(rs_std::NonNull<std::int32_t>) == sizeof(std::int32_t*));
+
+TEST(RsNonNullTest, CheckNonNull) {
+  std::int32_t x = 123;
+  rs_std::NonNull<std::int32_t> ptr = rs_std::CheckNonNull(&x);
+  EXPECT_EQ(&x, ptr);
+  EXPECT_EQ(123, *ptr);
+}
+
+TEST(RsNonNullTest, CheckNonNullConst) {
+  const std::int32_t x = 123;
+  rs_std::NonNull<const std::int32_t> ptr = rs_std::CheckNonNull(&x);
+  EXPECT_EQ(&x, ptr);
+}
+
+TEST(RsNonNullDeathTest, CheckNonNullAbortsOnNull) {
+  std::int32_t* null_ptr = nullptr;
+  EXPECT_DEATH(rs_std::CheckNonNull(null_ptr), "unexpected null pointer");
+}
+
+}  // namespace
@@ -0,0 +1,81 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_NONZERO_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_NONZERO_H_
+
+#include <cstdint>
+#include <optional>
+#include <type_traits>
+
+#include "absl/base/optimization.h"
+
+namespace rs_std {
+
+// `rs_std::NonZero<T>` is a C++ representation of the `NonZero*` integer types
+// from Rust (e.g. `rs_std::NonZero<std::uint32_t>` represents Rust's
+// `NonZeroU32`).  Like in Rust, the value is guaranteed to be non-zero - a
+// zero `NonZeroU32` would be Undefined Behavior in Rust, and therefore
+// `rs_std::NonZero<T>` can only be created through the checked `from_int`.
+//
+// Rust uses the zero value as a niche, which means that `Option<NonZeroU32>`
+// has the same layout as `u32` (with 0 representing `None`).  The generated
+// bindings represent `Option<NonZero...>` as the plain C++ integer type.
+//
+// `rust_builtin_type_abi_assumptions.md` documents the ABI compatibility of
+// `NonZero*` types and `rs_std::NonZero<T>`.
+template <typename T>
+class NonZero final {
+  static_assert(std::is_integral_v<T> && !std::is_same_v<T, bool>);
+
+ public:
+  // There is no default constructor, because zero is not a valid value.
+  NonZero() = delete;
+
+  // Converts `value` into a `rs_std::NonZero<T>`, or returns `std::nullopt` if
+  // `value` is zero.
+  //
+  // This function mimics Rust's `NonZeroU32::new`:
+  // https://doc.rust-lang.org/std/num/struct.NonZeroU32.html#method.new
+  static constexpr std::optional<NonZero> from_int(T value) {
+    if (ABSL_PREDICT_FALSE(value == 0)) return std::nullopt;
+    return NonZero(value);
+  }
+
+  constexpr NonZero(const NonZero&) = default;
+  constexpr NonZero& operator=(const NonZero&) = default;
+
+  // Returns the (non-zero) value.
+  //
+  // This function mimics Rust's `NonZeroU32::get`:
+  // https://doc.rust-lang.org/std/num/struct.NonZeroU32.html#method.get
+  constexpr T get() const { return value_; }
+
+  friend constexpr bool operator==(NonZero lhs, NonZero rhs) {
+    return lhs.value_ == rhs.value_;
+  }
+  friend constexpr bool operator!=(NonZero lhs, NonZero rhs) {
+    return lhs.value_ != rhs.value_;
+  }
+
+ private:
+  explicit constexpr NonZero(T value) : value_(value) {}
+
+  T value_;
+};
+
+using NonZeroI8 = NonZero<std::int8_t>;
+using NonZeroI16 = NonZero<std::int16_t>;
+using NonZeroI32 = NonZero<std::int32_t>;
+using NonZeroI64 = NonZero<std::int64_t>;
+using NonZeroIsize = NonZero<std::intptr_t>;
+using NonZeroU8 = NonZero<std::uint8_t>;
+using NonZeroU16 = NonZero<std::uint16_t>;
+using NonZeroU32 = NonZero<std::uint32_t>;
+using NonZeroU64 = NonZero<std::uint64_t>;
+using NonZeroUsize = NonZero<std::uintptr_t>;
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_NONZERO_H_
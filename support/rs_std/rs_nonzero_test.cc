@@ -0,0 +1,53 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_nonzero.h"
+
+#include <cstdint>
+#include <optional>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// Check that `rs_std::NonZero<T>` is trivially destructible and copyable, but
+// isn't default-constructible (because zero is not a valid value).
+static_assert(std::is_trivially_destructible_v<rs_std::NonZeroU32>);
+static_assert(std::is_trivially_copyable_v<rs_std::NonZeroU32>);
+static_assert(!std::is_default_constructible_v<rs_std::NonZeroU32>);
+static_assert(!std::is_constructible_v<rs_std::NonZeroU32, std::uint32_t>);
+
+// Layout tests.  (`Option<NonZero...>` is represented as the plain integer
+// type, and therefore `NonZero<T>` has to have the same layout as `T`.)
+static_assert(sizeof(rs_std::NonZeroU8) == 1);
+static_assert(alignof(rs_std::NonZeroU8) == 1);
+static_assert(sizeof(rs_std::NonZeroI64) == sizeof(std::int64_t));
+static_assert(alignof(rs_std::NonZeroI64) == alignof(std::int64_t));
+static_assert(sizeof(rs_std::NonZeroUsize) == sizeof(std::uintptr_t));
+
+TEST(RsNonZeroTest, FromInt) {
+  constexpr std::optional<rs_std::NonZeroU32> x =
+      rs_std::NonZeroU32::from_int(123);
+  static_assert(x.has_value());
+  static_assert(x->get() == 123);
+
+  constexpr std::optional<rs_std::NonZeroI16> y =
+      rs_std::NonZeroI16::from_int(-1);
+  static_assert(y->get() == -1);
+}
+
+TEST(RsNonZeroTest, FromIntZero) {
+  static_assert(!rs_std::NonZeroU32::from_int(0).has_value());
+  EXPECT_EQ(std::nullopt, rs_std::NonZeroI64::from_int(0));
+}
+
+TEST(RsNonZeroTest, Comparison) {
+  constexpr rs_std::NonZeroU8 a = *rs_std::NonZeroU8::from_int(1);
+  constexpr rs_std::NonZeroU8 b = *rs_std::NonZeroU8::from_int(2);
+  static_assert(a == a);
+  static_assert(a != b);
+}
+
+}  // namespace
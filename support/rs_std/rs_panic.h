@@ -0,0 +1,88 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_PANIC_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_PANIC_H_
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdio>
+#include <cstdlib>
+#include <exception>
+#include <string>
+#include <string_view>
+#include <utility>
+
+#include "absl/base/optimization.h"
+
+namespace rs_std {
+
+// `rs_std::Panic` is the C++ exception that the bindings generated by
+// `cc_bindings_from_rs` throw when a Rust function called from C++ panics.
+//
+// The panic itself doesn't cross the FFI boundary (unwinding Rust frames into
+// C++ is not supported) - the generated Rust thunks catch the panic (see
+// `support/rs_std/panic_bridge.rs`), and only the (possibly truncated) panic
+// message is preserved.
+class Panic final : public std::exception {
+ public:
+  explicit Panic(std::string message) : message_(std::move(message)) {}
+
+  // Returns the panic message (e.g. "boom" for `panic!("boom")`).
+  const std::string& message() const { return message_; }
+
+  const char* what() const noexcept override { return message_.c_str(); }
+
+ private:
+  std::string message_;
+};
+
+namespace internal {
+
+// `PanicSlot` is passed by the C++ side of the bindings to the Rust thunk,
+// which uses it to report whether the wrapped Rust function has panicked:
+//
+//     ```cc
+//     inline int foo(int x) {
+//       rs_std::internal::PanicSlot panic;
+//       int result = __crubit_internal::__crubit_thunk_foo(x, &panic);
+//       panic.ThrowIfPanicked();
+//       return result;
+//     }
+//     ```
+//
+// The layout of `PanicSlot` has to match the layout of
+// `panic_bridge::PanicSlot` in Rust.
+struct PanicSlot {
+  // Longer messages are truncated (at a `char` boundary) by Rust.
+  static constexpr size_t kMaxMessageLength = 255;
+
+  // Throws `rs_std::Panic` if the Rust thunk has stored a panic in the slot.
+  //
+  // Code built without C++ exceptions can't observe the panic, and therefore
+  // in this case the panic message is printed to `stderr` and the process is
+  // aborted (mimicking `panic = "abort"` in Rust).
+  void ThrowIfPanicked() const {
+    if (ABSL_PREDICT_TRUE(!panicked)) return;
+    std::string_view panic_message(
+        message, std::min(message_length, kMaxMessageLength));
+#if defined(__cpp_exceptions) || defined(__EXCEPTIONS)
+    throw Panic(std::string(panic_message));
+#else
+    std::fprintf(stderr, "Rust panic: %.*s\n",
+                 static_cast<int>(panic_message.size()), panic_message.data());
+    std::abort();
+#endif
+  }
+
+  bool panicked = false;
+  size_t message_length = 0;
+  char message[kMaxMessageLength] = {};
+};
+
+}  // namespace internal
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_PANIC_H_
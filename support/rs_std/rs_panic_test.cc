@@ -0,0 +1,62 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_panic.h"
+
+#include <cstddef>
+#include <cstring>
+#include <exception>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// Layout tests.  `panic_bridge::PanicSlot` in Rust is a `#[repr(C)]` struct
+// with the same fields.
+static_assert(std::is_standard_layout_v<rs_std::internal::PanicSlot>);
+static_assert(offsetof(rs_std::internal::PanicSlot, panicked) == 0);
+static_assert(offsetof(rs_std::internal::PanicSlot, message_length) ==
+              sizeof(size_t));
+static_assert(offsetof(rs_std::internal::PanicSlot, message) ==
+              2 * sizeof(size_t));
+
+static_assert(std::is_base_of_v<std::exception, rs_std::Panic>);
+
+// Mimics a Rust thunk that has caught a panic.
+void StorePanic(rs_std::internal::PanicSlot& slot, const char* message) {
+  slot.panicked = true;
+  slot.message_length = std::strlen(message);
+  std::memcpy(slot.message, message, slot.message_length);
+}
+
+TEST(RsPanicTest, NoPanic) {
+  rs_std::internal::PanicSlot slot;
+  EXPECT_NO_THROW(slot.ThrowIfPanicked());
+}
+
+TEST(RsPanicTest, Panic) {
+  rs_std::internal::PanicSlot slot;
+  StorePanic(slot, "boom");
+  try {
+    slot.ThrowIfPanicked();
+    FAIL() << "Expected `rs_std::Panic` to be thrown";
+  } catch (const rs_std::Panic& panic) {
+    EXPECT_EQ("boom", panic.message());
+    EXPECT_STREQ("boom", panic.what());
+  }
+}
+
+TEST(RsPanicTest, CatchAsStdException) {
+  rs_std::internal::PanicSlot slot;
+  StorePanic(slot, "boom");
+  try {
+    slot.ThrowIfPanicked();
+    FAIL() << "Expected `rs_std::Panic` to be thrown";
+  } catch (const std::exception& e) {
+    EXPECT_STREQ("boom", e.what());
+  }
+}
+
+}  // namespace
@@ -0,0 +1,146 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_SLICE_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_SLICE_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <type_traits>
+
+#include "absl/types/span.h"
+
+namespace rs_std {
+
+namespace internal {
+
+// Returns the pointer that Rust uses for empty slices: a non-null, dangling,
+// but well-aligned pointer (see
+// https://doc.rust-lang.org/std/ptr/struct.NonNull.html#method.dangling).
+template <typename T>
+T* DanglingSlicePtr() {
+  return reinterpret_cast<T*>(static_cast<uintptr_t>(alignof(T)));
+}
+
+}  // namespace internal
+
+// `rs_std::Slice<T>` is a C++ representation of a Rust shared slice reference:
+// `&[T]`.  Like `absl::Span<const T>`, it is a non-owning view of a contiguous
+// sequence of elements, but (unlike `absl::Span`) its data pointer is never
+// null.
+//
+// `rust_builtin_type_abi_assumptions.md` documents the ABI compatibility of
+// `&[T]` and `rs_std::Slice<T>`: a pointer to the first element, followed by
+// the number of elements.
+template <typename T>
+class Slice final {
+  static_assert(!std::is_reference_v<T> && !std::is_const_v<T>);
+
+ public:
+  using value_type = T;
+  using iterator = const T*;
+  using const_iterator = const T*;
+
+  // Creates an empty slice.
+  Slice() : data_(internal::DanglingSlicePtr<const T>()), size_(0) {}
+
+  // Creates a slice that views the elements of `span`.
+  //
+  // The elements have to stay alive (and must not be mutated) while the
+  // slice is used by Rust.
+  //
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  Slice(absl::Span<const T> span)
+      : data_(span.empty() ? internal::DanglingSlicePtr<const T>()
+                           : span.data()),
+        size_(span.size()) {}
+
+  constexpr Slice(const Slice&) = default;
+  constexpr Slice& operator=(const Slice&) = default;
+  ~Slice() = default;
+
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  operator absl::Span<const T>() const {
+    return absl::Span<const T>(data_, size_);
+  }
+
+  const T* data() const { return data_; }
+  size_t size() const { return size_; }
+  bool empty() const { return size_ == 0; }
+
+  const T& operator[](size_t i) const { return data_[i]; }
+
+  const T* begin() const { return data_; }
+  const T* end() const { return data_ + size_; }
+
+ private:
+  const T* data_;
+  size_t size_;
+};
+
+// `rs_std::MutSlice<T>` is a C++ representation of a Rust mutable slice
+// reference: `&mut [T]`.  Like `absl::Span<T>`, it is a non-owning view of a
+// contiguous sequence of elements, but (unlike `absl::Span`) its data pointer
+// is never null.
+//
+// Rust requires `&mut [T]` to be the only reference to the elements while the
+// slice is used by Rust - C++ callers have to ensure that the elements are not
+// accessed through other pointers or references in the meantime.  In
+// particular, passing overlapping `MutSlice`s (or a `MutSlice` and an
+// overlapping `Slice`) to the same Rust function is Undefined Behavior.  When a
+// Rust function returns a slice that borrows from a `&mut [T]` parameter, the
+// returned slice keeps the exclusive access to the elements, and the C++
+// parameter is annotated with `CRUBIT_LIFETIME_BOUND`.
+//
+// `rs_std::MutSlice<T>` has the same ABI as `rs_std::Slice<T>`.
+template <typename T>
+class MutSlice final {
+  static_assert(!std::is_reference_v<T> && !std::is_const_v<T>);
+
+ public:
+  using value_type = T;
+  using iterator = T*;
+  using const_iterator = T*;
+
+  // Creates an empty slice.
+  MutSlice() : data_(internal::DanglingSlicePtr<T>()), size_(0) {}
+
+  // Creates a slice that views the elements of `span`.
+  //
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  MutSlice(absl::Span<T> span)
+      : data_(span.empty() ? internal::DanglingSlicePtr<T>() : span.data()),
+        size_(span.size()) {}
+
+  constexpr MutSlice(const MutSlice&) = default;
+  constexpr MutSlice& operator=(const MutSlice&) = default;
+  ~MutSlice() = default;
+
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  operator absl::Span<T>() const { return absl::Span<T>(data_, size_); }
+
+  // Mimics the implicit reborrowing of `&mut [T]` as `&[T]` in Rust.
+  //
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  operator Slice<T>() const {
+    return Slice<T>(absl::Span<const T>(data_, size_));
+  }
+
+  T* data() const { return data_; }
+  size_t size() const { return size_; }
+  bool empty() const { return size_ == 0; }
+
+  T& operator[](size_t i) const { return data_[i]; }
+
+  T* begin() const { return data_; }
+  T* end() const { return data_ + size_; }
+
+ private:
+  T* data_;
+  size_t size_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_SLICE_H_
@@ -0,0 +1,91 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_slice.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <type_traits>
+#include <vector>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "absl/types/span.h"
+
+namespace {
+
+using ::testing::ElementsAre;
+using ::testing::IsEmpty;
+
+// Layout tests.
+//
+// https://doc.rust-lang.org/std/primitive.slice.html documents that slice
+// references are "represented as a pointer and a length".
+// `rust_builtin_type_abi_assumptions.md` documents that Crubit additionally
+// assumes that the pointer comes first.
+static_assert(sizeof(rs_std::Slice<int32_t>) == 2 * sizeof(void*));
+static_assert(alignof(rs_std::Slice<int32_t>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::Slice<int32_t>>);
+static_assert(std::is_trivially_copyable_v<rs_std::Slice<int32_t>>);
+static_assert(sizeof(rs_std::MutSlice<int32_t>) == 2 * sizeof(void*));
+static_assert(alignof(rs_std::MutSlice<int32_t>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::MutSlice<int32_t>>);
+static_assert(std::is_trivially_copyable_v<rs_std::MutSlice<int32_t>>);
+
+TEST(RsSliceTest, Default) {
+  rs_std::Slice<int32_t> s;
+  EXPECT_TRUE(s.empty());
+  EXPECT_THAT(s, IsEmpty());
+  // Rust requires non-null, well-aligned pointers (even for empty slices).
+  EXPECT_NE(nullptr, s.data());
+  EXPECT_EQ(0, reinterpret_cast<uintptr_t>(s.data()) % alignof(int32_t));
+}
+
+TEST(RsSliceTest, FromEmptySpan) {
+  rs_std::Slice<int64_t> s = absl::Span<const int64_t>();
+  EXPECT_TRUE(s.empty());
+  EXPECT_NE(nullptr, s.data());
+  EXPECT_EQ(0, reinterpret_cast<uintptr_t>(s.data()) % alignof(int64_t));
+}
+
+TEST(RsSliceTest, FromSpan) {
+  std::vector<int32_t> v = {1, 2, 3};
+  rs_std::Slice<int32_t> s = absl::MakeConstSpan(v);
+  EXPECT_EQ(v.data(), s.data());
+  EXPECT_EQ(3, s.size());
+  EXPECT_EQ(2, s[1]);
+  EXPECT_THAT(s, ElementsAre(1, 2, 3));
+
+  absl::Span<const int32_t> span = s;
+  EXPECT_EQ(v.data(), span.data());
+  EXPECT_EQ(3, span.size());
+}
+
+TEST(RsMutSliceTest, Default) {
+  rs_std::MutSlice<int32_t> s;
+  EXPECT_TRUE(s.empty());
+  EXPECT_NE(nullptr, s.data());
+}
+
+TEST(RsMutSliceTest, FromSpan) {
+  std::vector<int32_t> v = {1, 2, 3};
+  rs_std::MutSlice<int32_t> s = absl::MakeSpan(v);
+  s[1] = 20;
+  for (int32_t& element : s) element *= 10;
+  EXPECT_THAT(v, ElementsAre(10, 200, 30));
+
+  absl::Span<int32_t> span = s;
+  EXPECT_EQ(v.data(), span.data());
+  EXPECT_EQ(3, span.size());
+}
+
+TEST(RsMutSliceTest, ToSlice) {
+  std::vector<int32_t> v = {1, 2, 3};
+  rs_std::MutSlice<int32_t> mut_slice = absl::MakeSpan(v);
+  rs_std::Slice<int32_t> slice = mut_slice;
+  EXPECT_EQ(v.data(), slice.data());
+  EXPECT_THAT(slice, ElementsAre(1, 2, 3));
+}
+
+}  // namespace
@@ -0,0 +1,146 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_STR_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_STR_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string_view>
+
+#include "absl/base/optimization.h"
+
+namespace rs_std {
+
+namespace internal {
+
+// Returns true if `s` is a well-formed UTF-8 sequence (i.e. if `s` is a valid
+// Rust `str`): no overlong encodings, no surrogates, no code points above
+// `char::MAX`, and no truncated sequences.
+//
+// This function mimics Rust's `std::str::from_utf8`:
+// https://doc.rust-lang.org/std/str/fn.from_utf8.html
+constexpr bool IsValidUtf8(std::string_view s) {
+  size_t i = 0;
+  while (i < s.size()) {
+    const auto byte = [&](size_t offset) -> uint8_t {
+      return static_cast<uint8_t>(s[i + offset]);
+    };
+    const auto is_continuation = [&](size_t offset) {
+      return (byte(offset) & 0xC0) == 0x80;
+    };
+    const uint8_t first = byte(0);
+    size_t len = 0;
+    if (first < 0x80) {
+      len = 1;
+    } else if (first >= 0xC2 && first <= 0xDF) {
+      len = 2;
+    } else if (first >= 0xE0 && first <= 0xEF) {
+      len = 3;
+    } else if (first >= 0xF0 && first <= 0xF4) {
+      len = 4;
+    } else {
+      // Continuation bytes, overlong 2-byte sequences (0xC0 and 0xC1), and
+      // bytes above 0xF4 can't start a sequence.
+      return false;
+    }
+    if (ABSL_PREDICT_FALSE(s.size() - i < len)) return false;
+    for (size_t offset = 1; offset < len; ++offset) {
+      if (ABSL_PREDICT_FALSE(!is_continuation(offset))) return false;
+    }
+    // Reject overlong 3- and 4-byte sequences, surrogates (U+D800..U+DFFF),
+    // and code points above U+10FFFF.
+    if (first == 0xE0 && byte(1) < 0xA0) return false;
+    if (first == 0xED && byte(1) > 0x9F) return false;
+    if (first == 0xF0 && byte(1) < 0x90) return false;
+    if (first == 0xF4 && byte(1) > 0x8F) return false;
+    i += len;
+  }
+  return true;
+}
+
+}  // namespace internal
+
+// `rs_std::Str` is a C++ representation of a Rust string slice reference:
+// `&str`.  Like `std::string_view`, it is a non-owning view of a sequence of
+// bytes, but (unlike `std::string_view`):
+// - the bytes are guaranteed to be valid UTF-8 (`rs_std::Str` can only be
+//   constructed from arbitrary C++ strings via the checked `from_utf8`),
+// - the data pointer is never null.
+//
+// `rust_builtin_type_abi_assumptions.md` documents the ABI compatibility of
+// `&str` and `rs_std::Str`: a pointer to the first byte, followed by the
+// number of bytes.
+class Str final {
+ public:
+  // Creates an empty string slice.
+  constexpr Str() : Str(kDanglingPtr, 0) {}
+
+  // Creates a string slice that views the bytes of `s`.
+  //
+  // Rust string slices have to be valid UTF-8, and `from_utf8` returns
+  // `std::nullopt` if `s` is not a well-formed UTF-8 sequence.
+  //
+  // The bytes have to stay alive (and must not be mutated) while the string
+  // slice is used by Rust.
+  //
+  // This function mimics Rust's `std::str::from_utf8`:
+  // https://doc.rust-lang.org/std/str/fn.from_utf8.html
+  static constexpr std::optional<Str> from_utf8(std::string_view s) {
+    if (ABSL_PREDICT_FALSE(!internal::IsValidUtf8(s))) return std::nullopt;
+    return from_utf8_unchecked(s);
+  }
+
+  // Creates a string slice that views the bytes of `s`, without checking that
+  // `s` is valid UTF-8.
+  //
+  // TODO(b/254095482): Figure out how to annotate/expose unsafe functions in
+  // C++.
+  static constexpr Str from_utf8_unchecked(std::string_view s) {
+    return s.empty() ? Str() : Str(s.data(), s.size());
+  }
+
+  constexpr Str(const Str&) = default;
+  constexpr Str& operator=(const Str&) = default;
+  ~Str() = default;
+
+  // Converting to `std::string_view` is cheap (no copying or validation).
+  //
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  constexpr operator std::string_view() const {
+    return std::string_view(data_, size_);
+  }
+
+  // Returns a pointer to the bytes of the string slice.  The bytes are not
+  // NUL-terminated.
+  constexpr const char* data() const { return data_; }
+
+  // Returns the length of the string slice in bytes.
+  constexpr size_t size() const { return size_; }
+
+  constexpr bool empty() const { return size_ == 0; }
+
+  constexpr bool operator==(std::string_view other) const {
+    return std::string_view(*this) == other;
+  }
+  constexpr bool operator!=(std::string_view other) const {
+    return std::string_view(*this) != other;
+  }
+
+ private:
+  // Rust uses a non-null, dangling pointer for empty string slices (the
+  // alignment of `u8` is 1).  `""` is used here instead, because it is also
+  // non-null and can be used in constant expressions.
+  static constexpr const char* kDanglingPtr = "";
+
+  constexpr Str(const char* data, size_t size) : data_(data), size_(size) {}
+
+  const char* data_;
+  size_t size_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_STR_H_
@@ -0,0 +1,79 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_str.h"
+
+#include <optional>
+#include <string>
+#include <string_view>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// Layout tests.
+//
+// `rust_builtin_type_abi_assumptions.md` documents that Crubit assumes that
+// `&str` has the same ABI as `&[u8]` (a pointer, followed by a length).
+static_assert(sizeof(rs_std::Str) == 2 * sizeof(void*));
+static_assert(alignof(rs_std::Str) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::Str>);
+static_assert(std::is_trivially_copyable_v<rs_std::Str>);
+
+static_assert(rs_std::internal::IsValidUtf8(""));
+static_assert(rs_std::internal::IsValidUtf8("ascii"));
+static_assert(rs_std::internal::IsValidUtf8("\xC5\xBC\xC3\xB3\xC5\x82w"));
+static_assert(rs_std::internal::IsValidUtf8("\xF0\x9F\xA6\x80"));  // U+1F980
+// Unexpected continuation byte.
+static_assert(!rs_std::internal::IsValidUtf8("\x80"));
+// Truncated sequence.
+static_assert(!rs_std::internal::IsValidUtf8("\xC5"));
+// Overlong encoding of '/'.
+static_assert(!rs_std::internal::IsValidUtf8("\xC0\xAF"));
+// Surrogate (U+D800).
+static_assert(!rs_std::internal::IsValidUtf8("\xED\xA0\x80"));
+// Above `char::MAX` (U+110000).
+static_assert(!rs_std::internal::IsValidUtf8("\xF4\x90\x80\x80"));
+
+static_assert(rs_std::Str::from_utf8("foo").has_value());
+static_assert(!rs_std::Str::from_utf8("\x80").has_value());
+
+TEST(RsStrTest, Default) {
+  rs_std::Str s;
+  EXPECT_TRUE(s.empty());
+  EXPECT_EQ(0, s.size());
+  // Rust requires a non-null pointer (even for empty string slices).
+  EXPECT_NE(nullptr, s.data());
+  EXPECT_EQ("", std::string_view(s));
+}
+
+TEST(RsStrTest, FromEmptyStringView) {
+  std::optional<rs_std::Str> s = rs_std::Str::from_utf8(std::string_view());
+  ASSERT_TRUE(s.has_value());
+  EXPECT_TRUE(s->empty());
+  EXPECT_NE(nullptr, s->data());
+}
+
+TEST(RsStrTest, FromUtf8) {
+  std::string input = "\xC5\xBC\xC3\xB3\xC5\x82w";
+  std::optional<rs_std::Str> s = rs_std::Str::from_utf8(input);
+  ASSERT_TRUE(s.has_value());
+  // No copying - the `rs_std::Str` views the bytes of `input`.
+  EXPECT_EQ(input.data(), s->data());
+  EXPECT_EQ(input.size(), s->size());
+  EXPECT_EQ(*s, input);
+
+  std::string_view view = *s;
+  EXPECT_EQ(input.data(), view.data());
+  EXPECT_EQ(input.size(), view.size());
+}
+
+TEST(RsStrTest, FromInvalidUtf8) {
+  EXPECT_FALSE(rs_std::Str::from_utf8("\xC5").has_value());
+  EXPECT_FALSE(rs_std::Str::from_utf8("\xED\xA0\x80").has_value());
+  EXPECT_FALSE(rs_std::Str::from_utf8(std::string("a\xFF" "b")).has_value());
+}
+
+}  // namespace
@@ -0,0 +1,156 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_STRING_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_STRING_H_
+
+#include <cstddef>
+#include <cstring>
+#include <optional>
+#include <string_view>
+#include <utility>
+
+#include "absl/base/optimization.h"
+#include "support/rs_std/rs_str.h"
+#include "support/rs_std/rust_allocator.h"
+
+namespace rs_std {
+
+// `rs_std::String` is a C++ representation of the `String` type from Rust: an
+// owned, growable, UTF-8 encoded string.  The bytes of the string live in an
+// allocation from the Rust global allocator, which `rs_std::String` owns and
+// frees (via the Rust allocator) when it is destroyed.  This means that a
+// Rust `String` can be passed to C++ (and back to Rust) without copying its
+// bytes.
+//
+// The layout of Rust's `String` is not guaranteed, and therefore the generated
+// bindings convert between `String` and `rs_std::String` by passing the raw
+// parts of the string (see `from_raw_parts` and `release`).
+class String final {
+ public:
+  // Creates an empty `rs_std::String` (one that doesn't own any allocation).
+  //
+  // This constructor mimics Rust's `String::new`:
+  // https://doc.rust-lang.org/std/string/struct.String.html#method.new
+  constexpr String() = default;
+
+  // Copies `s` into a new `rs_std::String`.
+  //
+  // Rust strings have to be valid UTF-8, and `from_utf8` returns
+  // `std::nullopt` if `s` is not a well-formed UTF-8 sequence.
+  //
+  // This function mimics Rust's `String::from_utf8`:
+  // https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8
+  static std::optional<String> from_utf8(std::string_view s) {
+    if (ABSL_PREDICT_FALSE(!internal::IsValidUtf8(s))) return std::nullopt;
+    if (s.empty()) return String();
+    char* data = static_cast<char*>(internal::RustAllocOrDie(s.size(), 1));
+    std::memcpy(data, s.data(), s.size());
+    return String(data, s.size(), s.size());
+  }
+
+  // Takes ownership of the Rust allocation described by the raw parts of a
+  // Rust `String` (see
+  // https://doc.rust-lang.org/std/string/struct.String.html#method.into_raw_parts).
+  //
+  // `data` has to point to an allocation of `capacity` bytes from the Rust
+  // global allocator (or `capacity` has to be 0), and the first `size` bytes
+  // of the allocation have to be valid UTF-8.
+  //
+  // TODO(b/254095482): Figure out how to annotate/expose unsafe functions in
+  // C++.
+  static String from_raw_parts(char* data, size_t size, size_t capacity) {
+    return String(data, size, capacity);
+  }
+
+  String(const String&) = delete;
+  String& operator=(const String&) = delete;
+
+  String(String&& other) noexcept
+      : data_(std::exchange(other.data_, nullptr)),
+        size_(std::exchange(other.size_, 0)),
+        capacity_(std::exchange(other.capacity_, 0)) {}
+  String& operator=(String&& other) noexcept {
+    if (this != &other) {
+      String old = std::move(*this);
+      data_ = std::exchange(other.data_, nullptr);
+      size_ = std::exchange(other.size_, 0);
+      capacity_ = std::exchange(other.capacity_, 0);
+    }
+    return *this;
+  }
+
+  ~String() {
+    if (capacity_ != 0) __rust_dealloc(data_, capacity_, 1);
+  }
+
+  // Returns a pointer to the bytes of the string.  The bytes are not
+  // NUL-terminated.
+  const char* data() const { return data_ != nullptr ? data_ : ""; }
+
+  // Returns the length of the string in bytes.
+  size_t size() const { return size_; }
+
+  // Returns the number of bytes that the string can hold without
+  // reallocating.
+  size_t capacity() const { return capacity_; }
+
+  bool empty() const { return size_ == 0; }
+
+  operator std::string_view() const { return std::string_view(data(), size_); }
+
+  // Borrows the bytes of the string as a Rust `&str` (mimics the `Deref`
+  // implementation of Rust's `String`).  The returned `rs_std::Str` should not
+  // outlive the `rs_std::String`.
+  //
+  // NOLINTNEXTLINE(google-explicit-constructor)
+  operator Str() const { return Str::from_utf8_unchecked(*this); }
+
+  // The raw parts of a Rust `String`.  Note that `data` may be null when
+  // `capacity` is 0 (while Rust requires a non-null, dangling pointer in this
+  // case).
+  //
+  // The layout of `RawParts` has to match the layout of
+  // `alloc_bridge::StringRawParts` in Rust (see
+  // `support/rs_std/alloc_bridge.rs`).
+  struct RawParts {
+    char* data;
+    size_t size;
+    size_t capacity;
+  };
+
+  // Takes ownership of the Rust allocation described by `parts` (see the
+  // other overload of `from_raw_parts` above).  Used by the generated bindings
+  // of Rust functions that return a `String`.
+  static String from_raw_parts(RawParts parts) {
+    return String(parts.data, parts.size, parts.capacity);
+  }
+
+  // Releases the ownership of the Rust allocation (so that it can be passed
+  // back to a Rust `String` - see `String::from_raw_parts` in Rust).  The
+  // `rs_std::String` is left empty.
+  RawParts release() && {
+    return RawParts{std::exchange(data_, nullptr), std::exchange(size_, 0),
+                    std::exchange(capacity_, 0)};
+  }
+
+  bool operator==(std::string_view other) const {
+    return std::string_view(*this) == other;
+  }
+  bool operator!=(std::string_view other) const {
+    return std::string_view(*this) != other;
+  }
+
+ private:
+  String(char* data, size_t size, size_t capacity)
+      : data_(data), size_(size), capacity_(capacity) {}
+
+  char* data_ = nullptr;
+  size_t size_ = 0;
+  size_t capacity_ = 0;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_STRING_H_
@@ -0,0 +1,107 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_string.h"
+
+#include <optional>
+#include <string_view>
+#include <type_traits>
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "support/rs_std/rust_allocator_for_testing.h"
+
+namespace {
+
+using ::rs_std::testing::GetLiveRustAllocations;
+
+static_assert(!std::is_copy_constructible_v<rs_std::String>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::String>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::String>);
+
+TEST(RsStringTest, Default) {
+  rs_std::String s;
+  EXPECT_TRUE(s.empty());
+  EXPECT_EQ(0, s.size());
+  EXPECT_EQ(0, s.capacity());
+  EXPECT_EQ("", std::string_view(s));
+}
+
+TEST(RsStringTest, FromUtf8) {
+  {
+    std::optional<rs_std::String> s = rs_std::String::from_utf8("hello");
+    ASSERT_TRUE(s.has_value());
+    EXPECT_EQ(1, GetLiveRustAllocations());
+    EXPECT_EQ(5, s->size());
+    EXPECT_EQ(5, s->capacity());
+    EXPECT_EQ(*s, "hello");
+    EXPECT_EQ("hello", std::string_view(*s));
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsStringTest, FromUtf8Empty) {
+  std::optional<rs_std::String> s = rs_std::String::from_utf8("");
+  ASSERT_TRUE(s.has_value());
+  EXPECT_TRUE(s->empty());
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsStringTest, FromUtf8Invalid) {
+  EXPECT_FALSE(rs_std::String::from_utf8("\xFF").has_value());
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsStringTest, Move) {
+  {
+    rs_std::String s1 = *rs_std::String::from_utf8("foo");
+    rs_std::String s2 = std::move(s1);
+    EXPECT_EQ(s2, "foo");
+    EXPECT_TRUE(s1.empty());  // NOLINT(bugprone-use-after-move)
+
+    s1 = *rs_std::String::from_utf8("bar");
+    EXPECT_EQ(2, GetLiveRustAllocations());
+    s2 = std::move(s1);
+    EXPECT_EQ(1, GetLiveRustAllocations());
+    EXPECT_EQ(s2, "bar");
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsStringTest, RawPartsRoundTrip) {
+  rs_std::String s1 = *rs_std::String::from_utf8("foo");
+  rs_std::String::RawParts parts = std::move(s1).release();
+  EXPECT_EQ(3, parts.size);
+  EXPECT_EQ(3, parts.capacity);
+  EXPECT_TRUE(s1.empty());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1, GetLiveRustAllocations());
+
+  {
+    rs_std::String s2 =
+        rs_std::String::from_raw_parts(parts.data, parts.size, parts.capacity);
+    EXPECT_EQ(s2, "foo");
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsStringTest, FromRawPartsStruct) {
+  rs_std::String s1 = *rs_std::String::from_utf8("foo");
+  {
+    rs_std::String s2 = rs_std::String::from_raw_parts(std::move(s1).release());
+    EXPECT_EQ(s2, "foo");
+    EXPECT_EQ(1, GetLiveRustAllocations());
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsStringTest, ToStr) {
+  std::optional<rs_std::String> s = rs_std::String::from_utf8("foo");
+  ASSERT_TRUE(s.has_value());
+  rs_std::Str str = *s;
+  EXPECT_EQ(s->data(), str.data());
+  EXPECT_EQ(str, "foo");
+}
+
+}  // namespace
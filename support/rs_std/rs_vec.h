@@ -0,0 +1,205 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_VEC_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_VEC_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <cstring>
+#include <type_traits>
+#include <utility>
+
+#include "absl/base/optimization.h"
+#include "support/rs_std/rust_allocator.h"
+
+namespace rs_std {
+
+// `rs_std::Vec<T>` is a C++ representation of the `Vec<T>` type from Rust: an
+// owned, growable array.  The elements live in an allocation from the Rust
+// global allocator, which `rs_std::Vec<T>` owns and frees (via the Rust
+// allocator) when it is destroyed.
+//
+// `T` has to be the C++ binding of the Rust element type (e.g. `std::int32_t`
+// for `Vec<i32>`, or the C++ struct generated by `cc_bindings_from_rs` for a
+// Rust struct).  Dropping the `Vec<T>` destroys the elements, which runs the
+// Rust drop glue through the destructor of the generated C++ struct (if any).
+//
+// The layout of Rust's `Vec<T>` is not guaranteed, and therefore the generated
+// bindings convert between `Vec<T>` and `rs_std::Vec<T>` by passing the raw
+// parts of the vector (see `from_raw_parts` and `release`).
+template <typename T>
+class Vec final {
+  // The elements are owned by the vector (and mutated through `&mut Vec<T>`
+  // in Rust).
+  static_assert(!std::is_reference_v<T> && !std::is_const_v<T>);
+
+ public:
+  using value_type = T;
+  using iterator = T*;
+  using const_iterator = const T*;
+
+  // Creates an empty `rs_std::Vec<T>` (one that doesn't own any allocation).
+  //
+  // This constructor mimics Rust's `Vec::new`:
+  // https://doc.rust-lang.org/std/vec/struct.Vec.html#method.new
+  Vec() = default;
+
+  // Takes ownership of the Rust allocation described by the raw parts of a
+  // Rust `Vec<T>` (see
+  // https://doc.rust-lang.org/std/vec/struct.Vec.html#method.into_raw_parts).
+  //
+  // `data` has to point to an allocation for `capacity` elements from the Rust
+  // global allocator (or `capacity` has to be 0 and `data` has to be a
+  // non-null, aligned pointer), and the first `size` elements of the
+  // allocation have to be initialized.
+  //
+  // TODO(b/254095482): Figure out how to annotate/expose unsafe functions in
+  // C++.
+  static Vec from_raw_parts(T* data, size_t size, size_t capacity) {
+    return Vec(data, size, capacity);
+  }
+
+  Vec(const Vec&) = delete;
+  Vec& operator=(const Vec&) = delete;
+
+  Vec(Vec&& other) noexcept
+      : data_(std::exchange(other.data_, Dangling())),
+        size_(std::exchange(other.size_, 0)),
+        capacity_(std::exchange(other.capacity_, 0)) {}
+  Vec& operator=(Vec&& other) noexcept {
+    if (this != &other) {
+      Vec old = std::move(*this);
+      data_ = std::exchange(other.data_, Dangling());
+      size_ = std::exchange(other.size_, 0);
+      capacity_ = std::exchange(other.capacity_, 0);
+    }
+    return *this;
+  }
+
+  ~Vec() {
+    clear();
+    if (capacity_ != 0) {
+      __rust_dealloc(data_, capacity_ * sizeof(T), alignof(T));
+    }
+  }
+
+  T* data() { return data_; }
+  const T* data() const { return data_; }
+
+  size_t size() const { return size_; }
+  size_t capacity() const { return capacity_; }
+  bool empty() const { return size_ == 0; }
+
+  T& operator[](size_t i) { return data_[i]; }
+  const T& operator[](size_t i) const { return data_[i]; }
+
+  iterator begin() { return data_; }
+  iterator end() { return data_ + size_; }
+  const_iterator begin() const { return data_; }
+  const_iterator end() const { return data_ + size_; }
+
+  // Appends `value` to the back of the vector, growing the Rust allocation if
+  // needed.
+  //
+  // Growing the allocation `memcpy`s the elements (like Rust does), and
+  // therefore `push_back` is only supported for trivially copyable `T`s (e.g.
+  // for integers or for the C++ bindings of `Copy` Rust structs).
+  //
+  // This function mimics Rust's `Vec::push`:
+  // https://doc.rust-lang.org/std/vec/struct.Vec.html#method.push
+  void push_back(const T& value) {
+    static_assert(std::is_trivially_copyable_v<T>,
+                  "push_back requires a trivially copyable `T`");
+    if (ABSL_PREDICT_FALSE(size_ == capacity_)) {
+      // Doubling the capacity can't overflow, because `reserve` keeps the
+      // capacity below `kMaxCapacity`.
+      static_assert(kMaxCapacity <= SIZE_MAX / 2);
+      reserve(capacity_ == 0 ? kMinNonZeroCapacity : capacity_ * 2);
+    }
+    std::memcpy(static_cast<void*>(data_ + size_), &value, sizeof(T));
+    ++size_;
+  }
+
+  // Ensures that the vector can hold at least `new_capacity` elements without
+  // reallocating.  Aborts the process if the allocation would exceed
+  // `PTRDIFF_MAX` bytes (Rust's `Vec` panics with "capacity overflow" in this
+  // case).
+  void reserve(size_t new_capacity) {
+    static_assert(std::is_trivially_copyable_v<T>,
+                  "reserve requires a trivially copyable `T`");
+    if (new_capacity <= capacity_) return;
+    if (ABSL_PREDICT_FALSE(new_capacity > kMaxCapacity)) std::abort();
+    void* new_data =
+        capacity_ == 0
+            ? internal::RustAllocOrDie(new_capacity * sizeof(T), alignof(T))
+            : internal::RustReallocOrDie(data_, capacity_ * sizeof(T),
+                                         alignof(T), new_capacity * sizeof(T));
+    data_ = static_cast<T*>(new_data);
+    capacity_ = new_capacity;
+  }
+
+  // Destroys all the elements (keeping the allocation).
+  void clear() {
+    if constexpr (!std::is_trivially_destructible_v<T>) {
+      for (T& element : *this) element.~T();
+    }
+    size_ = 0;
+  }
+
+  // The raw parts of a Rust `Vec<T>`.  When `capacity` is 0, then `data` is a
+  // non-null, dangling pointer (just like in Rust).
+  //
+  // The layout of `RawParts` has to match the layout of
+  // `alloc_bridge::VecRawParts<T>` in Rust (see
+  // `support/rs_std/alloc_bridge.rs`).
+  struct RawParts {
+    T* data;
+    size_t size;
+    size_t capacity;
+  };
+
+  // Takes ownership of the Rust allocation and of the elements described by
+  // `parts` (see the other overload of `from_raw_parts` above).  Used by the
+  // generated bindings of Rust functions that return a `Vec<T>`.
+  static Vec from_raw_parts(RawParts parts) {
+    return Vec(parts.data, parts.size, parts.capacity);
+  }
+
+  // Releases the ownership of the Rust allocation and of the elements (so that
+  // they can be passed back to a Rust `Vec<T>` - see `Vec::from_raw_parts` in
+  // Rust).  The `rs_std::Vec<T>` is left empty.
+  RawParts release() && {
+    return RawParts{std::exchange(data_, Dangling()), std::exchange(size_, 0),
+                    std::exchange(capacity_, 0)};
+  }
+
+ private:
+  // Mimics `RawVec::MIN_NON_ZERO_CAP` from the Rust standard library.
+  static constexpr size_t kMinNonZeroCapacity =
+      sizeof(T) == 1 ? 8 : (sizeof(T) <= 1024 ? 4 : 1);
+
+  // Rust allocations can't exceed `isize::MAX` bytes.
+  static constexpr size_t kMaxCapacity = PTRDIFF_MAX / sizeof(T);
+
+  // A non-null, aligned pointer that is used when the vector doesn't own an
+  // allocation (this mimics `NonNull::dangling` from Rust, so that the raw
+  // parts can be passed to Rust's `Vec::from_raw_parts` as-is).
+  static T* Dangling() {
+    // NOLINTNEXTLINE(performance-no-int-to-ptr)
+    return reinterpret_cast<T*>(alignof(T));
+  }
+
+  Vec(T* data, size_t size, size_t capacity)
+      : data_(data), size_(size), capacity_(capacity) {}
+
+  T* data_ = Dangling();
+  size_t size_ = 0;
+  size_t capacity_ = 0;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_VEC_H_
@@ -0,0 +1,152 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_vec.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <new>
+#include <type_traits>
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "support/rs_std/rust_allocator_for_testing.h"
+
+namespace {
+
+using ::rs_std::testing::GetLiveRustAllocations;
+using ::testing::ElementsAre;
+using ::testing::IsEmpty;
+
+static_assert(!std::is_copy_constructible_v<rs_std::Vec<int32_t>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::Vec<int32_t>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::Vec<int32_t>>);
+
+// Counts the live instances, so that the tests can verify that dropping a
+// `Vec<T>` destroys its elements.
+struct Counted {
+  explicit Counted(int value) : value(value) { ++live_instances; }
+  ~Counted() { --live_instances; }
+  static int live_instances;
+  int value;
+};
+int Counted::live_instances = 0;
+
+TEST(RsVecTest, Default) {
+  rs_std::Vec<int32_t> v;
+  EXPECT_TRUE(v.empty());
+  EXPECT_EQ(0, v.size());
+  EXPECT_EQ(0, v.capacity());
+  EXPECT_THAT(v, IsEmpty());
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsVecTest, EmptyDataIsDangling) {
+  // Like in Rust, the data pointer of an empty vector is non-null and aligned
+  // (so that the raw parts can be passed to Rust's `Vec::from_raw_parts`).
+  rs_std::Vec<int64_t> v;
+  EXPECT_NE(nullptr, v.data());
+  EXPECT_EQ(0, reinterpret_cast<uintptr_t>(v.data()) % alignof(int64_t));
+
+  v.push_back(1);
+  rs_std::Vec<int64_t>::RawParts parts = std::move(v).release();
+  EXPECT_NE(nullptr, v.data());  // NOLINT(bugprone-use-after-move)
+  v = rs_std::Vec<int64_t>::from_raw_parts(parts);
+  EXPECT_THAT(v, ElementsAre(1));
+}
+
+TEST(RsVecTest, ReserveCapacityOverflow) {
+  rs_std::Vec<int64_t> v;
+  EXPECT_DEATH(v.reserve(SIZE_MAX / 2), "");
+}
+
+TEST(RsVecTest, PushBack) {
+  {
+    rs_std::Vec<int32_t> v;
+    for (int32_t i = 0; i < 10; ++i) v.push_back(i * i);
+    EXPECT_EQ(10, v.size());
+    EXPECT_LE(10, v.capacity());
+    EXPECT_EQ(1, GetLiveRustAllocations());
+    EXPECT_EQ(81, v[9]);
+    EXPECT_THAT(v, ElementsAre(0, 1, 4, 9, 16, 25, 36, 49, 64, 81));
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsVecTest, Reserve) {
+  rs_std::Vec<int64_t> v;
+  v.reserve(100);
+  EXPECT_EQ(100, v.capacity());
+  v.push_back(123);
+  EXPECT_EQ(100, v.capacity());
+  v.reserve(1);
+  EXPECT_EQ(100, v.capacity());
+  EXPECT_THAT(v, ElementsAre(123));
+}
+
+TEST(RsVecTest, Move) {
+  {
+    rs_std::Vec<int32_t> v1;
+    v1.push_back(1);
+    rs_std::Vec<int32_t> v2 = std::move(v1);
+    EXPECT_THAT(v2, ElementsAre(1));
+    EXPECT_TRUE(v1.empty());  // NOLINT(bugprone-use-after-move)
+
+    v1.push_back(2);
+    EXPECT_EQ(2, GetLiveRustAllocations());
+    v2 = std::move(v1);
+    EXPECT_EQ(1, GetLiveRustAllocations());
+    EXPECT_THAT(v2, ElementsAre(2));
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsVecTest, DropDestroysElements) {
+  {
+    // Simulates a `Vec<T>` received from Rust.
+    rs_std::Vec<int32_t> storage;
+    storage.reserve(2);
+    rs_std::Vec<int32_t>::RawParts parts = std::move(storage).release();
+    Counted* data = reinterpret_cast<Counted*>(parts.data);
+    new (&data[0]) Counted(1);
+    new (&data[1]) Counted(2);
+    EXPECT_EQ(2, Counted::live_instances);
+
+    rs_std::Vec<Counted> v = rs_std::Vec<Counted>::from_raw_parts(data, 2, 2);
+    EXPECT_EQ(2, v[1].value);
+  }
+  EXPECT_EQ(0, Counted::live_instances);
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsVecTest, RawPartsRoundTrip) {
+  rs_std::Vec<int32_t> v1;
+  v1.push_back(42);
+  rs_std::Vec<int32_t>::RawParts parts = std::move(v1).release();
+  EXPECT_EQ(1, parts.size);
+  EXPECT_TRUE(v1.empty());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1, GetLiveRustAllocations());
+
+  {
+    rs_std::Vec<int32_t> v2 = rs_std::Vec<int32_t>::from_raw_parts(
+        parts.data, parts.size, parts.capacity);
+    EXPECT_THAT(v2, ElementsAre(42));
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+TEST(RsVecTest, FromRawPartsStruct) {
+  rs_std::Vec<int32_t> v1;
+  v1.push_back(42);
+  {
+    rs_std::Vec<int32_t> v2 =
+        rs_std::Vec<int32_t>::from_raw_parts(std::move(v1).release());
+    EXPECT_THAT(v2, ElementsAre(42));
+    EXPECT_EQ(1, GetLiveRustAllocations());
+  }
+  EXPECT_EQ(0, GetLiveRustAllocations());
+}
+
+}  // namespace
@@ -0,0 +1,51 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RUST_ALLOCATOR_H_
+#define CRUBIT_SUPPORT_RS_STD_RUST_ALLOCATOR_H_
+
+#include <cstddef>
+
+// The global allocator of Rust (see
+// https://doc.rust-lang.org/std/alloc/trait.GlobalAlloc.html).  These symbols
+// are emitted by `rustc` into every binary that links the Rust standard
+// library (see also `crubit/common/rust_allocator_shims.c`).
+//
+// The C++ types that own Rust allocations (e.g. `rs_std::String`) have to use
+// these functions (rather than `malloc` / `free` or `operator new` /
+// `operator delete`), so that the allocations can be passed back to Rust.
+extern "C" void* __rust_alloc(size_t size, size_t align);
+extern "C" void __rust_dealloc(void* ptr, size_t size, size_t align);
+extern "C" void* __rust_realloc(void* ptr, size_t old_size, size_t align,
+                                size_t new_size);
+
+// Reports an allocation failure (i.e. a null pointer returned by
+// `__rust_alloc` or `__rust_realloc`) - like Rust's
+// `std::alloc::handle_alloc_error`, this aborts the process (or panics, if
+// the Rust binary opted into that) and doesn't return.
+extern "C" [[noreturn]] void __rust_alloc_error_handler(size_t size,
+                                                        size_t align);
+
+namespace rs_std::internal {
+
+// Like `__rust_alloc`, but calls `__rust_alloc_error_handler` instead of
+// returning a null pointer when the allocation fails.
+inline void* RustAllocOrDie(size_t size, size_t align) {
+  void* ptr = __rust_alloc(size, align);
+  if (ptr == nullptr) __rust_alloc_error_handler(size, align);
+  return ptr;
+}
+
+// Like `__rust_realloc`, but calls `__rust_alloc_error_handler` instead of
+// returning a null pointer when the allocation fails.
+inline void* RustReallocOrDie(void* ptr, size_t old_size, size_t align,
+                              size_t new_size) {
+  void* new_ptr = __rust_realloc(ptr, old_size, align, new_size);
+  if (new_ptr == nullptr) __rust_alloc_error_handler(new_size, align);
+  return new_ptr;
+}
+
+}  // namespace rs_std::internal
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RUST_ALLOCATOR_H_
@@ -0,0 +1,44 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rust_allocator_for_testing.h"
+
+#include <stdlib.h>
+#include <string.h>
+
+#include <cstddef>
+
+#include "support/rs_std/rust_allocator.h"
+
+namespace {
+int live_rust_allocations = 0;
+}  // namespace
+
+extern "C" void* __rust_alloc(size_t size, size_t align) {
+  ++live_rust_allocations;
+  return aligned_alloc(align, (size + align - 1) / align * align);
+}
+
+extern "C" void __rust_dealloc(void* ptr, size_t size, size_t align) {
+  --live_rust_allocations;
+  free(ptr);
+}
+
+extern "C" void* __rust_realloc(void* ptr, size_t old_size, size_t align,
+                                size_t new_size) {
+  void* new_ptr = __rust_alloc(new_size, align);
+  memcpy(new_ptr, ptr, old_size < new_size ? old_size : new_size);
+  __rust_dealloc(ptr, old_size, align);
+  return new_ptr;
+}
+
+extern "C" void __rust_alloc_error_handler(size_t size, size_t align) {
+  abort();
+}
+
+namespace rs_std::testing {
+
+int GetLiveRustAllocations() { return live_rust_allocations; }
+
+}  // namespace rs_std::testing
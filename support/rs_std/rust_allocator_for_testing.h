@@ -0,0 +1,21 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RUST_ALLOCATOR_FOR_TESTING_H_
+#define CRUBIT_SUPPORT_RS_STD_RUST_ALLOCATOR_FOR_TESTING_H_
+
+namespace rs_std::testing {
+
+// Returns the number of live allocations from the Rust global allocator.
+//
+// `rust_allocator_for_testing.cc` provides its own (`malloc`-based)
+// definitions of the functions from `rust_allocator.h`, so that C++ tests of
+// the `rs_std` types don't need to link the Rust standard library.  These
+// definitions count the live allocations, so that the tests can verify that
+// the allocations are freed.
+int GetLiveRustAllocations();
+
+}  // namespace rs_std::testing
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RUST_ALLOCATOR_FOR_TESTING_H_
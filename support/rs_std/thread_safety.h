@@ -0,0 +1,75 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_THREAD_SAFETY_H_
+#define CRUBIT_SUPPORT_RS_STD_THREAD_SAFETY_H_
+
+#include <type_traits>
+
+namespace rs_std {
+
+// `rs_std::is_send_v<T>` is true if values of type `T` can be safely moved to
+// another thread - in other words, if `T` is a C++ binding of a Rust type that
+// implements the `Send` auto trait
+// (https://doc.rust-lang.org/std/marker/trait.Send.html).
+//
+// `cc_bindings_from_rs` records whether a Rust type implements `Send` in the
+// `kIsSend` static data member of the generated C++ type.  Arithmetic types
+// are `Send` (like Rust's integer and floating-point types).  Other C++ types
+// (e.g. raw pointers) are conservatively assumed not to be `Send`.
+template <typename T, typename = void>
+struct is_send : std::bool_constant<std::is_arithmetic_v<T>> {};
+
+template <typename T>
+struct is_send<T, std::void_t<decltype(T::kIsSend)>>
+    : std::bool_constant<T::kIsSend> {};
+
+template <typename T>
+inline constexpr bool is_send_v = is_send<T>::value;
+
+// `rs_std::is_sync_v<T>` is true if `const T&` references can be safely shared
+// between threads - in other words, if `T` is a C++ binding of a Rust type
+// that implements the `Sync` auto trait
+// (https://doc.rust-lang.org/std/marker/trait.Sync.html).
+//
+// Like `rs_std::is_send_v`, this uses the `kIsSync` static data member of the
+// C++ types generated by `cc_bindings_from_rs`.
+template <typename T, typename = void>
+struct is_sync : std::bool_constant<std::is_arithmetic_v<T>> {};
+
+template <typename T>
+struct is_sync<T, std::void_t<decltype(T::kIsSync)>>
+    : std::bool_constant<T::kIsSync> {};
+
+template <typename T>
+inline constexpr bool is_sync_v = is_sync<T>::value;
+
+// `AssertIsSend<T>()` and `AssertIsSync<T>()` fail to compile unless `T` is
+// `Send` (or `Sync`).  They may be called by helpers that move objects to
+// other threads (or share them across threads) - for example:
+//
+//     ```cc
+//     template <typename T>
+//     void RunOnWorkerThread(T value, absl::AnyInvocable<void(T)> f) {
+//       rs_std::AssertIsSend<T>();
+//       ...
+//     }
+//     ```
+template <typename T>
+constexpr void AssertIsSend() {
+  static_assert(is_send_v<T>,
+                "The Rust type bound by `T` doesn't implement `Send` - it "
+                "can't be moved to another thread");
+}
+
+template <typename T>
+constexpr void AssertIsSync() {
+  static_assert(is_sync_v<T>,
+                "The Rust type bound by `T` doesn't implement `Sync` - it "
+                "can't be shared between threads");
+}
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_THREAD_SAFETY_H_
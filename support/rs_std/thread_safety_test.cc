@@ -0,0 +1,61 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/thread_safety.h"
+
+#include <cstdint>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// Mimic the C++ bindings generated by `cc_bindings_from_rs`.
+struct SendAndSync final {
+  static constexpr bool kIsSend = true;
+  static constexpr bool kIsSync = true;
+};
+
+// E.g. `std::cell::Cell<i32>`.
+struct SendButNotSync final {
+  static constexpr bool kIsSend = true;
+  static constexpr bool kIsSync = false;
+};
+
+// E.g. `std::rc::Rc<i32>`.
+struct NeitherSendNorSync final {
+  static constexpr bool kIsSend = false;
+  static constexpr bool kIsSync = false;
+};
+
+// A C++ type that is not a binding of a Rust type.
+struct NotARustType final {};
+
+TEST(ThreadSafetyTest, GeneratedBindings) {
+  EXPECT_TRUE(rs_std::is_send_v<SendAndSync>);
+  EXPECT_TRUE(rs_std::is_sync_v<SendAndSync>);
+
+  EXPECT_TRUE(rs_std::is_send_v<SendButNotSync>);
+  EXPECT_FALSE(rs_std::is_sync_v<SendButNotSync>);
+
+  EXPECT_FALSE(rs_std::is_send_v<NeitherSendNorSync>);
+  EXPECT_FALSE(rs_std::is_sync_v<NeitherSendNorSync>);
+}
+
+TEST(ThreadSafetyTest, OtherTypes) {
+  EXPECT_TRUE(rs_std::is_send_v<std::int32_t>);
+  EXPECT_TRUE(rs_std::is_sync_v<double>);
+
+  EXPECT_FALSE(rs_std::is_send_v<std::int32_t*>);
+  EXPECT_FALSE(rs_std::is_sync_v<const std::int32_t*>);
+  EXPECT_FALSE(rs_std::is_send_v<NotARustType>);
+  EXPECT_FALSE(rs_std::is_sync_v<NotARustType>);
+}
+
+TEST(ThreadSafetyTest, Assertions) {
+  rs_std::AssertIsSend<SendAndSync>();
+  rs_std::AssertIsSync<SendAndSync>();
+  rs_std::AssertIsSend<SendButNotSync>();
+}
+
+}  // namespace
@@ -0,0 +1,29 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_UNSAFE_CALL_H_
+#define CRUBIT_SUPPORT_RS_STD_UNSAFE_CALL_H_
+
+namespace rs_std {
+
+// `rs_std::UnsafeCall` is a tag type that C++ callers have to pass (as the
+// first argument) to the bindings of `unsafe` Rust functions.  Spelling out
+// `rs_std::UnsafeCall()` at the call site is the C++ equivalent of an
+// `unsafe { ... }` block in Rust: it signals that the caller has consciously
+// opted into upholding the safety requirements of the Rust function:
+//
+//     ```cc
+//     // Bindings of `pub unsafe fn get_unchecked(s: &[i32], i: usize) -> i32`:
+//     int32_t x = get_unchecked(rs_std::UnsafeCall(), s, i);
+//     ```
+//
+// The constructor is `explicit`, so that `get_unchecked({}, s, i)` doesn't
+// compile.
+struct UnsafeCall final {
+  explicit constexpr UnsafeCall() = default;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_UNSAFE_CALL_H_
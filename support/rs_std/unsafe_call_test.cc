@@ -0,0 +1,32 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/unsafe_call.h"
+
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// Mimics the C++ bindings of `pub unsafe fn add(x: i32, y: i32) -> i32`.
+int Add(rs_std::UnsafeCall, int x, int y) { return x + y; }
+
+template <typename T, typename = void>
+constexpr bool kIsImplicitlyConstructibleFromBraces = false;
+
+template <typename T>
+constexpr bool kIsImplicitlyConstructibleFromBraces<
+    T, std::void_t<decltype(std::declval<void (&)(T)>()({}))>> = true;
+
+static_assert(std::is_trivially_copyable_v<rs_std::UnsafeCall>);
+static_assert(std::is_empty_v<rs_std::UnsafeCall>);
+static_assert(std::is_default_constructible_v<rs_std::UnsafeCall>);
+static_assert(!kIsImplicitlyConstructibleFromBraces<rs_std::UnsafeCall>);
+static_assert(!std::is_convertible_v<int, rs_std::UnsafeCall>);
+
+TEST(UnsafeCallTest, Call) { EXPECT_EQ(3, Add(rs_std::UnsafeCall(), 1, 2)); }
+
+}  // namespace